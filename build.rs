@@ -0,0 +1,14 @@
+//! Embeds the git commit the crate is built from, see the 'manifest' module.
+
+use std::process::Command;
+
+fn main() {
+    let hash = Command::new("git").args(["rev-parse", "HEAD"]).output().ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=THOR_GIT_HASH={}", hash);
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
@@ -0,0 +1,58 @@
+/*
+ * C API of thor_reforged, see src/ffi.rs for the details of every function.
+ *
+ * Build the library with:
+ *   cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Functions returning int return THOR_OK on success, otherwise the exit code of the kind of the
+ * error (1 other, 2 input, 3 validation, 4 analysis, 5 cancelled), and thor_last_error() returns
+ * its message on the calling thread.
+ */
+
+#ifndef THOR_H
+#define THOR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define THOR_API_VERSION 1
+#define THOR_OK 0
+
+typedef struct ThorGraph ThorGraph;
+typedef struct ThorResults ThorResults;
+
+typedef struct ThorNodeResult {
+    uint32_t id;
+    uint64_t on_count;
+    uint64_t off_count;
+    double mean_end_on;
+    double mean_end_off;
+    double criticality;
+} ThorNodeResult;
+
+uint32_t thor_api_version(void);
+const char *thor_last_error(void);
+
+ThorGraph *thor_graph_new(void);
+void thor_graph_free(ThorGraph *graph);
+int thor_graph_add_node(ThorGraph *graph, uint32_t id, const char *name);
+int thor_graph_add_edge(ThorGraph *graph, uint32_t from, uint32_t to);
+int thor_graph_set_off_chance(ThorGraph *graph, uint32_t id, float off_chance);
+int thor_graph_set_terminals(ThorGraph *graph, uint32_t start_id, uint32_t end_id);
+
+int thor_criticality_run(const ThorGraph *graph, uint64_t samples, uint32_t threads, ThorResults **results);
+uint64_t thor_results_state_count(const ThorResults *results);
+double thor_results_end_op_mean(const ThorResults *results);
+size_t thor_results_node_count(const ThorResults *results);
+int thor_results_node(const ThorResults *results, size_t index, ThorNodeResult *node);
+void thor_results_free(ThorResults *results);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
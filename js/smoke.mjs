@@ -0,0 +1,31 @@
+// Smoke test of the WebAssembly build through thor.js, run by CI after building the module:
+//
+//   node js/smoke.mjs target/wasm32-unknown-unknown/release/thor_reforged.wasm
+
+import { readFile } from "node:fs/promises";
+import { loadThor } from "./thor.js";
+
+const bytes = await readFile(process.argv[2]);
+const thor = await loadThor(new Response(bytes, { headers: { "Content-Type": "application/wasm" } }));
+
+// start -> a -> end and start -> b -> end
+const nodes = ["start", "a", "b", "end"].map((name, id) => ({ name, id, attributes: {} }));
+const edges = [[0, 1], [0, 2], [1, 3], [2, 3]].map(([from, to]) => ({ from, to }));
+const document = { graph: { nodes, edges, static_nodes: [] }, data: { edge_attributes: {}, off_chances: {} } };
+
+const reply = thor.analyze({ document, samples: 100 });
+if (reply.error || !reply.results) {
+    throw new Error(`The analysis failed: ${JSON.stringify(reply)}`);
+}
+// Four states are fewer than the samples, so they are all evaluated exactly
+for (const id of ["1", "2"]) {
+    if (reply.results.nodes[id].criticality !== 0.5) {
+        throw new Error(`Node ${id} should have a criticality of 0.5: ${JSON.stringify(reply.results)}`);
+    }
+}
+
+const refused = thor.analyze({ samples: 100 });
+if (!refused.error) {
+    throw new Error(`A request without a document should fail: ${JSON.stringify(refused)}`);
+}
+console.log("ok");
@@ -0,0 +1,38 @@
+// Loads the WebAssembly build of thor_reforged, see src/wasm.rs.
+//
+//   const thor = await loadThor(fetch("thor_reforged.wasm"));
+//   const reply = thor.analyze({ document, samples: 10000 });
+//   if (reply.error) { ... } else { reply.results.nodes ... }
+
+export async function loadThor(source) {
+    let memory = null;
+    const imports = {
+        env: {
+            // Seeds of the sampling, the module has no entropy of its own
+            thor_random_fill(ptr, len) {
+                const bytes = new Uint8Array(memory.buffer, ptr, len);
+                for (let start = 0; start < len; start += 65536) {
+                    crypto.getRandomValues(bytes.subarray(start, Math.min(start + 65536, len)));
+                }
+            },
+        },
+    };
+    const { instance } = await WebAssembly.instantiateStreaming(source, imports);
+    const exports = instance.exports;
+    memory = exports.memory;
+
+    return {
+        // Runs the criticality analysis of the request and returns the parsed reply
+        analyze(request) {
+            const input = new TextEncoder().encode(JSON.stringify(request));
+            const ptr = exports.thor_alloc(input.length);
+            new Uint8Array(memory.buffer, ptr, input.length).set(input);
+            const reply = exports.thor_analyze(ptr, input.length);
+            exports.thor_free(ptr, input.length);
+            const len = new DataView(memory.buffer, reply, 4).getUint32(0, true);
+            const text = new TextDecoder().decode(new Uint8Array(memory.buffer, reply + 4, len));
+            exports.thor_free(reply, len + 4);
+            return JSON.parse(text);
+        },
+    };
+}
@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+use std::error::Error;
+use crate::analyses::criticality::Criticality;
+use crate::analyses::criticality::loop_condition::{CritLoopCondition, MaxLoopCondition};
+use crate::analyses::criticality::vis_gen::visibility_states_gen::{RandomGen, seeded_rng, VisGen};
+use crate::errors::network::UnknownNodesError;
+use crate::network::{Graph, NodeValueMap};
+use crate::output::{Output, StdOutput};
+use crate::roll_up::{OrRule, RollUp};
+
+/// Number of states sampled when no loop condition is given
+pub const DEFAULT_SAMPLES: u64 = 10_000;
+
+/// Configures a ['Criticality'] analysis from a graph. Every setting has a default:
+///
+/// * the start and end node are the only nodes without children and without parents
+/// * the dynamic nodes are all nodes that are not static, start or end nodes
+/// * the states are sampled by a ['RandomGen'] from the 'off_chances', seeded from the system,
+///   ['DEFAULT_SAMPLES'] times, or all evaluated exactly if there are fewer
+/// * nodes are rolled up with the ['OrRule'] on one thread per cpu
+/// * the results are written to the ['StdOutput']
+pub struct CriticalityBuilder {
+    graph: Graph,
+    threads: Option<u8>,
+    dynamic_ids: Option<HashSet<u32>>,
+    off_chances: NodeValueMap<f32>,
+    seed: Option<u64>,
+    vis_gen: Option<Box<dyn VisGen>>,
+    loop_condition: Option<Box<dyn CritLoopCondition>>,
+    roll_up_rule: Option<Box<dyn RollUp>>,
+    start_id: Option<u32>,
+    end_id: Option<u32>,
+    end_weights: Vec<(u32, f64)>,
+    outputs: Vec<Box<dyn Output>>,
+    sample_log: Option<String>,
+    surrogate: Option<(usize, f64)>,
+    exhaustive_fallback: bool,
+    enumerate: bool,
+}
+
+impl CriticalityBuilder {
+    pub fn new(graph: Graph) -> CriticalityBuilder {
+        CriticalityBuilder {
+            graph,
+            threads: None,
+            dynamic_ids: None,
+            off_chances: NodeValueMap::new(),
+            seed: None,
+            vis_gen: None,
+            loop_condition: None,
+            roll_up_rule: None,
+            start_id: None,
+            end_id: None,
+            end_weights: vec![],
+            outputs: vec![],
+            sample_log: None,
+            surrogate: None,
+            exhaustive_fallback: true,
+            enumerate: false,
+        }
+    }
+
+    pub fn threads(mut self, threads: u8) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    pub fn dynamic_ids(mut self, dynamic_ids: HashSet<u32>) -> Self {
+        self.dynamic_ids = Some(dynamic_ids);
+        self
+    }
+
+    /// Off chances of the default ['RandomGen'], ignored if a generator is given
+    pub fn off_chances(mut self, off_chances: NodeValueMap<f32>) -> Self {
+        self.off_chances = off_chances;
+        self
+    }
+
+    /// Seed of the default ['RandomGen'], ignored if a generator is given. The same seed draws the
+    /// same states on the same number of threads.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn vis_gen(mut self, vis_gen: Box<dyn VisGen>) -> Self {
+        self.vis_gen = Some(vis_gen);
+        self
+    }
+
+    pub fn loop_condition(mut self, loop_condition: Box<dyn CritLoopCondition>) -> Self {
+        self.loop_condition = Some(loop_condition);
+        self
+    }
+
+    /// Samples 'samples' states, shorthand for a ['MaxLoopCondition']
+    pub fn samples(self, samples: u64) -> Self {
+        self.loop_condition(Box::new(MaxLoopCondition { max: samples, index: 0 }))
+    }
+
+    pub fn roll_up_rule(mut self, roll_up_rule: Box<dyn RollUp>) -> Self {
+        self.roll_up_rule = Some(roll_up_rule);
+        self
+    }
+
+    pub fn start_id(mut self, start_id: u32) -> Self {
+        self.start_id = Some(start_id);
+        self
+    }
+
+    pub fn end_id(mut self, end_id: u32) -> Self {
+        self.end_id = Some(end_id);
+        self
+    }
+
+    /// Weighted end nodes, see ['Criticality::end_weights']. The first one is the end node if
+    /// none is given.
+    pub fn end_weights(mut self, end_weights: Vec<(u32, f64)>) -> Self {
+        self.end_weights = end_weights;
+        self
+    }
+
+    /// Adds a destination for the results, replacing the default ['StdOutput']
+    pub fn output(mut self, output: Box<dyn Output>) -> Self {
+        self.outputs.push(output);
+        self
+    }
+
+    /// Logs every evaluated state to 'path', see ['crate::analyses::criticality::samples']
+    pub fn sample_log(mut self, path: &str) -> Self {
+        self.sample_log = Some(path.to_string());
+        self
+    }
+
+    /// Screens the states with a surrogate fitted to the first 'training' states, rolling up only
+    /// those it predicts more than 'margin' away from 0 and 1, see
+    /// ['crate::analyses::criticality::surrogate']
+    pub fn surrogate(mut self, training: usize, margin: f64) -> Self {
+        self.surrogate = Some((training, margin));
+        self
+    }
+
+    /// Whether to evaluate every state exactly instead of sampling when there are fewer states than
+    /// samples, on by default, see ['crate::analyses::criticality::exhaustive']
+    pub fn exhaustive_fallback(mut self, fallback: bool) -> Self {
+        self.exhaustive_fallback = fallback;
+        self
+    }
+
+    /// Whether to evaluate every state exactly whatever the samples, off by default. The states
+    /// are weighted by the probabilities of the generator, see
+    /// ['crate::analyses::criticality::exhaustive']
+    pub fn enumerate(mut self, enumerate: bool) -> Self {
+        self.enumerate = enumerate;
+        self
+    }
+
+    /// Fills in the defaults and checks that the configuration is consistent
+    ///
+    /// # Errors
+    ///
+    /// * Returns a ['StartNodeError'] or ['EndNodeError'] if a node isn't given and can't be
+    ///   determined
+    /// * Returns an ['UnknownNodesError'] if a given node is not part of the graph
+    /// * Returns a ['NoEndConnectionError'] if an end node can't be reached from the start node
+    pub fn build(self) -> Result<Criticality, Box<dyn Error>> {
+        let mut graph = self.graph;
+        let l_map = graph.links_map();
+        let start_id = match self.start_id {
+            Some(id) => id,
+            None => Graph::get_start_id(&l_map)?,
+        };
+        let end_id = match (self.end_id, self.end_weights.first()) {
+            (Some(id), _) => id,
+            (None, Some((id, _))) => *id,
+            (None, None) => Graph::get_end_id(&l_map)?,
+        };
+        let ends: Vec<u32> = std::iter::once(end_id).chain(self.end_weights.iter().map(|(id, _)| *id)).collect();
+
+        let node_ids = graph.get_node_ids();
+        let mut unknown: Vec<u32> = self.dynamic_ids.iter().flatten()
+            .chain(std::iter::once(&start_id))
+            .chain(ends.iter())
+            .filter(|id| !node_ids.contains(id))
+            .copied()
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort();
+            unknown.dedup();
+            return Err(Box::new(UnknownNodesError { ids: unknown }));
+        }
+        for end in ends.iter() {
+            Graph::validate_end_connection(&l_map, start_id, *end)?;
+        }
+
+        graph.static_nodes.insert(start_id);
+        graph.static_nodes.extend(ends.iter());
+        let dynamic_ids = match self.dynamic_ids {
+            Some(ids) => ids,
+            None => node_ids.into_iter().filter(|id| !graph.static_nodes.contains(id)).collect(),
+        };
+        let vis_gen = match self.vis_gen {
+            Some(vis_gen) => vis_gen,
+            None => Box::new(RandomGen {
+                rng: seeded_rng(self.seed),
+                ids: dynamic_ids.clone(),
+                off_chances: self.off_chances,
+            }),
+        };
+        let outputs = match self.outputs.is_empty() {
+            true => vec![Box::new(StdOutput::default()) as Box<dyn Output>],
+            false => self.outputs,
+        };
+        Ok(Criticality {
+            threads: self.threads.unwrap_or(num_cpus::get() as u8).max(1),
+            graph,
+            dynamic_ids,
+            vis_gen,
+            loop_condition: self.loop_condition.unwrap_or(Box::new(MaxLoopCondition { max: DEFAULT_SAMPLES, index: 0 })),
+            roll_up_rule: self.roll_up_rule.unwrap_or(Box::new(OrRule {})),
+            l_map,
+            start_id,
+            end_id,
+            end_weights: self.end_weights,
+            outputs,
+            sample_log: self.sample_log,
+            surrogate: self.surrogate,
+            exhaustive_fallback: self.exhaustive_fallback,
+            enumerate: self.enumerate,
+        })
+    }
+}
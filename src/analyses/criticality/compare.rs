@@ -0,0 +1,98 @@
+//! Comparing the criticality of the nodes of two analyses, such as before and after a change of
+//! the architecture.
+//!
+//! The end operability of a state lies between 0 and 1, so the variance of the mean end
+//! operability 'm' over 'n' states is at most m(1 - m) / n. The mean is smoothed to
+//! (mn + 1) / (n + 2) first, so means of exactly 0 or 1 over few states still have a variance.
+//! The standard error of a criticality is taken from the variances of both of its means, a change
+//! is significant if its confidence interval doesn't contain zero.
+
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult};
+use crate::network::NodeValueMap;
+use crate::util::normal_quantile;
+
+/// Confidence level used when none is given
+pub const DEFAULT_CONFIDENCE: f64 = 0.95;
+
+/// Change of the criticality of a node allowed by a baseline check when none is given
+pub const DEFAULT_TOLERANCE: f64 = 0.01;
+
+impl NodeCritResult {
+    /// Upper bound of the standard error of the criticality, see the module documentation
+    pub fn std_error(&self) -> f64 {
+        let variance = |mean: f64, count: u64| {
+            let n = count as f64;
+            let smoothed = (mean.clamp(0.0, 1.0) * n + 1.0) / (n + 2.0);
+            smoothed * (1.0 - smoothed) / (n + 2.0)
+        };
+        (variance(self.mean_end_on, self.on_count) + variance(self.mean_end_off, self.off_count)).sqrt()
+    }
+}
+
+/// Change of the criticality of a node between two results
+#[derive(Debug, Clone)]
+pub struct NodeDelta {
+    /// Criticality in the first results, None if the node wasn't analysed
+    pub before: Option<f64>,
+    /// Criticality in the second results, None if the node wasn't analysed
+    pub after: Option<f64>,
+    /// Difference of the criticality, after minus before
+    pub delta: f64,
+    /// Half width of the confidence interval of the delta
+    pub margin: f64,
+    /// Whether the confidence interval of the delta excludes zero. Nodes in only one of the
+    /// results are never significant.
+    pub significant: bool,
+}
+
+/// Changes of the criticality of every node in either results
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub confidence: f64,
+    /// Change of the mean end operability, after minus before
+    pub end_op_delta: f64,
+    pub nodes: NodeValueMap<NodeDelta>,
+}
+
+impl Comparison {
+    /// Compares the criticality of the nodes in 'before' and 'after' at the 'confidence' level,
+    /// a share between 0 and 1
+    pub fn new(before: &CriticalityResults, after: &CriticalityResults, confidence: f64) -> Comparison {
+        let z = normal_quantile(0.5 + confidence.clamp(0.0, 1.0) / 2.0);
+        let mut nodes = NodeValueMap::new();
+        for id in before.nodes.keys().chain(after.nodes.keys()) {
+            let (a, b) = (before.nodes.get(id), after.nodes.get(id));
+            let delta = match (a, b) {
+                (Some(a), Some(b)) => {
+                    let margin = z * (a.std_error().powi(2) + b.std_error().powi(2)).sqrt();
+                    let delta = b.criticality - a.criticality;
+                    NodeDelta { before: Some(a.criticality), after: Some(b.criticality), delta, margin, significant: delta.abs() > margin }
+                }
+                _ => NodeDelta {
+                    before: a.map(|n| n.criticality),
+                    after: b.map(|n| n.criticality),
+                    delta: b.map(|n| n.criticality).unwrap_or(0.0) - a.map(|n| n.criticality).unwrap_or(0.0),
+                    margin: f64::INFINITY,
+                    significant: false,
+                },
+            };
+            nodes.insert(*id, delta);
+        }
+        Comparison { confidence, end_op_delta: after.end_op_mean - before.end_op_mean, nodes }
+    }
+
+    /// Nodes ordered by the size of their change, largest first
+    pub fn ranked(&self) -> Vec<(u32, &NodeDelta)> {
+        let mut ranked: Vec<(u32, &NodeDelta)> = self.nodes.iter().map(|(id, d)| (*id, d)).collect();
+        ranked.sort_by(|a, b| b.1.delta.abs().total_cmp(&a.1.delta.abs()).then(a.0.cmp(&b.0)));
+        ranked
+    }
+
+    /// Nodes whose criticality changed by more than 'tolerance', or that are in only one of the
+    /// results, largest change first
+    pub fn deviations(&self, tolerance: f64) -> Vec<(u32, &NodeDelta)> {
+        self.ranked().into_iter()
+            .filter(|(_, d)| d.before.is_none() || d.after.is_none() || d.delta.abs() > tolerance)
+            .collect()
+    }
+}
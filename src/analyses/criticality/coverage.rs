@@ -0,0 +1,147 @@
+//! Coverage of the state space by the sampled states, so a claim that a small graph was analysed
+//! exhaustively can be checked.
+//!
+//! The n dynamic nodes of a graph have 2^n visibility states. With a ['CoverageLog'] in the
+//! context every sampling thread counts the unique states it evaluated and their probability mass,
+//! the chance of drawing one of them from the off chances of the nodes. The overall coverage
+//! counts a state evaluated by several threads once. The mass is only known if the generator
+//! draws every node independently by its off chance, see ['VisGen::state_probability']; for
+//! correlated draws, such as common causes and dependencies, and for enumerated, recorded or
+//! replayed states only the share of the state space is reported.
+//!
+//! States are told apart by a 64 bit hash, two states sharing a hash count once. That is unlikely
+//! below billions of states.
+
+use std::collections::HashMap;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::json::JsonValue;
+use crate::network::NodeValueMap;
+
+/// Unique states evaluated out of the state space of the dynamic nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    /// Dynamic nodes, the state space holds 2 to the power of their count states
+    pub nodes: usize,
+    /// Unique states evaluated
+    pub states: u64,
+    /// Probability mass of the unique states, None if the probabilities of the states are unknown
+    pub mass: Option<f64>,
+}
+
+impl Coverage {
+    /// Share of the state space that was evaluated, rounded to zero for large spaces
+    pub fn fraction(&self) -> f64 {
+        self.states as f64 / 2f64.powf(self.nodes as f64)
+    }
+
+    /// Whether every state was evaluated
+    pub fn is_exhaustive(&self) -> bool {
+        self.nodes < 64 && self.states >= 1u64 << self.nodes
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object()
+            .with("nodes", self.nodes as u64)
+            .with("states", self.states)
+            .with("fraction", self.fraction())
+            .with("mass", self.mass)
+            .with("exhaustive", self.is_exhaustive())
+    }
+}
+
+impl Display for Coverage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of 2^{} states ({:.4}%)", self.states, self.nodes, self.fraction() * 100.0)?;
+        if let Some(mass) = self.mass {
+            write!(f, ", probability mass {:.6}", mass)?;
+        }
+        if self.is_exhaustive() {
+            write!(f, ", exhaustive")?;
+        }
+        Ok(())
+    }
+}
+
+/// Coverage of a sampling over all threads and of every thread
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageReport {
+    pub overall: Coverage,
+    /// In the order of the threads
+    pub workers: Vec<Coverage>,
+}
+
+impl CoverageReport {
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object()
+            .with("overall", self.overall.to_json())
+            .with("workers", self.workers.iter().map(|w| w.to_json()).collect::<Vec<JsonValue>>())
+    }
+}
+
+/// The coverage of every sampling of an analysis, in the order they finished. Analyses such as
+/// the pairwise criticality sample more than once.
+#[derive(Debug, Default)]
+pub struct CoverageLog {
+    reports: Mutex<Vec<CoverageReport>>,
+}
+
+impl CoverageLog {
+    pub fn push(&self, report: CoverageReport) {
+        self.reports.lock().unwrap().push(report);
+    }
+
+    pub fn reports(&self) -> Vec<CoverageReport> {
+        self.reports.lock().unwrap().clone()
+    }
+}
+
+/// Unique states evaluated by a sampling thread, with their probabilities
+#[derive(Debug, Clone)]
+pub(crate) struct StateCoverage {
+    /// Probability of every state by its hash
+    probabilities: HashMap<u64, f64>,
+    /// False once a state of unknown probability was added
+    mass_known: bool,
+}
+
+impl StateCoverage {
+    pub fn new() -> StateCoverage {
+        StateCoverage { probabilities: HashMap::new(), mass_known: true }
+    }
+
+    /// Adds a state drawn by the 'generator'
+    pub fn add(&mut self, state: &NodeValueMap<u8>, generator: &dyn VisGen) {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        if let Entry::Vacant(entry) = self.probabilities.entry(hasher.finish()) {
+            let probability = generator.state_probability(state);
+            self.mass_known &= probability.is_some();
+            entry.insert(probability.unwrap_or(0.0));
+        }
+    }
+
+    /// Adds the states of 'other', counting the states of both once
+    pub fn merge(&mut self, other: &StateCoverage) {
+        self.mass_known &= other.mass_known;
+        for (hash, probability) in other.probabilities.iter() {
+            self.probabilities.entry(*hash).or_insert(*probability);
+        }
+    }
+
+    /// The coverage of the state space of 'nodes' dynamic nodes
+    pub fn coverage(&self, nodes: usize) -> Coverage {
+        Coverage {
+            nodes,
+            states: self.probabilities.len() as u64,
+            mass: match self.mass_known {
+                true => Some(self.probabilities.values().sum::<f64>().min(1.0)),
+                false => None,
+            },
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Comparing two versions of a model on common random numbers.
+//!
+//! Comparing two independent runs, as the 'compare' command does, adds the sampling noise of both
+//! runs to every delta. Here every sampled visibility state is evaluated on both versions, so the
+//! noise shared by the versions cancels out and only the effect of the change is left.
+//!
+//! Every state is evaluated on the first version and as the difference of the end value of the
+//! second version minus the first. The criticality delta of a node is the difference of the mean
+//! of those differences while the node is on and while it is off, with a standard error estimated
+//! from their spread, see ['GraphCritData::std_errors']. The results of the second version are
+//! the ones of the first plus the differences.
+//!
+//! The states cover the dynamic nodes of both versions, a node of only one version is ignored by
+//! the other. Nodes are matched by their ids, so the ids have to be kept stable between the
+//! versions.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use log::{info, warn};
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::{AnalysisGraph, CriticalityResults, GraphCritData, NodeCritResult, RollUpEvaluator, sample_states_many, StateEvaluator};
+use crate::analyses::criticality::compare::{Comparison, NodeDelta};
+use crate::analyses::criticality::histogram::Histogram;
+use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::errors::analysis::ThorError;
+use crate::network::{CsrLinks, Graph, NodeValueMap};
+use crate::roll_up::RollUp;
+use crate::util::normal_quantile;
+
+/// A version of the model, rolled up from 'start_id' to the end node
+pub struct ModelVersion {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// (end node, weight), if empty only the 'end_id' is used, see
+    /// ['crate::analyses::criticality::Criticality::end_weights']
+    pub end_weights: Vec<(u32, f64)>,
+}
+
+impl ModelVersion {
+    fn evaluator(&self) -> RollUpEvaluator {
+        let l_map = self.graph.links_map();
+        let path = Graph::get_bfs_path(&l_map, self.start_id);
+        RollUpEvaluator::new(
+            Arc::new(AnalysisGraph { graph: self.graph.clone(), links: CsrLinks::new(&l_map, &path) }),
+            dyn_clone::clone_box(&*self.roll_up_rule),
+            match self.end_weights.is_empty() {
+                true => vec![(self.end_id, 1.0)],
+                false => self.end_weights.clone(),
+            },
+        )
+    }
+}
+
+/// Evaluates a state as the end value of the second version minus the one of the first
+#[derive(Clone)]
+struct DifferenceEvaluator {
+    before: RollUpEvaluator,
+    after: RollUpEvaluator,
+}
+
+impl StateEvaluator for DifferenceEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        self.after.evaluate(visibility_state) - self.before.evaluate(visibility_state)
+    }
+}
+
+/// Criticality deltas between two versions of a model, see the module documentation
+pub struct DeltaCriticality {
+    pub threads: u8,
+    pub before: ModelVersion,
+    pub after: ModelVersion,
+    /// Draws the states over the dynamic nodes of both versions
+    pub vis_gen: Box<dyn VisGen>,
+    pub loop_condition: Box<dyn CritLoopCondition>,
+    /// Confidence level of the margins, a share between 0 and 1
+    pub confidence: f64,
+}
+
+/// Results of both versions and their deltas
+#[derive(Debug, Clone)]
+pub struct DeltaResults {
+    pub before: CriticalityResults,
+    /// Derived from the differences, so without a histogram
+    pub after: CriticalityResults,
+    /// Deltas of every node with margins of the paired differences
+    pub comparison: Comparison,
+    /// Half width of the confidence interval of the delta of the mean end operability
+    pub end_op_margin: f64,
+}
+
+impl DeltaResults {
+    /// Prints the change of the mean end operability and of the 'top' nodes changing the most,
+    /// named after the 'graph' of either version
+    pub fn print(&self, before: &Graph, after: &Graph, top: usize) {
+        let comparison = &self.comparison;
+        println!("Unique states: {}", self.before.row_count);
+        println!("Mean end operability: {} -> {} ({:+} ± {:.6})", self.before.end_op_mean, self.after.end_op_mean,
+                 comparison.end_op_delta, self.end_op_margin);
+        let significant = comparison.nodes.values().filter(|d| d.significant).count();
+        println!("{} of {} nodes changed significantly at {}% confidence", significant, comparison.nodes.len(), comparison.confidence * 100.0);
+        let format = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or("-".to_string());
+        for (id, delta) in comparison.ranked().into_iter().take(top) {
+            let name = after.get_node(&id).or(before.get_node(&id)).map(|n| n.name.as_str()).unwrap_or("");
+            let flag = match (delta.before, delta.after) {
+                (None, _) => "added",
+                (_, None) => "removed",
+                _ if delta.significant => "significant",
+                _ => "not significant",
+            };
+            println!("{} ({}): {} -> {} ({:+} ± {:.6}, {})", name, id, format(delta.before), format(delta.after), delta.delta, delta.margin, flag);
+        }
+    }
+}
+
+impl Analysis for DeltaCriticality {
+    type Output = DeltaResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<DeltaResults, ThorError> {
+        info!("Starting Delta Criticality Analysis");
+        let renamed = self.before.dynamic_ids.intersection(&self.after.dynamic_ids)
+            .filter(|id| self.before.graph.get_node(id).map(|n| &n.name) != self.after.graph.get_node(id).map(|n| &n.name))
+            .count();
+        if renamed > 0 {
+            warn!("{} nodes have different names in the two versions, nodes are matched by their ids", renamed);
+        }
+        let dynamic_ids: HashSet<u32> = self.before.dynamic_ids.union(&self.after.dynamic_ids).copied().collect();
+        let before = self.before.evaluator();
+        let evaluators: Vec<Box<dyn StateEvaluator>> = vec![
+            Box::new(before.clone()),
+            Box::new(DifferenceEvaluator { before, after: self.after.evaluator() }),
+        ];
+        let data = sample_states_many(self.threads, &dynamic_ids, self.vis_gen.as_ref(),
+                                      self.loop_condition.as_ref(), &evaluators, ctx)?;
+        Ok(self.compare(&data[0], &data[1]))
+    }
+}
+
+impl DeltaCriticality {
+    /// The results of both versions and their deltas from the sums of the first version and of
+    /// the differences
+    fn compare(&self, before: &GraphCritData, differences: &GraphCritData) -> DeltaResults {
+        let z = normal_quantile(0.5 + self.confidence.clamp(0.0, 1.0) / 2.0);
+        let (mut before, (end_op_error, errors)) = (before.results(), differences.std_errors());
+        let differences = differences.results();
+        let mut after = CriticalityResults {
+            row_count: before.row_count,
+            end_op_mean: before.end_op_mean + differences.end_op_mean,
+            end_op_histogram: Histogram::default(),
+            nodes: NodeValueMap::new(),
+        };
+        let mut nodes = NodeValueMap::new();
+        for (id, node) in before.nodes.iter() {
+            let difference = &differences.nodes[id];
+            let (in_before, in_after) = (self.before.dynamic_ids.contains(id), self.after.dynamic_ids.contains(id));
+            if in_after {
+                after.nodes.insert(*id, NodeCritResult {
+                    on_count: node.on_count,
+                    off_count: node.off_count,
+                    mean_end_on: node.mean_end_on + difference.mean_end_on,
+                    mean_end_off: node.mean_end_off + difference.mean_end_off,
+                    criticality: node.criticality + difference.criticality,
+                });
+            }
+            let margin = z * errors[id];
+            nodes.insert(*id, NodeDelta {
+                before: Some(node.criticality).filter(|_| in_before),
+                after: Some(node.criticality + difference.criticality).filter(|_| in_after),
+                delta: difference.criticality,
+                margin,
+                // Nodes of only one version are never significant, as in ['Comparison']
+                significant: in_before && in_after && difference.criticality.abs() > margin,
+            });
+        }
+        before.nodes.retain(|id, _| self.before.dynamic_ids.contains(id));
+        DeltaResults {
+            before,
+            after,
+            comparison: Comparison { confidence: self.confidence, end_op_delta: differences.end_op_mean, nodes },
+            end_op_margin: z * end_op_error,
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Exact results for small state spaces.
+//!
+//! When the 2^n states of the n dynamic nodes are fewer than the states a criticality analysis
+//! would sample, sampling mostly draws states that were already evaluated. The analysis then
+//! evaluates every state once instead, in Gray code order, and weights it by its probability. The
+//! results are exact: the mean end value is the expected end value and the means while a node is
+//! visible or not are the expected end values given its visibility.
+//!
+//! The weights are the ['VisGen::state_probability'] of the generator, so only generators drawing
+//! every node independently by its off chance fall back, correlated, enumerating and recording
+//! generators keep sampling. The states can also be enumerated whatever the samples, see
+//! ['crate::analyses::criticality::builder::CriticalityBuilder::enumerate'], which is what
+//! '--gray-code' does. The state and visibility counts are those of the enumerated states.
+//! The histogram counts every state by its probability in ['HISTOGRAM_SCALE'] parts, so its
+//! percentiles are those of the exact distribution of the end value. The states are evaluated on the calling thread, which reports a single progress event and is counted as
+//! one worker by the metrics. Partial results and their convergence curve aren't written, a
+//! warning says so if they were asked for.
+
+use std::collections::HashSet;
+use std::time::Instant;
+use log::{info, warn, Level};
+use crate::analyses::{AnalysisContext, VISIBLE_VAL};
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult, StateEvaluator};
+use crate::analyses::criticality::coverage::{Coverage, CoverageReport};
+use crate::analyses::criticality::histogram::Histogram;
+use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::{GrayCodeGen, MAX_ENUMERATED_NODES, VisGen};
+use crate::errors::analysis::{ThorError, TooManyNodesError};
+use crate::json::JsonValue;
+use crate::logging::event;
+use crate::network::NodeValueMap;
+
+/// Count of the histogram for a state of probability 1, see the module documentation
+pub const HISTOGRAM_SCALE: f64 = 1e9;
+
+/// Number of states of the 'dynamic_ids' if they are fewer than the 'loop_condition' samples and
+/// the 'vis_gen' knows their probabilities, logging the fallback, None if the states should be
+/// sampled
+pub(crate) fn exhaustive_states(dynamic_ids: &HashSet<u32>, vis_gen: &dyn VisGen, loop_condition: &dyn CritLoopCondition) -> Option<u64> {
+    if dynamic_ids.len() > MAX_ENUMERATED_NODES {
+        return None;
+    }
+    let states = 1u64 << dynamic_ids.len();
+    let samples = loop_condition.remaining()?;
+    let visible: NodeValueMap<u8> = dynamic_ids.iter().map(|id| (*id, VISIBLE_VAL)).collect();
+    match states < samples && vis_gen.state_probability(&visible).is_some() {
+        true => {
+            info!("The {} states of the dynamic nodes are fewer than the {} samples, evaluating each of them exactly", states, samples);
+            event(Level::Info, "exhaustive_fallback", JsonValue::object()
+                .with("nodes", dynamic_ids.len() as u64)
+                .with("states", states)
+                .with("samples", samples));
+            Some(states)
+        }
+        false => None,
+    }
+}
+
+/// Number of states of the 'dynamic_ids' when they are enumerated whatever the samples
+///
+/// # Errors
+///
+/// Returns a ['TooManyNodesError'] if there are more than ['MAX_ENUMERATED_NODES'] ids, or
+/// ['ThorError::Failed'] if the 'vis_gen' doesn't know the probabilities to weight the states by
+pub(crate) fn enumerated_states(dynamic_ids: &HashSet<u32>, vis_gen: &dyn VisGen) -> Result<u64, ThorError> {
+    if dynamic_ids.len() > MAX_ENUMERATED_NODES {
+        return Err(TooManyNodesError { analysis: "state enumeration".to_string(), nodes: dynamic_ids.len(), max: MAX_ENUMERATED_NODES }.into());
+    }
+    let visible: NodeValueMap<u8> = dynamic_ids.iter().map(|id| (*id, VISIBLE_VAL)).collect();
+    match vis_gen.state_probability(&visible) {
+        Some(_) => Ok(1u64 << dynamic_ids.len()),
+        None => Err(ThorError::Failed("The states can only be enumerated when their probabilities are known, as for nodes drawn independently by their off chance".into())),
+    }
+}
+
+/// Evaluates every state of the 'dynamic_ids' once with the 'evaluator', weighting it by its
+/// probability under the 'vis_gen', see the module documentation
+///
+/// # Errors
+///
+/// Returns ['ThorError::Cancelled'] if the context is cancelled while enumerating, or a
+/// ['LimitExceededError'] once its wall time is over
+pub(crate) fn enumerate_states(dynamic_ids: &HashSet<u32>,
+                               vis_gen: &dyn VisGen,
+                               evaluator: &mut dyn StateEvaluator,
+                               ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+    let mut states = GrayCodeGen::new(dynamic_ids)?;
+    let count = states.state_count();
+    if ctx.partial.is_some() {
+        warn!("The {} states are evaluated exactly, no partial results or convergence curve are written", count);
+    }
+    // Instant isn't available in the browser
+    let start = (!cfg!(target_arch = "wasm32")).then(Instant::now);
+    if let Some(metrics) = ctx.metrics.as_ref() {
+        metrics.worker_started();
+    }
+    let mut ids: Vec<u32> = dynamic_ids.iter().copied().collect();
+    ids.sort();
+
+    // (weight, weighted end value sum) of every state, the weights only sum to one up to rounding
+    let mut total = (0.0, 0.0);
+    let mut histogram = Histogram::default();
+    // (on count, on weight, weighted end value sum while on) and the same while off, per node
+    let mut on = vec![(0u64, 0.0, 0.0); ids.len()];
+    let mut off = vec![(0u64, 0.0, 0.0); ids.len()];
+    for _ in 0..count {
+        ctx.check_cancelled()?;
+        let state = states.next_states();
+        let probability = vis_gen.state_probability(&state).unwrap_or(0.0);
+        let end_val = evaluator.evaluate(&state);
+        total.0 += probability;
+        total.1 += probability * end_val;
+        histogram.add_count(end_val, (probability * HISTOGRAM_SCALE).round() as u64);
+        for (i, id) in ids.iter().enumerate() {
+            let sums = match state.get(id) {
+                Some(&VISIBLE_VAL) => &mut on[i],
+                _ => &mut off[i],
+            };
+            sums.0 += 1;
+            sums.1 += probability;
+            sums.2 += probability * end_val;
+        }
+    }
+
+    if let Some(metrics) = ctx.metrics.as_ref() {
+        metrics.states_sampled(count);
+        metrics.worker_finished();
+    }
+    let seconds = start.map(|start| start.elapsed().as_secs_f64()).unwrap_or(0.0);
+    event(Level::Info, "progress", JsonValue::object()
+        .with("states", count)
+        .with("total", count)
+        .with("states_per_second", if seconds > 0.0 { count as f64 / seconds } else { 0.0 })
+        .with("remaining_seconds", 0.0));
+
+    let weighted_mean = |weight: f64, sum: f64| if weight > 0.0 { sum / weight } else { 0.0 };
+    let nodes = ids.iter().enumerate()
+        .map(|(i, id)| {
+            let (mean_end_on, mean_end_off) = (weighted_mean(on[i].1, on[i].2), weighted_mean(off[i].1, off[i].2));
+            (*id, NodeCritResult {
+                on_count: on[i].0,
+                off_count: off[i].0,
+                mean_end_on,
+                mean_end_off,
+                criticality: mean_end_on - mean_end_off,
+            })
+        })
+        .collect();
+    if let Some(log) = ctx.coverage.as_ref() {
+        let coverage = Coverage { nodes: ids.len(), states: count, mass: Some(1.0) };
+        log.push(CoverageReport { overall: coverage.clone(), workers: vec![coverage] });
+    }
+    Ok(CriticalityResults {
+        row_count: count,
+        end_op_mean: weighted_mean(total.0, total.1),
+        end_op_histogram: histogram,
+        nodes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyses::criticality::vis_gen::visibility_states_gen::{RandomGen, seeded_rng};
+    use super::*;
+
+    /// The end node is operable while node 1 or node 2 is
+    #[derive(Clone)]
+    struct EitherOn;
+
+    impl StateEvaluator for EitherOn {
+        fn evaluate(&mut self, state: &NodeValueMap<u8>) -> f64 {
+            [1, 2].iter().any(|id| state.get(id) == Some(&VISIBLE_VAL)) as u8 as f64
+        }
+    }
+
+    #[test]
+    fn the_histogram_weights_the_states_like_the_mean() {
+        let dynamic_ids = HashSet::from([1, 2]);
+        let off_chances = NodeValueMap::from([(1, 0.9), (2, 0.9)]);
+        let vis_gen = RandomGen { rng: seeded_rng(Some(0)), ids: dynamic_ids.clone(), off_chances };
+        let results = enumerate_states(&dynamic_ids, &vis_gen, &mut EitherOn, &AnalysisContext::default()).unwrap();
+        assert_eq!(results.row_count, 4);
+        assert!((results.end_op_mean - 0.19).abs() < 1e-6);
+        // Both nodes are off in 81% of the states, though in only one of the four
+        let histogram = &results.end_op_histogram;
+        assert_eq!(histogram.percentile(50.0), Some(0.0));
+        assert_eq!(histogram.percentile(90.0), Some(1.0));
+    }
+}
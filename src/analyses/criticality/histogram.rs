@@ -0,0 +1,121 @@
+//! Distribution of the end node value over the sampled states.
+//!
+//! The mean end operability hides how often the end node is only partly operable, which matters
+//! when the roll up rules produce continuous values. Every sampled value is counted in one of
+//! ['HISTOGRAM_BINS'] bins of equal width between 0 and 1, a value of exactly 1 in the last one,
+//! together with the smallest and largest value in the bin. Percentiles are interpolated between
+//! those, so they are exact when every value in a bin is the same, as for boolean end nodes.
+//! Weighted end nodes can add up to values outside of that range, they are counted apart.
+
+/// Number of bins between 0 and 1
+pub const HISTOGRAM_BINS: usize = 20;
+
+/// Bin of a value between 0 and 1
+pub fn bin_of(value: f64) -> usize {
+    ((value * HISTOGRAM_BINS as f64) as usize).min(HISTOGRAM_BINS - 1)
+}
+
+/// Counts of the end node values, empty for analyses that don't sample states
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Histogram {
+    /// Values in every bin, empty if no value was added
+    pub counts: Vec<u64>,
+    /// Smallest and largest value in every bin, None for empty bins
+    pub bounds: Vec<Option<(f64, f64)>>,
+    /// Values below 0
+    pub below: u64,
+    /// Values above 1
+    pub above: u64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Histogram {
+    pub fn add(&mut self, value: f64) {
+        self.add_count(value, 1);
+    }
+
+    /// Adds 'value' 'count' times, nothing if 'count' is 0
+    pub fn add_count(&mut self, value: f64, count: u64) {
+        if count == 0 {
+            return;
+        }
+        if self.counts.is_empty() {
+            self.counts = vec![0; HISTOGRAM_BINS];
+            self.bounds = vec![None; HISTOGRAM_BINS];
+        }
+        match value {
+            v if v < 0.0 => self.below += count,
+            v if v > 1.0 => self.above += count,
+            v => {
+                let bin = bin_of(v);
+                self.counts[bin] += count;
+                self.bounds[bin] = Some(self.bounds[bin].map_or((v, v), |(low, high)| (low.min(v), high.max(v))));
+            }
+        }
+        self.min = Some(self.min.map_or(value, |m| m.min(value)));
+        self.max = Some(self.max.map_or(value, |m| m.max(value)));
+    }
+
+    /// Adds the counts of 'other'
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.counts.is_empty() {
+            return;
+        }
+        if self.counts.is_empty() {
+            self.counts = vec![0; other.counts.len()];
+            self.bounds = vec![None; other.bounds.len()];
+        }
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+        for (bounds, other) in self.bounds.iter_mut().zip(other.bounds.iter()) {
+            *bounds = match (*bounds, *other) {
+                (Some((low, high)), Some((other_low, other_high))) => Some((low.min(other_low), high.max(other_high))),
+                (bounds, other) => bounds.or(other),
+            };
+        }
+        self.below += other.below;
+        self.above += other.above;
+        self.min = self.min.into_iter().chain(other.min).reduce(f64::min);
+        self.max = self.max.into_iter().chain(other.max).reduce(f64::max);
+    }
+
+    /// Number of values
+    pub fn total(&self) -> u64 {
+        self.below + self.above + self.counts.iter().sum::<u64>()
+    }
+
+    /// (upper edge of the bin, share of the values up to the edge) of every bin, the empirical
+    /// distribution function at the edges. Values above 1 are left out of every share.
+    pub fn cdf(&self) -> Vec<(f64, f64)> {
+        let total = self.total().max(1) as f64;
+        let mut cumulative = self.below;
+        self.counts.iter().enumerate()
+            .map(|(bin, count)| {
+                cumulative += count;
+                ((bin + 1) as f64 / self.counts.len() as f64, cumulative as f64 / total)
+            })
+            .collect()
+    }
+
+    /// Estimate of the 'p'th percentile of the values, interpolated between the smallest and
+    /// largest value of its bin. Percentiles outside of the bins are the smallest or largest
+    /// value. None if there are no values.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        let (min, max) = (self.min?, self.max?);
+        let rank = (p / 100.0).clamp(0.0, 1.0) * self.total() as f64;
+        if rank <= self.below as f64 {
+            return Some(min);
+        }
+        let mut before = self.below as f64;
+        for (count, bounds) in self.counts.iter().zip(self.bounds.iter()) {
+            let count = *count as f64;
+            if let Some((low, high)) = bounds.filter(|_| rank <= before + count) {
+                return Some(low + (high - low) * (rank - before) / count);
+            }
+            before += count;
+        }
+        Some(max)
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+use crate::analyses::criticality::{AnalysisGraph, RollUpEvaluator, StateEvaluator};
+use crate::network::{NodeValueMap, RollUpPath};
+
+/// Evaluates states like the wrapped ['RollUpEvaluator'] but updates the values of the previous
+/// state with ['crate::network::Graph::roll_up_delta'] for every node whose visibility changed,
+/// instead of rolling up every node. The values are always the same as those of the wrapped
+/// evaluator. Meant for generators whose consecutive states differ in few nodes.
+pub struct IncrementalRollUpEvaluator {
+    pub full: RollUpEvaluator,
+    /// Shared by the clones of every thread like the graph
+    path: Arc<RollUpPath>,
+    /// Visibilities of the previous state, its values are those of the last roll up of 'full'
+    previous: Option<NodeValueMap<u8>>,
+    /// Nodes whose visibility changed since the previous state, reused for every state
+    changed: Vec<u32>,
+}
+
+impl Clone for IncrementalRollUpEvaluator {
+    fn clone(&self) -> Self {
+        IncrementalRollUpEvaluator {
+            full: self.full.clone(),
+            path: self.path.clone(),
+            previous: self.previous.clone(),
+            changed: vec![],
+        }
+    }
+}
+
+impl IncrementalRollUpEvaluator {
+    pub fn new(full: RollUpEvaluator) -> IncrementalRollUpEvaluator {
+        let path = Arc::new(RollUpPath::new(&full.graph.links));
+        IncrementalRollUpEvaluator { full, path, previous: None, changed: vec![] }
+    }
+}
+
+impl StateEvaluator for IncrementalRollUpEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let Some(previous) = self.previous.as_mut() else {
+            let end_value = self.full.evaluate(visibility_state);
+            self.previous = Some(visibility_state.clone());
+            return end_value;
+        };
+        self.changed.clear();
+        self.changed.extend(visibility_state.iter()
+            .filter(|(id, v)| previous.get(id) != Some(v))
+            .map(|(id, _)| *id)
+            .chain(previous.keys().filter(|id| !visibility_state.contains_key(id)).copied()));
+        let AnalysisGraph { graph, links } = self.full.graph.as_ref();
+        for id in self.changed.iter() {
+            graph.roll_up_delta(&self.path, links, self.full.roll_up_rule.as_ref(), visibility_state, &mut self.full.values, *id);
+            match visibility_state.get(id) {
+                Some(visibility) => previous.insert(*id, *visibility),
+                None => previous.remove(id),
+            };
+        }
+        self.full.end_value()
+    }
+}
@@ -4,6 +4,11 @@ use crate::util;
 pub trait CritLoopCondition : DynClone + Send{
     fn stop(&mut self) -> bool;
     fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn CritLoopCondition>>;
+
+    /// Iterations left before the condition stops, None if that isn't known in advance
+    fn remaining(&self) -> Option<u64> {
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -18,11 +23,11 @@ impl CritLoopCondition for MaxLoopCondition {
             return true
         }
         self.index += 1;
-        return false
+        false
     }
 
-    fn split_to_threads(&self, threads: u64) -> Vec<Box<(dyn CritLoopCondition)>> {
-        let mut out: Vec<Box<(dyn CritLoopCondition)>> = vec![];
+    fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn CritLoopCondition>> {
+        let mut out: Vec<Box<dyn CritLoopCondition>> = vec![];
         let splits = util::split_range(self.index, self.max, threads);
         for split in splits {
             out.push(Box::new(
@@ -31,4 +36,8 @@ impl CritLoopCondition for MaxLoopCondition {
         }
         out
     }
+
+    fn remaining(&self) -> Option<u64> {
+        Some(self.max.saturating_sub(self.index))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::errors::analysis::ThorError;
+use crate::analyses::criticality::{AnalysisGraph, CriticalityResults, RollUpEvaluator, sample_states};
+use crate::analyses::criticality::exhaustive::{enumerate_states, exhaustive_states};
+use crate::analyses::criticality::incremental::IncrementalRollUpEvaluator;
+use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::{Lifetime, LifetimeGen, seeded_rng};
+use crate::network::{CsrLinks, Graph, LinkMap, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// Criticality of every dynamic node at several mission times. The states at a mission time are
+/// sampled from the lifetime distributions of the nodes, see ['LifetimeGen'], or evaluated
+/// exactly if there are fewer of them than samples, see ['crate::analyses::criticality::exhaustive'].
+pub struct MissionTimeCurve {
+    pub threads: u8,
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    pub lifetimes: NodeValueMap<Lifetime>,
+    /// Off chances of the nodes without a lifetime
+    pub off_chances: NodeValueMap<f32>,
+    pub mission_times: Vec<f64>,
+    pub loop_condition: Box<dyn CritLoopCondition>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub l_map: LinkMap,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// Whether to evaluate every state exactly instead of sampling if there are fewer states than
+    /// samples
+    pub exhaustive_fallback: bool,
+    /// Seed of the states at every mission time, None to seed them from the system. Every
+    /// mission time draws from the same seed, so the curves don't jump by the sampling noise.
+    pub seed: Option<u64>,
+}
+
+/// Criticality results at every mission time, in the order of the mission times
+#[derive(Debug, Clone)]
+pub struct MissionTimeResults {
+    pub mission_times: Vec<f64>,
+    pub results: Vec<CriticalityResults>,
+}
+
+impl MissionTimeResults {
+    /// Criticality of every node at each mission time, in the order of the mission times
+    pub fn curves(&self) -> NodeValueMap<Vec<f64>> {
+        let mut curves: NodeValueMap<Vec<f64>> = NodeValueMap::new();
+        for results in self.results.iter() {
+            for (id, node) in results.nodes.iter() {
+                curves.entry(*id).or_default().push(node.criticality);
+            }
+        }
+        curves
+    }
+
+    /// Prints the curves as csv, one row per node and one column per mission time
+    pub fn print(&self, graph: &Graph) {
+        let header: Vec<String> = self.mission_times.iter().map(|t| t.to_string()).collect();
+        println!("node,{}", header.join(","));
+        let operability: Vec<String> = self.results.iter().map(|r| r.end_op_mean.to_string()).collect();
+        println!("end operability,{}", operability.join(","));
+        for (id, curve) in self.curves().iter() {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            let curve: Vec<String> = curve.iter().map(|c| c.to_string()).collect();
+            println!("{} ({}),{}", name, id, curve.join(","));
+        }
+    }
+}
+
+impl Analysis for MissionTimeCurve {
+    type Output = MissionTimeResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<MissionTimeResults, ThorError> {
+        info!("Starting Mission Time Analysis");
+        let path = Graph::get_bfs_path(&self.l_map, self.start_id);
+        let evaluator = RollUpEvaluator::new(
+            Arc::new(AnalysisGraph { graph: self.graph.clone(), links: CsrLinks::new(&self.l_map, &path) }),
+            dyn_clone::clone_box(&*self.roll_up_rule),
+            vec![(self.end_id, 1.0)],
+        );
+        let mut results = vec![];
+        for mission_time in self.mission_times.iter() {
+            let vis_gen = LifetimeGen {
+                rng: seeded_rng(self.seed),
+                ids: self.dynamic_ids.clone(),
+                lifetimes: self.lifetimes.clone(),
+                off_chances: self.off_chances.clone(),
+                mission_time: *mission_time,
+            };
+            let exhaustive = match self.exhaustive_fallback {
+                true => exhaustive_states(&self.dynamic_ids, &vis_gen, self.loop_condition.as_ref()),
+                false => None,
+            };
+            results.push(match exhaustive {
+                Some(_) => {
+                    let mut evaluator = IncrementalRollUpEvaluator::new(evaluator.clone());
+                    enumerate_states(&self.dynamic_ids, &vis_gen, &mut evaluator, ctx)?
+                }
+                None => sample_states(self.threads, &self.dynamic_ids, &vis_gen, self.loop_condition.as_ref(), &evaluator, ctx)?.results(),
+            });
+        }
+        Ok(MissionTimeResults { mission_times: self.mission_times.clone(), results })
+    }
+}
@@ -1,20 +1,101 @@
-use std::collections::{HashMap, HashSet};
-use std::ops::Index;
-use log::info;
-use crate::analyses::{Analysis, VISIBLE_VAL};
-use crate::network::{Graph, LinkMap, NodeValueMap};
+use std::collections::HashSet;
+use std::time::Instant;
+use dyn_clone::DynClone;
+use log::{debug, error, info, warn, Level};
+use crate::analyses::{Analysis, AnalysisContext, CancellationToken, VISIBLE_VAL};
+use crate::analyses::limits::{Limit, LimitAction};
+use crate::errors::analysis::{LimitExceededError, ThorError};
+use crate::json::JsonValue;
+use crate::logging::event;
+use crate::metrics::Metrics;
+use crate::network::{ALPHA_ATTR, CsrLinks, DenseValues, EdgeAttributeMaps, EdgeValueMap, Graph, LinkMap, NodeValueMap};
+use crate::output::Output;
 use crate::roll_up::RollUp;
-use std::sync::mpsc;
+use std::sync::{Arc, mpsc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
 use std::thread;
+use crate::analyses::criticality::coverage::{CoverageReport, StateCoverage};
+use crate::analyses::criticality::exhaustive::{enumerate_states, enumerated_states, exhaustive_states};
+use crate::analyses::criticality::histogram::Histogram;
 use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::incremental::IncrementalRollUpEvaluator;
 use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::analyses::criticality::partial::PartialWriter;
+use crate::analyses::criticality::samples::SampleLogEvaluator;
+use crate::analyses::criticality::surrogate::SurrogateEvaluator;
+use crate::analyses::criticality::throughput::{ESTIMATE_INTERVAL, FIRST_ESTIMATE_AFTER, Throughput};
+use crate::analyses::criticality::visited::Visited;
 
+pub mod builder;
+pub mod compare;
+pub mod coverage;
+pub mod delta;
+pub mod exhaustive;
+pub mod histogram;
+pub mod incremental;
 pub mod loop_condition;
+pub mod mission_time;
+pub mod pairwise;
+pub mod partial;
+pub mod ranking;
+pub mod recording;
+pub mod samples;
+pub mod statistics;
+pub mod surrogate;
+pub mod throughput;
 pub mod vis_gen;
+pub mod visited;
 
+/// Additional values read alongside the graph for the criticality analysis
+#[derive(Debug, Clone, Default)]
 pub struct CriticalityData {
+    /// Named attribute maps of the edges, the alpha weights are stored under ['ALPHA_ATTR']
+    pub edge_attributes: EdgeAttributeMaps,
+    /// Chance of every dynamic node to not be visible in a sampled state
+    pub off_chances: NodeValueMap<f32>,
+    /// Problems of the input that were resolved while reading it, such as dropped rows
+    pub warnings: Vec<String>,
 }
 
+impl CriticalityData {
+    /// Alpha weight of every edge, if the input provided any
+    pub fn alpha(&self) -> Option<&EdgeValueMap<f32>> {
+        self.edge_attributes.get(ALPHA_ATTR)
+    }
+
+    /// Moves the data of the nodes 'ids' to the supernode 'new_id', see ['Graph::contract']. Edge
+    /// attributes follow the rewired edges, of edges that are merged the one of the smallest
+    /// edge is kept. The merged nodes lose their off chances, the supernode has 'off_chance'.
+    pub fn contract(&mut self, ids: &HashSet<u32>, new_id: u32, off_chance: Option<f32>) {
+        let rewire = |id: u32| if ids.contains(&id) { new_id } else { id };
+        for values in self.edge_attributes.values_mut() {
+            let mut rewired = EdgeValueMap::new();
+            for ((from, to), value) in values.iter() {
+                let edge = (rewire(*from), rewire(*to));
+                if edge != (new_id, new_id) {
+                    rewired.entry(edge).or_insert(*value);
+                }
+            }
+            *values = rewired;
+        }
+        self.off_chances.retain(|id, _| !ids.contains(id));
+        if let Some(off_chance) = off_chance {
+            self.off_chances.insert(new_id, off_chance);
+        }
+    }
+
+    /// Merges a named edge attribute map into the data, overwriting values of edges that
+    /// already have the attribute
+    pub fn add_edge_attribute(&mut self, name: &str, mut values: EdgeValueMap<f32>) {
+        self.edge_attributes.entry(name.to_string()).or_default().append(&mut values);
+    }
+}
+
+/// Samples visibility states of the dynamic nodes and rolls each of them up. The criticality of a
+/// dynamic node is the mean end value over the states in which the node is visible minus the mean
+/// over the states in which it isn't, the end value being the operability of the end node or the
+/// weighted sum of the 'end_weights'. The rolled up value of the node itself is not used.
 pub struct Criticality {
     pub threads: u8,
     pub graph: Graph,
@@ -24,149 +105,961 @@ pub struct Criticality {
     pub roll_up_rule: Box<dyn RollUp>,
     pub l_map: LinkMap,
     pub start_id: u32,
-    pub end_id: u32
+    pub end_id: u32,
+    /// Weight of every end node, the criticality is computed against the weighted sum of their
+    /// operabilities. If empty only the 'end_id' is used, with a weight of one.
+    pub end_weights: Vec<(u32, f64)>,
+    /// Destinations the results are written to once every thread has finished
+    pub outputs: Vec<Box<dyn Output>>,
+    /// File every evaluated state is logged to, see ['samples']
+    pub sample_log: Option<String>,
+    /// (training states, margin) of the surrogate screening the states, see ['surrogate']
+    pub surrogate: Option<(usize, f64)>,
+    /// Whether to evaluate every state exactly instead of sampling if there are fewer states than
+    /// samples and no 'sample_log', see ['exhaustive']
+    pub exhaustive_fallback: bool,
+    /// Whether to evaluate every state exactly whatever the samples, see ['exhaustive']
+    pub enumerate: bool,
 }
 
 impl Analysis for Criticality {
-    fn analyze(self) {
+    type Output = CriticalityResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
         info!("Starting Criticality Analysis");
         let path = Graph::get_bfs_path(&self.l_map, self.start_id);
+        let evaluator = RollUpEvaluator::new(
+            Arc::new(AnalysisGraph { graph: self.graph.clone(), links: CsrLinks::new(&self.l_map, &path) }),
+            dyn_clone::clone_box(&*self.roll_up_rule),
+            match self.end_weights.is_empty() {
+                true => vec![(self.end_id, 1.0)],
+                false => self.end_weights.clone(),
+            },
+        );
+        // A sample log holds sampled states without weights, see ['samples']
+        let exhaustive = match (self.enumerate, self.exhaustive_fallback && self.sample_log.is_none()) {
+            (true, _) => Some(enumerated_states(&self.dynamic_ids, self.vis_gen.as_ref())?),
+            (false, true) => exhaustive_states(&self.dynamic_ids, self.vis_gen.as_ref(), self.loop_condition.as_ref()),
+            (false, false) => None,
+        };
+        // The states are enumerated in Gray code order, see ['exhaustive']
+        let evaluator: Box<dyn StateEvaluator> = match self.vis_gen.incremental() || exhaustive.is_some() {
+            true => Box::new(IncrementalRollUpEvaluator::new(evaluator)),
+            false => Box::new(evaluator),
+        };
+        // Screening saves nothing once every state is evaluated once
+        let evaluator: Box<dyn StateEvaluator> = match (self.surrogate, exhaustive) {
+            (Some((training, margin)), None) => Box::new(SurrogateEvaluator::new(evaluator, training, margin)),
+            _ => evaluator,
+        };
+        let mut evaluator: Box<dyn StateEvaluator> = match &self.sample_log {
+            Some(path) => Box::new(SampleLogEvaluator::new(evaluator, &self.dynamic_ids, path)
+                .map_err(|e| ThorError::Failed(format!("The sample log {} can't be created: {}", path, e).into()))?),
+            None => evaluator,
+        };
+        let results = match exhaustive {
+            Some(_) => enumerate_states(&self.dynamic_ids, self.vis_gen.as_ref(), evaluator.as_mut(), ctx)?,
+            None => sample_states(self.threads, &self.dynamic_ids, self.vis_gen.as_ref(),
+                                  self.loop_condition.as_ref(), evaluator.as_ref(), ctx)?.results(),
+        };
+        write_outputs(&self.outputs, &self.graph, &results);
+        Ok(results)
+    }
+}
 
-        let (tx1, rx) = mpsc::channel();
-
-        let mut loop_conditions = self.loop_condition.split_to_threads(self.threads as u64);
-        let mut vis_gens = self.vis_gen.split_to_threads(self.threads as u64);
-        let mut senders = vec![];
-        for _ in 0..self.threads -1 {
-            senders.push(tx1.clone());
-        }
-        senders.push(tx1);
-        for _ in 0..self.threads {
-            let tx = senders.pop().unwrap();
-
-            let graph = self.graph.clone();
-            let loop_condition = loop_conditions.pop().unwrap();
-            let vis_gen = vis_gens.pop().unwrap();
-            let roll_up_rule = dyn_clone::clone_box(&*self.roll_up_rule);
-            let l_map = self.l_map.clone();
-            let new_path = path.clone();
-            let dynamic_ids = self.dynamic_ids.clone();
-            let end_id = self.end_id;
-
-            thread::spawn(move || {
-                let data = Criticality::calculate_data(
-                    graph,
-                    vis_gen,
-                    loop_condition,
-                    roll_up_rule,
-                    l_map,
-                    new_path,
-                    dynamic_ids,
-                    end_id
-                );
-                tx.send(data).unwrap();
-            });
-        }
-
-        for received in rx {
-            println!("Got {:?}", received);
-        }
-    }
-}
-
-impl Criticality {
-    fn calculate_data(graph: Graph,
-                      mut states_generator: Box<dyn VisGen>,
-                      mut loop_condition: Box<dyn CritLoopCondition>,
-                      roll_up_rule: Box<dyn RollUp>,
-                      l_map: LinkMap,
-                      path: Vec<u32>,
-                      dynamic_ids: HashSet<u32>,
-                      end_id: u32
-    ) -> GraphCritData
-    {
-        let mut data = GraphCritData {
-            row_count: 0,
-            end_op_sum: 0.0,
-            node_data: HashMap::new(),
+/// Value computed for every sampled visibility state, such as the operability of the end node.
+/// Every thread evaluates the states using its own clone.
+pub trait StateEvaluator: DynClone + Send {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64;
+}
+
+/// The read only part of a roll up, shared by the evaluators of every sampling thread instead of
+/// being cloned for each of them
+pub struct AnalysisGraph {
+    pub graph: Graph,
+    /// The links packed along the roll up path
+    pub links: CsrLinks,
+}
+
+/// Evaluates a state as the weighted sum of the end node operabilities after rolling up the graph
+pub struct RollUpEvaluator {
+    pub graph: Arc<AnalysisGraph>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    /// (end node, weight), end nodes that are not rolled up count as not operable
+    pub end_weights: Vec<(u32, f64)>,
+    /// Visibilities of the evaluated state by the index of the links, reused for every state
+    visibilities: DenseValues<u8>,
+    /// Values of the last roll up, reused for every state
+    values: DenseValues<f32>,
+}
+
+impl Clone for RollUpEvaluator {
+    fn clone(&self) -> Self {
+        RollUpEvaluator {
+            graph: self.graph.clone(),
+            roll_up_rule: dyn_clone::clone_box(&*self.roll_up_rule),
+            end_weights: self.end_weights.clone(),
+            visibilities: self.visibilities.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl RollUpEvaluator {
+    pub fn new(graph: Arc<AnalysisGraph>, roll_up_rule: Box<dyn RollUp>, end_weights: Vec<(u32, f64)>) -> RollUpEvaluator {
+        let index = graph.links.index().clone();
+        RollUpEvaluator {
+            graph,
+            roll_up_rule,
+            end_weights,
+            visibilities: DenseValues::new(index.clone()),
+            values: DenseValues::new(index),
+        }
+    }
+
+    /// Weighted sum of the end node operabilities of the last roll up
+    fn end_value(&self) -> f64 {
+        self.end_weights.iter()
+            .map(|(id, weight)| weight * *self.values.get(id).unwrap_or(&0.0) as f64)
+            .sum()
+    }
+}
+
+impl StateEvaluator for RollUpEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let AnalysisGraph { graph, links } = self.graph.as_ref();
+        self.visibilities.assign(visibility_state);
+        graph.roll_up_state_into(links, self.roll_up_rule.as_ref(), &self.visibilities, &mut self.values);
+        self.end_value()
+    }
+}
+
+/// Hands the 'results' to every output, logging the outputs that fail
+pub(crate) fn write_outputs(outputs: &[Box<dyn Output>], graph: &Graph, results: &CriticalityResults) {
+    for output in outputs.iter() {
+        if let Err(e) = output.write(graph, results) {
+            error!("Failed to write the criticality results: {}", e);
+        }
+    }
+}
+
+/// Samples visibility states on 'threads' threads until the loop condition stops, evaluating
+/// every unique state and aggregating the values per dynamic node. The limits of the context are
+/// enforced, see ['crate::analyses::limits'].
+///
+/// # Errors
+///
+/// Returns ['ThorError::Cancelled'] if the context is cancelled while sampling, or a
+/// ['LimitExceededError'] if a limit is reached and the analysis may not degrade
+pub(crate) fn sample_states(threads: u8,
+                            dynamic_ids: &HashSet<u32>,
+                            vis_gen: &dyn VisGen,
+                            loop_condition: &dyn CritLoopCondition,
+                            evaluator: &(dyn StateEvaluator + 'static),
+                            ctx: &AnalysisContext) -> Result<GraphCritData, ThorError> {
+    let evaluators = vec![dyn_clone::clone_box(evaluator)];
+    Ok(sample_states_many(threads, dynamic_ids, vis_gen, loop_condition, &evaluators, ctx)?.pop().unwrap())
+}
+
+/// Same as ['sample_states'] but evaluates every sampled state with each of the 'evaluators',
+/// returning the aggregated values in the order of the evaluators
+pub(crate) fn sample_states_many(threads: u8,
+                                 dynamic_ids: &HashSet<u32>,
+                                 vis_gen: &dyn VisGen,
+                                 loop_condition: &dyn CritLoopCondition,
+                                 evaluators: &[Box<dyn StateEvaluator>],
+                                 ctx: &AnalysisContext) -> Result<Vec<GraphCritData>, ThorError> {
+    if cfg!(target_arch = "wasm32") {
+        return sample_states_inline(dynamic_ids, vis_gen, loop_condition, evaluators, ctx);
+    }
+    let (tx1, rx) = mpsc::channel();
+
+    let mut loop_conditions = loop_condition.split_to_threads(threads as u64);
+    let mut vis_gens = vis_gen.split_to_threads(threads as u64);
+    let guard = Guard {
+        deadline: ctx.deadline,
+        max_wall_time: ctx.limits.max_wall_time,
+        visited: ctx.limits.visited_per_thread(dynamic_ids.len(), threads),
+        on_limit: ctx.limits.on_limit,
+    };
+    // The ids are only read, every thread shares them
+    let shared_ids = Arc::new(dynamic_ids.clone());
+    let mut throughput = Throughput::new(loop_conditions.iter().map(|c| c.remaining()).sum());
+    let iterations = Arc::new(AtomicU64::new(0));
+    let mut senders = vec![];
+    for _ in 0..threads -1 {
+        senders.push(tx1.clone());
+    }
+    senders.push(tx1);
+    for worker in 0..threads {
+        let tx = senders.pop().unwrap();
+
+        let loop_condition = loop_conditions.pop().unwrap();
+        let vis_gen = vis_gens.pop().unwrap();
+        let evaluators: Vec<Box<dyn StateEvaluator>> = evaluators.iter().map(|e| dyn_clone::clone_box(&**e)).collect();
+        let dynamic_ids = shared_ids.clone();
+        let progress = Progress {
+            iterations: iterations.clone(),
+            partial: ctx.partial.as_ref().map(|p| PartialSender { every: p.every.max(1), worker: worker as usize, tx: tx.clone() }),
+            coverage: ctx.coverage.is_some(),
+            metrics: ctx.metrics.clone(),
         };
+        let cancellation = ctx.cancellation.clone();
+        let metrics = ctx.metrics.clone();
 
-        for n_id in dynamic_ids.iter() {
-            data.node_data.insert(*n_id, NodeCritData {
-                sum_end_on: 0.0,
-                sum_end_off: 0.0,
-            });
+        thread::spawn(move || {
+            let start = Instant::now();
+            if let Some(metrics) = metrics.as_ref() {
+                metrics.worker_started();
+            }
+            let data = calculate_data(
+                vis_gen,
+                loop_condition,
+                evaluators,
+                dynamic_ids,
+                cancellation,
+                guard,
+                progress,
+            );
+            let states = data.0.first().map(|d| d.row_count).unwrap_or(0);
+            if let Some(metrics) = metrics.as_ref() {
+                metrics.worker_finished();
+            }
+            event(Level::Info, "worker_finished", JsonValue::object()
+                .with("worker", worker as u64)
+                .with("states", states)
+                .with("seconds", start.elapsed().as_secs_f64()));
+            tx.send(WorkerMessage::Done(worker as usize, data.0, data.1, data.2)).unwrap();
+        });
+    }
+
+    // The sums of every worker are added in the order of the workers once all are done, so a
+    // seeded run adds them up the same way every time
+    let mut finished: Vec<Vec<GraphCritData>> = vec![vec![]; threads as usize];
+    let mut reached: Option<Limit> = None;
+    let mut coverages: Vec<Option<StateCoverage>> = vec![None; threads as usize];
+    let mut partial = ctx.partial.clone().map(|p| PartialWriter::new(p, threads));
+    let mut next_estimate = Instant::now() + FIRST_ESTIMATE_AFTER;
+    loop {
+        let message = match rx.recv_timeout(next_estimate.saturating_duration_since(Instant::now())) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => {
+                let estimate = throughput.update(iterations.load(Ordering::Relaxed));
+                info!("{}", estimate);
+                let fields = JsonValue::object()
+                    .with("states", estimate.done)
+                    .with("total", estimate.total)
+                    .with("states_per_second", estimate.states_per_second)
+                    .with("remaining_seconds", estimate.remaining.map(|r| r.as_secs_f64()));
+                if let Some(partial) = partial.as_ref() {
+                    partial.notify("progress", fields.clone());
+                }
+                event(Level::Info, "progress", fields);
+                next_estimate = Instant::now() + ESTIMATE_INTERVAL;
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let (worker, received, limit, coverage) = match message {
+            WorkerMessage::Partial(worker, received) => {
+                if let Some(partial) = partial.as_mut() {
+                    partial.update(worker, received, true);
+                }
+                continue;
+            }
+            WorkerMessage::Done(worker, received, limit, coverage) => (worker, received, limit, coverage),
+        };
+        debug!("Got {:?}", received);
+        if let (Some(partial), Some(first)) = (partial.as_mut(), received.first()) {
+            partial.update(worker, first.clone(), false);
+        }
+        finished[worker] = received;
+        reached = reached.or(limit);
+        coverages[worker] = coverage;
+    }
+    let mut data: Vec<GraphCritData> = evaluators.iter().map(|_| GraphCritData::new(dynamic_ids)).collect();
+    for received in finished.iter() {
+        for (total, thread_data) in data.iter_mut().zip(received.iter()) {
+            total.add(thread_data);
         }
+    }
+    report_coverage(&coverages, dynamic_ids.len(), ctx);
+    finish_sampling(data, reached, ctx)
+}
 
-        let mut visited: HashSet<NodeValueMap<u8>> = HashSet::new();
+/// Same as ['sample_states_many'] on the calling thread, for targets without threads or clocks
+/// such as WebAssembly in a browser. The wall time limit, the progress estimates and the partial
+/// results are left out.
+fn sample_states_inline(dynamic_ids: &HashSet<u32>,
+                        vis_gen: &dyn VisGen,
+                        loop_condition: &dyn CritLoopCondition,
+                        evaluators: &[Box<dyn StateEvaluator>],
+                        ctx: &AnalysisContext) -> Result<Vec<GraphCritData>, ThorError> {
+    let guard = Guard {
+        deadline: None,
+        max_wall_time: None,
+        visited: ctx.limits.visited_per_thread(dynamic_ids.len(), 1),
+        on_limit: ctx.limits.on_limit,
+    };
+    let progress = Progress {
+        iterations: Arc::new(AtomicU64::new(0)),
+        partial: None,
+        coverage: ctx.coverage.is_some(),
+        metrics: ctx.metrics.clone(),
+    };
+    let (data, reached, coverage) = calculate_data(
+        vis_gen.split_to_threads(1).pop().unwrap(),
+        loop_condition.split_to_threads(1).pop().unwrap(),
+        evaluators.iter().map(|e| dyn_clone::clone_box(&**e)).collect(),
+        Arc::new(dynamic_ids.clone()),
+        ctx.cancellation.clone(),
+        guard,
+        progress,
+    );
+    report_coverage(&[coverage], dynamic_ids.len(), ctx);
+    finish_sampling(data, reached, ctx)
+}
 
-        while !loop_condition.stop() {
-            let visibility_state = states_generator.next_states();
-            if visited.contains(&visibility_state) {
-                continue
+/// Adds the coverage of the state space by the states of every thread to the coverage log of the
+/// context, if any, see ['coverage']
+fn report_coverage(coverages: &[Option<StateCoverage>], nodes: usize, ctx: &AnalysisContext) {
+    let Some(log) = ctx.coverage.as_ref() else { return };
+    let mut overall = StateCoverage::new();
+    let mut workers = vec![];
+    for coverage in coverages.iter().flatten() {
+        overall.merge(coverage);
+        workers.push(coverage.coverage(nodes));
+    }
+    let report = CoverageReport { overall: overall.coverage(nodes), workers };
+    info!("Sampled {}", report.overall);
+    event(Level::Info, "coverage", report.to_json());
+    log.push(report);
+}
+
+/// The sampled 'data', or the error of a cancelled run or of the limit it 'reached'
+fn finish_sampling(data: Vec<GraphCritData>, reached: Option<Limit>, ctx: &AnalysisContext) -> Result<Vec<GraphCritData>, ThorError> {
+    if ctx.is_cancelled() {
+        return Err(ThorError::Cancelled);
+    }
+    if let Some(limit) = reached {
+        match ctx.limits.on_limit {
+            LimitAction::Abort => return Err(LimitExceededError { limit: limit.to_string() }.into()),
+            LimitAction::Degrade => warn!("The sampling reached its {}, the results are approximate", limit),
+        }
+    }
+    Ok(data)
+}
+
+/// Messages of the sampling threads to the main thread
+enum WorkerMessage {
+    /// The sums of the first evaluator of the thread so far
+    Partial(usize, GraphCritData),
+    /// The final sums of the thread, the limit it reached and the states it covered, if any
+    Done(usize, Vec<GraphCritData>, Option<Limit>, Option<StateCoverage>),
+}
+
+/// How a sampling thread reports its progress to the main thread
+struct Progress {
+    /// Iterations of every thread so far, see ['throughput']
+    iterations: Arc<AtomicU64>,
+    partial: Option<PartialSender>,
+    /// Whether to track the unique states evaluated, see ['coverage']
+    coverage: bool,
+    /// Metrics of the service running the analysis, given the iterations with the shared count
+    metrics: Option<Arc<Metrics>>,
+}
+
+/// Where a sampling thread sends its sums every 'every' states, see ['partial']
+struct PartialSender {
+    every: u64,
+    worker: usize,
+    tx: mpsc::Sender<WorkerMessage>,
+}
+
+/// Iterations a sampling thread counts before adding them to the shared count
+const ITERATION_BATCH: u64 = 1024;
+
+/// Limits every sampling thread enforces
+#[derive(Debug, Clone, Copy)]
+struct Guard {
+    deadline: Option<Instant>,
+    max_wall_time: Option<std::time::Duration>,
+    /// Visited states the thread may keep exactly and the limit bounding them
+    visited: Option<(usize, Limit)>,
+    on_limit: LimitAction,
+}
+
+fn calculate_data(mut states_generator: Box<dyn VisGen>,
+                  mut loop_condition: Box<dyn CritLoopCondition>,
+                  mut evaluators: Vec<Box<dyn StateEvaluator>>,
+                  dynamic_ids: Arc<HashSet<u32>>,
+                  cancellation: CancellationToken,
+                  guard: Guard,
+                  progress: Progress,
+) -> (Vec<GraphCritData>, Option<Limit>, Option<StateCoverage>)
+{
+    let mut data: Vec<GraphCritData> = evaluators.iter().map(|_| GraphCritData::new(&dynamic_ids)).collect();
+    // Every data set uses the same compact node indexes
+    let ids = data.first().map(|d| d.ids.clone()).unwrap_or_default();
+    let mut visible = vec![true; ids.len()];
+
+    let mut visited = Visited::new();
+    let mut reached: Option<Limit> = None;
+    let Progress { iterations, partial, coverage, metrics } = progress;
+    let count_iterations = |counted: u64| {
+        iterations.fetch_add(counted, Ordering::Relaxed);
+        if let Some(metrics) = metrics.as_ref() {
+            metrics.states_sampled(counted);
+        }
+    };
+    let mut coverage = coverage.then(StateCoverage::new);
+    let mut next_partial = partial.as_ref().map(|p| p.every).unwrap_or(u64::MAX);
+    // Iterations not yet added to the shared count, which is updated in batches
+    let mut counted = 0;
+
+    // Both states of a pair are drawn together and then evaluated one after the other, each
+    // counting as an iteration and skipped if it was seen like any other state
+    let paired = states_generator.paired();
+    let distinct = states_generator.distinct();
+    // The states are drawn into the same maps every iteration
+    let mut visibility_state = NodeValueMap::new();
+    let mut partner = NodeValueMap::new();
+    // Whether the next state is the partner of the last one drawn
+    let mut partner_next = false;
+    // End values of every evaluator for the current state and for the first state of the pair,
+    // if it was evaluated
+    let mut ends = vec![0.0; evaluators.len()];
+    let mut first_ends = vec![0.0; evaluators.len()];
+    let mut first_added = false;
+    while !cancellation.is_cancelled() && !loop_condition.stop() {
+        counted += 1;
+        if counted == ITERATION_BATCH {
+            count_iterations(counted);
+            counted = 0;
+        }
+        if let (Some(deadline), Some(t)) = (guard.deadline, guard.max_wall_time) {
+            if Instant::now() >= deadline {
+                reached = Some(Limit::WallTime(t));
+                break;
             }
-            let result = graph.roll_up_state(&path, &l_map, &roll_up_rule, &visibility_state);
-            let end_val = result.get(&end_id).unwrap();
-            let mut node_data = HashMap::new();
-            for id in dynamic_ids.iter() {
-                let visible = match visibility_state.get(&id) {
-                    None => { true }
-                    Some(x) => { *x == VISIBLE_VAL }
-                };
-                let state_val = result.get(&id).unwrap();
-                let mut sum_end_on = 0.0;
-                let mut sum_end_off = 0.0;
-                match visible {
-                    true => {
-                        sum_end_on += state_val;
-                    }
-                    false => {
-                        sum_end_off += state_val;
-                    }
+        }
+        let first = !partner_next;
+        if partner_next {
+            std::mem::swap(&mut visibility_state, &mut partner);
+            partner_next = false;
+        } else {
+            states_generator.next_states_into(&mut visibility_state);
+            if paired {
+                states_generator.next_states_into(&mut partner);
+                partner_next = true;
+            }
+        }
+        if !distinct && visited.contains(&visibility_state) {
+            first_added &= !first;
+            continue
+        }
+        add_state(&ids, &mut visible, &mut evaluators, &mut data, &visibility_state, &mut ends);
+        if let Some(coverage) = coverage.as_mut() {
+            coverage.add(&visibility_state, states_generator.as_ref());
+        }
+        if paired && first {
+            first_ends.copy_from_slice(&ends);
+            first_added = true;
+        } else if paired && first_added {
+            for (data, (first_end, end)) in data.iter_mut().zip(first_ends.iter().zip(ends.iter())) {
+                data.add_pair(*first_end, *end);
+            }
+        }
+        if let (Some(partial), Some(first)) = (partial.as_ref(), data.first()) {
+            if first.row_count >= next_partial {
+                // The main thread may be gone after a failure, the sums are then dropped
+                let _ = partial.tx.send(WorkerMessage::Partial(partial.worker, first.clone()));
+                next_partial = first.row_count + partial.every;
+            }
+        }
+        if distinct {
+            continue
+        }
+        visited.insert(visibility_state.clone());
+        if let Some((max, limit)) = guard.visited {
+            if !visited.is_degraded() && visited.exact_len() >= max {
+                reached = Some(limit);
+                match guard.on_limit {
+                    LimitAction::Abort => break,
+                    LimitAction::Degrade => visited.degrade(),
                 }
-                node_data.insert(*id, NodeCritData {
-                    sum_end_on: sum_end_on as f64,
-                    sum_end_off: sum_end_off as f64,
-                });
             }
-            let new_data = GraphCritData {
-                row_count: 1,
-                end_op_sum: *end_val as f64,
-                node_data,
-            };
-            data.add(&new_data);
-            visited.insert(visibility_state);
         }
-        data
+    }
+    count_iterations(counted);
+    (data, reached, coverage)
+}
+
+/// Evaluates a single state with every evaluator and adds it to their data
+fn add_state(ids: &[u32],
+             visible: &mut [bool],
+             evaluators: &mut [Box<dyn StateEvaluator>],
+             data: &mut [GraphCritData],
+             visibility_state: &NodeValueMap<u8>,
+             ends: &mut [f64]) {
+    for (i, id) in ids.iter().enumerate() {
+        visible[i] = match visibility_state.get(id) {
+            None => { true }
+            Some(x) => { *x == VISIBLE_VAL }
+        };
+    }
+    for ((evaluator, data), end) in evaluators.iter_mut().zip(data.iter_mut()).zip(ends.iter_mut()) {
+        *end = evaluator.evaluate(visibility_state);
+        data.add_state(visible, *end);
     }
 }
 
-#[derive(Debug)]
-struct GraphCritData {
+/// Sums accumulated over the sampled states. The values of the nodes are stored densely in the
+/// order of 'ids'.
+#[derive(Debug, Clone)]
+pub(crate) struct GraphCritData {
     row_count: u64,
     end_op_sum: f64,
-    node_data: HashMap<u32, NodeCritData>
+    /// Sum of the squared end values, for the variance of the means
+    end_op_sq_sum: f64,
+    end_op_histogram: Histogram,
+    /// Antithetic pairs whose states were both evaluated, with the sum of the means of their end
+    /// values and of the squared means, for the variance of the means of paired states
+    pair_count: u64,
+    pair_sum: f64,
+    pair_sq_sum: f64,
+    /// Dynamic node ids in ascending order, the index of an id is its index in 'node_data'
+    ids: Vec<u32>,
+    node_data: Vec<NodeCritData>
 }
 
 impl GraphCritData {
+    fn new(dynamic_ids: &HashSet<u32>) -> GraphCritData {
+        let mut ids: Vec<u32> = dynamic_ids.iter().copied().collect();
+        ids.sort();
+        GraphCritData {
+            row_count: 0,
+            end_op_sum: 0.0,
+            end_op_sq_sum: 0.0,
+            end_op_histogram: Histogram::default(),
+            pair_count: 0,
+            pair_sum: 0.0,
+            pair_sq_sum: 0.0,
+            node_data: ids.iter().map(|_| NodeCritData::default()).collect(),
+            ids,
+        }
+    }
+
+    /// Adds a single state where node 'ids[i]' is visible if 'visible[i]' is true
+    fn add_state(&mut self, visible: &[bool], end_val: f64) {
+        self.row_count += 1;
+        self.end_op_sum += end_val;
+        self.end_op_sq_sum += end_val * end_val;
+        self.end_op_histogram.add(end_val);
+        for (crit_data, visible) in self.node_data.iter_mut().zip(visible.iter()) {
+            match visible {
+                true => { crit_data.on_count += 1; crit_data.sum_end_on += end_val; crit_data.sq_sum_end_on += end_val * end_val; }
+                false => { crit_data.off_count += 1; crit_data.sum_end_off += end_val; crit_data.sq_sum_end_off += end_val * end_val; }
+            }
+        }
+    }
+
+    /// Adds the end values of the two states of an antithetic pair, both already added as states
+    fn add_pair(&mut self, first: f64, second: f64) {
+        let pair_mean = (first + second) / 2.0;
+        self.pair_count += 1;
+        self.pair_sum += pair_mean;
+        self.pair_sq_sum += pair_mean * pair_mean;
+    }
+
+    /// Merges the sums of 'd2', which must be created from the same dynamic ids
     pub fn add(&mut self, d2: &GraphCritData){
+        debug_assert_eq!(self.ids, d2.ids);
         self.row_count += d2.row_count;
         self.end_op_sum += d2.end_op_sum;
-        for (id, crit_data) in self.node_data.iter_mut() {
-            crit_data.add(d2.node_data.index(id));
+        self.end_op_sq_sum += d2.end_op_sq_sum;
+        self.end_op_histogram.merge(&d2.end_op_histogram);
+        self.pair_count += d2.pair_count;
+        self.pair_sum += d2.pair_sum;
+        self.pair_sq_sum += d2.pair_sq_sum;
+        for (crit_data, other) in self.node_data.iter_mut().zip(d2.node_data.iter()) {
+            crit_data.add(other);
         }
     }
+
+    /// Converts the accumulated sums into the mean values and scores of every node
+    pub(crate) fn results(&self) -> CriticalityResults {
+        CriticalityResults {
+            row_count: self.row_count,
+            end_op_mean: mean(self.end_op_sum, self.row_count),
+            end_op_histogram: self.end_op_histogram.clone(),
+            nodes: self.ids.iter().zip(self.node_data.iter()).map(|(id, d)| (*id, d.result())).collect(),
+        }
+    }
+
+    /// Standard error of the mean end value and of the criticality of every node, estimated
+    /// from the spread of the sampled values. Unlike ['NodeCritResult::std_error'] it holds for
+    /// values outside of 0 and 1, such as the differences of ['delta']. The states of antithetic
+    /// pairs aren't independent, the error of the mean end value is then taken from the spread of
+    /// the means of the pairs.
+    pub(crate) fn std_errors(&self) -> (f64, NodeValueMap<f64>) {
+        let nodes = self.ids.iter().zip(self.node_data.iter())
+            .map(|(id, d)| {
+                let on = mean_variance(d.sum_end_on, d.sq_sum_end_on, d.on_count);
+                let off = mean_variance(d.sum_end_off, d.sq_sum_end_off, d.off_count);
+                (*id, (on + off).sqrt())
+            })
+            .collect();
+        let end_op_variance = match self.pair_count {
+            0 => mean_variance(self.end_op_sum, self.end_op_sq_sum, self.row_count),
+            pairs => mean_variance(self.pair_sum, self.pair_sq_sum, pairs),
+        };
+        (end_op_variance.sqrt(), nodes)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 struct NodeCritData {
+    on_count: u64,
+    off_count: u64,
     sum_end_on: f64,
     sum_end_off: f64,
+    sq_sum_end_on: f64,
+    sq_sum_end_off: f64,
 }
 
 impl NodeCritData {
     pub fn add(&mut self, d2: &NodeCritData){
+        self.on_count += d2.on_count;
+        self.off_count += d2.off_count;
         self.sum_end_on += d2.sum_end_on;
         self.sum_end_off += d2.sum_end_off;
+        self.sq_sum_end_on += d2.sq_sum_end_on;
+        self.sq_sum_end_off += d2.sq_sum_end_off;
     }
-}
\ No newline at end of file
+
+    fn result(&self) -> NodeCritResult {
+        let mean_end_on = mean(self.sum_end_on, self.on_count);
+        let mean_end_off = mean(self.sum_end_off, self.off_count);
+        NodeCritResult {
+            on_count: self.on_count,
+            off_count: self.off_count,
+            mean_end_on,
+            mean_end_off,
+            criticality: mean_end_on - mean_end_off,
+        }
+    }
+}
+
+fn mean(sum: f64, count: u64) -> f64 {
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+/// Variance of the mean of 'count' values from their sum and the sum of their squares, infinite
+/// for fewer than two values
+fn mean_variance(sum: f64, sq_sum: f64, count: u64) -> f64 {
+    if count < 2 {
+        return f64::INFINITY;
+    }
+    let n = count as f64;
+    ((sq_sum - sum * sum / n) / (n - 1.0)).max(0.0) / n
+}
+
+/// Final results of a criticality analysis
+#[derive(Debug, Clone)]
+pub struct CriticalityResults {
+    /// Number of unique states that were rolled up
+    pub row_count: u64,
+    /// Mean operability of the end node over all states
+    pub end_op_mean: f64,
+    /// Distribution of the end node value over all states, empty if the states weren't sampled
+    pub end_op_histogram: Histogram,
+    pub nodes: NodeValueMap<NodeCritResult>,
+}
+
+/// Criticality values of a single dynamic node
+#[derive(Debug, Clone)]
+pub struct NodeCritResult {
+    /// Number of states where the node was visible
+    pub on_count: u64,
+    /// Number of states where the node was not visible
+    pub off_count: u64,
+    /// Mean operability of the end node while the node is visible
+    pub mean_end_on: f64,
+    /// Mean operability of the end node while the node is not visible
+    pub mean_end_off: f64,
+    /// Difference between the mean end operability while on and while off
+    pub criticality: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::error::Error;
+    use crate::analyses::{Analysis, AnalysisContext};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use crate::analyses::criticality::{AnalysisGraph, CriticalityResults, GraphCritData, ITERATION_BATCH, RollUpEvaluator, sample_states_many, StateEvaluator};
+    use crate::analyses::criticality::loop_condition::MaxLoopCondition;
+    use crate::analyses::criticality::incremental::IncrementalRollUpEvaluator;
+    use crate::analyses::criticality::builder::CriticalityBuilder;
+    use crate::analyses::criticality::vis_gen::visibility_states_gen::{AntitheticGen, GrayCodeGen, RandomGen, seeded_rng, VisGen};
+    use crate::metrics::Metrics;
+    use crate::network::{CsrLinks, Graph, NodeValueMap};
+    use crate::output::Output;
+    use crate::roll_up::OrRule;
+
+    /// Writes nothing, so the test doesn't print
+    struct NoOutput;
+
+    impl Output for NoOutput {
+        fn write(&self, _graph: &Graph, _results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+    }
+
+    /// start -> a -> end and start -> b -> end, the end node is operable while a or b is
+    fn parallel() -> Graph {
+        let mut graph = Graph::new();
+        for (name, id) in [("start", 0), ("a", 1), ("b", 2), ("end", 3)] {
+            graph.add_node(name.to_string(), id);
+        }
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    #[test]
+    fn criticality_compares_the_end_value_while_on_and_off() {
+        let dynamic_ids: HashSet<u32> = HashSet::from([1, 2]);
+        let results = CriticalityBuilder::new(parallel())
+            .start_id(0)
+            .end_id(3)
+            .dynamic_ids(dynamic_ids.clone())
+            .vis_gen(Box::new(GrayCodeGen::new(&dynamic_ids).unwrap()))
+            .samples(4)
+            .threads(1)
+            .output(Box::new(NoOutput))
+            .build().unwrap()
+            .run(&AnalysisContext::default()).unwrap();
+        assert_eq!(results.row_count, 4);
+        assert_eq!(results.end_op_mean, 0.75);
+        for id in [1, 2] {
+            let node = &results.nodes[&id];
+            assert_eq!((node.on_count, node.off_count), (2, 2));
+            // The end node is operable in both states with the node on, in one with it off. The
+            // value of the node itself would give a criticality of 1.
+            assert_eq!((node.mean_end_on, node.mean_end_off, node.criticality), (1.0, 0.5, 0.5));
+        }
+    }
+
+    #[test]
+    fn a_node_off_the_path_to_the_end_is_not_critical() {
+        // start -> c is a dead end, c is operable exactly while it is visible
+        let mut graph = parallel();
+        graph.add_node("c".to_string(), 4);
+        graph.add_edge(0, 4);
+        let dynamic_ids: HashSet<u32> = HashSet::from([1, 2, 4]);
+        let results = CriticalityBuilder::new(graph)
+            .start_id(0)
+            .end_id(3)
+            .dynamic_ids(dynamic_ids.clone())
+            .vis_gen(Box::new(GrayCodeGen::new(&dynamic_ids).unwrap()))
+            .samples(8)
+            .threads(1)
+            .output(Box::new(NoOutput))
+            .build().unwrap()
+            .run(&AnalysisContext::default()).unwrap();
+        assert_eq!(results.row_count, 8);
+        let c = &results.nodes[&4];
+        assert_eq!((c.on_count, c.off_count), (4, 4));
+        // Averaging the rolled up value of c itself gave 1 - 0 here
+        assert_eq!((c.mean_end_on, c.mean_end_off, c.criticality), (0.75, 0.75, 0.0));
+        assert_eq!(results.nodes[&1].criticality, 0.5);
+    }
+
+    #[test]
+    fn enumerated_states_are_weighted_by_their_probability() {
+        let dynamic_ids: HashSet<u32> = HashSet::from([1, 2]);
+        let off_chances = NodeValueMap::from([(1, 0.9), (2, 0.9)]);
+        let random = RandomGen { rng: seeded_rng(Some(0)), ids: dynamic_ids.clone(), off_chances };
+        let results = CriticalityBuilder::new(parallel())
+            .start_id(0)
+            .end_id(3)
+            .dynamic_ids(dynamic_ids)
+            .vis_gen(Box::new(random))
+            .samples(1)
+            .threads(1)
+            .enumerate(true)
+            .output(Box::new(NoOutput))
+            .build().unwrap()
+            .run(&AnalysisContext::default()).unwrap();
+        assert_eq!(results.row_count, 4);
+        // The end node is down only while both nodes are, unweighted that is one state in four
+        assert!((results.end_op_mean - 0.19).abs() < 1e-6);
+        for id in [1, 2] {
+            assert!((results.nodes[&id].criticality - 0.9).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn evaluators_reusing_their_buffers_roll_up_like_a_fresh_roll_up() {
+        let graph = parallel();
+        let l_map = graph.links_map();
+        let links = CsrLinks::new(&l_map, &Graph::get_bfs_path(&l_map, 0));
+        let analysis = Arc::new(AnalysisGraph { graph: graph.clone(), links: links.clone() });
+        let mut full = RollUpEvaluator::new(analysis, Box::new(OrRule {}), vec![(3, 1.0)]);
+        let mut incremental = IncrementalRollUpEvaluator::new(full.clone());
+        for (a, b) in [(1, 1), (0, 1), (0, 0), (1, 0), (1, 1)] {
+            let state = NodeValueMap::from([(1, a), (2, b)]);
+            let fresh = *graph.roll_up_state(&links, &OrRule {}, &state).get(&3).unwrap_or(&0.0) as f64;
+            assert_eq!(full.evaluate(&state), fresh);
+            assert_eq!(incremental.evaluate(&state), fresh);
+        }
+    }
+
+    /// start -> a -> c -> end and start -> b -> d -> end, with a shortcut from a to d
+    fn ladder() -> Graph {
+        let mut graph = Graph::new();
+        for (name, id) in [("start", 0), ("a", 1), ("b", 2), ("c", 3), ("d", 4), ("end", 5)] {
+            graph.add_node(name.to_string(), id);
+        }
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 4), (1, 4), (3, 5), (4, 5)] {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    #[test]
+    fn seeded_results_are_pinned_and_match_fresh_draws_and_roll_ups() {
+        let dynamic_ids: HashSet<u32> = (1..5).collect();
+        let off_chances = NodeValueMap::from([(1, 0.3), (2, 0.6), (3, 0.2), (4, 0.5)]);
+        let random = RandomGen { rng: seeded_rng(Some(1396)), ids: dynamic_ids.clone(), off_chances };
+        let results = CriticalityBuilder::new(ladder())
+            .start_id(0)
+            .end_id(5)
+            .dynamic_ids(dynamic_ids)
+            .vis_gen(Box::new(random.clone()))
+            .samples(12)
+            .threads(1)
+            .output(Box::new(NoOutput))
+            .build().unwrap()
+            .run(&AnalysisContext::default()).unwrap();
+        assert_eq!((results.row_count, results.end_op_mean), (8, 0.5));
+        let pinned = [(1, 4, 4, 0.5), (2, 3, 5, 2.0 / 3.0 - 0.4), (3, 7, 1, 4.0 / 7.0), (4, 4, 4, 0.5)];
+        for (id, on_count, off_count, criticality) in pinned {
+            let node = &results.nodes[&id];
+            assert_eq!((node.on_count, node.off_count), (on_count, off_count));
+            assert!((node.criticality - criticality).abs() < 1e-12);
+        }
+        // The states of the one thread drawn into new maps and rolled up from scratch, without
+        // any buffer reuse
+        let graph = ladder();
+        let l_map = graph.links_map();
+        let links = CsrLinks::new(&l_map, &Graph::get_bfs_path(&l_map, 0));
+        let mut random = random.split_to_threads(1).pop().unwrap();
+        let mut seen = HashSet::new();
+        let (mut end_sum, mut on) = (0.0, NodeValueMap::<(u64, f64)>::new());
+        for _ in 0..12 {
+            let state = random.next_states();
+            if !seen.insert(state.clone()) {
+                continue
+            }
+            let end = *graph.roll_up_state(&links, &OrRule {}, &state).get(&5).unwrap_or(&0.0) as f64;
+            end_sum += end;
+            for (id, visibility) in &state {
+                let entry = on.entry(*id).or_default();
+                if *visibility == 1 {
+                    *entry = (entry.0 + 1, entry.1 + end);
+                }
+            }
+        }
+        assert_eq!(seen.len() as u64, results.row_count);
+        assert_eq!(end_sum / seen.len() as f64, results.end_op_mean);
+        for (id, (on_count, on_sum)) in on {
+            let node = &results.nodes[&id];
+            assert_eq!(node.on_count, on_count);
+            assert_eq!(node.mean_end_on, on_sum / on_count as f64);
+        }
+    }
+
+    fn antithetic(graph: Graph, end_id: u32, dynamic_ids: HashSet<u32>, samples: u64) -> CriticalityResults {
+        let random = RandomGen { rng: seeded_rng(Some(7)), ids: dynamic_ids.clone(), off_chances: NodeValueMap::new() };
+        CriticalityBuilder::new(graph)
+            .start_id(0)
+            .end_id(end_id)
+            .dynamic_ids(dynamic_ids)
+            .vis_gen(Box::new(AntitheticGen::new(random)))
+            .samples(samples)
+            .threads(1)
+            .output(Box::new(NoOutput))
+            .build().unwrap()
+            .run(&AnalysisContext::default()).unwrap()
+    }
+
+    #[test]
+    fn both_states_of_an_antithetic_pair_are_samples() {
+        // Four states, the complements are skipped once seen like the sampled states
+        let results = antithetic(parallel(), 3, HashSet::from([1, 2]), 64);
+        assert_eq!(results.row_count, 4);
+        assert_eq!(results.end_op_mean, 0.75);
+        // start -> n -> end for 20 nodes, every state is new and each one is a sample
+        let mut wide = Graph::new();
+        wide.add_node("start".to_string(), 0);
+        wide.add_node("end".to_string(), 1);
+        for id in 2..22 {
+            wide.add_node(format!("n{}", id), id);
+            wide.add_edge(0, id);
+            wide.add_edge(id, 1);
+        }
+        let results = antithetic(wide, 1, (2..22).collect(), 10);
+        assert_eq!(results.row_count, 10);
+    }
+
+    #[test]
+    fn the_error_of_paired_states_comes_from_the_means_of_the_pairs() {
+        let mut data = GraphCritData::new(&HashSet::from([1]));
+        for (first, second) in [(1.0, 0.0), (0.0, 1.0), (1.0, 0.0), (0.0, 1.0)] {
+            data.add_state(&[true], first);
+            data.add_state(&[false], second);
+            data.add_pair(first, second);
+        }
+        // Every pair has the mean 0.5, while the states alone spread from 0 to 1
+        assert_eq!(data.std_errors().0, 0.0);
+        data.pair_count = 0;
+        assert!(data.std_errors().0 > 0.1);
+    }
+
+    /// Keeps the states sampled according to the metrics when it evaluates a state
+    #[derive(Clone)]
+    struct WatchingMetrics {
+        metrics: Arc<Metrics>,
+        seen: Arc<AtomicU64>,
+    }
+
+    impl StateEvaluator for WatchingMetrics {
+        fn evaluate(&mut self, _state: &NodeValueMap<u8>) -> f64 {
+            let sampled = self.metrics.render().lines()
+                .find_map(|line| line.strip_prefix("thor_states_sampled_total "))
+                .and_then(|value| value.parse::<f64>().ok())
+                .unwrap();
+            self.seen.store(sampled as u64, Ordering::Relaxed);
+            1.0
+        }
+    }
+
+    #[test]
+    fn the_metrics_count_the_states_while_the_workers_run() {
+        let dynamic_ids: HashSet<u32> = (0..12).collect();
+        let metrics = Arc::new(Metrics::new());
+        let seen = Arc::new(AtomicU64::new(0));
+        let evaluator: Box<dyn StateEvaluator> = Box::new(WatchingMetrics { metrics: metrics.clone(), seen: seen.clone() });
+        let ctx = AnalysisContext { metrics: Some(metrics), ..Default::default() };
+        let samples = 4 * ITERATION_BATCH;
+        sample_states_many(1, &dynamic_ids, &GrayCodeGen::new(&dynamic_ids).unwrap(),
+                           &MaxLoopCondition { max: samples, index: 0 }, &[evaluator], &ctx).unwrap();
+        // The last state was evaluated before the worker finished
+        assert_eq!(seen.load(Ordering::Relaxed), samples);
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use log::{info, warn};
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::errors::analysis::ThorError;
+use crate::analyses::criticality::{AnalysisGraph, CriticalityResults, RollUpEvaluator, sample_states_many, StateEvaluator};
+use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::network::{CsrLinks, Graph, Links, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// Criticality of every dynamic node with respect to several (source, sink) pairs. All pairs are
+/// evaluated on the same sampled states, so the scores of different pairs are comparable.
+pub struct PairwiseCriticality {
+    pub threads: u8,
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    pub vis_gen: Box<dyn VisGen>,
+    pub loop_condition: Box<dyn CritLoopCondition>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    /// (source, sink) pairs, the operability of the sink is rolled up from the source only
+    pub pairs: Vec<(u32, u32)>,
+}
+
+/// Criticality results of every pair, in the order of the pairs
+#[derive(Debug, Clone)]
+pub struct PairwiseResults {
+    pub pairs: Vec<(u32, u32)>,
+    pub results: Vec<CriticalityResults>,
+}
+
+impl PairwiseResults {
+    /// Criticality of every node for each pair, in the order of the pairs
+    pub fn matrix(&self) -> NodeValueMap<Vec<f64>> {
+        let mut matrix: NodeValueMap<Vec<f64>> = NodeValueMap::new();
+        for results in self.results.iter() {
+            for (id, node) in results.nodes.iter() {
+                matrix.entry(*id).or_default().push(node.criticality);
+            }
+        }
+        matrix
+    }
+
+    /// Prints the matrix as csv, one row per node and one column per pair
+    pub fn print(&self, graph: &Graph) {
+        let header: Vec<String> = self.pairs.iter().map(|(source, sink)| format!("{}->{}", source, sink)).collect();
+        println!("node,{}", header.join(","));
+        for (id, row) in self.matrix().iter() {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            let row: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+            println!("{} ({}),{}", name, id, row.join(","));
+        }
+    }
+}
+
+/// Evaluates every state as zero, used for pairs whose sink can't be reached from the source
+#[derive(Clone)]
+struct DisconnectedEvaluator {}
+
+impl StateEvaluator for DisconnectedEvaluator {
+    fn evaluate(&mut self, _visibility_state: &NodeValueMap<u8>) -> f64 {
+        0.0
+    }
+}
+
+/// Part of 'graph' lying on a path from 'source' to 'sink'. Edges into the source are dropped so
+/// the source is the only node without children.
+fn pair_subgraph(graph: &Graph, source: u32, sink: u32) -> Graph {
+    let l_map = graph.links_map();
+    let downstream: HashSet<u32> = Graph::get_bfs_path(&l_map, source).into_iter().collect();
+    let mut upstream: HashSet<u32> = HashSet::from([sink]);
+    let mut agenda: VecDeque<u32> = VecDeque::from([sink]);
+    while let Some(current) = agenda.pop_front() {
+        for child in l_map.children_of(&current) {
+            if upstream.insert(*child) {
+                agenda.push_back(*child);
+            }
+        }
+    }
+
+    let mut subgraph = Graph::new();
+    for id in downstream.intersection(&upstream) {
+        if let Some(node) = graph.get_node(id) {
+            subgraph.add_node(node.name.to_string(), *id);
+        }
+    }
+    for edge in graph.get_edges() {
+        let inside = subgraph.get_node(&edge.from).is_some() && subgraph.get_node(&edge.to).is_some();
+        if inside && edge.to != source {
+            subgraph.add_edge(edge.from, edge.to);
+        }
+    }
+    subgraph
+}
+
+impl PairwiseCriticality {
+    fn evaluator(&self, source: u32, sink: u32) -> Box<dyn StateEvaluator> {
+        let subgraph = pair_subgraph(&self.graph, source, sink);
+        let l_map = subgraph.links_map();
+        let path = Graph::get_bfs_path(&l_map, source);
+        if !path.contains(&sink) || source == sink {
+            warn!("Node {} can't be reached from node {}, its criticalities are zero", sink, source);
+            return Box::new(DisconnectedEvaluator {});
+        }
+        Box::new(RollUpEvaluator::new(
+            Arc::new(AnalysisGraph { graph: subgraph, links: CsrLinks::new(&l_map, &path) }),
+            dyn_clone::clone_box(&*self.roll_up_rule),
+            vec![(sink, 1.0)],
+        ))
+    }
+}
+
+impl Analysis for PairwiseCriticality {
+    type Output = PairwiseResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<PairwiseResults, ThorError> {
+        info!("Starting Pairwise Criticality Analysis");
+        let evaluators: Vec<Box<dyn StateEvaluator>> = self.pairs.iter()
+            .map(|(source, sink)| self.evaluator(*source, *sink))
+            .collect();
+        let data = sample_states_many(self.threads, &self.dynamic_ids, self.vis_gen.as_ref(),
+                                      self.loop_condition.as_ref(), &evaluators, ctx)?;
+        Ok(PairwiseResults {
+            pairs: self.pairs.clone(),
+            results: data.iter().map(|d| d.results()).collect(),
+        })
+    }
+}
@@ -0,0 +1,112 @@
+//! Writing the results of a sampling run while it is still running.
+//!
+//! Every sampling thread sends the sums of its states to the main thread after each
+//! ['PartialResults::every'] states. The main thread merges the latest sums of all threads and
+//! replaces the file at ['PartialResults::path'] with the results so far as json: the number of
+//! 'states', the 'end_op_mean' and the 'nodes' from the most to the least critical. With a
+//! ['PartialResults::curve_path'] a line is also appended to a csv file for every update, with
+//! the number of states, the mean end operability and the criticality of every node in the order
+//! of the ids of its header, so the convergence of the values can be plotted.
+//!
+//! A ['PartialResults::listener'] is sent '{"type": "partial", "data": ...}' with the same json
+//! on every update, and the progress estimates of ['crate::analyses::criticality::throughput']
+//! with the 'type' 'progress', so a service can stream them while the analysis runs.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::Sender;
+use log::warn;
+use crate::analyses::criticality::GraphCritData;
+use crate::analyses::criticality::ranking::rank;
+use crate::json::JsonValue;
+use crate::output::write_output;
+
+/// States every thread samples between two updates if no interval is given
+pub const DEFAULT_PARTIAL_EVERY: u64 = 10_000;
+
+/// Where and how often the results of a running analysis are written
+#[derive(Debug, Clone)]
+pub struct PartialResults {
+    /// States every thread samples between two updates
+    pub every: u64,
+    /// File replaced with the results so far on every update
+    pub path: Option<String>,
+    /// File a line is appended to on every update
+    pub curve_path: Option<String>,
+    /// Channel sent every update and progress estimate, see the module documentation
+    pub listener: Option<Sender<JsonValue>>,
+}
+
+/// Merges the sums sent by the threads and writes them
+pub(crate) struct PartialWriter {
+    config: PartialResults,
+    /// Latest sums of every thread
+    latest: Vec<Option<GraphCritData>>,
+    curve: Option<BufWriter<File>>,
+}
+
+impl PartialWriter {
+    pub fn new(config: PartialResults, threads: u8) -> PartialWriter {
+        PartialWriter { config, latest: (0..threads).map(|_| None).collect(), curve: None }
+    }
+
+    /// Replaces the sums of the 'thread' and, if 'write' is set, writes the merged results
+    pub fn update(&mut self, thread: usize, data: GraphCritData, write: bool) {
+        self.latest[thread] = Some(data);
+        if !write {
+            return;
+        }
+        if let Err(e) = self.write() {
+            warn!("Failed to write the partial results: {}", e);
+        }
+    }
+
+    /// Sends the 'fields' to the listener, if any, with their 'kind' as their 'type'
+    pub fn notify(&self, kind: &str, fields: JsonValue) {
+        if let Some(listener) = &self.config.listener {
+            // A listener that hung up no longer wants the updates, the analysis goes on
+            let _ = listener.send(JsonValue::object().with("type", kind).with("data", fields));
+        }
+    }
+
+    fn write(&mut self) -> std::io::Result<()> {
+        let mut latest = self.latest.iter().flatten();
+        let Some(first) = latest.next() else { return Ok(()) };
+        let mut merged = first.clone();
+        for data in latest {
+            merged.add(data);
+        }
+        let results = merged.results();
+        let nodes: Vec<JsonValue> = rank(&results).into_iter()
+            .map(|(id, node)| JsonValue::object()
+                .with("id", id)
+                .with("criticality", node.criticality)
+                .with("mean_end_on", node.mean_end_on)
+                .with("mean_end_off", node.mean_end_off)
+                .with("on_count", node.on_count)
+                .with("off_count", node.off_count))
+            .collect();
+        let json = JsonValue::object()
+            .with("states", results.row_count)
+            .with("end_op_mean", results.end_op_mean)
+            .with("nodes", nodes);
+        if let Some(path) = &self.config.path {
+            write_output(path, json.to_pretty_string() + "\n")?;
+        }
+        self.notify("partial", json);
+
+        let Some(curve_path) = &self.config.curve_path else { return Ok(()) };
+        if self.curve.is_none() {
+            let mut curve = BufWriter::new(File::create(curve_path)?);
+            let ids: Vec<String> = merged.ids.iter().map(|id| id.to_string()).collect();
+            writeln!(curve, "states,end_op_mean,{}", ids.join(","))?;
+            self.curve = Some(curve);
+        }
+        if let Some(curve) = self.curve.as_mut() {
+            let values: Vec<String> = results.nodes.values().map(|node| node.criticality.to_string()).collect();
+            writeln!(curve, "{},{},{}", results.row_count, results.end_op_mean, values.join(","))?;
+            curve.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+//! Ranking the nodes of criticality results and summarizing the spread of their criticality, so
+//! the results of large graphs can be reported as their most critical nodes.
+
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult};
+
+/// Percentiles reported by ['CriticalitySummary']
+pub const SUMMARY_PERCENTILES: [f64; 5] = [10.0, 25.0, 50.0, 75.0, 90.0];
+
+/// Nodes of the results from the most to the least critical, ties ordered by id
+pub fn rank(results: &CriticalityResults) -> Vec<(u32, &NodeCritResult)> {
+    let mut ranked: Vec<(u32, &NodeCritResult)> = results.nodes.iter().map(|(id, node)| (*id, node)).collect();
+    ranked.sort_by(|a, b| b.1.criticality.total_cmp(&a.1.criticality).then(a.0.cmp(&b.0)));
+    ranked
+}
+
+impl CriticalityResults {
+    /// The results of only the 'n' most critical nodes, see ['rank']
+    pub fn top(&self, n: usize) -> CriticalityResults {
+        CriticalityResults {
+            row_count: self.row_count,
+            end_op_mean: self.end_op_mean,
+            end_op_histogram: self.end_op_histogram.clone(),
+            nodes: rank(self).into_iter().take(n).map(|(id, node)| (id, node.clone())).collect(),
+        }
+    }
+}
+
+/// Spread of the criticality over all nodes
+#[derive(Debug, Clone)]
+pub struct CriticalitySummary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// (percentile, criticality) for every one of ['SUMMARY_PERCENTILES']
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl CriticalitySummary {
+    /// Summarizes the results, None if they have no nodes
+    pub fn new(results: &CriticalityResults) -> Option<CriticalitySummary> {
+        let mut values: Vec<f64> = results.nodes.values().map(|n| n.criticality).collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        Some(CriticalitySummary {
+            count: values.len(),
+            min: values[0],
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            percentiles: SUMMARY_PERCENTILES.iter().map(|p| (*p, percentile(&values, *p))).collect(),
+        })
+    }
+}
+
+/// The 'p'th percentile of the sorted 'values', interpolating linearly between the closest ranks
+pub fn percentile(values: &[f64], p: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let position = (p / 100.0).clamp(0.0, 1.0) * (values.len() - 1) as f64;
+    let (low, high) = (position.floor() as usize, position.ceil() as usize);
+    values[low] + (values[high] - values[low]) * (position - low as f64)
+}
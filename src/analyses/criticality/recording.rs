@@ -0,0 +1,210 @@
+//! Recording the sampled visibility states to a file and replaying them.
+//!
+//! A recording starts with the lines 'thor-states 1', 'ids <id>,<id>,...' and 'paired 0|1',
+//! followed by one line per state: the index of the thread that drew it and the value of every
+//! node in the order of the ids, '-' for a node missing from the state. Replaying a recording
+//! with the thread count it was recorded with reproduces the results of the recorded run
+//! exactly, also after the generators changed.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use log::error;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::errors::input::RecordingError;
+use crate::network::NodeValueMap;
+
+const HEADER: &str = "thor-states 1";
+
+/// Writes every state of the wrapped generator to a recording, see the module documentation.
+/// The threads share the file, which is flushed once the last generator is dropped.
+pub struct RecordingGen {
+    pub inner: Box<dyn VisGen>,
+    ids: Arc<Vec<u32>>,
+    writer: Arc<Mutex<BufWriter<File>>>,
+    thread: usize,
+}
+
+impl Clone for RecordingGen {
+    fn clone(&self) -> Self {
+        RecordingGen {
+            inner: dyn_clone::clone_box(&*self.inner),
+            ids: self.ids.clone(),
+            writer: self.writer.clone(),
+            thread: self.thread,
+        }
+    }
+}
+
+impl RecordingGen {
+    /// Records the states of the 'inner' generator for the dynamic nodes 'ids' to 'path'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be created
+    pub fn new(inner: Box<dyn VisGen>, ids: &HashSet<u32>, path: &str) -> std::io::Result<RecordingGen> {
+        let mut ids: Vec<u32> = ids.iter().copied().collect();
+        ids.sort();
+        let mut writer = BufWriter::new(File::create(path)?);
+        let id_list: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        writeln!(writer, "{}\nids {}\npaired {}", HEADER, id_list.join(","), inner.paired() as u8)?;
+        Ok(RecordingGen { inner, ids: Arc::new(ids), writer: Arc::new(Mutex::new(writer)), thread: 0 })
+    }
+}
+
+impl VisGen for RecordingGen {
+    fn next_states(&mut self) -> NodeValueMap<u8> {
+        let states = self.inner.next_states();
+        let values: String = self.ids.iter()
+            .map(|id| match states.get(id) {
+                Some(value) => char::from_digit(*value as u32, 36).unwrap_or('?'),
+                None => '-',
+            })
+            .collect();
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{} {}", self.thread, values) {
+            error!("Failed to record a visibility state: {}", e);
+        }
+        states
+    }
+
+    fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+        self.inner.split_to_threads(threads).into_iter().enumerate()
+            .map(|(thread, inner)| Box::new(RecordingGen {
+                inner,
+                ids: self.ids.clone(),
+                writer: self.writer.clone(),
+                thread,
+            }) as Box<dyn VisGen>)
+            .collect()
+    }
+
+    fn paired(&self) -> bool {
+        self.inner.paired()
+    }
+
+    fn distinct(&self) -> bool {
+        self.inner.distinct()
+    }
+
+    fn incremental(&self) -> bool {
+        self.inner.incremental()
+    }
+
+    /// Unknown, so the states of a recording are always sampled and can be replayed, see
+    /// ['crate::analyses::criticality::exhaustive']
+    fn state_probability(&self, _state: &NodeValueMap<u8>) -> Option<f64> {
+        None
+    }
+}
+
+/// Feeds the states of a recording back in, see the module documentation. Every thread replays
+/// the states of the recorded thread with the same index; for another thread count the states
+/// are dealt out in recorded order. After its last state a thread starts over, the repeated
+/// states are skipped as visited.
+#[derive(Clone)]
+pub struct ReplayGen {
+    sequences: Arc<Vec<Vec<NodeValueMap<u8>>>>,
+    paired: bool,
+    thread: usize,
+    position: usize,
+}
+
+impl ReplayGen {
+    /// Reads the recording at 'path'
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['RecordingError'] if the file can't be read, is malformed or has no states
+    pub fn read(path: &str) -> Result<ReplayGen, RecordingError> {
+        let fail = |reason: String| RecordingError { path: path.to_string(), reason };
+        let file = File::open(path).map_err(|e| fail(e.to_string()))?;
+        let mut lines = BufReader::new(file).lines();
+        let mut next_line = || lines.next().transpose().map_err(|e| fail(e.to_string()));
+        if next_line()?.as_deref() != Some(HEADER) {
+            return Err(fail(format!("the first line must be '{}'", HEADER)));
+        }
+        let ids: Vec<u32> = match next_line()? {
+            Some(line) if line.starts_with("ids ") => line[4..].split(',')
+                .filter(|id| !id.is_empty())
+                .map(|id| id.parse::<u32>().map_err(|_| fail(format!("invalid node id '{}'", id))))
+                .collect::<Result<Vec<u32>, RecordingError>>()?,
+            _ => return Err(fail("the second line must list the ids".to_string())),
+        };
+        let paired = match next_line()?.as_deref() {
+            Some("paired 0") => false,
+            Some("paired 1") => true,
+            _ => return Err(fail("the third line must be 'paired 0' or 'paired 1'".to_string())),
+        };
+        let mut sequences: Vec<Vec<NodeValueMap<u8>>> = vec![];
+        let mut number = 3;
+        while let Some(line) = next_line()? {
+            number += 1;
+            let invalid = || fail(format!("line {} is not a recorded state", number));
+            let (thread, values) = line.split_once(' ').ok_or_else(invalid)?;
+            let thread = thread.parse::<usize>().map_err(|_| invalid())?;
+            if values.chars().count() != ids.len() {
+                return Err(invalid());
+            }
+            let mut states = NodeValueMap::new();
+            for (id, value) in ids.iter().zip(values.chars()) {
+                if value != '-' {
+                    states.insert(*id, value.to_digit(36).ok_or_else(invalid)? as u8);
+                }
+            }
+            if sequences.len() <= thread {
+                sequences.resize(thread + 1, vec![]);
+            }
+            sequences[thread].push(states);
+        }
+        if sequences.iter().all(|s| s.is_empty()) {
+            return Err(fail("it has no states".to_string()));
+        }
+        Ok(ReplayGen { sequences: Arc::new(sequences), paired, thread: 0, position: 0 })
+    }
+
+    /// Number of recorded states
+    pub fn len(&self) -> usize {
+        self.sequences.iter().map(|s| s.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl VisGen for ReplayGen {
+    fn next_states(&mut self) -> NodeValueMap<u8> {
+        let sequence = match self.sequences[self.thread].is_empty() {
+            true => self.sequences.iter().find(|s| !s.is_empty()).unwrap(),
+            false => &self.sequences[self.thread],
+        };
+        let states = sequence[self.position % sequence.len()].clone();
+        self.position += 1;
+        states
+    }
+
+    fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+        let threads = threads.max(1) as usize;
+        let sequences = match threads == self.sequences.len() {
+            true => self.sequences.clone(),
+            false => {
+                let states: Vec<NodeValueMap<u8>> = self.sequences.iter().flatten().cloned().collect();
+                // Pairs stay on the same thread
+                let step = if self.paired { 2 } else { 1 };
+                let chunk = states.len().div_ceil(threads * step).max(1) * step;
+                let mut dealt: Vec<Vec<NodeValueMap<u8>>> = states.chunks(chunk).map(|c| c.to_vec()).collect();
+                dealt.resize(threads, vec![]);
+                Arc::new(dealt)
+            }
+        };
+        (0..threads)
+            .map(|thread| Box::new(ReplayGen { sequences: sequences.clone(), paired: self.paired, thread, position: 0 }) as Box<dyn VisGen>)
+            .collect()
+    }
+
+    fn paired(&self) -> bool {
+        self.paired
+    }
+}
@@ -0,0 +1,227 @@
+//! Logging the evaluated states with their end node value, and aggregating the log again.
+//!
+//! Unlike a recording of the drawn states, see ['crate::analyses::criticality::recording'], a
+//! sample log holds exactly the states that were added to the results, each with the value the
+//! roll up gave the end node. Any metric over the states can then be computed from the log of an
+//! expensive run without rolling up the graph again.
+//!
+//! A log starts with the lines 'thor-samples 1' and 'ids <id>,<id>,...', followed by one line
+//! per evaluated state: the value of every node in the order of the ids, '-' for a node missing
+//! from the state, and the end node value.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use log::error;
+use crate::analyses::VISIBLE_VAL;
+use crate::analyses::criticality::{mean, CriticalityResults, GraphCritData, StateEvaluator};
+use crate::analyses::criticality::ranking::percentile;
+use crate::errors::input::SampleLogError;
+use crate::network::NodeValueMap;
+
+const HEADER: &str = "thor-samples 1";
+
+/// Writes every state evaluated by the wrapped evaluator to a sample log, see the module
+/// documentation. The threads share the file, which is flushed once the last clone is dropped.
+pub struct SampleLogEvaluator {
+    pub inner: Box<dyn StateEvaluator>,
+    ids: Arc<Vec<u32>>,
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl Clone for SampleLogEvaluator {
+    fn clone(&self) -> Self {
+        SampleLogEvaluator {
+            inner: dyn_clone::clone_box(&*self.inner),
+            ids: self.ids.clone(),
+            writer: self.writer.clone(),
+        }
+    }
+}
+
+impl SampleLogEvaluator {
+    /// Logs the states of the dynamic nodes 'ids' evaluated by 'inner' to 'path'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be created
+    pub fn new(inner: Box<dyn StateEvaluator>, ids: &HashSet<u32>, path: &str) -> std::io::Result<SampleLogEvaluator> {
+        let mut ids: Vec<u32> = ids.iter().copied().collect();
+        ids.sort();
+        let mut writer = BufWriter::new(File::create(path)?);
+        let id_list: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        writeln!(writer, "{}\nids {}", HEADER, id_list.join(","))?;
+        Ok(SampleLogEvaluator { inner, ids: Arc::new(ids), writer: Arc::new(Mutex::new(writer)) })
+    }
+}
+
+impl StateEvaluator for SampleLogEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let end_val = self.inner.evaluate(visibility_state);
+        let values: String = self.ids.iter()
+            .map(|id| match visibility_state.get(id) {
+                Some(value) => char::from_digit(*value as u32, 36).unwrap_or('?'),
+                None => '-',
+            })
+            .collect();
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{} {}", values, end_val) {
+            error!("Failed to log a sampled state: {}", e);
+        }
+        end_val
+    }
+}
+
+/// The states of a sample log, see the module documentation
+#[derive(Debug, Clone)]
+pub struct SampleLog {
+    /// Dynamic node ids in ascending order
+    pub ids: Vec<u32>,
+    /// Whether node 'ids[i]' was visible in a state, for every state. Nodes missing from a state
+    /// count as visible, as in the sampling.
+    pub visible: Vec<Vec<bool>>,
+    /// End node value of every state
+    pub end_values: Vec<f64>,
+}
+
+/// Importance measures of a single node, estimated from the logged states
+#[derive(Debug, Clone)]
+pub struct NodeImportance {
+    pub id: u32,
+    /// Mean end value while the node is visible minus while it isn't, the criticality
+    pub birnbaum: f64,
+    /// Factor the unreliability of the end node grows by while the node isn't visible, None if
+    /// the end node never failed
+    pub risk_achievement: Option<f64>,
+    /// Factor the unreliability of the end node shrinks by while the node is visible, None if
+    /// the end node never failed while it was
+    pub risk_reduction: Option<f64>,
+}
+
+/// Named group of nodes, see ['SampleLog::groups']
+#[derive(Debug, Clone)]
+pub struct NodeGroup {
+    pub name: String,
+    pub members: Vec<u32>,
+}
+
+/// Effect of a group of nodes, which is off in a state if any of its members is
+#[derive(Debug, Clone)]
+pub struct GroupResult {
+    pub name: String,
+    /// States where every member was visible
+    pub on_count: u64,
+    /// States where a member wasn't visible
+    pub off_count: u64,
+    pub mean_end_on: f64,
+    pub mean_end_off: f64,
+    /// Difference between the mean end value while on and while off
+    pub criticality: f64,
+}
+
+impl SampleLog {
+    /// Reads the sample log at 'path'
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['SampleLogError'] if the file can't be read, is malformed or has no states
+    pub fn read(path: &str) -> Result<SampleLog, SampleLogError> {
+        let fail = |reason: String| SampleLogError { path: path.to_string(), reason };
+        let file = File::open(path).map_err(|e| fail(e.to_string()))?;
+        let mut lines = BufReader::new(file).lines();
+        let mut next_line = || lines.next().transpose().map_err(|e| fail(e.to_string()));
+        if next_line()?.as_deref() != Some(HEADER) {
+            return Err(fail(format!("the first line must be '{}'", HEADER)));
+        }
+        let ids: Vec<u32> = match next_line()? {
+            Some(line) if line.starts_with("ids ") => line[4..].split(',')
+                .filter(|id| !id.is_empty())
+                .map(|id| id.parse::<u32>().map_err(|_| fail(format!("invalid node id '{}'", id))))
+                .collect::<Result<Vec<u32>, SampleLogError>>()?,
+            _ => return Err(fail("the second line must list the ids".to_string())),
+        };
+        let mut log = SampleLog { ids, visible: vec![], end_values: vec![] };
+        let mut number = 2;
+        while let Some(line) = next_line()? {
+            number += 1;
+            let invalid = || fail(format!("line {} is not a logged state", number));
+            let (values, end_val) = line.rsplit_once(' ').ok_or_else(invalid)?;
+            if values.chars().count() != log.ids.len() {
+                return Err(invalid());
+            }
+            let visible = values.chars()
+                .map(|value| match value {
+                    '-' => Some(true),
+                    value => value.to_digit(36).map(|v| v == VISIBLE_VAL as u32),
+                })
+                .collect::<Option<Vec<bool>>>()
+                .ok_or_else(invalid)?;
+            log.visible.push(visible);
+            log.end_values.push(end_val.parse::<f64>().map_err(|_| invalid())?);
+        }
+        if log.end_values.is_empty() {
+            return Err(fail("it has no states".to_string()));
+        }
+        Ok(log)
+    }
+
+    /// The criticality results of the logged states, those of the logged run up to the rounding
+    /// of the sums
+    pub fn criticality(&self) -> CriticalityResults {
+        let mut data = GraphCritData::new(&self.ids.iter().copied().collect());
+        for (visible, end_val) in self.visible.iter().zip(self.end_values.iter()) {
+            data.add_state(visible, *end_val);
+        }
+        data.results()
+    }
+
+    /// Importance measures of every node, in the order of the ids. The unreliability of the end
+    /// node is one minus its mean value.
+    pub fn importance(&self) -> Vec<NodeImportance> {
+        let results = self.criticality();
+        let unreliability = 1.0 - results.end_op_mean;
+        let ratio = |a: f64, b: f64| if b > 0.0 { Some(a / b) } else { None };
+        self.ids.iter()
+            .filter_map(|id| results.nodes.get(id).map(|node| NodeImportance {
+                id: *id,
+                birnbaum: node.criticality,
+                risk_achievement: ratio(1.0 - node.mean_end_off, unreliability),
+                risk_reduction: ratio(unreliability, 1.0 - node.mean_end_on),
+            }))
+            .collect()
+    }
+
+    /// (percentile, end value) for each of the 'percentiles', computed from the exact values
+    pub fn quantiles(&self, percentiles: &[f64]) -> Vec<(f64, f64)> {
+        let mut values = self.end_values.clone();
+        values.sort_by(f64::total_cmp);
+        percentiles.iter().map(|p| (*p, percentile(&values, *p))).collect()
+    }
+
+    /// Effect of every group of nodes. Members that aren't dynamic nodes of the log are never
+    /// off.
+    pub fn groups(&self, groups: &[NodeGroup]) -> Vec<GroupResult> {
+        groups.iter()
+            .map(|group| {
+                let indexes: Vec<usize> = group.members.iter().filter_map(|id| self.ids.binary_search(id).ok()).collect();
+                let (mut on_count, mut off_count, mut sum_on, mut sum_off) = (0, 0, 0.0, 0.0);
+                for (visible, end_val) in self.visible.iter().zip(self.end_values.iter()) {
+                    match indexes.iter().all(|i| visible[*i]) {
+                        true => { on_count += 1; sum_on += end_val; }
+                        false => { off_count += 1; sum_off += end_val; }
+                    }
+                }
+                let (mean_end_on, mean_end_off) = (mean(sum_on, on_count), mean(sum_off, off_count));
+                GroupResult {
+                    name: group.name.clone(),
+                    on_count,
+                    off_count,
+                    mean_end_on,
+                    mean_end_off,
+                    criticality: mean_end_on - mean_end_off,
+                }
+            })
+            .collect()
+    }
+}
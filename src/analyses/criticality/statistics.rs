@@ -0,0 +1,81 @@
+//! Association between the state of every node and the end node value over logged states.
+//!
+//! The criticality compares the mean end value of the states where a node is on and off. The
+//! statistics here measure the association without assuming that only the means differ:
+//!
+//! * the correlation of the state of the node, one if it is visible, with the end node value,
+//!   the point-biserial correlation. It is the criticality scaled by the spread of both, so it
+//!   also tells how much of the variation of the end value follows the node.
+//! * the mutual information of the state of the node and the end node value in bits, with the
+//!   end values counted in the bins of the ['crate::analyses::criticality::histogram'], values
+//!   outside of them in the first or last bin. It also picks up nodes that change how the end
+//!   value spreads rather than its mean.
+//!
+//! Both are computed from a ['SampleLog'], which holds the state of every node in every state.
+
+use crate::analyses::criticality::histogram::{bin_of, HISTOGRAM_BINS};
+use crate::analyses::criticality::samples::SampleLog;
+
+/// Association of a single node with the end node value
+#[derive(Debug, Clone)]
+pub struct NodeStatistics {
+    pub id: u32,
+    /// None if the node or the end node value never varied
+    pub correlation: Option<f64>,
+    /// In bits, None if it wasn't computed
+    pub mutual_information: Option<f64>,
+}
+
+/// Statistics of every node of the 'log' in the order of its ids, with the mutual information if
+/// 'mutual_information' is set
+pub fn node_statistics(log: &SampleLog, mutual_information: bool) -> Vec<NodeStatistics> {
+    let bins: Vec<usize> = log.end_values.iter().map(|v| bin_of(v.clamp(0.0, 1.0))).collect();
+    log.ids.iter().enumerate()
+        .map(|(i, id)| {
+            let states: Vec<bool> = log.visible.iter().map(|visible| visible[i]).collect();
+            NodeStatistics {
+                id: *id,
+                correlation: correlation(&states, &log.end_values),
+                mutual_information: match mutual_information {
+                    true => Some(binned_mutual_information(&states, &bins)),
+                    false => None,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Correlation of the 'states', one if true, with the 'values'. None if either never varies.
+pub fn correlation(states: &[bool], values: &[f64]) -> Option<f64> {
+    let n = values.len() as f64;
+    let on = states.iter().filter(|s| **s).count() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+    if on == 0.0 || on == n || variance <= 0.0 {
+        return None;
+    }
+    let sum_on: f64 = states.iter().zip(values.iter()).filter(|(s, _)| **s).map(|(_, v)| v).sum();
+    let covariance = sum_on / n - on / n * mean;
+    Some(covariance / ((on / n * (1.0 - on / n)).sqrt() * variance.sqrt()))
+}
+
+/// Mutual information in bits of the 'states' and the values counted in the 'bins'
+fn binned_mutual_information(states: &[bool], bins: &[usize]) -> f64 {
+    let mut joint = [[0u64; HISTOGRAM_BINS]; 2];
+    for (state, bin) in states.iter().zip(bins.iter()) {
+        joint[*state as usize][*bin] += 1;
+    }
+    let n = states.len() as f64;
+    let state_counts: Vec<f64> = joint.iter().map(|row| row.iter().sum::<u64>() as f64).collect();
+    let bin_counts: Vec<f64> = (0..HISTOGRAM_BINS).map(|bin| (joint[0][bin] + joint[1][bin]) as f64).collect();
+    let mut information = 0.0;
+    for (state, row) in joint.iter().enumerate() {
+        for (bin, count) in row.iter().enumerate() {
+            if *count > 0 {
+                let count = *count as f64;
+                information += count / n * (count * n / (state_counts[state] * bin_counts[bin])).log2();
+            }
+        }
+    }
+    information
+}
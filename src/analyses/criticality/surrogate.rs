@@ -0,0 +1,210 @@
+//! Screening the sampled states with a fast surrogate of the roll up.
+//!
+//! On large graphs rolling up every sampled state is what the analysis spends its time on. The
+//! ['SurrogateEvaluator'] rolls up the first 'training' states in full, shared by every thread,
+//! and fits a linear model of the end node value over the nodes that are off in a state:
+//!
+//! end value = intercept + sum of the weights of the nodes that are off
+//!
+//! The weights are fitted by ridge regression with coordinate descent, so the cost grows with the
+//! number of off nodes in the training states rather than with the square of the number of nodes.
+//! Afterwards a state is only rolled up if the model is unsure about it: its prediction is more
+//! than 'margin' away from 0 and 1, or more nodes are off than in any training state. The other
+//! states get the value the prediction is close to. Every ['AUDIT_INTERVAL']th screened state is
+//! rolled up anyway to count how often the model is wrong, which is logged once sampling ends.
+//!
+//! The model only screens boolean end values, if a training state has an end value other than 0
+//! or 1 every state is rolled up. The results are an approximation: a state the model is wrong
+//! about but sure of counts with the wrong value, so the audit count should stay small.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+use crate::analyses::VISIBLE_VAL;
+use crate::analyses::criticality::StateEvaluator;
+use crate::network::NodeValueMap;
+
+/// Default number of states rolled up in full to fit the model
+pub const DEFAULT_TRAINING: usize = 1000;
+
+/// Default distance of a prediction to 0 or 1 within which a state is screened
+pub const DEFAULT_MARGIN: f64 = 0.05;
+
+/// Screened states between two that are rolled up to audit the model
+pub const AUDIT_INTERVAL: u64 = 100;
+
+/// Passes of coordinate descent over the weights
+const EPOCHS: usize = 50;
+
+/// Penalty on the size of the weights, keeps nodes that are rarely off from getting large ones
+const RIDGE: f64 = 1.0;
+
+/// Linear model of the end value over the nodes that are off
+#[derive(Debug, Clone)]
+pub struct LinearSurrogate {
+    pub intercept: f64,
+    pub weights: HashMap<u32, f64>,
+    /// Largest number of nodes that were off in a training state
+    pub max_off: usize,
+}
+
+impl LinearSurrogate {
+    /// Fits the model to the 'states', each the ids of the nodes that are off with the end value
+    pub fn fit(states: &[(Vec<u32>, f64)]) -> LinearSurrogate {
+        let mut columns: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (index, (off, _)) in states.iter().enumerate() {
+            for id in off {
+                columns.entry(*id).or_default().push(index);
+            }
+        }
+        let mut residuals: Vec<f64> = states.iter().map(|(_, value)| *value).collect();
+        let mut intercept = 0.0;
+        let mut weights: HashMap<u32, f64> = columns.keys().map(|id| (*id, 0.0)).collect();
+        for _ in 0..EPOCHS {
+            let shift = residuals.iter().sum::<f64>() / residuals.len().max(1) as f64;
+            intercept += shift;
+            residuals.iter_mut().for_each(|r| *r -= shift);
+            for (id, column) in columns.iter() {
+                let weight = weights.get_mut(id).unwrap();
+                let fitted = column.iter().map(|i| residuals[*i] + *weight).sum::<f64>() / (column.len() as f64 + RIDGE);
+                for i in column {
+                    residuals[*i] -= fitted - *weight;
+                }
+                *weight = fitted;
+            }
+        }
+        LinearSurrogate {
+            intercept,
+            weights,
+            max_off: states.iter().map(|(off, _)| off.len()).max().unwrap_or(0),
+        }
+    }
+
+    pub fn predict(&self, off: &[u32]) -> f64 {
+        self.intercept + off.iter().filter_map(|id| self.weights.get(id)).sum::<f64>()
+    }
+}
+
+/// State of the training shared by the clones of every thread
+#[derive(Debug, Default)]
+struct Training {
+    states: Vec<(Vec<u32>, f64)>,
+    /// None while training, and if the end values aren't boolean
+    model: Option<Arc<LinearSurrogate>>,
+    done: bool,
+    screened: u64,
+    rolled_up: u64,
+    audited: u64,
+    wrong: u64,
+}
+
+impl Drop for Training {
+    fn drop(&mut self) {
+        if self.done && self.model.is_some() {
+            info!("The surrogate screened {} states and rolled up {}, {} of {} audited states were wrong",
+                  self.screened, self.rolled_up, self.wrong, self.audited);
+        }
+    }
+}
+
+/// Evaluates states with the wrapped evaluator or the surrogate, see the module documentation
+pub struct SurrogateEvaluator {
+    pub inner: Box<dyn StateEvaluator>,
+    /// Number of states rolled up in full to fit the model
+    pub training: usize,
+    pub margin: f64,
+    shared: Arc<Mutex<Training>>,
+    /// The fitted model once this clone has seen it, saves locking for every state
+    model: Option<Arc<LinearSurrogate>>,
+    /// States this clone screened, added to the shared count at every audit
+    screened: u64,
+}
+
+impl Clone for SurrogateEvaluator {
+    fn clone(&self) -> Self {
+        SurrogateEvaluator {
+            inner: dyn_clone::clone_box(&*self.inner),
+            training: self.training,
+            margin: self.margin,
+            shared: self.shared.clone(),
+            model: self.model.clone(),
+            screened: 0,
+        }
+    }
+}
+
+impl Drop for SurrogateEvaluator {
+    fn drop(&mut self) {
+        // The screened states since the last audit
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.screened += self.screened % AUDIT_INTERVAL;
+        }
+    }
+}
+
+impl SurrogateEvaluator {
+    pub fn new(inner: Box<dyn StateEvaluator>, training: usize, margin: f64) -> SurrogateEvaluator {
+        SurrogateEvaluator { inner, training, margin, shared: Arc::new(Mutex::new(Training::default())), model: None, screened: 0 }
+    }
+
+    /// Rolls up a training state, fitting the model once there are enough of them
+    fn train(&mut self, off: Vec<u32>, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let end_val = self.inner.evaluate(visibility_state);
+        let mut shared = self.shared.lock().unwrap();
+        if shared.done {
+            shared.rolled_up += 1;
+            self.model = shared.model.clone();
+            return end_val;
+        }
+        shared.states.push((off, end_val));
+        if shared.states.len() >= self.training {
+            shared.done = true;
+            let states = std::mem::take(&mut shared.states);
+            match states.iter().all(|(_, value)| *value == 0.0 || *value == 1.0) {
+                true => {
+                    let model = LinearSurrogate::fit(&states);
+                    info!("Fitted the surrogate to {} states, {} nodes have a weight", states.len(), model.weights.len());
+                    shared.model = Some(Arc::new(model));
+                }
+                false => warn!("The end values aren't boolean, the surrogate doesn't screen any state"),
+            }
+            self.model = shared.model.clone();
+        }
+        end_val
+    }
+}
+
+impl StateEvaluator for SurrogateEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let off: Vec<u32> = visibility_state.iter()
+            .filter(|(_, value)| **value != VISIBLE_VAL)
+            .map(|(id, _)| *id)
+            .collect();
+        let Some(model) = self.model.clone() else {
+            return self.train(off, visibility_state);
+        };
+        let prediction = model.predict(&off);
+        let screened = match prediction {
+            _ if off.len() > model.max_off => None,
+            p if p <= self.margin => Some(0.0),
+            p if p >= 1.0 - self.margin => Some(1.0),
+            _ => None,
+        };
+        let Some(value) = screened else {
+            self.shared.lock().unwrap().rolled_up += 1;
+            return self.inner.evaluate(visibility_state);
+        };
+        self.screened += 1;
+        if !self.screened.is_multiple_of(AUDIT_INTERVAL) {
+            return value;
+        }
+        let end_val = self.inner.evaluate(visibility_state);
+        let mut shared = self.shared.lock().unwrap();
+        shared.screened += AUDIT_INTERVAL;
+        shared.audited += 1;
+        if end_val != value {
+            shared.wrong += 1;
+        }
+        end_val
+    }
+}
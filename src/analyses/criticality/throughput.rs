@@ -0,0 +1,92 @@
+//! Estimating when a sampling run ends from its throughput.
+//!
+//! The threads count the iterations of their loop conditions. Once the run has sampled for
+//! ['FIRST_ESTIMATE_AFTER'] the main thread reports the states per second and, if the loop
+//! conditions know how many iterations they run, the projected time until the run completes. The
+//! estimate is updated every ['ESTIMATE_INTERVAL'], following the throughput of the latest
+//! intervals so a run that slows down or speeds up is projected by its current pace.
+
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// Sampling time measured before the first estimate
+pub const FIRST_ESTIMATE_AFTER: Duration = Duration::from_secs(2);
+
+/// Time between two estimates after the first
+pub const ESTIMATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Weight of the latest interval in the smoothed throughput
+const LATEST_WEIGHT: f64 = 0.5;
+
+/// Measures the throughput of a run from the iterations done so far
+#[derive(Debug, Clone)]
+pub struct Throughput {
+    start: Instant,
+    /// Iterations of the whole run, None if the loop conditions don't know them
+    total: Option<u64>,
+    /// Time and iterations done at the previous estimate
+    last: (Instant, u64),
+    /// Smoothed iterations per second
+    rate: Option<f64>,
+}
+
+/// Progress of a run and its projected end
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    pub done: u64,
+    pub total: Option<u64>,
+    pub elapsed: Duration,
+    pub states_per_second: f64,
+    /// Time until the run completes at the current throughput, None if the total is unknown
+    pub remaining: Option<Duration>,
+}
+
+impl Throughput {
+    pub fn new(total: Option<u64>) -> Throughput {
+        let start = Instant::now();
+        Throughput { start, total, last: (start, 0), rate: None }
+    }
+
+    /// Estimates the end of the run after 'done' iterations
+    pub fn update(&mut self, done: u64) -> Estimate {
+        let now = Instant::now();
+        let interval = now.duration_since(self.last.0).as_secs_f64();
+        if interval > 0.0 {
+            let latest = done.saturating_sub(self.last.1) as f64 / interval;
+            self.rate = Some(match self.rate {
+                None => latest,
+                Some(rate) => LATEST_WEIGHT * latest + (1.0 - LATEST_WEIGHT) * rate,
+            });
+            self.last = (now, done);
+        }
+        let rate = self.rate.unwrap_or(0.0);
+        let remaining = self.total
+            .filter(|_| rate > 0.0)
+            .map(|total| Duration::from_secs_f64(total.saturating_sub(done) as f64 / rate));
+        Estimate { done, total: self.total, elapsed: now.duration_since(self.start), states_per_second: rate, remaining }
+    }
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sampled {} ", self.done)?;
+        if let Some(total) = self.total {
+            write!(f, "of {} ({:.1}%) ", total, 100.0 * self.done as f64 / total.max(1) as f64)?;
+        }
+        write!(f, "states in {}, {:.0} states/s", format_duration(self.elapsed), self.states_per_second)?;
+        match self.remaining {
+            Some(remaining) => write!(f, ", about {} left", format_duration(remaining)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A duration in whole hours, minutes and seconds, such as '1h 02m 03s'
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, s) => format!("{}h {:02}m {:02}s", h, m, s),
+    }
+}
@@ -3,15 +3,80 @@ pub mod visibility_states_gen {
     use dyn_clone::DynClone;
     use rand::{Rng, SeedableRng};
     use rand::rngs::{StdRng};
-    use crate::network::{NodeValueMap};
+    use crate::analyses::{INVISIBLE_VAL, VISIBLE_VAL};
+    use crate::errors::analysis::TooManyNodesError;
+    use crate::errors::input::LifetimeError;
+    use crate::network::{Graph, NodeValueMap};
 
     pub trait VisGen: DynClone + Send {
         fn next_states(&mut self) -> NodeValueMap<u8>;
         fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>>;
+
+        /// Draws the next state into the 'state' drawn before, so a sampling thread reuses one
+        /// map for every state instead of allocating one per state
+        fn next_states_into(&mut self, state: &mut NodeValueMap<u8>) {
+            *state = self.next_states();
+        }
+
+        /// Whether the states come in pairs that must be aggregated together, the first state
+        /// of every pair is the one returned after an even number of states
+        fn paired(&self) -> bool {
+            false
+        }
+
+        /// Whether no state is drawn twice, so the sampling doesn't need to remember the visited
+        /// states
+        fn distinct(&self) -> bool {
+            false
+        }
+
+        /// Whether consecutive states differ in a few nodes only, so evaluators can update the
+        /// values of the previous state instead of rolling up every node
+        fn incremental(&self) -> bool {
+            false
+        }
+
+        /// Chance of drawing the 'state', None if it is unknown, such as for states whose nodes
+        /// aren't drawn independently, see ['crate::analyses::criticality::coverage']
+        fn state_probability(&self, _state: &NodeValueMap<u8>) -> Option<f64> {
+            None
+        }
+    }
+
+    /// Off chance of the nodes that have none in the input
+    pub const DEFAULT_OFF_CHANCE: f32 = 0.5;
+
+    /// Random number generator seeded with 'seed', so the same seed draws the same states, or
+    /// from the system without one
+    pub fn seeded_rng(seed: Option<u64>) -> StdRng {
+        match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
     }
 
-    const DEFAULT_OFF_CHANCE: f32 = 0.5;
+    /// Random number generators of 'threads' threads seeded from the 'rng', so a seeded
+    /// generator split to the same number of threads draws the same states on every run
+    pub fn split_rng(rng: &StdRng, threads: u64) -> Vec<StdRng> {
+        let mut rng = rng.clone();
+        (0..threads).map(|_| StdRng::seed_from_u64(rng.gen())).collect()
+    }
 
+    /// Visible state of the 'ids' unless 'state' already holds exactly them. The generators draw
+    /// the nodes in the order of the state, which is the order of their ids, so a seeded
+    /// generator doesn't depend on the order of the set of ids.
+    fn reset_state(state: &mut NodeValueMap<u8>, ids: &HashSet<u32>) {
+        if state.len() != ids.len() || !state.keys().all(|id| ids.contains(id)) {
+            *state = ids.iter().map(|id| (*id, VISIBLE_VAL)).collect();
+        }
+    }
+
+    /// Draws every node independently: a node is off, ['INVISIBLE_VAL'], with its off chance and
+    /// visible otherwise. Nodes without an off chance use ['DEFAULT_OFF_CHANCE'].
+    ///
+    /// The draws agree with ['VisGen::state_probability'], which the exact enumeration weights the
+    /// states with. The generator used to turn a node off with the complement of its off chance,
+    /// which went unnoticed while no input set off chances and every node used the default of 0.5.
     #[derive(Clone)]
     pub struct RandomGen {
         pub rng: StdRng,
@@ -22,24 +87,25 @@ pub mod visibility_states_gen {
     impl VisGen for RandomGen {
         fn next_states(&mut self) -> NodeValueMap<u8> {
             let mut new_states = NodeValueMap::new();
-            for id in &self.ids {
+            self.next_states_into(&mut new_states);
+            new_states
+        }
+
+        fn next_states_into(&mut self, state: &mut NodeValueMap<u8>) {
+            reset_state(state, &self.ids);
+            for (id, visibility) in state.iter_mut() {
                 let rand: f32 = self.rng.gen();
                 let off_chance = self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE);
-                if *off_chance < rand{
-                    new_states.insert(*id, 0);
-                } else {
-                    new_states.insert(*id, 1);
-                }
+                *visibility = if rand < *off_chance { INVISIBLE_VAL } else { VISIBLE_VAL };
             }
-            new_states
         }
 
-        fn split_to_threads(&self, threads: u64) -> Vec<Box<(dyn VisGen)>> {
-            let mut out: Vec<Box<(dyn VisGen)>> = vec![];
-            for _ in 0..threads {
+        fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+            let mut out: Vec<Box<dyn VisGen>> = vec![];
+            for rng in split_rng(&self.rng, threads) {
                 out.push(Box::new(
                     RandomGen {
-                        rng: StdRng::from_entropy(),
+                        rng,
                         ids: self.ids.clone(),
                         off_chances: self.off_chances.clone(),
                     }
@@ -47,5 +113,511 @@ pub mod visibility_states_gen {
             }
             out
         }
+
+        fn state_probability(&self, state: &NodeValueMap<u8>) -> Option<f64> {
+            Some(self.ids.iter()
+                .map(|id| {
+                    let off_chance = *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE) as f64;
+                    match state.get(id) {
+                        Some(&INVISIBLE_VAL) => off_chance,
+                        _ => 1.0 - off_chance,
+                    }
+                })
+                .product())
+        }
+    }
+
+    /// Antithetic variates around a ['RandomGen']: every sampled state is followed by its
+    /// complement, which draws every node from the inverted uniform 1 - u. A node that is off in
+    /// the sampled state is on in the complement unless its off chance is above one half. For roll
+    /// up rules that are roughly monotone the end values of a pair are negatively correlated, which
+    /// lowers the variance of the means.
+    #[derive(Clone)]
+    pub struct AntitheticGen {
+        pub base: RandomGen,
+        /// Complement of the last sampled state, returned by the next call
+        complement: Option<NodeValueMap<u8>>,
+    }
+
+    impl AntitheticGen {
+        pub fn new(base: RandomGen) -> AntitheticGen {
+            AntitheticGen { base, complement: None }
+        }
+    }
+
+    impl VisGen for AntitheticGen {
+        fn next_states(&mut self) -> NodeValueMap<u8> {
+            if let Some(complement) = self.complement.take() {
+                return complement;
+            }
+            let mut states = NodeValueMap::new();
+            reset_state(&mut states, &self.base.ids);
+            let mut complement = states.clone();
+            for ((id, state), complemented) in states.iter_mut().zip(complement.values_mut()) {
+                let rand: f32 = self.base.rng.gen();
+                let off_chance = *self.base.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE);
+                *state = if rand < off_chance { INVISIBLE_VAL } else { VISIBLE_VAL };
+                *complemented = if 1.0 - rand < off_chance { INVISIBLE_VAL } else { VISIBLE_VAL };
+            }
+            self.complement = Some(complement);
+            states
+        }
+
+        fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+            split_rng(&self.base.rng, threads).into_iter()
+                .map(|rng| Box::new(AntitheticGen::new(RandomGen { rng, ..self.base.clone() })) as Box<dyn VisGen>)
+                .collect()
+        }
+
+        fn paired(&self) -> bool {
+            true
+        }
+
+        /// Both states of a pair are drawn by the off chances, only together they are correlated
+        fn state_probability(&self, state: &NodeValueMap<u8>) -> Option<f64> {
+            self.base.state_probability(state)
+        }
+    }
+
+    /// Most dynamic nodes whose states can be enumerated
+    pub const MAX_ENUMERATED_NODES: usize = 63;
+
+    /// Enumerates every state of the dynamic nodes once, in Gray code order: consecutive states
+    /// differ in a single node, so an incremental evaluator only rolls up the ancestors of that
+    /// node. The bit k of the code is set while the node with the k-th smallest id is off. The
+    /// loop condition should stop after ['GrayCodeGen::state_count'] states.
+    #[derive(Clone)]
+    pub struct GrayCodeGen {
+        ids: Vec<u32>,
+        next: u64,
+        end: u64,
+    }
+
+    impl GrayCodeGen {
+        /// # Errors
+        ///
+        /// Returns a ['TooManyNodesError'] if there are more than ['MAX_ENUMERATED_NODES'] ids
+        pub fn new(ids: &HashSet<u32>) -> Result<GrayCodeGen, TooManyNodesError> {
+            if ids.len() > MAX_ENUMERATED_NODES {
+                return Err(TooManyNodesError { analysis: "gray code enumeration".to_string(), nodes: ids.len(), max: MAX_ENUMERATED_NODES });
+            }
+            let mut ids: Vec<u32> = ids.iter().copied().collect();
+            ids.sort();
+            let end = 1u64 << ids.len();
+            Ok(GrayCodeGen { ids, next: 0, end })
+        }
+
+        /// Number of states of the nodes, 2 to the power of their count
+        pub fn state_count(&self) -> u64 {
+            1u64 << self.ids.len()
+        }
+    }
+
+    impl VisGen for GrayCodeGen {
+        fn next_states(&mut self) -> NodeValueMap<u8> {
+            let mut states = NodeValueMap::new();
+            self.next_states_into(&mut states);
+            states
+        }
+
+        fn next_states_into(&mut self, state: &mut NodeValueMap<u8>) {
+            let code = self.next ^ (self.next >> 1);
+            // Starts over after the last state of the range
+            self.next = if self.next + 1 >= self.end { 0 } else { self.next + 1 };
+            state.retain(|id, _| self.ids.binary_search(id).is_ok());
+            for (k, id) in self.ids.iter().enumerate() {
+                state.insert(*id, if code >> k & 1 == 1 { INVISIBLE_VAL } else { VISIBLE_VAL });
+            }
+        }
+
+        fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+            crate::util::split_range(self.next, self.end, threads).into_iter()
+                .map(|(next, end)| Box::new(GrayCodeGen { ids: self.ids.clone(), next, end }) as Box<dyn VisGen>)
+                .collect()
+        }
+
+        fn distinct(&self) -> bool {
+            true
+        }
+
+        fn incremental(&self) -> bool {
+            true
+        }
+    }
+
+    /// Nodes sharing a common cause of failure under the beta-factor model
+    #[derive(Debug, Clone)]
+    pub struct CcfGroup {
+        pub name: String,
+        /// Fraction of the failure chance of every member that is due to the shared cause
+        pub beta: f32,
+        pub members: Vec<u32>,
+    }
+
+    /// Beta-factor common-cause failure model wrapped around another generator. The wrapped
+    /// generator samples the independent failures, after which the shared cause of every group
+    /// switches off all its members at once with the chance beta times the mean off chance of the
+    /// members. The wrapped generator should use the off chances of
+    /// ['BetaFactorGen::independent_off_chances'] so the total off chance of a node stays about
+    /// the same.
+    pub struct BetaFactorGen {
+        pub inner: Box<dyn VisGen>,
+        pub rng: StdRng,
+        /// (group, chance of its shared cause occurring)
+        pub groups: Vec<(CcfGroup, f32)>,
+    }
+
+    impl Clone for BetaFactorGen {
+        fn clone(&self) -> Self {
+            BetaFactorGen {
+                inner: dyn_clone::clone_box(&*self.inner),
+                rng: self.rng.clone(),
+                groups: self.groups.clone(),
+            }
+        }
+    }
+
+    impl BetaFactorGen {
+        pub fn new(inner: Box<dyn VisGen>, groups: Vec<CcfGroup>, off_chances: &NodeValueMap<f32>) -> BetaFactorGen {
+            let groups = groups.into_iter().map(|group| {
+                let total: f32 = group.members.iter()
+                    .map(|id| *off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE))
+                    .sum();
+                let mean = if group.members.is_empty() { 0.0 } else { total / group.members.len() as f32 };
+                let common_chance = group.beta * mean;
+                (group, common_chance)
+            }).collect();
+            BetaFactorGen { inner, rng: StdRng::from_entropy(), groups }
+        }
+
+        /// Off chances of the independent failures, the share 'beta' of every group member is
+        /// removed
+        pub fn independent_off_chances(off_chances: &NodeValueMap<f32>, ids: &HashSet<u32>, groups: &[CcfGroup]) -> NodeValueMap<f32> {
+            let mut independent = off_chances.clone();
+            for group in groups.iter() {
+                for id in group.members.iter().filter(|id| ids.contains(id)) {
+                    let off_chance = *off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE);
+                    independent.insert(*id, off_chance * (1.0 - group.beta));
+                }
+            }
+            independent
+        }
+    }
+
+    impl VisGen for BetaFactorGen {
+        fn next_states(&mut self) -> NodeValueMap<u8> {
+            let mut states = NodeValueMap::new();
+            self.next_states_into(&mut states);
+            states
+        }
+
+        fn next_states_into(&mut self, states: &mut NodeValueMap<u8>) {
+            self.inner.next_states_into(states);
+            for (group, common_chance) in self.groups.iter() {
+                let rand: f32 = self.rng.gen();
+                if rand < *common_chance {
+                    for id in group.members.iter() {
+                        if let Some(state) = states.get_mut(id) {
+                            *state = INVISIBLE_VAL;
+                        }
+                    }
+                }
+            }
+        }
+
+        fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+            self.inner.split_to_threads(threads).into_iter().zip(split_rng(&self.rng, threads)).map(|(inner, rng)| {
+                let split: Box<dyn VisGen> = Box::new(BetaFactorGen {
+                    inner,
+                    rng,
+                    groups: self.groups.clone(),
+                });
+                split
+            }).collect()
+        }
+    }
+
+    /// What a dependency does to its dependent node while the trigger node is off
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum DependencyEffect {
+        /// The dependent node is off as well
+        Off,
+        /// The dependent node is off with this chance, or its own off chance if that is higher
+        OffChance(f32),
+    }
+
+    /// Physical dependency between two nodes, such as a shared cooling system: if the 'trigger'
+    /// is off the 'effect' applies to the 'dependent'
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Dependency {
+        pub trigger: u32,
+        pub dependent: u32,
+        pub effect: DependencyEffect,
+    }
+
+    /// Enforces dependencies on the states of the wrapped generator. A dependent node that is on
+    /// while its trigger is off is switched off with the chance that raises its off chance from
+    /// its own to the one of the effect. Dependencies cascade: a node switched off triggers the
+    /// dependencies on it, every dependency is applied at most once per state.
+    pub struct DependencyGen {
+        pub inner: Box<dyn VisGen>,
+        pub rng: StdRng,
+        pub dependencies: Vec<Dependency>,
+        /// Off chances of the nodes, used to condition the elevated off chances
+        pub off_chances: NodeValueMap<f32>,
+    }
+
+    impl Clone for DependencyGen {
+        fn clone(&self) -> Self {
+            DependencyGen {
+                inner: dyn_clone::clone_box(&*self.inner),
+                rng: self.rng.clone(),
+                dependencies: self.dependencies.clone(),
+                off_chances: self.off_chances.clone(),
+            }
+        }
+    }
+
+    impl DependencyGen {
+        pub fn new(inner: Box<dyn VisGen>, dependencies: Vec<Dependency>, off_chances: NodeValueMap<f32>) -> DependencyGen {
+            DependencyGen { inner, rng: StdRng::from_entropy(), dependencies, off_chances }
+        }
+
+        /// Chance of switching off a dependent node that is on so its total off chance becomes
+        /// the one of the 'effect'
+        fn switch_off_chance(&self, dependent: &u32, effect: DependencyEffect) -> f32 {
+            match effect {
+                DependencyEffect::Off => 1.0,
+                DependencyEffect::OffChance(chance) => {
+                    let own = *self.off_chances.get(dependent).unwrap_or(&DEFAULT_OFF_CHANCE);
+                    match own < 1.0 {
+                        true => ((chance - own) / (1.0 - own)).max(0.0),
+                        false => 0.0,
+                    }
+                }
+            }
+        }
+    }
+
+    impl VisGen for DependencyGen {
+        fn next_states(&mut self) -> NodeValueMap<u8> {
+            let mut states = self.inner.next_states();
+            let mut applied = vec![false; self.dependencies.len()];
+            loop {
+                let mut changed = false;
+                for (i, dependency) in self.dependencies.iter().enumerate() {
+                    if applied[i] || states.get(&dependency.trigger) != Some(&INVISIBLE_VAL) {
+                        continue;
+                    }
+                    applied[i] = true;
+                    if states.get(&dependency.dependent) != Some(&VISIBLE_VAL) {
+                        continue;
+                    }
+                    let rand: f32 = self.rng.gen();
+                    if rand < self.switch_off_chance(&dependency.dependent, dependency.effect) {
+                        states.insert(dependency.dependent, INVISIBLE_VAL);
+                        changed = true;
+                    }
+                }
+                if !changed {
+                    return states;
+                }
+            }
+        }
+
+        fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+            self.inner.split_to_threads(threads).into_iter().zip(split_rng(&self.rng, threads))
+                .map(|(inner, rng)| Box::new(DependencyGen { rng, ..DependencyGen::new(inner, self.dependencies.clone(), self.off_chances.clone()) }) as Box<dyn VisGen>)
+                .collect()
+        }
+    }
+
+    /// Node attribute naming the lifetime distribution, 'exponential' or 'weibull'
+    pub const LIFETIME_ATTR: &str = "lifetime";
+    /// Failure rate of an exponential lifetime
+    pub const RATE_ATTR: &str = "rate";
+    /// Shape of a weibull lifetime
+    pub const SHAPE_ATTR: &str = "shape";
+    /// Scale of a weibull lifetime
+    pub const SCALE_ATTR: &str = "scale";
+
+    /// Distribution of the time until a node fails
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Lifetime {
+        Exponential { rate: f64 },
+        Weibull { shape: f64, scale: f64 },
+    }
+
+    impl Lifetime {
+        /// Chance of the node having failed by the time 't'
+        pub fn failure_chance(&self, t: f64) -> f64 {
+            match self {
+                Lifetime::Exponential { rate } => 1.0 - (-rate * t).exp(),
+                Lifetime::Weibull { shape, scale } => 1.0 - (-(t / scale).powf(*shape)).exp(),
+            }
+        }
+
+        /// Samples a lifetime by inverting the distribution function
+        pub fn sample(&self, rng: &mut StdRng) -> f64 {
+            let u: f64 = rng.gen();
+            let hazard = -(1.0 - u).ln();
+            match self {
+                Lifetime::Exponential { rate } => hazard / rate,
+                Lifetime::Weibull { shape, scale } => scale * hazard.powf(1.0 / shape),
+            }
+        }
+
+        /// Reads the lifetime of every node that has a ['LIFETIME_ATTR'] attribute
+        ///
+        /// # Errors
+        ///
+        /// Returns a ['LifetimeError'] if the distribution is unknown or a parameter is missing
+        /// or not positive
+        pub fn from_attributes(graph: &Graph) -> Result<NodeValueMap<Lifetime>, LifetimeError> {
+            let mut lifetimes = NodeValueMap::new();
+            for id in graph.get_node_ids() {
+                let distribution = match graph.get_node_attr(&id, LIFETIME_ATTR) {
+                    None => continue,
+                    Some(d) => d.to_string().to_ascii_lowercase(),
+                };
+                let parameter = |name: &str| match graph.get_node_attr_f64(&id, name) {
+                    Some(value) if value > 0.0 => Ok(value),
+                    _ => Err(LifetimeError { id, reason: format!("'{}' should be a positive number", name) }),
+                };
+                let lifetime = match distribution.as_str() {
+                    "exponential" => Lifetime::Exponential { rate: parameter(RATE_ATTR)? },
+                    "weibull" => Lifetime::Weibull { shape: parameter(SHAPE_ATTR)?, scale: parameter(SCALE_ATTR)? },
+                    other => return Err(LifetimeError { id, reason: format!("unknown distribution '{}'", other) }),
+                };
+                lifetimes.insert(id, lifetime);
+            }
+            Ok(lifetimes)
+        }
+    }
+
+    /// Samples the states at a mission time: a node is off if its sampled lifetime is shorter
+    /// than the mission time. Nodes without a lifetime use their fixed off chance.
+    #[derive(Clone)]
+    pub struct LifetimeGen {
+        pub rng: StdRng,
+        pub ids: HashSet<u32>,
+        pub lifetimes: NodeValueMap<Lifetime>,
+        pub off_chances: NodeValueMap<f32>,
+        pub mission_time: f64,
+    }
+
+    impl VisGen for LifetimeGen {
+        fn next_states(&mut self) -> NodeValueMap<u8> {
+            let mut new_states = NodeValueMap::new();
+            self.next_states_into(&mut new_states);
+            new_states
+        }
+
+        fn next_states_into(&mut self, state: &mut NodeValueMap<u8>) {
+            reset_state(state, &self.ids);
+            for (id, visibility) in state.iter_mut() {
+                let off = match self.lifetimes.get(id) {
+                    Some(lifetime) => lifetime.sample(&mut self.rng) < self.mission_time,
+                    None => {
+                        let rand: f32 = self.rng.gen();
+                        rand < *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE)
+                    }
+                };
+                *visibility = if off { INVISIBLE_VAL } else { VISIBLE_VAL };
+            }
+        }
+
+        fn split_to_threads(&self, threads: u64) -> Vec<Box<dyn VisGen>> {
+            let mut out: Vec<Box<dyn VisGen>> = vec![];
+            for rng in split_rng(&self.rng, threads) {
+                out.push(Box::new(
+                    LifetimeGen {
+                        rng,
+                        ..self.clone()
+                    }
+                ))
+            }
+            out
+        }
+
+        /// Every node fails independently, by its lifetime or its off chance
+        fn state_probability(&self, state: &NodeValueMap<u8>) -> Option<f64> {
+            Some(self.ids.iter()
+                .map(|id| {
+                    let off_chance = match self.lifetimes.get(id) {
+                        Some(lifetime) => lifetime.failure_chance(self.mission_time),
+                        None => *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE) as f64,
+                    };
+                    match state.get(id) {
+                        Some(&INVISIBLE_VAL) => off_chance,
+                        _ => 1.0 - off_chance,
+                    }
+                })
+                .product())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::analyses::{INVISIBLE_VAL, VISIBLE_VAL};
+    use crate::analyses::criticality::vis_gen::visibility_states_gen::{RandomGen, seeded_rng, VisGen};
+    use crate::network::NodeValueMap;
+
+    #[test]
+    fn random_states_are_off_with_the_off_chance() {
+        let off_chances: NodeValueMap<f32> = [(1, 0.0), (2, 1.0), (3, 0.9)].into_iter().collect();
+        let mut generator = RandomGen { rng: StdRng::seed_from_u64(7), ids: HashSet::from([1, 2, 3]), off_chances };
+        let draws = 10_000;
+        let mut off = 0;
+        for _ in 0..draws {
+            let state = generator.next_states();
+            assert_eq!(state[&1], VISIBLE_VAL);
+            assert_eq!(state[&2], INVISIBLE_VAL);
+            if state[&3] == INVISIBLE_VAL {
+                off += 1;
+            }
+        }
+        let share = off as f64 / draws as f64;
+        assert!((share - 0.9).abs() < 0.02, "node 3 was off in {} of the states", share);
+    }
+
+    #[test]
+    fn random_states_are_drawn_with_their_state_probability() {
+        let off_chances: NodeValueMap<f32> = [(1, 0.2), (2, 0.7)].into_iter().collect();
+        let mut generator = RandomGen { rng: seeded_rng(Some(3)), ids: HashSet::from([1, 2]), off_chances };
+        let draws = 20_000;
+        let mut counts: HashMap<(u8, u8), u32> = HashMap::new();
+        for _ in 0..draws {
+            let state = generator.next_states();
+            *counts.entry((state[&1], state[&2])).or_default() += 1;
+        }
+        for (a, b) in [(VISIBLE_VAL, VISIBLE_VAL), (VISIBLE_VAL, INVISIBLE_VAL), (INVISIBLE_VAL, VISIBLE_VAL), (INVISIBLE_VAL, INVISIBLE_VAL)] {
+            let state: NodeValueMap<u8> = [(1, a), (2, b)].into_iter().collect();
+            let expected = generator.state_probability(&state).unwrap();
+            let share = *counts.get(&(a, b)).unwrap_or(&0) as f64 / draws as f64;
+            assert!((share - expected).abs() < 0.02, "{:?} was drawn in {} of the states, not {}", (a, b), share, expected);
+        }
+        // Off with the complement of the off chance both nodes were off in 0.8 * 0.3 of the states
+        let both_off: NodeValueMap<u8> = [(1, INVISIBLE_VAL), (2, INVISIBLE_VAL)].into_iter().collect();
+        assert!((generator.state_probability(&both_off).unwrap() - 0.14).abs() < 1e-6);
+    }
+
+    #[test]
+    fn seeded_generators_draw_the_same_states_on_every_run() {
+        let off_chances: NodeValueMap<f32> = (1..=20).map(|id| (id, 0.5)).collect();
+        // The same ids inserted in another order, which may iterate in another order
+        let generators = [(1..=20).collect::<HashSet<u32>>(), (1..=20).rev().collect::<HashSet<u32>>()]
+            .map(|ids| RandomGen { rng: seeded_rng(Some(42)), ids, off_chances: off_chances.clone() });
+        let draws = generators.map(|generator| {
+            generator.split_to_threads(3).into_iter()
+                .map(|mut thread| (0..10).map(|_| thread.next_states()).collect::<Vec<NodeValueMap<u8>>>())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(draws[0], draws[1]);
+        assert_ne!(draws[0][0], draws[0][1], "the threads should draw different states");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,104 @@
+//! Sets of the visibility states a sampling thread has already evaluated.
+
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use crate::network::NodeValueMap;
+
+/// Bits of the Bloom filter per state it was sized for
+const BLOOM_BITS_PER_STATE: usize = 10;
+/// Hash functions of the Bloom filter, optimal for 'BLOOM_BITS_PER_STATE'
+const BLOOM_HASHES: u64 = 7;
+/// The Bloom filter is sized for this many times the states of the exact set it replaces
+const BLOOM_GROWTH: usize = 8;
+
+/// Visited states, exact until it is degraded to a Bloom filter. A degraded set may report a new
+/// state as visited, about 1% of the states until it holds 'BLOOM_GROWTH' times the states it
+/// was created with, so a few states are skipped but none is evaluated twice.
+pub enum Visited {
+    Exact(HashSet<NodeValueMap<u8>>),
+    Bloom(BloomFilter),
+}
+
+impl Visited {
+    pub fn new() -> Visited {
+        Visited::Exact(HashSet::new())
+    }
+
+    pub fn contains(&self, state: &NodeValueMap<u8>) -> bool {
+        match self {
+            Visited::Exact(states) => states.contains(state),
+            Visited::Bloom(filter) => filter.contains(state),
+        }
+    }
+
+    pub fn insert(&mut self, state: NodeValueMap<u8>) {
+        match self {
+            Visited::Exact(states) => { states.insert(state); }
+            Visited::Bloom(filter) => filter.insert(&state),
+        }
+    }
+
+    /// Number of states in the exact set, zero once degraded
+    pub fn exact_len(&self) -> usize {
+        match self {
+            Visited::Exact(states) => states.len(),
+            Visited::Bloom(_) => 0,
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        matches!(self, Visited::Bloom(_))
+    }
+
+    /// Replaces the exact set by a Bloom filter holding the same states
+    pub fn degrade(&mut self) {
+        if let Visited::Exact(states) = self {
+            let mut filter = BloomFilter::new(states.len().max(1) * BLOOM_GROWTH);
+            for state in states.iter() {
+                filter.insert(state);
+            }
+            *self = Visited::Bloom(filter);
+        }
+    }
+}
+
+impl Default for Visited {
+    fn default() -> Self {
+        Visited::new()
+    }
+}
+
+/// Bloom filter over visibility states using double hashing
+pub struct BloomFilter {
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    /// A filter with a false positive rate of about 1% once it holds 'capacity' states
+    pub fn new(capacity: usize) -> BloomFilter {
+        BloomFilter { bits: vec![0; (capacity * BLOOM_BITS_PER_STATE).div_ceil(64).max(1)] }
+    }
+
+    fn indexes(&self, state: &NodeValueMap<u8>) -> impl Iterator<Item = usize> {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        let h1 = hasher.finish();
+        // A second hash from the same state, odd so every step visits a new bit
+        h1.hash(&mut hasher);
+        let h2 = hasher.finish() | 1;
+        let len = self.bits.len() as u64 * 64;
+        (0..BLOOM_HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    pub fn insert(&mut self, state: &NodeValueMap<u8>) {
+        let indexes: Vec<usize> = self.indexes(state).collect();
+        for i in indexes {
+            self.bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    pub fn contains(&self, state: &NodeValueMap<u8>) -> bool {
+        self.indexes(state).all(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+    }
+}
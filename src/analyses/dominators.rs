@@ -0,0 +1,131 @@
+//! Single points of failure found from the structure of the graph alone.
+//!
+//! A node dominates the end node if every path from the start node to the end node passes through
+//! it, so removing the node disconnects the end from the start. The dominators are found with the
+//! iterative algorithm of Cooper, Harvey and Kennedy on the nodes reached from the start node.
+//! Bridges are the edges every path passes through, they link two consecutive dominators.
+
+use std::collections::{HashMap, HashSet};
+use crate::network::{AttrValue, Graph, LinkMap, Links};
+
+/// Node attribute set to true on every single point of failure by ['mark_single_points']
+pub const SINGLE_POINT_ATTR: &str = "single_point";
+
+/// The nodes other than 'start' and 'end' that lie on every path from 'start' to 'end', from the
+/// closest to 'end' to the closest to 'start'. Empty if 'end' can't be reached from 'start'.
+pub fn single_points(l_map: &LinkMap, start: u32, end: u32) -> Vec<u32> {
+    let order = reverse_post_order(l_map, start);
+    let index: HashMap<u32, usize> = order.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    if !index.contains_key(&end) {
+        return vec![];
+    }
+    // Immediate dominator of every node by its index in the reverse post order
+    let mut idom: Vec<Option<usize>> = vec![None; order.len()];
+    idom[0] = Some(0);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, id) in order.iter().enumerate().skip(1) {
+            let mut new_idom: Option<usize> = None;
+            for child in l_map.children_of(id).iter().filter_map(|c| index.get(c)) {
+                if idom[*child].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => *child,
+                    Some(current) => intersect(&idom, current, *child),
+                });
+            }
+            if new_idom.is_some() && idom[i] != new_idom {
+                idom[i] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    let mut points = vec![];
+    let mut current = idom[index[&end]].unwrap_or(0);
+    while current != 0 {
+        points.push(order[current]);
+        current = idom[current].unwrap_or(0);
+    }
+    points
+}
+
+/// Sets ['SINGLE_POINT_ATTR'] on every single point of failure between 'start' and 'end', the
+/// attribute is printed next to the criticality of the node
+pub fn mark_single_points(graph: &mut Graph, start: u32, end: u32) -> Vec<u32> {
+    let points = single_points(&graph.links_map(), start, end);
+    for id in points.iter() {
+        graph.set_node_attr(id, SINGLE_POINT_ATTR, AttrValue::Bool(true));
+    }
+    points
+}
+
+/// The edges that lie on every path from 'start' to 'end', from the closest to 'start' to the
+/// closest to 'end'. Empty if 'end' can't be reached from 'start'.
+pub fn bridges(l_map: &LinkMap, start: u32, end: u32) -> Vec<(u32, u32)> {
+    if start == end || !Graph::get_bfs_path(l_map, start).contains(&end) {
+        return vec![];
+    }
+    // Every path passes the dominators in this order, so a bridge has to link two of them
+    let mut chain = vec![end];
+    chain.extend(single_points(l_map, start, end));
+    chain.push(start);
+    chain.reverse();
+    chain.windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .filter(|(from, to)| l_map.parents_of(from).contains(to))
+        .filter(|edge| !reaches_without(l_map, start, end, *edge))
+        .collect()
+}
+
+/// Whether 'end' can be reached from 'start' along every edge but 'skipped'
+fn reaches_without(l_map: &LinkMap, start: u32, end: u32, skipped: (u32, u32)) -> bool {
+    let mut visited: HashSet<u32> = HashSet::from([start]);
+    let mut stack: Vec<u32> = vec![start];
+    while let Some(id) = stack.pop() {
+        if id == end {
+            return true;
+        }
+        for parent in l_map.parents_of(&id) {
+            if (id, *parent) != skipped && visited.insert(*parent) {
+                stack.push(*parent);
+            }
+        }
+    }
+    false
+}
+
+/// Nearest common dominator of the nodes at the indexes 'a' and 'b'
+fn intersect(idom: &[Option<usize>], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while a > b {
+            a = idom[a].unwrap_or(0);
+        }
+        while b > a {
+            b = idom[b].unwrap_or(0);
+        }
+    }
+    a
+}
+
+/// The nodes reached from 'start' along the edges in reverse post order, 'start' first
+fn reverse_post_order(l_map: &LinkMap, start: u32) -> Vec<u32> {
+    let mut order = vec![];
+    let mut visited: HashSet<u32> = HashSet::from([start]);
+    // (node, index of the next parent to visit)
+    let mut stack: Vec<(u32, usize)> = vec![(start, 0)];
+    while let Some((id, next)) = stack.pop() {
+        match l_map.parents_of(&id).get(next) {
+            Some(parent) => {
+                stack.push((id, next + 1));
+                if visited.insert(*parent) {
+                    stack.push((*parent, 0));
+                }
+            }
+            None => order.push(id),
+        }
+    }
+    order.reverse();
+    order
+}
@@ -0,0 +1,61 @@
+//! Estimating the cost of a sampled analysis without running it.
+
+use std::time::{Duration, Instant};
+use crate::analyses::criticality::StateEvaluator;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::analyses::limits::estimated_state_bytes;
+
+/// Time spent measuring the throughput of the evaluator
+pub const CALIBRATION_TIME: Duration = Duration::from_millis(200);
+/// States evaluated at most while measuring the throughput
+pub const CALIBRATION_STATES: u64 = 10_000;
+
+/// Size and projected runtime of an analysis sampling 'samples' states
+#[derive(Debug, Clone)]
+pub struct CostEstimate {
+    pub dynamic_nodes: usize,
+    /// Number of distinct visibility states, 2 to the power of the dynamic nodes
+    pub state_space: f64,
+    pub samples: u64,
+    /// Unique states the visited set holds at most
+    pub visited_states: u64,
+    /// Estimated bytes of the visited set
+    pub visited_bytes: u64,
+    /// Measured states evaluated per second on all threads
+    pub states_per_second: f64,
+    pub projected: Duration,
+}
+
+impl CostEstimate {
+    /// Measures the throughput by evaluating the states of the 'vis_gen' for at most
+    /// ['CALIBRATION_TIME'] on one thread and assumes 'threads' threads scale linearly
+    pub fn measure(evaluator: &mut dyn StateEvaluator, vis_gen: &mut dyn VisGen, dynamic_nodes: usize, samples: u64, threads: u8) -> CostEstimate {
+        let start = Instant::now();
+        let mut measured = 0;
+        while measured < CALIBRATION_STATES && start.elapsed() < CALIBRATION_TIME {
+            evaluator.evaluate(&vis_gen.next_states());
+            measured += 1;
+        }
+        let states_per_second = measured as f64 / start.elapsed().as_secs_f64().max(1e-9) * threads.max(1) as f64;
+        let state_space = 2f64.powi(dynamic_nodes.min(i32::MAX as usize) as i32);
+        let visited_states = (samples as f64).min(state_space) as u64;
+        CostEstimate {
+            dynamic_nodes,
+            state_space,
+            samples,
+            visited_states,
+            visited_bytes: visited_states * estimated_state_bytes(dynamic_nodes),
+            states_per_second,
+            projected: Duration::from_secs_f64(visited_states as f64 / states_per_second),
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Dynamic nodes: {}", self.dynamic_nodes);
+        println!("State space: 2^{} = {:e} states", self.dynamic_nodes, self.state_space);
+        println!("Samples: {}, unique states visited: at most {}", self.samples, self.visited_states);
+        println!("Estimated memory of the visited set: {:.1} MiB", self.visited_bytes as f64 / (1024.0 * 1024.0));
+        println!("Measured throughput: {:.0} states per second", self.states_per_second);
+        println!("Projected runtime: {:?}", self.projected);
+    }
+}
@@ -0,0 +1,80 @@
+//! Event trees chained to the outcome of the end node.
+//!
+//! An event tree is flattened into its sequences. Every sequence starts with the outcome of the
+//! end node, success while it is operable and failure while it isn't, and continues with the
+//! branch probabilities of the following events given that outcome. Since the expected
+//! consequence is linear in the end node operability, the contribution of a node follows
+//! directly from its criticality.
+
+use crate::analyses::criticality::CriticalityResults;
+use crate::network::NodeValueMap;
+
+/// Outcome of the end node a sequence starts with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+/// A path through the event tree
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    pub name: String,
+    pub outcome: Outcome,
+    /// Product of the branch probabilities after the end node outcome
+    pub probability: f64,
+    /// Weight of the sequence, e.g. its cost or damage
+    pub consequence: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventTree {
+    pub sequences: Vec<Sequence>,
+}
+
+/// Risk numbers of an event tree for one set of criticality results
+#[derive(Debug, Clone)]
+pub struct ConsequenceResults {
+    /// Sum of probability times consequence over all sequences
+    pub expected_consequence: f64,
+    /// (name, probability) of every sequence, including the end node outcome
+    pub sequences: Vec<(String, f64)>,
+    /// Increase of the expected consequence while a node is not visible compared to while it is
+    pub contributions: NodeValueMap<f64>,
+}
+
+impl EventTree {
+    /// Expected consequence of the sequences starting with the given 'outcome', as if the
+    /// outcome were certain
+    fn conditional_consequence(&self, outcome: Outcome) -> f64 {
+        self.sequences.iter()
+            .filter(|s| s.outcome == outcome)
+            .map(|s| s.probability * s.consequence)
+            .sum()
+    }
+
+    /// Expected consequence when the end node is operable with the chance 'end_operability'
+    pub fn expected_consequence(&self, end_operability: f64) -> f64 {
+        end_operability * self.conditional_consequence(Outcome::Success)
+            + (1.0 - end_operability) * self.conditional_consequence(Outcome::Failure)
+    }
+
+    pub fn evaluate(&self, results: &CriticalityResults) -> ConsequenceResults {
+        let operability = results.end_op_mean;
+        let sequences = self.sequences.iter().map(|s| {
+            let outcome_chance = match s.outcome {
+                Outcome::Success => operability,
+                Outcome::Failure => 1.0 - operability,
+            };
+            (s.name.to_string(), outcome_chance * s.probability)
+        }).collect();
+        let contributions = results.nodes.iter()
+            .map(|(id, node)| (*id, self.expected_consequence(node.mean_end_off) - self.expected_consequence(node.mean_end_on)))
+            .collect();
+        ConsequenceResults {
+            expected_consequence: self.expected_consequence(operability),
+            sequences,
+            contributions,
+        }
+    }
+}
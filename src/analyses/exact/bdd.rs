@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+/// Reference to a node of a ['Bdd']
+pub type BddRef = usize;
+
+/// The constant false function
+pub const FALSE: BddRef = 0;
+/// The constant true function
+pub const TRUE: BddRef = 1;
+
+/// Variable index of the terminals, ordered after every real variable
+const TERMINAL_VAR: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Op {
+    And,
+    Or,
+}
+
+/// Reduced ordered binary decision diagram. Variables are ordered by their index, every function
+/// is represented by a single shared node so equal functions have equal references.
+#[derive(Debug, Clone)]
+pub struct Bdd {
+    /// (variable, low child, high child) of every node, the first two are the terminals
+    nodes: Vec<(usize, BddRef, BddRef)>,
+    unique: HashMap<(usize, BddRef, BddRef), BddRef>,
+    cache: HashMap<(Op, BddRef, BddRef), BddRef>,
+}
+
+impl Default for Bdd {
+    fn default() -> Self {
+        Bdd::new()
+    }
+}
+
+impl Bdd {
+    pub fn new() -> Bdd {
+        Bdd {
+            nodes: vec![(TERMINAL_VAR, FALSE, FALSE), (TERMINAL_VAR, TRUE, TRUE)],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Number of nodes, including the two terminals
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn make(&mut self, var: usize, low: BddRef, high: BddRef) -> BddRef {
+        if low == high {
+            return low;
+        }
+        if let Some(node) = self.unique.get(&(var, low, high)) {
+            return *node;
+        }
+        self.nodes.push((var, low, high));
+        let node = self.nodes.len() - 1;
+        self.unique.insert((var, low, high), node);
+        node
+    }
+
+    /// The function that is true exactly when the variable 'var' is
+    pub fn variable(&mut self, var: usize) -> BddRef {
+        self.make(var, FALSE, TRUE)
+    }
+
+    pub fn and(&mut self, a: BddRef, b: BddRef) -> BddRef {
+        self.apply(Op::And, a, b)
+    }
+
+    pub fn or(&mut self, a: BddRef, b: BddRef) -> BddRef {
+        self.apply(Op::Or, a, b)
+    }
+
+    /// The function that is true when at least 'min' of the 'functions' are
+    pub fn at_least(&mut self, min: usize, functions: &[BddRef]) -> BddRef {
+        // counts[k] is true when at least k of the functions seen so far are
+        let mut counts: Vec<BddRef> = vec![FALSE; min + 1];
+        counts[0] = TRUE;
+        for function in functions {
+            for k in (1..=min).rev() {
+                let with = self.and(*function, counts[k - 1]);
+                counts[k] = self.or(counts[k], with);
+            }
+        }
+        counts[min]
+    }
+
+    fn apply(&mut self, op: Op, a: BddRef, b: BddRef) -> BddRef {
+        match (op, a, b) {
+            (Op::And, FALSE, _) | (Op::And, _, FALSE) => return FALSE,
+            (Op::And, TRUE, x) | (Op::And, x, TRUE) => return x,
+            (Op::Or, TRUE, _) | (Op::Or, _, TRUE) => return TRUE,
+            (Op::Or, FALSE, x) | (Op::Or, x, FALSE) => return x,
+            _ if a == b => return a,
+            _ => {}
+        }
+        let key = (op, a.min(b), a.max(b));
+        if let Some(result) = self.cache.get(&key) {
+            return *result;
+        }
+        let (a_var, a_low, a_high) = self.nodes[a];
+        let (b_var, b_low, b_high) = self.nodes[b];
+        let var = a_var.min(b_var);
+        let (a_low, a_high) = if a_var == var { (a_low, a_high) } else { (a, a) };
+        let (b_low, b_high) = if b_var == var { (b_low, b_high) } else { (b, b) };
+        let low = self.apply(op, a_low, b_low);
+        let high = self.apply(op, a_high, b_high);
+        let result = self.make(var, low, high);
+        self.cache.insert(key, result);
+        result
+    }
+
+    /// The function 'root' with the variable 'var' fixed to 'value'
+    pub fn restrict(&mut self, root: BddRef, var: usize, value: bool) -> BddRef {
+        let mut memo = HashMap::new();
+        self.restrict_memo(root, var, value, &mut memo)
+    }
+
+    fn restrict_memo(&mut self, node: BddRef, var: usize, value: bool, memo: &mut HashMap<BddRef, BddRef>) -> BddRef {
+        let (node_var, low, high) = self.nodes[node];
+        // Variables are ordered, nothing below a later variable depends on 'var'
+        if node_var > var {
+            return node;
+        }
+        if node_var == var {
+            return if value { high } else { low };
+        }
+        if let Some(result) = memo.get(&node) {
+            return *result;
+        }
+        let new_low = self.restrict_memo(low, var, value, memo);
+        let new_high = self.restrict_memo(high, var, value, memo);
+        let result = self.make(node_var, new_low, new_high);
+        memo.insert(node, result);
+        result
+    }
+
+    /// Value of 'root' when every variable 'var' has the value 'values(var)'
+    pub fn evaluate<F: Fn(usize) -> bool>(&self, root: BddRef, values: F) -> bool {
+        let mut node = root;
+        while node != FALSE && node != TRUE {
+            let (var, low, high) = self.nodes[node];
+            node = if values(var) { high } else { low };
+        }
+        node == TRUE
+    }
+
+    /// Probability of 'root' being true when every variable is independently true with the
+    /// probability 'true_chances[var]'
+    pub fn probability(&self, root: BddRef, true_chances: &[f64]) -> f64 {
+        let mut memo: HashMap<BddRef, f64> = HashMap::from([(FALSE, 0.0), (TRUE, 1.0)]);
+        self.probability_memo(root, true_chances, &mut memo)
+    }
+
+    fn probability_memo(&self, node: BddRef, true_chances: &[f64], memo: &mut HashMap<BddRef, f64>) -> f64 {
+        if let Some(p) = memo.get(&node) {
+            return *p;
+        }
+        let (var, low, high) = self.nodes[node];
+        let p_low = self.probability_memo(low, true_chances, memo);
+        let p_high = self.probability_memo(high, true_chances, memo);
+        let p = true_chances[var] * p_high + (1.0 - true_chances[var]) * p_low;
+        memo.insert(node, p);
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_functions_are_the_same_node() {
+        let mut bdd = Bdd::new();
+        let (x, y) = (bdd.variable(0), bdd.variable(1));
+        let xy = bdd.and(x, y);
+        let yx = bdd.and(y, x);
+        assert_eq!(xy, yx);
+        let either = bdd.or(x, y);
+        assert_eq!(bdd.or(xy, either), either);
+        assert_eq!(bdd.and(xy, either), xy);
+        assert_eq!(bdd.or(x, TRUE), TRUE);
+        assert_eq!(bdd.and(x, FALSE), FALSE);
+        // x, y, x and y, x or y and the terminals
+        assert_eq!(bdd.size(), 6);
+    }
+
+    #[test]
+    fn at_least_counts_the_true_functions() {
+        let mut bdd = Bdd::new();
+        let variables: Vec<BddRef> = (0..3).map(|var| bdd.variable(var)).collect();
+        let two = bdd.at_least(2, &variables);
+        for bits in 0..8usize {
+            let expected = bits.count_ones() >= 2;
+            assert_eq!(bdd.evaluate(two, |var| bits >> var & 1 == 1), expected);
+        }
+        assert_eq!(bdd.at_least(0, &variables), TRUE);
+        assert_eq!(bdd.at_least(4, &variables), FALSE);
+    }
+
+    #[test]
+    fn probabilities_and_restrictions_follow_the_function() {
+        let mut bdd = Bdd::new();
+        let (x, y, z) = (bdd.variable(0), bdd.variable(1), bdd.variable(2));
+        // (x and y) or z
+        let xy = bdd.and(x, y);
+        let root = bdd.or(xy, z);
+        let chances = [0.5, 0.4, 0.1];
+        let p = bdd.probability(root, &chances);
+        assert!((p - (1.0 - (1.0 - 0.2) * (1.0 - 0.1))).abs() < 1e-12);
+        let without_z = bdd.restrict(root, 2, false);
+        assert_eq!(without_z, xy);
+        assert_eq!(bdd.restrict(root, 2, true), TRUE);
+        let with_x = bdd.restrict(root, 0, true);
+        assert_eq!(with_x, bdd.or(y, z));
+    }
+}
@@ -0,0 +1,153 @@
+//! Minimal cut sets of the end node and their probabilities.
+//!
+//! A cut set is a set of dynamic nodes whose failure alone makes the end node inoperable, it is
+//! minimal if no smaller set does. The operability of the end node is compiled to a decision
+//! diagram as for the ['crate::analyses::exact::ExactCriticality'], and the sets of nodes are
+//! tried from the smallest to the largest, leaving out the supersets of the cut sets already
+//! found. The probability of a cut set is the product of the off chances of its nodes.
+//!
+//! Two controls truncate the enumeration, as the number of sets grows exponentially with their
+//! size: the 'max_order' bounds the size of a cut set, and sets less likely than the 'cutoff' are
+//! left out together with their supersets, which can only be less likely. The sum of the
+//! probabilities of the remaining cut sets is the rare event approximation of the unreliability
+//! of the end node, which is reported next to its exact value.
+
+use std::collections::HashSet;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::analyses::exact::bdd::{Bdd, BddRef};
+use crate::analyses::exact::compile_end_node;
+use crate::errors::analysis::ThorError;
+use crate::network::{Graph, LinkMap, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// Largest cut sets enumerated when no order is given
+pub const DEFAULT_MAX_ORDER: usize = 3;
+
+/// Probability below which cut sets are left out when no cutoff is given
+pub const DEFAULT_CUTOFF: f64 = 1e-12;
+
+/// Ranks the minimal cut sets of the end node by their probability, see the module documentation
+pub struct CutSets {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Chance of every dynamic node to not be visible
+    pub off_chances: NodeValueMap<f32>,
+    /// Must be equivalent to a ['crate::roll_up::BooleanGate']
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub l_map: LinkMap,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// Largest number of nodes in a cut set
+    pub max_order: usize,
+    /// Cut sets less likely than this are left out
+    pub cutoff: f64,
+}
+
+/// The minimal cut sets found within the truncation
+#[derive(Debug, Clone)]
+pub struct CutSetResults {
+    /// Nodes of every cut set in ascending order with its probability, most likely first
+    pub cut_sets: Vec<(Vec<u32>, f64)>,
+    /// Exact chance that the end node is inoperable
+    pub unreliability: f64,
+    pub max_order: usize,
+    pub cutoff: f64,
+}
+
+impl CutSetResults {
+    /// Sum of the probabilities of the cut sets, an upper bound of the unreliability if no cut
+    /// set was truncated
+    pub fn rare_event_approximation(&self) -> f64 {
+        self.cut_sets.iter().map(|(_, p)| p).sum()
+    }
+
+    pub fn print(&self, graph: &Graph) {
+        let approximation = self.rare_event_approximation();
+        println!("Exact unreliability of the end node: {}", self.unreliability);
+        println!("Rare event approximation over {} minimal cut sets of at most {} nodes and a probability of at least {}: {}",
+                 self.cut_sets.len(), self.max_order, self.cutoff, approximation);
+        for (rank, (nodes, probability)) in self.cut_sets.iter().enumerate() {
+            let names: Vec<String> = nodes.iter()
+                .map(|id| format!("{} ({})", graph.get_node(id).map(|n| n.name.as_str()).unwrap_or(""), id))
+                .collect();
+            let share = match approximation > 0.0 {
+                true => 100.0 * probability / approximation,
+                false => 0.0,
+            };
+            println!("{}: probability {} ({:.2}%): {}", rank + 1, probability, share, names.join(", "));
+        }
+    }
+}
+
+impl Analysis for CutSets {
+    type Output = CutSetResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<CutSetResults, ThorError> {
+        info!("Starting Cut Set Analysis");
+        let (bdd, root, variables) = compile_end_node(&self.l_map, &self.dynamic_ids, self.roll_up_rule.as_ref(),
+                                                      self.start_id, self.end_id, "cut set")?;
+        info!("Compiled the end node to a decision diagram of {} nodes", bdd.size());
+        let off_chances: Vec<f64> = variables.iter()
+            .map(|id| *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE) as f64)
+            .collect();
+        let on_chances: Vec<f64> = off_chances.iter().map(|p| 1.0 - p).collect();
+        // Variables in the order of their node ids, so the nodes of every cut set are ascending
+        let mut candidates: Vec<usize> = (0..variables.len()).collect();
+        candidates.sort_by_key(|var| variables[*var]);
+
+        let mut search = Search { bdd: &bdd, root, off_chances: &off_chances, cutoff: self.cutoff, cut_sets: vec![] };
+        // Every cut set of an order is found before the larger ones, which are minimal if they
+        // hold none of them
+        for order in 1..=self.max_order.min(candidates.len()) {
+            ctx.check_cancelled()?;
+            search.extend(&candidates, &mut vec![], 1.0, order);
+        }
+        let mut cut_sets: Vec<(Vec<u32>, f64)> = search.cut_sets.into_iter()
+            .map(|(vars, probability)| (vars.iter().map(|var| variables[*var]).collect(), probability))
+            .collect();
+        cut_sets.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        Ok(CutSetResults {
+            cut_sets,
+            unreliability: 1.0 - bdd.probability(root, &on_chances),
+            max_order: self.max_order,
+            cutoff: self.cutoff,
+        })
+    }
+}
+
+/// State of the enumeration of the cut sets
+struct Search<'a> {
+    bdd: &'a Bdd,
+    root: BddRef,
+    off_chances: &'a [f64],
+    cutoff: f64,
+    /// Variables of the cut sets found so far and their probability
+    cut_sets: Vec<(Vec<usize>, f64)>,
+}
+
+impl Search<'_> {
+    /// Adds the cut sets of 'order' variables that start with the variables of 'set', whose
+    /// probability is 'probability', and continue with the 'candidates'
+    fn extend(&mut self, candidates: &[usize], set: &mut Vec<usize>, probability: f64, order: usize) {
+        if set.len() == order {
+            if !self.bdd.evaluate(self.root, |var| !set.contains(&var)) {
+                self.cut_sets.push((set.clone(), probability));
+            }
+            return;
+        }
+        for (i, var) in candidates.iter().enumerate() {
+            let probability = probability * self.off_chances[*var];
+            if probability < self.cutoff {
+                continue;
+            }
+            set.push(*var);
+            // Supersets of a cut set are never minimal
+            if !self.cut_sets.iter().any(|(cut, _)| cut.iter().all(|v| set.contains(v))) {
+                self.extend(&candidates[i + 1..], set, probability, order);
+            }
+            set.pop();
+        }
+    }
+}
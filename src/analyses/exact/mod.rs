@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult, write_outputs};
+use crate::analyses::criticality::histogram::Histogram;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::analyses::exact::bdd::{Bdd, BddRef, TRUE};
+use crate::errors::analysis::{ThorError, UnsupportedRuleError};
+use crate::network::{Graph, LinkMap, Links, NodeValueMap};
+use crate::output::Output;
+use crate::roll_up::{BooleanGate, RollUp};
+
+pub mod bdd;
+pub mod cut_sets;
+pub mod tornado;
+
+/// Exact counterpart of the criticality analysis. The operability of the end node is compiled to
+/// a binary decision diagram over the visibility of the dynamic nodes, from which the end node
+/// reliability and the importance of every node are computed without sampling.
+///
+/// The results use the layout of the criticality analysis: the mean end value is the reliability
+/// of the end node and the criticality of a node is its Birnbaum importance, the reliability with
+/// the node visible minus the reliability with the node not visible. No states are rolled up so
+/// the state and visibility counts are zero.
+pub struct ExactCriticality {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Chance of every dynamic node to not be visible
+    pub off_chances: NodeValueMap<f32>,
+    /// Must be equivalent to a ['BooleanGate']
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub l_map: LinkMap,
+    pub start_id: u32,
+    pub end_id: u32,
+    pub outputs: Vec<Box<dyn Output>>,
+}
+
+impl ExactCriticality {
+    /// Compiles the operability of the end node, see ['compile_end_node']
+    ///
+    /// # Errors
+    ///
+    /// Returns an ['UnsupportedRuleError'] if the roll up rule of a node isn't a boolean gate
+    pub fn compile(&self) -> Result<(Bdd, BddRef, Vec<u32>), UnsupportedRuleError> {
+        compile_end_node(&self.l_map, &self.dynamic_ids, self.roll_up_rule.as_ref(), self.start_id, self.end_id, "exact")
+    }
+
+    fn compute(&self, ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+        let (mut bdd, root, variables) = self.compile()?;
+        info!("Compiled the end node to a decision diagram of {} nodes", bdd.size());
+        let on_chances: Vec<f64> = variables.iter()
+            .map(|id| 1.0 - *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE) as f64)
+            .collect();
+
+        let mut nodes = NodeValueMap::new();
+        for id in self.dynamic_ids.iter() {
+            ctx.check_cancelled()?;
+            let (mean_end_on, mean_end_off) = match variables.iter().position(|v| v == id) {
+                Some(var) => {
+                    let on = bdd.restrict(root, var, true);
+                    let off = bdd.restrict(root, var, false);
+                    (bdd.probability(on, &on_chances), bdd.probability(off, &on_chances))
+                }
+                // Nodes that aren't rolled up have no influence on the end node
+                None => {
+                    let p = bdd.probability(root, &on_chances);
+                    (p, p)
+                }
+            };
+            nodes.insert(*id, NodeCritResult {
+                on_count: 0,
+                off_count: 0,
+                mean_end_on,
+                mean_end_off,
+                criticality: mean_end_on - mean_end_off,
+            });
+        }
+        Ok(CriticalityResults {
+            row_count: 0,
+            end_op_mean: bdd.probability(root, &on_chances),
+            end_op_histogram: Histogram::default(),
+            nodes,
+        })
+    }
+}
+
+impl Analysis for ExactCriticality {
+    type Output = CriticalityResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+        info!("Starting Exact Criticality Analysis");
+        let results = self.compute(ctx)?;
+        write_outputs(&self.outputs, &self.graph, &results);
+        Ok(results)
+    }
+}
+
+/// Compiles the operability of the 'end_id' node, returning the diagram, its root and the dynamic
+/// node of every variable
+///
+/// # Errors
+///
+/// Returns an ['UnsupportedRuleError'] naming the 'analysis' if the roll up rule of a node isn't a
+/// boolean gate
+pub fn compile_end_node(l_map: &LinkMap, dynamic_ids: &HashSet<u32>, roll_up_rule: &dyn RollUp, start_id: u32, end_id: u32,
+                        analysis: &str) -> Result<(Bdd, BddRef, Vec<u32>), UnsupportedRuleError> {
+    // Nodes are compiled in the order they are rolled up, which is also the variable order
+    let path = Graph::get_bfs_path(l_map, start_id);
+    let variables: Vec<u32> = path.iter().filter(|id| dynamic_ids.contains(id)).copied().collect();
+
+    let mut bdd = Bdd::new();
+    let mut functions: NodeValueMap<BddRef> = NodeValueMap::new();
+    for node in path.iter() {
+        let children = l_map.children_of(node);
+        if children.is_empty() {
+            functions.insert(*node, TRUE);
+            continue;
+        }
+        // Children that aren't rolled up yet are operable, as in the roll up rules
+        let child_functions: Vec<BddRef> = children.iter().map(|c| *functions.get(c).unwrap_or(&TRUE)).collect();
+        let gate = roll_up_rule.boolean_gate(node)
+            .ok_or_else(|| UnsupportedRuleError { analysis: analysis.to_string() })?;
+        let mut function = match gate {
+            BooleanGate::Or => child_functions.into_iter().fold(bdd::FALSE, |f, c| bdd.or(f, c)),
+            BooleanGate::And => child_functions.into_iter().fold(TRUE, |f, c| bdd.and(f, c)),
+            BooleanGate::AtLeast(min) => bdd.at_least(min, &child_functions),
+        };
+        if let Some(var) = variables.iter().position(|id| id == node) {
+            let visible = bdd.variable(var);
+            function = bdd.and(visible, function);
+        }
+        functions.insert(*node, function);
+    }
+    let root = *functions.get(&end_id).unwrap_or(&bdd::FALSE);
+    Ok((bdd, root, variables))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roll_up::{NoisyOrRule, OrRule};
+
+    /// start -> a -> end and start -> b -> end
+    fn parallel() -> Graph {
+        let mut graph = Graph::new();
+        for (name, id) in [("start", 0), ("a", 1), ("b", 2), ("end", 3)] {
+            graph.add_node(name.to_string(), id);
+        }
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    fn exact(roll_up_rule: Box<dyn RollUp>) -> ExactCriticality {
+        let graph = parallel();
+        ExactCriticality {
+            l_map: graph.links_map(),
+            graph,
+            dynamic_ids: HashSet::from([1, 2]),
+            off_chances: NodeValueMap::from([(1, 0.1), (2, 0.2)]),
+            roll_up_rule,
+            start_id: 0,
+            end_id: 3,
+            outputs: vec![],
+        }
+    }
+
+    #[test]
+    fn reliability_and_importance_are_computed_without_sampling() {
+        let results = exact(Box::new(OrRule {})).run(&AnalysisContext::default()).unwrap();
+        // The off chances are f32, the results are exact up to their precision
+        assert_eq!(results.row_count, 0);
+        // The end node is down only while both a and b are
+        assert!((results.end_op_mean - (1.0 - 0.1 * 0.2)).abs() < 1e-6);
+        // Birnbaum importance: a matters only while b is down and the other way around
+        assert!((results.nodes[&1].criticality - 0.2).abs() < 1e-6);
+        assert!((results.nodes[&2].criticality - 0.1).abs() < 1e-6);
+        assert!((results.nodes[&1].mean_end_off - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rules_without_a_boolean_gate_are_refused() {
+        let rule = NoisyOrRule { alpha: Default::default() };
+        let error = exact(Box::new(rule)).compile().err().unwrap();
+        assert_eq!(error.analysis, "exact");
+    }
+}
@@ -0,0 +1,142 @@
+//! One at a time sensitivity of the end node reliability to the off chance of every node, the
+//! data of a tornado chart.
+//!
+//! Every dynamic node with a pessimistic and an optimistic off chance, read from the node
+//! attributes ['PESSIMISTIC_ATTR'] and ['OPTIMISTIC_ATTR'] of the input, is set to each bound in
+//! turn while the other nodes keep their off chance. The end node reliability is computed exactly
+//! from the decision diagram of the ['crate::analyses::exact::ExactCriticality'], which is
+//! compiled once, so the ranges are free of sampling noise and the analysis takes two evaluations
+//! of the diagram per node. The nodes are ranked by the width of their range, the widest bar on
+//! top of the chart.
+
+use std::collections::HashSet;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::analyses::exact::compile_end_node;
+use crate::errors::analysis::ThorError;
+use crate::network::{Graph, LinkMap, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// Node attribute holding the pessimistic, largest, off chance of a node
+pub const PESSIMISTIC_ATTR: &str = "off_chance_high";
+
+/// Node attribute holding the optimistic, smallest, off chance of a node
+pub const OPTIMISTIC_ATTR: &str = "off_chance_low";
+
+pub struct Tornado {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Chance of every dynamic node to not be visible
+    pub off_chances: NodeValueMap<f32>,
+    /// (pessimistic, optimistic) off chance of every node that is varied
+    pub bounds: NodeValueMap<(f64, f64)>,
+    /// Must be equivalent to a ['crate::roll_up::BooleanGate']
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub l_map: LinkMap,
+    pub start_id: u32,
+    pub end_id: u32,
+}
+
+/// Range of the end node reliability over the bounds of a node
+#[derive(Debug, Clone)]
+pub struct TornadoBar {
+    pub id: u32,
+    pub pessimistic_off_chance: f64,
+    pub optimistic_off_chance: f64,
+    /// End node reliability with the node at its pessimistic off chance
+    pub pessimistic: f64,
+    /// End node reliability with the node at its optimistic off chance
+    pub optimistic: f64,
+}
+
+impl TornadoBar {
+    /// Width of the range
+    pub fn swing(&self) -> f64 {
+        (self.optimistic - self.pessimistic).abs()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TornadoResults {
+    /// End node reliability with every node at its off chance
+    pub base: f64,
+    /// Bars from the widest to the narrowest range
+    pub bars: Vec<TornadoBar>,
+    /// Dynamic nodes without both bounds, which weren't varied
+    pub unbounded: Vec<u32>,
+}
+
+impl TornadoResults {
+    pub fn print(&self, graph: &Graph) {
+        println!("End node reliability {} with every node at its off chance", self.base);
+        for bar in self.bars.iter() {
+            let name = graph.get_node(&bar.id).map(|n| n.name.as_str()).unwrap_or("");
+            println!("{} ({}): {} at off chance {} to {} at off chance {}, swing {}",
+                     name, bar.id, bar.pessimistic, bar.pessimistic_off_chance, bar.optimistic, bar.optimistic_off_chance, bar.swing());
+        }
+        if !self.unbounded.is_empty() {
+            println!("{} dynamic nodes have no '{}' and '{}' attributes and weren't varied", self.unbounded.len(), PESSIMISTIC_ATTR, OPTIMISTIC_ATTR);
+        }
+    }
+}
+
+/// Reads the (pessimistic, optimistic) off chance of every node that has both attributes
+///
+/// # Errors
+///
+/// Returns the reason if a bound isn't an off chance between 0 and 1
+pub fn bounds_from_attributes(graph: &Graph, pessimistic_attr: &str, optimistic_attr: &str) -> Result<NodeValueMap<(f64, f64)>, String> {
+    let mut bounds = NodeValueMap::new();
+    for id in graph.get_node_ids() {
+        let (Some(pessimistic), Some(optimistic)) = (graph.get_node_attr_f64(&id, pessimistic_attr), graph.get_node_attr_f64(&id, optimistic_attr)) else { continue };
+        if !(0.0..=1.0).contains(&pessimistic) || !(0.0..=1.0).contains(&optimistic) {
+            return Err(format!("The off chance bounds {} and {} of node {} must be between 0 and 1", pessimistic, optimistic, id));
+        }
+        bounds.insert(id, (pessimistic, optimistic));
+    }
+    Ok(bounds)
+}
+
+impl Analysis for Tornado {
+    type Output = TornadoResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<TornadoResults, ThorError> {
+        info!("Starting Tornado Analysis");
+        let (bdd, root, variables) = compile_end_node(&self.l_map, &self.dynamic_ids, self.roll_up_rule.as_ref(),
+                                                      self.start_id, self.end_id, "tornado")?;
+        info!("Compiled the end node to a decision diagram of {} nodes", bdd.size());
+        let mut on_chances: Vec<f64> = variables.iter()
+            .map(|id| 1.0 - *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE) as f64)
+            .collect();
+        let base = bdd.probability(root, &on_chances);
+
+        let mut ids: Vec<u32> = self.dynamic_ids.iter().copied().collect();
+        ids.sort();
+        let mut bars = vec![];
+        let mut unbounded = vec![];
+        for id in ids {
+            ctx.check_cancelled()?;
+            let Some((pessimistic_off_chance, optimistic_off_chance)) = self.bounds.get(&id).copied() else {
+                unbounded.push(id);
+                continue;
+            };
+            let (pessimistic, optimistic) = match variables.iter().position(|v| *v == id) {
+                Some(var) => {
+                    let on_chance = on_chances[var];
+                    on_chances[var] = 1.0 - pessimistic_off_chance;
+                    let pessimistic = bdd.probability(root, &on_chances);
+                    on_chances[var] = 1.0 - optimistic_off_chance;
+                    let optimistic = bdd.probability(root, &on_chances);
+                    on_chances[var] = on_chance;
+                    (pessimistic, optimistic)
+                }
+                // Nodes that aren't rolled up have no influence on the end node
+                None => (base, base),
+            };
+            bars.push(TornadoBar { id, pessimistic_off_chance, optimistic_off_chance, pessimistic, optimistic });
+        }
+        bars.sort_by(|a, b| b.swing().total_cmp(&a.swing()).then(a.id.cmp(&b.id)));
+        Ok(TornadoResults { base, bars, unbounded })
+    }
+}
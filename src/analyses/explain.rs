@@ -0,0 +1,182 @@
+//! Explaining the criticality of a single node from the results of a run and the structure of
+//! the graph.
+//!
+//! The criticality of a node is the mean end operability of the states where it is on minus that
+//! of the states where it is off. The explanation lists both means with the number of states
+//! behind them and the rank of the node, then the reasons the graph gives for the score: whether
+//! the node lies on a path from the start to the end node at all, how many of those paths pass
+//! through it, the shortest of them, and whether every path passes through it.
+//!
+//! Paths are counted in the order of a topological sort of the nodes on paths from the start to
+//! the end, so they are only counted if those nodes hold no cycle.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult};
+use crate::analyses::criticality::ranking::rank;
+use crate::analyses::dominators::{bridges, single_points};
+use crate::network::{Graph, LinkMap, Links};
+
+/// Why a node scored the criticality it did
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub id: u32,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// Results of the node, None if it wasn't analysed, such as the start and end nodes
+    pub result: Option<NodeCritResult>,
+    /// Position of the node from the most critical, starting at one, and the number of nodes
+    pub rank: Option<(usize, usize)>,
+    /// Number of states of the run
+    pub states: u64,
+    /// Mean end operability over all states
+    pub end_op_mean: f64,
+    /// (paths through the node, all paths) from the start to the end node, None if the nodes on
+    /// those paths hold a cycle. Counts are floats as they grow exponentially with the depth.
+    pub paths: Option<(f64, f64)>,
+    /// Shortest path from the start to the end node through the node, None if there is none
+    pub shortest_path: Option<Vec<u32>>,
+    /// Whether every path from the start to the end node passes through the node
+    pub single_point: bool,
+    /// Edges of the node that every path from the start to the end node passes through
+    pub bridges: Vec<(u32, u32)>,
+}
+
+impl Explanation {
+    /// Explains the criticality of the node 'id' in the 'results' of analysing the 'graph'
+    /// between 'start_id' and 'end_id'
+    pub fn new(graph: &Graph, results: &CriticalityResults, id: u32, start_id: u32, end_id: u32) -> Explanation {
+        let l_map = graph.links_map();
+        let ranked = rank(results);
+        let on_paths = Graph::nodes_on_paths(&l_map, start_id, end_id);
+        let paths = count_paths(&l_map, &on_paths, start_id, end_id)
+            .map(|(from_start, to_end)| {
+                let count = |map: &HashMap<u32, f64>, id: u32| *map.get(&id).unwrap_or(&0.0);
+                (count(&from_start, id) * count(&to_end, id), count(&from_start, end_id))
+            });
+        let shortest_path = match on_paths.contains(&id) {
+            true => shortest_path(&l_map, &on_paths, start_id, id)
+                .zip(shortest_path(&l_map, &on_paths, id, end_id))
+                .map(|(mut to_node, from_node)| {
+                    to_node.extend(from_node.into_iter().skip(1));
+                    to_node
+                }),
+            false => None,
+        };
+        Explanation {
+            id,
+            start_id,
+            end_id,
+            result: results.nodes.get(&id).cloned(),
+            rank: ranked.iter().position(|(ranked_id, _)| *ranked_id == id).map(|i| (i + 1, ranked.len())),
+            states: results.row_count,
+            end_op_mean: results.end_op_mean,
+            paths,
+            shortest_path,
+            single_point: single_points(&l_map, start_id, end_id).contains(&id),
+            bridges: bridges(&l_map, start_id, end_id).into_iter().filter(|(from, to)| *from == id || *to == id).collect(),
+        }
+    }
+
+    /// Prints the explanation, naming the nodes of the 'graph'
+    pub fn print(&self, graph: &Graph) {
+        let name = |id: &u32| format!("{} ({})", graph.get_node(id).map(|n| n.name.as_str()).unwrap_or(""), id);
+        println!("{}", name(&self.id));
+        match (&self.result, self.rank) {
+            (Some(result), Some((rank, count))) => {
+                println!("  criticality {}, rank {} of {}", result.criticality, rank, count);
+                println!("  on in {} of {} states, mean end operability {}", result.on_count, self.states, result.mean_end_on);
+                println!("  off in {} of {} states, mean end operability {}", result.off_count, self.states, result.mean_end_off);
+                if result.off_count == 0 || result.on_count == 0 {
+                    println!("  the node was never {} in a sampled state, its criticality is not measured", match result.off_count {
+                        0 => "off",
+                        _ => "on",
+                    });
+                }
+            }
+            _ => println!("  not analysed, start, end and static nodes have no criticality"),
+        }
+        println!("  mean end operability over all states {}", self.end_op_mean);
+        match (&self.shortest_path, self.paths) {
+            (None, _) => println!("  lies on no path from {} to {}, it can't change the end operability", name(&self.start_id), name(&self.end_id)),
+            (Some(path), paths) => {
+                if let Some((through, total)) = paths {
+                    println!("  lies on {} of {} paths from {} to {}", through, total, name(&self.start_id), name(&self.end_id));
+                }
+                let path: Vec<String> = path.iter().map(name).collect();
+                println!("  shortest path through it: {}", path.join(" -> "));
+            }
+        }
+        if self.single_point {
+            println!("  every path passes through it, it is a single point of failure");
+        }
+        for (from, to) in self.bridges.iter() {
+            println!("  every path passes through the edge {} -> {}", name(from), name(to));
+        }
+    }
+}
+
+/// Number of paths from 'start' to every node and from every node to 'end', over the nodes
+/// 'on_paths' only. None if those nodes hold a cycle.
+fn count_paths(l_map: &LinkMap, on_paths: &HashSet<u32>, start: u32, end: u32) -> Option<(HashMap<u32, f64>, HashMap<u32, f64>)> {
+    let parents = |id: &u32| l_map.parents_of(id).iter().filter(|p| on_paths.contains(p)).copied().collect::<Vec<u32>>();
+    // Kahn's algorithm over the edges between the nodes on paths
+    let mut in_degree: HashMap<u32, usize> = on_paths.iter().map(|id| (*id, 0)).collect();
+    for id in on_paths.iter() {
+        for parent in parents(id) {
+            *in_degree.get_mut(&parent).unwrap() += 1;
+        }
+    }
+    let mut agenda: VecDeque<u32> = in_degree.iter().filter(|(_, d)| **d == 0).map(|(id, _)| *id).collect();
+    let mut order = vec![];
+    while let Some(id) = agenda.pop_front() {
+        order.push(id);
+        for parent in parents(&id) {
+            let degree = in_degree.get_mut(&parent).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                agenda.push_back(parent);
+            }
+        }
+    }
+    if order.len() < on_paths.len() {
+        return None;
+    }
+    let mut from_start: HashMap<u32, f64> = HashMap::from([(start, 1.0)]);
+    for id in order.iter() {
+        let count = *from_start.get(id).unwrap_or(&0.0);
+        for parent in parents(id) {
+            *from_start.entry(parent).or_insert(0.0) += count;
+        }
+    }
+    let mut to_end: HashMap<u32, f64> = HashMap::from([(end, 1.0)]);
+    for id in order.iter().rev() {
+        let count: f64 = parents(id).iter().map(|p| *to_end.get(p).unwrap_or(&0.0)).sum();
+        if *id != end {
+            to_end.insert(*id, count);
+        }
+    }
+    Some((from_start, to_end))
+}
+
+/// Shortest path from 'from' to 'to' over the nodes 'on_paths', found breadth first
+fn shortest_path(l_map: &LinkMap, on_paths: &HashSet<u32>, from: u32, to: u32) -> Option<Vec<u32>> {
+    let mut previous: HashMap<u32, u32> = HashMap::from([(from, from)]);
+    let mut agenda = VecDeque::from([from]);
+    while let Some(id) = agenda.pop_front() {
+        if id == to {
+            let mut path = vec![to];
+            while *path.last().unwrap() != from {
+                path.push(previous[path.last().unwrap()]);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for parent in l_map.parents_of(&id).iter().filter(|p| on_paths.contains(p)) {
+            if !previous.contains_key(parent) {
+                previous.insert(*parent, id);
+                agenda.push_back(*parent);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,121 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Directed flow network solved with Dinic's algorithm. The edges are created once and only their
+/// capacities change between solves, so the same network can evaluate many sampled states.
+#[derive(Debug, Clone)]
+pub struct FlowNetwork {
+    /// Index of every node id
+    index: HashMap<u32, usize>,
+    /// Outgoing residual edges of every node, as indexes into 'to', 'cap' and 'flow'
+    adjacency: Vec<Vec<usize>>,
+    to: Vec<usize>,
+    cap: Vec<f64>,
+    flow: Vec<f64>,
+    level: Vec<i32>,
+    next_edge: Vec<usize>,
+}
+
+const EPSILON: f64 = 1e-9;
+
+impl FlowNetwork {
+    pub fn new(node_ids: &[u32]) -> FlowNetwork {
+        let index: HashMap<u32, usize> = node_ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let count = index.len();
+        FlowNetwork {
+            index,
+            adjacency: vec![vec![]; count],
+            to: vec![],
+            cap: vec![],
+            flow: vec![],
+            level: vec![0; count],
+            next_edge: vec![0; count],
+        }
+    }
+
+    /// Adds an edge and returns its handle for ['FlowNetwork::set_capacity']. Edges between ids
+    /// that are not part of the network are ignored.
+    pub fn add_edge(&mut self, from: u32, to: u32, capacity: f64) -> Option<usize> {
+        let from = *self.index.get(&from)?;
+        let to = *self.index.get(&to)?;
+        let handle = self.to.len();
+        self.adjacency[from].push(handle);
+        self.to.push(to);
+        self.cap.push(capacity);
+        self.flow.push(0.0);
+        // Reverse residual edge
+        self.adjacency[to].push(handle + 1);
+        self.to.push(from);
+        self.cap.push(0.0);
+        self.flow.push(0.0);
+        Some(handle)
+    }
+
+    pub fn set_capacity(&mut self, handle: usize, capacity: f64) {
+        self.cap[handle] = capacity;
+    }
+
+    fn bfs(&mut self, source: usize, sink: usize) -> bool {
+        self.level.iter_mut().for_each(|l| *l = -1);
+        self.level[source] = 0;
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for &e in &self.adjacency[node] {
+                let next = self.to[e];
+                if self.level[next] < 0 && self.cap[e] - self.flow[e] > EPSILON {
+                    self.level[next] = self.level[node] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        self.level[sink] >= 0
+    }
+
+    fn dfs(&mut self, node: usize, sink: usize, pushed: f64) -> f64 {
+        if node == sink {
+            return pushed;
+        }
+        while self.next_edge[node] < self.adjacency[node].len() {
+            let e = self.adjacency[node][self.next_edge[node]];
+            let next = self.to[e];
+            let residual = self.cap[e] - self.flow[e];
+            if self.level[next] == self.level[node] + 1 && residual > EPSILON {
+                let sent = self.dfs(next, sink, pushed.min(residual));
+                if sent > EPSILON {
+                    self.flow[e] += sent;
+                    self.flow[e ^ 1] -= sent;
+                    return sent;
+                }
+            }
+            self.next_edge[node] += 1;
+        }
+        0.0
+    }
+
+    /// Computes the maximum flow from 'source' to 'sink' with the current capacities. Returns 0 if
+    /// either node is not part of the network.
+    pub fn max_flow(&mut self, source: u32, sink: u32) -> f64 {
+        let (source, sink) = match (self.index.get(&source), self.index.get(&sink)) {
+            (Some(s), Some(t)) => (*s, *t),
+            _ => return 0.0,
+        };
+        if source == sink {
+            return f64::INFINITY;
+        }
+        self.flow.iter_mut().for_each(|f| *f = 0.0);
+        let mut total = 0.0;
+        while self.bfs(source, sink) {
+            self.next_edge.iter_mut().for_each(|n| *n = 0);
+            loop {
+                let sent = self.dfs(source, sink, f64::INFINITY);
+                if sent.is_infinite() {
+                    return f64::INFINITY;
+                }
+                if sent <= EPSILON {
+                    break;
+                }
+                total += sent;
+            }
+        }
+        total
+    }
+}
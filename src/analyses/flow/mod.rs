@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext, VISIBLE_VAL};
+use crate::analyses::criticality::{CriticalityResults, sample_states, StateEvaluator, write_outputs};
+use crate::errors::analysis::ThorError;
+use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::analyses::flow::max_flow::FlowNetwork;
+use crate::network::{EdgeValueMap, Graph, NodeValueMap};
+use crate::output::Output;
+
+pub mod max_flow;
+
+/// Name of the edge attribute read as the capacity of every edge by default
+pub const CAPACITY_ATTR: &str = "capacity";
+
+/// Capacity-based analysis. Every sampled state removes the nodes that are not visible and
+/// computes the maximum flow that can still be delivered from the start to the end node through
+/// the edge capacities.
+///
+/// The results use the same layout as the criticality analysis: the mean end value is the
+/// expected delivered flow, and the criticality of a node is the expected flow while it is
+/// visible minus the expected flow while it is not, i.e. its contribution to the flow loss.
+pub struct Flow {
+    pub threads: u8,
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    pub vis_gen: Box<dyn VisGen>,
+    pub loop_condition: Box<dyn CritLoopCondition>,
+    /// Capacity of every edge
+    pub capacities: EdgeValueMap<f32>,
+    /// Capacity used for edges that have none in 'capacities'
+    pub default_capacity: f32,
+    pub start_id: u32,
+    pub end_id: u32,
+    pub outputs: Vec<Box<dyn Output>>,
+}
+
+impl Analysis for Flow {
+    type Output = CriticalityResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+        info!("Starting Flow Analysis");
+        let evaluator = FlowEvaluator::new(&self.graph, &self.capacities, self.default_capacity, self.start_id, self.end_id);
+        let full_flow = evaluator.clone().evaluate(&NodeValueMap::new());
+        info!("Maximum flow with every node visible: {}", full_flow);
+
+        let data = sample_states(self.threads, &self.dynamic_ids, self.vis_gen.as_ref(),
+                                 self.loop_condition.as_ref(), &evaluator, ctx)?;
+        let results = data.results();
+        info!("Expected delivered flow: {}", results.end_op_mean);
+        write_outputs(&self.outputs, &self.graph, &results);
+        Ok(results)
+    }
+}
+
+/// Evaluates a state as the maximum flow from the start to the end node
+#[derive(Clone)]
+pub struct FlowEvaluator {
+    network: FlowNetwork,
+    /// (from, to, handle, capacity) of every edge
+    edges: Vec<(u32, u32, usize, f64)>,
+    start_id: u32,
+    end_id: u32,
+}
+
+impl FlowEvaluator {
+    pub fn new(graph: &Graph, capacities: &EdgeValueMap<f32>, default_capacity: f32, start_id: u32, end_id: u32) -> FlowEvaluator {
+        let ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+        let mut network = FlowNetwork::new(&ids);
+        let mut edges = vec![];
+        for edge in graph.get_edges() {
+            let capacity = *capacities.get(&(edge.from, edge.to)).unwrap_or(&default_capacity) as f64;
+            if let Some(handle) = network.add_edge(edge.from, edge.to, capacity) {
+                edges.push((edge.from, edge.to, handle, capacity));
+            }
+        }
+        FlowEvaluator { network, edges, start_id, end_id }
+    }
+}
+
+impl StateEvaluator for FlowEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let visible = |id: &u32| visibility_state.get(id).map(|v| *v == VISIBLE_VAL).unwrap_or(true);
+        for (from, to, handle, capacity) in self.edges.iter() {
+            let capacity = if visible(from) && visible(to) { *capacity } else { 0.0 };
+            self.network.set_capacity(*handle, capacity);
+        }
+        self.network.max_flow(self.start_id, self.end_id)
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::builder::CriticalityBuilder;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::errors::analysis::ThorError;
+use crate::network::{Graph, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// Node attribute holding the cost of hardening the node
+pub const HARDENING_COST_ATTR: &str = "hardening_cost";
+
+/// What hardening a node does to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hardening {
+    /// The node becomes static and is always visible
+    Static,
+    /// The off chance of the node is multiplied by the factor
+    ReduceOffChance(f32),
+}
+
+/// Greedy optimizer choosing which nodes to harden within a budget. Every round hardens the
+/// affordable node with the largest gain of the mean end operability per unit of cost, measured
+/// by running a criticality analysis for every candidate. The optimizer stops when the budget is
+/// spent or no node improves the end operability. Only nodes with a cost are candidates.
+///
+/// The end operability is sampled, so gains smaller than the sampling noise of 'samples' states
+/// may be chosen over slightly larger ones.
+pub struct GreedyHardening {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Chance of every dynamic node to not be visible
+    pub off_chances: NodeValueMap<f32>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// Cost of hardening every candidate node
+    pub costs: NodeValueMap<f64>,
+    pub budget: f64,
+    pub hardening: Hardening,
+    /// Number of states sampled by every criticality analysis
+    pub samples: u64,
+    /// Seed of the states of every criticality analysis, None to seed them from the system
+    pub seed: Option<u64>,
+    pub threads: u8,
+}
+
+/// Reads the cost of every node that has the ['HARDENING_COST_ATTR'] attribute
+pub fn costs_from_attributes(graph: &Graph) -> NodeValueMap<f64> {
+    graph.get_node_ids().into_iter()
+        .filter_map(|id| Some((id, graph.get_node_attr_f64(&id, HARDENING_COST_ATTR)?)))
+        .collect()
+}
+
+/// Nodes chosen by the optimizer in the order they were chosen
+#[derive(Debug, Clone)]
+pub struct HardeningPlan {
+    /// Mean end operability without hardening any node
+    pub base_operability: f64,
+    pub steps: Vec<HardeningStep>,
+    /// Total cost of the chosen nodes
+    pub spent: f64,
+}
+
+impl HardeningPlan {
+    pub fn print(&self, graph: &Graph) {
+        print!("{}", self.report(graph));
+    }
+
+    /// The lines printed by ['HardeningPlan::print']
+    pub fn report(&self, graph: &Graph) -> String {
+        let mut report = format!("Mean end operability: {}\n", self.base_operability);
+        for step in self.steps.iter() {
+            let name = graph.get_node(&step.id).map(|n| n.name.as_str()).unwrap_or("");
+            report += &format!("Harden {} ({}): cost {}, mean end operability {}\n", name, step.id, step.cost, step.end_operability);
+        }
+        report + &format!("Total cost: {}\n", self.spent)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HardeningStep {
+    pub id: u32,
+    pub cost: f64,
+    /// Mean end operability with this and every earlier node hardened
+    pub end_operability: f64,
+}
+
+impl GreedyHardening {
+    /// Mean end operability with the 'hardened' nodes hardened
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the criticality analysis can't be built or is cancelled
+    pub fn end_operability(&self, hardened: &[u32], ctx: &AnalysisContext) -> Result<f64, ThorError> {
+        let mut dynamic_ids = self.dynamic_ids.clone();
+        let mut off_chances = self.off_chances.clone();
+        for id in hardened {
+            match self.hardening {
+                Hardening::Static => { dynamic_ids.remove(id); }
+                Hardening::ReduceOffChance(factor) => {
+                    let off_chance = *off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE);
+                    off_chances.insert(*id, off_chance * factor);
+                }
+            }
+        }
+        let mut builder = CriticalityBuilder::new(self.graph.clone())
+            .threads(self.threads)
+            .dynamic_ids(dynamic_ids)
+            .off_chances(off_chances)
+            .roll_up_rule(dyn_clone::clone_box(&*self.roll_up_rule))
+            .start_id(self.start_id)
+            .end_id(self.end_id)
+            .samples(self.samples);
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        let mut criticality = builder.build()?;
+        criticality.outputs.clear();
+        Ok(criticality.run(ctx)?.end_op_mean)
+    }
+}
+
+impl Analysis for GreedyHardening {
+    type Output = HardeningPlan;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<HardeningPlan, ThorError> {
+        info!("Starting Greedy Hardening");
+        let base_operability = self.end_operability(&[], ctx)?;
+        let mut plan = HardeningPlan { base_operability, steps: vec![], spent: 0.0 };
+        let mut hardened: Vec<u32> = vec![];
+        let mut current = base_operability;
+        let mut candidates: Vec<(u32, f64)> = self.costs.iter()
+            .filter(|(id, _)| self.dynamic_ids.contains(id))
+            .map(|(id, cost)| (*id, *cost))
+            .collect();
+        loop {
+            // (gain per cost, node, cost, end operability) of the best candidate so far
+            let mut best: Option<(f64, u32, f64, f64)> = None;
+            for (id, cost) in candidates.iter().filter(|(_, cost)| plan.spent + cost <= self.budget) {
+                hardened.push(*id);
+                let operability = self.end_operability(&hardened, ctx)?;
+                hardened.pop();
+                let gain = operability - current;
+                if gain <= 0.0 {
+                    continue;
+                }
+                let score = match *cost > 0.0 {
+                    true => gain / cost,
+                    false => f64::INFINITY,
+                };
+                if best.map(|(s, ..)| score > s).unwrap_or(true) {
+                    best = Some((score, *id, *cost, operability));
+                }
+            }
+            let Some((_, id, cost, operability)) = best else { break };
+            info!("Hardening node {} raises the mean end operability to {}", id, operability);
+            hardened.push(id);
+            candidates.retain(|(c, _)| *c != id);
+            current = operability;
+            plan.spent += cost;
+            plan.steps.push(HardeningStep { id, cost, end_operability: operability });
+        }
+        Ok(plan)
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::errors::analysis::ThorError;
+use crate::network::{AttrValue, EdgeValueMap, Graph, Links, NodeValueMap};
+
+/// Node attribute holding the influence score set by ['InfluenceResults::mark']
+pub const INFLUENCE_ATTR: &str = "influence";
+
+/// Damping used when none is given
+pub const DEFAULT_DAMPING: f64 = 0.85;
+
+/// Iterations after which the scores are reported even if they haven't converged
+const MAX_ITERATIONS: usize = 1000;
+
+/// Largest change of any score at which the scores count as converged
+const TOLERANCE: f64 = 1e-9;
+
+/// PageRank like influence scoring over the reversed graph, a fast analytic proxy for the
+/// criticality. The end node has an influence of one, and every node passes its influence on to
+/// its children, damped and split by the weights of the edges from the children:
+///
+/// influence(c) = d * sum over the parents p of c of influence(p) * w(c, p) / sum of w(.., p)
+///
+/// Edges without a weight weigh one. Nodes that don't feed the end node have no influence.
+pub struct Influence {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Weight of every edge, usually the alpha weights
+    pub weights: EdgeValueMap<f32>,
+    /// Share of the influence of a node passed on to its children, between 0 and 1
+    pub damping: f64,
+    pub end_id: u32,
+}
+
+/// Influence score of every dynamic node
+#[derive(Debug, Clone)]
+pub struct InfluenceResults {
+    /// Number of iterations until the scores converged
+    pub iterations: usize,
+    pub nodes: NodeValueMap<f64>,
+}
+
+impl InfluenceResults {
+    /// Prints the nodes from the most to the least influential
+    pub fn print(&self, graph: &Graph) {
+        println!("Influence converged after {} iterations", self.iterations);
+        let mut nodes: Vec<(&u32, &f64)> = self.nodes.iter().collect();
+        nodes.sort_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)));
+        for (id, influence) in nodes {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            println!("{} ({}): influence {}", name, id, influence);
+        }
+    }
+
+    /// Sets ['INFLUENCE_ATTR'] on every scored node, the attribute is printed next to the
+    /// criticality of the node
+    pub fn mark(&self, graph: &mut Graph) {
+        for (id, influence) in self.nodes.iter() {
+            graph.set_node_attr(id, INFLUENCE_ATTR, AttrValue::Number(*influence));
+        }
+    }
+}
+
+impl Analysis for Influence {
+    type Output = InfluenceResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<InfluenceResults, ThorError> {
+        info!("Starting Influence Scoring");
+        let l_map = self.graph.links_map();
+        let weight = |child: &u32, parent: &u32| *self.weights.get(&(*child, *parent)).unwrap_or(&1.0) as f64;
+        let mut ids: Vec<u32> = self.graph.get_node_ids().into_iter().collect();
+        ids.sort_unstable();
+        let mut scores: NodeValueMap<f64> = ids.iter().map(|id| (*id, 0.0)).collect();
+        scores.insert(self.end_id, 1.0);
+        let mut iterations = 0;
+        while iterations < MAX_ITERATIONS {
+            ctx.check_cancelled()?;
+            iterations += 1;
+            let mut next: NodeValueMap<f64> = NodeValueMap::new();
+            for id in ids.iter() {
+                let fed: f64 = l_map.parents_of(id).iter()
+                    .map(|parent| {
+                        let total: f64 = l_map.children_of(parent).iter().map(|c| weight(c, parent)).sum();
+                        match total > 0.0 {
+                            true => scores[parent] * weight(id, parent) / total,
+                            false => 0.0,
+                        }
+                    })
+                    .sum();
+                let own = if *id == self.end_id { 1.0 } else { 0.0 };
+                next.insert(*id, own + self.damping * fed);
+            }
+            let change = ids.iter().map(|id| (next[id] - scores[id]).abs()).fold(0.0, f64::max);
+            scores = next;
+            if change < TOLERANCE {
+                break;
+            }
+        }
+        scores.retain(|id, _| self.dynamic_ids.contains(id));
+        Ok(InfluenceResults { iterations, nodes: scores })
+    }
+}
@@ -0,0 +1,70 @@
+//! Limits guarding the host against analyses that run too long or use too much memory.
+//!
+//! The wall time is checked by ['AnalysisContext::check_cancelled'] and by the sampling engine of
+//! the criticality analyses, the number of visited states and the memory they take only by the
+//! sampling engine, whose set of visited states is what grows with the samples. When a limit is
+//! reached the analysis either fails with a ['LimitExceededError'] or degrades: the sampling stops
+//! early on the wall time and switches to an approximate ['crate::analyses::criticality::visited']
+//! set on the number of states or the memory.
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// What an analysis does when it reaches a limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitAction {
+    /// The analysis fails with a diagnostic naming the limit
+    #[default]
+    Abort,
+    /// The analysis continues with reduced accuracy and logs a warning
+    Degrade,
+}
+
+/// A limit that was reached
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    WallTime(Duration),
+    VisitedStates(usize),
+    /// Estimated bytes of the visited states
+    Memory(u64),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::WallTime(t) => write!(f, "wall time limit of {:?}", t),
+            Limit::VisitedStates(n) => write!(f, "limit of {} visited states", n),
+            Limit::Memory(bytes) => write!(f, "memory limit of {} MiB", bytes / (1024 * 1024)),
+        }
+    }
+}
+
+/// Limits of a single analysis, none by default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub max_wall_time: Option<Duration>,
+    pub max_visited: Option<usize>,
+    /// Estimated bytes the visited states may take
+    pub max_memory: Option<u64>,
+    pub on_limit: LimitAction,
+}
+
+impl Limits {
+    /// Number of visited states every one of 'threads' threads may keep for states of
+    /// 'dynamic_nodes' nodes, and the limit that bounds them, or None if they are unlimited
+    pub fn visited_per_thread(&self, dynamic_nodes: usize, threads: u8) -> Option<(usize, Limit)> {
+        let by_count = self.max_visited.map(|n| (n, Limit::VisitedStates(n)));
+        let by_memory = self.max_memory
+            .map(|bytes| ((bytes / estimated_state_bytes(dynamic_nodes)) as usize, Limit::Memory(bytes)));
+        let (total, limit) = match (by_count, by_memory) {
+            (Some(c), Some(m)) => if c.0 <= m.0 { c } else { m },
+            (c, m) => c.or(m)?,
+        };
+        Some(((total / threads.max(1) as usize).max(1), limit))
+    }
+}
+
+/// Rough number of bytes a visited state of 'dynamic_nodes' nodes takes in the exact set
+pub fn estimated_state_bytes(dynamic_nodes: usize) -> u64 {
+    64 + 16 * dynamic_nodes as u64
+}
@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+use log::{info, warn};
+use crate::analyses::{Analysis, AnalysisContext, INVISIBLE_VAL, VISIBLE_VAL};
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult, write_outputs};
+use crate::analyses::criticality::histogram::Histogram;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::errors::analysis::{ThorError, TooManyNodesError};
+use crate::network::{CsrLinks, Graph, LinkMap, NodeValueMap};
+use crate::output::Output;
+use crate::roll_up::RollUp;
+
+/// Node attribute holding the rate at which a node goes down
+pub const FAILURE_RATE_ATTR: &str = "failure_rate";
+/// Node attribute holding the rate at which a down node is repaired
+pub const REPAIR_RATE_ATTR: &str = "repair_rate";
+/// The chain has 2^n states for n dynamic nodes
+pub const MAX_MARKOV_NODES: usize = 16;
+
+const MAX_ITERATIONS: usize = 100_000;
+const TOLERANCE: f64 = 1e-12;
+
+/// Steady state availability analysis of a continuous-time Markov chain over the up and down
+/// states of the dynamic nodes. Every node fails and is repaired with its own rates.
+///
+/// The results use the layout of the criticality analysis: the mean end value is the steady state
+/// availability of the end node, the means while on and off are the availabilities given the node
+/// is up or down and the counts are the number of chain states with the node up or down.
+pub struct MarkovAvailability {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// (failure rate, repair rate) of every dynamic node
+    pub rates: NodeValueMap<(f64, f64)>,
+    /// Nodes without rates fail with their off chance and are repaired with one minus it, which
+    /// gives them the off chance as steady state unavailability
+    pub off_chances: NodeValueMap<f32>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub l_map: LinkMap,
+    pub start_id: u32,
+    pub end_id: u32,
+    pub outputs: Vec<Box<dyn Output>>,
+}
+
+/// Reads the (failure rate, repair rate) of every node that has both rate attributes
+pub fn rates_from_attributes(graph: &Graph) -> NodeValueMap<(f64, f64)> {
+    graph.get_node_ids().into_iter()
+        .filter_map(|id| Some((id, (graph.get_node_attr_f64(&id, FAILURE_RATE_ATTR)?, graph.get_node_attr_f64(&id, REPAIR_RATE_ATTR)?))))
+        .collect()
+}
+
+impl MarkovAvailability {
+    fn node_rates(&self, id: &u32) -> (f64, f64) {
+        match self.rates.get(id) {
+            Some(rates) => *rates,
+            None => {
+                let off_chance = *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE) as f64;
+                (off_chance, 1.0 - off_chance)
+            }
+        }
+    }
+
+    /// Steady state distribution of the chain, state 's' has node 'ids[i]' down when bit i is set
+    fn steady_state(&self, ids: &[u32], ctx: &AnalysisContext) -> Result<Vec<f64>, ThorError> {
+        let rates: Vec<(f64, f64)> = ids.iter().map(|id| self.node_rates(id)).collect();
+        let states = 1usize << ids.len();
+        // Total rate of leaving every state
+        let exit: Vec<f64> = (0..states).map(|s| {
+            rates.iter().enumerate()
+                .map(|(i, (failure, repair))| if s & (1 << i) == 0 { *failure } else { *repair })
+                .sum()
+        }).collect();
+
+        // Gauss-Seidel on the balance equations pi_s * exit_s = sum of the inflows into s
+        let mut pi = vec![1.0 / states as f64; states];
+        for iteration in 0..MAX_ITERATIONS {
+            ctx.check_cancelled()?;
+            let mut change: f64 = 0.0;
+            for s in 0..states {
+                if exit[s] == 0.0 {
+                    continue;
+                }
+                let inflow: f64 = rates.iter().enumerate().map(|(i, (failure, repair))| {
+                    let from = s ^ (1 << i);
+                    // Node i went down coming from 'from' if it is down in 's'
+                    let rate = if s & (1 << i) != 0 { failure } else { repair };
+                    pi[from] * rate
+                }).sum();
+                let new = inflow / exit[s];
+                change = change.max((new - pi[s]).abs());
+                pi[s] = new;
+            }
+            let total: f64 = pi.iter().sum();
+            pi.iter_mut().for_each(|p| *p /= total);
+            if change < TOLERANCE {
+                info!("The steady state converged after {} iterations", iteration + 1);
+                return Ok(pi);
+            }
+        }
+        warn!("The steady state did not converge after {} iterations", MAX_ITERATIONS);
+        Ok(pi)
+    }
+
+    /// # Errors
+    ///
+    /// Returns a ['TooManyNodesError'] if the graph has more than ['MAX_MARKOV_NODES'] dynamic nodes
+    fn compute(&self, ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+        let mut ids: Vec<u32> = self.dynamic_ids.iter().copied().collect();
+        ids.sort();
+        if ids.len() > MAX_MARKOV_NODES {
+            return Err(TooManyNodesError { analysis: "markov".to_string(), nodes: ids.len(), max: MAX_MARKOV_NODES }.into());
+        }
+        let pi = self.steady_state(&ids, ctx)?;
+        let links = CsrLinks::new(&self.l_map, &Graph::get_bfs_path(&self.l_map, self.start_id));
+
+        let mut availability = 0.0;
+        // (probability up, availability mass up, probability down, availability mass down)
+        let mut sums = vec![(0.0, 0.0, 0.0, 0.0); ids.len()];
+        for (s, p) in pi.iter().enumerate() {
+            let visibilities: NodeValueMap<u8> = ids.iter().enumerate()
+                .map(|(i, id)| (*id, if s & (1 << i) == 0 { VISIBLE_VAL } else { INVISIBLE_VAL }))
+                .collect();
+            let values = self.graph.roll_up_state(&links, self.roll_up_rule.as_ref(), &visibilities);
+            let end = *values.get(&self.end_id).unwrap_or(&0.0) as f64;
+            availability += p * end;
+            for (i, sum) in sums.iter_mut().enumerate() {
+                match s & (1 << i) == 0 {
+                    true => { sum.0 += p; sum.1 += p * end; }
+                    false => { sum.2 += p; sum.3 += p * end; }
+                }
+            }
+        }
+
+        let half = (pi.len() / 2) as u64;
+        let nodes = ids.iter().zip(sums).map(|(id, (p_up, up, p_down, down))| {
+            let mean_end_on = if p_up > 0.0 { up / p_up } else { 0.0 };
+            let mean_end_off = if p_down > 0.0 { down / p_down } else { 0.0 };
+            (*id, NodeCritResult {
+                on_count: half,
+                off_count: half,
+                mean_end_on,
+                mean_end_off,
+                criticality: mean_end_on - mean_end_off,
+            })
+        }).collect();
+        Ok(CriticalityResults { row_count: pi.len() as u64, end_op_mean: availability, end_op_histogram: Histogram::default(), nodes })
+    }
+}
+
+impl Analysis for MarkovAvailability {
+    type Output = CriticalityResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+        info!("Starting Markov Availability Analysis");
+        let results = self.compute(ctx)?;
+        write_outputs(&self.outputs, &self.graph, &results);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roll_up::OrRule;
+
+    /// start -> n -> end for every dynamic node n, the end node is up while any of them is
+    fn parallel(dynamic: u32) -> Graph {
+        let mut graph = Graph::new();
+        graph.add_node("start".to_string(), 0);
+        graph.add_node("end".to_string(), 1);
+        for id in 2..2 + dynamic {
+            graph.add_node(format!("n{}", id), id);
+            graph.add_edge(0, id);
+            graph.add_edge(id, 1);
+        }
+        graph
+    }
+
+    fn markov(graph: Graph, rates: NodeValueMap<(f64, f64)>, off_chances: NodeValueMap<f32>) -> MarkovAvailability {
+        MarkovAvailability {
+            l_map: graph.links_map(),
+            dynamic_ids: graph.get_node_ids().into_iter().filter(|id| *id > 1).collect(),
+            graph,
+            rates,
+            off_chances,
+            roll_up_rule: Box::new(OrRule {}),
+            start_id: 0,
+            end_id: 1,
+            outputs: vec![],
+        }
+    }
+
+    #[test]
+    fn availability_is_the_share_of_time_up() {
+        // Up for 9 hours between failures on average and repaired in 1
+        let results = markov(parallel(1), NodeValueMap::from([(2, (1.0 / 9.0, 1.0))]), NodeValueMap::new())
+            .run(&AnalysisContext::default()).unwrap();
+        assert_eq!(results.row_count, 2);
+        assert!((results.end_op_mean - 0.9).abs() < 1e-9);
+        let node = &results.nodes[&2];
+        assert_eq!((node.mean_end_on, node.mean_end_off), (1.0, 0.0));
+    }
+
+    #[test]
+    fn independent_nodes_are_down_together_with_the_product_of_their_unavailabilities() {
+        // The node without rates is down with its off chance
+        let rates = NodeValueMap::from([(2, (1.0, 3.0))]);
+        let results = markov(parallel(2), rates, NodeValueMap::from([(3, 0.5)]))
+            .run(&AnalysisContext::default()).unwrap();
+        assert_eq!(results.row_count, 4);
+        assert!((results.end_op_mean - (1.0 - 0.25 * 0.5)).abs() < 1e-9);
+        // The end node depends on node 2 only while node 3 is down
+        assert!((results.nodes[&2].criticality - 0.5).abs() < 1e-9);
+        assert!((results.nodes[&3].criticality - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn graphs_with_too_many_dynamic_nodes_are_refused() {
+        let error = markov(parallel(MAX_MARKOV_NODES as u32 + 1), NodeValueMap::new(), NodeValueMap::new())
+            .run(&AnalysisContext::default()).err().unwrap();
+        assert!(error.to_string().contains(&format!("{}", MAX_MARKOV_NODES + 1)));
+    }
+}
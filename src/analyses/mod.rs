@@ -1,7 +1,115 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use crate::analyses::criticality::coverage::CoverageLog;
+use crate::analyses::criticality::partial::PartialResults;
+use crate::analyses::limits::{Limit, Limits};
+use crate::errors::analysis::{LimitExceededError, ThorError};
+use crate::metrics::Metrics;
+
 pub mod criticality;
+pub mod dominators;
+pub mod estimate;
+pub mod event_tree;
+pub mod exact;
+pub mod explain;
+pub mod flow;
+pub mod hardening;
+pub mod influence;
+pub mod limits;
+pub mod markov;
+pub mod paths;
+pub mod reliability;
+pub mod removal;
+pub mod scenario;
+pub mod search;
+pub mod shortest_path;
+pub mod sobol;
 
 pub const VISIBLE_VAL: u8 = 1;
+pub const INVISIBLE_VAL: u8 = 0;
+
+/// Flag shared between clones, used to stop a running analysis from another thread
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Asks every analysis using the token to stop as soon as possible
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// State handed to an analysis while it runs
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisContext {
+    pub cancellation: CancellationToken,
+    pub limits: Limits,
+    /// End of the wall time given by the limits, counted from ['AnalysisContext::with_limits']
+    pub deadline: Option<Instant>,
+    /// Counters of the service running the analysis, if any
+    pub metrics: Option<Arc<Metrics>>,
+    /// Where the sampling analyses write their results while they run, if anywhere
+    pub partial: Option<PartialResults>,
+    /// Collects the coverage of the state space of every sampling, if any
+    pub coverage: Option<Arc<CoverageLog>>,
+}
+
+impl AnalysisContext {
+    pub fn new(cancellation: CancellationToken) -> AnalysisContext {
+        AnalysisContext { cancellation, ..Default::default() }
+    }
+
+    /// Sets the limits, starting the wall time of the analysis now
+    pub fn with_limits(mut self, limits: Limits) -> AnalysisContext {
+        self.deadline = limits.max_wall_time.map(|t| Instant::now() + t);
+        self.limits = limits;
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    pub fn is_past_deadline(&self) -> bool {
+        self.deadline.map(|d| Instant::now() >= d).unwrap_or(false)
+    }
+
+    /// # Errors
+    ///
+    /// Returns ['ThorError::Cancelled'] once the analysis has been cancelled, or a
+    /// ['LimitExceededError'] once its wall time is over
+    pub fn check_cancelled(&self) -> Result<(), ThorError> {
+        if self.is_cancelled() {
+            return Err(ThorError::Cancelled);
+        }
+        match (self.is_past_deadline(), self.limits.max_wall_time) {
+            (true, Some(t)) => Err(LimitExceededError { limit: Limit::WallTime(t).to_string() }.into()),
+            _ => Ok(()),
+        }
+    }
+}
 
 pub trait Analysis {
-    fn analyze(self);
-}
\ No newline at end of file
+    /// The results of the analysis
+    type Output;
+
+    /// Runs the analysis. The configuration is not consumed so the same analysis can be run
+    /// several times.
+    ///
+    /// # Errors
+    ///
+    /// Returns ['ThorError::Cancelled'] if the context is cancelled before the analysis finished,
+    /// or the error that stopped the analysis
+    fn run(&self, ctx: &AnalysisContext) -> Result<Self::Output, ThorError>;
+}
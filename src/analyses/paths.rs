@@ -0,0 +1,161 @@
+//! Attributing the end operability to the paths from the start to the end node.
+//!
+//! Under the ['crate::roll_up::OrRule'] the end node is operable if every node of at least one
+//! path from the start node is visible. The paths are ranked from the shortest to the longest,
+//! ties by their ids, and in every sampled state the first path whose nodes are all visible is the
+//! active one, the route that carries the mission while the later ones stand by. The share of the
+//! states each path is active in shows which routes carry the mission, and the paths and nodes
+//! that are never active are redundancy the sampled states never exercise.
+//!
+//! Every path is checked in every state, so the number of simple paths is bounded by
+//! ['PathContribution::max_paths'].
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext, VISIBLE_VAL};
+use crate::analyses::criticality::{sample_states_many, StateEvaluator};
+use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::errors::analysis::ThorError;
+use crate::network::{Graph, LinkMap, Links, NodeValueMap};
+
+/// Paths enumerated when no maximum is given
+pub const DEFAULT_MAX_PATHS: usize = 100;
+
+pub struct PathContribution {
+    pub threads: u8,
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    pub vis_gen: Box<dyn VisGen>,
+    pub loop_condition: Box<dyn CritLoopCondition>,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// The analysis fails if there are more simple paths from the start to the end node
+    pub max_paths: usize,
+}
+
+/// Share of the states every path was active in
+#[derive(Debug, Clone)]
+pub struct PathResults {
+    /// Number of unique states that were evaluated
+    pub states: u64,
+    /// Share of the states the end node was reached in
+    pub reached: f64,
+    /// Nodes of every path from the start to the end node in their rank, with the share of the
+    /// states the path was active in
+    pub paths: Vec<(Vec<u32>, f64)>,
+}
+
+impl PathResults {
+    /// Nodes on paths from the start to the end node that lie on no path that was ever active
+    pub fn unused_nodes(&self) -> Vec<u32> {
+        let used: HashSet<u32> = self.paths.iter().filter(|(_, share)| *share > 0.0).flat_map(|(path, _)| path.iter().copied()).collect();
+        let mut unused: Vec<u32> = self.paths.iter().flat_map(|(path, _)| path.iter().copied()).filter(|id| !used.contains(id)).collect();
+        unused.sort_unstable();
+        unused.dedup();
+        unused
+    }
+
+    pub fn print(&self, graph: &Graph) {
+        let name = |id: &u32| format!("{} ({})", graph.get_node(id).map(|n| n.name.as_str()).unwrap_or(""), id);
+        println!("The end node was reached in {:.2}% of {} states", 100.0 * self.reached, self.states);
+        for (rank, (path, share)) in self.paths.iter().enumerate() {
+            let nodes: Vec<String> = path.iter().map(name).collect();
+            println!("Path {}: active in {:.2}% of the states: {}", rank + 1, 100.0 * share, nodes.join(" -> "));
+        }
+        let idle = self.paths.iter().filter(|(_, share)| *share == 0.0).count();
+        println!("{} of {} paths were never active", idle, self.paths.len());
+        let unused: Vec<String> = self.unused_nodes().iter().map(name).collect();
+        match unused.is_empty() {
+            true => println!("Every node on a path was on an active path"),
+            false => println!("Nodes on no active path: {}", unused.join(", ")),
+        }
+    }
+}
+
+impl Analysis for PathContribution {
+    type Output = PathResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<PathResults, ThorError> {
+        info!("Starting Path Contribution Analysis");
+        let l_map = self.graph.links_map();
+        let paths = Arc::new(simple_paths(&l_map, self.start_id, self.end_id, self.max_paths)?);
+        info!("Found {} paths from {} to {}", paths.len(), self.start_id, self.end_id);
+        // One evaluator per path, and one more telling whether any path is available
+        let evaluators: Vec<Box<dyn StateEvaluator>> = (0..=paths.len())
+            .map(|index| Box::new(ActivePathEvaluator { paths: paths.clone(), index }) as Box<dyn StateEvaluator>)
+            .collect();
+        let data = sample_states_many(self.threads, &self.dynamic_ids, self.vis_gen.as_ref(),
+                                      self.loop_condition.as_ref(), &evaluators, ctx)?;
+        let mut shares: Vec<f64> = data.iter().map(|d| d.results().end_op_mean).collect();
+        let reached = shares.pop().unwrap_or(0.0);
+        Ok(PathResults {
+            states: data.first().map(|d| d.results().row_count).unwrap_or(0),
+            reached,
+            paths: paths.iter().cloned().zip(shares).collect(),
+        })
+    }
+}
+
+/// Evaluates a state as one if the path at 'index' is the first path whose nodes are all
+/// visible. An index past the last path evaluates whether any path is available.
+#[derive(Clone)]
+struct ActivePathEvaluator {
+    paths: Arc<Vec<Vec<u32>>>,
+    index: usize,
+}
+
+impl StateEvaluator for ActivePathEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let available = |path: &Vec<u32>| path.iter().all(|id| visibility_state.get(id).map(|v| *v == VISIBLE_VAL).unwrap_or(true));
+        match self.paths.iter().position(available) {
+            Some(first) if first == self.index || self.index == self.paths.len() => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Every simple path from 'start' to 'end', from the shortest to the longest and ties by their
+/// ids
+///
+/// # Errors
+///
+/// Returns an error if there are more than 'max_paths' paths
+fn simple_paths(l_map: &LinkMap, start: u32, end: u32, max_paths: usize) -> Result<Vec<Vec<u32>>, ThorError> {
+    let on_paths = Graph::nodes_on_paths(l_map, start, end);
+    let mut paths = vec![];
+    let mut path = vec![start];
+    let mut on_path = HashSet::from([start]);
+    // Position in the parents of every node of the path
+    let mut next = vec![0];
+    while let Some(last) = path.last().copied() {
+        if last == end {
+            paths.push(path.clone());
+            if paths.len() > max_paths {
+                return Err(ThorError::Failed(format!("There are more than {} paths from {} to {}, raise --max-paths", max_paths, start, end).into()));
+            }
+        }
+        let parents = l_map.parents_of(&last);
+        let position = next.last_mut().unwrap();
+        let parent = match last == end {
+            true => None,
+            false => parents[*position..].iter().position(|p| on_paths.contains(p) && !on_path.contains(p)).map(|i| *position + i),
+        };
+        match parent {
+            Some(i) => {
+                *position = i + 1;
+                path.push(parents[i]);
+                on_path.insert(parents[i]);
+                next.push(0);
+            }
+            None => {
+                on_path.remove(&last);
+                path.pop();
+                next.pop();
+            }
+        }
+    }
+    paths.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+    Ok(paths)
+}
@@ -0,0 +1,97 @@
+use std::collections::{HashMap, HashSet};
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext, VISIBLE_VAL};
+use crate::analyses::criticality::{CriticalityResults, sample_states, StateEvaluator, write_outputs};
+use crate::analyses::criticality::loop_condition::CritLoopCondition;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::errors::analysis::ThorError;
+use crate::network::{Graph, NodeValueMap};
+use crate::output::Output;
+
+/// k-terminal network reliability. Every sampled state removes the nodes that are not visible and
+/// checks whether the 'terminals' are still mutually connected, following the edges in either
+/// direction. Edges fail with their link nodes, see the parallel edges of the csv input.
+///
+/// The results use the same layout as the criticality analysis: the mean end value is the chance
+/// that the terminals stay connected, and the criticality of a node is that chance while it is
+/// visible minus the chance while it is not.
+pub struct TerminalReliability {
+    pub threads: u8,
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    pub vis_gen: Box<dyn VisGen>,
+    pub loop_condition: Box<dyn CritLoopCondition>,
+    /// The nodes that have to stay connected
+    pub terminals: Vec<u32>,
+    pub outputs: Vec<Box<dyn Output>>,
+}
+
+impl Analysis for TerminalReliability {
+    type Output = CriticalityResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+        info!("Starting Terminal Reliability Analysis");
+        let evaluator = ConnectivityEvaluator::new(&self.graph, &self.terminals);
+        let data = sample_states(self.threads, &self.dynamic_ids, self.vis_gen.as_ref(),
+                                 self.loop_condition.as_ref(), &evaluator, ctx)?;
+        let results = data.results();
+        info!("Chance that the terminals stay connected: {}", results.end_op_mean);
+        write_outputs(&self.outputs, &self.graph, &results);
+        Ok(results)
+    }
+}
+
+/// Evaluates a state as one if every terminal is visible and connected to the others, and zero
+/// otherwise
+#[derive(Clone)]
+pub struct ConnectivityEvaluator {
+    ids: Vec<u32>,
+    /// Indexes of the neighbours of every node, linked by an edge of either direction
+    neighbours: Vec<Vec<usize>>,
+    terminals: Vec<usize>,
+}
+
+impl ConnectivityEvaluator {
+    /// Terminals that aren't part of the graph are never connected
+    pub fn new(graph: &Graph, terminals: &[u32]) -> ConnectivityEvaluator {
+        let mut ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+        ids.sort_unstable();
+        let index: HashMap<u32, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let mut neighbours: Vec<Vec<usize>> = vec![vec![]; ids.len()];
+        for edge in graph.get_edges() {
+            if let (Some(from), Some(to)) = (index.get(&edge.from), index.get(&edge.to)) {
+                neighbours[*from].push(*to);
+                neighbours[*to].push(*from);
+            }
+        }
+        let terminals = terminals.iter()
+            .map(|id| index.get(id).copied().unwrap_or(usize::MAX))
+            .collect();
+        ConnectivityEvaluator { ids, neighbours, terminals }
+    }
+}
+
+impl StateEvaluator for ConnectivityEvaluator {
+    fn evaluate(&mut self, visibility_state: &NodeValueMap<u8>) -> f64 {
+        let visible = |i: usize| i < self.ids.len() && visibility_state.get(&self.ids[i]).map(|v| *v == VISIBLE_VAL).unwrap_or(true);
+        let Some(first) = self.terminals.first() else { return 1.0 };
+        if !self.terminals.iter().all(|t| visible(*t)) {
+            return 0.0;
+        }
+        let mut reached = vec![false; self.ids.len()];
+        reached[*first] = true;
+        let mut stack = vec![*first];
+        while let Some(i) = stack.pop() {
+            for n in self.neighbours[i].iter() {
+                if !reached[*n] && visible(*n) {
+                    reached[*n] = true;
+                    stack.push(*n);
+                }
+            }
+        }
+        match self.terminals.iter().all(|t| reached[*t]) {
+            true => 1.0,
+            false => 0.0,
+        }
+    }
+}
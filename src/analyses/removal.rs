@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::analyses::exact::compile_end_node;
+use crate::errors::analysis::ThorError;
+use crate::network::{Graph, LinkMap, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// What-if sweep which removes every dynamic node in turn, forcing it off, and computes the
+/// reliability of the end node without it. The reliability is computed exactly from the decision
+/// diagram of the exact analysis, so the roll up rule must be equivalent to a
+/// ['crate::roll_up::BooleanGate'].
+pub struct NodeRemoval {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Chance of every dynamic node to not be visible
+    pub off_chances: NodeValueMap<f32>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub l_map: LinkMap,
+    pub start_id: u32,
+    pub end_id: u32,
+}
+
+/// End node reliability with every node and without each dynamic node
+#[derive(Debug, Clone)]
+pub struct RemovalResults {
+    /// Reliability of the end node with every node in the graph
+    pub base_operability: f64,
+    pub nodes: NodeValueMap<RemovalImpact>,
+}
+
+impl RemovalResults {
+    /// Prints the nodes from the largest to the smallest drop
+    pub fn print(&self, graph: &Graph) {
+        print!("{}", self.report(graph));
+    }
+
+    /// The lines printed by ['RemovalResults::print']
+    pub fn report(&self, graph: &Graph) -> String {
+        let mut report = format!("End node reliability: {}\n", self.base_operability);
+        let mut nodes: Vec<(&u32, &RemovalImpact)> = self.nodes.iter().collect();
+        nodes.sort_by(|a, b| b.1.drop.total_cmp(&a.1.drop).then(a.0.cmp(b.0)));
+        for (id, node) in nodes {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            report += &format!("{} ({}): reliability without {}, drop {}\n", name, id, node.operability_without, node.drop);
+        }
+        report
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RemovalImpact {
+    /// Reliability of the end node while the node is forced off
+    pub operability_without: f64,
+    /// Decrease of the end node reliability caused by removing the node
+    pub drop: f64,
+}
+
+impl Analysis for NodeRemoval {
+    type Output = RemovalResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<RemovalResults, ThorError> {
+        info!("Starting Node Removal Sweep");
+        let (mut bdd, root, variables) = compile_end_node(&self.l_map, &self.dynamic_ids, self.roll_up_rule.as_ref(),
+                                                          self.start_id, self.end_id, "removal")?;
+        let on_chances: Vec<f64> = variables.iter()
+            .map(|id| 1.0 - *self.off_chances.get(id).unwrap_or(&DEFAULT_OFF_CHANCE) as f64)
+            .collect();
+        let base_operability = bdd.probability(root, &on_chances);
+
+        let mut nodes = NodeValueMap::new();
+        for id in self.dynamic_ids.iter() {
+            ctx.check_cancelled()?;
+            // Nodes that aren't rolled up have no influence on the end node
+            let operability_without = match variables.iter().position(|v| v == id) {
+                Some(var) => {
+                    let off = bdd.restrict(root, var, false);
+                    bdd.probability(off, &on_chances)
+                }
+                None => base_operability,
+            };
+            nodes.insert(*id, RemovalImpact { operability_without, drop: base_operability - operability_without });
+        }
+        Ok(RemovalResults { base_operability, nodes })
+    }
+}
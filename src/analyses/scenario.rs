@@ -0,0 +1,51 @@
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::errors::analysis::ThorError;
+use crate::network::{CsrLinks, Graph, LinkMap, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// A named what-if case, every listed node is explicitly switched on or off. Nodes that are not
+/// listed stay visible.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    /// ['VISIBLE_VAL'] or ['INVISIBLE_VAL'] of every listed node
+    pub states: NodeValueMap<u8>,
+}
+
+/// Deterministic evaluation which rolls up every scenario exactly once, no states are sampled
+pub struct ScenarioEvaluation {
+    pub graph: Graph,
+    pub l_map: LinkMap,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub start_id: u32,
+    pub end_id: u32,
+    pub scenarios: Vec<Scenario>,
+}
+
+/// Operability of the end node in a scenario
+#[derive(Debug, Clone)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub end_operability: f32,
+}
+
+impl Analysis for ScenarioEvaluation {
+    type Output = Vec<ScenarioResult>;
+
+    /// Rolls up every scenario, the results are in the order of the scenarios
+    fn run(&self, ctx: &AnalysisContext) -> Result<Vec<ScenarioResult>, ThorError> {
+        info!("Starting Scenario Evaluation");
+        let links = CsrLinks::new(&self.l_map, &Graph::get_bfs_path(&self.l_map, self.start_id));
+        let mut results = vec![];
+        for scenario in self.scenarios.iter() {
+            ctx.check_cancelled()?;
+            let values = self.graph.roll_up_state(&links, self.roll_up_rule.as_ref(), &scenario.states);
+            results.push(ScenarioResult {
+                name: scenario.name.to_string(),
+                end_operability: *values.get(&self.end_id).unwrap_or(&0.0),
+            });
+        }
+        Ok(results)
+    }
+}
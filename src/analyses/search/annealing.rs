@@ -0,0 +1,56 @@
+use log::info;
+use rand::Rng;
+use rand::rngs::StdRng;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::search::{Design, DesignSpace, ScoredDesigns, SearchResults};
+use crate::errors::analysis::ThorError;
+
+/// Temperature at the first iteration, in units of end operability
+pub const DEFAULT_START_TEMPERATURE: f64 = 0.05;
+
+/// Simulated annealing over the designs of a ['DesignSpace']. Every iteration adds or removes a
+/// random candidate; changes that lower the end operability by 'd' are accepted with the chance
+/// exp(-d / T), where the temperature T falls linearly to zero over the iterations.
+pub struct SimulatedAnnealing {
+    pub space: DesignSpace,
+    pub iterations: u64,
+    pub start_temperature: f64,
+    /// Number of designs reported
+    pub reported: usize,
+    pub rng: StdRng,
+}
+
+impl Analysis for SimulatedAnnealing {
+    type Output = SearchResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<SearchResults, ThorError> {
+        info!("Starting Simulated Annealing Search");
+        let mut rng = self.rng.clone();
+        let mut scored = ScoredDesigns::new(&self.space);
+        let mut current = Design::new();
+        let mut current_score = scored.score(&current, ctx)?;
+        if self.space.candidates.is_empty() {
+            return Ok(scored.results(self.reported));
+        }
+        for iteration in 0..self.iterations {
+            ctx.check_cancelled()?;
+            let temperature = self.start_temperature * (1.0 - iteration as f64 / self.iterations as f64);
+            let mut next = current.clone();
+            let candidate = rng.gen_range(0..self.space.candidates.len());
+            if !next.remove(&candidate) {
+                next.insert(candidate);
+            }
+            if !self.space.is_affordable(&next) {
+                continue;
+            }
+            let score = scored.score(&next, ctx)?;
+            let accept = score >= current_score
+                || (temperature > 0.0 && rng.gen::<f64>() < ((score - current_score) / temperature).exp());
+            if accept {
+                current = next;
+                current_score = score;
+            }
+        }
+        Ok(scored.results(self.reported))
+    }
+}
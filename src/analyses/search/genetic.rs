@@ -0,0 +1,88 @@
+use log::info;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::search::{Design, DesignSpace, ScoredDesigns, SearchResults};
+use crate::errors::analysis::ThorError;
+
+/// Designs in every generation when no size is given
+pub const DEFAULT_POPULATION: usize = 20;
+
+/// Genetic algorithm over the designs of a ['DesignSpace']. Parents are picked by tournaments of
+/// two, children take every candidate from either parent and flip every candidate with the chance
+/// 1 / candidates. Children over the budget drop random modifications until they are affordable.
+/// The best design of a generation always survives into the next.
+pub struct GeneticSearch {
+    pub space: DesignSpace,
+    pub generations: u64,
+    pub population: usize,
+    /// Number of designs reported
+    pub reported: usize,
+    pub rng: StdRng,
+}
+
+impl GeneticSearch {
+    /// Removes random modifications until the design is within the budget
+    fn repair(&self, design: &mut Design, rng: &mut StdRng) {
+        while !self.space.is_affordable(design) {
+            let chosen: Vec<usize> = design.iter().copied().collect();
+            design.remove(chosen.choose(rng).unwrap());
+        }
+    }
+
+    fn random_design(&self, rng: &mut StdRng) -> Design {
+        let mut design: Design = (0..self.space.candidates.len()).filter(|_| rng.gen_bool(0.5)).collect();
+        self.repair(&mut design, rng);
+        design
+    }
+}
+
+impl Analysis for GeneticSearch {
+    type Output = SearchResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<SearchResults, ThorError> {
+        info!("Starting Genetic Search");
+        let mut rng = self.rng.clone();
+        let mut scored = ScoredDesigns::new(&self.space);
+        scored.score(&Design::new(), ctx)?;
+        let count = self.space.candidates.len();
+        if count == 0 {
+            return Ok(scored.results(self.reported));
+        }
+        let mut population: Vec<(Design, f64)> = vec![];
+        for _ in 0..self.population.max(2) {
+            let design = self.random_design(&mut rng);
+            let score = scored.score(&design, ctx)?;
+            population.push((design, score));
+        }
+        for _ in 0..self.generations {
+            ctx.check_cancelled()?;
+            population.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let mut next = vec![population[0].clone()];
+            while next.len() < population.len() {
+                let mut tournament = || {
+                    let (a, b) = (population.choose(&mut rng).unwrap(), population.choose(&mut rng).unwrap());
+                    if a.1 >= b.1 { a.0.clone() } else { b.0.clone() }
+                };
+                let (mother, father) = (tournament(), tournament());
+                let mut child: Design = (0..count)
+                    .filter(|i| match rng.gen_bool(0.5) {
+                        true => mother.contains(i),
+                        false => father.contains(i),
+                    })
+                    .collect();
+                for i in 0..count {
+                    if rng.gen_bool(1.0 / count as f64) && !child.remove(&i) {
+                        child.insert(i);
+                    }
+                }
+                self.repair(&mut child, &mut rng);
+                let score = scored.score(&child, ctx)?;
+                next.push((child, score));
+            }
+            population = next;
+        }
+        Ok(scored.results(self.reported))
+    }
+}
@@ -0,0 +1,203 @@
+//! Searching for modifications of the design that raise the mean end operability.
+//!
+//! A design is a set of modifications of the graph, either a hardened node or a redundant edge,
+//! whose total cost stays within a budget. Designs are scored by running a criticality analysis
+//! on the modified graph and the search keeps the best designs it has scored. Redundant edges
+//! bypass a single node: for every path u -> w -> v the edge u -> v is a candidate, so added edges
+//! never create a cycle.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::builder::CriticalityBuilder;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::analyses::hardening::Hardening;
+use crate::errors::analysis::ThorError;
+use crate::network::{Graph, Links, NodeValueMap};
+use crate::roll_up::RollUp;
+
+pub mod annealing;
+pub mod genetic;
+
+/// Number of designs reported when no number is given
+pub const DEFAULT_REPORTED_DESIGNS: usize = 5;
+
+/// Change of the graph that is part of a design
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Modification {
+    /// Hardens the node, see ['Hardening']
+    Harden(u32),
+    /// Adds an edge (from, to)
+    AddEdge(u32, u32),
+}
+
+impl Display for Modification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Modification::Harden(id) => write!(f, "harden {}", id),
+            Modification::AddEdge(from, to) => write!(f, "add edge {} -> {}", from, to),
+        }
+    }
+}
+
+/// Indexes into the candidate modifications of a ['DesignSpace']
+pub type Design = BTreeSet<usize>;
+
+/// The candidate modifications, their costs and the analysis scoring a design
+pub struct DesignSpace {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Chance of every dynamic node to not be visible
+    pub off_chances: NodeValueMap<f32>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub start_id: u32,
+    pub end_id: u32,
+    pub hardening: Hardening,
+    /// (modification, cost) of every candidate
+    pub candidates: Vec<(Modification, f64)>,
+    pub budget: f64,
+    /// Number of states sampled by every criticality analysis
+    pub samples: u64,
+    /// Seed of the states of every criticality analysis, None to seed them from the system
+    pub seed: Option<u64>,
+    pub threads: u8,
+}
+
+impl DesignSpace {
+    /// Candidates hardening every dynamic node with a cost and adding every bypassing edge at
+    /// 'edge_cost', see the module documentation
+    pub fn candidates(graph: &Graph, dynamic_ids: &HashSet<u32>, costs: &NodeValueMap<f64>, edge_cost: f64) -> Vec<(Modification, f64)> {
+        let mut candidates: Vec<(Modification, f64)> = costs.iter()
+            .filter(|(id, _)| dynamic_ids.contains(id))
+            .map(|(id, cost)| (Modification::Harden(*id), *cost))
+            .collect();
+        let l_map = graph.links_map();
+        let mut edges: BTreeSet<(u32, u32)> = BTreeSet::new();
+        for (middle, (from_nodes, to_nodes)) in l_map.iter() {
+            if !dynamic_ids.contains(middle) {
+                continue;
+            }
+            for from in from_nodes {
+                for to in to_nodes.iter().filter(|to| !l_map.parents_of(from).contains(to)) {
+                    edges.insert((*from, *to));
+                }
+            }
+        }
+        candidates.extend(edges.into_iter().map(|(from, to)| (Modification::AddEdge(from, to), edge_cost)));
+        candidates
+    }
+
+    pub fn cost(&self, design: &Design) -> f64 {
+        design.iter().map(|i| self.candidates[*i].1).sum()
+    }
+
+    pub fn is_affordable(&self, design: &Design) -> bool {
+        self.cost(design) <= self.budget
+    }
+
+    /// Mean end operability of the graph with the modifications of the 'design'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the criticality analysis can't be built or is cancelled
+    pub fn end_operability(&self, design: &Design, ctx: &AnalysisContext) -> Result<f64, ThorError> {
+        let mut graph = self.graph.clone();
+        let mut dynamic_ids = self.dynamic_ids.clone();
+        let mut off_chances = self.off_chances.clone();
+        for i in design {
+            match (self.candidates[*i].0, self.hardening) {
+                (Modification::AddEdge(from, to), _) => { graph.add_edge(from, to); }
+                (Modification::Harden(id), Hardening::Static) => { dynamic_ids.remove(&id); }
+                (Modification::Harden(id), Hardening::ReduceOffChance(factor)) => {
+                    let off_chance = *off_chances.get(&id).unwrap_or(&DEFAULT_OFF_CHANCE);
+                    off_chances.insert(id, off_chance * factor);
+                }
+            }
+        }
+        let mut builder = CriticalityBuilder::new(graph)
+            .threads(self.threads)
+            .dynamic_ids(dynamic_ids)
+            .off_chances(off_chances)
+            .roll_up_rule(dyn_clone::clone_box(&*self.roll_up_rule))
+            .start_id(self.start_id)
+            .end_id(self.end_id)
+            .samples(self.samples);
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        let mut criticality = builder.build()?;
+        criticality.outputs.clear();
+        Ok(criticality.run(ctx)?.end_op_mean)
+    }
+}
+
+/// Scores designs once and remembers every score
+pub struct ScoredDesigns<'a> {
+    pub space: &'a DesignSpace,
+    scores: HashMap<Design, f64>,
+}
+
+impl<'a> ScoredDesigns<'a> {
+    pub fn new(space: &'a DesignSpace) -> ScoredDesigns<'a> {
+        ScoredDesigns { space, scores: HashMap::new() }
+    }
+
+    /// Mean end operability of the design, analysed the first time the design is scored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the criticality analysis fails
+    pub fn score(&mut self, design: &Design, ctx: &AnalysisContext) -> Result<f64, ThorError> {
+        if let Some(score) = self.scores.get(design) {
+            return Ok(*score);
+        }
+        let score = self.space.end_operability(design, ctx)?;
+        self.scores.insert(design.clone(), score);
+        Ok(score)
+    }
+
+    /// The 'count' best scored designs, best first and the cheapest of equally good designs first
+    pub fn results(&self, count: usize) -> SearchResults {
+        let base_operability = self.scores.get(&Design::new()).copied().unwrap_or(0.0);
+        let mut designs: Vec<(&Design, &f64)> = self.scores.iter().filter(|(d, _)| !d.is_empty()).collect();
+        designs.sort_by(|a, b| b.1.total_cmp(a.1)
+            .then(self.space.cost(a.0).total_cmp(&self.space.cost(b.0)))
+            .then(a.0.cmp(b.0)));
+        SearchResults {
+            base_operability,
+            evaluated: self.scores.len(),
+            designs: designs.into_iter().take(count).map(|(design, score)| ScoredDesign {
+                modifications: design.iter().map(|i| self.space.candidates[*i].0).collect(),
+                cost: self.space.cost(design),
+                end_operability: *score,
+            }).collect(),
+        }
+    }
+}
+
+/// Best designs found by a search
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    /// Mean end operability of the unmodified graph
+    pub base_operability: f64,
+    /// Number of distinct designs that were analysed
+    pub evaluated: usize,
+    pub designs: Vec<ScoredDesign>,
+}
+
+impl SearchResults {
+    pub fn print(&self) {
+        println!("Mean end operability: {}, {} designs evaluated", self.base_operability, self.evaluated);
+        for (rank, design) in self.designs.iter().enumerate() {
+            let modifications: Vec<String> = design.modifications.iter().map(|m| m.to_string()).collect();
+            println!("{}. mean end operability {}, cost {}: {}", rank + 1, design.end_operability, design.cost, modifications.join(", "));
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoredDesign {
+    pub modifications: Vec<Modification>,
+    pub cost: f64,
+    pub end_operability: f64,
+}
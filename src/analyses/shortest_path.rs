@@ -0,0 +1,121 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::errors::analysis::ThorError;
+use crate::network::{EdgeValueMap, Graph, NodeValueMap};
+
+/// Name of the edge attribute read as the length of every edge by default
+pub const LATENCY_ATTR: &str = "latency";
+
+/// Structural importance metric: for every dynamic node, how much longer the shortest start to
+/// end path becomes when that node is removed from the graph. Edge lengths are taken from an edge
+/// attribute such as latency or cost.
+pub struct ShortestPathDegradation {
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Length of every edge
+    pub latencies: EdgeValueMap<f32>,
+    /// Length used for edges that have none in 'latencies'
+    pub default_latency: f32,
+    pub start_id: u32,
+    pub end_id: u32,
+}
+
+/// Shortest path lengths with and without each node. Lengths are infinite when the end node
+/// can't be reached.
+#[derive(Debug, Clone)]
+pub struct ShortestPathResults {
+    /// Length of the shortest path with every node in the graph
+    pub base_length: f64,
+    pub nodes: NodeValueMap<PathDegradation>,
+}
+
+impl ShortestPathResults {
+    pub fn print(&self, graph: &Graph) {
+        println!("Shortest path length: {}", self.base_length);
+        for (id, node) in self.nodes.iter() {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            println!("{} ({}): length without {}, increase {}", name, id, node.length_without, node.increase);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PathDegradation {
+    /// Length of the shortest path without the node
+    pub length_without: f64,
+    /// Increase of the shortest path length caused by removing the node
+    pub increase: f64,
+}
+
+/// Dijkstra queue entry ordered by smallest distance first
+#[derive(PartialEq)]
+struct QueueEntry(f64, u32);
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then_with(|| self.1.cmp(&other.1))
+    }
+}
+
+impl ShortestPathDegradation {
+    fn adjacency(&self) -> HashMap<u32, Vec<(u32, f64)>> {
+        let mut adjacency: HashMap<u32, Vec<(u32, f64)>> = HashMap::new();
+        for edge in self.graph.get_edges() {
+            let length = *self.latencies.get(&(edge.from, edge.to)).unwrap_or(&self.default_latency) as f64;
+            adjacency.entry(edge.from).or_default().push((edge.to, length));
+        }
+        adjacency
+    }
+
+    /// Length of the shortest path from the start to the end node that avoids 'removed'
+    fn shortest_length(&self, adjacency: &HashMap<u32, Vec<(u32, f64)>>, removed: Option<u32>) -> f64 {
+        let mut distances: HashMap<u32, f64> = HashMap::from([(self.start_id, 0.0)]);
+        let mut queue = BinaryHeap::from([QueueEntry(0.0, self.start_id)]);
+        while let Some(QueueEntry(distance, node)) = queue.pop() {
+            if node == self.end_id {
+                return distance;
+            }
+            if distance > *distances.get(&node).unwrap_or(&f64::INFINITY) {
+                continue;
+            }
+            for (next, length) in adjacency.get(&node).into_iter().flatten() {
+                if Some(*next) == removed {
+                    continue;
+                }
+                let candidate = distance + length;
+                if candidate < *distances.get(next).unwrap_or(&f64::INFINITY) {
+                    distances.insert(*next, candidate);
+                    queue.push(QueueEntry(candidate, *next));
+                }
+            }
+        }
+        f64::INFINITY
+    }
+}
+
+impl Analysis for ShortestPathDegradation {
+    type Output = ShortestPathResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<ShortestPathResults, ThorError> {
+        info!("Starting Shortest Path Degradation Analysis");
+        let adjacency = self.adjacency();
+        let base_length = self.shortest_length(&adjacency, None);
+        let mut nodes = NodeValueMap::new();
+        for id in self.dynamic_ids.iter() {
+            ctx.check_cancelled()?;
+            let length_without = self.shortest_length(&adjacency, Some(*id));
+            nodes.insert(*id, PathDegradation { length_without, increase: length_without - base_length });
+        }
+        Ok(ShortestPathResults { base_length, nodes })
+    }
+}
@@ -0,0 +1,194 @@
+//! Variance based global sensitivity of the end node operability to the state of every node.
+//!
+//! The first order Sobol index of a node is the share of the variance of the end operability
+//! explained by the state of the node alone, the total index adds the variance of every
+//! interaction the node takes part in. A node whose total index is far above its first order
+//! index matters mostly together with other nodes, as the members of redundant paths do.
+//!
+//! The indices are estimated with the pick-freeze scheme: two independent states A and B are
+//! drawn, and for every node the state A with the value of the node taken from B is evaluated
+//! too. With f the end operability and V its variance over A and B,
+//!
+//! * first order (Saltelli): mean of f(B) * (f(A with i from B) - f(A)) / V
+//! * total (Jansen): mean of (f(A) - f(A with i from B))^2 / (2 V)
+//!
+//! A node with the same value in A and B leaves the state unchanged, so only the nodes that
+//! differ are rolled up again. The states must be independent draws, so generators that
+//! enumerate the states or draw them in pairs can't be used.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::{AnalysisGraph, RollUpEvaluator, StateEvaluator};
+use crate::analyses::criticality::vis_gen::visibility_states_gen::VisGen;
+use crate::errors::analysis::ThorError;
+use crate::network::{CsrLinks, Graph, LinkMap, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// Pick-freeze samples between two checks for cancellation
+const CANCEL_CHECK_INTERVAL: u64 = 256;
+
+pub struct SobolSensitivity {
+    pub threads: u8,
+    pub graph: Graph,
+    pub dynamic_ids: HashSet<u32>,
+    /// Must draw independent states, see the module documentation
+    pub vis_gen: Box<dyn VisGen>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub l_map: LinkMap,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// Number of pairs of states A and B, every pair rolls up at most two states more than
+    /// there are dynamic nodes
+    pub samples: u64,
+}
+
+/// Sobol indices of a single dynamic node
+#[derive(Debug, Clone)]
+pub struct SobolIndex {
+    pub first_order: f64,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SobolResults {
+    /// Number of pairs of states
+    pub samples: u64,
+    /// Mean and variance of the end operability over the states A and B
+    pub end_op_mean: f64,
+    pub variance: f64,
+    pub nodes: NodeValueMap<SobolIndex>,
+}
+
+impl SobolResults {
+    /// Prints the nodes from the largest to the smallest total index
+    pub fn print(&self, graph: &Graph) {
+        println!("Sobol indices over {} pick-freeze samples, mean end operability {}, variance {}", self.samples, self.end_op_mean, self.variance);
+        if self.variance == 0.0 {
+            println!("The end operability never varied, no node explains any of its variance");
+        }
+        let first_order: f64 = self.nodes.values().map(|i| i.first_order).sum();
+        println!("The first order indices sum to {}, interactions explain the rest of the variance", first_order);
+        let mut nodes: Vec<(&u32, &SobolIndex)> = self.nodes.iter().collect();
+        nodes.sort_by(|a, b| b.1.total.total_cmp(&a.1.total).then(a.0.cmp(b.0)));
+        for (id, index) in nodes {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            println!("{} ({}): first order {}, total {}", name, id, index.first_order, index.total);
+        }
+    }
+}
+
+impl Analysis for SobolSensitivity {
+    type Output = SobolResults;
+
+    fn run(&self, ctx: &AnalysisContext) -> Result<SobolResults, ThorError> {
+        info!("Starting Sobol Sensitivity Analysis");
+        if self.vis_gen.distinct() || self.vis_gen.paired() {
+            return Err(ThorError::Failed("The Sobol indices need independently drawn states, not enumerated or paired ones".into()));
+        }
+        let mut ids: Vec<u32> = self.dynamic_ids.iter().copied().collect();
+        ids.sort();
+        let path = Graph::get_bfs_path(&self.l_map, self.start_id);
+        let evaluator = RollUpEvaluator::new(
+            Arc::new(AnalysisGraph { graph: self.graph.clone(), links: CsrLinks::new(&self.l_map, &path) }),
+            dyn_clone::clone_box(&*self.roll_up_rule),
+            vec![(self.end_id, 1.0)],
+        );
+        let threads = self.threads.max(1) as u64;
+        let sums = match thread::scope(|scope| {
+            let handles: Vec<_> = self.vis_gen.split_to_threads(threads).into_iter().enumerate()
+                .map(|(thread, vis_gen)| {
+                    let samples = self.samples / threads + u64::from((thread as u64) < self.samples % threads);
+                    let (ids, evaluator) = (&ids, evaluator.clone());
+                    scope.spawn(move || pick_freeze(vis_gen, evaluator, ids, samples, ctx))
+                })
+                .collect();
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap_or(None))
+                .try_fold(SobolSums::new(ids.len()), |mut sums, other| {
+                    sums.add(&other?);
+                    Some(sums)
+                })
+        }) {
+            Some(sums) => sums,
+            // Tells a cancellation from a deadline that passed
+            None => return Err(ctx.check_cancelled().err().unwrap_or(ThorError::Cancelled)),
+        };
+        let count = 2.0 * sums.samples as f64;
+        let end_op_mean = if sums.samples == 0 { 0.0 } else { sums.sum / count };
+        let variance = if sums.samples == 0 { 0.0 } else { sums.sum_sq / count - end_op_mean * end_op_mean };
+        let index = |sum: f64, scale: f64| match variance > 0.0 {
+            true => sum / sums.samples as f64 / (scale * variance),
+            false => 0.0,
+        };
+        Ok(SobolResults {
+            samples: sums.samples,
+            end_op_mean,
+            variance,
+            nodes: ids.iter().enumerate()
+                .map(|(i, id)| (*id, SobolIndex { first_order: index(sums.first_order[i], 1.0), total: index(sums.total[i], 2.0) }))
+                .collect(),
+        })
+    }
+}
+
+/// Sums of a thread, the indices of the nodes are those of the sorted dynamic ids
+struct SobolSums {
+    samples: u64,
+    /// Sums of f and f^2 over the states A and B
+    sum: f64,
+    sum_sq: f64,
+    first_order: Vec<f64>,
+    total: Vec<f64>,
+}
+
+impl SobolSums {
+    fn new(nodes: usize) -> SobolSums {
+        SobolSums { samples: 0, sum: 0.0, sum_sq: 0.0, first_order: vec![0.0; nodes], total: vec![0.0; nodes] }
+    }
+
+    fn add(&mut self, other: &SobolSums) {
+        self.samples += other.samples;
+        self.sum += other.sum;
+        self.sum_sq += other.sum_sq;
+        for (sum, other) in self.first_order.iter_mut().zip(other.first_order.iter()) {
+            *sum += other;
+        }
+        for (sum, other) in self.total.iter_mut().zip(other.total.iter()) {
+            *sum += other;
+        }
+    }
+}
+
+/// Draws 'samples' pairs of states and sums the terms of the estimators, None if the context
+/// was cancelled or its deadline passed
+fn pick_freeze(mut vis_gen: Box<dyn VisGen>, mut evaluator: RollUpEvaluator, ids: &[u32], samples: u64, ctx: &AnalysisContext) -> Option<SobolSums> {
+    let mut sums = SobolSums::new(ids.len());
+    for sample in 0..samples {
+        if sample % CANCEL_CHECK_INTERVAL == 0 && (ctx.is_cancelled() || ctx.is_past_deadline()) {
+            return None;
+        }
+        let a = vis_gen.next_states();
+        let b = vis_gen.next_states();
+        let (f_a, f_b) = (evaluator.evaluate(&a), evaluator.evaluate(&b));
+        sums.samples += 1;
+        sums.sum += f_a + f_b;
+        sums.sum_sq += f_a * f_a + f_b * f_b;
+        for (i, id) in ids.iter().enumerate() {
+            if a.get(id) == b.get(id) {
+                continue;
+            }
+            let mut mixed = a.clone();
+            match b.get(id) {
+                Some(value) => mixed.insert(*id, *value),
+                None => mixed.remove(id),
+            };
+            let f_mixed = evaluator.evaluate(&mixed);
+            sums.first_order[i] += f_b * (f_mixed - f_a);
+            sums.total[i] += (f_a - f_mixed) * (f_a - f_mixed);
+        }
+    }
+    Some(sums)
+}
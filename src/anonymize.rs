@@ -0,0 +1,101 @@
+//! Anonymizing a graph so the model of a sensitive architecture can be shared, for example to
+//! reproduce a problem outside of the organisation that owns it.
+//!
+//! Every node is named 'n' followed by the xxh64 hash of its name keyed with a salt, and
+//! numbered in the order of the new names, so neither the names nor the order of the ids tell
+//! anything about the model. Nodes sharing a name get a '_2', '_3', ... suffix. The edges, the
+//! static nodes, the off chances, the edge attributes and the numeric and boolean node attributes
+//! are kept under the new ids, so the structure and the probabilities of the model are unchanged.
+//! Text node attributes, such as the ['KEY_ATTR'], and the warnings of the input are dropped.
+//!
+//! The ['MappedNode'] of every node leads back from the new ids and names to the original ones. It
+//! is meant to stay with the owner of the model, together with the salt, which must not be
+//! shared either: anyone knowing it can hash guessed names and compare them.
+
+use std::collections::HashMap;
+use std::error::Error;
+use crate::analyses::criticality::CriticalityData;
+use crate::checksum::xxh64;
+use crate::network::{AttrValue, EdgeValueMap, Graph, NodeValueMap};
+use crate::output::create_output;
+
+/// Original and new id and name of a node
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappedNode {
+    pub id: u32,
+    pub name: String,
+    pub new_id: u32,
+    pub new_name: String,
+}
+
+/// An anonymized graph with its data and the mapping back to the original nodes
+#[derive(Debug, Clone)]
+pub struct Anonymized {
+    pub graph: Graph,
+    pub data: CriticalityData,
+    /// In the order of the new ids
+    pub mapping: Vec<MappedNode>,
+}
+
+/// Anonymizes the 'graph' and its 'data' with the 'salt', see the module documentation
+pub fn anonymize(graph: &Graph, data: &CriticalityData, salt: &[u8]) -> Anonymized {
+    let seed = xxh64(salt, 0);
+    let mut nodes: Vec<(String, u32)> = graph.get_node_ids().into_iter()
+        .map(|id| (format!("n{:016x}", xxh64(graph.get_node(&id).unwrap().name.as_bytes(), seed)), id))
+        .collect();
+    nodes.sort();
+
+    let mut mapping = vec![];
+    let mut seen: HashMap<String, u32> = HashMap::new();
+    for (new_id, (hashed, id)) in nodes.into_iter().enumerate() {
+        let count = seen.entry(hashed.clone()).or_insert(0);
+        *count += 1;
+        let new_name = match *count {
+            1 => hashed,
+            n => format!("{}_{}", hashed, n),
+        };
+        mapping.push(MappedNode { id, name: graph.get_node(&id).unwrap().name.clone(), new_id: new_id as u32, new_name });
+    }
+    let new_ids: HashMap<u32, u32> = mapping.iter().map(|m| (m.id, m.new_id)).collect();
+
+    let mut anonymized = Graph::new();
+    for node in mapping.iter() {
+        anonymized.add_node(node.new_name.clone(), node.new_id);
+        for (attribute, value) in graph.get_node(&node.id).unwrap().attributes.iter() {
+            if !matches!(value, AttrValue::Text(_)) {
+                anonymized.set_node_attr(&node.new_id, attribute, value.clone());
+            }
+        }
+    }
+    for edge in graph.get_edges() {
+        anonymized.add_edge(new_ids[&edge.from], new_ids[&edge.to]);
+    }
+    anonymized.static_nodes = graph.static_nodes.iter().filter_map(|id| new_ids.get(id).copied()).collect();
+
+    let remap_edges = |values: &EdgeValueMap<f32>| values.iter()
+        .filter_map(|((from, to), value)| Some(((*new_ids.get(from)?, *new_ids.get(to)?), *value)))
+        .collect::<EdgeValueMap<f32>>();
+    let data = CriticalityData {
+        edge_attributes: data.edge_attributes.iter().map(|(name, values)| (name.clone(), remap_edges(values))).collect(),
+        off_chances: data.off_chances.iter()
+            .filter_map(|(id, chance)| Some((*new_ids.get(id)?, *chance)))
+            .collect::<NodeValueMap<f32>>(),
+        warnings: vec![],
+    };
+    Anonymized { graph: anonymized, data, mapping }
+}
+
+/// Writes the 'mapping' as csv with the columns 'id', 'name', 'original_id' and 'original_name'
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written
+pub fn write_mapping(path: &str, mapping: &[MappedNode]) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(create_output(path)?);
+    writer.write_record(["id", "name", "original_id", "original_name"])?;
+    for node in mapping {
+        writer.write_record([node.new_id.to_string(), node.new_name.clone(), node.id.to_string(), node.name.clone()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
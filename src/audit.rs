@@ -0,0 +1,108 @@
+//! Append-only audit log of the requests answered in server mode, so every result can be traced
+//! back to who asked for it and what it was computed from.
+//!
+//! With '--audit-log <path>' the 'serve' and 'daemon' commands append a json object per line for
+//! every request that submits work, with:
+//!
+//! * 'time': when it was recorded, in UTC
+//! * 'event': such as 'analyze', 'validate', 'analyze_stream', 'job_submitted', 'job_cancelled'
+//!   or 'job_finished'
+//! * 'user' and 'client': the user named by the ['USER_HEADER'] and the address of the client.
+//!   The server doesn't authenticate, the header is expected to be set by the proxy that does.
+//! * 'inputs': the size and xxh64 hash of the posted graph, or of the files read by a job, see
+//!   ['crate::manifest']
+//! * 'config': the query parameters of the request or the arguments of the job
+//! * 'status' and 'result_id': the http status of the answer, or 'result' or 'error' for a
+//!   stream, and the xxh64 hash of the body or the message carrying the results, so a result can
+//!   be matched to its entry by hashing what was received
+//! * 'previous': the xxh64 hash of the line before, null for the first line. A line that was
+//!   changed or removed breaks the chain.
+//!
+//! Lines are only ever appended and synced to the disk before the answer is sent. A request
+//! that can't be recorded is refused rather than answered unrecorded.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use crate::checksum::xxh64;
+use crate::json::JsonValue;
+use crate::manifest::utc_timestamp;
+use crate::server::Request;
+
+/// Header naming the user a request is made for
+pub const USER_HEADER: &str = "X-User";
+
+/// Hex xxh64 hash of 'data', as used for the inputs and the result ids
+pub fn content_id(data: &[u8]) -> String {
+    format!("{:016x}", xxh64(data, 0))
+}
+
+/// The 'user' and 'client' of an entry for the 'request'
+pub fn requester(request: &Request) -> JsonValue {
+    JsonValue::object()
+        .with("user", request.header(USER_HEADER).map(|u| u.to_string()))
+        .with("client", request.peer.clone())
+}
+
+/// The query parameters of the 'request' as an object
+pub fn query_config(request: &Request) -> JsonValue {
+    let mut config = JsonValue::object();
+    for (key, value) in request.query.iter() {
+        config.insert(key.as_str(), value.as_str());
+    }
+    config
+}
+
+/// The audit log file, see the module documentation
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    /// The file and the hash of its last line, shared by the threads answering requests
+    state: Mutex<(File, Option<String>)>,
+}
+
+impl AuditLog {
+    /// Opens the log at 'path' to append to it, continuing the chain of the lines it holds
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the file can't be read or opened
+    pub fn open(path: &Path) -> io::Result<AuditLog> {
+        let previous = match path.exists() {
+            true => std::fs::read_to_string(path)?.lines().rev()
+                .find(|line| !line.trim().is_empty())
+                .map(|line| content_id(line.as_bytes())),
+            false => None,
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(AuditLog { path: path.to_path_buf(), state: Mutex::new((file, previous)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends an entry of the 'event' with the 'fields' and syncs it to the disk
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the entry can't be written
+    pub fn record(&self, event: &str, fields: JsonValue) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let mut entry = JsonValue::object()
+            .with("time", utc_timestamp(SystemTime::now()))
+            .with("event", event);
+        for (key, value) in fields.as_object().into_iter().flatten() {
+            entry.insert(key.as_str(), value.clone());
+        }
+        entry.insert("previous", state.1.clone());
+        let line = entry.to_string();
+        state.0.write_all(format!("{}\n", line).as_bytes())?;
+        state.0.sync_data()?;
+        state.1 = Some(content_id(line.as_bytes()));
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Checksums used by the file formats the program writes and reads.
+
+/// CRC-32 (ISO-HDLC) as used by png, gzip and zip
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Continues the CRC-32 'crc' of the data read so far with 'data'
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+/// Adler-32 as used by zlib streams
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+const XXH_PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const XXH_PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const XXH_PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const XXH_PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const XXH_PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME_2)).rotate_left(31).wrapping_mul(XXH_PRIME_1)
+}
+
+fn xxh64_merge(acc: u64, value: u64) -> u64 {
+    (acc ^ xxh64_round(0, value)).wrapping_mul(XXH_PRIME_1).wrapping_add(XXH_PRIME_4)
+}
+
+/// XXH64 as used by zstd frames
+pub fn xxh64(data: &[u8], seed: u64) -> u64 {
+    let u64_at = |b: &[u8]| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+    let mut stripes = data.chunks_exact(32);
+    let mut hash = match data.len() >= 32 {
+        true => {
+            let mut v = [
+                seed.wrapping_add(XXH_PRIME_1).wrapping_add(XXH_PRIME_2),
+                seed.wrapping_add(XXH_PRIME_2),
+                seed,
+                seed.wrapping_sub(XXH_PRIME_1),
+            ];
+            for stripe in stripes.by_ref() {
+                for (i, lane) in v.iter_mut().enumerate() {
+                    *lane = xxh64_round(*lane, u64_at(&stripe[i * 8..]));
+                }
+            }
+            let hash = v[0].rotate_left(1).wrapping_add(v[1].rotate_left(7))
+                .wrapping_add(v[2].rotate_left(12)).wrapping_add(v[3].rotate_left(18));
+            v.iter().fold(hash, |hash, lane| xxh64_merge(hash, *lane))
+        }
+        false => seed.wrapping_add(XXH_PRIME_5),
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    let mut rest = stripes.remainder();
+    while rest.len() >= 8 {
+        hash ^= xxh64_round(0, u64_at(rest));
+        hash = hash.rotate_left(27).wrapping_mul(XXH_PRIME_1).wrapping_add(XXH_PRIME_4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        let word = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as u64;
+        hash ^= word.wrapping_mul(XXH_PRIME_1);
+        hash = hash.rotate_left(23).wrapping_mul(XXH_PRIME_2).wrapping_add(XXH_PRIME_3);
+        rest = &rest[4..];
+    }
+    for byte in rest {
+        hash ^= (*byte as u64).wrapping_mul(XXH_PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(XXH_PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(XXH_PRIME_3);
+    hash ^ (hash >> 32)
+}
+
+/// SHA-1 as used by the WebSocket handshake, see ['crate::websocket']. Not to be relied on for
+/// anything that has to resist collisions.
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+    // The message is padded with a one bit, zeros and its length in bits to a multiple of 64 bytes
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+    let mut digest = [0u8; 20];
+    for (bytes, state) in digest.chunks_exact_mut(4).zip(h) {
+        bytes.copy_from_slice(&state.to_be_bytes());
+    }
+    digest
+}
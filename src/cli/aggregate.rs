@@ -0,0 +1,94 @@
+//! 'aggregate <log>': computes metrics from the states logged by 'analyze --sample-log'.
+
+use std::error::Error;
+use crate::analyses::criticality::ranking::SUMMARY_PERCENTILES;
+use crate::analyses::criticality::samples::SampleLog;
+use crate::analyses::criticality::statistics::node_statistics;
+use crate::cli::{alert_output, arg_value, check_alerts, filter_outputs, has_flag, load_input, path_arg, render_outputs, std_output};
+use crate::input::read_node_groups;
+use crate::network::Graph;
+use crate::output::Output;
+
+/// Computes the '--metric' selected from the sample log following the command, without rolling
+/// up the graph again:
+///
+/// * 'criticality', the default: the results of the logged run, written to the standard output
+///   as drawings with '--svg', '--png' or '--html' and as Cytoscape.js json with '--cytoscape',
+///   only for the nodes passing '--filter <expression>' if given, see
+///   ['crate::cli::filter_outputs'], and alerted on with '--alert-file' and '--fail-on-critical',
+///   see ['crate::output::bands']
+/// * 'importance': the Birnbaum importance, risk achievement and risk reduction worth of every
+///   node
+/// * 'quantiles': the end node value at the '--percentiles', a comma separated list
+/// * 'groups': the criticality of the groups of nodes read from the csv file '--groups', see
+///   ['read_node_groups']
+/// * 'statistics': the correlation of the state of every node with the end node value, and
+///   their mutual information with '--mutual-information', see
+///   ['crate::analyses::criticality::statistics']
+///
+/// The log holds no graph, nodes are named after the graph given by '--input' if any.
+///
+/// # Errors
+///
+/// Returns an error if the log, the graph or the groups can't be read, or the metric is unknown
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = path_arg(args).ok_or("The aggregate command needs the path of a sample log")?;
+    let log = SampleLog::read(path)?;
+    let graph = match arg_value(args, "--input") {
+        Some(_) => load_input(args)?.graph,
+        None => Graph::default(),
+    };
+    let name = |id: &u32| graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+    println!("Aggregating {} logged states of {} nodes", log.end_values.len(), log.ids.len());
+    match arg_value(args, "--metric").map(|m| m.as_str()).unwrap_or("criticality") {
+        "criticality" => {
+            let results = log.criticality();
+            let mut outputs: Vec<Box<dyn Output>> = vec![Box::new(std_output(args, results.nodes.len())?)];
+            outputs.extend(render_outputs(args, None));
+            let alert = alert_output(args)?;
+            if let Some(alert) = alert.as_ref() {
+                outputs.push(Box::new(alert.clone()));
+            }
+            for output in filter_outputs(args, outputs)?.iter() {
+                output.write(&graph, &results)?;
+            }
+            check_alerts(args, alert.as_ref())?;
+        }
+        "importance" => {
+            let ratio = |r: Option<f64>| r.map(|r| r.to_string()).unwrap_or("-".to_string());
+            for node in log.importance() {
+                println!("{} ({}): birnbaum {}, risk achievement {}, risk reduction {}",
+                         name(&node.id), node.id, node.birnbaum, ratio(node.risk_achievement), ratio(node.risk_reduction));
+            }
+        }
+        "quantiles" => {
+            let percentiles = match arg_value(args, "--percentiles") {
+                Some(list) => list.split(',')
+                    .map(|p| p.trim().parse::<f64>().map_err(|_| format!("Invalid percentile '{}'", p)))
+                    .collect::<Result<Vec<f64>, String>>()?,
+                None => SUMMARY_PERCENTILES.to_vec(),
+            };
+            for (p, value) in log.quantiles(&percentiles) {
+                println!("p{}: {}", p, value);
+            }
+        }
+        "groups" => {
+            let groups_path = arg_value(args, "--groups").ok_or("The groups metric needs the groups of nodes, given by --groups <path>")?;
+            for group in log.groups(&read_node_groups(groups_path, false)?) {
+                println!("{}: criticality {}, mean end operability {} in {} states with every member on, {} in {} states with a member off",
+                         group.name, group.criticality, group.mean_end_on, group.on_count, group.mean_end_off, group.off_count);
+            }
+        }
+        "statistics" => {
+            let value = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or("-".to_string());
+            for node in node_statistics(&log, has_flag(args, "--mutual-information")) {
+                match node.mutual_information {
+                    Some(information) => println!("{} ({}): correlation {}, mutual information {} bits", name(&node.id), node.id, value(node.correlation), information),
+                    None => println!("{} ({}): correlation {}", name(&node.id), node.id, value(node.correlation)),
+                }
+            }
+        }
+        other => return Err(format!("Unknown metric '{}', the metrics are criticality, importance, quantiles, groups and statistics", other).into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,827 @@
+//! 'analyze': reads the input and runs the analysis selected by '--analysis'.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Instant;
+use log::{info, Level};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::analyses::Analysis;
+use crate::analyses::criticality::{AnalysisGraph, CriticalityResults, RollUpEvaluator};
+use crate::analyses::criticality::builder::{CriticalityBuilder, DEFAULT_SAMPLES};
+use crate::analyses::criticality::compare::{Comparison, DEFAULT_CONFIDENCE};
+use crate::analyses::criticality::delta::{DeltaCriticality, ModelVersion};
+#[cfg(feature = "serde")]
+use crate::analyses::criticality::compare::DEFAULT_TOLERANCE;
+use crate::analyses::criticality::loop_condition::MaxLoopCondition;
+use crate::analyses::criticality::mission_time::MissionTimeCurve;
+use crate::analyses::criticality::pairwise::PairwiseCriticality;
+use crate::analyses::criticality::recording::{RecordingGen, ReplayGen};
+use crate::analyses::criticality::surrogate::{DEFAULT_MARGIN, DEFAULT_TRAINING};
+use crate::analyses::criticality::vis_gen::visibility_states_gen::{AntitheticGen, BetaFactorGen, DependencyGen, GrayCodeGen, Lifetime, LifetimeGen, RandomGen, seeded_rng, VisGen};
+use crate::analyses::dominators::{bridges, mark_single_points};
+use crate::analyses::estimate::CostEstimate;
+use crate::analyses::exact::ExactCriticality;
+use crate::analyses::exact::cut_sets::{CutSets, DEFAULT_CUTOFF, DEFAULT_MAX_ORDER};
+use crate::analyses::exact::tornado::{bounds_from_attributes, OPTIMISTIC_ATTR, PESSIMISTIC_ATTR, Tornado};
+use crate::analyses::flow::{CAPACITY_ATTR, Flow};
+use crate::analyses::hardening::{costs_from_attributes, GreedyHardening, Hardening};
+use crate::analyses::influence::{DEFAULT_DAMPING, Influence};
+use crate::analyses::markov::{MarkovAvailability, rates_from_attributes};
+use crate::analyses::paths::{DEFAULT_MAX_PATHS, PathContribution};
+use crate::analyses::sobol::SobolSensitivity;
+use crate::analyses::reliability::TerminalReliability;
+use crate::analyses::removal::NodeRemoval;
+use crate::analyses::scenario::ScenarioEvaluation;
+use crate::analyses::search::{DEFAULT_REPORTED_DESIGNS, DesignSpace};
+use crate::analyses::search::annealing::{DEFAULT_START_TEMPERATURE, SimulatedAnnealing};
+use crate::analyses::search::genetic::{DEFAULT_POPULATION, GeneticSearch};
+use crate::analyses::shortest_path::{LATENCY_ATTR, ShortestPathDegradation};
+use crate::cli::{alert_output, analysis_context, arg_number, arg_value, check_alerts, coverage_lines, EndWeights, filter_outputs, has_flag, load_input, LoadedInput, NodePairs, parse_node, render_outputs, select_pairs, std_output, thread_count, with_arg_value};
+use crate::errors::analysis::BaselineDeviationError;
+use crate::errors::validation::ValidationError;
+use crate::hooks::HookSummaryOutput;
+use crate::input::{read_ccf_groups, read_dependencies, read_event_tree, read_scenarios};
+use crate::json;
+use crate::json::JsonValue;
+use crate::logging::event;
+use crate::network::{CsrLinks, Graph};
+use crate::orchestrator::Orchestrator;
+use crate::output::{Output, write_output};
+use crate::output::event_tree::EventTreeOutput;
+#[cfg(feature = "serde")]
+use crate::output::json::{JsonOutput, StoredResults, run_metadata};
+#[cfg(feature = "serde")]
+use crate::output::STDOUT_PATH;
+use crate::output::html::tornado_to_html;
+use crate::output::neo4j::Neo4jOutput;
+use crate::output::networkx::NodeLinkOutput;
+use crate::pipeline::PipelineState;
+use crate::registry::AnalysisRegistry;
+use crate::validation::validate_model;
+
+/// Runs the analysis on the input given by the arguments, see ['crate::cli::load_input']. With
+/// '--per-component' every weakly connected component holding a pair of '--pairs' is analysed on
+/// its own, nodes of other components are left out.
+///
+/// '--format json' writes the results to the standard output as stored by '--results' instead of
+/// the text report, the remaining report lines then go to the standard error. Together with the
+/// '-' input path this lets the command sit in a shell pipeline.
+///
+/// The sampling analyses run on '--threads' worker threads, see ['crate::cli::thread_count'].
+///
+/// '--baseline <path>' checks the criticality of every node against the results stored by an
+/// earlier '--results', and fails with a ['BaselineDeviationError'] if a node moved by more than
+/// '--tolerance' (['DEFAULT_TOLERANCE'] if not given) or is in only one of the results.
+///
+/// '--node-link <path>' writes the graph with the criticality of every node as node-link json
+/// for NetworkX, see ['crate::export::networkx'].
+///
+/// '--cytoscape <path>' writes the graph with the criticality and heatmap color of every node as
+/// Cytoscape.js elements json for the web viewer, see ['crate::export::cytoscape'].
+///
+/// '--sample-log <path>' logs every state the criticality analysis evaluates with its end node
+/// value, for the 'aggregate' command, see ['crate::analyses::criticality::samples'].
+///
+/// '--analysis delta --against <path>' evaluates every sampled state on the input and on the
+/// version of the model at 'path', read with the same flags, and prints the change of the mean end
+/// operability and of the criticality of every node with margins at '--confidence', see
+/// ['crate::analyses::criticality::delta'].
+///
+/// '--bands' classifies the nodes of the results into bands of criticality, '--alert-file <path>'
+/// writes the nodes in the critical band and '--fail-on-critical' fails the run if there are any,
+/// see ['crate::output::bands'].
+///
+/// '--filter <expression>' writes only the results of the nodes passing the filter to every
+/// output, such as 'name~^DB_ and crit>0.1', see ['crate::output::filter'].
+///
+/// The criticality analysis evaluates every state exactly, weighted by its probability, when the
+/// dynamic nodes have fewer states than it would sample and are drawn independently, unless the
+/// states are recorded or logged. '--no-exhaustive' samples them anyway, see
+/// ['crate::analyses::criticality::exhaustive']. '--gray-code' evaluates every state exactly
+/// whatever the samples, it fails if the nodes aren't drawn independently.
+///
+/// '--coverage' reports the share of the state space, and of its probability mass where known,
+/// covered by the unique states every sampling evaluated, overall and per thread, see
+/// ['crate::analyses::criticality::coverage'].
+///
+/// '--surrogate' screens the states of the criticality analysis with a model fitted to the first
+/// '--surrogate-training' states (['DEFAULT_TRAINING'] if not given), rolling up only those it
+/// predicts more than '--surrogate-margin' (['DEFAULT_MARGIN']) away from 0 and 1, see
+/// ['crate::analyses::criticality::surrogate'].
+///
+/// # Errors
+///
+/// Returns an error if the input can't be read, the options are invalid or the analysis fails
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let loading = Instant::now();
+    let input = load_input(args)?;
+    event(Level::Info, "phase_completed", JsonValue::object()
+        .with("phase", "load_input")
+        .with("nodes", input.graph.get_node_ids().len() as u64)
+        .with("seconds", loading.elapsed().as_secs_f64()));
+    if !has_flag(args, "--per-component") {
+        return analyze(args, input);
+    }
+
+    // Every component holding a pair is analysed on its own, components without one are skipped
+    let (pairs, _) = select_pairs(args, &input.graph)?;
+    for component in input.graph.components() {
+        let ids: HashSet<u32> = component.into_iter().collect();
+        let component_pairs: Vec<String> = pairs.iter()
+            .filter(|(source, _)| ids.contains(source))
+            .map(|(source, sink)| format!("{}:{}", source, sink))
+            .collect();
+        if component_pairs.is_empty() {
+            continue;
+        }
+        report_line(args, &format!("== component of {} nodes, pairs {} ==", ids.len(), component_pairs.join(",")))?;
+        let component_args = with_arg_value(args, "--pairs", &component_pairs.join(","));
+        let component_input = LoadedInput {
+            graph: input.graph.subgraph(&ids),
+            crit_data: input.crit_data.clone(),
+            roll_up_rule: dyn_clone::clone_box(&*input.roll_up_rule),
+            neo4j: input.neo4j.clone(),
+        };
+        analyze(&component_args, component_input)?;
+    }
+    Ok(())
+}
+
+/// Runs the analysis selected by the arguments on the loaded 'input'
+fn analyze(args: &[String], input: LoadedInput) -> Result<(), Box<dyn Error>> {
+    let LoadedInput { mut graph, crit_data, roll_up_rule, neo4j } = input;
+
+    let mut outputs: Vec<Box<dyn Output>> = match json_format(args)? {
+        false => vec![Box::new(std_output(args, graph.get_node_ids().len())?)],
+        #[cfg(feature = "serde")]
+        true => vec![Box::new(JsonOutput { path: STDOUT_PATH.to_string(), run: run_metadata(args) })],
+        #[cfg(not(feature = "serde"))]
+        true => return Err("Writing json results needs the 'serde' feature".into()),
+    };
+    // Chains the end node outcome into an event tree and reports the expected consequence
+    if let Some(path) = arg_value(args, "--event-tree") {
+        outputs.push(Box::new(EventTreeOutput { tree: read_event_tree(path, false)? }));
+    }
+    // Stores the results so the 'report' command can print them again later
+    #[cfg(feature = "serde")]
+    if let Some(path) = arg_value(args, "--results") {
+        outputs.push(Box::new(JsonOutput { path: path.to_string(), run: run_metadata(args) }));
+    }
+    outputs.extend(render_outputs(args, crit_data.alpha()));
+    // Writes the results back onto the graph for NetworkX
+    if let Some(path) = arg_value(args, "--node-link") {
+        outputs.push(Box::new(NodeLinkOutput { path: path.to_string(), data: crit_data.clone() }));
+    }
+    if let Some(connection) = neo4j {
+        outputs.push(Box::new(Neo4jOutput::new(connection)));
+    }
+    // Summarizes the results for the payload of the hooks
+    if arg_value(args, "--on-complete").is_some() || arg_value(args, "--on-failure").is_some() {
+        outputs.push(Box::new(HookSummaryOutput {}));
+    }
+    // Collects the nodes in the critical band, failing the run once it is over if asked to
+    let alert = alert_output(args)?;
+    if let Some(alert) = alert.as_ref() {
+        outputs.push(Box::new(alert.clone()));
+    }
+    let outputs = filter_outputs(args, outputs)?;
+
+    let mut l_map = graph.links_map();
+    let (pairs, end_weights) = select_pairs(args, &graph)?;
+    let (start_id, end_id) = pairs[0];
+    // Nodes on no path from a source to its sink, or to a weighted end, only cost sampling effort
+    if has_flag(args, "--prune") {
+        let mut targets = pairs.clone();
+        targets.extend(end_weights.iter().map(|(end, _)| (start_id, *end)));
+        let relevant: HashSet<u32> = targets.iter()
+            .flat_map(|(source, sink)| Graph::nodes_on_paths(&l_map, *source, *sink))
+            .collect();
+        info!("Pruned {} nodes that lie on no start to end path", graph.get_node_ids().len() - relevant.len());
+        graph = graph.subgraph(&relevant);
+        l_map = graph.links_map();
+    }
+    // Flags the nodes every path from the start to the end passes through next to their results,
+    // and lists the edges every path passes through
+    if has_flag(args, "--single-points") {
+        let points = mark_single_points(&mut graph, start_id, end_id);
+        info!("Found {} single points of failure", points.len());
+        let name = |id: &u32| graph.get_node(id).map(|n| n.name.to_string()).unwrap_or_default();
+        let bridges: Vec<String> = bridges(&l_map, start_id, end_id).iter()
+            .map(|(from, to)| format!("{} ({}) -> {} ({})", name(from), from, name(to), to))
+            .collect();
+        match bridges.is_empty() {
+            true => report_line(args, "Bridge edges: none")?,
+            false => report_line(args, &format!("Bridge edges: {}", bridges.join(", ")))?,
+        }
+    }
+    let dynamic_ids = mark_terminals(&mut graph, &pairs, &end_weights);
+    // Scores the influence of every node next to its results, to compare it with the criticality
+    if has_flag(args, "--influence") {
+        let influence = Influence {
+            graph: graph.clone(),
+            dynamic_ids: dynamic_ids.clone(),
+            weights: crit_data.alpha().cloned().unwrap_or_default(),
+            damping: arg_number(args, "--damping", DEFAULT_DAMPING)?,
+            end_id,
+        };
+        influence.run(&analysis_context(args)?)?.mark(&mut graph);
+    }
+
+    // Common-cause groups move the share beta of the off chance of their members to a shared cause
+    let ccf_groups = match arg_value(args, "--ccf-groups") {
+        Some(path) => read_ccf_groups(path, false)?,
+        None => vec![],
+    };
+    // Lifetimes are read from the node attributes, at a mission time they replace the off chances
+    let lifetimes = Lifetime::from_attributes(&graph)?;
+    let mission_time = match arg_value(args, "--mission-time") {
+        Some(t) => Some(t.parse::<f64>()?),
+        None => None,
+    };
+    // Every generator is seeded from '--seed', which the run is given if it has none, see
+    // ['crate::cli::run']
+    let seed = arg_value(args, "--seed").map(|s| s.parse::<u64>()).transpose()?;
+    let mut seeds = seeded_rng(seed);
+    let vis_gen: Box<dyn VisGen> = match ccf_groups.is_empty() {
+        true if mission_time.is_some() => Box::new(
+            LifetimeGen {
+                rng: StdRng::seed_from_u64(seeds.gen()),
+                ids: dynamic_ids.clone(),
+                lifetimes: lifetimes.clone(),
+                off_chances: crit_data.off_chances.clone(),
+                mission_time: mission_time.unwrap(),
+            }
+        ),
+        true => {
+            let random = RandomGen {
+                rng: StdRng::seed_from_u64(seeds.gen()),
+                ids: dynamic_ids.clone(),
+                off_chances: crit_data.off_chances.clone(),
+            };
+            // Pairs every state with its antithetic complement to lower the variance
+            match has_flag(args, "--antithetic") {
+                true => Box::new(AntitheticGen::new(random)),
+                false => Box::new(random),
+            }
+        }
+        false => {
+            let independent = RandomGen {
+                rng: StdRng::seed_from_u64(seeds.gen()),
+                ids: dynamic_ids.clone(),
+                off_chances: BetaFactorGen::independent_off_chances(&crit_data.off_chances, &dynamic_ids, &ccf_groups),
+            };
+            Box::new(BetaFactorGen {
+                rng: StdRng::seed_from_u64(seeds.gen()),
+                ..BetaFactorGen::new(Box::new(independent), ccf_groups, &crit_data.off_chances)
+            })
+        }
+    };
+    // Dependencies between nodes are enforced on the sampled states
+    let vis_gen: Box<dyn VisGen> = match arg_value(args, "--dependencies") {
+        Some(path) => Box::new(DependencyGen {
+            rng: StdRng::seed_from_u64(seeds.gen()),
+            ..DependencyGen::new(vis_gen, read_dependencies(path, false)?, crit_data.off_chances.clone())
+        }),
+        None => vis_gen,
+    };
+    // Enumerates every state once instead of sampling, weighted by the probabilities of the
+    // generator, the analysis then covers the state space
+    let enumeration = match has_flag(args, "--gray-code") {
+        true => Some(GrayCodeGen::new(&dynamic_ids)?),
+        false => None,
+    };
+    // Replays the states of a recording instead of sampling them, and records the states used
+    let vis_gen: Box<dyn VisGen> = match arg_value(args, "--replay") {
+        Some(path) => Box::new(ReplayGen::read(path)?),
+        None => vis_gen,
+    };
+    let vis_gen: Box<dyn VisGen> = match arg_value(args, "--record") {
+        Some(path) => Box::new(RecordingGen::new(vis_gen, &dynamic_ids, path)?),
+        None => vis_gen,
+    };
+    let threads = thread_count(args)?;
+    let samples = sample_count(args, enumeration.as_ref())?;
+    // Validates the input and estimates the cost of sampling it instead of running the analysis
+    if has_flag(args, "--dry-run") {
+        let report = validate_model(&graph, &crit_data, &pairs);
+        report.print();
+        if !report.is_valid() {
+            return Err(ValidationError { errors: report.errors }.into());
+        }
+        let mut evaluator = RollUpEvaluator::new(
+            Arc::new(AnalysisGraph { links: CsrLinks::new(&l_map, &Graph::get_bfs_path(&l_map, start_id)), graph }),
+            roll_up_rule,
+            match end_weights.is_empty() {
+                true => vec![(end_id, 1.0)],
+                false => end_weights,
+            },
+        );
+        let mut calibration_gen = vis_gen.split_to_threads(1).pop().unwrap();
+        CostEstimate::measure(&mut evaluator, calibration_gen.as_mut(), dynamic_ids.len(), samples, threads).print();
+        return Ok(());
+    }
+    let loop_condition = Box::new(MaxLoopCondition { max: samples, index: 0 });
+    let ctx = analysis_context(args)?;
+    let analysis = arg_value(args, "--analysis").map(|a| a.as_str()).unwrap_or("criticality");
+    // Read before the run so a missing baseline doesn't waste the sampling
+    let baseline = read_baseline(args)?;
+    if baseline.is_some() && analysis != "criticality" {
+        return Err(format!("'--baseline' checks criticality results, the {} analysis has none", analysis).into());
+    }
+    if has_flag(args, "--sample-log") && analysis != "criticality" {
+        return Err(format!("'--sample-log' logs the states of the criticality analysis, not of the {} analysis", analysis).into());
+    }
+    if has_flag(args, "--surrogate") && analysis != "criticality" {
+        return Err(format!("'--surrogate' screens the states of the criticality analysis, not of the {} analysis", analysis).into());
+    }
+    if enumeration.is_some() && analysis != "criticality" {
+        return Err(format!("'--gray-code' enumerates the states of the criticality analysis, not of the {} analysis", analysis).into());
+    }
+    let start = Instant::now();
+    match analysis {
+        // A list of analyses of the registry runs concurrently on the loaded graph
+        names if names.contains(',') => {
+            let names: Vec<&str> = names.split(',').map(|n| n.trim()).collect();
+            let orchestrator = Orchestrator::from_names(&AnalysisRegistry::default(), &names, &options(args)?)?;
+            let state = PipelineState::new(graph, dynamic_ids, crit_data.off_chances.clone(), roll_up_rule, start_id, end_id);
+            for outcome in orchestrator.run(&state, &ctx)? {
+                println!("== {} ({:?}) ==", outcome.name, outcome.elapsed);
+                print!("{}", outcome.report);
+                // Only the standard output, the other outputs would overwrite each other's files
+                if let Some(results) = outcome.results {
+                    outputs[0].write(&state.graph, &results)?;
+                }
+            }
+        }
+        "criticality" => {
+            let mut builder = CriticalityBuilder::new(graph)
+                .dynamic_ids(dynamic_ids)
+                .vis_gen(vis_gen)
+                .loop_condition(loop_condition)
+                .roll_up_rule(roll_up_rule)
+                .start_id(start_id)
+                .end_id(end_id)
+                .end_weights(end_weights)
+                .threads(threads)
+                .exhaustive_fallback(!has_flag(args, "--no-exhaustive"))
+                .enumerate(enumeration.is_some());
+            for output in outputs {
+                builder = builder.output(output);
+            }
+            // Logs the evaluated states so the 'aggregate' command can compute other metrics
+            if let Some(path) = arg_value(args, "--sample-log") {
+                builder = builder.sample_log(path);
+            }
+            if has_flag(args, "--surrogate") {
+                let training = arg_number(args, "--surrogate-training", DEFAULT_TRAINING)?;
+                let margin = arg_number(args, "--surrogate-margin", DEFAULT_MARGIN)?;
+                if training == 0 || !(0.0..0.5).contains(&margin) {
+                    return Err("'--surrogate-training' must be positive and '--surrogate-margin' between 0 and 0.5".into());
+                }
+                builder = builder.surrogate(training, margin);
+            }
+            let results = builder.build()?.run(&ctx)?;
+            if let Some((baseline, tolerance)) = baseline {
+                check_baseline(args, &baseline, &results, tolerance)?;
+            }
+        }
+        "flow" => {
+            let capacity_attr = arg_value(args, "--capacity").map(|a| a.as_str()).unwrap_or(CAPACITY_ATTR);
+            let flow = Flow {
+                threads,
+                graph,
+                dynamic_ids,
+                vis_gen,
+                loop_condition,
+                capacities: crit_data.edge_attributes.get(capacity_attr).cloned().unwrap_or_default(),
+                default_capacity: 1.0,
+                start_id,
+                end_id,
+                outputs,
+            };
+            flow.run(&ctx)?;
+        }
+        "reliability" => {
+            // The start and end nodes when no terminals are given
+            let terminals = match arg_value(args, "--terminals") {
+                Some(terminals) => terminals.split(',').map(|t| parse_node(t, &graph)).collect::<Result<Vec<u32>, _>>()?,
+                None => vec![start_id, end_id],
+            };
+            let reliability = TerminalReliability {
+                threads,
+                graph,
+                dynamic_ids,
+                vis_gen,
+                loop_condition,
+                terminals,
+                outputs,
+            };
+            reliability.run(&ctx)?;
+        }
+        "influence" => {
+            let influence = Influence {
+                graph,
+                dynamic_ids,
+                weights: crit_data.alpha().cloned().unwrap_or_default(),
+                damping: arg_number(args, "--damping", DEFAULT_DAMPING)?,
+                end_id,
+            };
+            influence.run(&ctx)?.print(&influence.graph);
+        }
+        // Attributes the end operability to the paths that carry it, see the module documentation
+        "paths" => {
+            let paths = PathContribution {
+                threads,
+                graph,
+                dynamic_ids,
+                vis_gen,
+                loop_condition,
+                start_id,
+                end_id,
+                max_paths: arg_number(args, "--max-paths", DEFAULT_MAX_PATHS)?,
+            };
+            paths.run(&ctx)?.print(&paths.graph);
+        }
+        // First order and total Sobol indices of the end operability, see the module documentation
+        "sobol" => {
+            let sobol = SobolSensitivity {
+                threads,
+                graph,
+                dynamic_ids,
+                vis_gen,
+                roll_up_rule,
+                l_map,
+                start_id,
+                end_id,
+                samples: arg_number(args, "--samples", DEFAULT_SAMPLES)?,
+            };
+            sobol.run(&ctx)?.print(&sobol.graph);
+        }
+        "shortest-path" => {
+            let latency_attr = arg_value(args, "--latency").map(|a| a.as_str()).unwrap_or(LATENCY_ATTR);
+            let shortest_path = ShortestPathDegradation {
+                graph,
+                dynamic_ids,
+                latencies: crit_data.edge_attributes.get(latency_attr).cloned().unwrap_or_default(),
+                default_latency: 1.0,
+                start_id,
+                end_id,
+            };
+            shortest_path.run(&ctx)?.print(&shortest_path.graph);
+        }
+        "scenarios" => {
+            let scenarios_path = arg_value(args, "--scenarios")
+                .ok_or("The scenarios analysis needs a --scenarios <path> file")?;
+            let scenarios = ScenarioEvaluation {
+                graph,
+                l_map,
+                roll_up_rule,
+                start_id,
+                end_id,
+                scenarios: read_scenarios(scenarios_path, false)?,
+            };
+            for result in scenarios.run(&ctx)? {
+                println!("{}: end operability {}", result.name, result.end_operability);
+            }
+        }
+        "exact" => {
+            let exact = ExactCriticality {
+                graph,
+                dynamic_ids,
+                off_chances: crit_data.off_chances.clone(),
+                roll_up_rule,
+                l_map,
+                start_id,
+                end_id,
+                outputs,
+            };
+            exact.run(&ctx)?;
+        }
+        // Ranks the minimal cut sets of the end node by their probability
+        "cut-sets" => {
+            let cut_sets = CutSets {
+                graph,
+                dynamic_ids,
+                off_chances: crit_data.off_chances.clone(),
+                roll_up_rule,
+                l_map,
+                start_id,
+                end_id,
+                max_order: arg_number(args, "--max-order", DEFAULT_MAX_ORDER)?,
+                cutoff: arg_number(args, "--cutoff", DEFAULT_CUTOFF)?,
+            };
+            cut_sets.run(&ctx)?.print(&cut_sets.graph);
+        }
+        // Ranges of the end node reliability over the off chance bounds of every node, drawn as a
+        // tornado chart by '--html'
+        "tornado" => {
+            let pessimistic_attr = arg_value(args, "--pessimistic").map(|a| a.as_str()).unwrap_or(PESSIMISTIC_ATTR);
+            let optimistic_attr = arg_value(args, "--optimistic").map(|a| a.as_str()).unwrap_or(OPTIMISTIC_ATTR);
+            let tornado = Tornado {
+                bounds: bounds_from_attributes(&graph, pessimistic_attr, optimistic_attr)?,
+                graph,
+                dynamic_ids,
+                off_chances: crit_data.off_chances.clone(),
+                roll_up_rule,
+                l_map,
+                start_id,
+                end_id,
+            };
+            let results = tornado.run(&ctx)?;
+            results.print(&tornado.graph);
+            if let Some(path) = arg_value(args, "--html") {
+                write_output(path, tornado_to_html(&tornado.graph, &results))?;
+            }
+        }
+        "mission-time" => {
+            let mission_times = arg_value(args, "--mission-times")
+                .ok_or("The mission-time analysis needs --mission-times t1,t2,...")?
+                .split(',')
+                .map(|t| t.trim().parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()?;
+            let curve = MissionTimeCurve {
+                threads,
+                graph,
+                dynamic_ids,
+                lifetimes,
+                off_chances: crit_data.off_chances.clone(),
+                mission_times,
+                loop_condition,
+                roll_up_rule,
+                l_map,
+                start_id,
+                end_id,
+                exhaustive_fallback: !has_flag(args, "--no-exhaustive"),
+                seed,
+            };
+            curve.run(&ctx)?.print(&curve.graph);
+        }
+        "markov" => {
+            let markov = MarkovAvailability {
+                rates: rates_from_attributes(&graph),
+                graph,
+                dynamic_ids,
+                off_chances: crit_data.off_chances.clone(),
+                roll_up_rule,
+                l_map,
+                start_id,
+                end_id,
+                outputs,
+            };
+            markov.run(&ctx)?;
+        }
+        "removal" => {
+            let removal = NodeRemoval {
+                graph,
+                dynamic_ids,
+                off_chances: crit_data.off_chances.clone(),
+                roll_up_rule,
+                l_map,
+                start_id,
+                end_id,
+            };
+            removal.run(&ctx)?.print(&removal.graph);
+        }
+        "hardening" => {
+            let budget = arg_value(args, "--budget")
+                .ok_or("The hardening analysis needs a --budget <cost>")?
+                .parse::<f64>()?;
+            let greedy = GreedyHardening {
+                costs: costs_from_attributes(&graph),
+                graph,
+                dynamic_ids,
+                off_chances: crit_data.off_chances.clone(),
+                roll_up_rule,
+                start_id,
+                end_id,
+                budget,
+                hardening: hardening(args)?,
+                samples: arg_number(args, "--samples", DEFAULT_SAMPLES)?,
+                seed,
+                threads,
+            };
+            greedy.run(&ctx)?.print(&greedy.graph);
+        }
+        "search" => {
+            let budget = arg_value(args, "--budget")
+                .ok_or("The search analysis needs a --budget <cost>")?
+                .parse::<f64>()?;
+            let costs = costs_from_attributes(&graph);
+            let space = DesignSpace {
+                candidates: DesignSpace::candidates(&graph, &dynamic_ids, &costs, arg_number(args, "--edge-cost", 1.0)?),
+                graph,
+                dynamic_ids,
+                off_chances: crit_data.off_chances.clone(),
+                roll_up_rule,
+                start_id,
+                end_id,
+                hardening: hardening(args)?,
+                budget,
+                samples: arg_number(args, "--samples", DEFAULT_SAMPLES)?,
+                seed,
+                threads,
+            };
+            let rng = seeded_rng(seed);
+            let reported = arg_number(args, "--top", DEFAULT_REPORTED_DESIGNS)?;
+            let results = match arg_value(args, "--method").map(|m| m.as_str()).unwrap_or("annealing") {
+                "annealing" => SimulatedAnnealing {
+                    space,
+                    iterations: arg_number(args, "--iterations", 200)?,
+                    start_temperature: DEFAULT_START_TEMPERATURE,
+                    reported,
+                    rng,
+                }.run(&ctx)?,
+                "genetic" => GeneticSearch {
+                    space,
+                    generations: arg_number(args, "--iterations", 10)?,
+                    population: arg_number(args, "--population", DEFAULT_POPULATION)?,
+                    reported,
+                    rng,
+                }.run(&ctx)?,
+                other => return Err(format!("Unknown search method '{}'", other).into()),
+            };
+            results.print();
+        }
+        // Evaluates the same states on the input and on the version at '--against'
+        "delta" => {
+            let path = arg_value(args, "--against").ok_or("The delta analysis needs the --against <path> of the other version")?;
+            let LoadedInput { graph: mut other_graph, crit_data: other_data, roll_up_rule: other_rule, .. } = load_input(&with_input(args, path))?;
+            let (other_pairs, other_weights) = select_pairs(args, &other_graph)?;
+            let other_ids = mark_terminals(&mut other_graph, &other_pairs, &other_weights);
+            // The off chances of the other version are used for the nodes of both
+            let mut off_chances = crit_data.off_chances.clone();
+            off_chances.extend(other_data.off_chances.iter().map(|(id, chance)| (*id, *chance)));
+            let random = RandomGen {
+                rng: StdRng::seed_from_u64(seeds.gen()),
+                ids: dynamic_ids.union(&other_ids).copied().collect(),
+                off_chances,
+            };
+            let vis_gen: Box<dyn VisGen> = match has_flag(args, "--antithetic") {
+                true => Box::new(AntitheticGen::new(random)),
+                false => Box::new(random),
+            };
+            let delta = DeltaCriticality {
+                threads,
+                before: ModelVersion { graph, dynamic_ids, roll_up_rule, start_id, end_id, end_weights },
+                after: ModelVersion {
+                    graph: other_graph,
+                    dynamic_ids: other_ids,
+                    roll_up_rule: other_rule,
+                    start_id: other_pairs[0].0,
+                    end_id: other_pairs[0].1,
+                    end_weights: other_weights,
+                },
+                vis_gen,
+                loop_condition,
+                confidence: arg_number(args, "--confidence", DEFAULT_CONFIDENCE)?,
+            };
+            delta.run(&ctx)?.print(&delta.before.graph, &delta.after.graph, arg_number(args, "--top", usize::MAX)?);
+        }
+        "pairwise" => {
+            let pairwise = PairwiseCriticality {
+                threads,
+                graph,
+                dynamic_ids,
+                vis_gen,
+                loop_condition,
+                roll_up_rule,
+                pairs,
+            };
+            pairwise.run(&ctx)?.print(&pairwise.graph);
+        }
+        // Any other analysis is looked up in the registry and built from the '--options' json
+        other => {
+            let registry = AnalysisRegistry::default();
+            if !registry.contains(other) {
+                return Err(format!("Unknown analysis '{}'", other).into());
+            }
+            let stage = registry.build(other, &options(args)?)?;
+            let mut state = PipelineState::new(graph, dynamic_ids, crit_data.off_chances.clone(), roll_up_rule, start_id, end_id);
+            stage.run(&mut state, &ctx)?;
+            print!("{}", state.report);
+            if let Some(results) = state.results {
+                for output in outputs.iter() {
+                    output.write(&state.graph, &results)?;
+                }
+            }
+        }
+    }
+    event(Level::Info, "phase_completed", JsonValue::object()
+        .with("phase", "analysis")
+        .with("seconds", start.elapsed().as_secs_f64()));
+    for line in coverage_lines(&ctx) {
+        report_line(args, &line)?;
+    }
+    report_line(args, &format!("Time elapsed: {:?}", start.elapsed()))?;
+    check_alerts(args, alert.as_ref())
+}
+
+/// States the criticality analysis samples: '--samples', ['DEFAULT_SAMPLES'] like a
+/// ['CriticalityBuilder'] if not given, or every state of the 'enumeration' once
+///
+/// # Errors
+///
+/// Returns an error if '--samples' is not a number
+pub fn sample_count(args: &[String], enumeration: Option<&GrayCodeGen>) -> Result<u64, Box<dyn Error>> {
+    match enumeration {
+        Some(gray_code) => Ok(gray_code.state_count()),
+        None => arg_number(args, "--samples", DEFAULT_SAMPLES),
+    }
+}
+
+/// Whether '--format' asks for json results rather than the default text report
+///
+/// # Errors
+///
+/// Returns an error if the format is neither 'text' nor 'json'
+fn json_format(args: &[String]) -> Result<bool, Box<dyn Error>> {
+    match arg_value(args, "--format").map(|f| f.as_str()) {
+        None | Some("text") => Ok(false),
+        Some("json") => Ok(true),
+        Some(other) => Err(format!("Unknown output format '{}'. The formats are: text, json", other).into()),
+    }
+}
+
+/// Prints a line of the text report, to the standard error if the standard output carries the
+/// json results
+fn report_line(args: &[String], line: &str) -> Result<(), Box<dyn Error>> {
+    match json_format(args)? {
+        true => eprintln!("{}", line),
+        false => println!("{}", line),
+    }
+    Ok(())
+}
+
+/// The results stored at '--baseline' and the '--tolerance' of their criticality, None without a
+/// baseline
+#[cfg(feature = "serde")]
+fn read_baseline(args: &[String]) -> Result<Option<(CriticalityResults, f64)>, Box<dyn Error>> {
+    let Some(path) = arg_value(args, "--baseline") else { return Ok(None) };
+    let tolerance = arg_number(args, "--tolerance", DEFAULT_TOLERANCE)?;
+    if tolerance.is_nan() || tolerance < 0.0 {
+        return Err(format!("The tolerance must not be negative, got {}", tolerance).into());
+    }
+    Ok(Some((StoredResults::read(path)?.results, tolerance)))
+}
+
+#[cfg(not(feature = "serde"))]
+fn read_baseline(args: &[String]) -> Result<Option<(CriticalityResults, f64)>, Box<dyn Error>> {
+    match arg_value(args, "--baseline") {
+        Some(_) => Err("Reading a baseline needs the 'serde' feature".into()),
+        None => Ok(None),
+    }
+}
+
+/// Fails with a ['BaselineDeviationError'] if the criticality of a node in the 'results' deviates
+/// from the 'baseline' by more than the 'tolerance'
+fn check_baseline(args: &[String], baseline: &CriticalityResults, results: &CriticalityResults, tolerance: f64) -> Result<(), Box<dyn Error>> {
+    let comparison = Comparison::new(baseline, results, DEFAULT_CONFIDENCE);
+    let nodes: Vec<(u32, Option<f64>, Option<f64>)> = comparison.deviations(tolerance).into_iter()
+        .map(|(id, delta)| (id, delta.before, delta.after))
+        .collect();
+    if !nodes.is_empty() {
+        return Err(BaselineDeviationError { tolerance, nodes }.into());
+    }
+    report_line(args, &format!("Baseline: the criticality of all {} nodes is within {}", comparison.nodes.len(), tolerance))
+}
+
+/// Makes the sources and sinks of the 'pairs' and the weighted ends static, returning the
+/// remaining dynamic nodes of the 'graph'
+fn mark_terminals(graph: &mut Graph, pairs: &NodePairs, end_weights: &EndWeights) -> HashSet<u32> {
+    for (source, sink) in pairs.iter() {
+        graph.static_nodes.insert(*source);
+        graph.static_nodes.insert(*sink);
+    }
+    for (end, _) in end_weights.iter() {
+        graph.static_nodes.insert(*end);
+    }
+    graph.get_node_ids().into_iter()
+        .filter(|id| !graph.static_nodes.contains(id))
+        .collect()
+}
+
+/// The 'args' reading the input from 'path' instead, with every other flag kept
+fn with_input(args: &[String], path: &str) -> Vec<String> {
+    let mut rewritten = vec![args[0].to_string(), "--input".to_string(), path.to_string()];
+    rewritten.extend(args.iter().skip(1).cloned());
+    rewritten
+}
+
+/// Options of the analyses built from the registry, the json object given by '--options'
+fn options(args: &[String]) -> Result<JsonValue, Box<dyn Error>> {
+    match arg_value(args, "--options") {
+        Some(options) => Ok(json::parse(options)?),
+        None => Ok(JsonValue::object()),
+    }
+}
+
+/// Hardening selected by '--hardening': 'static' (the default) makes the chosen nodes static, a
+/// number multiplies their off chance
+fn hardening(args: &[String]) -> Result<Hardening, Box<dyn Error>> {
+    match arg_value(args, "--hardening").map(|h| h.as_str()).unwrap_or("static") {
+        "static" => Ok(Hardening::Static),
+        factor => Ok(Hardening::ReduceOffChance(factor.parse::<f32>()?)),
+    }
+}
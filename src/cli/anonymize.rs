@@ -0,0 +1,47 @@
+//! 'anonymize': writes the input with hashed node names and renumbered ids, see
+//! ['crate::anonymize'].
+
+use std::error::Error;
+use crate::anonymize::{anonymize, write_mapping};
+use crate::cli::{arg_value, load_input};
+use crate::export::GraphFormat;
+use crate::output::STDOUT_PATH;
+
+/// Writes the anonymized input given by the arguments to '--output', in the ['GraphFormat'] given
+/// by '--to' or by the extension of the output as for the 'convert' command, and the mapping back
+/// to the original nodes to '--mapping'. The names are hashed with '--salt', a random salt if
+/// none is given, so the same names only hash alike across runs that share a salt.
+///
+/// # Errors
+///
+/// Returns an error if the paths are missing or the same, the input can't be read or the files
+/// can't be written
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = arg_value(args, "--output").ok_or("The anonymize command needs an --output <path>")?;
+    let mapping_path = arg_value(args, "--mapping")
+        .ok_or("The anonymize command needs a --mapping <path> to write the original names to")?;
+    if mapping_path == path {
+        return Err("The mapping has to be written apart from the anonymized graph".into());
+    }
+    let format = match arg_value(args, "--to") {
+        Some(format) => format.parse::<GraphFormat>()?,
+        None => GraphFormat::from_path(path)
+            .ok_or_else(|| format!("Can't tell the output format of '{}' from its extension, use --to <format>", path))?,
+    };
+    let exporter = format.exporter()
+        .ok_or_else(|| format!("Graphs can't be written as {}", format))?;
+    let salt = match arg_value(args, "--salt") {
+        Some(salt) => salt.as_bytes().to_vec(),
+        None => rand::random::<[u8; 16]>().to_vec(),
+    };
+
+    let input = load_input(args)?;
+    let anonymized = anonymize(&input.graph, &input.crit_data, &salt);
+    exporter.export(&anonymized.graph, &anonymized.data, path)?;
+    write_mapping(mapping_path, &anonymized.mapping)?;
+    // The standard output carries the graph itself
+    if path != STDOUT_PATH {
+        println!("Saved {} anonymized nodes to {} as {}, and their original names to {}", anonymized.mapping.len(), path, format, mapping_path);
+    }
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! 'compare <before> <after>': compares the criticality of two stored results.
+
+use std::error::Error;
+use crate::analyses::criticality::compare::{Comparison, DEFAULT_CONFIDENCE};
+use crate::cli::{arg_number, has_flag};
+use crate::output::json::StoredResults;
+
+/// Prints the change of the criticality of every node between the results stored by
+/// 'analyze --results', largest change first. '--confidence' sets the confidence level of the
+/// significance test, '--significant' prints only the significant changes and '--top <n>' only
+/// the n largest.
+///
+/// # Errors
+///
+/// Returns an error if the paths are missing, the results can't be read or the options are
+/// invalid
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let paths: Vec<&String> = args.iter().skip(2).take_while(|a| !a.starts_with("--")).collect();
+    let [before_path, after_path] = paths[..] else {
+        return Err("The compare command needs the paths of two stored results".into());
+    };
+    let confidence = arg_number(args, "--confidence", DEFAULT_CONFIDENCE)?;
+    if !(0.0..1.0).contains(&confidence) {
+        return Err(format!("The confidence must be between 0 and 1, got {}", confidence).into());
+    }
+    let (before, after) = (StoredResults::read(before_path)?, StoredResults::read(after_path)?);
+    let comparison = Comparison::new(&before.results, &after.results, confidence);
+
+    println!("Mean end operability: {} -> {} ({:+})", before.results.end_op_mean, after.results.end_op_mean, comparison.end_op_delta);
+    let significant = comparison.nodes.values().filter(|d| d.significant).count();
+    println!("{} of {} nodes changed significantly at {}% confidence", significant, comparison.nodes.len(), confidence * 100.0);
+    let top = arg_number(args, "--top", usize::MAX)?;
+    let only_significant = has_flag(args, "--significant");
+    let format = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or("-".to_string());
+    for (id, delta) in comparison.ranked().into_iter().filter(|(_, d)| d.significant || !only_significant).take(top) {
+        let name = after.graph.get_node(&id).or(before.graph.get_node(&id)).map(|n| n.name.as_str()).unwrap_or("");
+        let flag = match (delta.before, delta.after) {
+            (None, _) => "added",
+            (_, None) => "removed",
+            _ if delta.significant => "significant",
+            _ => "not significant",
+        };
+        println!("{} ({}): {} -> {} ({:+} ± {:.6}, {})", name, id, format(delta.before), format(delta.after), delta.delta, delta.margin, flag);
+    }
+    Ok(())
+}
@@ -0,0 +1,33 @@
+//! 'convert': reads the input and writes it in another format.
+
+use std::error::Error;
+use crate::cli::{arg_value, load_input};
+use crate::export::GraphFormat;
+use crate::output::STDOUT_PATH;
+
+/// Writes the input given by the arguments to '--output' in the ['GraphFormat'] given by '--to',
+/// or by the extension of the output. The output '-' writes to the standard output and needs
+/// '--to'. The input format is chosen by '--from' or its extension,
+/// see ['crate::cli::load_input'].
+///
+/// # Errors
+///
+/// Returns an error if the input can't be read, the output format is unknown or can't be written,
+/// or the output can't be written
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = arg_value(args, "--output").ok_or("The convert command needs an --output <path>")?;
+    let format = match arg_value(args, "--to") {
+        Some(format) => format.parse::<GraphFormat>()?,
+        None => GraphFormat::from_path(path)
+            .ok_or_else(|| format!("Can't tell the output format of '{}' from its extension, use --to <format>", path))?,
+    };
+    let exporter = format.exporter()
+        .ok_or_else(|| format!("Graphs can't be written as {}", format))?;
+    let input = load_input(args)?;
+    exporter.export(&input.graph, &input.crit_data, path)?;
+    // The standard output carries the graph itself
+    if path != STDOUT_PATH {
+        println!("Saved {} nodes and {} edges to {} as {}", input.graph.get_node_ids().len(), input.graph.get_edges().len(), path, format);
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! 'daemon': runs queued analysis jobs within a budget of cores, see ['crate::daemon'].
+
+use std::env;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::audit::AuditLog;
+use crate::cli::{arg_number, arg_value};
+use crate::daemon::{DEFAULT_DAEMON_ADDRESS, DaemonConfig, run_daemon};
+
+/// Starts the daemon keeping its jobs in '--jobs <dir>', reading job files from '--spool <dir>'
+/// if given and running jobs on at most '--cores' threads together, by default one per cpu. The
+/// http api listens on '--address host:port'. '--audit-log <path>' records every job, see
+/// ['crate::audit'].
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid, the jobs directory or the audit log can't be
+/// opened or the address can't be bound
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let jobs_dir = arg_value(args, "--jobs").ok_or("The daemon needs a directory for its jobs, given by --jobs <dir>")?;
+    let cores = arg_number(args, "--cores", (num_cpus::get() as u8).max(1))?;
+    if cores == 0 {
+        return Err("The core budget must be at least 1".into());
+    }
+    let config = DaemonConfig {
+        address: arg_value(args, "--address").cloned().unwrap_or(DEFAULT_DAEMON_ADDRESS.to_string()),
+        jobs_dir: PathBuf::from(jobs_dir),
+        spool_dir: arg_value(args, "--spool").map(PathBuf::from),
+        cores,
+        executable: env::current_exe()?,
+        audit_log: match arg_value(args, "--audit-log") {
+            Some(path) => Some(Arc::new(AuditLog::open(Path::new(path))?)),
+            None => None,
+        },
+    };
+    println!("Running jobs on http://{}", config.address);
+    run_daemon(config)
+}
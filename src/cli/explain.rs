@@ -0,0 +1,30 @@
+//! 'explain <node>': breaks the criticality of a node down after a run.
+
+use std::error::Error;
+use crate::analyses::explain::Explanation;
+use crate::cli::{arg_value, parse_node, path_arg, select_pairs};
+use crate::output::json::StoredResults;
+
+/// Prints why the node following the command scored its criticality in the results stored by
+/// 'analyze --results <path>', see ['Explanation']. The same '--results' flag names the stored
+/// results, so the command can follow a run with the same configuration. The start and end nodes
+/// are selected by '--pairs' as for the analysis.
+///
+/// # Errors
+///
+/// Returns an error if the node or the results are missing, the results can't be read or the
+/// node isn't part of the analysed graph
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let node = path_arg(args).ok_or("The explain command needs the id or key of a node")?;
+    let path = arg_value(args, "--results")
+        .ok_or("The explain command needs the results of a run, given by --results <path>")?;
+    let stored = StoredResults::read(path)?;
+    let id = parse_node(node, &stored.graph)?;
+    if stored.graph.get_node(&id).is_none() {
+        return Err(format!("The node {} is not part of the analysed graph", id).into());
+    }
+    let (pairs, _) = select_pairs(args, &stored.graph)?;
+    let (start_id, end_id) = pairs[0];
+    Explanation::new(&stored.graph, &stored.results, id, start_id, end_id).print(&stored.graph);
+    Ok(())
+}
@@ -0,0 +1,34 @@
+//! 'generate <path>': writes a synthetic graph in the headered links format.
+
+use std::error::Error;
+use crate::cli::{arg_number, arg_value, path_arg};
+use crate::generator::{GraphGenerator, GraphModel, write_links_csv};
+
+/// Writes a synthetic graph of the model given by '--model' to the path following the command
+///
+/// # Errors
+///
+/// Returns an error if no path is given, the model is unknown or the file can't be written
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = path_arg(args).ok_or("The generate command needs an output path")?;
+    let model = match arg_value(args, "--model").map(|m| m.as_str()).unwrap_or("layered") {
+        "layered" => GraphModel::Layered {
+            layers: arg_number(args, "--layers", 10)?,
+            width: arg_number(args, "--width", 10)?,
+            edge_chance: arg_number(args, "--edge-chance", 0.2)?,
+        },
+        "random" => GraphModel::Random {
+            nodes: arg_number(args, "--nodes", 100)?,
+            edge_chance: arg_number(args, "--edge-chance", 0.05)?,
+        },
+        "scale-free" => GraphModel::ScaleFree {
+            nodes: arg_number(args, "--nodes", 100)?,
+            edges_per_node: arg_number(args, "--edges-per-node", 2)?,
+        },
+        other => return Err(format!("Unknown graph model '{}'", other).into()),
+    };
+    let (graph, data) = GraphGenerator::new(model, arg_number(args, "--seed", 0)?).generate();
+    write_links_csv(path, &graph, &data)?;
+    println!("Generated {} nodes and {} edges in {}", graph.get_node_ids().len(), graph.get_edges().len(), path);
+    Ok(())
+}
@@ -0,0 +1,685 @@
+//! Subcommands of the thor binary.
+//!
+//! The first argument selects the command, every command reads its options as '--flag value'
+//! pairs from the remaining arguments. Running the binary without a command analyses the input,
+//! like the 'analyze' command.
+
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use log::{info, Level};
+use crate::analyses::AnalysisContext;
+use crate::analyses::criticality::coverage::CoverageLog;
+use crate::analyses::criticality::CriticalityData;
+use crate::analyses::criticality::partial::{DEFAULT_PARTIAL_EVERY, PartialResults};
+use crate::analyses::limits::{LimitAction, Limits};
+use crate::config::{Config, DEFAULT_CONFIG};
+use crate::settings::Settings;
+use crate::errors::config::ConfigError;
+use crate::errors::input::InputError;
+use crate::errors::network::UnknownNodeError;
+use crate::input::{alpha_path, ColumnMapping, CsvCritConfigs, CsvCritInput, CsvFormat, EdgeDirection, EdgePolicy, Input, LinkPolicies, orient_undirected, NodeAttributeTable, read_node_groups, STDCritConfigs, STDCritInput, TextEncoding};
+use crate::input::neo4j::{Neo4jConnection, Neo4jCritConfigs, Neo4jCritInput};
+use crate::input::openpsa::{OpenPsaConfigs, OpenPsaInput};
+use crate::input::remote;
+use crate::export::GraphFormat;
+use crate::input::adjacency::{AdjacencyListConfigs, AdjacencyListInput};
+use crate::input::dot::{DotConfigs, DotInput};
+use crate::input::matrix::{MatrixConfigs, MatrixInput};
+use crate::input::networkx::{NodeLinkConfigs, NodeLinkInput};
+use crate::input::graphml::{GraphMlConfigs, GraphMlInput};
+#[cfg(feature = "serde")]
+use crate::input::json::{JsonGraphConfigs, JsonGraphInput};
+use crate::input::snapshot::{SnapshotConfigs, SnapshotInput};
+use crate::input::xlsx::{XlsxCritConfigs, XlsxCritInput};
+use crate::json;
+use crate::json::JsonValue;
+use crate::logging::event;
+use crate::hooks::Hooks;
+use crate::manifest::{Manifest, MAX_EXACT_SEED};
+use crate::network::{EdgeValueMap, Graph};
+use crate::output::{Output, StdOutput};
+use crate::output::STDOUT_PATH;
+use crate::output::bands::{AlertOutput, CriticalityBands, DEFAULT_BANDS};
+use crate::output::filter::{FilteredOutput, ResultFilter};
+use crate::output::html::HtmlOutput;
+use crate::output::render::{CytoscapeOutput, PngOutput, SvgOutput};
+use crate::roll_up::{rule_from_json, OrRule, RollUp};
+
+pub mod aggregate;
+pub mod analyze;
+pub mod anonymize;
+#[cfg(feature = "serde")]
+pub mod compare;
+pub mod convert;
+#[cfg(feature = "serde")]
+pub mod daemon;
+#[cfg(feature = "serde")]
+pub mod explain;
+pub mod generate;
+pub mod pipeline;
+#[cfg(feature = "serde")]
+pub mod report;
+#[cfg(feature = "serde")]
+pub mod serve;
+pub mod sweep;
+pub mod validate;
+
+/// Links file read when no '--input' is given
+pub const DEFAULT_INPUT: &str = "./links.csv";
+/// Results of more nodes than this only print the most critical ones unless '--top' is given
+pub const LARGE_RESULTS: usize = 100;
+/// Number of nodes printed for large results
+pub const DEFAULT_TOP: usize = 20;
+
+/// Runs the command selected by the arguments, 'args[0]' is the name of the binary. The binary
+/// adds the flags given by environment variables to the arguments, see ['crate::settings'], and an
+/// analysis without a '--seed' is given a random one, see ['with_seed']. A successful run writes its manifest if one is asked for, see ['crate::manifest']. Once the run
+/// is over the '--on-complete' or '--on-failure' hook is fired, see ['crate::hooks'].
+///
+/// # Errors
+///
+/// Returns an error if the command is unknown or fails, its manifest can't be written or a hook
+/// can't be used
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let name = command(args).unwrap_or("analyze");
+    event(Level::Info, "run_started", JsonValue::object().with("command", name));
+    let start = Instant::now();
+    let args = with_seed(with_config_flags(args)?);
+    let manifest = Manifest::start(&args);
+    let hooks = Hooks::from_args(&args)?;
+    let outcome = run_command(&args).and_then(|()| match manifest {
+        Some(manifest) => Ok(manifest.write(&args)?),
+        None => Ok(()),
+    });
+    if let Some(hooks) = hooks {
+        hooks.fire(&args, outcome.as_ref().err().map(|e| e.as_ref()));
+    }
+    outcome?;
+    event(Level::Info, "run_finished", JsonValue::object()
+        .with("command", name)
+        .with("seconds", start.elapsed().as_secs_f64()));
+    Ok(())
+}
+
+fn run_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    match command(args) {
+        None | Some("analyze") => analyze::run(args),
+        Some("validate") => validate::run(args),
+        Some("convert") => convert::run(args),
+        Some("anonymize") => anonymize::run(args),
+        Some("generate") => generate::run(args),
+        Some("pipeline") => pipeline::run(args),
+        Some("sweep") => sweep::run(args),
+        Some("aggregate") => aggregate::run(args),
+        #[cfg(feature = "serde")]
+        Some("report") => report::run(args),
+        #[cfg(feature = "serde")]
+        Some("compare") => compare::run(args),
+        #[cfg(feature = "serde")]
+        Some("explain") => explain::run(args),
+        #[cfg(feature = "serde")]
+        Some("serve") => serve::run(args),
+        #[cfg(feature = "serde")]
+        Some("daemon") => daemon::run(args),
+        // 'load <snapshot>' and 'save <snapshot>' are kept as shorthands for the analyze and
+        // convert commands
+        Some("load") => analyze::run(&with_path_flag(args, "analyze", "--input")?),
+        Some("save") => convert::run(&with_path_flag(args, "convert", "--output")?),
+        Some(other) => Err(format!("Unknown command '{}'", other).into()),
+    }
+}
+
+/// The command given as the first argument, if it isn't a flag
+pub fn command(args: &[String]) -> Option<&str> {
+    args.get(1).filter(|a| !a.starts_with("--")).map(|a| a.as_str())
+}
+
+/// Rewrites '<shorthand> <path>' into '<command> <flag> <path>'
+fn with_path_flag(args: &[String], command: &str, flag: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let path = path_arg(args)
+        .ok_or_else(|| format!("The {} command needs a path", args[1]))?;
+    let mut rewritten = vec![args[0].to_string(), command.to_string(), flag.to_string(), path.to_string()];
+    rewritten.extend(args.iter().skip(3).cloned());
+    Ok(rewritten)
+}
+
+/// The path given right after the command, such as 'generate <path>'
+pub fn path_arg(args: &[String]) -> Option<&String> {
+    args.get(2).filter(|a| !a.starts_with("--"))
+}
+
+/// Value following the flag 'name' in the arguments
+pub fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1))
+}
+
+/// The 'args' with 'value' as the value of the flag 'name', replacing the value it had or adding
+/// the flag at the end, so the program name and the command keep their positions
+pub fn with_arg_value(args: &[String], name: &str, value: &str) -> Vec<String> {
+    let mut args = args.to_vec();
+    match args.iter().position(|a| a == name) {
+        Some(i) if i + 1 < args.len() => args[i + 1] = value.to_string(),
+        Some(_) => args.push(value.to_string()),
+        None => args.extend([name.to_string(), value.to_string()]),
+    }
+    args
+}
+
+/// Whether the flag 'name' is part of the arguments
+pub fn has_flag(args: &[String], name: &str) -> bool {
+    args.iter().any(|a| a == name)
+}
+
+/// Numeric value of the flag 'name', or 'default' if it isn't given
+pub fn arg_number<T: std::str::FromStr>(args: &[String], name: &str, default: T) -> Result<T, Box<dyn Error>>
+    where T::Err: Error + 'static
+{
+    match arg_value(args, name) {
+        Some(value) => Ok(value.parse::<T>()?),
+        None => Ok(default),
+    }
+}
+
+/// Number of worker threads given by '--threads', by default one per cpu
+///
+/// # Errors
+///
+/// Returns an error if the value is not a number between 1 and 255
+pub fn thread_count(args: &[String]) -> Result<u8, Box<dyn Error>> {
+    let threads = arg_number(args, "--threads", (num_cpus::get() as u8).max(1))?;
+    match threads {
+        0 => Err("The number of threads must be at least 1".into()),
+        threads => Ok(threads),
+    }
+}
+
+/// Context of the analyses with the limits given by '--max-time <seconds>', '--max-visited <states>'
+/// and '--max-memory <MiB>'. '--on-limit degrade' lets the analyses continue approximately
+/// instead of failing when they reach a limit, see ['crate::analyses::limits'].
+///
+/// With '--partial-results <path>' the sampling analyses write their results so far to the file
+/// every '--partial-every <states>' states of a thread, and append them to the csv file given by
+/// '--convergence <path>', see ['crate::analyses::criticality::partial'].
+///
+/// With '--coverage' the sampling analyses track how much of the state space the unique states
+/// they evaluated cover, see ['coverage_lines'].
+///
+/// # Errors
+///
+/// Returns an error if a limit or the interval is not a number or the action is unknown
+pub fn analysis_context(args: &[String]) -> Result<AnalysisContext, Box<dyn Error>> {
+    let limits = Limits {
+        max_wall_time: arg_value(args, "--max-time").map(|t| t.parse::<f64>()).transpose()?.map(Duration::from_secs_f64),
+        max_visited: arg_value(args, "--max-visited").map(|n| n.parse::<usize>()).transpose()?,
+        max_memory: arg_value(args, "--max-memory").map(|m| m.parse::<u64>()).transpose()?.map(|m| m * 1024 * 1024),
+        on_limit: match arg_value(args, "--on-limit").map(|a| a.as_str()).unwrap_or("abort") {
+            "abort" => LimitAction::Abort,
+            "degrade" => LimitAction::Degrade,
+            other => return Err(format!("Unknown limit action '{}', expected abort or degrade", other).into()),
+        },
+    };
+    let partial = match arg_value(args, "--partial-results") {
+        Some(path) => Some(PartialResults {
+            every: arg_number(args, "--partial-every", DEFAULT_PARTIAL_EVERY)?,
+            path: Some(path.to_string()),
+            curve_path: arg_value(args, "--convergence").cloned(),
+            listener: None,
+        }),
+        None => None,
+    };
+    let coverage = has_flag(args, "--coverage").then(|| Arc::new(CoverageLog::default()));
+    Ok(AnalysisContext { partial, coverage, ..AnalysisContext::default().with_limits(limits) })
+}
+
+/// Lines reporting the coverage of the state space of every sampling of the analyses run with
+/// the 'ctx', none without '--coverage', see ['crate::analyses::criticality::coverage']
+pub fn coverage_lines(ctx: &AnalysisContext) -> Vec<String> {
+    let Some(log) = ctx.coverage.as_ref() else { return vec![] };
+    let mut lines = vec![];
+    for report in log.reports() {
+        lines.push(format!("Coverage: {}", report.overall));
+        if report.workers.len() > 1 {
+            for (worker, coverage) in report.workers.iter().enumerate() {
+                lines.push(format!("  thread {}: {}", worker, coverage));
+            }
+        }
+    }
+    lines
+}
+
+/// (source, sink) pairs of nodes
+pub type NodePairs = Vec<(u32, u32)>;
+
+/// Id of the node of the 'graph' given on the command line by its numeric id or its string key,
+/// see ['Graph::id_of']
+///
+/// # Errors
+///
+/// Returns an ['UnknownNodeError'] if the graph has no such node
+pub fn parse_node(node: &str, graph: &Graph) -> Result<u32, Box<dyn Error>> {
+    graph.id_of(node)
+        .ok_or_else(|| UnknownNodeError { node: node.trim().to_string() }.into())
+}
+
+/// Parses (source, sink) pairs of nodes of the 'graph' written as 'source:sink,source:sink'
+pub fn parse_pairs(pairs: &str, graph: &Graph) -> Result<NodePairs, Box<dyn Error>> {
+    let mut parsed = vec![];
+    for pair in pairs.split(',').filter(|p| !p.trim().is_empty()) {
+        let (source, sink) = pair.split_once(':')
+            .ok_or_else(|| format!("Expected a pair 'source:sink', got '{}'", pair))?;
+        parsed.push((parse_node(source, graph)?, parse_node(sink, graph)?));
+    }
+    match parsed.is_empty() {
+        true => Err("Expected at least one 'source:sink' pair".into()),
+        false => Ok(parsed),
+    }
+}
+
+/// (end node, weight) of every weighted end node
+pub type EndWeights = Vec<(u32, f64)>;
+
+/// Parses end node weights of nodes of the 'graph' written as 'id:weight,id:weight'
+pub fn parse_end_weights(weights: &str, graph: &Graph) -> Result<EndWeights, Box<dyn Error>> {
+    let mut parsed = vec![];
+    for weight in weights.split(',').filter(|w| !w.trim().is_empty()) {
+        let (id, value) = weight.split_once(':')
+            .ok_or_else(|| format!("Expected an end node weight 'id:weight', got '{}'", weight))?;
+        parsed.push((parse_node(id, graph)?, value.trim().parse::<f64>()?));
+    }
+    Ok(parsed)
+}
+
+/// A graph read by ['load_input'] with everything needed to analyse it
+pub struct LoadedInput {
+    pub graph: Graph,
+    pub crit_data: CriticalityData,
+    /// Fault trees come with a rule per gate, every other input is rolled up with the ['OrRule']
+    pub roll_up_rule: Box<dyn RollUp>,
+    /// The database the graph was read from, if it was read from Neo4j
+    pub neo4j: Option<Neo4jConnection>,
+}
+
+/// Reads the input selected by the arguments:
+///
+/// * '--neo4j <url>' reads the graph from a Neo4j database, using the 'NEO4J_USER' and
+///   'NEO4J_PASSWORD' environment variables to log in
+/// * '--input <path>', or the path right after the command, reads the file in the
+///   ['GraphFormat'] given by '--from', or by the extension of the path. Files without a known
+///   extension are read as links csv, and the path '-' reads the standard input.
+///   ['DEFAULT_INPUT'] is read if no path is given. Gzip and zstd files are decompressed, the
+///   extension of the compression ('.gz', '.zst') is ignored when choosing the format. A
+///   'http://' or 's3://' url is fetched into a local cache first, see ['remote']. There is no
+///   TLS, so 'https://' urls are refused, and 's3://' objects are read unsigned from the plain
+///   http endpoint in 'S3_ENDPOINT', so only from buckets allowing anonymous reads. Adjacency
+///   matrices are only read with '--from matrix', see ['crate::input::matrix'], and NetworkX
+///   node-link json with '--from node-link', see ['crate::input::networkx']
+/// * '--headers' or '--columns <mapping>' read the links file with the column layout given by its
+///   header or the mapping, '--node-attributes <path>' adds a table of typed node attributes.
+///   Without either the alpha values of the edges are read from '--alpha <path>', by default the
+///   ['crate::input::ALPHA_FILE'] next to the input
+/// * '--delimiter <char>', '--quote <char|none>' and '--encoding utf-8|latin-1' give the
+///   ['CsvFormat'] of csv files, '.tsv' files are delimited by tabs by default
+/// * '--self-loops' and '--duplicate-edges' with 'reject', 'drop' or 'keep' give the
+///   ['LinkPolicies'] of the links file
+/// * '--direction child-to-parent|parent-to-child|undirected' gives the meaning of the edges of the
+///   input, see ['EdgeDirection']. Undirected edges are oriented away from the source of the first
+///   pair of '--pairs'.
+/// * '--roll-up <json>' or the 'roll_up' value of the configuration replaces the roll up rule,
+///   see ['rule_from_json']
+///
+/// # Errors
+///
+/// Returns an ['InputError'] if the input or the configuration can't be read or the roll up rule is
+/// invalid
+pub fn load_input(args: &[String]) -> Result<LoadedInput, Box<dyn Error>> {
+    read_input(args).map_err(|source| InputError { source }.into())
+}
+
+fn read_input(args: &[String]) -> Result<LoadedInput, Box<dyn Error>> {
+    let in_path = arg_value(args, "--input")
+        .or_else(|| command(args).and(path_arg(args)))
+        .map(|p| p.to_string())
+        .unwrap_or(DEFAULT_INPUT.to_string());
+    // Read next to the input, before a remote input is replaced by its cached copy
+    let alpha = arg_value(args, "--alpha").cloned().or_else(|| alpha_path(&in_path));
+    let in_path = remote::local_path(&in_path)?;
+    let has_headers = has_flag(args, "--headers");
+    let mapping = match arg_value(args, "--columns") {
+        None => None,
+        Some(columns) => Some(columns.parse::<ColumnMapping>()?),
+    };
+
+    let format = match arg_value(args, "--from") {
+        Some(format) => format.parse::<GraphFormat>()?,
+        None => GraphFormat::from_path(&in_path).unwrap_or(GraphFormat::Csv),
+    };
+    let direction = match arg_value(args, "--direction") {
+        Some(direction) => direction.parse::<EdgeDirection>()?,
+        None => EdgeDirection::default(),
+    };
+    let mut csv_format = CsvFormat::from_path(&in_path);
+    if let Some(delimiter) = arg_value(args, "--delimiter") {
+        csv_format.delimiter = CsvFormat::parse_char(delimiter)?;
+    }
+    if let Some(quote) = arg_value(args, "--quote") {
+        csv_format.quote = match quote.eq_ignore_ascii_case("none") {
+            true => None,
+            false => Some(CsvFormat::parse_char(quote)?),
+        };
+    }
+    if let Some(encoding) = arg_value(args, "--encoding") {
+        csv_format.encoding = encoding.parse::<TextEncoding>()?;
+    }
+    let mut policies = LinkPolicies::default();
+    if let Some(policy) = arg_value(args, "--self-loops") {
+        policies.self_loops = policy.parse::<EdgePolicy>()?;
+    }
+    if let Some(policy) = arg_value(args, "--duplicate-edges") {
+        policies.duplicates = policy.parse::<EdgePolicy>()?;
+    }
+    // The mapped csv reader orients the edges itself, the others are oriented after reading
+    let mapped_csv = arg_value(args, "--neo4j").is_none() && format == GraphFormat::Csv && (has_headers || mapping.is_some());
+
+    let mut roll_up_rule: Box<dyn RollUp> = Box::new(OrRule {});
+    let mut neo4j = None;
+    let (mut graph, mut crit_data) = match (arg_value(args, "--neo4j"), format) {
+        (None, GraphFormat::Snapshot) => SnapshotInput {}.read(SnapshotConfigs { in_path })?,
+        (None, GraphFormat::Xlsx) => XlsxCritInput {}.read(XlsxCritConfigs { in_path, ..Default::default() })?,
+        (None, GraphFormat::OpenPsa) => {
+            let (graph, tree_data) = OpenPsaInput {}.read(OpenPsaConfigs { in_path })?;
+            roll_up_rule = Box::new(tree_data.roll_up_rule);
+            (graph, tree_data.crit_data)
+        }
+        (None, GraphFormat::GraphMl) => GraphMlInput {}.read(GraphMlConfigs { in_path })?,
+        #[cfg(feature = "serde")]
+        (None, GraphFormat::Json) => JsonGraphInput {}.read(JsonGraphConfigs { in_path })?,
+        #[cfg(not(feature = "serde"))]
+        (None, GraphFormat::Json) => return Err("Reading json needs the 'serde' feature".into()),
+        (None, GraphFormat::Dot) => DotInput {}.read(DotConfigs { in_path })?,
+        (None, GraphFormat::AdjacencyList) => AdjacencyListInput {}.read(AdjacencyListConfigs { in_path })?,
+        (None, GraphFormat::NodeLink) => NodeLinkInput {}.read(NodeLinkConfigs { in_path })?,
+        (None, GraphFormat::Cytoscape) => return Err("Cytoscape.js json can only be written".into()),
+        (None, GraphFormat::Matrix) => MatrixInput {}.read(MatrixConfigs { in_path, format: csv_format })?,
+        (None, GraphFormat::Csv) if has_headers || mapping.is_some() => {
+            let node_attributes = arg_value(args, "--node-attributes")
+                .map(|source| NodeAttributeTable { source: source.to_string(), id: 0.into(), columns: vec![] });
+            CsvCritInput {}.read(CsvCritConfigs { in_path, has_headers, mapping, probabilities: None, node_attributes, direction, policies, format: csv_format })?
+        }
+        (None, GraphFormat::Csv) => {
+            let alpha_path = alpha.map(|path| remote::local_path(&path)).transpose()?;
+            STDCritInput {}.read(STDCritConfigs { in_path, alpha_path, format: csv_format })?
+        }
+        (Some(url), _) => {
+            let connection = Neo4jConnection {
+                url: url.to_string(),
+                user: env::var("NEO4J_USER").ok(),
+                password: env::var("NEO4J_PASSWORD").ok(),
+                ..Default::default()
+            };
+            neo4j = Some(connection.clone());
+            Neo4jCritInput {}.read(Neo4jCritConfigs { connection, ..Default::default() })?
+        }
+    };
+    if !mapped_csv {
+        direction.orient(&mut graph, &mut crit_data);
+    }
+    if direction == EdgeDirection::Undirected {
+        let pairs = arg_value(args, "--pairs").ok_or("Orienting an undirected input needs the start node of '--pairs'")?;
+        let start_id = parse_pairs(pairs, &graph)?[0].0;
+        orient_undirected(&mut graph, &mut crit_data, start_id);
+    }
+    let config = configuration(args)?;
+    if let Some(config) = &config {
+        contract_from_config(config, &mut graph, &mut crit_data)?;
+    }
+    let no_alpha = EdgeValueMap::new();
+    let alpha = crit_data.alpha().unwrap_or(&no_alpha);
+    if let Some(declaration) = arg_value(args, "--roll-up") {
+        // A bare rule name doesn't need the quotes of a json string
+        let declaration = json::parse(declaration).unwrap_or(JsonValue::String(declaration.to_string()));
+        roll_up_rule = rule_from_json(&declaration, alpha)?;
+    } else if let Some(config) = &config {
+        if let Some(declaration) = config.get("roll_up") {
+            roll_up_rule = rule_from_json(declaration, alpha).map_err(|reason| config.error("roll_up", &reason))?;
+        }
+    }
+    Ok(LoadedInput { graph, crit_data, roll_up_rule, neo4j })
+}
+
+/// Collapses the groups of nodes listed under 'contract' in the configuration into supernodes,
+/// see ['Graph::contract']. The off chance of a supernode is optional:
+///
+/// ```json
+/// { "contract": [{ "ids": [4, 5, 6], "id": 100, "name": "pumps", "off_chance": 0.05 }] }
+/// ```
+///
+/// # Errors
+///
+/// Returns a ['ConfigError'] if a group is invalid
+fn contract_from_config(config: &Config, graph: &mut Graph, crit_data: &mut CriticalityData) -> Result<(), ConfigError> {
+    let Some(groups) = config.get("contract") else { return Ok(()) };
+    let groups = groups.as_array().ok_or_else(|| config.error("contract", "must be a list of node groups"))?;
+    for group in groups {
+        let ids: HashSet<u32> = group.get("ids")
+            .and_then(|ids| ids.as_array())
+            .and_then(|ids| ids.iter().map(|id| id.as_u64().map(|id| id as u32)).collect())
+            .ok_or_else(|| config.error("contract", "groups need the 'ids' of their nodes"))?;
+        let new_id = group.get("id")
+            .and_then(|id| id.as_u64())
+            .ok_or_else(|| config.error("contract", "groups need the 'id' of their supernode"))? as u32;
+        let name = group.get("name").and_then(|n| n.as_str()).map(|n| n.to_string()).unwrap_or(new_id.to_string());
+        let off_chance = group.get("off_chance").and_then(|c| c.as_f64()).map(|c| c as f32);
+        graph.contract(&ids, new_id, name);
+        crit_data.contract(&ids, new_id, off_chance);
+    }
+    Ok(())
+}
+
+/// The configuration given by '--config', or ['DEFAULT_CONFIG'] if it exists, with the profile
+/// selected by '--profile' applied
+///
+/// # Errors
+///
+/// Returns an error if the configuration can't be read, or the profile doesn't exist
+pub fn configuration(args: &[String]) -> Result<Option<Config>, Box<dyn Error>> {
+    let config = match arg_value(args, "--config") {
+        Some(path) => Some(Config::read(path)?),
+        None if Path::new(DEFAULT_CONFIG).exists() => Some(Config::read(DEFAULT_CONFIG)?),
+        None => None,
+    };
+    match (config, arg_value(args, "--profile")) {
+        (Some(config), Some(profile)) => Ok(Some(config.with_profile(profile)?)),
+        (None, Some(profile)) => Err(format!("The profile '{}' needs a configuration, give one with '--config'", profile).into()),
+        (config, None) => Ok(config),
+    }
+}
+
+/// The arguments followed by the flags of the configuration and of the profile selected by
+/// '--profile', see ['Settings']. Flags given in the arguments come first, so they take precedence
+/// over the configuration.
+///
+/// # Errors
+///
+/// Returns an error if the configuration or its flags are invalid
+pub(crate) fn with_config_flags(args: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let settings = Settings::new(args).with_config(configuration(args)?.as_ref())?;
+    settings.log();
+    Ok(settings.args())
+}
+
+/// The 'args' with a random '--seed' if they run an analysis without one. The analyses seed their
+/// random states from it, and the manifest and the stored results record it, so every run can be
+/// repeated with the same states.
+fn with_seed(mut args: Vec<String>) -> Vec<String> {
+    if matches!(command(&args), None | Some("analyze") | Some("load")) && arg_value(&args, "--seed").is_none() {
+        let seed = rand::random::<u64>() & MAX_EXACT_SEED;
+        info!("Seeding the random states with {}, give '--seed {}' to draw them again", seed, seed);
+        args.extend(["--seed".to_string(), seed.to_string()]);
+    }
+    args
+}
+
+/// The (source, sink) pairs and weighted end nodes selected by '--pairs' and '--end-weights'.
+/// Analyses of a single pair use the first one, by default the only start and end nodes.
+///
+/// # Errors
+///
+/// Returns an error if the flags can't be parsed, or no pair is given and the graph doesn't have
+/// a single start and end node
+pub fn select_pairs(args: &[String], graph: &Graph) -> Result<(NodePairs, EndWeights), Box<dyn Error>> {
+    let l_map = graph.links_map();
+    let end_weights = match arg_value(args, "--end-weights") {
+        Some(weights) => parse_end_weights(weights, graph)?,
+        None => vec![],
+    };
+    let pairs = match (arg_value(args, "--pairs"), end_weights.first()) {
+        (Some(pairs), _) => parse_pairs(pairs, graph)?,
+        (None, Some((end_id, _))) => vec![(Graph::get_start_id(&l_map)?, *end_id)],
+        (None, None) => vec![(Graph::get_start_id(&l_map)?, Graph::get_end_id(&l_map)?)],
+    };
+    Ok((pairs, end_weights))
+}
+
+/// Outputs drawing the results, selected by '--svg', '--png', '--html' and '--cytoscape' with the
+/// path to write to. Edges are drawn by their 'alpha' weights if given.
+pub fn render_outputs(args: &[String], alpha: Option<&EdgeValueMap<f32>>) -> Vec<Box<dyn Output>> {
+    let mut outputs: Vec<Box<dyn Output>> = vec![];
+    let alpha = alpha.cloned();
+    if let Some(path) = arg_value(args, "--svg") {
+        outputs.push(Box::new(SvgOutput { path: path.to_string(), alpha: alpha.clone() }));
+    }
+    if let Some(path) = arg_value(args, "--png") {
+        outputs.push(Box::new(PngOutput { path: path.to_string(), alpha: alpha.clone() }));
+    }
+    if let Some(path) = arg_value(args, "--html") {
+        outputs.push(Box::new(HtmlOutput { path: path.to_string(), alpha: alpha.clone() }));
+    }
+    if let Some(path) = arg_value(args, "--cytoscape") {
+        outputs.push(Box::new(CytoscapeOutput { path: path.to_string(), alpha }));
+    }
+    outputs
+}
+
+/// The 'outputs' writing only the nodes passing the '--filter <expression>', see
+/// ['crate::output::filter'], or the outputs as they are without the flag. The groups of
+/// '--groups <path>', read with ['read_node_groups'], are known to the filter besides those the
+/// nodes name themselves.
+///
+/// # Errors
+///
+/// Returns an error if the filter is invalid or the groups can't be read
+pub fn filter_outputs(args: &[String], outputs: Vec<Box<dyn Output>>) -> Result<Vec<Box<dyn Output>>, Box<dyn Error>> {
+    let expression = match arg_value(args, "--filter") {
+        Some(expression) => expression,
+        None => return Ok(outputs),
+    };
+    let mut filter = ResultFilter::parse(expression)?;
+    if let Some(path) = arg_value(args, "--groups") {
+        filter = filter.with_groups(read_node_groups(path, false)?);
+    }
+    Ok(outputs.into_iter()
+        .map(|output| Box::new(FilteredOutput { filter: filter.clone(), output }) as Box<dyn Output>)
+        .collect())
+}
+
+/// Standard output printing the '--top <n>' most critical nodes, or every node with '--top all'.
+/// Without the flag results of more than ['LARGE_RESULTS'] nodes print the ['DEFAULT_TOP'] most
+/// critical ones.
+///
+/// # Errors
+///
+/// Returns an error if the flag is neither 'all' nor a number
+pub fn std_output(args: &[String], node_count: usize) -> Result<StdOutput, Box<dyn Error>> {
+    let top = match arg_value(args, "--top").map(|t| t.as_str()) {
+        Some("all") => None,
+        Some(n) => Some(n.parse::<usize>().map_err(|_| format!("--top must be a number or 'all', got '{}'", n))?),
+        None if node_count > LARGE_RESULTS => Some(DEFAULT_TOP),
+        None => None,
+    };
+    Ok(StdOutput { top, bands: criticality_bands(args)? })
+}
+
+/// The criticality bands given by '--bands', or ['DEFAULT_BANDS'] if only '--alert-file' or
+/// '--fail-on-critical' is given, see ['crate::output::bands']
+///
+/// # Errors
+///
+/// Returns an error if the bands are invalid
+pub fn criticality_bands(args: &[String]) -> Result<Option<CriticalityBands>, Box<dyn Error>> {
+    match arg_value(args, "--bands") {
+        Some(bands) => Ok(Some(CriticalityBands::parse(bands)?)),
+        None if arg_value(args, "--alert-file").is_some() || has_flag(args, "--fail-on-critical") => Ok(Some(CriticalityBands::parse(DEFAULT_BANDS)?)),
+        None => Ok(None),
+    }
+}
+
+/// The output collecting the nodes in the critical band if '--alert-file <path>' or
+/// '--fail-on-critical' is given, see ['crate::output::bands']. An alert file left by an earlier
+/// run is removed, so the file only exists if the results of this run raised an alert.
+///
+/// # Errors
+///
+/// Returns an error if the bands are invalid or the earlier alert file can't be removed
+pub fn alert_output(args: &[String]) -> Result<Option<AlertOutput>, Box<dyn Error>> {
+    let Some(bands) = criticality_bands(args)? else { return Ok(None) };
+    let path = arg_value(args, "--alert-file").cloned();
+    if path.is_none() && !has_flag(args, "--fail-on-critical") {
+        return Ok(None);
+    }
+    if let Some(path) = path.as_ref().filter(|p| p.as_str() != STDOUT_PATH && Path::new(p).exists()) {
+        std::fs::remove_file(path)?;
+    }
+    Ok(Some(AlertOutput::new(bands, path)))
+}
+
+/// Fails with a ['crate::errors::analysis::CriticalBandError'] if '--fail-on-critical' is given
+/// and the 'alert' output collected nodes in the critical band
+///
+/// # Errors
+///
+/// Returns the error of the nodes in the critical band
+pub fn check_alerts(args: &[String], alert: Option<&AlertOutput>) -> Result<(), Box<dyn Error>> {
+    match (alert, has_flag(args, "--fail-on-critical")) {
+        (Some(alert), true) => Ok(alert.check()?),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{AttrValue, KEY_ATTR};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn nodes_are_parsed_by_their_id_or_key() {
+        let mut graph = Graph::new();
+        graph.add_node("pump".to_string(), 3);
+        graph.set_node_attr(&3, KEY_ATTR, AttrValue::Text("pump-a".to_string()));
+        assert_eq!(parse_node(" 3 ", &graph).unwrap(), 3);
+        assert_eq!(parse_node("pump-a", &graph).unwrap(), 3);
+        for node in ["4", "valve"] {
+            let error = parse_node(node, &graph).unwrap_err();
+            assert_eq!(error.downcast::<UnknownNodeError>().unwrap().node, node);
+        }
+        assert!(parse_pairs("3:4", &graph).is_err());
+    }
+
+    #[test]
+    fn flag_values_are_replaced_after_the_command() {
+        let replaced = with_arg_value(&args(&["thor", "analyze", "links.csv", "--pairs", "0:3,4:7", "--headers"]), "--pairs", "4:7");
+        assert_eq!(replaced, args(&["thor", "analyze", "links.csv", "--pairs", "4:7", "--headers"]));
+        let added = with_arg_value(&args(&["thor", "analyze", "links.csv"]), "--pairs", "4:7");
+        assert_eq!((command(&added), path_arg(&added)), (Some("analyze"), Some(&"links.csv".to_string())));
+        assert_eq!(arg_value(&added, "--pairs").unwrap(), "4:7");
+        let trailing = with_arg_value(&args(&["thor", "analyze", "--pairs"]), "--pairs", "4:7");
+        assert_eq!(trailing, args(&["thor", "analyze", "--pairs", "4:7"]));
+    }
+}
@@ -0,0 +1,60 @@
+//! 'pipeline': runs the analyses declared in the configuration on one loaded graph.
+
+use std::error::Error;
+use std::collections::HashSet;
+use crate::cli::{alert_output, analysis_context, check_alerts, configuration, coverage_lines, filter_outputs, load_input, LoadedInput, render_outputs, select_pairs, std_output};
+#[cfg(feature = "serde")]
+use crate::cli::arg_value;
+use crate::config::DEFAULT_CONFIG;
+use crate::output::Output;
+#[cfg(feature = "serde")]
+use crate::output::json::{JsonOutput, run_metadata};
+use crate::pipeline::{Pipeline, PipelineState};
+
+/// Reads the input once and runs the pipeline of the configuration given by '--config', or of
+/// ['DEFAULT_CONFIG'], with the profile selected by '--profile'. The results of the last analysis stage are written like those of the
+/// analyze command, filtered by '--filter <expression>' if given and alerted on as with
+/// '--alert-file' and '--fail-on-critical'. '--coverage' prints the coverage of the state space of
+/// every sampling of the stages.
+///
+/// # Errors
+///
+/// Returns an error if the configuration or the input can't be read or a stage fails
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let config = configuration(args)?
+        .ok_or_else(|| format!("The pipeline needs a configuration, give one with '--config' or create {}", DEFAULT_CONFIG))?;
+    let pipeline = Pipeline::from_config(&config)?;
+    let LoadedInput { mut graph, crit_data, roll_up_rule, .. } = load_input(args)?;
+    let (pairs, _) = select_pairs(args, &graph)?;
+    let (start_id, end_id) = pairs[0];
+    graph.static_nodes.insert(start_id);
+    graph.static_nodes.insert(end_id);
+    let dynamic_ids: HashSet<u32> = graph.get_node_ids().into_iter()
+        .filter(|id| !graph.static_nodes.contains(id))
+        .collect();
+
+    let mut state = PipelineState::new(graph, dynamic_ids, crit_data.off_chances.clone(), roll_up_rule, start_id, end_id);
+    let ctx = analysis_context(args)?;
+    pipeline.run(&mut state, &ctx)?;
+    for line in coverage_lines(&ctx) {
+        println!("{}", line);
+    }
+
+    if let Some(results) = state.results {
+        let mut outputs: Vec<Box<dyn Output>> = vec![Box::new(std_output(args, results.nodes.len())?)];
+        outputs.extend(render_outputs(args, crit_data.alpha()));
+        #[cfg(feature = "serde")]
+        if let Some(path) = arg_value(args, "--results") {
+            outputs.push(Box::new(JsonOutput { path: path.to_string(), run: run_metadata(args) }));
+        }
+        let alert = alert_output(args)?;
+        if let Some(alert) = alert.as_ref() {
+            outputs.push(Box::new(alert.clone()));
+        }
+        for output in filter_outputs(args, outputs)?.iter() {
+            output.write(&state.graph, &results)?;
+        }
+        check_alerts(args, alert.as_ref())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! 'report <results>': prints results stored by 'analyze --results' again.
+
+use std::error::Error;
+use crate::cli::{alert_output, arg_value, check_alerts, filter_outputs, path_arg, render_outputs, std_output};
+use crate::input::read_event_tree;
+use crate::output::Output;
+use crate::output::event_tree::EventTreeOutput;
+use crate::output::json::StoredResults;
+
+/// Writes the stored results following the command to the standard output, through an event
+/// tree if '--event-tree <path>' is given, as drawings with '--svg', '--png' or '--html' and as
+/// Cytoscape.js json with '--cytoscape'. '--filter <expression>' keeps only the nodes passing the
+/// filter, see ['crate::cli::filter_outputs']. The nodes in the critical band are alerted on as
+/// for the analyze command, with '--alert-file' and '--fail-on-critical', so stored results can
+/// gate a change without running the analysis again.
+///
+/// # Errors
+///
+/// Returns an error if the results or the event tree can't be read, or a
+/// ['crate::errors::analysis::CriticalBandError'] with '--fail-on-critical'
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = path_arg(args).ok_or("The report command needs the path of stored results")?;
+    let stored = StoredResults::read(path)?;
+    let mut outputs: Vec<Box<dyn Output>> = vec![Box::new(std_output(args, stored.results.nodes.len())?)];
+    if let Some(tree) = arg_value(args, "--event-tree") {
+        outputs.push(Box::new(EventTreeOutput { tree: read_event_tree(tree, false)? }));
+    }
+    // Stored results don't keep the edge attributes, so every edge is drawn the same
+    outputs.extend(render_outputs(args, None));
+    let alert = alert_output(args)?;
+    if let Some(alert) = alert.as_ref() {
+        outputs.push(Box::new(alert.clone()));
+    }
+    for output in filter_outputs(args, outputs)?.iter() {
+        output.write(&stored.graph, &stored.results)?;
+    }
+    check_alerts(args, alert.as_ref())
+}
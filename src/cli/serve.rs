@@ -0,0 +1,32 @@
+//! 'serve': answers analysis requests over http, see ['crate::server'].
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use crate::audit::AuditLog;
+use crate::cli::{arg_number, arg_value};
+use crate::server::{serve, ServerConfig};
+
+/// Starts the server on '--address host:port' and samples '--samples' states per analysis unless
+/// a request asks for another number. '--workers' connections are answered at once
+/// (['crate::server::DEFAULT_WORKERS'] if not given). '--audit-log <path>' records every request posting work,
+/// see ['crate::audit'].
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid, the audit log can't be opened or the address
+/// can't be bound
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let defaults = ServerConfig::default();
+    let config = ServerConfig {
+        address: arg_value(args, "--address").cloned().unwrap_or(defaults.address),
+        samples: arg_number(args, "--samples", defaults.samples)?,
+        audit_log: match arg_value(args, "--audit-log") {
+            Some(path) => Some(Arc::new(AuditLog::open(Path::new(path))?)),
+            None => None,
+        },
+        workers: arg_number(args, "--workers", defaults.workers)?,
+    };
+    println!("Serving on http://{}", config.address);
+    serve(config)
+}
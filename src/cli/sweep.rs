@@ -0,0 +1,36 @@
+//! 'sweep': runs the criticality analysis over the parameter grid declared in the configuration.
+
+use std::error::Error;
+use std::collections::HashSet;
+use crate::cli::{analysis_context, arg_value, configuration, load_input, LoadedInput, select_pairs, thread_count};
+use crate::config::DEFAULT_CONFIG;
+use crate::output::{STDOUT_PATH, write_output};
+use crate::pipeline::PipelineState;
+use crate::sweep::Sweep;
+
+/// Reads the input once and analyses it at every point of the sweep of the configuration given
+/// by '--config', or of ['DEFAULT_CONFIG'], on '--threads' threads. The table of the results is
+/// written to '--output', or to the standard output if not given.
+///
+/// # Errors
+///
+/// Returns an error if the configuration or the input can't be read or a point fails
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let config = configuration(args)?
+        .ok_or_else(|| format!("The sweep needs a configuration, give one with '--config' or create {}", DEFAULT_CONFIG))?;
+    let sweep = Sweep::from_config(&config)?;
+    let LoadedInput { mut graph, crit_data, roll_up_rule, .. } = load_input(args)?;
+    let (pairs, _) = select_pairs(args, &graph)?;
+    let (start_id, end_id) = pairs[0];
+    graph.static_nodes.insert(start_id);
+    graph.static_nodes.insert(end_id);
+    let dynamic_ids: HashSet<u32> = graph.get_node_ids().into_iter()
+        .filter(|id| !graph.static_nodes.contains(id))
+        .collect();
+
+    let state = PipelineState::new(graph, dynamic_ids, crit_data.off_chances, roll_up_rule, start_id, end_id);
+    let results = sweep.run(&state, thread_count(args)? as usize, &analysis_context(args)?)?;
+    let path = arg_value(args, "--output").map(|p| p.as_str()).unwrap_or(STDOUT_PATH);
+    write_output(path, results.to_csv())?;
+    Ok(())
+}
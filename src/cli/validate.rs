@@ -0,0 +1,29 @@
+//! 'validate': reads the input and checks it without running an analysis.
+
+use std::error::Error;
+use crate::cli::{load_input, select_pairs};
+use crate::errors::validation::ValidationError;
+use crate::validation::validate_model;
+
+/// Checks the input given by the arguments with ['validate_model'] and prints every problem found
+///
+/// # Errors
+///
+/// Returns an error if the input can't be read or has any errors
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let input = load_input(args)?;
+    let (report, pairs) = match select_pairs(args, &input.graph) {
+        Ok((pairs, _)) => (validate_model(&input.graph, &input.crit_data, &pairs), pairs),
+        Err(e) => {
+            let mut report = validate_model(&input.graph, &input.crit_data, &[]);
+            report.errors.insert(0, e.to_string());
+            (report, vec![])
+        }
+    };
+    println!("Read {} nodes and {} edges, checked the pairs {:?}", input.graph.get_node_ids().len(), input.graph.get_edges().len(), pairs);
+    report.print();
+    match report.is_valid() {
+        true => Ok(()),
+        false => Err(ValidationError { errors: report.errors }.into()),
+    }
+}
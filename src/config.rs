@@ -0,0 +1,172 @@
+//! The configuration file, a json object whose sections configure the commands.
+//!
+//! ```json
+//! {
+//!   "flags": { "threads": 4 },
+//!   "roll_up": { "weighted": [[0.7, "and"], [0.3, "or"]] },
+//!   "contract": [{ "ids": [4, 5, 6], "id": 100, "name": "pumps" }],
+//!   "pipeline": [
+//!     { "stage": "criticality", "samples": 10000 },
+//!     { "stage": "freeze", "below": 0.01 },
+//!     { "stage": "exact" }
+//!   ],
+//!   "profiles": {
+//!     "quick": { "flags": { "samples": 1000 } },
+//!     "nightly": {
+//!       "flags": { "samples": 100000, "gen": "antithetic", "html": "nightly.html" },
+//!       "pipeline": [{ "stage": "criticality", "samples": 100000 }, { "stage": "exact" }]
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! The 'flags' section gives command line flags for every run, see ['Config::flags'] and the
+//! settings module. A profile is a named preset selected with '--profile', see
+//! ['Config::with_profile']. Its sections replace the sections of the same name, except for its
+//! 'flags' which replace those of the configuration flag by flag.
+
+use std::error::Error;
+use std::fs;
+use crate::errors::config::ConfigError;
+use crate::json;
+use crate::json::JsonValue;
+
+/// Path of the configuration read when no '--config' is given and the file exists
+pub const DEFAULT_CONFIG: &str = "./thor.json";
+
+/// Section holding the named profiles
+pub const PROFILES_KEY: &str = "profiles";
+
+/// Section of the configuration, or of a profile, holding command line flags
+pub const FLAGS_KEY: &str = "flags";
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The file the configuration was read from
+    pub path: String,
+    pub values: JsonValue,
+    /// The profile applied to the values, if any
+    pub profile: Option<String>,
+}
+
+impl Config {
+    /// Reads the configuration file at 'path'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read, is not json or is not a json object
+    pub fn read(path: &str) -> Result<Config, Box<dyn Error>> {
+        let values = json::parse(&fs::read_to_string(path)?)?;
+        if values.as_object().is_none() {
+            return Err(Box::new(ConfigError { path: path.to_string(), reason: "the configuration must be a json object".to_string() }));
+        }
+        Ok(Config { path: path.to_string(), values, profile: None })
+    }
+
+    /// The section or value 'key' of the configuration
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.values.get(key)
+    }
+
+    /// Error about the value of 'key' in this configuration
+    pub fn error(&self, key: &str, reason: &str) -> ConfigError {
+        let reason = match &self.profile {
+            Some(profile) => format!("'{}' of the profile '{}' {}", key, profile, reason),
+            None => format!("'{}' {}", key, reason),
+        };
+        ConfigError { path: self.path.to_string(), reason }
+    }
+
+    /// Names of the profiles of the configuration
+    pub fn profiles(&self) -> Vec<&str> {
+        match self.get(PROFILES_KEY).and_then(|p| p.as_object()) {
+            Some(profiles) => profiles.iter().map(|(name, _)| name.as_str()).collect(),
+            None => vec![],
+        }
+    }
+
+    /// The configuration with the sections of the profile 'name' in place of the sections of the
+    /// same name. The 'flags' of the profile are merged into the 'flags' of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ConfigError'] if there is no such profile or it is not a json object
+    pub fn with_profile(mut self, name: &str) -> Result<Config, ConfigError> {
+        let profile = self.get(PROFILES_KEY).and_then(|p| p.get(name)).cloned()
+            .ok_or_else(|| self.error(PROFILES_KEY, &format!("has no profile '{}', the profiles are: {:?}", name, self.profiles())))?;
+        let Some(sections) = profile.as_object() else {
+            return Err(self.error(PROFILES_KEY, &format!("has a profile '{}' that is not a json object", name)));
+        };
+        for (key, value) in sections {
+            let mut value = value.clone();
+            if let (FLAGS_KEY, Some(JsonValue::Object(flags))) = (key.as_str(), self.get(FLAGS_KEY)) {
+                let mut merged = JsonValue::Object(flags.clone());
+                for (flag, flag_value) in value.as_object().into_iter().flatten() {
+                    merged.insert(flag.as_str(), flag_value.clone());
+                }
+                value = merged;
+            }
+            self.values.insert(key.as_str(), value);
+        }
+        self.profile = Some(name.to_string());
+        Ok(self)
+    }
+
+    /// The command line arguments given by the 'flags' of the configuration: every key becomes a
+    /// flag followed by its value, 'true' stands for a flag without a value and 'false' leaves it
+    /// out
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ConfigError'] if the flags are not a json object, or a value is an array or an
+    /// object
+    pub fn flags(&self) -> Result<Vec<String>, ConfigError> {
+        let Some(flags) = self.get(FLAGS_KEY) else { return Ok(vec![]) };
+        let flags = flags.as_object().ok_or_else(|| self.error(FLAGS_KEY, "must be a json object"))?;
+        let mut args = vec![];
+        for (name, value) in flags {
+            let flag = format!("--{}", name.trim_start_matches('-'));
+            match value {
+                JsonValue::Bool(false) | JsonValue::Null => {}
+                JsonValue::Bool(true) => args.push(flag),
+                JsonValue::String(s) => args.extend([flag, s.to_string()]),
+                JsonValue::Number(_) => args.extend([flag, value.to_string()]),
+                _ => return Err(self.error(FLAGS_KEY, &format!("has a value for '{}' that is neither a string, a number nor a boolean", name))),
+            }
+        }
+        Ok(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::analyses::criticality::builder::DEFAULT_SAMPLES;
+    use crate::cli::analyze::sample_count;
+    use crate::cli::with_config_flags;
+
+    const PROFILES: &str = r#"{
+        "flags": { "threads": 1 },
+        "profiles": {
+            "quick": { "flags": { "samples": 1000 } },
+            "nightly": { "flags": { "samples": 100000 } }
+        }
+    }"#;
+
+    #[test]
+    fn profiles_set_the_samples_of_the_criticality_analysis() {
+        let path = std::env::temp_dir().join(format!("thor_profiles_{}.json", std::process::id()));
+        fs::write(&path, PROFILES).unwrap();
+        let samples = |extra: &[&str]| {
+            let mut args: Vec<String> = vec!["thor".to_string(), "--config".to_string(), path.display().to_string()];
+            args.extend(extra.iter().map(|a| a.to_string()));
+            sample_count(&with_config_flags(&args).unwrap(), None).unwrap()
+        };
+        assert_eq!(samples(&[]), DEFAULT_SAMPLES);
+        assert_eq!(samples(&["--profile", "quick"]), 1000);
+        assert_eq!(samples(&["--profile", "nightly"]), 100000);
+        // The command line takes precedence over the profile
+        assert_eq!(samples(&["--profile", "nightly", "--samples", "50"]), 50);
+        fs::remove_file(path).unwrap();
+    }
+}
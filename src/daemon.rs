@@ -0,0 +1,689 @@
+//! Queue of analysis jobs run side by side by the 'daemon' command, within a budget of cores.
+//!
+//! A job is a command line of the binary, such as '["analyze", "--input", "links.csv"]', with the
+//! number of threads it may use. Jobs are submitted over http or by dropping a job file in the
+//! spool directory, and run in the order they were submitted as separate processes of the
+//! binary. A job starts once the threads of the running jobs and its own fit in the core
+//! budget, so a large job waits for cores rather than being overtaken by smaller ones.
+//!
+//! Every job has a directory named after its id in the jobs directory, holding:
+//!
+//! * 'job.json': the job with its 'state', one of 'queued', 'running', 'done', 'failed' or
+//!   'cancelled', and the times it was submitted, started and finished
+//! * 'results.json': the results, always written here by '--results'
+//! * 'stdout.txt' and 'stderr.txt': what the job printed
+//!
+//! The directory is read again when the daemon starts, so queued jobs survive a restart. Jobs
+//! that were running when the daemon stopped are marked as failed.
+//!
+//! A job file is a json object with the 'args' of the job and optionally its 'threads', by
+//! default the '--threads' of the args or 1. The api has no authentication, so a job may only
+//! run the 'analyze' command, with an optional input path and the flags of ['JOB_SWITCHES'] and
+//! ['JOB_OPTIONS']. Flags writing files, running hooks or reading another configuration are
+//! refused, a job only writes to its own directory. Only files ending in ['SPOOL_EXTENSION'] are read,
+//! so a job file should be written under another name and renamed once complete. Accepted files
+//! are removed, files that aren't valid jobs are renamed with the '.rejected' extension.
+//!
+//! The routes of the http api are:
+//!
+//! * 'GET /health' answers with '{"status": "ok"}'
+//! * 'POST /jobs' queues the job posted as json and answers with its 'id'
+//! * 'GET /jobs' lists every job, 'GET /jobs/<id>' answers with one
+//! * 'GET /jobs/<id>/results' answers with the results of a finished job
+//! * 'GET /jobs/<id>/output' answers with what the job printed
+//! * 'DELETE /jobs/<id>' cancels a queued or running job
+//!
+//! With an audit log, the submission, start, cancellation and end of every job are recorded,
+//! with the files it read when it started and the hash of its results, see ['crate::audit']. A
+//! job or a cancellation that can't be recorded is refused.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use log::{error, info, warn};
+use crate::audit::{AuditLog, content_id, requester};
+use crate::cli::arg_value;
+use crate::errors::daemon::JobArgError;
+use crate::errors::json::JsonFieldError;
+use crate::json;
+use crate::json::JsonValue;
+use crate::manifest::{INPUT_FLAGS, files, utc_timestamp};
+use crate::output::write_output;
+use crate::server::{DEFAULT_WORKERS, read_request, Reply, Request, serve_connections, write_reply};
+
+/// Address the daemon listens on when none is given
+pub const DEFAULT_DAEMON_ADDRESS: &str = "127.0.0.1:7879";
+/// Extension of the job files read from the spool directory
+pub const SPOOL_EXTENSION: &str = ".json";
+/// Time between two rounds of the scheduler
+const TICK: Duration = Duration::from_millis(200);
+
+const JOB_FILE: &str = "job.json";
+const RESULTS_FILE: &str = "results.json";
+const STDOUT_FILE: &str = "stdout.txt";
+const STDERR_FILE: &str = "stderr.txt";
+
+/// Command a job may run
+const JOB_COMMAND: &str = "analyze";
+/// Flags without a value a job may give
+pub const JOB_SWITCHES: [&str; 14] = [
+    "--antithetic", "--coverage", "--dry-run", "--fail-on-critical", "--gray-code", "--headers", "--influence",
+    "--mutual-information", "--no-exhaustive", "--per-component", "--prune", "--significant", "--single-points",
+    "--surrogate",
+];
+/// Flags followed by a value a job may give
+pub const JOB_OPTIONS: [&str; 56] = [
+    "--input", "--alpha", "--node-attributes", "--scenarios", "--dependencies", "--ccf-groups", "--event-tree", "--replay",
+    "--baseline", "--neo4j", "--from", "--columns", "--delimiter", "--quote", "--encoding", "--duplicate-edges",
+    "--self-loops", "--end-weights", "--filter", "--groups", "--direction", "--terminals", "--roll-up", "--analysis",
+    "--method", "--samples", "--seed", "--threads", "--top", "--cutoff", "--confidence", "--tolerance",
+    "--iterations", "--damping", "--max-time", "--max-memory", "--max-visited", "--on-limit", "--max-order",
+    "--max-paths", "--mission-time", "--mission-times", "--hardening", "--budget", "--capacity", "--edge-cost",
+    "--latency", "--population", "--bands", "--against", "--pairs", "--options", "--optimistic", "--pessimistic",
+    "--surrogate-margin", "--surrogate-training",
+];
+/// Flags followed by a value that only change what the job prints
+const JOB_PRINT_OPTIONS: [&str; 4] = ["--profile", "--format", "--log-format", "--error-format"];
+
+/// Where a job is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+const JOB_STATES: [(JobState, &str); 5] = [
+    (JobState::Queued, "queued"),
+    (JobState::Running, "running"),
+    (JobState::Done, "done"),
+    (JobState::Failed, "failed"),
+    (JobState::Cancelled, "cancelled"),
+];
+
+impl JobState {
+    pub fn name(&self) -> &'static str {
+        JOB_STATES.iter().find(|(s, _)| s == self).map(|(_, name)| *name).unwrap()
+    }
+
+    pub fn from_name(name: &str) -> Option<JobState> {
+        JOB_STATES.iter().find(|(_, n)| *n == name).map(|(state, _)| *state)
+    }
+
+    /// Whether the job will never run again
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Done | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+/// What a job runs, see the module documentation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobSpec {
+    /// Arguments of the binary, starting with the command
+    pub args: Vec<String>,
+    /// Cores the job takes from the budget while it runs
+    pub threads: u8,
+}
+
+impl JobSpec {
+    /// Reads a job posted or dropped in the spool directory
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['JsonFieldError'] if the args aren't a list of strings or the threads aren't a
+    /// number between 1 and 255, and a ['JobArgError'] if the args aren't allowed in a job
+    pub fn from_json(value: &JsonValue) -> Result<JobSpec, Box<dyn Error>> {
+        let field_error = |field: &str, expected: &str| JsonFieldError { field: field.to_string(), expected: expected.to_string() };
+        let args = value.get("args").and_then(|a| a.as_array())
+            .and_then(|a| a.iter().map(|arg| arg.as_str().map(|s| s.to_string())).collect::<Option<Vec<String>>>())
+            .filter(|args| !args.is_empty())
+            .ok_or_else(|| field_error("args", "a list of strings starting with the command"))?;
+        check_args(&args)?;
+        // Flags are read after the name of the binary
+        let flags: Vec<String> = std::iter::once(String::new()).chain(args.iter().cloned()).collect();
+        let threads = match value.get("threads") {
+            Some(threads) => threads.as_u64(),
+            None => Some(arg_value(&flags, "--threads").and_then(|t| t.parse::<u64>().ok()).unwrap_or(1)),
+        };
+        let threads = threads.filter(|t| (1..=255).contains(t))
+            .ok_or_else(|| field_error("threads", "a number between 1 and 255"))?;
+        Ok(JobSpec { args, threads: threads as u8 })
+    }
+}
+
+/// Checks the args of a job are the 'analyze' command, an optional input path and the allowed
+/// flags, so a job can't write outside of its directory or run a command through a hook.
+/// Values starting with '--' are refused too, as a flag is found wherever it is in the args.
+fn check_args(args: &[String]) -> Result<(), JobArgError> {
+    let refuse = |arg: &str, reason: &str| JobArgError { arg: arg.to_string(), reason: reason.to_string() };
+    if args[0] != JOB_COMMAND {
+        return Err(refuse(&args[0], "a job can only run the 'analyze' command"));
+    }
+    let mut rest = args[1..].iter().enumerate();
+    while let Some((i, arg)) = rest.next() {
+        if JOB_SWITCHES.contains(&arg.as_str()) {
+            continue;
+        }
+        if JOB_OPTIONS.contains(&arg.as_str()) || JOB_PRINT_OPTIONS.contains(&arg.as_str()) {
+            match rest.next() {
+                Some((_, value)) if !value.starts_with("--") => continue,
+                _ => return Err(refuse(arg, "the flag needs a value")),
+            }
+        }
+        if arg.starts_with("--") {
+            return Err(refuse(arg, "the flag isn't allowed in a job"));
+        }
+        if i != 0 {
+            return Err(refuse(arg, "only the input path may follow the command"));
+        }
+    }
+    Ok(())
+}
+
+/// A job of the queue
+#[derive(Debug)]
+pub struct Job {
+    pub id: u64,
+    pub spec: JobSpec,
+    pub state: JobState,
+    pub submitted: String,
+    pub started: Option<String>,
+    pub finished: Option<String>,
+    /// Exit code of the process of a finished job
+    pub exit_code: Option<i32>,
+    /// Why the job failed or was cancelled
+    pub error: Option<String>,
+    /// Process of a running job
+    child: Option<Child>,
+}
+
+impl Job {
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object()
+            .with("id", self.id)
+            .with("state", self.state.name())
+            .with("args", self.spec.args.clone())
+            .with("threads", self.spec.threads as u32)
+            .with("submitted", self.submitted.as_str())
+            .with("started", self.started.clone())
+            .with("finished", self.finished.clone())
+            .with("exit_code", self.exit_code.map(|c| c as i64))
+            .with("error", self.error.clone())
+    }
+
+    /// Reads a job written by ['Job::to_json']
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['JsonFieldError'] if the id, the state or the spec is missing or invalid, and a
+    /// ['JobArgError'] if the args aren't allowed in a job
+    pub fn from_json(value: &JsonValue) -> Result<Job, Box<dyn Error>> {
+        let field_error = |field: &str, expected: &str| JsonFieldError { field: field.to_string(), expected: expected.to_string() };
+        let text = |field: &str| value.get(field).and_then(|v| v.as_str()).map(|s| s.to_string());
+        Ok(Job {
+            id: value.get("id").and_then(|id| id.as_u64()).ok_or_else(|| field_error("id", "a number"))?,
+            spec: JobSpec::from_json(value)?,
+            state: text("state").and_then(|s| JobState::from_name(&s)).ok_or_else(|| field_error("state", "a job state"))?,
+            submitted: text("submitted").unwrap_or_default(),
+            started: text("started"),
+            finished: text("finished"),
+            exit_code: value.get("exit_code").and_then(|c| c.as_f64()).map(|c| c as i32),
+            error: text("error"),
+            child: None,
+        })
+    }
+
+    /// The arguments the process of the job is started with, the threads are always given and
+    /// the results are always written to the job directory
+    fn process_args(&self, dir: &Path) -> Vec<String> {
+        let mut args = self.spec.args.clone();
+        let flags: Vec<String> = std::iter::once(String::new()).chain(args.iter().cloned()).collect();
+        if arg_value(&flags, "--threads").is_none() {
+            args.extend(["--threads".to_string(), self.spec.threads.to_string()]);
+        }
+        args.extend(["--results".to_string(), dir.join(RESULTS_FILE).to_string_lossy().to_string()]);
+        args
+    }
+}
+
+/// Settings of the daemon
+#[derive(Debug, Clone)]
+pub struct DaemonConfig {
+    /// 'host:port' the http api listens on
+    pub address: String,
+    /// Directory holding a directory for every job
+    pub jobs_dir: PathBuf,
+    /// Directory polled for job files, if any
+    pub spool_dir: Option<PathBuf>,
+    /// Threads the running jobs may use together
+    pub cores: u8,
+    /// Binary run by the jobs, usually the one running the daemon
+    pub executable: PathBuf,
+    /// Log the jobs are recorded to, if any
+    pub audit_log: Option<Arc<AuditLog>>,
+}
+
+/// The jobs of the daemon, see the module documentation
+pub struct JobQueue {
+    config: DaemonConfig,
+    jobs: BTreeMap<u64, Job>,
+}
+
+impl JobQueue {
+    /// Opens the jobs directory, reading the jobs left by an earlier daemon
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the directory can't be created or read
+    pub fn open(config: DaemonConfig) -> io::Result<JobQueue> {
+        fs::create_dir_all(&config.jobs_dir)?;
+        let mut queue = JobQueue { config, jobs: BTreeMap::new() };
+        for entry in fs::read_dir(&queue.config.jobs_dir)? {
+            let path = entry?.path().join(JOB_FILE);
+            let job = fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|text| json::parse(&text).map_err(|e| e.to_string()))
+                .and_then(|value| Job::from_json(&value).map_err(|e| e.to_string()));
+            let mut job = match job {
+                Ok(job) => job,
+                Err(e) => { warn!("Skipped the job file {}: {}", path.display(), e); continue; }
+            };
+            if job.state == JobState::Running {
+                job.state = JobState::Failed;
+                job.finished = Some(utc_timestamp(SystemTime::now()));
+                job.error = Some("The daemon stopped while the job was running".to_string());
+                queue.persist(&job);
+            }
+            queue.jobs.insert(job.id, job);
+        }
+        info!("Opened {} jobs in {}", queue.jobs.len(), queue.config.jobs_dir.display());
+        Ok(queue)
+    }
+
+    pub fn job(&self, id: u64) -> Option<&Job> {
+        self.jobs.get(&id)
+    }
+
+    /// Every job in the order they were submitted
+    pub fn jobs(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.values()
+    }
+
+    fn dir(&self, id: u64) -> PathBuf {
+        self.config.jobs_dir.join(id.to_string())
+    }
+
+    /// The file the job 'id' writes its results to
+    pub fn results_path(&self, id: u64) -> PathBuf {
+        self.dir(id).join(RESULTS_FILE)
+    }
+
+    /// The file the job 'id' prints to
+    pub fn output_path(&self, id: u64) -> PathBuf {
+        self.dir(id).join(STDOUT_FILE)
+    }
+
+    /// Records the 'event' of the job 'id' with the 'fields' in the audit log, if there is one
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the entry can't be written
+    pub fn audit(&self, event: &str, id: u64, fields: JsonValue) -> io::Result<()> {
+        match self.config.audit_log.as_ref() {
+            Some(audit) => audit.record(event, fields.with("job", id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Queues a job submitted by the 'submitter', see ['crate::audit::requester'], and records
+    /// it. A job that can't be recorded is cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the job can't be queued or recorded
+    pub fn submit_audited(&mut self, spec: JobSpec, submitter: JsonValue) -> io::Result<u64> {
+        let id = self.submit(spec)?;
+        let fields = submitter.with("config", self.jobs[&id].spec.args.clone());
+        if let Err(e) = self.audit("job_submitted", id, fields) {
+            self.cancel(id);
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Queues a job, its threads are cut down to the core budget
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the directory of the job can't be created
+    pub fn submit(&mut self, mut spec: JobSpec) -> io::Result<u64> {
+        let id = self.jobs.keys().next_back().map(|id| id + 1).unwrap_or(1);
+        spec.threads = spec.threads.min(self.config.cores.max(1));
+        fs::create_dir_all(self.dir(id))?;
+        let job = Job {
+            id,
+            spec,
+            state: JobState::Queued,
+            submitted: utc_timestamp(SystemTime::now()),
+            started: None,
+            finished: None,
+            exit_code: None,
+            error: None,
+            child: None,
+        };
+        self.persist(&job);
+        info!("Queued job {}: {}", id, job.spec.args.join(" "));
+        self.jobs.insert(id, job);
+        Ok(id)
+    }
+
+    /// Cancels a queued or running job, returning its state afterwards. None if there is no such
+    /// job.
+    pub fn cancel(&mut self, id: u64) -> Option<JobState> {
+        let job = self.jobs.get_mut(&id)?;
+        if job.state.is_finished() {
+            return Some(job.state);
+        }
+        if let Some(mut child) = job.child.take() {
+            if let Err(e) = child.kill().and_then(|_| child.wait()) {
+                warn!("Failed to stop the process of job {}: {}", id, e);
+            }
+        }
+        job.state = JobState::Cancelled;
+        job.finished = Some(utc_timestamp(SystemTime::now()));
+        job.error = Some("Cancelled".to_string());
+        info!("Cancelled job {}", id);
+        let state = job.state;
+        self.persist(&self.jobs[&id]);
+        Some(state)
+    }
+
+    /// Threads used by the running jobs
+    pub fn busy_cores(&self) -> u32 {
+        self.jobs.values().filter(|j| j.state == JobState::Running).map(|j| j.spec.threads as u32).sum()
+    }
+
+    /// Collects the jobs that finished and starts queued jobs while their threads fit in the budget
+    pub fn schedule(&mut self) {
+        let running: Vec<u64> = self.jobs.values().filter(|j| j.state == JobState::Running).map(|j| j.id).collect();
+        for id in running {
+            self.reap(id);
+        }
+        let queued: Vec<u64> = self.jobs.values().filter(|j| j.state == JobState::Queued).map(|j| j.id).collect();
+        for id in queued {
+            // Jobs start in order, the first that doesn't fit waits for the running ones
+            if self.busy_cores() + self.jobs[&id].spec.threads as u32 > self.config.cores as u32 {
+                break;
+            }
+            self.start(id);
+        }
+    }
+
+    fn start(&mut self, id: u64) {
+        let dir = self.dir(id);
+        let job = self.jobs.get_mut(&id).unwrap();
+        let spawned = File::create(dir.join(STDOUT_FILE))
+            .and_then(|stdout| Ok((stdout, File::create(dir.join(STDERR_FILE))?)))
+            .and_then(|(stdout, stderr)| Command::new(&self.config.executable)
+                .args(job.process_args(&dir))
+                .stdin(Stdio::null())
+                .stdout(stdout)
+                .stderr(stderr)
+                .spawn());
+        job.started = Some(utc_timestamp(SystemTime::now()));
+        match spawned {
+            Ok(child) => {
+                info!("Started job {} on {} threads", id, job.spec.threads);
+                job.child = Some(child);
+                job.state = JobState::Running;
+            }
+            Err(e) => {
+                error!("Failed to start job {}: {}", id, e);
+                job.state = JobState::Failed;
+                job.finished = job.started.clone();
+                job.error = Some(format!("The job could not be started: {}", e));
+            }
+        }
+        self.persist(&self.jobs[&id]);
+        // The inputs are hashed as the job reads them, they may have changed since it was queued
+        let job = &self.jobs[&id];
+        let fields = JsonValue::object()
+            .with("state", job.state.name())
+            .with("inputs", files(&job.spec.args, &INPUT_FLAGS));
+        if let Err(e) = self.audit("job_started", id, fields) {
+            error!("Failed to record the start of job {} in the audit log: {}", id, e);
+        }
+    }
+
+    /// Marks the running job 'id' as done or failed if its process exited
+    fn reap(&mut self, id: u64) {
+        let stderr = self.dir(id).join(STDERR_FILE);
+        let job = self.jobs.get_mut(&id).unwrap();
+        let Some(child) = job.child.as_mut() else { return };
+        let status = match child.try_wait() {
+            Ok(Some(status)) => status,
+            Ok(None) => return,
+            Err(e) => { warn!("Failed to check the process of job {}: {}", id, e); return; }
+        };
+        job.child = None;
+        job.finished = Some(utc_timestamp(SystemTime::now()));
+        job.exit_code = status.code();
+        match status.success() {
+            true => job.state = JobState::Done,
+            false => {
+                job.state = JobState::Failed;
+                // The error of a failed run is the last line it printed to stderr
+                let last_line = fs::read_to_string(stderr).ok()
+                    .and_then(|text| text.lines().rev().find(|l| !l.trim().is_empty()).map(|l| l.to_string()));
+                job.error = Some(last_line.unwrap_or(format!("The job exited with {}", status)));
+            }
+        }
+        info!("Job {} is {}", id, job.state.name());
+        self.persist(&self.jobs[&id]);
+        let job = &self.jobs[&id];
+        let result_id = match job.state {
+            JobState::Done => fs::read(self.results_path(id)).ok().map(|data| content_id(&data)),
+            _ => None,
+        };
+        let fields = JsonValue::object()
+            .with("state", job.state.name())
+            .with("exit_code", job.exit_code.map(|c| c as i64))
+            .with("result_id", result_id);
+        if let Err(e) = self.audit("job_finished", id, fields) {
+            error!("Failed to record the end of job {} in the audit log: {}", id, e);
+        }
+    }
+
+    /// Queues the job files of the spool directory, see the module documentation
+    pub fn poll_spool(&mut self) {
+        let Some(spool) = self.config.spool_dir.clone() else { return };
+        let mut files: Vec<PathBuf> = match fs::read_dir(&spool) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+                .filter(|p| p.is_file() && p.to_string_lossy().ends_with(SPOOL_EXTENSION))
+                .collect(),
+            Err(e) => { warn!("Failed to read the spool directory {}: {}", spool.display(), e); return; }
+        };
+        files.sort();
+        for path in files {
+            let spec = fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|text| json::parse(&text).map_err(|e| e.to_string()))
+                .and_then(|value| JobSpec::from_json(&value).map_err(|e| e.to_string()));
+            let queued = match spec {
+                Ok(spec) => self.submit_audited(spec, JsonValue::object().with("spool_file", path.to_string_lossy().to_string()))
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            let moved = match queued {
+                Ok(_) => fs::remove_file(&path),
+                Err(e) => {
+                    warn!("Rejected the job file {}: {}", path.display(), e);
+                    fs::rename(&path, format!("{}.rejected", path.display()))
+                }
+            };
+            if let Err(e) = moved {
+                warn!("Failed to move the job file {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn persist(&self, job: &Job) {
+        let path = self.dir(job.id).join(JOB_FILE);
+        if let Err(e) = write_output(&path.to_string_lossy(), job.to_json().to_pretty_string() + "\n") {
+            error!("Failed to write {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Runs the scheduler and answers the http api until the process is stopped
+///
+/// # Errors
+///
+/// Returns an error if the jobs directory can't be opened or the address can't be bound
+pub fn run_daemon(config: DaemonConfig) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(&config.address)?;
+    info!("Running jobs on {} cores, listening on {}", config.cores, config.address);
+    let queue = Arc::new(Mutex::new(JobQueue::open(config)?));
+    let scheduled = queue.clone();
+    thread::spawn(move || loop {
+        {
+            let mut queue = scheduled.lock().unwrap();
+            queue.poll_spool();
+            queue.schedule();
+        }
+        thread::sleep(TICK);
+    });
+
+    serve_connections(&listener, DEFAULT_WORKERS, move |stream| handle_connection(stream, &queue));
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, queue: &Mutex<JobQueue>) -> Result<(), Box<dyn Error>> {
+    let reply = match read_request(BufReader::new(stream.try_clone()?)) {
+        Ok(mut request) => {
+            request.peer = stream.peer_addr().ok().map(|a| a.to_string());
+            info!("{} {}", request.method, request.path);
+            route(&request, queue)
+        }
+        Err(e) => Reply::unreadable(e.as_ref()),
+    };
+    write_reply(&mut stream, &reply)
+}
+
+/// Answers a request, see the module documentation for the routes
+pub fn route(request: &Request, queue: &Mutex<JobQueue>) -> Reply {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let id = segments.get(1).map(|id| id.parse::<u64>());
+    match (request.method.as_str(), segments.as_slice(), id) {
+        ("GET", ["health"], _) => Reply::json(200, &JsonValue::object().with("status", "ok")),
+        ("GET", ["jobs"], _) => {
+            let jobs: Vec<JsonValue> = queue.lock().unwrap().jobs().map(|j| j.to_json()).collect();
+            Reply::json(200, &JsonValue::object().with("jobs", jobs))
+        }
+        ("POST", ["jobs"], _) => {
+            let spec = json::parse(&String::from_utf8_lossy(&request.body)).map_err(|e| e.to_string())
+                .and_then(|value| JobSpec::from_json(&value).map_err(|e| e.to_string()));
+            match spec.map(|spec| queue.lock().unwrap().submit_audited(spec, requester(request))) {
+                Ok(Ok(id)) => Reply::json(202, &JsonValue::object().with("id", id)),
+                Ok(Err(e)) => Reply::error(500, &e.to_string()),
+                Err(e) => Reply::error(422, &e),
+            }
+        }
+        (_, ["jobs", ..], Some(Err(_))) => Reply::error(404, "Not found"),
+        ("GET", ["jobs", _], Some(Ok(id))) => match queue.lock().unwrap().job(id) {
+            Some(job) => Reply::json(200, &job.to_json()),
+            None => Reply::error(404, "No such job"),
+        },
+        ("DELETE", ["jobs", _], Some(Ok(id))) => {
+            let mut queue = queue.lock().unwrap();
+            match queue.job(id) {
+                None => return Reply::error(404, "No such job"),
+                Some(job) if job.state.is_finished() => {
+                    return Reply::json(200, &JsonValue::object().with("id", id).with("state", job.state.name()));
+                }
+                Some(_) => {}
+            }
+            // The cancellation is recorded first, so none happens unrecorded
+            if let Err(e) = queue.audit("job_cancelled", id, requester(request)) {
+                error!("Failed to record the cancellation of job {} in the audit log: {}", id, e);
+                return Reply::error(500, "The cancellation could not be recorded in the audit log");
+            }
+            match queue.cancel(id) {
+                Some(state) => Reply::json(200, &JsonValue::object().with("id", id).with("state", state.name())),
+                None => Reply::error(404, "No such job"),
+            }
+        }
+        ("GET", ["jobs", _, file], Some(Ok(id))) if *file == "results" || *file == "output" => {
+            let queue = queue.lock().unwrap();
+            let (state, path) = match (queue.job(id), *file) {
+                (None, _) => return Reply::error(404, "No such job"),
+                (Some(job), "results") => (job.state, queue.results_path(id)),
+                (Some(job), _) => (job.state, queue.output_path(id)),
+            };
+            if *file == "results" && state != JobState::Done {
+                return Reply::error(409, &format!("The job is {}, it has no results", state.name()));
+            }
+            let content_type = if *file == "results" { "application/json" } else { "text/plain" };
+            match fs::read(&path) {
+                Ok(body) => Reply { status: 200, content_type: content_type.to_string(), body },
+                Err(e) => Reply::error(404, &format!("The job wrote no {}: {}", file, e)),
+            }
+        }
+        (_, ["health"], _) | (_, ["jobs"], _) | (_, ["jobs", _], _) => Reply::error(405, "Method not allowed"),
+        _ => Reply::error(404, "Not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(args: &[&str]) -> Result<JobSpec, Box<dyn Error>> {
+        JobSpec::from_json(&JsonValue::object().with("args", args.iter().map(|a| a.to_string()).collect::<Vec<String>>()))
+    }
+
+    #[test]
+    fn jobs_only_take_analysis_flags() {
+        assert!(spec(&["analyze", "links.csv", "--samples", "100", "--antithetic", "--seed", "-1"]).is_ok());
+        assert!(spec(&["analyze", "--input", "links.csv", "--format", "json"]).is_ok());
+        for refused in [
+            &["analyze", "--input", "links.csv", "--on-complete", "touch /tmp/x"][..],
+            &["analyze", "--input", "links.csv", "--results", "/etc/passwd"],
+            &["analyze", "--input", "links.csv", "--svg", "graph.svg"],
+            &["analyze", "--input", "links.csv", "--config", "other.toml"],
+            &["analyze", "--samples", "--on-failure", "rm -rf /"],
+            &["analyze", "--input", "links.csv", "extra"],
+            &["serve", "--input", "links.csv"],
+        ] {
+            assert!(spec(refused).is_err(), "{:?} was accepted", refused);
+        }
+    }
+
+    #[test]
+    fn results_are_written_to_the_job_directory() {
+        let job = Job {
+            id: 1,
+            spec: spec(&["analyze", "--input", "links.csv"]).unwrap(),
+            state: JobState::Queued,
+            submitted: String::new(),
+            started: None,
+            finished: None,
+            exit_code: None,
+            error: None,
+            child: None,
+        };
+        let args = job.process_args(Path::new("jobs/1"));
+        let flags: Vec<String> = std::iter::once(String::new()).chain(args.iter().cloned()).collect();
+        assert_eq!(arg_value(&flags, "--results").map(PathBuf::from), Some(Path::new("jobs/1").join(RESULTS_FILE)));
+        assert_eq!(arg_value(&flags, "--threads").map(|t| t.as_str()), Some("1"));
+    }
+}
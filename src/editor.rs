@@ -0,0 +1,215 @@
+//! Batched edits of a ['Graph'] that are applied together or not at all.
+//!
+//! The ['GraphEditor'] queues edits without touching the graph. ['GraphEditor::commit'] applies
+//! them in order and checks the edited graph:
+//!
+//! * every edit has to refer to nodes that exist when it is applied
+//! * the graph has to stay acyclic, as the roll up visits every node once
+//! * if the graph had a single start node and a single end node before the edits, it still has
+//!   to have a single one of each afterwards
+//!
+//! If an edit or a check fails, every applied edit is undone in reverse order and the graph is
+//! left exactly as it was. ['GraphEditor::rollback'] drops the queued edits, as does dropping
+//! the editor.
+
+use crate::errors::network::GraphEditError;
+use crate::network::{AttrValue, Graph, Node};
+use crate::validation::nodes_on_cycles;
+
+/// An edit queued by the ['GraphEditor']
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Adds a node, replacing the node with the same id and its attributes
+    AddNode { id: u32, name: String },
+    /// Removes a node together with its edges
+    RemoveNode(u32),
+    AddEdge(u32, u32),
+    RemoveEdge(u32, u32),
+    SetNodeAttr { id: u32, name: String, value: AttrValue },
+}
+
+/// Restores what an applied edit changed
+enum Undo {
+    /// The node before the edit, None if there was none, and whether it was static
+    Node(u32, Option<Node>, bool),
+    /// Whether the edge existed before the edit
+    Edge(u32, u32, bool),
+    Attr(u32, String, Option<AttrValue>),
+}
+
+/// Queues edits of a graph and applies them as one, see the module documentation
+pub struct GraphEditor<'a> {
+    graph: &'a mut Graph,
+    edits: Vec<Edit>,
+}
+
+impl<'a> GraphEditor<'a> {
+    pub fn new(graph: &'a mut Graph) -> GraphEditor<'a> {
+        GraphEditor { graph, edits: vec![] }
+    }
+
+    pub fn add_node(&mut self, id: u32, name: &str) -> &mut Self {
+        self.edit(Edit::AddNode { id, name: name.to_string() })
+    }
+
+    pub fn remove_node(&mut self, id: u32) -> &mut Self {
+        self.edit(Edit::RemoveNode(id))
+    }
+
+    pub fn add_edge(&mut self, from: u32, to: u32) -> &mut Self {
+        self.edit(Edit::AddEdge(from, to))
+    }
+
+    pub fn remove_edge(&mut self, from: u32, to: u32) -> &mut Self {
+        self.edit(Edit::RemoveEdge(from, to))
+    }
+
+    pub fn set_node_attr(&mut self, id: u32, name: &str, value: AttrValue) -> &mut Self {
+        self.edit(Edit::SetNodeAttr { id, name: name.to_string(), value })
+    }
+
+    /// Queues any edit
+    pub fn edit(&mut self, edit: Edit) -> &mut Self {
+        self.edits.push(edit);
+        self
+    }
+
+    /// The edits queued since the last commit or rollback
+    pub fn pending(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// The graph as it is, without the queued edits
+    pub fn graph(&self) -> &Graph {
+        self.graph
+    }
+
+    /// Drops the queued edits
+    pub fn rollback(&mut self) {
+        self.edits.clear();
+    }
+
+    /// Applies the queued edits and checks the graph. The queue is empty afterwards either way.
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['GraphEditError'] with every problem found if an edit or a check fails, the
+    /// graph is then unchanged
+    pub fn commit(&mut self) -> Result<(), GraphEditError> {
+        let edits = std::mem::take(&mut self.edits);
+        let l_map = self.graph.links_map();
+        let had_start = Graph::get_start_id(&l_map).is_ok();
+        let had_end = Graph::get_end_id(&l_map).is_ok();
+
+        let mut undo = vec![];
+        let mut errors = vec![];
+        for (index, edit) in edits.into_iter().enumerate() {
+            match self.apply(edit) {
+                Ok(mut applied) => undo.append(&mut applied),
+                Err(reason) => errors.push(format!("edit {}: {}", index, reason)),
+            }
+        }
+        if errors.is_empty() {
+            let l_map = self.graph.links_map();
+            if let Some(cycle_nodes) = nodes_on_cycles(&l_map) {
+                errors.push(format!("the graph would have a cycle through the nodes {:?}", cycle_nodes));
+            }
+            if had_start {
+                if let Err(e) = Graph::get_start_id(&l_map) {
+                    errors.push(e.to_string());
+                }
+            }
+            if had_end {
+                if let Err(e) = Graph::get_end_id(&l_map) {
+                    errors.push(e.to_string());
+                }
+            }
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        for undo in undo.into_iter().rev() {
+            self.restore(undo);
+        }
+        Err(GraphEditError { errors })
+    }
+
+    /// Applies an edit, returning how to undo it or the reason it can't be applied
+    fn apply(&mut self, edit: Edit) -> Result<Vec<Undo>, String> {
+        let graph = &mut *self.graph;
+        let unknown = |id: u32| format!("there is no node {}", id);
+        match edit {
+            Edit::AddNode { id, name } => {
+                let was_static = graph.static_nodes.contains(&id);
+                Ok(vec![Undo::Node(id, graph.add_node(name, id), was_static)])
+            }
+            Edit::RemoveNode(id) => {
+                if graph.get_node(&id).is_none() {
+                    return Err(unknown(id));
+                }
+                // The edges go first so they are restored after the node
+                let edges: Vec<(u32, u32)> = graph.get_edges().iter()
+                    .filter(|e| e.from == id || e.to == id)
+                    .map(|e| (e.from, e.to))
+                    .collect();
+                let mut undo: Vec<Undo> = edges.into_iter()
+                    .map(|(from, to)| Undo::Edge(from, to, graph.remove_edge(from, to)))
+                    .collect();
+                let was_static = graph.static_nodes.remove(&id);
+                undo.push(Undo::Node(id, graph.remove_node(&id), was_static));
+                Ok(undo)
+            }
+            Edit::AddEdge(from, to) => {
+                if let Some(id) = [from, to].into_iter().find(|id| graph.get_node(id).is_none()) {
+                    return Err(unknown(id));
+                }
+                if from == to {
+                    return Err(format!("the edge ({}, {}) would be a loop", from, to));
+                }
+                Ok(vec![Undo::Edge(from, to, !graph.add_edge(from, to))])
+            }
+            Edit::RemoveEdge(from, to) => match graph.remove_edge(from, to) {
+                true => Ok(vec![Undo::Edge(from, to, true)]),
+                false => Err(format!("there is no edge ({}, {})", from, to)),
+            },
+            Edit::SetNodeAttr { id, name, value } => {
+                if graph.get_node(&id).is_none() {
+                    return Err(unknown(id));
+                }
+                let previous = graph.set_node_attr(&id, &name, value);
+                Ok(vec![Undo::Attr(id, name, previous)])
+            }
+        }
+    }
+
+    fn restore(&mut self, undo: Undo) {
+        let graph = &mut *self.graph;
+        match undo {
+            Undo::Node(id, node, was_static) => {
+                graph.remove_node(&id);
+                if let Some(Node { name, id, attributes }) = node {
+                    graph.add_node(name, id);
+                    for (name, value) in attributes {
+                        graph.set_node_attr(&id, &name, value);
+                    }
+                }
+                match was_static {
+                    true => graph.static_nodes.insert(id),
+                    false => graph.static_nodes.remove(&id),
+                };
+            }
+            Undo::Edge(from, to, existed) => {
+                match existed {
+                    true => graph.add_edge(from, to),
+                    false => graph.remove_edge(from, to),
+                };
+            }
+            Undo::Attr(id, name, previous) => {
+                match previous {
+                    Some(value) => graph.set_node_attr(&id, &name, value),
+                    None => graph.remove_node_attr(&id, &name),
+                };
+            }
+        }
+    }
+}
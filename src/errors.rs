@@ -46,6 +46,22 @@ pub mod input {
         }
     }
 
+    pub struct UnknownNodeKeyError {
+        pub cell_pos: (usize, usize),
+        pub key: String,
+    }
+    impl Error for UnknownNodeKeyError {}
+    impl Debug for UnknownNodeKeyError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The cell at ({}, {}), with value: {}, is neither a node id nor the key of a node", self.cell_pos.0, self.cell_pos.1, self.key)
+        }
+    }
+    impl Display for UnknownNodeKeyError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The cell at ({}, {}), with value: {}, is neither a node id nor the key of a node", self.cell_pos.0, self.cell_pos.1, self.key)
+        }
+    }
+
     pub struct CreateError<T>
         where T: Debug{
         pub task: String,
@@ -75,14 +91,362 @@ pub mod input {
                    , self.task, self.get_string_error(), self.input)
         }
     }
+
+    pub struct ColumnNotFoundError {
+        pub column: String,
+        pub headers: Vec<String>,
+    }
+    impl Error for ColumnNotFoundError {}
+    impl Debug for ColumnNotFoundError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "No column is called '{}'. The headers are: {:?}", self.column, self.headers)
+        }
+    }
+    impl Display for ColumnNotFoundError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "No column is called '{}'. The headers are: {:?}", self.column, self.headers)
+        }
+    }
+
+    pub struct NodeStateError {
+        pub cell_pos: (usize, usize),
+        pub cell_val: String,
+    }
+    impl Error for NodeStateError {}
+    impl Debug for NodeStateError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The cell at ({}, {}), with value: {}, should be 'on' or 'off'", self.cell_pos.0, self.cell_pos.1, self.cell_val)
+        }
+    }
+    impl Display for NodeStateError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The cell at ({}, {}), with value: {}, should be 'on' or 'off'", self.cell_pos.0, self.cell_pos.1, self.cell_val)
+        }
+    }
+
+    pub struct ModelError {
+        pub reason: String,
+    }
+    impl Error for ModelError {}
+    impl Debug for ModelError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The fault tree model can't be imported: {}", self.reason)
+        }
+    }
+    impl Display for ModelError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The fault tree model can't be imported: {}", self.reason)
+        }
+    }
+
+    pub struct SnapshotError {
+        pub path: String,
+        pub reason: String,
+    }
+    impl Error for SnapshotError {}
+    impl Debug for SnapshotError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The snapshot {} can't be loaded: {}", self.path, self.reason)
+        }
+    }
+    impl Display for SnapshotError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The snapshot {} can't be loaded: {}", self.path, self.reason)
+        }
+    }
+
+    pub struct RecordingError {
+        pub path: String,
+        pub reason: String,
+    }
+    impl Error for RecordingError {}
+    impl Debug for RecordingError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The state recording {} can't be replayed: {}", self.path, self.reason)
+        }
+    }
+    impl Display for RecordingError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The state recording {} can't be replayed: {}", self.path, self.reason)
+        }
+    }
+
+    pub struct SampleLogError {
+        pub path: String,
+        pub reason: String,
+    }
+    impl Error for SampleLogError {}
+    impl Debug for SampleLogError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The sample log {} can't be read: {}", self.path, self.reason)
+        }
+    }
+    impl Display for SampleLogError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The sample log {} can't be read: {}", self.path, self.reason)
+        }
+    }
+
+    pub struct AdjacencyMatrixError {
+        pub path: String,
+        pub reason: String,
+    }
+    impl Error for AdjacencyMatrixError {}
+    impl Debug for AdjacencyMatrixError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The adjacency matrix {} can't be read: {}", self.path, self.reason)
+        }
+    }
+    impl Display for AdjacencyMatrixError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The adjacency matrix {} can't be read: {}", self.path, self.reason)
+        }
+    }
+
+    pub struct LifetimeError {
+        pub id: u32,
+        pub reason: String,
+    }
+    impl Error for LifetimeError {}
+    impl Debug for LifetimeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The lifetime of node {} is invalid: {}", self.id, self.reason)
+        }
+    }
+    impl Display for LifetimeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The lifetime of node {} is invalid: {}", self.id, self.reason)
+        }
+    }
+
+    pub struct UnknownFormatError {
+        pub format: String,
+        pub known: Vec<String>,
+    }
+    impl Error for UnknownFormatError {}
+    impl Debug for UnknownFormatError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown graph format '{}'. The formats are: {:?}", self.format, self.known)
+        }
+    }
+    impl Display for UnknownFormatError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown graph format '{}'. The formats are: {:?}", self.format, self.known)
+        }
+    }
+
+    pub struct UnknownDirectionError {
+        pub direction: String,
+    }
+    impl Error for UnknownDirectionError {}
+    impl Debug for UnknownDirectionError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown edge direction '{}'. The directions are: child-to-parent, parent-to-child, undirected", self.direction)
+        }
+    }
+    impl Display for UnknownDirectionError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown edge direction '{}'. The directions are: child-to-parent, parent-to-child, undirected", self.direction)
+        }
+    }
+
+    pub struct UnknownEncodingError {
+        pub encoding: String,
+    }
+    impl Error for UnknownEncodingError {}
+    impl Debug for UnknownEncodingError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown text encoding '{}'. The encodings are: utf-8, latin-1", self.encoding)
+        }
+    }
+    impl Display for UnknownEncodingError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown text encoding '{}'. The encodings are: utf-8, latin-1", self.encoding)
+        }
+    }
+
+    pub struct CsvCharacterError {
+        pub value: String,
+    }
+    impl Error for CsvCharacterError {}
+    impl Debug for CsvCharacterError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid csv character '{}'. Expected a single ascii character, tab, comma, semicolon or pipe", self.value)
+        }
+    }
+    impl Display for CsvCharacterError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid csv character '{}'. Expected a single ascii character, tab, comma, semicolon or pipe", self.value)
+        }
+    }
+
+    pub struct UnknownEdgePolicyError {
+        pub policy: String,
+    }
+    impl Error for UnknownEdgePolicyError {}
+    impl Debug for UnknownEdgePolicyError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown edge policy '{}'. The policies are: reject, drop, keep", self.policy)
+        }
+    }
+    impl Display for UnknownEdgePolicyError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown edge policy '{}'. The policies are: reject, drop, keep", self.policy)
+        }
+    }
+
+    pub struct DotParseError {
+        pub line: usize,
+        pub reason: String,
+    }
+    impl Error for DotParseError {}
+    impl Debug for DotParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid dot at line {}: {}", self.line, self.reason)
+        }
+    }
+    impl Display for DotParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid dot at line {}: {}", self.line, self.reason)
+        }
+    }
+
+    pub struct AdjacencyListError {
+        pub line: usize,
+        pub reason: String,
+    }
+    impl Error for AdjacencyListError {}
+    impl Debug for AdjacencyListError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid adjacency list at line {}: {}", self.line, self.reason)
+        }
+    }
+    impl Display for AdjacencyListError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid adjacency list at line {}: {}", self.line, self.reason)
+        }
+    }
+
+    pub struct AlphaFileError {
+        /// Path of the alpha file, None if there is none to read
+        pub path: Option<String>,
+        pub reason: String,
+    }
+    impl Error for AlphaFileError {}
+    impl Debug for AlphaFileError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            match &self.path {
+                Some(path) => write!(f, "The alpha file {} can't be read: {}", path, self.reason),
+                None => write!(f, "No alpha file can be read: {}", self.reason),
+            }
+        }
+    }
+    impl Display for AlphaFileError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            match &self.path {
+                Some(path) => write!(f, "The alpha file {} can't be read: {}", path, self.reason),
+                None => write!(f, "No alpha file can be read: {}", self.reason),
+            }
+        }
+    }
+
+    pub struct SheetNotFoundError {
+        pub sheet: String,
+        pub available: Vec<String>,
+    }
+    impl Error for SheetNotFoundError {}
+    impl Debug for SheetNotFoundError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The workbook has no sheet called '{}'. The sheets are: {:?}", self.sheet, self.available)
+        }
+    }
+
+    /// A cell or row of a worksheet with a reference outside of the sheet, such as 'A0'
+    pub struct CellReferenceError {
+        pub sheet: String,
+        pub reference: String,
+    }
+    impl Error for CellReferenceError {}
+    impl Debug for CellReferenceError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The sheet '{}' has a cell or row with the invalid reference '{}'", self.sheet, self.reference)
+        }
+    }
+    impl Display for CellReferenceError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The sheet '{}' has a cell or row with the invalid reference '{}'", self.sheet, self.reference)
+        }
+    }
+    /// A text node id that can't be interned because every numeric id after the largest one is
+    /// taken
+    pub struct IdOverflowError {
+        pub key: String,
+    }
+    impl Error for IdOverflowError {}
+    impl Debug for IdOverflowError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The node id '{}' can't be interned, every id up to {} is taken", self.key, u32::MAX)
+        }
+    }
+    impl Display for IdOverflowError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The node id '{}' can't be interned, every id up to {} is taken", self.key, u32::MAX)
+        }
+    }
+    impl Display for SheetNotFoundError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The workbook has no sheet called '{}'. The sheets are: {:?}", self.sheet, self.available)
+        }
+    }
+
+    /// Any error met while reading the input, so it can be told apart from the errors of the
+    /// analysis. Shows the message of the error it wraps.
+    pub struct InputError {
+        pub source: Box<dyn Error>,
+    }
+    impl Error for InputError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(self.source.as_ref())
+        }
+    }
+    impl Debug for InputError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{:?}", self.source)
+        }
+    }
+    impl Display for InputError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.source)
+        }
+    }
+}
+
+pub mod validation {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct ValidationError {
+        pub errors: Vec<String>,
+    }
+    impl Error for ValidationError {}
+    impl Debug for ValidationError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The input has {} errors", self.errors.len())
+        }
+    }
+    impl Display for ValidationError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The input has {} errors", self.errors.len())
+        }
+    }
 }
 
 pub mod network {
     use std::{error::Error, fmt};
     use std::fmt::{Debug, Display, Formatter};
 
-    fn multiple_nodes_error(node_type: &str, node_dependent: &str, nodes: &Vec<u32>) -> String {
-        return if nodes.is_empty() {
+    fn multiple_nodes_error(node_type: &str, node_dependent: &str, nodes: &[u32]) -> String {
+        if nodes.is_empty() {
             format!("Couldn't determine a {} node. \
                 All nodes have {}", node_type, node_dependent)
         } else {
@@ -135,4 +499,567 @@ pub mod network {
             write!(f, "The start node with id: {} does not connect to the end node with id: {}", self.start_id, self.end_id)
         }
     }
-}
\ No newline at end of file
+
+    pub struct UnknownNodesError {
+        pub ids: Vec<u32>,
+    }
+    impl Error for UnknownNodesError {}
+    impl Debug for UnknownNodesError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The graph has no nodes with the ids: {:?}", self.ids)
+        }
+    }
+    impl Display for UnknownNodesError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The graph has no nodes with the ids: {:?}", self.ids)
+        }
+    }
+
+    /// A node given on the command line that is neither the id nor the key of a node of the graph
+    pub struct UnknownNodeError {
+        pub node: String,
+    }
+    impl Error for UnknownNodeError {}
+    impl Debug for UnknownNodeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown node '{}', the graph has no node with that id or key", self.node)
+        }
+    }
+    impl Display for UnknownNodeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Unknown node '{}', the graph has no node with that id or key", self.node)
+        }
+    }
+
+    pub struct GraphEditError {
+        pub errors: Vec<String>,
+    }
+    impl Error for GraphEditError {}
+    impl Debug for GraphEditError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The edits of the graph were rolled back: {}", self.errors.join("; "))
+        }
+    }
+    impl Display for GraphEditError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The edits of the graph were rolled back: {}", self.errors.join("; "))
+        }
+    }
+}
+pub mod json {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct JsonParseError {
+        pub pos: usize,
+        pub reason: String,
+    }
+    impl Error for JsonParseError {}
+    impl Debug for JsonParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid json at position {}: {}", self.pos, self.reason)
+        }
+    }
+    impl Display for JsonParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid json at position {}: {}", self.pos, self.reason)
+        }
+    }
+
+    pub struct JsonFieldError {
+        pub field: String,
+        pub expected: String,
+    }
+    impl Error for JsonFieldError {}
+    impl Debug for JsonFieldError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The json field '{}' is missing or is not {}", self.field, self.expected)
+        }
+    }
+    impl Display for JsonFieldError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The json field '{}' is missing or is not {}", self.field, self.expected)
+        }
+    }
+
+    pub struct ResultsVersionError {
+        /// Format and version given by the file
+        pub found: String,
+        /// Major version of the format this build reads
+        pub supported: u64,
+    }
+    impl Error for ResultsVersionError {}
+    impl Debug for ResultsVersionError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The results are stored as {}, this build reads results up to version {}.x", self.found, self.supported)
+        }
+    }
+    impl Display for ResultsVersionError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The results are stored as {}, this build reads results up to version {}.x", self.found, self.supported)
+        }
+    }
+
+    pub struct JsonSerdeError {
+        pub reason: String,
+    }
+    impl Error for JsonSerdeError {}
+    impl Debug for JsonSerdeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not convert between json and the value: {}", self.reason)
+        }
+    }
+    impl Display for JsonSerdeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not convert between json and the value: {}", self.reason)
+        }
+    }
+}
+
+pub mod http {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct UrlError {
+        pub url: String,
+    }
+    impl Error for UrlError {}
+    impl Debug for UrlError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The url '{}' is not a supported http url", self.url)
+        }
+    }
+    impl Display for UrlError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The url '{}' is not a supported http url", self.url)
+        }
+    }
+
+    pub struct ResponseError {
+        pub status: u16,
+        pub body: String,
+    }
+    impl Error for ResponseError {}
+    impl Debug for ResponseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The server responded with status {}: {}", self.status, self.body)
+        }
+    }
+    impl Display for ResponseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The server responded with status {}: {}", self.status, self.body)
+        }
+    }
+
+    pub struct RequestTooLargeError {
+        /// Part of the request over the limit, 'head' or 'body'
+        pub part: String,
+        pub limit: usize,
+    }
+    impl Error for RequestTooLargeError {}
+    impl Debug for RequestTooLargeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The {} of the request is larger than {} bytes", self.part, self.limit)
+        }
+    }
+    impl Display for RequestTooLargeError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The {} of the request is larger than {} bytes", self.part, self.limit)
+        }
+    }
+
+    /// An object store refusing an 's3://' read, which is sent without a signature
+    pub struct UnsignedRequestError {
+        pub url: String,
+        pub status: u16,
+    }
+    impl Error for UnsignedRequestError {}
+    impl Debug for UnsignedRequestError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The object store refused '{}' with status {}. Requests aren't signed, so only buckets allowing anonymous reads can be read", self.url, self.status)
+        }
+    }
+    impl Display for UnsignedRequestError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The object store refused '{}' with status {}. Requests aren't signed, so only buckets allowing anonymous reads can be read", self.url, self.status)
+        }
+    }
+
+    pub struct TlsUnsupportedError {
+        pub url: String,
+    }
+    impl Error for TlsUnsupportedError {}
+    impl Debug for TlsUnsupportedError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Fetching '{}' needs TLS, which isn't supported, use a plain http url or mirror", self.url)
+        }
+    }
+    impl Display for TlsUnsupportedError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Fetching '{}' needs TLS, which isn't supported, use a plain http url or mirror", self.url)
+        }
+    }
+}
+
+pub mod neo4j {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct QueryError {
+        pub statement: String,
+        pub errors: Vec<String>,
+    }
+    impl Error for QueryError {}
+    impl Debug for QueryError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Neo4j rejected the statement '{}' with the errors: {:?}", self.statement, self.errors)
+        }
+    }
+    impl Display for QueryError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Neo4j rejected the statement '{}' with the errors: {:?}", self.statement, self.errors)
+        }
+    }
+}
+
+pub mod compression {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct InflateError {
+        pub reason: String,
+    }
+    impl Error for InflateError {}
+    impl Debug for InflateError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not decompress the deflate stream: {}", self.reason)
+        }
+    }
+    impl Display for InflateError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not decompress the deflate stream: {}", self.reason)
+        }
+    }
+
+    pub struct GzipError {
+        pub reason: String,
+    }
+    impl Error for GzipError {}
+    impl Debug for GzipError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not decompress the gzip stream: {}", self.reason)
+        }
+    }
+    impl Display for GzipError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not decompress the gzip stream: {}", self.reason)
+        }
+    }
+
+    pub struct ZstdError {
+        pub reason: String,
+    }
+    impl Error for ZstdError {}
+    impl Debug for ZstdError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not decompress the zstd stream: {}", self.reason)
+        }
+    }
+    impl Display for ZstdError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not decompress the zstd stream: {}", self.reason)
+        }
+    }
+
+    pub struct ZipError {
+        pub reason: String,
+    }
+    impl Error for ZipError {}
+    impl Debug for ZipError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not read the zip archive: {}", self.reason)
+        }
+    }
+    impl Display for ZipError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Could not read the zip archive: {}", self.reason)
+        }
+    }
+}
+
+pub mod xml {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct XmlParseError {
+        pub pos: usize,
+        pub reason: String,
+    }
+    impl Error for XmlParseError {}
+    impl Debug for XmlParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid xml at position {}: {}", self.pos, self.reason)
+        }
+    }
+    impl Display for XmlParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid xml at position {}: {}", self.pos, self.reason)
+        }
+    }
+}
+
+pub mod config {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct ConfigError {
+        pub path: String,
+        pub reason: String,
+    }
+    impl Error for ConfigError {}
+    impl Debug for ConfigError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid configuration '{}': {}", self.path, self.reason)
+        }
+    }
+    impl Display for ConfigError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid configuration '{}': {}", self.path, self.reason)
+        }
+    }
+}
+
+pub mod daemon {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    pub struct JobArgError {
+        /// Argument of the job that was refused
+        pub arg: String,
+        pub reason: String,
+    }
+    impl Error for JobArgError {}
+    impl Debug for JobArgError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The job argument '{}' is refused: {}", self.arg, self.reason)
+        }
+    }
+    impl Display for JobArgError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The job argument '{}' is refused: {}", self.arg, self.reason)
+        }
+    }
+}
+
+pub mod analysis {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    /// Error returned by every analysis
+    pub enum ThorError {
+        /// The analysis was stopped through its cancellation token
+        Cancelled,
+        /// The analysis could not be completed
+        Failed(Box<dyn Error>),
+    }
+    impl ThorError {
+        fn message(&self) -> String {
+            match self {
+                ThorError::Cancelled => "The analysis was cancelled".to_string(),
+                ThorError::Failed(e) => format!("The analysis failed: {}", e),
+            }
+        }
+    }
+    impl Error for ThorError {}
+    impl Debug for ThorError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+    impl Display for ThorError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+    impl From<Box<dyn Error>> for ThorError {
+        fn from(e: Box<dyn Error>) -> Self {
+            ThorError::Failed(e)
+        }
+    }
+    impl From<UnsupportedRuleError> for ThorError {
+        fn from(e: UnsupportedRuleError) -> Self {
+            ThorError::Failed(Box::new(e))
+        }
+    }
+    impl From<TooManyNodesError> for ThorError {
+        fn from(e: TooManyNodesError) -> Self {
+            ThorError::Failed(Box::new(e))
+        }
+    }
+    impl From<LimitExceededError> for ThorError {
+        fn from(e: LimitExceededError) -> Self {
+            ThorError::Failed(Box::new(e))
+        }
+    }
+
+    pub struct UnsupportedRuleError {
+        pub analysis: String,
+    }
+    impl Error for UnsupportedRuleError {}
+    impl Debug for UnsupportedRuleError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The {} analysis needs a roll up rule that is equivalent to a boolean gate", self.analysis)
+        }
+    }
+    impl Display for UnsupportedRuleError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The {} analysis needs a roll up rule that is equivalent to a boolean gate", self.analysis)
+        }
+    }
+
+    pub struct TooManyNodesError {
+        pub analysis: String,
+        pub nodes: usize,
+        pub max: usize,
+    }
+    impl Error for TooManyNodesError {}
+    impl Debug for TooManyNodesError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The {} analysis supports at most {} dynamic nodes, the graph has {}", self.analysis, self.max, self.nodes)
+        }
+    }
+    impl Display for TooManyNodesError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The {} analysis supports at most {} dynamic nodes, the graph has {}", self.analysis, self.max, self.nodes)
+        }
+    }
+
+    pub struct LimitExceededError {
+        /// Description of the limit, such as "wall time limit of 10s"
+        pub limit: String,
+    }
+    impl Error for LimitExceededError {}
+    impl Debug for LimitExceededError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The analysis reached its {}, raise the limit or let the analysis degrade instead", self.limit)
+        }
+    }
+    impl Display for LimitExceededError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "The analysis reached its {}, raise the limit or let the analysis degrade instead", self.limit)
+        }
+    }
+
+    /// The criticality of nodes moved away from a baseline by more than its tolerance
+    pub struct BaselineDeviationError {
+        pub tolerance: f64,
+        /// (id, baseline criticality, new criticality) of every deviating node, largest change
+        /// first. None if the node is missing from the baseline or the new results.
+        pub nodes: Vec<(u32, Option<f64>, Option<f64>)>,
+    }
+    impl BaselineDeviationError {
+        /// Nodes listed in the message, the context of a json error report holds all of them
+        const LISTED: usize = 10;
+
+        fn message(&self) -> String {
+            let format = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or("-".to_string());
+            let listed: Vec<String> = self.nodes.iter().take(Self::LISTED)
+                .map(|(id, before, after)| format!("{} ({} -> {})", id, format(*before), format(*after)))
+                .collect();
+            let more = match self.nodes.len() > Self::LISTED {
+                true => format!(" and {} more", self.nodes.len() - Self::LISTED),
+                false => String::new(),
+            };
+            format!("The criticality of {} nodes deviates from the baseline by more than {}: {}{}", self.nodes.len(), self.tolerance, listed.join(", "), more)
+        }
+    }
+    impl Error for BaselineDeviationError {}
+    impl Debug for BaselineDeviationError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+    impl Display for BaselineDeviationError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+
+    /// Nodes reached the critical band of the results, see ['crate::output::bands']
+    pub struct CriticalBandError {
+        pub band: String,
+        /// Lowest criticality of the band
+        pub cutoff: f64,
+        /// (id, criticality) of every node in the band, most critical first
+        pub nodes: Vec<(u32, f64)>,
+    }
+    impl CriticalBandError {
+        /// Nodes listed in the message, the context of a json error report holds all of them
+        const LISTED: usize = 10;
+
+        fn message(&self) -> String {
+            let listed: Vec<String> = self.nodes.iter().take(Self::LISTED)
+                .map(|(id, criticality)| format!("{} ({})", id, criticality))
+                .collect();
+            let more = match self.nodes.len() > Self::LISTED {
+                true => format!(" and {} more", self.nodes.len() - Self::LISTED),
+                false => String::new(),
+            };
+            format!("{} nodes are in the '{}' band, with a criticality of at least {}: {}{}", self.nodes.len(), self.band, self.cutoff, listed.join(", "), more)
+        }
+    }
+    impl Error for CriticalBandError {}
+    impl Debug for CriticalBandError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+    impl Display for CriticalBandError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+}
+
+pub mod filter {
+    use std::{error::Error, fmt};
+    use std::fmt::{Debug, Display, Formatter};
+
+    /// Error of a regular expression, see ['crate::pattern']
+    pub struct PatternError {
+        pub pattern: String,
+        /// Position of the character in the pattern
+        pub pos: usize,
+        pub reason: String,
+    }
+    impl Error for PatternError {}
+    impl Debug for PatternError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid pattern '{}' at position {}: {}", self.pattern, self.pos, self.reason)
+        }
+    }
+    impl Display for PatternError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid pattern '{}' at position {}: {}", self.pattern, self.pos, self.reason)
+        }
+    }
+
+    /// Error of a result filter, see ['crate::output::filter']
+    pub struct FilterError {
+        pub expression: String,
+        /// Position of the character in the expression
+        pub pos: usize,
+        pub reason: String,
+    }
+    impl Error for FilterError {}
+    impl Debug for FilterError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid filter '{}' at position {}: {}", self.expression, self.pos, self.reason)
+        }
+    }
+    impl Display for FilterError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid filter '{}' at position {}: {}", self.expression, self.pos, self.reason)
+        }
+    }
+}
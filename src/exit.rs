@@ -0,0 +1,257 @@
+//! Exit codes and error reports of the binary.
+//!
+//! Every error is sorted into a ['FailureKind'] with its own exit code, so a scheduler can tell a
+//! broken input from a failed or cancelled analysis, and a model check from results that moved
+//! away from their '--baseline' or reached the critical band with '--fail-on-critical'. With '--error-format json' the error is
+//! written to stderr as a single json object with its 'code' (see ['error_code']), 'kind',
+//! 'exit_code' and 'message', and a 'context' object with the details known for its type.
+
+use std::error::Error;
+use std::io;
+use std::process::ExitCode;
+use crate::errors::analysis::{BaselineDeviationError, CriticalBandError, LimitExceededError, ThorError, TooManyNodesError, UnsupportedRuleError};
+use crate::errors::compression::{GzipError, InflateError, ZipError, ZstdError};
+use crate::errors::config::ConfigError;
+use crate::errors::filter::FilterError;
+use crate::errors::http::{ResponseError, TlsUnsupportedError, UrlError};
+use crate::errors::input::{AdjacencyListError, AdjacencyMatrixError, CellNotFoundError, ColumnNotFoundError, CsvCharacterError, DotParseError, InputError, LifetimeError, ModelError, NodeStateError, RecordingError, SampleLogError, SheetNotFoundError, SnapshotError, UnknownDirectionError, UnknownEdgePolicyError, UnknownEncodingError, UnknownFormatError, UnknownNodeKeyError};
+use crate::errors::json::{JsonFieldError, JsonParseError, JsonSerdeError, ResultsVersionError};
+use crate::errors::neo4j::QueryError;
+use crate::errors::network::{EndNodeError, GraphEditError, NoEndConnectionError, StartNodeError, UnknownNodeError, UnknownNodesError};
+use crate::errors::validation::ValidationError;
+use crate::errors::xml::XmlParseError;
+use crate::json::JsonValue;
+use crate::logging::error_code;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// 'Error: ' followed by the message
+    #[default]
+    Text,
+    /// One json object, see ['error_json']
+    Json,
+}
+
+impl ErrorFormat {
+    /// The format given by '--error-format text|json'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format is unknown
+    pub fn from_args(args: &[String]) -> Result<ErrorFormat, String> {
+        let position = args.iter().position(|a| a == "--error-format");
+        match position.and_then(|i| args.get(i + 1)).map(|f| f.as_str()) {
+            None | Some("text") => Ok(ErrorFormat::Text),
+            Some("json") => Ok(ErrorFormat::Json),
+            Some(other) => Err(format!("Unknown error format '{}', expected text or json", other)),
+        }
+    }
+}
+
+/// What went wrong, from the point of view of whoever started the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Anything else, such as an unknown command or flag value
+    Other,
+    /// The input, the configuration or another file couldn't be read or parsed
+    Input,
+    /// The input was read but is not a valid model
+    Validation,
+    /// The analysis failed or reached one of its limits
+    Analysis,
+    /// The analysis was cancelled
+    Cancelled,
+    /// The analysis succeeded but its results deviate from the baseline they were checked against
+    Regression,
+    /// The analysis succeeded but nodes are in the critical band, see ['crate::output::bands']
+    Alert,
+}
+
+impl FailureKind {
+    /// The kind of the error 'e'
+    pub fn of(e: &(dyn Error + 'static)) -> FailureKind {
+        match e.downcast_ref::<ThorError>() {
+            Some(ThorError::Cancelled) => return FailureKind::Cancelled,
+            Some(ThorError::Failed(_)) => return FailureKind::Analysis,
+            None => {}
+        }
+        // Problems with the model found while reading it are still validation failures
+        if let Some(InputError { source }) = e.downcast_ref::<InputError>() {
+            return match FailureKind::of(source.as_ref()) {
+                FailureKind::Validation => FailureKind::Validation,
+                _ => FailureKind::Input,
+            };
+        }
+        match e {
+            e if e.is::<ValidationError>() || e.is::<StartNodeError>() || e.is::<EndNodeError>()
+                || e.is::<NoEndConnectionError>() || e.is::<UnknownNodesError>() || e.is::<UnknownNodeError>() || e.is::<GraphEditError>() => FailureKind::Validation,
+            e if e.is::<BaselineDeviationError>() => FailureKind::Regression,
+            e if e.is::<CriticalBandError>() => FailureKind::Alert,
+            e if e.is::<LimitExceededError>() || e.is::<TooManyNodesError>() || e.is::<UnsupportedRuleError>() => FailureKind::Analysis,
+            e if e.is::<io::Error>() || e.is::<csv::Error>() || e.is::<ConfigError>()
+                || e.is::<JsonParseError>() || e.is::<JsonFieldError>() || e.is::<JsonSerdeError>() || e.is::<ResultsVersionError>()
+                || e.is::<XmlParseError>() || e.is::<DotParseError>() || e.is::<AdjacencyListError>()
+                || e.is::<CellNotFoundError>() || e.is::<UnknownNodeKeyError>() || e.is::<NodeStateError>()
+                || e.is::<ColumnNotFoundError>() || e.is::<SheetNotFoundError>()
+                || e.is::<ModelError>() || e.is::<SnapshotError>() || e.is::<RecordingError>() || e.is::<SampleLogError>() || e.is::<AdjacencyMatrixError>() || e.is::<LifetimeError>()
+                || e.is::<UnknownFormatError>() || e.is::<UnknownDirectionError>() || e.is::<UnknownEncodingError>()
+                || e.is::<CsvCharacterError>() || e.is::<UnknownEdgePolicyError>()
+                || e.is::<InflateError>() || e.is::<GzipError>() || e.is::<ZstdError>() || e.is::<ZipError>()
+                || e.is::<UrlError>() || e.is::<ResponseError>() || e.is::<TlsUnsupportedError>()
+                || e.is::<QueryError>() => FailureKind::Input,
+            _ => FailureKind::Other,
+        }
+    }
+
+    /// Exit code of the binary when it fails with an error of this kind
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            FailureKind::Other => 1,
+            FailureKind::Input => 2,
+            FailureKind::Validation => 3,
+            FailureKind::Analysis => 4,
+            FailureKind::Cancelled => 5,
+            FailureKind::Regression => 6,
+            FailureKind::Alert => 7,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FailureKind::Other => "other",
+            FailureKind::Input => "input",
+            FailureKind::Validation => "validation",
+            FailureKind::Analysis => "analysis",
+            FailureKind::Cancelled => "cancelled",
+            FailureKind::Regression => "regression",
+            FailureKind::Alert => "alert",
+        }
+    }
+}
+
+/// The error 'e' of the 'command' as a json object
+pub fn error_json(e: &(dyn Error + 'static), command: &str) -> JsonValue {
+    let kind = FailureKind::of(e);
+    let mut context = context(e);
+    context.insert("command", command);
+    JsonValue::object()
+        .with("code", error_code(e))
+        .with("kind", kind.name())
+        .with("exit_code", kind.exit_code() as u32)
+        .with("message", e.to_string())
+        .with("context", context)
+}
+
+/// Writes the error 'e' of the 'command' to stderr in the 'format' and returns the exit code of
+/// its kind
+pub fn report_error(e: &(dyn Error + 'static), format: ErrorFormat, command: &str) -> ExitCode {
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {:?}", e),
+        ErrorFormat::Json => eprintln!("{}", error_json(e, command)),
+    }
+    ExitCode::from(FailureKind::of(e).exit_code())
+}
+
+/// The fields of the errors that carry more than their message
+fn context(e: &(dyn Error + 'static)) -> JsonValue {
+    if let Some(ThorError::Failed(inner)) = e.downcast_ref::<ThorError>() {
+        return context(inner.as_ref());
+    }
+    if let Some(InputError { source }) = e.downcast_ref::<InputError>() {
+        return context(source.as_ref());
+    }
+    let context = JsonValue::object();
+    if let Some(e) = e.downcast_ref::<ValidationError>() {
+        return context.with("errors", e.errors.clone());
+    }
+    if let Some(e) = e.downcast_ref::<UnknownNodesError>() {
+        return context.with("ids", e.ids.clone());
+    }
+    if let Some(e) = e.downcast_ref::<UnknownNodeError>() {
+        return context.with("node", e.node.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<StartNodeError>() {
+        return context.with("starts", e.starts.clone());
+    }
+    if let Some(e) = e.downcast_ref::<EndNodeError>() {
+        return context.with("ends", e.ends.clone());
+    }
+    if let Some(e) = e.downcast_ref::<NoEndConnectionError>() {
+        return context.with("start_id", e.start_id).with("end_id", e.end_id);
+    }
+    if let Some(e) = e.downcast_ref::<TooManyNodesError>() {
+        return context.with("analysis", e.analysis.as_str()).with("nodes", e.nodes).with("max", e.max);
+    }
+    if let Some(e) = e.downcast_ref::<UnsupportedRuleError>() {
+        return context.with("analysis", e.analysis.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<BaselineDeviationError>() {
+        let nodes: Vec<JsonValue> = e.nodes.iter()
+            .map(|(id, baseline, result)| JsonValue::object().with("id", *id).with("baseline", *baseline).with("result", *result))
+            .collect();
+        return context.with("tolerance", e.tolerance).with("nodes", nodes);
+    }
+    if let Some(e) = e.downcast_ref::<CriticalBandError>() {
+        let nodes: Vec<JsonValue> = e.nodes.iter()
+            .map(|(id, criticality)| JsonValue::object().with("id", *id).with("criticality", *criticality))
+            .collect();
+        return context.with("band", e.band.as_str()).with("cutoff", e.cutoff).with("nodes", nodes);
+    }
+    if let Some(e) = e.downcast_ref::<LimitExceededError>() {
+        return context.with("limit", e.limit.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<ConfigError>() {
+        return context.with("path", e.path.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<SnapshotError>() {
+        return context.with("path", e.path.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<RecordingError>() {
+        return context.with("path", e.path.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<SampleLogError>() {
+        return context.with("path", e.path.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<AdjacencyMatrixError>() {
+        return context.with("path", e.path.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<ColumnNotFoundError>() {
+        return context.with("column", e.column.as_str()).with("headers", e.headers.clone());
+    }
+    if let Some(e) = e.downcast_ref::<UnknownNodeKeyError>() {
+        return context.with("row", e.cell_pos.0).with("column", e.cell_pos.1).with("key", e.key.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<UnknownFormatError>() {
+        return context.with("format", e.format.as_str()).with("known", e.known.clone());
+    }
+    if let Some(e) = e.downcast_ref::<JsonParseError>() {
+        return context.with("position", e.pos);
+    }
+    if let Some(e) = e.downcast_ref::<XmlParseError>() {
+        return context.with("position", e.pos);
+    }
+    if let Some(e) = e.downcast_ref::<FilterError>() {
+        return context.with("expression", e.expression.as_str()).with("position", e.pos);
+    }
+    if let Some(e) = e.downcast_ref::<DotParseError>() {
+        return context.with("line", e.line);
+    }
+    if let Some(e) = e.downcast_ref::<AdjacencyListError>() {
+        return context.with("line", e.line);
+    }
+    if let Some(e) = e.downcast_ref::<ResponseError>() {
+        return context.with("status", e.status as u32);
+    }
+    if let Some(e) = e.downcast_ref::<UrlError>() {
+        return context.with("url", e.url.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<TlsUnsupportedError>() {
+        return context.with("url", e.url.as_str());
+    }
+    if let Some(e) = e.downcast_ref::<csv::Error>() {
+        if let Some(position) = e.position() {
+            return context.with("line", position.line());
+        }
+    }
+    context
+}
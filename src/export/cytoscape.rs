@@ -0,0 +1,121 @@
+//! Writing a graph as the elements json of Cytoscape.js, loaded in the browser by
+//! 'cy.json(document)' or by passing the document to 'cytoscape({ ... })'.
+//!
+//! The document holds the 'elements' with their 'nodes' and 'edges', a 'style' sheet and a
+//! 'preset' layout placing every node at its position in the ['crate::render::Layout'] of the heatmaps. Element
+//! ids are strings, as Cytoscape.js expects, and edges lead from the child to the parent. Nodes
+//! carry their 'label', the fill 'color' of the heatmap and, with criticality results, their
+//! ['CRITICALITY_KEY'], ['END_ON_KEY'] and ['END_OFF_KEY']. Edges carry their 'width' in the
+//! heatmap and their 'alpha' weight if they have one.
+//!
+//! Nodes get the classes 'static' or 'dynamic', 'source' if they have no children, 'sink' if
+//! they have no parents and 'critical' if their criticality is at least ['CRITICAL_SHARE'] of the
+//! highest one, so the viewer can select them with '.critical'.
+
+use std::error::Error;
+use crate::analyses::criticality::{CriticalityData, CriticalityResults};
+use crate::export::Export;
+use crate::export::networkx::{CRITICALITY_KEY, END_OFF_KEY, END_ON_KEY, decimal};
+use crate::json::JsonValue;
+use crate::network::{ALPHA_ATTR, Graph, Links, NodeValueMap};
+use crate::output::write_output;
+use crate::render::Heatmap;
+use crate::render::svg::hex;
+
+/// Share of the highest criticality from which a node gets the 'critical' class
+pub const CRITICAL_SHARE: f64 = 0.75;
+
+/// Writes the elements json of Cytoscape.js without results, nodes are all colored as unscored
+pub struct CytoscapeExport {}
+
+impl Export for CytoscapeExport {
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>> {
+        let heatmap = Heatmap::new(graph, NodeValueMap::new(), data.alpha());
+        write_output(path, to_cytoscape(&heatmap, None).to_pretty_string() + "\n")?;
+        Ok(())
+    }
+}
+
+/// The style sheet of the document, coloring and sizing the elements by their data
+fn style() -> JsonValue {
+    let rule = |selector: &str, style: JsonValue| JsonValue::object().with("selector", selector).with("style", style);
+    JsonValue::Array(vec![
+        rule("node", JsonValue::object()
+            .with("label", "data(label)")
+            .with("background-color", "data(color)")
+            .with("border-width", 1u32)
+            .with("border-color", "#333333")),
+        rule("node.static", JsonValue::object().with("shape", "round-rectangle")),
+        rule("node.critical", JsonValue::object().with("border-width", 3u32)),
+        rule("edge", JsonValue::object()
+            .with("width", "data(width)")
+            .with("line-color", "#999999")
+            .with("target-arrow-color", "#999999")
+            .with("target-arrow-shape", "triangle")
+            .with("curve-style", "bezier")),
+    ])
+}
+
+/// The Cytoscape.js document of the 'heatmap' with the criticality 'results' if given, see the
+/// module documentation. Nodes and edges are written in ascending order.
+pub fn to_cytoscape(heatmap: &Heatmap, results: Option<&CriticalityResults>) -> JsonValue {
+    let graph = heatmap.graph;
+    let l_map = graph.links_map();
+    let mut ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+    ids.sort_unstable();
+    let max = results.map(|r| r.nodes.values().fold(0.0, |m: f64, n| m.max(n.criticality))).unwrap_or(0.0);
+
+    let nodes: Vec<JsonValue> = ids.iter()
+        .filter_map(|id| graph.get_node(id))
+        .map(|node| {
+            let mut data = JsonValue::object()
+                .with("id", node.id.to_string())
+                .with("label", node.name.as_str())
+                .with("color", hex(heatmap.node_color(&node.id)));
+            let mut classes = vec![match graph.static_nodes.contains(&node.id) {
+                true => "static",
+                false => "dynamic",
+            }];
+            if l_map.children_of(&node.id).is_empty() {
+                classes.push("source");
+            }
+            if l_map.parents_of(&node.id).is_empty() {
+                classes.push("sink");
+            }
+            if let Some(result) = results.and_then(|r| r.nodes.get(&node.id)) {
+                data.insert(CRITICALITY_KEY, result.criticality);
+                data.insert(END_ON_KEY, result.mean_end_on);
+                data.insert(END_OFF_KEY, result.mean_end_off);
+                if max > 0.0 && result.criticality >= CRITICAL_SHARE * max {
+                    classes.push("critical");
+                }
+            }
+            let mut element = JsonValue::object().with("data", data).with("classes", classes.join(" "));
+            if let Some((x, y)) = heatmap.layout.positions.get(&node.id) {
+                element.insert("position", JsonValue::object().with("x", *x).with("y", *y));
+            }
+            element
+        })
+        .collect();
+
+    let edges: Vec<JsonValue> = heatmap.edges().into_iter()
+        .map(|(from, to)| {
+            let mut data = JsonValue::object()
+                .with("id", format!("{}-{}", from, to))
+                .with("source", from.to_string())
+                .with("target", to.to_string())
+                .with("width", heatmap.edge_width(from, to));
+            if let Some(alpha) = heatmap.weights.and_then(|w| w.get(&(from, to))) {
+                data.insert(ALPHA_ATTR, decimal(*alpha));
+            }
+            JsonValue::object().with("data", data)
+        })
+        .collect();
+
+    JsonValue::object()
+        .with("elements", JsonValue::object()
+            .with("nodes", JsonValue::Array(nodes))
+            .with("edges", JsonValue::Array(edges)))
+        .with("style", style())
+        .with("layout", JsonValue::object().with("name", "preset"))
+}
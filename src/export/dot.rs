@@ -0,0 +1,73 @@
+//! Writing a graph and its data in the DOT language of Graphviz.
+//!
+//! Every node is written with its name as the 'label', its off chance, a 'static' flag and its
+//! attributes. Edges carry their attribute maps, so the file can be read back with
+//! ['crate::input::dot::DotInput'] as well as rendered with 'dot -Tsvg'.
+
+use std::error::Error;
+use std::fmt::Write as _;
+use crate::analyses::criticality::CriticalityData;
+use crate::export::Export;
+use crate::output::write_output;
+use crate::network::{AttrValue, Graph};
+
+/// Attribute holding the name of a node
+pub const LABEL_ATTR: &str = "label";
+
+/// Writes the DOT format
+pub struct DotExport {}
+
+impl Export for DotExport {
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>> {
+        write_output(path, to_dot(graph, data))?;
+        Ok(())
+    }
+}
+
+/// Quotes a string so it can be used as a DOT id
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn dot_value(value: &AttrValue) -> String {
+    match value {
+        AttrValue::Text(t) => quote(t),
+        other => other.to_string(),
+    }
+}
+
+/// The DOT digraph of the 'graph' and its 'data', nodes and edges in ascending order
+pub fn to_dot(graph: &Graph, data: &CriticalityData) -> String {
+    let mut out = String::from("digraph thor {\n");
+    let mut ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+    ids.sort_unstable();
+    for node in ids.iter().filter_map(|id| graph.get_node(id)) {
+        let mut attributes = vec![format!("{}={}", LABEL_ATTR, quote(&node.name))];
+        if let Some(off_chance) = data.off_chances.get(&node.id) {
+            attributes.push(format!("off_chance={}", off_chance));
+        }
+        if graph.static_nodes.contains(&node.id) {
+            attributes.push("static=true".to_string());
+        }
+        for (name, value) in node.attributes.iter() {
+            attributes.push(format!("{}={}", quote(name), dot_value(value)));
+        }
+        writeln!(out, "  {} [{}];", node.id, attributes.join(", ")).unwrap();
+    }
+
+    let mut names: Vec<&String> = data.edge_attributes.keys().collect();
+    names.sort();
+    let mut edges: Vec<(u32, u32)> = graph.get_edges().iter().map(|e| (e.from, e.to)).collect();
+    edges.sort_unstable();
+    for (from, to) in edges {
+        let attributes: Vec<String> = names.iter()
+            .filter_map(|name| data.edge_attributes[*name].get(&(from, to)).map(|v| format!("{}={}", quote(name), v)))
+            .collect();
+        match attributes.is_empty() {
+            true => writeln!(out, "  {} -> {};", from, to).unwrap(),
+            false => writeln!(out, "  {} -> {} [{}];", from, to, attributes.join(", ")).unwrap(),
+        }
+    }
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,116 @@
+//! Writing a graph and its data as GraphML, which is read by most graph tools.
+//!
+//! The name, off chance and static flag of every node are written as the node data 'name',
+//! 'off_chance' and 'static'. Node attributes and edge attribute maps are written as data with
+//! their own name, see ['crate::input::graphml::GraphMlInput'] for reading the files back.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use crate::analyses::criticality::CriticalityData;
+use crate::export::Export;
+use crate::output::write_output;
+use crate::network::{AttrValue, Graph};
+use crate::xml::escape;
+
+/// Node data holding the name of the node
+pub const NAME_KEY: &str = "name";
+/// Node data holding the off chance of the node
+pub const OFF_CHANCE_KEY: &str = "off_chance";
+/// Node data set to true for static nodes
+pub const STATIC_KEY: &str = "static";
+
+/// Writes the GraphML format
+pub struct GraphMlExport {}
+
+impl Export for GraphMlExport {
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>> {
+        write_output(path, to_graphml(graph, data))?;
+        Ok(())
+    }
+}
+
+/// GraphML type of a node attribute, 'string' if the nodes don't agree on a type
+fn attribute_type<'a>(values: impl Iterator<Item = &'a AttrValue>) -> &'static str {
+    let mut kind = None;
+    for value in values {
+        let value_kind = match value {
+            AttrValue::Number(_) => "double",
+            AttrValue::Bool(_) => "boolean",
+            AttrValue::Text(_) => "string",
+        };
+        match kind {
+            None => kind = Some(value_kind),
+            Some(k) if k != value_kind => return "string",
+            Some(_) => {}
+        }
+    }
+    kind.unwrap_or("string")
+}
+
+/// The GraphML document of the 'graph' and its 'data'. Nodes, edges and keys are written in
+/// ascending order.
+pub fn to_graphml(graph: &Graph, data: &CriticalityData) -> String {
+    let mut ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+    ids.sort_unstable();
+    let nodes: Vec<_> = ids.iter().filter_map(|id| graph.get_node(id)).collect();
+    let mut node_attributes: BTreeMap<&str, Vec<&AttrValue>> = BTreeMap::new();
+    for node in nodes.iter() {
+        for (name, value) in node.attributes.iter() {
+            node_attributes.entry(name.as_str()).or_default().push(value);
+        }
+    }
+    let mut edge_attributes: Vec<&String> = data.edge_attributes.keys().collect();
+    edge_attributes.sort();
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    let mut key = |id: String, domain: &str, name: &str, kind: &str| {
+        writeln!(out, "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>", escape(&id), domain, escape(name), kind).unwrap();
+    };
+    key(NAME_KEY.to_string(), "node", NAME_KEY, "string");
+    key(OFF_CHANCE_KEY.to_string(), "node", OFF_CHANCE_KEY, "double");
+    key(STATIC_KEY.to_string(), "node", STATIC_KEY, "boolean");
+    for (name, values) in node_attributes.iter() {
+        key(format!("node_{}", name), "node", name, attribute_type(values.iter().copied()));
+    }
+    for name in edge_attributes.iter() {
+        key(format!("edge_{}", name), "edge", name, "double");
+    }
+
+    out.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+    for node in nodes.iter() {
+        writeln!(out, "    <node id=\"{}\">", node.id).unwrap();
+        writeln!(out, "      <data key=\"{}\">{}</data>", NAME_KEY, escape(&node.name)).unwrap();
+        if let Some(off_chance) = data.off_chances.get(&node.id) {
+            writeln!(out, "      <data key=\"{}\">{}</data>", OFF_CHANCE_KEY, off_chance).unwrap();
+        }
+        if graph.static_nodes.contains(&node.id) {
+            writeln!(out, "      <data key=\"{}\">true</data>", STATIC_KEY).unwrap();
+        }
+        for (name, value) in node.attributes.iter() {
+            writeln!(out, "      <data key=\"node_{}\">{}</data>", escape(name), escape(&value.to_string())).unwrap();
+        }
+        out.push_str("    </node>\n");
+    }
+    let mut edges: Vec<(u32, u32)> = graph.get_edges().iter().map(|e| (e.from, e.to)).collect();
+    edges.sort_unstable();
+    for (from, to) in edges {
+        let values: Vec<(&String, f32)> = edge_attributes.iter()
+            .filter_map(|name| data.edge_attributes[*name].get(&(from, to)).map(|v| (*name, *v)))
+            .collect();
+        match values.is_empty() {
+            true => writeln!(out, "    <edge source=\"{}\" target=\"{}\"/>", from, to).unwrap(),
+            false => {
+                writeln!(out, "    <edge source=\"{}\" target=\"{}\">", from, to).unwrap();
+                for (name, value) in values {
+                    writeln!(out, "      <data key=\"edge_{}\">{}</data>", escape(name), value).unwrap();
+                }
+                out.push_str("    </edge>\n");
+            }
+        }
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
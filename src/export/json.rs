@@ -0,0 +1,21 @@
+//! Writing a graph and its data as a json ['GraphDocument'].
+
+use std::error::Error;
+use crate::analyses::criticality::CriticalityData;
+use crate::export::Export;
+use crate::output::write_output;
+use crate::network::Graph;
+use crate::serialization::GraphDocument;
+use crate::serialization::json::to_json;
+
+/// Writes the graph and its data as pretty printed json, see
+/// ['crate::input::json::JsonGraphInput'] for reading it back
+pub struct JsonExport {}
+
+impl Export for JsonExport {
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>> {
+        let document = GraphDocument { graph: graph.clone(), data: data.clone() };
+        write_output(path, to_json(&document)?.to_pretty_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! Module containing the structures used to write a graph and its data in another format.
+//!
+//! Every format that can be written implements the ['Export'] trait, the counterpart of the
+//! ['crate::input::Input'] trait. ['GraphFormat'] names the formats known to the program and
+//! picks the exporter or the format of a file from its extension.
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::UnknownFormatError;
+use crate::generator::write_links_csv;
+use crate::input::Compression;
+use crate::input::snapshot::{SNAPSHOT_EXTENSION, write_snapshot};
+use crate::network::Graph;
+
+pub mod cytoscape;
+pub mod dot;
+pub mod graphml;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod networkx;
+
+/// A trait which provides a method for writing a graph and its data to a file
+pub trait Export {
+    /// Writes the 'graph' and its 'data' to 'path'
+    ///
+    /// # Errors
+    ///
+    /// May return a ['Error'] if the file can't be written
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// Writes the headered links format, see ['write_links_csv']
+pub struct CsvExport {}
+impl Export for CsvExport {
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>> {
+        write_links_csv(path, graph, data)
+    }
+}
+
+/// Writes a binary snapshot, see ['write_snapshot']
+pub struct SnapshotExport {}
+impl Export for SnapshotExport {
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>> {
+        write_snapshot(path, graph, data)
+    }
+}
+
+/// File formats a graph can be read from or written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Links table, headered or in the standard layout
+    Csv,
+    Snapshot,
+    Xlsx,
+    /// Open-PSA fault tree
+    OpenPsa,
+    GraphMl,
+    Json,
+    Dot,
+    /// Square adjacency matrix csv, only read when asked for as it shares the extension of the
+    /// links table
+    Matrix,
+    /// Hand-written 'node: child child ...' lines
+    AdjacencyList,
+    /// Node-link json of NetworkX, only read when asked for as it shares the extension of the
+    /// json graph document
+    NodeLink,
+    /// Elements json of Cytoscape.js, only written
+    Cytoscape,
+}
+
+/// Every format with its name and the file extensions it is recognised by
+const FORMATS: [(GraphFormat, &str, &[&str]); 11] = [
+    (GraphFormat::Csv, "csv", &[".csv"]),
+    (GraphFormat::Snapshot, "snapshot", &[SNAPSHOT_EXTENSION]),
+    (GraphFormat::Xlsx, "xlsx", &[".xlsx"]),
+    (GraphFormat::OpenPsa, "openpsa", &[".xml"]),
+    (GraphFormat::GraphMl, "graphml", &[".graphml"]),
+    (GraphFormat::Json, "json", &[".json"]),
+    (GraphFormat::Dot, "dot", &[".dot", ".gv"]),
+    (GraphFormat::Matrix, "matrix", &[]),
+    (GraphFormat::AdjacencyList, "adjacency", &[".adj"]),
+    (GraphFormat::NodeLink, "node-link", &[]),
+    (GraphFormat::Cytoscape, "cytoscape", &[]),
+];
+
+impl GraphFormat {
+    /// The format of a file with the extension of 'path', if any format uses it
+    pub fn from_path(path: &str) -> Option<GraphFormat> {
+        let path = Compression::strip_extension(path).to_ascii_lowercase();
+        FORMATS.iter()
+            .find(|(_, _, extensions)| extensions.iter().any(|e| path.ends_with(e)))
+            .map(|(format, _, _)| *format)
+    }
+
+    pub fn name(&self) -> &'static str {
+        FORMATS.iter().find(|(f, _, _)| f == self).map(|(_, name, _)| *name).unwrap()
+    }
+
+    /// The exporter writing the format, None if the format can only be read
+    pub fn exporter(&self) -> Option<Box<dyn Export>> {
+        match self {
+            GraphFormat::Csv => Some(Box::new(CsvExport {})),
+            GraphFormat::Snapshot => Some(Box::new(SnapshotExport {})),
+            GraphFormat::GraphMl => Some(Box::new(graphml::GraphMlExport {})),
+            #[cfg(feature = "serde")]
+            GraphFormat::Json => Some(Box::new(json::JsonExport {})),
+            GraphFormat::Dot => Some(Box::new(dot::DotExport {})),
+            GraphFormat::NodeLink => Some(Box::new(networkx::NodeLinkExport {})),
+            GraphFormat::Cytoscape => Some(Box::new(cytoscape::CytoscapeExport {})),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = UnknownFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        FORMATS.iter()
+            .find(|(_, n, _)| *n == name)
+            .map(|(format, _, _)| *format)
+            .ok_or_else(|| UnknownFormatError {
+                format: s.to_string(),
+                known: FORMATS.iter().map(|(_, n, _)| n.to_string()).collect(),
+            })
+    }
+}
+
+impl Display for GraphFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,110 @@
+//! Writing a graph as the node-link json of NetworkX, read in Python by
+//! 'networkx.node_link_graph'.
+//!
+//! The document is a directed graph with a 'nodes' and a 'links' array. Every node has its 'id',
+//! the key of the node if it has a ['KEY_ATTR'], its 'name', its 'off_chance' and 'static' flag
+//! if it has them and its attributes. Every link has its 'source' and 'target', the child and the
+//! parent, and the value of every edge attribute map that holds the edge. With criticality
+//! results the nodes also get the result attributes ['CRITICALITY_KEY'], ['END_ON_KEY'] and
+//! ['END_OFF_KEY'], see ['crate::output::networkx::NodeLinkOutput'].
+
+use std::error::Error;
+use crate::analyses::criticality::{CriticalityData, CriticalityResults};
+use crate::export::Export;
+use crate::export::graphml::{NAME_KEY, OFF_CHANCE_KEY, STATIC_KEY};
+use crate::json::JsonValue;
+use crate::network::{AttrValue, Graph, KEY_ATTR};
+use crate::output::write_output;
+
+/// Node attribute holding the criticality of a node
+pub const CRITICALITY_KEY: &str = "criticality";
+/// Node attribute holding the mean end operability while the node is visible
+pub const END_ON_KEY: &str = "mean_end_on";
+/// Node attribute holding the mean end operability while the node is not visible
+pub const END_OFF_KEY: &str = "mean_end_off";
+
+/// The f64 with the shortest decimal of an f32 value, so 0.3 is written as 0.3 rather than as the
+/// f64 of its binary value
+pub(crate) fn decimal(value: f32) -> f64 {
+    value.to_string().parse::<f64>().unwrap_or(value as f64)
+}
+
+/// Writes the node-link json of NetworkX, see ['crate::input::networkx::NodeLinkInput'] for
+/// reading it back
+pub struct NodeLinkExport {}
+
+impl Export for NodeLinkExport {
+    fn export(&self, graph: &Graph, data: &CriticalityData, path: &str) -> Result<(), Box<dyn Error>> {
+        write_output(path, to_node_link(graph, Some(data), None).to_pretty_string())?;
+        Ok(())
+    }
+}
+
+/// The node-link document of the 'graph' with its 'data' and criticality 'results' if given.
+/// Nodes and links are written in ascending order.
+pub fn to_node_link(graph: &Graph, data: Option<&CriticalityData>, results: Option<&CriticalityResults>) -> JsonValue {
+    let mut ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+    ids.sort_unstable();
+    let node_id = |id: &u32| -> JsonValue {
+        match graph.get_node_attr(id, KEY_ATTR).and_then(|k| k.as_str()) {
+            Some(key) => key.into(),
+            None => (*id).into(),
+        }
+    };
+
+    let nodes: Vec<JsonValue> = ids.iter()
+        .filter_map(|id| graph.get_node(id))
+        .map(|node| {
+            let mut value = JsonValue::object()
+                .with("id", node_id(&node.id))
+                .with(NAME_KEY, node.name.as_str());
+            if let Some(off_chance) = data.and_then(|d| d.off_chances.get(&node.id)) {
+                value.insert(OFF_CHANCE_KEY, decimal(*off_chance));
+            }
+            if graph.static_nodes.contains(&node.id) {
+                value.insert(STATIC_KEY, true);
+            }
+            for (name, attribute) in node.attributes.iter().filter(|(name, _)| name.as_str() != KEY_ATTR) {
+                value.insert(name.as_str(), match attribute {
+                    AttrValue::Number(n) => JsonValue::from(*n),
+                    AttrValue::Bool(b) => JsonValue::from(*b),
+                    AttrValue::Text(t) => JsonValue::from(t.as_str()),
+                });
+            }
+            if let Some(result) = results.and_then(|r| r.nodes.get(&node.id)) {
+                value.insert(CRITICALITY_KEY, result.criticality);
+                value.insert(END_ON_KEY, result.mean_end_on);
+                value.insert(END_OFF_KEY, result.mean_end_off);
+            }
+            value
+        })
+        .collect();
+
+    let mut edges: Vec<(u32, u32)> = graph.get_edges().iter().map(|e| (e.from, e.to)).collect();
+    edges.sort_unstable();
+    let mut attribute_names: Vec<&String> = data.map(|d| d.edge_attributes.keys().collect()).unwrap_or_default();
+    attribute_names.sort();
+    let links: Vec<JsonValue> = edges.iter()
+        .map(|(from, to)| {
+            let mut link = JsonValue::object().with("source", node_id(from)).with("target", node_id(to));
+            for name in attribute_names.iter() {
+                if let Some(value) = data.and_then(|d| d.edge_attributes[*name].get(&(*from, *to))) {
+                    link.insert(name.as_str(), decimal(*value));
+                }
+            }
+            link
+        })
+        .collect();
+
+    let mut graph_attributes = JsonValue::object();
+    if let Some(results) = results {
+        graph_attributes.insert("end_op_mean", results.end_op_mean);
+        graph_attributes.insert("row_count", results.row_count);
+    }
+    JsonValue::object()
+        .with("directed", true)
+        .with("multigraph", false)
+        .with("graph", graph_attributes)
+        .with("nodes", JsonValue::Array(nodes))
+        .with("links", JsonValue::Array(links))
+}
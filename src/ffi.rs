@@ -0,0 +1,310 @@
+//! C API for embedding the criticality analysis in other programs, declared in 'include/thor.h'.
+//!
+//! Built with the 'ffi' feature as a shared library, for example with
+//! 'cargo rustc --release --lib --features ffi --crate-type cdylib'. A caller creates a graph
+//! with ['thor_graph_new'], adds its nodes, edges and off chances, runs ['thor_criticality_run']
+//! and reads the results it returns, then releases both with their free functions.
+//!
+//! Every function that can fail returns 0 on success, otherwise the exit code of the
+//! ['FailureKind'] of the error, and keeps its message for ['thor_last_error'] on the calling
+//! thread. Panics are caught at the boundary and reported the same way. Ids are the 'u32' ids of
+//! ['crate::network::Graph'], the layout of the structs and the meaning of the codes only change
+//! together with ['THOR_API_VERSION'].
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::builder::{CriticalityBuilder, DEFAULT_SAMPLES};
+use crate::analyses::criticality::CriticalityResults;
+use crate::errors::network::UnknownNodesError;
+use crate::errors::validation::ValidationError;
+use crate::exit::FailureKind;
+use crate::network::{Graph, NodeValueMap};
+
+/// Version of the C API, raised with every incompatible change
+pub const THOR_API_VERSION: u32 = 1;
+
+/// Returned by the functions that succeeded
+pub const THOR_OK: i32 = 0;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A graph built through the C API
+pub struct ThorGraph {
+    graph: Graph,
+    off_chances: NodeValueMap<f32>,
+    start_id: Option<u32>,
+    end_id: Option<u32>,
+}
+
+/// The results of a criticality analysis, with the nodes in the order of their ids
+pub struct ThorResults {
+    results: CriticalityResults,
+    ids: Vec<u32>,
+}
+
+/// Criticality of a single node, see ['crate::analyses::criticality::NodeCritResult']
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThorNodeResult {
+    pub id: u32,
+    pub on_count: u64,
+    pub off_count: u64,
+    pub mean_end_on: f64,
+    pub mean_end_off: f64,
+    pub criticality: f64,
+}
+
+/// Runs 'f', storing its error or panic for ['thor_last_error'] and turning it into a code
+fn guard<F: FnOnce() -> Result<(), Box<dyn Error>>>(f: F) -> i32 {
+    let (code, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return THOR_OK,
+        Ok(Err(e)) => {
+            let message = match e.downcast_ref::<ValidationError>() {
+                Some(validation) => format!("{}: {}", e, validation.errors.join("; ")),
+                None => e.to_string(),
+            };
+            (FailureKind::of(e.as_ref()).exit_code(), message)
+        }
+        Err(panic) => {
+            let message = panic.downcast_ref::<&str>().map(|m| m.to_string())
+                .or(panic.downcast_ref::<String>().cloned())
+                .unwrap_or("unknown panic".to_string());
+            (FailureKind::Other.exit_code(), format!("The engine panicked: {}", message))
+        }
+    };
+    // Messages never hold a nul, but the error must not be lost if one does
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    code as i32
+}
+
+/// The object behind 'ptr', an error if it is null
+///
+/// # Safety
+///
+/// 'ptr' must be null or point to a live object created by this module
+unsafe fn get<'a, T>(ptr: *const T, name: &str) -> Result<&'a T, Box<dyn Error>> {
+    ptr.as_ref().ok_or(format!("The {} is null", name).into())
+}
+
+/// # Safety
+///
+/// 'ptr' must be null or point to a live object created by this module that isn't used elsewhere
+unsafe fn get_mut<'a, T>(ptr: *mut T, name: &str) -> Result<&'a mut T, Box<dyn Error>> {
+    ptr.as_mut().ok_or(format!("The {} is null", name).into())
+}
+
+/// Version of the C API, to check against the header the caller was compiled with
+#[no_mangle]
+pub extern "C" fn thor_api_version() -> u32 {
+    THOR_API_VERSION
+}
+
+/// Message of the latest error on the calling thread, null if there was none. The string stays
+/// valid until the next call on the thread fails.
+#[no_mangle]
+pub extern "C" fn thor_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map(|m| m.as_ptr()).unwrap_or(std::ptr::null()))
+}
+
+/// Creates an empty graph, released with ['thor_graph_free']
+#[no_mangle]
+pub extern "C" fn thor_graph_new() -> *mut ThorGraph {
+    Box::into_raw(Box::new(ThorGraph { graph: Graph::new(), off_chances: NodeValueMap::new(), start_id: None, end_id: None }))
+}
+
+/// Releases a graph, nothing happens if it is null
+///
+/// # Safety
+///
+/// 'graph' must be null or returned by ['thor_graph_new'] and not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn thor_graph_free(graph: *mut ThorGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+/// Adds the node 'id', replacing a node with the same id. 'name' is a nul terminated utf-8 string, the
+/// id is used as the name if it is null.
+///
+/// # Safety
+///
+/// 'graph' must come from ['thor_graph_new'] and 'name' must be null or a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn thor_graph_add_node(graph: *mut ThorGraph, id: u32, name: *const c_char) -> i32 {
+    guard(|| {
+        let graph = get_mut(graph, "graph")?;
+        let name = match name.is_null() {
+            true => id.to_string(),
+            false => CStr::from_ptr(name).to_str()?.to_string(),
+        };
+        graph.graph.add_node(name, id);
+        Ok(())
+    })
+}
+
+/// Adds an edge from the node 'from' to the node 'to', both must have been added. The value of
+/// 'from' is rolled up into 'to'.
+///
+/// # Safety
+///
+/// 'graph' must come from ['thor_graph_new']
+#[no_mangle]
+pub unsafe extern "C" fn thor_graph_add_edge(graph: *mut ThorGraph, from: u32, to: u32) -> i32 {
+    guard(|| {
+        let graph = get_mut(graph, "graph")?;
+        let unknown: Vec<u32> = [from, to].into_iter().filter(|id| graph.graph.get_node(id).is_none()).collect();
+        if !unknown.is_empty() {
+            return Err(UnknownNodesError { ids: unknown }.into());
+        }
+        graph.graph.add_edge(from, to);
+        Ok(())
+    })
+}
+
+/// Sets the chance between 0 and 1 that the node 'id' is off in a sampled state
+///
+/// # Safety
+///
+/// 'graph' must come from ['thor_graph_new']
+#[no_mangle]
+pub unsafe extern "C" fn thor_graph_set_off_chance(graph: *mut ThorGraph, id: u32, off_chance: f32) -> i32 {
+    guard(|| {
+        let graph = get_mut(graph, "graph")?;
+        if graph.graph.get_node(&id).is_none() {
+            return Err(UnknownNodesError { ids: vec![id] }.into());
+        }
+        if !(0.0..=1.0).contains(&off_chance) {
+            return Err(ValidationError { errors: vec![format!("The off chance of node {} must be between 0 and 1, got {}", id, off_chance)] }.into());
+        }
+        graph.off_chances.insert(id, off_chance);
+        Ok(())
+    })
+}
+
+/// Sets the start and end node of the analysis. Without them the only node without children and
+/// the only node without parents are used.
+///
+/// # Safety
+///
+/// 'graph' must come from ['thor_graph_new']
+#[no_mangle]
+pub unsafe extern "C" fn thor_graph_set_terminals(graph: *mut ThorGraph, start_id: u32, end_id: u32) -> i32 {
+    guard(|| {
+        let graph = get_mut(graph, "graph")?;
+        graph.start_id = Some(start_id);
+        graph.end_id = Some(end_id);
+        Ok(())
+    })
+}
+
+/// Samples 'samples' states of the graph on 'threads' threads and stores the results in
+/// 'results', to be released with ['thor_results_free']. Zero samples or threads use the
+/// defaults, ['DEFAULT_SAMPLES'] and one thread per cpu. The nodes are rolled up with the
+/// ['crate::roll_up::OrRule'].
+///
+/// # Safety
+///
+/// 'graph' must come from ['thor_graph_new'] and 'results' must point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn thor_criticality_run(graph: *const ThorGraph, samples: u64, threads: u32, results: *mut *mut ThorResults) -> i32 {
+    guard(|| {
+        let graph = get(graph, "graph")?;
+        let results = get_mut(results, "results pointer")?;
+        let mut builder = CriticalityBuilder::new(graph.graph.clone())
+            .off_chances(graph.off_chances.clone())
+            .samples(match samples {
+                0 => DEFAULT_SAMPLES,
+                samples => samples,
+            });
+        if let Some(start_id) = graph.start_id {
+            builder = builder.start_id(start_id);
+        }
+        if let Some(end_id) = graph.end_id {
+            builder = builder.end_id(end_id);
+        }
+        if threads > 0 {
+            builder = builder.threads(threads.min(u8::MAX as u32) as u8);
+        }
+        let mut criticality = builder.build()?;
+        // The results are only handed to the caller
+        criticality.outputs.clear();
+        let analysed = criticality.run(&AnalysisContext::default())?;
+        let ids = analysed.nodes.keys().copied().collect();
+        *results = Box::into_raw(Box::new(ThorResults { results: analysed, ids }));
+        Ok(())
+    })
+}
+
+/// Number of unique states that were rolled up, zero if 'results' is null
+///
+/// # Safety
+///
+/// 'results' must be null or come from ['thor_criticality_run']
+#[no_mangle]
+pub unsafe extern "C" fn thor_results_state_count(results: *const ThorResults) -> u64 {
+    results.as_ref().map(|r| r.results.row_count).unwrap_or(0)
+}
+
+/// Mean operability of the end node over all states, zero if 'results' is null
+///
+/// # Safety
+///
+/// 'results' must be null or come from ['thor_criticality_run']
+#[no_mangle]
+pub unsafe extern "C" fn thor_results_end_op_mean(results: *const ThorResults) -> f64 {
+    results.as_ref().map(|r| r.results.end_op_mean).unwrap_or(0.0)
+}
+
+/// Number of analysed nodes, zero if 'results' is null
+///
+/// # Safety
+///
+/// 'results' must be null or come from ['thor_criticality_run']
+#[no_mangle]
+pub unsafe extern "C" fn thor_results_node_count(results: *const ThorResults) -> usize {
+    results.as_ref().map(|r| r.ids.len()).unwrap_or(0)
+}
+
+/// Writes the results of the node at 'index', in the order of the node ids, to 'node'
+///
+/// # Safety
+///
+/// 'results' must come from ['thor_criticality_run'] and 'node' must point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn thor_results_node(results: *const ThorResults, index: usize, node: *mut ThorNodeResult) -> i32 {
+    guard(|| {
+        let results = get(results, "results")?;
+        let node = get_mut(node, "node")?;
+        let id = *results.ids.get(index)
+            .ok_or(format!("The node index {} is out of range, there are {} nodes", index, results.ids.len()))?;
+        let result = &results.results.nodes[&id];
+        *node = ThorNodeResult {
+            id,
+            on_count: result.on_count,
+            off_count: result.off_count,
+            mean_end_on: result.mean_end_on,
+            mean_end_off: result.mean_end_off,
+            criticality: result.criticality,
+        };
+        Ok(())
+    })
+}
+
+/// Releases results, nothing happens if they are null
+///
+/// # Safety
+///
+/// 'results' must be null or come from ['thor_criticality_run'] and not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn thor_results_free(results: *mut ThorResults) {
+    if !results.is_null() {
+        drop(Box::from_raw(results));
+    }
+}
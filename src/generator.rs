@@ -0,0 +1,187 @@
+//! Synthetic graphs for benchmarks and tests.
+//!
+//! Every model builds a DAG on the inner nodes 1..=n where edges only go from lower to higher
+//! ids. A start node 0 feeds every inner node without children and every inner node without
+//! parents feeds the end node n+1, so the graph has exactly one start and one end node and every
+//! node lies on a path between them. The same seed always gives the same graph.
+
+use std::error::Error;
+use std::collections::HashSet;
+use std::ops::Range;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::analyses::criticality::CriticalityData;
+use crate::network::{ALPHA_ATTR, EdgeValueMap, Graph};
+use crate::output::create_output;
+
+/// Shape of the generated graph
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphModel {
+    /// 'layers' layers of 'width' nodes, each node is linked to every node of the next layer
+    /// with the 'edge_chance'. Every node gets at least one parent in the next layer.
+    Layered { layers: usize, width: usize, edge_chance: f64 },
+    /// Erdős–Rényi DAG, every pair of inner nodes is linked with the 'edge_chance'
+    Random { nodes: usize, edge_chance: f64 },
+    /// Preferential attachment, every new node links to 'edges_per_node' earlier nodes chosen
+    /// in proportion to their degree
+    ScaleFree { nodes: usize, edges_per_node: usize },
+}
+
+/// Generates graphs of a ['GraphModel'] with random off chances and alpha weights
+#[derive(Debug, Clone)]
+pub struct GraphGenerator {
+    pub model: GraphModel,
+    pub seed: u64,
+    /// Range the off chance of every inner node is drawn from
+    pub off_chances: Range<f32>,
+    /// Range the alpha weight of every edge is drawn from
+    pub alphas: Range<f32>,
+}
+
+impl GraphGenerator {
+    pub fn new(model: GraphModel, seed: u64) -> GraphGenerator {
+        GraphGenerator { model, seed, off_chances: 0.05..0.5, alphas: 0.1..1.0 }
+    }
+
+    /// Builds the graph and its criticality data
+    pub fn generate(&self) -> (Graph, CriticalityData) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (n, inner_edges) = match self.model {
+            GraphModel::Layered { layers, width, edge_chance } => (layers * width, layered(&mut rng, layers, width, edge_chance)),
+            GraphModel::Random { nodes, edge_chance } => (nodes, random_dag(&mut rng, nodes, edge_chance)),
+            GraphModel::ScaleFree { nodes, edges_per_node } => (nodes, scale_free(&mut rng, nodes, edges_per_node)),
+        };
+
+        let mut graph = Graph::new();
+        let end_id = n as u32 + 1;
+        graph.add_node("start".to_string(), 0);
+        graph.add_node("end".to_string(), end_id);
+        for id in 1..=n as u32 {
+            graph.add_node(format!("n{}", id), id);
+        }
+        let mut has_children: HashSet<u32> = HashSet::new();
+        let mut has_parents: HashSet<u32> = HashSet::new();
+        for (from, to) in inner_edges {
+            graph.add_edge(from, to);
+            has_parents.insert(from);
+            has_children.insert(to);
+        }
+        for id in 1..=n as u32 {
+            if !has_children.contains(&id) {
+                graph.add_edge(0, id);
+            }
+            if !has_parents.contains(&id) {
+                graph.add_edge(id, end_id);
+            }
+        }
+        if n == 0 {
+            graph.add_edge(0, end_id);
+        }
+
+        let mut data = CriticalityData::default();
+        // Sorted so the values drawn for each edge don't depend on the hash order of the edges
+        let mut edges: Vec<(u32, u32)> = graph.get_edges().iter().map(|e| (e.from, e.to)).collect();
+        edges.sort_unstable();
+        let alpha: EdgeValueMap<f32> = edges.into_iter().map(|edge| (edge, draw(&mut rng, &self.alphas))).collect();
+        data.add_edge_attribute(ALPHA_ATTR, alpha);
+        for id in 1..=n as u32 {
+            data.off_chances.insert(id, draw(&mut rng, &self.off_chances));
+        }
+        (graph, data)
+    }
+}
+
+fn draw(rng: &mut StdRng, range: &Range<f32>) -> f32 {
+    match range.is_empty() {
+        true => range.start,
+        false => rng.gen_range(range.clone()),
+    }
+}
+
+fn layered(rng: &mut StdRng, layers: usize, width: usize, edge_chance: f64) -> Vec<(u32, u32)> {
+    let id = |layer: usize, i: usize| (layer * width + i + 1) as u32;
+    let mut edges = vec![];
+    for layer in 0..layers.saturating_sub(1) {
+        for i in 0..width {
+            let mut linked = false;
+            for j in 0..width {
+                if rng.gen_bool(edge_chance.clamp(0.0, 1.0)) {
+                    edges.push((id(layer, i), id(layer + 1, j)));
+                    linked = true;
+                }
+            }
+            if !linked {
+                edges.push((id(layer, i), id(layer + 1, rng.gen_range(0..width))));
+            }
+        }
+    }
+    edges
+}
+
+/// Skips over the pairs that are not linked with geometrically distributed jumps, so sparse
+/// graphs take time in the number of edges rather than the number of node pairs
+fn random_dag(rng: &mut StdRng, nodes: usize, edge_chance: f64) -> Vec<(u32, u32)> {
+    let mut edges = vec![];
+    if edge_chance <= 0.0 {
+        return edges;
+    }
+    let log_miss = (1.0 - edge_chance.min(1.0)).ln();
+    for from in 1..nodes {
+        let mut to = from;
+        loop {
+            let skip = match edge_chance >= 1.0 {
+                true => 0,
+                false => ((1.0 - rng.gen::<f64>()).ln() / log_miss).floor() as usize,
+            };
+            to += skip + 1;
+            if to > nodes {
+                break;
+            }
+            edges.push((from as u32, to as u32));
+        }
+    }
+    edges
+}
+
+fn scale_free(rng: &mut StdRng, nodes: usize, edges_per_node: usize) -> Vec<(u32, u32)> {
+    let mut edges = vec![];
+    // Every node appears once per edge it has, plus once so nodes without edges can be chosen
+    let mut endpoints: Vec<u32> = vec![];
+    for to in 1..=nodes as u32 {
+        let mut targets: HashSet<u32> = HashSet::new();
+        let wanted = edges_per_node.min(to as usize - 1);
+        while targets.len() < wanted {
+            targets.insert(endpoints[rng.gen_range(0..endpoints.len())]);
+        }
+        let mut targets: Vec<u32> = targets.into_iter().collect();
+        targets.sort_unstable();
+        for from in targets {
+            edges.push((from, to));
+            endpoints.push(from);
+            endpoints.push(to);
+        }
+        endpoints.push(to);
+    }
+    edges
+}
+
+/// Writes the graph in the headered links format, with the alpha weight of every edge and the
+/// off chances of its nodes, so it can be read back with ['crate::input::CsvCritInput']
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written
+pub fn write_links_csv(path: &str, graph: &Graph, data: &CriticalityData) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(create_output(path)?);
+    writer.write_record(["from_name", "from_id", "to_name", "to_id", ALPHA_ATTR, "from_off_chance", "to_off_chance"])?;
+    let mut edges: Vec<(u32, u32)> = graph.get_edges().iter().map(|e| (e.from, e.to)).collect();
+    edges.sort_unstable();
+    let name = |id: &u32| graph.get_node(id).map(|n| n.name.clone()).unwrap_or_default();
+    let off_chance = |id: &u32| data.off_chances.get(id).copied().unwrap_or(0.0).to_string();
+    for (from, to) in edges {
+        let alpha = data.alpha().and_then(|a| a.get(&(from, to))).copied().unwrap_or(1.0);
+        writer.write_record([name(&from), from.to_string(), name(&to), to.to_string(), alpha.to_string(), off_chance(&from), off_chance(&to)])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
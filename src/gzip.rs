@@ -0,0 +1,104 @@
+//! Decoder for gzip files (RFC 1952), a deflate stream wrapped in a header and a checksum.
+//!
+//! Files made of several concatenated gzip members, as written by parallel compressors, are
+//! decompressed into the concatenation of their content.
+
+use crate::checksum::crc32;
+use crate::errors::compression::GzipError;
+use crate::inflate;
+
+/// First two bytes of every gzip member
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+const METHOD_DEFLATE: u8 = 8;
+const FLAG_HEADER_CRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+fn error(reason: &str) -> GzipError {
+    GzipError { reason: reason.to_string() }
+}
+
+/// Decompresses every member of a gzip file
+///
+/// # Errors
+///
+/// Returns a ['GzipError'] if a member is truncated, malformed or fails its checksum
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, GzipError> {
+    let mut out = vec![];
+    let mut pos = 0;
+    while pos < data.len() {
+        pos += member(&data[pos..], &mut out)?;
+    }
+    Ok(out)
+}
+
+/// Decompresses the member at the start of 'data' into 'out' and returns its length
+fn member(data: &[u8], out: &mut Vec<u8>) -> Result<usize, GzipError> {
+    if data.len() < 18 || data[..2] != GZIP_MAGIC {
+        return Err(error("missing gzip header"));
+    }
+    if data[2] != METHOD_DEFLATE {
+        return Err(error(&format!("unsupported compression method {}", data[2])));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    if flags & FLAG_EXTRA != 0 {
+        let len = data.get(pos..pos + 2).ok_or_else(|| error("truncated header"))?;
+        pos += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    for flag in [FLAG_NAME, FLAG_COMMENT] {
+        if flags & flag != 0 {
+            let end = data.iter().skip(pos).position(|b| *b == 0).ok_or_else(|| error("truncated header"))?;
+            pos += end + 1;
+        }
+    }
+    if flags & FLAG_HEADER_CRC != 0 {
+        pos += 2;
+    }
+    let stream = data.get(pos..).ok_or_else(|| error("truncated header"))?;
+
+    let (content, used) = inflate::inflate_prefix(stream).map_err(|e| error(&e.reason))?;
+    pos += used;
+    let trailer = data.get(pos..pos + 8).ok_or_else(|| error("missing checksum"))?;
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc32(&content) != crc || content.len() as u32 != size {
+        return Err(error("checksum mismatch"));
+    }
+    out.extend_from_slice(&content);
+    Ok(pos + 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "from,to\n0,1\n" as written by gzip
+    const LINKS: [u8; 32] = [0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x4b, 0x2b, 0xca, 0xcf,
+        0xd5, 0x29, 0xc9, 0xe7, 0x32, 0xd0, 0x31, 0xe4, 0x02, 0x00, 0x3d, 0x52, 0x2c, 0x75, 0x0c, 0x00, 0x00, 0x00];
+
+    #[test]
+    fn every_member_is_decompressed() {
+        assert_eq!(decompress(&LINKS).unwrap(), b"from,to\n0,1\n");
+        assert_eq!(decompress(&[LINKS, LINKS].concat()).unwrap(), b"from,to\n0,1\nfrom,to\n0,1\n");
+    }
+
+    #[test]
+    fn truncated_and_corrupt_members_are_errors() {
+        for end in 1..LINKS.len() {
+            assert!(decompress(&LINKS[..end]).is_err(), "cut at {}", end);
+        }
+        let mut corrupt = LINKS;
+        corrupt[24] ^= 1;
+        assert_eq!(decompress(&corrupt).unwrap_err().reason, "checksum mismatch");
+        // The size in the trailer claims 4 GiB
+        let mut claimed = LINKS;
+        claimed[28..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decompress(&claimed).unwrap_err().reason, "checksum mismatch");
+        let mut method = LINKS;
+        method[2] = 0;
+        assert_eq!(decompress(&method).unwrap_err().reason, "unsupported compression method 0");
+    }
+}
@@ -0,0 +1,199 @@
+//! Notification hooks fired once a run is over, so batch runs can report to a chat or a
+//! scheduler without a wrapper script.
+//!
+//! '--on-complete <hook>' is fired when the run succeeds and '--on-failure <hook>' when it fails.
+//! A hook starting with 'http://' is posted the payload as json, any other hook is run as a shell
+//! command with the payload on its standard input and the 'THOR_HOOK_EVENT' variable set to
+//! 'completed' or 'failed'. Endpoints needing TLS, such as chat webhooks, are reached through a
+//! command like 'curl -d @- https://...'.
+//!
+//! The payload is a json object with:
+//!
+//! * 'event': 'completed' or 'failed'
+//! * 'command' and 'args': the command and every flag in effect
+//! * 'seconds': the length of the run
+//! * 'results': a summary of the results of every analysis of the 'analyze' command, with its
+//!   'row_count', 'end_op_mean', the 'min', 'mean' and 'max' criticality and the ['TOP_NODES']
+//!   most critical nodes. Other commands have no results.
+//! * 'outputs': the path, size and hash of every file written, as in ['crate::manifest']
+//! * 'error': the error of a failed run, as written by '--error-format json', see
+//!   ['crate::exit::error_json']
+//!
+//! A hook that fails is logged as a warning and never changes the outcome of the run.
+
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Instant;
+use log::{info, warn};
+use crate::analyses::criticality::CriticalityResults;
+use crate::analyses::criticality::ranking::{CriticalitySummary, rank};
+use crate::cli::{arg_value, command};
+use crate::exit::error_json;
+use crate::http;
+use crate::json::JsonValue;
+use crate::manifest::{OUTPUT_FLAGS, files};
+use crate::network::Graph;
+use crate::output::Output;
+
+/// Most critical nodes listed in the summary of every set of results
+pub const TOP_NODES: usize = 5;
+
+/// Summaries of the results written during the run, one per analysed pair or component
+static RESULTS: Mutex<Vec<JsonValue>> = Mutex::new(vec![]);
+
+/// Where a hook sends the payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hook {
+    /// Posts the payload to a plain http url
+    Post(String),
+    /// Runs a shell command with the payload on its standard input
+    Command(String),
+}
+
+impl Hook {
+    /// The hook of a flag value, see the module documentation
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for https urls, which can't be posted to
+    pub fn parse(spec: &str) -> Result<Hook, Box<dyn Error>> {
+        let spec = spec.trim();
+        if spec.starts_with("https://") {
+            return Err(format!("The hook '{}' needs TLS, which isn't supported, post with a command such as curl instead", spec).into());
+        }
+        match spec.starts_with("http://") {
+            true => Ok(Hook::Post(spec.to_string())),
+            false => Ok(Hook::Command(spec.to_string())),
+        }
+    }
+
+    /// Sends the 'payload' of the 'event'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the command can't be run or exits with a failure
+    pub fn fire(&self, event: &str, payload: &JsonValue) -> Result<(), Box<dyn Error>> {
+        let body = payload.to_string();
+        match self {
+            Hook::Post(url) => {
+                http::request("POST", url, &[("Content-Type", "application/json")], body.as_bytes())?.ensure_success()?;
+            }
+            Hook::Command(command) => {
+                let mut child = shell(command)
+                    .env("THOR_HOOK_EVENT", event)
+                    .stdin(Stdio::piped())
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    // A command that doesn't read the payload closes its input early
+                    let _ = stdin.write_all(body.as_bytes());
+                }
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(format!("The hook command exited with {}", status).into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+/// The hooks of a run, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Hooks {
+    pub on_complete: Option<Hook>,
+    pub on_failure: Option<Hook>,
+    start: Instant,
+}
+
+impl Hooks {
+    /// The hooks given by the 'args', None if there are none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a hook can't be used, see ['Hook::parse']
+    pub fn from_args(args: &[String]) -> Result<Option<Hooks>, Box<dyn Error>> {
+        let on_complete = arg_value(args, "--on-complete").map(|h| Hook::parse(h)).transpose()?;
+        let on_failure = arg_value(args, "--on-failure").map(|h| Hook::parse(h)).transpose()?;
+        if on_complete.is_none() && on_failure.is_none() {
+            return Ok(None);
+        }
+        RESULTS.lock().unwrap().clear();
+        Ok(Some(Hooks { on_complete, on_failure, start: Instant::now() }))
+    }
+
+    /// The payload of the run with the 'args' that ended with the 'error', if any
+    pub fn payload(&self, args: &[String], error: Option<&(dyn Error + 'static)>) -> JsonValue {
+        let name = command(args).unwrap_or("analyze");
+        let mut payload = JsonValue::object()
+            .with("event", match error { None => "completed", Some(_) => "failed" })
+            .with("command", name)
+            .with("args", args.iter().skip(1).cloned().collect::<Vec<String>>())
+            .with("seconds", self.start.elapsed().as_secs_f64())
+            .with("results", RESULTS.lock().unwrap().clone())
+            .with("outputs", files(args, &OUTPUT_FLAGS));
+        if let Some(e) = error {
+            payload.insert("error", error_json(e, name));
+        }
+        payload
+    }
+
+    /// Fires the hook of the outcome of the run with the 'args', warning if it fails
+    pub fn fire(&self, args: &[String], error: Option<&(dyn Error + 'static)>) {
+        let (event, hook) = match error {
+            None => ("completed", &self.on_complete),
+            Some(_) => ("failed", &self.on_failure),
+        };
+        let Some(hook) = hook else { return };
+        match hook.fire(event, &self.payload(args, error)) {
+            Ok(()) => info!("Fired the {} hook", event),
+            Err(e) => warn!("The {} hook failed: {}", event, e),
+        }
+    }
+}
+
+/// Keeps a summary of the results for the payload of the hooks, added to the outputs of an
+/// analysis when hooks are given
+pub struct HookSummaryOutput {}
+
+impl Output for HookSummaryOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        RESULTS.lock().unwrap().push(summary(graph, results));
+        Ok(())
+    }
+}
+
+/// The summary of the 'results' in the payload, see the module documentation
+fn summary(graph: &Graph, results: &CriticalityResults) -> JsonValue {
+    let criticality = CriticalitySummary::new(results).map(|s| JsonValue::object()
+        .with("min", s.min)
+        .with("mean", s.mean)
+        .with("max", s.max));
+    let top: Vec<JsonValue> = rank(results).into_iter()
+        .take(TOP_NODES)
+        .map(|(id, node)| JsonValue::object()
+            .with("id", id)
+            .with("name", graph.get_node(&id).map(|n| n.name.clone()))
+            .with("criticality", node.criticality))
+        .collect();
+    JsonValue::object()
+        .with("row_count", results.row_count)
+        .with("end_op_mean", results.end_op_mean)
+        .with("criticality", criticality)
+        .with("top", top)
+}
@@ -0,0 +1,258 @@
+//! Minimal blocking HTTP/1.1 client used by integrations with external services.
+//!
+//! Only plain `http://` urls are supported. Each request opens a new connection and asks the
+//! server to close it once the response has been sent.
+
+use std::error::Error;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use crate::errors::http::{ResponseError, UrlError};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The parts of a http url that are needed to send a request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Url {
+    pub host: String,
+    pub port: u16,
+    /// Path including the query, always starts with '/'
+    pub path: String,
+}
+
+impl Url {
+    /// Splits a 'http://host:port/path' url into its parts
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['UrlError'] if the url does not use the http scheme or the port is invalid
+    pub fn parse(url: &str) -> Result<Url, UrlError> {
+        let error = || UrlError { url: url.to_string() };
+        let rest = url.strip_prefix("http://").ok_or_else(error)?;
+        let (authority, path) = match rest.find('/') {
+            None => (rest, "/"),
+            Some(i) => (&rest[..i], &rest[i..]),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            None => (authority, 80),
+            Some((host, port)) => (host, port.parse::<u16>().map_err(|_| error())?),
+        };
+        if host.is_empty() {
+            return Err(error());
+        }
+        Ok(Url { host: host.to_string(), port, path: path.to_string() })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn body_string(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// Returns the response unchanged if the status is 2xx
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ResponseError'] containing the body for any other status
+    pub fn ensure_success(self) -> Result<Response, ResponseError> {
+        if (200..300).contains(&self.status) {
+            Ok(self)
+        } else {
+            Err(ResponseError { status: self.status, body: self.body_string() })
+        }
+    }
+}
+
+/// Sends a request and reads the whole response
+///
+/// # Errors
+///
+/// Returns an error if the url is invalid, the connection fails, or the response is malformed
+pub fn request(method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Response, Box<dyn Error>> {
+    let mut reader = send(method, url, headers, body)?;
+    let mut response = read_head(&mut reader)?;
+    let mut content = vec![];
+    copy_body(&mut reader, &response, &mut content)?;
+    response.body = content;
+    Ok(response)
+}
+
+/// Sends a 'GET' request and streams the body of the response into 'out' instead of holding it in
+/// memory. The returned response has an empty body.
+///
+/// # Errors
+///
+/// Returns an error if the url is invalid, the connection fails, the response is malformed or
+/// 'out' can't be written
+pub fn download(url: &str, headers: &[(&str, &str)], out: &mut dyn Write) -> Result<Response, Box<dyn Error>> {
+    let mut reader = send("GET", url, headers, &[])?;
+    let response = read_head(&mut reader)?;
+    copy_body(&mut reader, &response, out)?;
+    Ok(response)
+}
+
+fn send(method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<BufReader<TcpStream>, Box<dyn Error>> {
+    let url = Url::parse(url)?;
+    let mut stream = TcpStream::connect((url.host.as_str(), url.port))?;
+    stream.set_read_timeout(Some(DEFAULT_TIMEOUT))?;
+    stream.set_write_timeout(Some(DEFAULT_TIMEOUT))?;
+
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\nContent-Length: {}\r\n",
+                           method, url.path, url.host, url.port, body.len());
+    for (name, value) in headers {
+        head += &format!("{}: {}\r\n", name, value);
+    }
+    head += "\r\n";
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    Ok(BufReader::new(stream))
+}
+
+/// Reads the status line and the headers, the body is left in the reader
+fn read_head<R: BufRead>(reader: &mut R) -> Result<Response, Box<dyn Error>> {
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line.split_whitespace().nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| format!("Malformed http status line: {}", status_line.trim()))?;
+
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 { break; }
+        let line = line.trim_end();
+        if line.is_empty() { break; }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Ok(Response { status, headers, body: vec![] })
+}
+
+/// Copies the body following the head of 'response' into 'out'
+fn copy_body<R: BufRead>(reader: &mut R, response: &Response, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    // These never have a body, even if they announce the length of the resource
+    if response.status == 204 || response.status == 304 {
+        return Ok(());
+    }
+    let chunked = response.header("Transfer-Encoding")
+        .map(|v| v.eq_ignore_ascii_case("chunked"))
+        .unwrap_or(false);
+    if chunked {
+        loop {
+            let mut size_line = String::new();
+            reader.read_line(&mut size_line)?;
+            let size_hex = size_line.trim().split(';').next().unwrap_or("");
+            let size = u64::from_str_radix(size_hex, 16)
+                .map_err(|_| format!("Malformed chunk size: {}", size_line.trim()))?;
+            if size == 0 { break; }
+            let copied = io::copy(&mut reader.take(size), out)?;
+            if copied < size {
+                return Err("The connection closed in the middle of a chunk".into());
+            }
+            let mut crlf = String::new();
+            reader.read_line(&mut crlf)?;
+        }
+    } else if let Some(length) = response.header("Content-Length").and_then(|l| l.parse::<u64>().ok()) {
+        let copied = io::copy(&mut reader.take(length), out)?;
+        if copied < length {
+            return Err(format!("The connection closed after {} of {} bytes", copied, length).into());
+        }
+    } else {
+        io::copy(reader, out)?;
+    }
+    Ok(())
+}
+
+/// Encodes 'user:password' for a basic authorization header
+pub fn basic_auth(user: &str, password: &str) -> String {
+    format!("Basic {}", base64(format!("{}:{}", user, password).as_bytes()))
+}
+
+/// Standard base64 with padding
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        out.push(TABLE[(n >> 18) as usize & 63] as char);
+        out.push(TABLE[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { TABLE[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { TABLE[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads the response in 'raw' like ['request'] does
+    fn read(raw: &str) -> Result<Response, Box<dyn Error>> {
+        let mut reader = raw.as_bytes();
+        let mut response = read_head(&mut reader)?;
+        let mut body = vec![];
+        copy_body(&mut reader, &response, &mut body)?;
+        response.body = body;
+        Ok(response)
+    }
+
+    #[test]
+    fn urls_are_split_into_their_parts() {
+        assert_eq!(Url::parse("http://neo4j:7474/db/data?x=1").unwrap(),
+                   Url { host: "neo4j".to_string(), port: 7474, path: "/db/data?x=1".to_string() });
+        assert_eq!(Url::parse("http://host").unwrap().port, 80);
+        for url in ["https://host/", "http://:80/", "http://host:http/", "host/path"] {
+            assert!(Url::parse(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn bodies_are_read_by_length_by_chunks_or_to_the_end() {
+        let sized = read("HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello and more").unwrap();
+        assert_eq!((sized.status, sized.body_string()), (200, "hello".to_string()));
+        let chunked = read("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5;ext\r\nhello\r\n6\r\n world\r\n0\r\n\r\n").unwrap();
+        assert_eq!(chunked.body_string(), "hello world");
+        assert_eq!(read("HTTP/1.0 404 Not Found\r\n\r\nmissing").unwrap().ensure_success().unwrap_err().body, "missing");
+        let unchanged = read("HTTP/1.1 304 Not Modified\r\nContent-Length: 100\r\n\r\n").unwrap();
+        assert!(unchanged.body.is_empty());
+    }
+
+    #[test]
+    fn truncated_and_malformed_responses_are_errors() {
+        for (raw, error) in [
+            ("", "Malformed http status line: "),
+            ("HTTP/1.1 OK\r\n\r\n", "Malformed http status line: HTTP/1.1 OK"),
+            ("HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello", "The connection closed after 5 of 10 bytes"),
+            ("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nA\r\nhello", "The connection closed in the middle of a chunk"),
+            ("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n", "Malformed chunk size: zz"),
+            // The stream ends before the last chunk
+            ("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n", "Malformed chunk size: "),
+        ] {
+            assert_eq!(read(raw).unwrap_err().to_string(), error, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn basic_auth_is_base64_encoded() {
+        assert_eq!(basic_auth("neo4j", "secret"), "Basic bmVvNGo6c2VjcmV0");
+        assert_eq!([base64(b""), base64(b"a"), base64(b"ab"), base64(b"abc")], ["", "YQ==", "YWI=", "YWJj"]);
+    }
+}
@@ -0,0 +1,342 @@
+//! Decoder for raw DEFLATE streams (RFC 1951).
+//!
+//! Used to read compressed archive entries and compressed input files without any native
+//! dependencies. Only decompression is supported.
+
+use crate::errors::compression::InflateError;
+
+/// Reads bits from a byte slice, least significant bit first
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u64,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data, pos: 0, bit_buf: 0, bit_count: 0 }
+    }
+
+    fn need(&mut self, count: u32) -> Result<(), InflateError> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.pos).ok_or_else(|| error("unexpected end of stream"))?;
+            self.pos += 1;
+            self.bit_buf |= (byte as u64) << self.bit_count;
+            self.bit_count += 8;
+        }
+        Ok(())
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, InflateError> {
+        if count == 0 {
+            return Ok(0);
+        }
+        self.need(count)?;
+        let value = (self.bit_buf & ((1u64 << count) - 1)) as u32;
+        self.bit_buf >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    /// Drops the remaining bits of the current byte
+    fn align(&mut self) {
+        let drop = self.bit_count % 8;
+        self.bit_buf >>= drop;
+        self.bit_count -= drop;
+    }
+
+    /// Number of bytes consumed from the input, including partially read ones
+    fn consumed(&self) -> usize {
+        self.pos - (self.bit_count / 8) as usize
+    }
+}
+
+/// Canonical huffman code stored as symbol counts per code length
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, InflateError> {
+        let mut counts = [0u16; 16];
+        for len in lengths {
+            counts[*len as usize] += 1;
+        }
+        counts[0] = 0;
+        // Each code length halves the codes left, more codes than that can't be told apart
+        let mut left: i32 = 1;
+        for count in counts.iter().skip(1) {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return Err(error("over-subscribed huffman code"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for i in 1..16 {
+            offsets[i] = offsets[i - 1] + counts[i - 1];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, len) in lengths.iter().enumerate() {
+            if *len != 0 {
+                symbols[offsets[*len as usize] as usize] = symbol as u16;
+                offsets[*len as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, InflateError> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(error("invalid huffman code"))
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59,
+    67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4,
+    5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513,
+    769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10,
+    11, 11, 12, 12, 13, 13];
+/// Order in which the code length code lengths are stored in a dynamic block
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn error(reason: &str) -> InflateError {
+    InflateError { reason: reason.to_string() }
+}
+
+/// Decompresses a raw deflate stream
+///
+/// # Errors
+///
+/// Returns an ['InflateError'] if the stream is truncated or malformed
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, InflateError> {
+    inflate_prefix(data).map(|(out, _)| out)
+}
+
+/// Decompresses a raw deflate stream whose content is at most 'limit' bytes, such as the declared
+/// size of an archive entry
+///
+/// # Errors
+///
+/// Returns an ['InflateError'] if the stream is truncated or malformed, or its content is larger
+/// than 'limit'
+pub fn inflate_limited(data: &[u8], limit: usize) -> Result<Vec<u8>, InflateError> {
+    decode(data, limit).map(|(out, _)| out)
+}
+
+/// Decompresses a raw deflate stream that may be followed by other data, returning the
+/// decompressed bytes and the number of input bytes the stream occupied
+///
+/// # Errors
+///
+/// Returns an ['InflateError'] if the stream is truncated or malformed
+pub fn inflate_prefix(data: &[u8]) -> Result<(Vec<u8>, usize), InflateError> {
+    decode(data, usize::MAX)
+}
+
+fn decode(data: &[u8], limit: usize) -> Result<(Vec<u8>, usize), InflateError> {
+    let mut reader = BitReader::new(data);
+    let mut out: Vec<u8> = Vec::with_capacity(limit.min(data.len() * 3));
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => stored_block(&mut reader, &mut out)?,
+            1 => {
+                let (lit, dist) = fixed_codes()?;
+                huffman_block(&mut reader, &mut out, &lit, &dist, limit)?
+            }
+            2 => {
+                let (lit, dist) = dynamic_codes(&mut reader)?;
+                huffman_block(&mut reader, &mut out, &lit, &dist, limit)?
+            }
+            _ => return Err(error("invalid block type")),
+        }
+        if out.len() > limit {
+            return Err(error("content larger than declared"));
+        }
+        if last {
+            break;
+        }
+    }
+    reader.align();
+    Ok((out, reader.consumed()))
+}
+
+fn stored_block(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), InflateError> {
+    reader.align();
+    let len = reader.bits(16)? as usize;
+    let nlen = reader.bits(16)? as usize;
+    if len != !nlen & 0xFFFF {
+        return Err(error("stored block length mismatch"));
+    }
+    // The bit buffer is empty after reading two aligned 16 bit values
+    let start = reader.consumed();
+    let bytes = reader.data.get(start..start + len).ok_or_else(|| error("unexpected end of stream"))?;
+    out.extend_from_slice(bytes);
+    reader.pos = start + len;
+    reader.bit_buf = 0;
+    reader.bit_count = 0;
+    Ok(())
+}
+
+fn fixed_codes() -> Result<(Huffman, Huffman), InflateError> {
+    let mut lengths = [0u8; 288];
+    for (i, len) in lengths.iter_mut().enumerate() {
+        *len = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5u8; 30])?))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), InflateError> {
+    let lit_count = reader.bits(5)? as usize + 257;
+    let dist_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for i in CODE_LENGTH_ORDER.iter().take(code_count) {
+        code_lengths[*i] = reader.bits(3)? as u8;
+    }
+    let code_huffman = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; lit_count + dist_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_huffman.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if i == 0 {
+                    return Err(error("repeat without a previous length"));
+                }
+                (lengths[i - 1], 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            18 => (0, 11 + reader.bits(7)? as usize),
+            _ => return Err(error("invalid code length symbol")),
+        };
+        if i + repeat > lengths.len() {
+            return Err(error("too many code lengths"));
+        }
+        for len in lengths.iter_mut().skip(i).take(repeat) {
+            *len = value;
+        }
+        i += repeat;
+    }
+    Ok((Huffman::new(&lengths[..lit_count])?, Huffman::new(&lengths[lit_count..])?))
+}
+
+fn huffman_block(reader: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman, limit: usize) -> Result<(), InflateError> {
+    loop {
+        if out.len() > limit {
+            return Err(error("content larger than declared"));
+        }
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let i = symbol - 257;
+                let len = LENGTH_BASE[i] as usize + reader.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = dist.decode(reader)? as usize;
+                if d >= 30 {
+                    return Err(error("invalid distance symbol"));
+                }
+                let distance = DIST_BASE[d] as usize + reader.bits(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(error("distance too far back"));
+                }
+                let start = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            }
+            _ => return Err(error("invalid literal/length symbol")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "0,1\n1,2\n" up to "6,7\n", which zlib compresses with a dynamic block
+    fn links() -> Vec<u8> {
+        (0..7).flat_map(|i| format!("{},{}\n", i, i + 1).into_bytes()).collect()
+    }
+
+    const DYNAMIC: [u8; 29] = [0x05, 0xc1, 0x37, 0x01, 0x00, 0x30, 0x0c, 0xc0, 0xb0, 0xdf, 0x58, 0x7c,
+        0x34, 0x9b, 0x3f, 0xb3, 0x4a, 0xcf, 0x20, 0x4c, 0xd2, 0xa2, 0x6c, 0xda, 0x61, 0x5c, 0xd6, 0xe3, 0x03];
+    /// "hello hello hello" with the fixed codes
+    const FIXED: [u8; 10] = [0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00];
+
+    /// A single stored block holding 'content'
+    fn stored(content: &[u8]) -> Vec<u8> {
+        let len = content.len() as u16;
+        let mut data = vec![0x01];
+        data.extend_from_slice(&len.to_le_bytes());
+        data.extend_from_slice(&(!len).to_le_bytes());
+        data.extend_from_slice(content);
+        data
+    }
+
+    #[test]
+    fn every_block_type_decodes_to_its_content() {
+        assert_eq!(inflate(&stored(b"from,to")).unwrap(), b"from,to");
+        assert_eq!(inflate(&FIXED).unwrap(), b"hello hello hello");
+        assert_eq!(inflate(&DYNAMIC).unwrap(), links());
+        let mut followed = DYNAMIC.to_vec();
+        followed.extend_from_slice(b"trailer");
+        assert_eq!(inflate_prefix(&followed).unwrap(), (links(), DYNAMIC.len()));
+    }
+
+    #[test]
+    fn truncated_streams_are_errors() {
+        for data in [&DYNAMIC[..], &FIXED[..], &stored(b"from,to")[..]] {
+            for end in 0..data.len() {
+                assert!(inflate(&data[..end]).is_err(), "{:?} cut at {}", data, end);
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_streams_are_errors() {
+        // Dynamic block whose 19 code length codes are all one bit long
+        let over_subscribed = [0x05, 0xe0, 0x93, 0x24, 0x49, 0x92, 0x24, 0x49, 0x92, 0x00];
+        assert_eq!(inflate(&over_subscribed).unwrap_err().reason, "over-subscribed huffman code");
+        assert_eq!(inflate(&[0x07]).unwrap_err().reason, "invalid block type");
+        assert_eq!(inflate(&[0x01, 0x03, 0x00, 0x00, 0x00]).unwrap_err().reason, "stored block length mismatch");
+        // A stored block declaring 65535 bytes but holding three
+        assert_eq!(inflate(&[0x01, 0xff, 0xff, 0x00, 0x00, 1, 2, 3]).unwrap_err().reason, "unexpected end of stream");
+        // Fixed block starting with a match of length 3 at distance 1, before any literal
+        assert_eq!(inflate(&[0x03, 0x02, 0x00]).unwrap_err().reason, "distance too far back");
+    }
+
+    #[test]
+    fn content_over_the_limit_is_refused() {
+        assert_eq!(inflate_limited(&FIXED, 17).unwrap(), b"hello hello hello");
+        assert_eq!(inflate_limited(&FIXED, 16).unwrap_err().reason, "content larger than declared");
+        assert!(inflate_limited(&stored(&[0; 100]), 99).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! Reading graphs from an adjacency list, a text format meant to be written by hand.
+//!
+//! Every line names a node, a colon and the children rolled up into the node, separated by
+//! whitespace:
+//!
+//! ```text
+//! # the end node e is operable if b or c is
+//! e: b c
+//! b: a
+//! c: a
+//! a: s
+//! ```
+//!
+//! A node may have no children and may be listed on several lines, its children are then added
+//! together. Empty lines and everything after a '#' are skipped. As in
+//! ['crate::input::dot'], node names are used as ids if every name is a number, otherwise nodes
+//! are numbered in the order they appear and keep their name as the ['KEY_ATTR'].
+
+use std::collections::HashMap;
+use std::error::Error;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::AdjacencyListError;
+use crate::input::{Input, read_input_string};
+use crate::network::{AttrValue, Graph, KEY_ATTR};
+
+/// Configurations which hold information necessary to read an adjacency list
+#[derive(Debug, Clone)]
+pub struct AdjacencyListConfigs {
+    /// The path to the text file
+    pub in_path: String,
+}
+
+/// Structure used to read a graph from an adjacency list
+pub struct AdjacencyListInput {}
+
+impl Input for AdjacencyListInput {
+    type Configs = AdjacencyListConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: AdjacencyListConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        Ok((parse_adjacency_list(&read_input_string(&configs.in_path)?)?, CriticalityData::default()))
+    }
+}
+
+/// Parses an adjacency list, see the module documentation for the format
+///
+/// # Errors
+///
+/// Returns an ['AdjacencyListError'] if a line has no colon, names no node or lists a node as its
+/// own child
+pub fn parse_adjacency_list(text: &str) -> Result<Graph, AdjacencyListError> {
+    let mut lines: Vec<(&str, Vec<&str>)> = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (node, children) = line.split_once(':')
+            .ok_or_else(|| AdjacencyListError { line: number, reason: "expected 'node: child child ...'".to_string() })?;
+        let node = node.trim();
+        if node.is_empty() || node.contains(char::is_whitespace) {
+            return Err(AdjacencyListError { line: number, reason: format!("'{}' is not a node name", node) });
+        }
+        let children: Vec<&str> = children.split_whitespace().collect();
+        if children.contains(&node) {
+            return Err(AdjacencyListError { line: number, reason: format!("the node '{}' is its own child", node) });
+        }
+        lines.push((node, children));
+    }
+    // Node names are used as ids if every name in the file is a number
+    let numeric = lines.iter()
+        .all(|(node, children)| std::iter::once(node).chain(children.iter()).all(|key| key.parse::<u32>().is_ok()));
+
+    let mut graph = Graph::new();
+    let mut ids: HashMap<String, u32> = HashMap::new();
+    let mut node = |graph: &mut Graph, key: &str| -> u32 {
+        if let Some(id) = ids.get(key) {
+            return *id;
+        }
+        let id = match numeric {
+            true => key.parse::<u32>().unwrap(),
+            false => ids.len() as u32,
+        };
+        ids.insert(key.to_string(), id);
+        graph.add_node(key.to_string(), id);
+        if !numeric {
+            graph.set_node_attr(&id, KEY_ATTR, AttrValue::Text(key.to_string()));
+        }
+        id
+    };
+    for (parent, children) in lines.iter() {
+        let parent = node(&mut graph, parent);
+        for child in children {
+            let child = node(&mut graph, child);
+            graph.add_edge(child, parent);
+        }
+    }
+    Ok(graph)
+}
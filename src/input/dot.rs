@@ -0,0 +1,282 @@
+//! Reading graphs from the DOT language of Graphviz, as written by
+//! ['crate::export::dot::DotExport'].
+//!
+//! Supports node statements, edge chains 'a -> b -> c' and attribute lists. Default attribute
+//! statements ('graph', 'node', 'edge') and graph attributes are skipped, subgraphs and
+//! undirected graphs are not supported. Node ids that are not numbers are numbered in the order
+//! they appear, kept as the ['KEY_ATTR'] of the node and used as the name if the node has no
+//! 'label'. The 'off_chance' and 'static'
+//! node attributes are read into the data, numeric edge attributes into the edge attribute maps.
+
+use std::collections::HashMap;
+use std::error::Error;
+use log::warn;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::DotParseError;
+use crate::export::dot::LABEL_ATTR;
+use crate::export::graphml::{OFF_CHANCE_KEY, STATIC_KEY};
+use crate::input::{Input, read_input_string};
+use crate::network::{AttrValue, Graph, KEY_ATTR};
+
+/// Configurations which hold information necessary to read a DOT file
+#[derive(Debug, Clone)]
+pub struct DotConfigs {
+    /// The path to the DOT file
+    pub in_path: String,
+}
+
+/// Structure used to read a graph and its criticality data from DOT
+pub struct DotInput {}
+
+impl Input for DotInput {
+    type Configs = DotConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: DotConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        Ok(parse_dot(&read_input_string(&configs.in_path)?)?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier, number or quoted string
+    Id(String),
+    Symbol(char),
+    Arrow,
+}
+
+/// Splits the text into (line, token) pairs, skipping comments
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, DotParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\n' => { line += 1; i += 1; }
+            c if c.is_whitespace() => i += 1,
+            '/' if chars.get(i + 1) == Some(&'/') => while i < chars.len() && chars[i] != '\n' { i += 1 },
+            '#' => while i < chars.len() && chars[i] != '\n' { i += 1 },
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    if chars[i] == '\n' { line += 1; }
+                    i += 1;
+                }
+                i += 2;
+            }
+            '-' if chars.get(i + 1) == Some(&'>') => { tokens.push((line, Token::Arrow)); i += 2; }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                return Err(DotParseError { line, reason: "undirected edges are not supported".to_string() });
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' => { tokens.push((line, Token::Symbol(c))); i += 1; }
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(DotParseError { line, reason: "unterminated string".to_string() }),
+                        Some('"') => { i += 1; break; }
+                        Some('\\') if matches!(chars.get(i + 1), Some('"') | Some('\\')) => { value.push(chars[i + 1]); i += 2; }
+                        Some(c) => { if *c == '\n' { line += 1; } value.push(*c); i += 1; }
+                    }
+                }
+                tokens.push((line, Token::Id(value)));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.' || (chars[i] == '-' && chars.get(i + 1) != Some(&'>'))) {
+                    i += 1;
+                }
+                tokens.push((line, Token::Id(chars[start..i].iter().collect())));
+            }
+            c => return Err(DotParseError { line, reason: format!("unexpected character '{}'", c) }),
+        }
+    }
+    Ok(tokens)
+}
+
+struct DotParser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl DotParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map(|(l, _)| *l).unwrap_or(1)
+    }
+
+    fn error(&self, reason: &str) -> DotParseError {
+        DotParseError { line: self.line(), reason: reason.to_string() }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, t)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), DotParseError> {
+        match self.next() {
+            Some(Token::Symbol(c)) if c == symbol => Ok(()),
+            _ => Err(self.error(&format!("expected '{}'", symbol))),
+        }
+    }
+
+    fn id(&mut self) -> Result<String, DotParseError> {
+        match self.next() {
+            Some(Token::Id(id)) => Ok(id),
+            _ => Err(self.error("expected an id")),
+        }
+    }
+
+    /// Reads any number of '[key=value, ...]' lists
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, DotParseError> {
+        let mut attributes = vec![];
+        while self.peek() == Some(&Token::Symbol('[')) {
+            self.next();
+            while self.peek() != Some(&Token::Symbol(']')) {
+                let key = self.id()?;
+                self.expect('=')?;
+                attributes.push((key, self.id()?));
+                if matches!(self.peek(), Some(Token::Symbol(',')) | Some(Token::Symbol(';'))) {
+                    self.next();
+                }
+            }
+            self.expect(']')?;
+        }
+        Ok(attributes)
+    }
+}
+
+/// Node or edge statement of the digraph
+enum Statement {
+    Node { key: String, attributes: Vec<(String, String)>, line: usize },
+    Edges { chain: Vec<String>, attributes: Vec<(String, String)> },
+}
+
+/// Reads the statements of a digraph, skipping default attribute statements and graph attributes
+fn statements(p: &mut DotParser) -> Result<Vec<Statement>, DotParseError> {
+    if p.peek() == Some(&Token::Id("strict".to_string())) {
+        p.next();
+    }
+    match p.next() {
+        Some(Token::Id(kind)) if kind == "digraph" => {}
+        _ => return Err(p.error("expected a digraph")),
+    }
+    if let Some(Token::Id(_)) = p.peek() {
+        p.next();
+    }
+    p.expect('{')?;
+    let mut statements = vec![];
+    loop {
+        match p.peek() {
+            None => return Err(p.error("expected '}'")),
+            Some(Token::Symbol('}')) => { p.next(); break; }
+            Some(Token::Symbol(';')) => { p.next(); }
+            Some(Token::Id(id)) if id == "subgraph" => return Err(p.error("subgraphs are not supported")),
+            Some(Token::Id(id)) if ["graph", "node", "edge"].contains(&id.as_str()) => {
+                p.next();
+                p.attributes()?;
+            }
+            Some(Token::Id(_)) => {
+                let line = p.line();
+                let first = p.id()?;
+                if p.peek() == Some(&Token::Symbol('=')) {
+                    p.next();
+                    p.id()?;
+                    continue;
+                }
+                let mut chain = vec![first];
+                while p.peek() == Some(&Token::Arrow) {
+                    p.next();
+                    chain.push(p.id()?);
+                }
+                let attributes = p.attributes()?;
+                statements.push(match chain.len() {
+                    1 => Statement::Node { key: chain.pop().unwrap(), attributes, line },
+                    _ => Statement::Edges { chain, attributes },
+                });
+            }
+            Some(_) => return Err(p.error("expected a statement")),
+        }
+    }
+    Ok(statements)
+}
+
+/// Parses a DOT digraph, see the module documentation for the supported subset
+///
+/// # Errors
+///
+/// Returns a ['DotParseError'] if the text is not a supported digraph or an off chance is not a
+/// number
+pub fn parse_dot(text: &str) -> Result<(Graph, CriticalityData), DotParseError> {
+    let mut parser = DotParser { tokens: tokenize(text)?, pos: 0 };
+    let statements = statements(&mut parser)?;
+    // Node ids are used as they are if every id in the file is a number
+    let numeric = statements.iter().all(|s| match s {
+        Statement::Node { key, .. } => key.parse::<u32>().is_ok(),
+        Statement::Edges { chain, .. } => chain.iter().all(|key| key.parse::<u32>().is_ok()),
+    });
+
+    let mut graph = Graph::new();
+    let mut data = CriticalityData::default();
+    let mut ids: HashMap<String, u32> = HashMap::new();
+    let mut node = |graph: &mut Graph, key: &str| -> u32 {
+        if let Some(id) = ids.get(key) {
+            return *id;
+        }
+        let id = match numeric {
+            true => key.parse::<u32>().unwrap(),
+            false => ids.len() as u32,
+        };
+        ids.insert(key.to_string(), id);
+        graph.add_node(key.to_string(), id);
+        if !numeric {
+            graph.set_node_attr(&id, KEY_ATTR, AttrValue::Text(key.to_string()));
+        }
+        id
+    };
+    for statement in statements {
+        match statement {
+            Statement::Node { key, attributes, line } => {
+                let id = node(&mut graph, &key);
+                for (key, value) in attributes {
+                    match key.as_str() {
+                        LABEL_ATTR => {
+                            let old = graph.add_node(value, id);
+                            for (name, value) in old.into_iter().flat_map(|n| n.attributes) {
+                                graph.set_node_attr(&id, &name, value);
+                            }
+                        }
+                        OFF_CHANCE_KEY => {
+                            let off_chance = value.parse::<f32>()
+                                .map_err(|_| DotParseError { line, reason: format!("the off chance '{}' is not a number", value) })?;
+                            data.off_chances.insert(id, off_chance);
+                        }
+                        STATIC_KEY => if value == "true" { graph.static_nodes.insert(id); },
+                        _ => { graph.set_node_attr(&id, &key, AttrValue::parse(&value)); }
+                    }
+                }
+            }
+            Statement::Edges { chain, attributes } => {
+                let chain: Vec<u32> = chain.iter().map(|key| node(&mut graph, key)).collect();
+                for pair in chain.windows(2) {
+                    graph.add_edge(pair[0], pair[1]);
+                    for (key, value) in attributes.iter() {
+                        match value.parse::<f32>() {
+                            Ok(v) => { data.edge_attributes.entry(key.to_string()).or_default().insert((pair[0], pair[1]), v); }
+                            Err(_) => warn!("The {} of the edge ({}, {}) is not a number and is ignored", key, pair[0], pair[1]),
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok((graph, data))
+}
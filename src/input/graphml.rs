@@ -0,0 +1,118 @@
+//! Reading graphs from GraphML, as written by ['crate::export::graphml::GraphMlExport'] or other
+//! graph tools.
+//!
+//! Only the first graph of the document is read and every edge is directed from its source to
+//! its target. Nodes whose ids are not all numbers are numbered in the order they appear, their
+//! GraphML id is kept as the ['KEY_ATTR'] and used as the name if they have none. Numeric edge data is read as named edge
+//! attributes, other edge data is ignored.
+
+use std::collections::HashMap;
+use std::error::Error;
+use log::warn;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::ModelError;
+use crate::export::graphml::{NAME_KEY, OFF_CHANCE_KEY, STATIC_KEY};
+use crate::input::{Input, read_input_string};
+use crate::network::{AttrValue, EdgeValueMap, Graph, KEY_ATTR};
+use crate::xml;
+use crate::xml::XmlElement;
+
+/// Configurations which hold information necessary to read a GraphML file
+#[derive(Debug, Clone)]
+pub struct GraphMlConfigs {
+    /// The path to the GraphML file
+    pub in_path: String,
+}
+
+/// Structure used to read a graph and its criticality data from GraphML
+pub struct GraphMlInput {}
+
+/// Declared data key: (domain, attribute name, attribute type)
+type Key = (String, String, String);
+
+impl Input for GraphMlInput {
+    type Configs = GraphMlConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: GraphMlConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let document = xml::parse(&read_input_string(&configs.in_path)?)?;
+        let model_error = |reason: &str| ModelError { reason: reason.to_string() };
+        let keys: HashMap<String, Key> = document.children_named("key")
+            .filter_map(|k| k.attr("id").map(|id| (id.to_string(), (
+                k.attr("for").unwrap_or("all").to_string(),
+                k.attr("attr.name").unwrap_or(id).to_string(),
+                k.attr("attr.type").unwrap_or("string").to_string(),
+            ))))
+            .collect();
+        let graph_element = document.child("graph").ok_or_else(|| model_error("the document has no graph"))?;
+        let nodes: Vec<&XmlElement> = graph_element.children_named("node").collect();
+
+        let numeric = nodes.iter().all(|n| n.attr("id").map(|id| id.parse::<u32>().is_ok()).unwrap_or(false));
+        let mut ids: HashMap<String, u32> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let key = node.attr("id").ok_or_else(|| model_error("a node has no id"))?;
+            let id = match numeric {
+                true => key.parse::<u32>().unwrap(),
+                false => i as u32,
+            };
+            ids.insert(key.to_string(), id);
+        }
+
+        let mut graph = Graph::new();
+        let mut data = CriticalityData::default();
+        for node in nodes.iter() {
+            let key = node.attr("id").unwrap();
+            let id = ids[key];
+            let values = element_data(node, &keys, "node");
+            let name = values.iter().find(|(name, _, _)| *name == NAME_KEY).map(|(_, _, v)| v.to_string());
+            graph.add_node(name.unwrap_or(key.to_string()), id);
+            if !numeric {
+                graph.set_node_attr(&id, KEY_ATTR, AttrValue::Text(key.to_string()));
+            }
+            for (name, kind, value) in values {
+                match name {
+                    NAME_KEY => {}
+                    OFF_CHANCE_KEY => { data.off_chances.insert(id, value.trim().parse::<f32>()?); }
+                    STATIC_KEY => if value.trim() == "true" { graph.static_nodes.insert(id); },
+                    _ => { graph.set_node_attr(&id, name, typed_value(kind, value)); }
+                }
+            }
+        }
+
+        let mut edge_attributes: HashMap<String, EdgeValueMap<f32>> = HashMap::new();
+        for edge in graph_element.children_named("edge") {
+            let endpoint = |name: &str| edge.attr(name)
+                .and_then(|key| ids.get(key).copied())
+                .ok_or_else(|| model_error(&format!("an edge has an unknown {}", name)));
+            let (from, to) = (endpoint("source")?, endpoint("target")?);
+            graph.add_edge(from, to);
+            for (name, _, value) in element_data(edge, &keys, "edge") {
+                match value.trim().parse::<f32>() {
+                    Ok(v) => { edge_attributes.entry(name.to_string()).or_default().insert((from, to), v); }
+                    Err(_) => warn!("The {} of the edge ({}, {}) is not a number and is ignored", name, from, to),
+                }
+            }
+        }
+        for (name, values) in edge_attributes {
+            data.add_edge_attribute(&name, values);
+        }
+        Ok((graph, data))
+    }
+}
+
+/// (attribute name, attribute type, value) of every data child of 'element' with a key declared
+/// for the 'domain'
+fn element_data<'a>(element: &'a XmlElement, keys: &'a HashMap<String, Key>, domain: &'a str) -> Vec<(&'a str, &'a str, String)> {
+    element.children_named("data")
+        .filter_map(|d| keys.get(d.attr("key")?).map(|k| (k, d.text())))
+        .filter(|((key_domain, _, _), _)| key_domain == domain || key_domain == "all")
+        .map(|((_, name, kind), value)| (name.as_str(), kind.as_str(), value))
+        .collect()
+}
+
+fn typed_value(kind: &str, value: String) -> AttrValue {
+    match kind {
+        "string" => AttrValue::Text(value),
+        _ => AttrValue::parse(&value),
+    }
+}
@@ -0,0 +1,28 @@
+//! Reading a graph and its data from a json ['GraphDocument'].
+
+use std::error::Error;
+use crate::analyses::criticality::CriticalityData;
+use crate::input::{Input, read_input_string};
+use crate::network::Graph;
+use crate::serialization::GraphDocument;
+use crate::serialization::json::from_json_str;
+
+/// Configurations which hold information necessary to read a json graph document
+#[derive(Debug, Clone)]
+pub struct JsonGraphConfigs {
+    /// The path to the json file
+    pub in_path: String,
+}
+
+/// Structure used to read a graph written by ['crate::export::json::JsonExport']
+pub struct JsonGraphInput {}
+
+impl Input for JsonGraphInput {
+    type Configs = JsonGraphConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: JsonGraphConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let document: GraphDocument = from_json_str(&read_input_string(&configs.in_path)?)?;
+        Ok((document.graph, document.data))
+    }
+}
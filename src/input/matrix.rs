@@ -0,0 +1,88 @@
+//! Reading graphs from a square adjacency matrix in a csv file, as written by older tooling.
+//!
+//! The header row names the nodes after an ignored corner cell, and every following row starts
+//! with the name of a node in the same order. The cell in the row of node a and the column of
+//! node b is the alpha weight of the edge from a to b, in the direction of the links table, empty
+//! and zero cells mean there is no edge. Nodes are numbered in the order of the header and keep
+//! their name as the ['KEY_ATTR'], so they can be referred to by name. The graph and the alpha
+//! map are built in the same pass over the cells.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::marker::PhantomData;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::{AdjacencyMatrixError, CellNotNumericError};
+use crate::input::{CsvFormat, Input, read_csv_table};
+use crate::network::{ALPHA_ATTR, AttrValue, EdgeValueMap, Graph, KEY_ATTR};
+
+/// Configurations which hold information necessary to read an adjacency matrix
+#[derive(Debug, Clone)]
+pub struct MatrixConfigs {
+    /// The path to the csv file
+    pub in_path: String,
+    /// Characters and encoding of the csv file
+    pub format: CsvFormat,
+}
+
+/// Structure used to read a graph and its alpha weights from an adjacency matrix
+pub struct MatrixInput {}
+
+impl Input for MatrixInput {
+    type Configs = MatrixConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: MatrixConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let fail = |reason: String| AdjacencyMatrixError { path: configs.in_path.clone(), reason };
+        let (headers, rows) = read_csv_table(&configs.in_path, true, &configs.format)?;
+        let names: Vec<String> = headers.unwrap_or_default().into_iter().skip(1).map(|n| n.trim().to_string()).collect();
+        if names.is_empty() {
+            return Err(fail("the header row names no nodes".to_string()).into());
+        }
+        if names.iter().collect::<HashSet<&String>>().len() != names.len() {
+            return Err(fail("the header row names a node twice".to_string()).into());
+        }
+        if rows.len() != names.len() {
+            return Err(fail(format!("it has {} rows for {} nodes, it must be square", rows.len(), names.len())).into());
+        }
+
+        let mut graph = Graph::new();
+        for (id, name) in names.iter().enumerate() {
+            graph.add_node(name.clone(), id as u32);
+            graph.set_node_attr(&(id as u32), KEY_ATTR, AttrValue::Text(name.clone()));
+        }
+        let mut data = CriticalityData::default();
+        let mut alpha = EdgeValueMap::new();
+        for (from, row) in rows.iter().enumerate() {
+            // Row 0 of the file is the header
+            let line = from + 1;
+            if row.first().map(|n| n.trim()) != Some(names[from].as_str()) {
+                return Err(fail(format!("row {} must start with the node '{}' of column {}", line, names[from], from + 1)).into());
+            }
+            if row.len() != names.len() + 1 {
+                return Err(fail(format!("row {} has {} values for {} nodes", line, row.len() - 1, names.len())).into());
+            }
+            for (to, cell) in row.iter().skip(1).enumerate() {
+                let cell = cell.trim();
+                if cell.is_empty() {
+                    continue;
+                }
+                let weight = cell.parse::<f32>().map_err(|_| CellNotNumericError::<f32> {
+                    cell_pos: (line, to + 1),
+                    cell_val: cell.to_string(),
+                    phantom: PhantomData,
+                })?;
+                if weight == 0.0 {
+                    continue;
+                }
+                if from == to {
+                    data.warnings.push(format!("Dropped the self loop of node '{}' on the diagonal", names[from]));
+                    continue;
+                }
+                graph.add_edge(from as u32, to as u32);
+                alpha.insert((from as u32, to as u32), weight);
+            }
+        }
+        data.add_edge_attribute(ALPHA_ATTR, alpha);
+        Ok((graph, data))
+    }
+}
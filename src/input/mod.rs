@@ -0,0 +1,1499 @@
+//! Module containing all necessary structures for reading inputs / data necessary for analyses.
+//!
+//! Reading from any file is done using a struct that implements the ['Input'] trait.
+//! All input structures must provide some way to build a graph, as well as some type of
+//! additional information for some analysis. Note that each analysis requires it's own input
+//! implementation. Most input structures will likely share similar code.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Cursor, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+use std::str::FromStr;
+use log::{debug, warn};
+use crate::network::{ALPHA_ATTR, AttrValue, Graph, EdgeValueMap, KEY_ATTR, NodeValueMap};
+use crate::{errors, gzip, zstd};
+use crate::mmap::MappedFile;
+use crate::analyses::criticality::CriticalityData;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::{CcfGroup, Dependency, DependencyEffect};
+use crate::analyses::criticality::samples::NodeGroup;
+use crate::analyses::{INVISIBLE_VAL, VISIBLE_VAL};
+use crate::analyses::event_tree::{EventTree, Outcome, Sequence};
+use crate::analyses::scenario::Scenario;
+
+use crate::errors::input::{AlphaFileError, CellNotNumericError, ColumnNotFoundError, CreateError, CsvCharacterError, IdOverflowError, NodeStateError, UnknownDirectionError, UnknownEdgePolicyError, UnknownEncodingError, UnknownNodeKeyError};
+
+pub mod adjacency;
+pub mod dot;
+pub mod graphml;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod matrix;
+pub mod neo4j;
+pub mod networkx;
+pub mod openpsa;
+mod parallel;
+pub mod remote;
+pub mod snapshot;
+pub mod xlsx;
+
+/// A row of a strings
+type StringRow = Vec<String>;
+
+/// A row of a strings belonging to a matrix
+type StringCol = Vec<String>;
+
+/// List of rows (list of strings) obtained from reading a file.
+type RowStringMatrix = Vec<StringRow>;
+
+/// List of rows (list of strings) obtained from reading a file.
+type ColStringMatrix = Vec<StringCol>;
+
+/// List of (from id, to id) pairs in the order they appear in the input. The pair of a parallel
+/// edge is the edge from its link node and dropped rows have none, see ['create_graph'].
+type EdgeList = Vec<Option<(u32, u32)>>;
+
+// TODO: return error if all the rows are not the same length
+fn row_to_col_matrix(row_matrix: &RowStringMatrix) -> ColStringMatrix {
+    let mut col = Vec::new();
+    for _ in 0..row_matrix.iter().map(|row| row.len()).max().unwrap_or(0) {
+        col.push(Vec::new());
+    }
+    for row in row_matrix {
+        for (i, cell) in row.iter().enumerate() {
+            col[i].push(cell.to_string());
+        }
+    }
+    col
+}
+
+// TODO: return error if all the col are not the same length
+fn col_to_row_matrix(col_matrix: &ColStringMatrix) -> RowStringMatrix {
+    let mut row = Vec::new();
+    for _ in 0..col_matrix[0].len() {
+        row.push(Vec::new());
+    }
+    for col in col_matrix {
+        for (i, cell) in col.iter().enumerate() {
+            row[i].push(cell.to_string());
+        }
+    }
+    row
+}
+
+/// Node name used if the input fails to provide a proper name
+const DEFAULT_NODE_NAME: &str = "DEFAULT";
+/// Node id used if the input fails to provide a proper id
+const DEFAULT_NODE_ID: u32 = 999999;
+
+/// A trait which provides a method for creating a graph from some configurations as well some
+/// data that is used to analyze the graph.
+ pub trait Input {
+    /// The type of struct that contains information on what is to be read and how to read it
+    type Configs;
+    /// The type of additional data that is required for the target analysis.
+    type AnalysisData;
+    /// Function that uses the input configuration to create a graph and additional data which
+    /// will be passed to some analysis
+    ///
+    /// # Errors
+    ///
+    /// May return a ['Error'] if the input configuration is invalid.
+    fn read(&self, configs: Self::Configs) -> Result<(Graph, Self::AnalysisData), Box<dyn Error>>;
+ }
+
+/// Compression of an input file, read transparently by every file input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+/// Every compression with the file extensions it is recognised by
+const COMPRESSIONS: [(Compression, &[&str]); 2] = [
+    (Compression::Gzip, &[".gz", ".gzip"]),
+    (Compression::Zstd, &[".zst", ".zstd"]),
+];
+
+impl Compression {
+    /// The compression of a file with the extension of 'path', if any
+    pub fn from_path(path: &str) -> Option<Compression> {
+        let path = path.to_ascii_lowercase();
+        COMPRESSIONS.iter()
+            .find(|(_, extensions)| extensions.iter().any(|e| path.ends_with(e)))
+            .map(|(compression, _)| *compression)
+    }
+
+    /// The compression of a file starting with the bytes 'start', if any
+    pub fn from_magic(start: &[u8]) -> Option<Compression> {
+        match start {
+            _ if start.starts_with(&gzip::GZIP_MAGIC) => Some(Compression::Gzip),
+            _ if start.starts_with(&zstd::ZSTD_MAGIC) => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The 'path' without the extension of its compression, so 'links.csv.gz' is read as a csv
+    pub fn strip_extension(path: &str) -> &str {
+        let lower = path.to_ascii_lowercase();
+        COMPRESSIONS.iter()
+            .flat_map(|(_, extensions)| extensions.iter())
+            .find(|e| lower.ends_with(*e))
+            .map(|e| &path[..path.len() - e.len()])
+            .unwrap_or(path)
+    }
+
+    /// Decompresses the whole content of a file
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the data is not valid for the compression
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match self {
+            Compression::Gzip => gzip::decompress(data)?,
+            Compression::Zstd => zstd::decompress(data)?,
+        })
+    }
+}
+
+/// Path standing for the standard input wherever an input is read from a path
+pub const STDIN_PATH: &str = "-";
+
+/// Opens the file at 'path' for reading, decompressing it if it is compressed. The compression
+/// is given by the extension of the path, or else by the first bytes of the file. Uncompressed
+/// files are streamed, compressed files are decompressed into memory. The ['STDIN_PATH'] reads
+/// the standard input, and remote urls are read from the local copy made by ['remote::fetch'].
+///
+/// # Errors
+///
+/// Will return an io error if the file can't be read, or an error if it can't be decompressed
+pub fn open_input(path: &str) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    if remote::is_remote(path) {
+        return open_input(&remote::local_path(path)?);
+    }
+    if path == STDIN_PATH {
+        let mut data = vec![];
+        io::stdin().lock().read_to_end(&mut data)?;
+        return match Compression::from_magic(&data) {
+            None => Ok(Box::new(Cursor::new(data))),
+            Some(compression) => Ok(Box::new(Cursor::new(compression.decompress(&data)?))),
+        };
+    }
+    let mut file = File::open(path)?;
+    let mut start = vec![];
+    (&mut file).take(4).read_to_end(&mut start)?;
+    let input = Cursor::new(start).chain(file);
+    match Compression::from_path(path).or_else(|| Compression::from_magic(input.get_ref().0.get_ref())) {
+        None => Ok(Box::new(BufReader::new(input))),
+        Some(compression) => {
+            let (start, mut file) = input.into_inner();
+            let mut data = start.into_inner();
+            file.read_to_end(&mut data)?;
+            Ok(Box::new(Cursor::new(compression.decompress(&data)?)))
+        }
+    }
+}
+
+/// The whole content of an input, mapped into memory if it is a plain local file
+enum InputBytes {
+    Mapped(MappedFile),
+    Read(Vec<u8>),
+}
+
+impl Deref for InputBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            InputBytes::Mapped(file) => file,
+            InputBytes::Read(data) => data,
+        }
+    }
+}
+
+/// The content of the input at 'path', see ['open_input']. Uncompressed local files are mapped
+/// into memory instead of being read.
+///
+/// # Errors
+///
+/// Will return an io error if the file can't be read, or an error if it can't be decompressed
+fn input_bytes(path: &str) -> Result<InputBytes, Box<dyn Error>> {
+    if path != STDIN_PATH && !remote::is_remote(path) && Compression::from_path(path).is_none() {
+        let file = MappedFile::open(path)?;
+        if Compression::from_magic(&file).is_none() {
+            return Ok(InputBytes::Mapped(file));
+        }
+    }
+    let mut data = vec![];
+    open_input(path)?.read_to_end(&mut data)?;
+    Ok(InputBytes::Read(data))
+}
+
+/// Reads the whole text of the file at 'path', decompressing it if needed, see ['open_input']
+///
+/// # Errors
+///
+/// Will return an error if the file can't be read or decompressed, or is not valid UTF-8
+pub fn read_input_string(path: &str) -> Result<String, Box<dyn Error>> {
+    let mut text = String::new();
+    open_input(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Character encoding of a text file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextEncoding {
+    /// UTF-8, with or without a byte order mark
+    #[default]
+    Utf8,
+    /// ISO-8859-1, as written by older spreadsheet programs
+    Latin1,
+}
+
+impl FromStr for TextEncoding {
+    type Err = UnknownEncodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(TextEncoding::Utf8),
+            "latin-1" | "latin1" | "iso-8859-1" => Ok(TextEncoding::Latin1),
+            _ => Err(UnknownEncodingError { encoding: s.to_string() }),
+        }
+    }
+}
+
+impl TextEncoding {
+    /// Decodes 'bytes' found at 'offset' in a file into text, without the byte order mark a file
+    /// may start with. UTF-8 text is borrowed from the bytes.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the bytes are not valid UTF-8 when decoding UTF-8
+    pub fn decode<'a>(&self, bytes: &'a [u8], offset: usize) -> Result<Cow<'a, str>, String> {
+        let text = match self {
+            TextEncoding::Utf8 => Cow::Borrowed(std::str::from_utf8(bytes).map_err(|e| {
+                format!("The text is not valid UTF-8 after byte {}, it may use another encoding", offset + e.valid_up_to())
+            })?),
+            // Every Latin-1 byte is the unicode code point of the same value
+            TextEncoding::Latin1 => Cow::Owned(bytes.iter().map(|b| char::from(*b)).collect()),
+        };
+        match (offset, text) {
+            (0, Cow::Borrowed(text)) => Ok(Cow::Borrowed(text.strip_prefix('\u{feff}').unwrap_or(text))),
+            (_, text) => Ok(text),
+        }
+    }
+}
+
+/// Characters and encoding of a csv file, such as the semicolon separated files written by
+/// spreadsheet programs in many European locales
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvFormat {
+    pub delimiter: u8,
+    /// Character quoting the cells holding the delimiter, None if quotes are ordinary characters
+    pub quote: Option<u8>,
+    pub encoding: TextEncoding,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        CsvFormat { delimiter: b',', quote: Some(b'"'), encoding: TextEncoding::Utf8 }
+    }
+}
+
+impl CsvFormat {
+    /// The default format for the file at 'path', delimited by tabs if it is a '.tsv' or '.tab'
+    /// file, compressed or not, and by commas otherwise
+    pub fn from_path(path: &str) -> CsvFormat {
+        let path = Compression::strip_extension(path).to_ascii_lowercase();
+        match path.ends_with(".tsv") || path.ends_with(".tab") {
+            true => CsvFormat { delimiter: b'\t', ..Default::default() },
+            false => CsvFormat::default(),
+        }
+    }
+
+    /// Parses a delimiter or quote character given by name ('tab', 'comma', 'semicolon', 'pipe')
+    /// or as a single ascii character, a backslash escaped 't' is a tab
+    ///
+    /// # Errors
+    ///
+    /// Will return a ['CsvCharacterError'] if the value is neither a name nor a single ascii
+    /// character
+    pub fn parse_char(value: &str) -> Result<u8, CsvCharacterError> {
+        match value.to_ascii_lowercase().as_str() {
+            "tab" | "\\t" | "\t" => Ok(b'\t'),
+            "comma" => Ok(b','),
+            "semicolon" => Ok(b';'),
+            "pipe" => Ok(b'|'),
+            _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+            _ => Err(CsvCharacterError { value: value.to_string() }),
+        }
+    }
+}
+
+/// Reads a csv file from a 'path' and converts it into a ['StringMatrix'].
+/// Every row of the csv file is part of the matrix, see ['read_csv_table'] for files with a header.
+///
+/// # Errors
+///
+/// Will return an io error if the file at the given path cannot be opened
+/// Will return an error if any rows in the csv file cannot be parsed
+fn read_csv_matrix(path: &str, format: &CsvFormat) -> Result<RowStringMatrix, Box<dyn Error>> {
+    read_csv_table(path, false, format).map(|(_, rows)| rows)
+}
+
+/// Reads a csv file from a 'path' into its header row, if 'has_headers' is set, and a
+/// ['StringMatrix'] of the remaining rows. Rows may have different lengths. Large files are parsed
+/// in place from a memory map, on several threads.
+///
+/// # Errors
+///
+/// Will return an io error if the file at the given path cannot be opened
+/// Will return an error if the file cannot be decompressed or any rows cannot be parsed
+fn read_csv_table(path: &str, has_headers: bool, format: &CsvFormat) -> Result<(Option<StringRow>, RowStringMatrix), Box<dyn Error>> {
+    read_csv_bytes(&input_bytes(path)?, has_headers, format)
+}
+
+/// Same as ['read_csv_table'] but reads the csv from any 'source'
+///
+/// # Errors
+///
+/// Will return an error if the source can't be read, decoded with the encoding of the 'format' or
+/// any rows can't be parsed
+fn read_csv_from<R: Read>(mut source: R, has_headers: bool, format: &CsvFormat) -> Result<(Option<StringRow>, RowStringMatrix), Box<dyn Error>> {
+    let mut bytes = vec![];
+    source.read_to_end(&mut bytes)?;
+    read_csv_bytes(&bytes, has_headers, format)
+}
+
+/// Parses the csv 'data', cut into chunks of whole records that are parsed on a thread each, see
+/// ['parallel::split_records']
+fn read_csv_bytes(data: &[u8], has_headers: bool, format: &CsvFormat) -> Result<(Option<StringRow>, RowStringMatrix), Box<dyn Error>> {
+    let chunks = parallel::split_records(data, format.delimiter, format.quote, parallel::chunk_count(data.len(), parallel::MIN_CHUNK_BYTES));
+    let mut offsets = vec![];
+    let mut offset = 0;
+    for chunk in chunks.iter() {
+        offsets.push(offset);
+        offset += chunk.len();
+    }
+    let parsed = parallel::map_chunks(&chunks, 1, |first, chunks| {
+        chunks.iter().enumerate()
+            .map(|(i, chunk)| parse_csv_chunk(chunk, offsets[first + i], has_headers && first + i == 0, format))
+            .collect::<Vec<_>>()
+    });
+    let mut headers = None;
+    let mut rows = Vec::new();
+    for result in parsed.into_iter().flatten() {
+        let (chunk_headers, mut chunk_rows) = result.map_err(|e| e as Box<dyn Error>)?;
+        headers = headers.or(chunk_headers);
+        rows.append(&mut chunk_rows);
+    }
+    Ok((headers, rows))
+}
+
+/// Parses the records of a 'chunk' found at 'offset' in the csv, with the header row if
+/// 'has_headers' is set
+fn parse_csv_chunk(chunk: &[u8], offset: usize, has_headers: bool, format: &CsvFormat) -> Result<(Option<StringRow>, RowStringMatrix), Box<dyn Error + Send + Sync>> {
+    let text = format.encoding.decode(chunk, offset)?;
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(has_headers)
+        .flexible(true)
+        .delimiter(format.delimiter)
+        .quote(format.quote.unwrap_or(b'"'))
+        .quoting(format.quote.is_some())
+        .from_reader(text.as_bytes());
+    let headers = match has_headers {
+        true => Some(reader.headers()?.iter().map(|x| x.trim().to_string()).collect()),
+        false => None,
+    };
+    let mut rows = Vec::new();
+    for result in reader.records() {
+        // read the csv
+        let record = result?;
+        // convert the record to a vector
+        let record = record.into_iter().map(|x| x.to_string()).collect();
+        rows.push(record);
+    }
+    Ok((headers, rows))
+}
+
+/// Index of the column holding each component of an edge within a links matrix
+#[derive(Debug, Clone)]
+pub struct LinkColumns {
+    pub from_name: usize,
+    pub from_id: usize,
+    pub to_name: usize,
+    pub to_id: usize,
+}
+
+impl Default for LinkColumns {
+    /// The order used by the standard links file
+    fn default() -> Self {
+        LinkColumns { from_name: 0, from_id: 1, to_name: 2, to_id: 3 }
+    }
+}
+
+/// Reference to a column of a table, either by its position or by the name in its header
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+impl ColumnRef {
+    /// Finds the index of the column. Names are compared to the 'headers' ignoring case and
+    /// surrounding whitespace.
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ColumnNotFoundError'] if the column is referenced by name and no header matches
+    pub fn resolve(&self, headers: &Option<StringRow>) -> Result<usize, ColumnNotFoundError> {
+        match self {
+            ColumnRef::Index(i) => Ok(*i),
+            ColumnRef::Name(name) => headers.iter().flatten()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| ColumnNotFoundError {
+                    column: name.to_string(),
+                    headers: headers.clone().unwrap_or_default(),
+                }),
+        }
+    }
+}
+
+impl FromStr for ColumnRef {
+    type Err = std::convert::Infallible;
+
+    /// Numbers are read as an index, anything else as a header name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim().parse::<usize>() {
+            Ok(i) => ColumnRef::Index(i),
+            Err(_) => ColumnRef::Name(s.trim().to_string()),
+        })
+    }
+}
+
+impl From<usize> for ColumnRef {
+    fn from(i: usize) -> Self { ColumnRef::Index(i) }
+}
+
+impl From<&str> for ColumnRef {
+    fn from(s: &str) -> Self { ColumnRef::Name(s.to_string()) }
+}
+
+/// Edge attribute names that are read automatically when a links table has a header with the name
+pub const KNOWN_EDGE_ATTRIBUTES: [&str; 5] = ["alpha", "weight", "capacity", "latency", "cost"];
+
+/// Describes which columns of a links table hold the components of each edge, and optionally
+/// values of the edge or its nodes that live in the same table.
+#[derive(Debug, Clone)]
+pub struct ColumnMapping {
+    pub from_name: ColumnRef,
+    pub from_id: ColumnRef,
+    pub to_name: ColumnRef,
+    pub to_id: ColumnRef,
+    /// Columns holding named numeric attributes of the edge, such as alpha or capacity
+    pub edge_attributes: Vec<(String, ColumnRef)>,
+    /// Column holding the off chance of the from node
+    pub from_off_chance: Option<ColumnRef>,
+    /// Column holding the off chance of the to node
+    pub to_off_chance: Option<ColumnRef>,
+}
+
+impl Default for ColumnMapping {
+    /// The column order used by the standard links file
+    fn default() -> Self {
+        ColumnMapping {
+            from_name: 0.into(),
+            from_id: 1.into(),
+            to_name: 2.into(),
+            to_id: 3.into(),
+            edge_attributes: vec![],
+            from_off_chance: None,
+            to_off_chance: None,
+        }
+    }
+}
+
+impl ColumnMapping {
+    /// A mapping using the header names 'from_name', 'from_id', 'to_name', 'to_id', and the
+    /// optional 'from_off_chance', 'to_off_chance' and ['KNOWN_EDGE_ATTRIBUTES'] columns if the
+    /// headers have them
+    pub fn from_headers(headers: &StringRow) -> ColumnMapping {
+        let optional = |name: &str| {
+            let column = ColumnRef::from(name);
+            column.resolve(&Some(headers.clone())).ok().map(|_| column)
+        };
+        ColumnMapping {
+            from_name: "from_name".into(),
+            from_id: "from_id".into(),
+            to_name: "to_name".into(),
+            to_id: "to_id".into(),
+            edge_attributes: KNOWN_EDGE_ATTRIBUTES.iter()
+                .filter_map(|name| optional(name).map(|column| (name.to_string(), column)))
+                .collect(),
+            from_off_chance: optional("from_off_chance"),
+            to_off_chance: optional("to_off_chance"),
+        }
+    }
+
+    /// Resolves the edge components to column indexes
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ColumnNotFoundError'] if a named column is not in the 'headers'
+    pub fn link_columns(&self, headers: &Option<StringRow>) -> Result<LinkColumns, ColumnNotFoundError> {
+        Ok(LinkColumns {
+            from_name: self.from_name.resolve(headers)?,
+            from_id: self.from_id.resolve(headers)?,
+            to_name: self.to_name.resolve(headers)?,
+            to_id: self.to_id.resolve(headers)?,
+        })
+    }
+}
+
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    /// Reads a comma separated list of 'component=column' assignments, such as
+    /// 'from_id=source,to_id=target,alpha=weight'. Components that aren't listed keep the column of
+    /// the standard links file. Any name other than the edge components and off chances is read as
+    /// a named edge attribute.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mapping = ColumnMapping::default();
+        for assignment in s.split(',').filter(|a| !a.trim().is_empty()) {
+            let (key, column) = assignment.split_once('=')
+                .ok_or_else(|| format!("Expected 'component=column' but found '{}'", assignment))?;
+            let column: ColumnRef = column.parse().unwrap();
+            match key.trim() {
+                "from_name" => mapping.from_name = column,
+                "from_id" => mapping.from_id = column,
+                "to_name" => mapping.to_name = column,
+                "to_id" => mapping.to_id = column,
+                "from_off_chance" => mapping.from_off_chance = Some(column),
+                "to_off_chance" => mapping.to_off_chance = Some(column),
+                "" => return Err(format!("Missing the component name in '{}'", assignment)),
+                attribute => mapping.edge_attributes.push((attribute.to_string(), column)),
+            }
+        }
+        Ok(mapping)
+    }
+}
+
+/// Creates a graph and the values living in the same table from the 'rows' of a links table
+/// described by a 'mapping'. Off chances of a node given on several rows must agree, the last
+/// one read is kept.
+///
+/// # Errors
+///
+/// Will return an error if a column of the mapping can't be found or any cell is invalid
+fn read_mapped_links(headers: &Option<StringRow>, rows: &RowStringMatrix, mapping: &ColumnMapping, policies: &LinkPolicies) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+    let columns = mapping.link_columns(headers)?;
+    let (graph, edges, warnings) = create_graph(rows, &columns, policies)?;
+    let mut data = CriticalityData { warnings, ..Default::default() };
+    for (name, column) in mapping.edge_attributes.iter() {
+        let values = create_listed_edge_value_map(rows, &edges, column.resolve(headers)?)?;
+        data.add_edge_attribute(name, values);
+    }
+    if let Some(off_chance) = &mapping.from_off_chance {
+        data.off_chances.append(&mut create_node_value_map(rows, columns.from_id, off_chance.resolve(headers)?, &graph.keys())?);
+    }
+    if let Some(off_chance) = &mapping.to_off_chance {
+        data.off_chances.append(&mut create_node_value_map(rows, columns.to_id, off_chance.resolve(headers)?, &graph.keys())?);
+    }
+    Ok((graph, data))
+}
+
+/// Table holding attributes of the nodes, one node per row
+#[derive(Debug, Clone)]
+pub struct NodeAttributeTable {
+    /// The file path or sheet name of the table
+    pub source: String,
+    pub id: ColumnRef,
+    /// (attribute name, column) of every attribute. If empty, every column other than the id is
+    /// read using its header as the attribute name.
+    pub columns: Vec<(String, ColumnRef)>,
+}
+
+/// Sets the attributes found in the 'rows' of a node attribute table on the nodes of the 'graph'.
+/// Cells are typed with ['AttrValue::parse'], empty cells are skipped. Rows of nodes that are not
+/// part of the graph are ignored with a warning.
+///
+/// # Errors
+///
+/// Will return an error if a column can't be found or a node id is neither numeric nor the key of
+/// a node
+fn apply_node_attributes(graph: &mut Graph, headers: &Option<StringRow>, rows: &RowStringMatrix, table: &NodeAttributeTable) -> Result<(), Box<dyn Error>> {
+    let id_col = table.id.resolve(headers)?;
+    let columns: Vec<(String, usize)> = match (table.columns.is_empty(), headers) {
+        (true, Some(headers)) => headers.iter().enumerate()
+            .filter(|(i, _)| *i != id_col)
+            .map(|(i, h)| (h.to_string(), i))
+            .collect(),
+        _ => table.columns.iter()
+            .map(|(name, col)| col.resolve(headers).map(|i| (name.to_string(), i)))
+            .collect::<Result<_, _>>()?,
+    };
+
+    let keys = graph.keys();
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        let id: u32 = match get_id_cell(row, (id_col, y), id_col, &keys, &mut errors) {
+            None => continue,
+            Some(id) => id,
+        };
+        if graph.get_node(&id).is_none() {
+            warn!("Node {} of the attribute table {} is not part of the graph", id, table.source);
+            continue;
+        }
+        for (name, col) in columns.iter() {
+            match row.get(*col).map(|c| c.trim()) {
+                None | Some("") => {}
+                Some(cell) => { graph.set_node_attr(&id, name, AttrValue::parse(cell)); }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Box::new(CreateError {
+            task: "reading node attributes".to_string(),
+            errors,
+            input: rows.clone(),
+        }))
+    }
+}
+
+/// Creates a graph from a links 'string_matrix'.
+/// A link ['StringMatrix'] is a matrix where each row is an edge composed of 4
+/// components: from node name, from node id, to node name, to node id. The 'columns' give the
+/// position of each component within a row, the standard links file uses exactly the order listed.
+/// This function also return a list of edges which can be passed to the ['read_edge_state_map']
+/// function to map any column of values to each edge. The edges returned are in the order that
+/// they appear in the input matrix.
+///
+/// Parallel edges, several rows linking the same two nodes, are kept apart by routing each of them
+/// through a link node of its own, named 'from->to' with an id after every node id. The link
+/// nodes are ordinary nodes that can fail individually, and the edge from the link node carries
+/// the values of the row.
+///
+/// Node ids don't have to be numeric: numeric ids are used as they are, and every other id, such
+/// as a UUID, is interned to an id after the largest numeric one in the order the ids appear. The
+/// string id is kept in the ['KEY_ATTR'] attribute of its node.
+///
+/// # Errors
+///
+/// Will return a ['GraphCreationError'] if the 'string_matrix' is somehow invalid.
+/// The 'string_matrix' can be invalid if:
+/// * A row is missing one of the components
+/// * The from node id or to node id is empty
+fn create_graph(edges_matrix: &RowStringMatrix, columns: &LinkColumns, policies: &LinkPolicies) -> Result<(Graph, EdgeList, Vec<String>), CreateError<RowStringMatrix>> {
+    let mut graph = Graph::new();
+    let mut errors: Vec<String> = vec![];
+    let mut warnings: Vec<String> = vec![];
+    let mut edges = vec![];
+
+    let mut names = vec![];
+    let mut keys = vec![];
+    let mut links = vec![];
+    let mut counts: HashMap<(u32, u32), usize> = HashMap::new();
+    // The cells of large tables are read by several threads, and collected in the order of the rows
+    let parts = parallel::map_chunks(edges_matrix, parallel::MIN_CHUNK_ROWS, |first, rows| {
+        let (mut names, mut keys, mut errors) = (vec![], vec![], vec![]);
+        for (y, row) in rows.iter().enumerate().map(|(i, row)| (first + i, row)) {
+            // Get the name and ID of the child and parent nodes
+            let c_name = get_string_cell(row, (columns.from_name, y), columns.from_name, &mut errors).unwrap_or(DEFAULT_NODE_NAME.to_string());
+            let p_name = get_string_cell(row, (columns.to_name, y), columns.to_name, &mut errors).unwrap_or(DEFAULT_NODE_NAME.to_string());
+            let c_key = CellKey::from(get_key_cell(row, (columns.from_id, y), columns.from_id, &mut errors));
+            let p_key = CellKey::from(get_key_cell(row, (columns.to_id, y), columns.to_id, &mut errors));
+            keys.push((c_key, p_key));
+            names.push((c_name, p_name));
+        }
+        (names, keys, errors)
+    });
+    for (mut part_names, mut part_keys, mut part_errors) in parts {
+        names.append(&mut part_names);
+        keys.append(&mut part_keys);
+        errors.append(&mut part_errors);
+    }
+
+    let ids = match intern_keys(keys.iter().flat_map(|(c_key, p_key)| [c_key, p_key])) {
+        Ok(ids) => ids,
+        Err(e) => return Err(CreateError {
+            task: "creating a graph".to_string(),
+            errors: vec![e.to_string()],
+            input: edges_matrix.clone(),
+        }),
+    };
+    for ((c_name, p_name), (c_key, p_key)) in names.into_iter().zip(keys.iter()) {
+        let (c_id, p_id) = (c_key.id(&ids), p_key.id(&ids));
+        *counts.entry((c_id, p_id)).or_default() += 1;
+        links.push((c_name, c_id, p_name, p_id));
+    }
+
+    let mut next_link_id = links.iter().map(|(_, c_id, _, p_id)| *c_id.max(p_id)).max().unwrap_or(0).checked_add(1);
+    let mut seen: HashSet<(u32, u32)> = HashSet::new();
+    for (y, (c_name, c_id, p_name, p_id)) in links.into_iter().enumerate() {
+        // Only rows repeating an edge may need a link node
+        let link_name = (counts[&(c_id, p_id)] > 1).then(|| format!("{}->{}", c_name, p_name));
+        // Add both nodes and an edge connecting the two
+        graph.add_node(c_name, c_id);
+        graph.add_node(p_name, p_id);
+        for (key, id) in [&keys[y].0, &keys[y].1].into_iter().zip([c_id, p_id]) {
+            if let CellKey::Text(key) = key {
+                graph.set_node_attr(&id, KEY_ATTR, AttrValue::Text(key.to_string()));
+            }
+        }
+        let repeated = !seen.insert((c_id, p_id));
+        let (policy, problem) = match (c_id == p_id, counts[&(c_id, p_id)] > 1) {
+            (true, _) => (policies.self_loops, format!("Row {} links node {} to itself", y + 1, c_id)),
+            (false, true) => (policies.duplicates, format!("Row {} repeats the edge ({}, {})", y + 1, c_id, p_id)),
+            (false, false) => {
+                graph.add_edge(c_id, p_id);
+                edges.push(Some((c_id, p_id)));
+                continue;
+            }
+        };
+        match policy {
+            EdgePolicy::Reject if c_id == p_id || repeated => {
+                errors.push(problem);
+                edges.push(None);
+            }
+            EdgePolicy::Drop if c_id == p_id || repeated => {
+                warn!("{}, the row is dropped", problem);
+                warnings.push(format!("{}, the row is dropped", problem));
+                edges.push(None);
+            }
+            // Parallel edges are kept apart by link nodes
+            EdgePolicy::Keep if c_id != p_id => {
+                if !repeated {
+                    warn!("The parallel edges from node {} to node {} are kept apart by link nodes", c_id, p_id);
+                    warnings.push(format!("The parallel edges from node {} to node {} are kept apart by link nodes", c_id, p_id));
+                }
+                let Some(link_id) = next_link_id else {
+                    errors.push(format!("Row {} needs a link node, but every node id up to {} is taken", y + 1, u32::MAX));
+                    edges.push(None);
+                    continue;
+                };
+                graph.add_node(link_name.unwrap_or_default(), link_id);
+                graph.add_edge(c_id, link_id);
+                graph.add_edge(link_id, p_id);
+                edges.push(Some((link_id, p_id)));
+                next_link_id = link_id.checked_add(1);
+            }
+            // Self loops that are kept, and the first of repeated edges that aren't
+            _ => {
+                graph.add_edge(c_id, p_id);
+                edges.push(Some((c_id, p_id)));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok((graph, edges, warnings))
+    } else {
+        Err(CreateError {
+            task: "creating a graph".to_string(),
+            errors,
+            input: edges_matrix.clone(),
+        })
+    }
+}
+
+
+fn create_edge_value_map<T: Clone + FromStr>(edges: &EdgeList, col: &StringCol, defaults: T) -> Result<EdgeValueMap<T>, CreateError<StringCol>>{
+    let mut map = EdgeValueMap::new();
+    let mut errors: Vec<String> = vec![];
+    for (y, edge) in edges.iter().enumerate() {
+        let value = get_from_str_cell(col, (0, y), y, &mut errors).unwrap_or(defaults.clone());
+        if let Some(edge) = edge {
+            map.insert(*edge, value);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(map)
+    } else {
+        Err(CreateError {
+            task: "creating an edge value map".to_string(),
+            errors,
+            input: col.clone(),
+        })
+    }
+}
+
+/// Creates an edge value map from a matrix where every row holds a value at the 'value_col' index
+/// for the edge of the row in the 'edges' returned by ['create_graph'].
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a cell can't be parsed
+fn create_listed_edge_value_map<T: FromStr>(matrix: &RowStringMatrix, edges: &EdgeList, value_col: usize) -> Result<EdgeValueMap<T>, CreateError<RowStringMatrix>>{
+    let mut map = EdgeValueMap::new();
+    let mut errors: Vec<String> = vec![];
+    for (y, (row, edge)) in matrix.iter().zip(edges.iter()).enumerate() {
+        let Some(edge) = edge else { continue };
+        if let Some(value) = get_from_str_cell(row, (value_col, y), value_col, &mut errors) {
+            map.insert(*edge, value);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(map)
+    } else {
+        Err(CreateError {
+            task: "creating an edge value map".to_string(),
+            errors,
+            input: matrix.clone(),
+        })
+    }
+}
+
+/// Creates an edge value map from a matrix where every row holds the from node id, the to node id
+/// and a value at the given column indexes.
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a row is missing a column or a cell can't be parsed
+fn create_keyed_edge_value_map<T: FromStr>(matrix: &RowStringMatrix, from_col: usize, to_col: usize, value_col: usize, keys: &HashMap<String, u32>) -> Result<EdgeValueMap<T>, CreateError<RowStringMatrix>>{
+    let mut map = EdgeValueMap::new();
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in matrix.iter().enumerate() {
+        let from = get_id_cell(row, (from_col, y), from_col, keys, &mut errors);
+        let to = get_id_cell(row, (to_col, y), to_col, keys, &mut errors);
+        let value = get_from_str_cell(row, (value_col, y), value_col, &mut errors);
+        if let (Some(from), Some(to), Some(value)) = (from, to, value) {
+            map.insert((from, to), value);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(map)
+    } else {
+        Err(CreateError {
+            task: "creating an edge value map".to_string(),
+            errors,
+            input: matrix.clone(),
+        })
+    }
+}
+
+/// Creates a node value map from a matrix where every row holds a node id and a value at the
+/// given column indexes.
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a row is missing a column or a cell can't be parsed
+fn create_node_value_map<T: FromStr>(matrix: &RowStringMatrix, id_col: usize, value_col: usize, keys: &HashMap<String, u32>) -> Result<NodeValueMap<T>, CreateError<RowStringMatrix>>{
+    let mut map = NodeValueMap::new();
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in matrix.iter().enumerate() {
+        let id = get_id_cell(row, (id_col, y), id_col, keys, &mut errors);
+        let value = get_from_str_cell(row, (value_col, y), value_col, &mut errors);
+        if let (Some(id), Some(value)) = (id, value) {
+            map.insert(id, value);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(map)
+    } else {
+        Err(CreateError {
+            task: "creating a node value map".to_string(),
+            errors,
+            input: matrix.clone(),
+        })
+    }
+}
+
+/// Reads named scenarios from a csv file where every row holds a scenario name, a node id and
+/// the state of the node ('on' / 'off', '1' / '0' or 'true' / 'false'). Rows of the same
+/// scenario don't have to be adjacent, the scenarios keep the order they first appear in.
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a row is missing a column, an id is not numeric or a state
+/// is not recognised
+pub fn read_scenarios(path: &str, has_headers: bool) -> Result<Vec<Scenario>, Box<dyn Error>> {
+    let (_, rows) = read_csv_table(path, has_headers, &CsvFormat::from_path(path))?;
+    let mut scenarios: Vec<Scenario> = vec![];
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        let name = get_string_cell(row, (0, y), 0, &mut errors);
+        let id = get_from_str_cell::<u32>(row, (1, y), 1, &mut errors);
+        let state = get_string_cell(row, (2, y), 2, &mut errors).and_then(|cell| {
+            match cell.to_ascii_lowercase().as_str() {
+                "on" | "1" | "true" => Some(VISIBLE_VAL),
+                "off" | "0" | "false" => Some(INVISIBLE_VAL),
+                _ => {
+                    errors.push(NodeStateError { cell_pos: (2, y), cell_val: cell }.to_string());
+                    None
+                }
+            }
+        });
+        if let (Some(name), Some(id), Some(state)) = (name, id, state) {
+            match scenarios.iter_mut().find(|s| s.name == name) {
+                Some(scenario) => { scenario.states.insert(id, state); }
+                None => scenarios.push(Scenario { name, states: NodeValueMap::from([(id, state)]) }),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(scenarios)
+    } else {
+        Err(Box::new(CreateError {
+            task: "reading the scenarios".to_string(),
+            errors,
+            input: rows,
+        }))
+    }
+}
+
+/// Reads a flattened event tree from a csv file where every row is a sequence: its name, the
+/// outcome of the end node ('success' / 'failure'), any number of branch probabilities that are
+/// multiplied and the consequence of the sequence in the last column.
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a row has less than three columns, an outcome is not
+/// recognised or a probability or consequence is not numeric
+pub fn read_event_tree(path: &str, has_headers: bool) -> Result<EventTree, Box<dyn Error>> {
+    let (_, rows) = read_csv_table(path, has_headers, &CsvFormat::from_path(path))?;
+    let mut tree = EventTree::default();
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        let last = row.len().max(3) - 1;
+        let name = get_string_cell(row, (0, y), 0, &mut errors);
+        let outcome = get_string_cell(row, (1, y), 1, &mut errors).and_then(|cell| {
+            match cell.to_ascii_lowercase().as_str() {
+                "success" | "on" | "1" | "true" => Some(Outcome::Success),
+                "failure" | "off" | "0" | "false" => Some(Outcome::Failure),
+                _ => {
+                    errors.push(NodeStateError { cell_pos: (1, y), cell_val: cell }.to_string());
+                    None
+                }
+            }
+        });
+        let probabilities: Vec<Option<f64>> = (2..last).map(|x| get_from_str_cell(row, (x, y), x, &mut errors)).collect();
+        let consequence = get_from_str_cell::<f64>(row, (last, y), last, &mut errors);
+        if let (Some(name), Some(outcome), Some(consequence)) = (name, outcome, consequence) {
+            let probability = probabilities.into_iter().map(|p| p.unwrap_or(1.0)).product();
+            tree.sequences.push(Sequence { name, outcome, probability, consequence });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(tree)
+    } else {
+        Err(Box::new(CreateError {
+            task: "reading the event tree".to_string(),
+            errors,
+            input: rows,
+        }))
+    }
+}
+
+/// Reads common-cause groups from a csv file where every row holds a group name, the id of a
+/// member and the beta factor of the group. The beta of the first row of a group is used.
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a row is missing a column or a cell is not numeric
+pub fn read_ccf_groups(path: &str, has_headers: bool) -> Result<Vec<CcfGroup>, Box<dyn Error>> {
+    let (_, rows) = read_csv_table(path, has_headers, &CsvFormat::from_path(path))?;
+    let mut groups: Vec<CcfGroup> = vec![];
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        let name = get_string_cell(row, (0, y), 0, &mut errors);
+        let id = get_from_str_cell::<u32>(row, (1, y), 1, &mut errors);
+        let beta = get_from_str_cell::<f32>(row, (2, y), 2, &mut errors);
+        if let (Some(name), Some(id), Some(beta)) = (name, id, beta) {
+            match groups.iter_mut().find(|g| g.name == name) {
+                Some(group) => {
+                    if group.beta != beta {
+                        warn!("The group '{}' has several betas, {} is used", name, group.beta);
+                    }
+                    group.members.push(id);
+                }
+                None => groups.push(CcfGroup { name, beta, members: vec![id] }),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(groups)
+    } else {
+        Err(Box::new(CreateError {
+            task: "reading the common-cause groups".to_string(),
+            errors,
+            input: rows,
+        }))
+    }
+}
+
+/// Reads groups of nodes from a csv file where every row holds a group name and the id of a
+/// member, in the order the groups first appear
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a row is missing a column or an id is not numeric
+pub fn read_node_groups(path: &str, has_headers: bool) -> Result<Vec<NodeGroup>, Box<dyn Error>> {
+    let (_, rows) = read_csv_table(path, has_headers, &CsvFormat::from_path(path))?;
+    let mut groups: Vec<NodeGroup> = vec![];
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        let name = get_string_cell(row, (0, y), 0, &mut errors);
+        let id = get_from_str_cell::<u32>(row, (1, y), 1, &mut errors);
+        if let (Some(name), Some(id)) = (name, id) {
+            match groups.iter_mut().find(|g| g.name == name) {
+                Some(group) => group.members.push(id),
+                None => groups.push(NodeGroup { name, members: vec![id] }),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(groups)
+    } else {
+        Err(Box::new(CreateError {
+            task: "reading the node groups".to_string(),
+            errors,
+            input: rows,
+        }))
+    }
+}
+
+/// Reads dependencies between nodes from a csv file where every row holds the id of the trigger
+/// node, the id of the dependent node and the effect: 'off' if the dependent node is off whenever
+/// the trigger is, or the off chance of the dependent node while the trigger is off
+///
+/// # Errors
+///
+/// Will return a ['CreateError'] if a row is missing a column, an id is not numeric or an effect
+/// is neither 'off' nor a chance between 0 and 1
+pub fn read_dependencies(path: &str, has_headers: bool) -> Result<Vec<Dependency>, Box<dyn Error>> {
+    let (_, rows) = read_csv_table(path, has_headers, &CsvFormat::from_path(path))?;
+    let mut dependencies = vec![];
+    let mut errors: Vec<String> = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        let trigger = get_from_str_cell::<u32>(row, (0, y), 0, &mut errors);
+        let dependent = get_from_str_cell::<u32>(row, (1, y), 1, &mut errors);
+        let effect = get_string_cell(row, (2, y), 2, &mut errors).and_then(|effect| {
+            match (effect.to_ascii_lowercase().as_str(), effect.parse::<f32>()) {
+                ("off", _) => Some(DependencyEffect::Off),
+                (_, Ok(chance)) if (0.0..=1.0).contains(&chance) => Some(DependencyEffect::OffChance(chance)),
+                _ => {
+                    errors.push(format!("The effect '{}' at (2, {}) is neither 'off' nor a chance between 0 and 1", effect, y));
+                    None
+                }
+            }
+        });
+        if let (Some(trigger), Some(dependent), Some(effect)) = (trigger, dependent, effect) {
+            dependencies.push(Dependency { trigger, dependent, effect });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(dependencies)
+    } else {
+        Err(Box::new(CreateError {
+            task: "reading the dependencies".to_string(),
+            errors,
+            input: rows,
+        }))
+    }
+}
+
+/// Get a string value from a 'row' in a ['StringMatrix'] and the 'pos' of the value.
+/// The 'pos' is (x, y) where x is the index of value within the row, and y is the index of the
+/// row within the string matrix.
+///
+/// # Errors
+///
+/// All errors are added to the 'errors' list which is meant to be passed to a ['GraphCreationError']
+/// Returns none if the index of the value is not in the row
+fn get_string_cell(list: &[String], pos: (usize, usize), cell_i: usize, errors: &mut Vec<String>) -> Option<String> {
+    match list.get(cell_i) {
+        Some(x) => {
+            Some(x.trim().to_string())
+        }
+        None => {
+            errors.push(errors::input::CellNotFoundError {
+                cell_pos: pos,
+            }.to_string());
+            None
+        }
+    }
+}
+
+/// Call ['get_string_cell'] and then attempt to convert the result to type T
+///
+/// # Errors
+///
+/// * Returns None if ['get_string_cell'] return None
+/// * Return None if the value at the given 'pos' cannot be converted to type T
+fn get_from_str_cell<T>(list: &[String], pos: (usize, usize), cell_i: usize, errors: &mut Vec<String>) -> Option<T>
+where T: FromStr
+{
+    let string_val = get_string_cell(list, pos, cell_i, errors).unwrap_or(DEFAULT_NODE_NAME.to_string());
+    match string_val.parse::<T>() {
+        Ok(x) => {
+            Some(x)
+        }
+        Err(_e) => {
+            errors.push(
+                CellNotNumericError::<T> { cell_pos: pos, cell_val: string_val, ..Default::default() }.to_string()
+            );
+            None
+        }
+    }
+
+}
+
+/// Call ['get_string_cell'] for the id of a node, which may be numeric or a string key
+///
+/// # Errors
+///
+/// * Returns the ['DEFAULT_NODE_ID'] as a key if ['get_string_cell'] returns None
+/// * Returns the ['DEFAULT_NODE_ID'] as a key if the cell is empty
+fn get_key_cell(list: &[String], pos: (usize, usize), cell_i: usize, errors: &mut Vec<String>) -> String {
+    match get_string_cell(list, pos, cell_i, errors) {
+        Some(key) if !key.is_empty() => key,
+        Some(key) => {
+            errors.push(UnknownNodeKeyError { cell_pos: pos, key }.to_string());
+            DEFAULT_NODE_ID.to_string()
+        }
+        None => DEFAULT_NODE_ID.to_string(),
+    }
+}
+
+/// Call ['get_string_cell'] and resolve the result to a node id, string keys are looked up in
+/// 'keys' and numeric ones are used as they are
+///
+/// # Errors
+///
+/// * Returns None if ['get_string_cell'] return None
+/// * Returns None if the value is neither a key of 'keys' nor numeric
+fn get_id_cell(list: &[String], pos: (usize, usize), cell_i: usize, keys: &HashMap<String, u32>, errors: &mut Vec<String>) -> Option<u32> {
+    let key = get_string_cell(list, pos, cell_i, errors)?;
+    match keys.get(&key).copied().or_else(|| key.parse::<u32>().ok()) {
+        Some(id) => Some(id),
+        None => {
+            errors.push(UnknownNodeKeyError { cell_pos: pos, key }.to_string());
+            None
+        }
+    }
+}
+
+/// Key of a node as read from its cell
+enum CellKey {
+    /// A numeric key, which is the id of the node
+    Id(u32),
+    Text(String),
+}
+
+impl From<String> for CellKey {
+    fn from(key: String) -> CellKey {
+        match key.parse::<u32>() {
+            Ok(id) => CellKey::Id(id),
+            Err(_) => CellKey::Text(key),
+        }
+    }
+}
+
+impl CellKey {
+    /// The id of the node, text keys are looked up in the 'ids' from ['intern_keys']
+    fn id(&self, ids: &HashMap<String, u32>) -> u32 {
+        match self {
+            CellKey::Id(id) => *id,
+            CellKey::Text(key) => ids[key],
+        }
+    }
+}
+
+/// Ids of the text 'keys' in the order they appear, after the largest numeric key
+///
+/// # Errors
+///
+/// Returns an ['IdOverflowError'] for the first text key without a free id up to u32::MAX
+fn intern_keys<'a>(keys: impl Iterator<Item = &'a CellKey> + Clone) -> Result<HashMap<String, u32>, IdOverflowError> {
+    let mut next_id = match keys.clone()
+        .filter_map(|key| match key {
+            CellKey::Id(id) => Some(*id),
+            CellKey::Text(_) => None,
+        })
+        .max() {
+        Some(id) => id.checked_add(1),
+        None => Some(0),
+    };
+    let mut ids: HashMap<String, u32> = HashMap::new();
+    for key in keys {
+        if let CellKey::Text(key) = key {
+            if !ids.contains_key(key) {
+                let id = next_id.ok_or_else(|| IdOverflowError { key: key.to_string() })?;
+                ids.insert(key.to_string(), id);
+                next_id = id.checked_add(1);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// What happens to a row of a links table that is a self loop or repeats an edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgePolicy {
+    /// Reading the links fails
+    Reject,
+    /// The row is left out with a warning, of repeated edges the first row is kept
+    Drop,
+    /// Self loops are added to the graph, repeated edges are kept apart by link nodes
+    Keep,
+}
+
+impl FromStr for EdgePolicy {
+    type Err = UnknownEdgePolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "reject" => Ok(EdgePolicy::Reject),
+            "drop" => Ok(EdgePolicy::Drop),
+            "keep" => Ok(EdgePolicy::Keep),
+            _ => Err(UnknownEdgePolicyError { policy: s.to_string() }),
+        }
+    }
+}
+
+/// The ['EdgePolicy'] of self loops and of repeated edges. Both are kept by default, the warnings
+/// about them are part of the ['CriticalityData'] read with the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkPolicies {
+    pub self_loops: EdgePolicy,
+    pub duplicates: EdgePolicy,
+}
+
+impl Default for LinkPolicies {
+    fn default() -> Self {
+        LinkPolicies { self_loops: EdgePolicy::Keep, duplicates: EdgePolicy::Keep }
+    }
+}
+
+/// What the 'from' and 'to' of the edges of an input mean
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeDirection {
+    /// Edges lead from the child to the parent it is rolled up into, the direction of the graph
+    #[default]
+    ChildToParent,
+    /// Edges lead from the parent to its child, like source -> target exports of dependencies
+    ParentToChild,
+    /// Edges have no direction, like the links of a mesh network. They are oriented away from the
+    /// start node by ['orient_undirected'] once it is known.
+    Undirected,
+}
+
+impl EdgeDirection {
+    /// Turns the read graph and its edge attributes into the child -> parent direction. Undirected
+    /// graphs are left as read.
+    pub fn orient(&self, graph: &mut Graph, data: &mut CriticalityData) {
+        if *self != EdgeDirection::ParentToChild {
+            return;
+        }
+        graph.reverse_edges();
+        for values in data.edge_attributes.values_mut() {
+            *values = values.iter().map(|((from, to), value)| ((*to, *from), *value)).collect();
+        }
+    }
+}
+
+/// Orients the edges of an undirected graph away from the 'start' node, see ['Graph::orient_from'],
+/// and moves the attributes of the turned edges along. Attributes of an edge given in both
+/// directions keep the values of the edge in the kept direction.
+pub fn orient_undirected(graph: &mut Graph, data: &mut CriticalityData, start: u32) {
+    let reversed = graph.orient_from(start);
+    for values in data.edge_attributes.values_mut() {
+        for (from, to) in reversed.iter() {
+            if let Some(value) = values.remove(&(*from, *to)) {
+                values.entry((*to, *from)).or_insert(value);
+            }
+        }
+    }
+}
+
+impl FromStr for EdgeDirection {
+    type Err = UnknownDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "child-to-parent" => Ok(EdgeDirection::ChildToParent),
+            "parent-to-child" => Ok(EdgeDirection::ParentToChild),
+            "undirected" => Ok(EdgeDirection::Undirected),
+            _ => Err(UnknownDirectionError { direction: s.to_string() }),
+        }
+    }
+}
+
+/// Configurations which holds information necessary to read values for the critically analysis
+/// using the STD (standard) input
+pub struct STDCritConfigs {
+    /// The path to the input file
+    pub in_path: String,
+    /// The path to the file of the alpha values of the edges, see ['alpha_path']. None if the
+    /// input has no place to look for it, such as the standard input.
+    pub alpha_path: Option<String>,
+    /// Characters and encoding of the input file and of the alpha file
+    pub format: CsvFormat,
+}
+
+/// Name of the file of alpha values read next to a links csv without headers
+pub const ALPHA_FILE: &str = "alpha.csv";
+
+/// The ['ALPHA_FILE'] in the directory of the input at 'in_path', a local path or a url. None
+/// for the standard input.
+pub fn alpha_path(in_path: &str) -> Option<String> {
+    match in_path {
+        STDIN_PATH => None,
+        url if remote::is_remote(url) => Some(match url.rsplit_once('/') {
+            Some((dir, _)) if !dir.ends_with('/') => format!("{}/{}", dir, ALPHA_FILE),
+            _ => format!("{}/{}", url.trim_end_matches('/'), ALPHA_FILE),
+        }),
+        path => Some(Path::new(path).with_file_name(ALPHA_FILE).to_string_lossy().to_string()),
+    }
+}
+
+/// Structure used to read all the values necessary for a criticality analysis
+pub struct STDCritInput {}
+impl Input for STDCritInput {
+    type Configs = STDCritConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: STDCritConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let links_map = read_csv_matrix(&configs.in_path, &configs.format)?;
+        debug!("row map: {:?}", links_map);
+        let col = row_to_col_matrix(&links_map);
+        debug!("col map: {:?}", col);
+        debug!("back to row map: {:?}", col_to_row_matrix(&col));
+        let alpha_path = configs.alpha_path.ok_or_else(|| AlphaFileError {
+            path: None,
+            reason: format!("the input {} has no directory to look for {} in, give it with '--alpha <path>'", configs.in_path, ALPHA_FILE),
+        })?;
+        let alpha_matrix = read_csv_matrix(&alpha_path, &configs.format)
+            .map_err(|e| AlphaFileError { path: Some(alpha_path.clone()), reason: e.to_string() })?;
+        let alpha_col = alpha_matrix.first()
+            .ok_or_else(|| AlphaFileError { path: Some(alpha_path.clone()), reason: "the file is empty".to_string() })?;
+        let (graph, edges, _) =  create_graph(&links_map, &LinkColumns::default(), &LinkPolicies::default())?;
+        let alpha = create_edge_value_map(&edges, alpha_col, -1.0f32)?;
+        let mut data = CriticalityData::default();
+        data.add_edge_attribute(ALPHA_ATTR, alpha);
+        Ok((graph, data))
+    }
+}
+/// File holding a value for every node, identified by the node id
+#[derive(Debug, Clone)]
+pub struct NodeValueFile {
+    pub path: String,
+    pub id: ColumnRef,
+    pub value: ColumnRef,
+}
+
+/// Configurations which hold information necessary to read values for the criticality analysis
+/// from a csv file with any column layout
+#[derive(Debug, Clone)]
+pub struct CsvCritConfigs {
+    /// The path to the links file
+    pub in_path: String,
+    /// Whether the first row of the files is a header
+    pub has_headers: bool,
+    /// Columns of the links file. If none is given the header names are used, see
+    /// ['ColumnMapping::from_headers']
+    pub mapping: Option<ColumnMapping>,
+    /// Separate file with the off chance of each node, using the same header setting
+    pub probabilities: Option<NodeValueFile>,
+    /// Separate file with typed attributes of the nodes, using the same header setting
+    pub node_attributes: Option<NodeAttributeTable>,
+    /// What the from and to columns of the links mean
+    pub direction: EdgeDirection,
+    /// What happens to self loops and repeated rows of the links
+    pub policies: LinkPolicies,
+    /// Characters and encoding of every file
+    pub format: CsvFormat,
+}
+
+/// Structure used to read all the values necessary for a criticality analysis from a csv file
+/// whose columns are described by a ['ColumnMapping']
+pub struct CsvCritInput {}
+impl Input for CsvCritInput {
+    type Configs = CsvCritConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: CsvCritConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let (headers, rows) = read_csv_table(&configs.in_path, configs.has_headers, &configs.format)?;
+        let mapping = match (&configs.mapping, &headers) {
+            (Some(mapping), _) => mapping.clone(),
+            (None, Some(headers)) => ColumnMapping::from_headers(headers),
+            (None, None) => ColumnMapping::default(),
+        };
+        let (mut graph, mut data) = read_mapped_links(&headers, &rows, &mapping, &configs.policies)?;
+        if let Some(table) = &configs.node_attributes {
+            let (a_headers, a_rows) = read_csv_table(&table.source, configs.has_headers, &configs.format)?;
+            apply_node_attributes(&mut graph, &a_headers, &a_rows, table)?;
+        }
+        if let Some(probabilities) = &configs.probabilities {
+            let (p_headers, p_rows) = read_csv_table(&probabilities.path, configs.has_headers, &configs.format)?;
+            let id = probabilities.id.resolve(&p_headers)?;
+            let value = probabilities.value.resolve(&p_headers)?;
+            data.off_chances.append(&mut create_node_value_map(&p_rows, id, value, &graph.keys())?);
+        }
+        configs.direction.orient(&mut graph, &mut data);
+        Ok((graph, data))
+    }
+}
+
+/// Reads a links table with a header from any 'source', such as the body of a request, using the
+/// columns given by ['ColumnMapping::from_headers']
+///
+/// # Errors
+///
+/// Will return an error if the source can't be read, a column is missing or any cell is invalid
+pub fn read_headered_links<R: Read>(source: R) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+    let (headers, rows) = read_csv_from(source, true, &CsvFormat::default())?;
+    let mapping = ColumnMapping::from_headers(headers.as_ref().ok_or("The links table has no header")?);
+    read_mapped_links(&headers, &rows, &mapping, &LinkPolicies::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_keys_are_interned_until_the_ids_run_out() {
+        let keys = [CellKey::Id(u32::MAX - 2), CellKey::Text("a".to_string()), CellKey::Text("a".to_string()), CellKey::Text("b".to_string())];
+        let ids = intern_keys(keys.iter()).unwrap();
+        assert_eq!(ids["a"], u32::MAX - 1);
+        assert_eq!(ids["b"], u32::MAX);
+
+        let keys = [CellKey::Id(u32::MAX), CellKey::Text("a".to_string())];
+        assert_eq!(intern_keys(keys.iter()).unwrap_err().key, "a");
+    }
+
+    #[test]
+    fn the_alpha_file_is_read_next_to_the_input() {
+        assert_eq!(alpha_path("models/links.csv").as_deref(), Some(Path::new("models").join(ALPHA_FILE).to_str().unwrap()));
+        assert_eq!(alpha_path("links.csv").as_deref(), Some(ALPHA_FILE));
+        assert_eq!(alpha_path("http://host/models/links.csv").as_deref(), Some("http://host/models/alpha.csv"));
+        assert_eq!(alpha_path(STDIN_PATH), None);
+
+        let dir = std::env::temp_dir().join(format!("thor_alpha_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let links = dir.join("links.csv").to_string_lossy().to_string();
+        std::fs::write(&links, "start,0,end,1\n").unwrap();
+        let missing = alpha_path(&links).unwrap();
+        let configs = STDCritConfigs { in_path: links, alpha_path: Some(missing.clone()), format: CsvFormat::default() };
+        let error = STDCritInput {}.read(configs).err().unwrap().to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(error.contains(&missing), "{}", error);
+    }
+}
@@ -0,0 +1,147 @@
+//! Reading graphs from a Neo4j database.
+//!
+//! Statements are sent as Cypher through the transactional http endpoint of the database
+//! ('/db/{database}/tx/commit'), so no bolt driver is required.
+//!
+//! The bolt protocol ('bolt://' and 'neo4j://' urls) is not supported, nor is https: the server
+//! must expose its plain http connector, by default on port 7474.
+
+use std::error::Error;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::neo4j::QueryError;
+use crate::http;
+use crate::input::{create_graph, Input, LinkColumns, LinkPolicies, RowStringMatrix};
+use crate::json;
+use crate::json::JsonValue;
+use crate::network::Graph;
+
+/// Information necessary to connect to a Neo4j database
+#[derive(Debug, Clone)]
+pub struct Neo4jConnection {
+    /// Base http url of the server, e.g. 'http://localhost:7474'
+    pub url: String,
+    pub database: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Default for Neo4jConnection {
+    fn default() -> Self {
+        Neo4jConnection {
+            url: "http://localhost:7474".to_string(),
+            database: "neo4j".to_string(),
+            user: None,
+            password: None,
+        }
+    }
+}
+
+impl Neo4jConnection {
+    /// Runs a single Cypher statement in its own transaction and returns the rows of its result
+    ///
+    /// # Errors
+    ///
+    /// Returns a http error if the server can't be reached and a ['QueryError'] if the database
+    /// rejected the statement
+    pub fn run(&self, statement: &str, parameters: JsonValue) -> Result<Vec<Vec<JsonValue>>, Box<dyn Error>> {
+        let body = JsonValue::object().with("statements", vec![
+            JsonValue::object()
+                .with("statement", statement)
+                .with("parameters", parameters)
+        ]);
+        let url = format!("{}/db/{}/tx/commit", self.url.trim_end_matches('/'), self.database);
+        let auth = match (&self.user, &self.password) {
+            (Some(user), Some(password)) => Some(http::basic_auth(user, password)),
+            _ => None,
+        };
+        let mut headers = vec![("Content-Type", "application/json"), ("Accept", "application/json")];
+        if let Some(auth) = &auth {
+            headers.push(("Authorization", auth));
+        }
+        let response = http::request("POST", &url, &headers, body.to_string().as_bytes())?.ensure_success()?;
+        let response = json::parse(&response.body_string())?;
+
+        let errors: Vec<String> = response.get("errors")
+            .and_then(|e| e.as_array())
+            .map(|errors| errors.iter().map(|e| e.to_string()).collect())
+            .unwrap_or_default();
+        if !errors.is_empty() {
+            return Err(Box::new(QueryError { statement: statement.to_string(), errors }));
+        }
+
+        let mut rows = vec![];
+        let data = response.get("results")
+            .and_then(|r| r.as_array())
+            .and_then(|r| r.first())
+            .and_then(|r| r.get("data"))
+            .and_then(|d| d.as_array());
+        for entry in data.into_iter().flatten() {
+            if let Some(row) = entry.get("row").and_then(|r| r.as_array()) {
+                rows.push(row.clone());
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Returns the Cypher expression used to get the id of the node bound to 'var'
+    pub fn id_expression(var: &str, id_property: &Option<String>) -> String {
+        match id_property {
+            None => format!("id({})", var),
+            Some(prop) => format!("{}.{}", var, prop),
+        }
+    }
+}
+
+/// Configurations which hold information necessary to read a graph for the criticality analysis
+/// from Neo4j
+#[derive(Debug, Clone)]
+pub struct Neo4jCritConfigs {
+    pub connection: Neo4jConnection,
+    /// Label of the nodes that are part of the graph
+    pub node_label: String,
+    /// Type of the relationships that are read as edges. Relationships point from the child to
+    /// the parent node, which matches the order of the standard links file.
+    pub relationship_type: String,
+    /// Property used as the node name
+    pub name_property: String,
+    /// Integer property used as the node id. The internal Neo4j id is used if none is given
+    pub id_property: Option<String>,
+}
+
+impl Default for Neo4jCritConfigs {
+    fn default() -> Self {
+        Neo4jCritConfigs {
+            connection: Default::default(),
+            node_label: "Node".to_string(),
+            relationship_type: "LINK".to_string(),
+            name_property: "name".to_string(),
+            id_property: None,
+        }
+    }
+}
+
+/// Structure used to read all the values necessary for a criticality analysis from Neo4j
+pub struct Neo4jCritInput {}
+impl Input for Neo4jCritInput {
+    type Configs = Neo4jCritConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: Neo4jCritConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let statement = format!(
+            "MATCH (c:`{label}`)-[:`{rel}`]->(p:`{label}`) RETURN c.`{name}`, {c_id}, p.`{name}`, {p_id}",
+            label = configs.node_label,
+            rel = configs.relationship_type,
+            name = configs.name_property,
+            c_id = Neo4jConnection::id_expression("c", &configs.id_property),
+            p_id = Neo4jConnection::id_expression("p", &configs.id_property),
+        );
+        let rows = configs.connection.run(&statement, JsonValue::object())?;
+        // Convert the rows to the standard links matrix so they are validated the same way as a
+        // links file
+        let links_matrix: RowStringMatrix = rows.iter()
+            .map(|row| row.iter().map(|cell| cell.to_cell_string()).collect())
+            .collect();
+        let (graph, _edges, _) = create_graph(&links_matrix, &LinkColumns::default(), &LinkPolicies::default())?;
+        Ok((graph, CriticalityData::default()))
+    }
+}
@@ -0,0 +1,119 @@
+//! Reading graphs from the node-link json of NetworkX, as written in Python by
+//! 'networkx.node_link_data' or by ['crate::export::networkx::NodeLinkExport'].
+//!
+//! The edges are read from 'links', or from 'edges' as newer NetworkX versions name them, and
+//! lead from their 'source' to their 'target'. Node ids are used as they are if every id is a
+//! whole number, otherwise nodes are numbered in the order they appear and keep their id as the
+//! ['KEY_ATTR']. The node attributes 'name', 'off_chance' and 'static' are read into the graph
+//! and the data, other scalar node attributes are kept as node attributes and numeric link
+//! attributes are read as edge attributes. Lists and objects are skipped.
+
+use std::collections::HashMap;
+use std::error::Error;
+use log::warn;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::json::JsonFieldError;
+use crate::export::graphml::{NAME_KEY, OFF_CHANCE_KEY, STATIC_KEY};
+use crate::input::{Input, read_input_string};
+use crate::json;
+use crate::json::JsonValue;
+use crate::network::{AttrValue, Graph, KEY_ATTR};
+
+/// Configurations which hold information necessary to read a node-link json file
+#[derive(Debug, Clone)]
+pub struct NodeLinkConfigs {
+    /// The path to the json file
+    pub in_path: String,
+}
+
+/// Structure used to read a graph and its criticality data from node-link json
+pub struct NodeLinkInput {}
+
+impl Input for NodeLinkInput {
+    type Configs = NodeLinkConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: NodeLinkConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        parse_node_link(&json::parse(&read_input_string(&configs.in_path)?)?)
+    }
+}
+
+/// Text of a node id, ids may be numbers or strings
+fn id_key(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(s) => Some(s.clone()),
+        JsonValue::Number(_) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads the graph of a node-link 'document', see the module documentation
+///
+/// # Errors
+///
+/// Returns a ['JsonFieldError'] if the nodes or links are missing, a node has no id, a link
+/// refers to an unknown node or an off chance is not a number
+pub fn parse_node_link(document: &JsonValue) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+    let field_error = |field: &str, expected: &str| JsonFieldError { field: field.to_string(), expected: expected.to_string() };
+    let nodes = document.get("nodes").and_then(|n| n.as_array())
+        .ok_or_else(|| field_error("nodes", "an array"))?;
+    let links = document.get("links").or_else(|| document.get("edges")).and_then(|l| l.as_array())
+        .ok_or_else(|| field_error("links", "an array"))?;
+    if document.get("directed").and_then(|d| d.as_bool()) == Some(false) {
+        warn!("The node-link graph is undirected, its links are read from the source to the target");
+    }
+    if document.get("multigraph").and_then(|m| m.as_bool()) == Some(true) {
+        warn!("The node-link graph is a multigraph, parallel links are read as one edge");
+    }
+
+    let keys: Vec<String> = nodes.iter().enumerate()
+        .map(|(i, node)| node.get("id").and_then(id_key).ok_or_else(|| field_error(&format!("nodes[{}].id", i), "a number or a string")))
+        .collect::<Result<Vec<String>, JsonFieldError>>()?;
+    // Node ids are used as they are if every id in the file is a number
+    let numeric = keys.iter().all(|key| key.parse::<u32>().is_ok());
+    let ids: HashMap<&str, u32> = keys.iter().enumerate()
+        .map(|(i, key)| (key.as_str(), if numeric { key.parse::<u32>().unwrap() } else { i as u32 }))
+        .collect();
+
+    let mut graph = Graph::new();
+    let mut data = CriticalityData::default();
+    for (i, (node, key)) in nodes.iter().zip(keys.iter()).enumerate() {
+        let id = ids[key.as_str()];
+        let name = node.get(NAME_KEY).map(|n| n.to_cell_string()).unwrap_or(key.clone());
+        graph.add_node(name, id);
+        if !numeric {
+            graph.set_node_attr(&id, KEY_ATTR, AttrValue::Text(key.clone()));
+        }
+        for (attribute, value) in node.as_object().into_iter().flatten() {
+            match (attribute.as_str(), value) {
+                ("id", _) | (NAME_KEY, _) => {}
+                (OFF_CHANCE_KEY, value) => {
+                    let off_chance = value.as_f64().ok_or_else(|| field_error(&format!("nodes[{}].{}", i, OFF_CHANCE_KEY), "a number"))?;
+                    data.off_chances.insert(id, off_chance as f32);
+                }
+                (STATIC_KEY, JsonValue::Bool(true)) => { graph.static_nodes.insert(id); }
+                (STATIC_KEY, _) => {}
+                (_, JsonValue::Number(n)) => { graph.set_node_attr(&id, attribute, AttrValue::Number(*n)); }
+                (_, JsonValue::Bool(b)) => { graph.set_node_attr(&id, attribute, AttrValue::Bool(*b)); }
+                (_, JsonValue::String(s)) => { graph.set_node_attr(&id, attribute, AttrValue::Text(s.clone())); }
+                _ => {}
+            }
+        }
+    }
+
+    for (i, link) in links.iter().enumerate() {
+        let end = |field: &str| -> Result<u32, JsonFieldError> {
+            link.get(field).and_then(id_key).and_then(|key| ids.get(key.as_str()).copied())
+                .ok_or_else(|| field_error(&format!("links[{}].{}", i, field), "the id of a node"))
+        };
+        let (from, to) = (end("source")?, end("target")?);
+        graph.add_edge(from, to);
+        for (attribute, value) in link.as_object().into_iter().flatten() {
+            let Some(value) = value.as_f64() else { continue };
+            if !matches!(attribute.as_str(), "source" | "target" | "key") {
+                data.edge_attributes.entry(attribute.clone()).or_default().insert((from, to), value as f32);
+            }
+        }
+    }
+    Ok((graph, data))
+}
@@ -0,0 +1,262 @@
+//! Importing fault trees from the Open-PSA Model Exchange Format (MEF) xml.
+//!
+//! Fault trees describe failures while graphs describe operability, so every gate is mapped to
+//! its dual rule: an 'or' of failures is an ['AndRule'] of the operable children, an 'and' is an
+//! ['OrRule'] and 'atleast k' failures out of n is an ['AtLeastRule'] of n - k + 1 operable
+//! children. Basic and house events become dynamic nodes whose off chance is their probability,
+//! each with a shared start node as their only child. The top gate becomes the end node.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use log::warn;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::ModelError;
+use crate::input::{Input, read_input_string};
+use crate::network::Graph;
+use crate::roll_up::{AndRule, AtLeastRule, NodeRules, OrRule, RollUp};
+use crate::xml;
+use crate::xml::XmlElement;
+
+/// Name of the node added as the only child of every event
+pub const START_NODE_NAME: &str = "start";
+
+/// Configurations which hold information necessary to import an Open-PSA fault tree
+#[derive(Debug, Clone)]
+pub struct OpenPsaConfigs {
+    /// The path to the MEF xml file
+    pub in_path: String,
+}
+
+/// Values read alongside the graph of a fault tree
+pub struct FaultTreeData {
+    /// The failure probability of every event is stored as its off chance
+    pub crit_data: CriticalityData,
+    /// Rule of every gate, the events use an ['OrRule'] over the start node
+    pub roll_up_rule: NodeRules,
+}
+
+/// Structure used to import a fault tree as a graph with a roll up rule per gate
+pub struct OpenPsaInput {}
+
+impl Input for OpenPsaInput {
+    type Configs = OpenPsaConfigs;
+    type AnalysisData = FaultTreeData;
+
+    fn read(&self, configs: OpenPsaConfigs) -> Result<(Graph, FaultTreeData), Box<dyn Error>> {
+        let model = xml::parse(&read_input_string(&configs.in_path)?)?;
+        let mut builder = TreeBuilder::new(&model);
+        let tops: Vec<String> = builder.top_gates();
+        if tops.is_empty() {
+            return Err(Box::new(ModelError { reason: "the model defines no gates".to_string() }));
+        }
+        for top in tops.iter() {
+            builder.gate(top)?;
+        }
+        Ok(builder.finish())
+    }
+}
+
+/// Collects every element called 'name' anywhere below 'element'
+fn descendants<'a>(element: &'a XmlElement, name: &str, found: &mut Vec<&'a XmlElement>) {
+    for child in element.elements() {
+        if child.local_name() == name {
+            found.push(child);
+        }
+        descendants(child, name, found);
+    }
+}
+
+/// The first child of a definition that isn't a label or attribute list
+fn definition_body(definition: &XmlElement) -> Option<&XmlElement> {
+    definition.elements().find(|e| e.local_name() != "label" && e.local_name() != "attributes")
+}
+
+fn named<'a>(model: &'a XmlElement, definition: &str) -> HashMap<String, &'a XmlElement> {
+    let mut found = vec![];
+    descendants(model, definition, &mut found);
+    found.into_iter()
+        .filter_map(|e| Some((e.attr("name")?.to_string(), e)))
+        .collect()
+}
+
+struct TreeBuilder<'a> {
+    gates: HashMap<String, &'a XmlElement>,
+    basic_events: HashMap<String, &'a XmlElement>,
+    house_events: HashMap<String, &'a XmlElement>,
+    parameters: HashMap<String, &'a XmlElement>,
+    graph: Graph,
+    crit_data: CriticalityData,
+    rules: HashMap<u32, Box<dyn RollUp>>,
+    /// Node id of every gate and event, keyed by its kind and name
+    ids: HashMap<(&'static str, String), u32>,
+    /// Gates currently being built, used to detect cycles
+    building: HashSet<String>,
+    start_id: u32,
+    next_id: u32,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn new(model: &'a XmlElement) -> TreeBuilder<'a> {
+        let start_id = 0;
+        let mut graph = Graph::new();
+        graph.add_node(START_NODE_NAME.to_string(), start_id);
+        TreeBuilder {
+            gates: named(model, "define-gate"),
+            basic_events: named(model, "define-basic-event"),
+            house_events: named(model, "define-house-event"),
+            parameters: named(model, "define-parameter"),
+            graph,
+            crit_data: CriticalityData::default(),
+            rules: HashMap::new(),
+            ids: HashMap::new(),
+            building: HashSet::new(),
+            start_id,
+            next_id: start_id + 1,
+        }
+    }
+
+    /// Gates that no other gate refers to, sorted by name
+    fn top_gates(&self) -> Vec<String> {
+        let mut referenced = HashSet::new();
+        for gate in self.gates.values() {
+            let mut references = vec![];
+            descendants(gate, "gate", &mut references);
+            descendants(gate, "event", &mut references);
+            referenced.extend(references.into_iter().filter_map(|r| r.attr("name")));
+        }
+        let mut tops: Vec<String> = self.gates.keys().filter(|g| !referenced.contains(g.as_str())).cloned().collect();
+        tops.sort();
+        tops
+    }
+
+    fn finish(self) -> (Graph, FaultTreeData) {
+        let roll_up_rule = NodeRules { rules: self.rules, default: Box::new(OrRule {}) };
+        (self.graph, FaultTreeData { crit_data: self.crit_data, roll_up_rule })
+    }
+
+    fn add_node(&mut self, kind: &'static str, name: &str, is_static: bool) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.graph.add_node(name.to_string(), id);
+        if is_static {
+            self.graph.static_nodes.insert(id);
+        }
+        self.ids.insert((kind, name.to_string()), id);
+        id
+    }
+
+    /// Node of the gate called 'name', building it and everything below it the first time
+    fn gate(&mut self, name: &str) -> Result<u32, ModelError> {
+        if let Some(id) = self.ids.get(&("gate", name.to_string())) {
+            return Ok(*id);
+        }
+        if !self.building.insert(name.to_string()) {
+            return Err(ModelError { reason: format!("the gate '{}' refers to itself", name) });
+        }
+        let definition = *self.gates.get(name)
+            .ok_or_else(|| ModelError { reason: format!("the gate '{}' is not defined", name) })?;
+        let formula = definition_body(definition)
+            .ok_or_else(|| ModelError { reason: format!("the gate '{}' has no formula", name) })?;
+        let id = self.add_node("gate", name, true);
+        self.formula(id, name, formula)?;
+        self.building.remove(name);
+        Ok(id)
+    }
+
+    /// Builds the 'formula' of the node 'id', nested formulas become anonymous gates named after
+    /// the gate they are part of
+    fn formula(&mut self, id: u32, gate: &str, formula: &XmlElement) -> Result<(), ModelError> {
+        let arguments: Vec<&XmlElement> = formula.elements().collect();
+        let rule: Box<dyn RollUp> = match formula.local_name() {
+            "or" => Box::new(AndRule {}),
+            "and" => Box::new(OrRule {}),
+            "atleast" => {
+                let min = formula.attr("min").and_then(|m| m.trim().parse::<usize>().ok())
+                    .ok_or_else(|| ModelError { reason: format!("the atleast formula of '{}' needs a numeric 'min'", gate) })?;
+                Box::new(AtLeastRule { min: (arguments.len() + 1).saturating_sub(min) })
+            }
+            "gate" | "basic-event" | "house-event" | "event" => {
+                let child = self.argument(gate, formula)?;
+                self.graph.add_edge(child, id);
+                self.rules.insert(id, Box::new(AndRule {}));
+                return Ok(());
+            }
+            other => return Err(ModelError { reason: format!("the '{}' formula of '{}' is not supported", other, gate) }),
+        };
+        self.rules.insert(id, rule);
+        for argument in arguments {
+            let child = self.argument(gate, argument)?;
+            self.graph.add_edge(child, id);
+        }
+        Ok(())
+    }
+
+    /// Node of a formula argument, either a reference or a nested formula
+    fn argument(&mut self, gate: &str, argument: &XmlElement) -> Result<u32, ModelError> {
+        let name = argument.attr("name").unwrap_or("");
+        match argument.local_name() {
+            "gate" => self.gate(name),
+            "basic-event" => self.event("basic-event", name),
+            "house-event" => self.event("house-event", name),
+            // Untyped references are resolved in the order gates, basic events, house events
+            "event" if self.gates.contains_key(name) => self.gate(name),
+            "event" if self.house_events.contains_key(name) => self.event("house-event", name),
+            "event" => self.event("basic-event", name),
+            _ => {
+                let nested = format!("{}/{}", gate, self.next_id);
+                let id = self.add_node("gate", &nested, true);
+                self.formula(id, gate, argument)?;
+                Ok(id)
+            }
+        }
+    }
+
+    /// Node of the event called 'name', a dynamic node whose only child is the start node
+    fn event(&mut self, kind: &'static str, name: &str) -> Result<u32, ModelError> {
+        if let Some(id) = self.ids.get(&(kind, name.to_string())) {
+            return Ok(*id);
+        }
+        let probability = match kind {
+            "house-event" => match self.house_events.get(name).and_then(|e| definition_body(e)) {
+                Some(constant) => match constant.attr("value").map(|v| v.trim()) {
+                    Some("true") => 1.0,
+                    Some("false") => 0.0,
+                    _ => return Err(ModelError { reason: format!("the house event '{}' needs a boolean constant", name) }),
+                },
+                None => 0.0,
+            },
+            _ => match self.basic_events.get(name).and_then(|e| definition_body(e)) {
+                Some(expression) => self.expression(name, expression)?,
+                None => {
+                    warn!("The basic event '{}' has no probability, the default off chance is used", name);
+                    let id = self.add_node(kind, name, false);
+                    self.graph.add_edge(self.start_id, id);
+                    return Ok(id);
+                }
+            },
+        };
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(ModelError { reason: format!("the probability of '{}' is not between 0 and 1", name) });
+        }
+        let id = self.add_node(kind, name, false);
+        self.graph.add_edge(self.start_id, id);
+        self.crit_data.off_chances.insert(id, probability as f32);
+        Ok(id)
+    }
+
+    /// Value of a constant expression, parameters are resolved by name
+    fn expression(&self, event: &str, expression: &XmlElement) -> Result<f64, ModelError> {
+        let unsupported = || ModelError { reason: format!("the probability expression of '{}' is not a constant", event) };
+        match expression.local_name() {
+            "float" | "int" => expression.attr("value").and_then(|v| v.trim().parse::<f64>().ok()).ok_or_else(unsupported),
+            "parameter" => {
+                let parameter = expression.attr("name")
+                    .and_then(|name| self.parameters.get(name))
+                    .and_then(|p| definition_body(p))
+                    .ok_or_else(unsupported)?;
+                self.expression(event, parameter)
+            }
+            _ => Err(unsupported()),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Parallel parsing of large inputs.
+//!
+//! A csv input is cut into one chunk per thread at record boundaries, and the chunks are decoded
+//! and parsed at the same time. A newline ends a record unless it is inside a quoted field, which
+//! is told by the number of quote characters before it. The count only follows the quoting if
+//! every quote opens or closes a field or is doubled inside one, as in RFC 4180, so inputs with
+//! other quotes are parsed in one piece.
+
+use std::thread;
+
+/// Inputs are only cut into chunks of at least this many bytes
+pub const MIN_CHUNK_BYTES: usize = 1 << 20;
+
+/// Tables are only cut into chunks of at least this many rows
+pub const MIN_CHUNK_ROWS: usize = 1 << 16;
+
+/// Number of chunks to cut 'len' units into, so that every chunk holds at least 'min_chunk' units
+/// and there is at most one chunk per cpu
+pub fn chunk_count(len: usize, min_chunk: usize) -> usize {
+    (len / min_chunk.max(1)).clamp(1, num_cpus::get().max(1))
+}
+
+/// Applies 'f' to chunks of at least 'min_chunk' of the 'items' on a thread per chunk, and returns
+/// the results in the order of the chunks. 'f' gets the index of the first item of its chunk.
+pub fn map_chunks<T, R, F>(items: &[T], min_chunk: usize, f: F) -> Vec<R>
+    where T: Sync, R: Send, F: Fn(usize, &[T]) -> R + Sync
+{
+    let size = items.len().div_ceil(chunk_count(items.len(), min_chunk)).max(1);
+    if items.len() <= size {
+        return vec![f(0, items)];
+    }
+    thread::scope(|s| {
+        let f = &f;
+        let handles: Vec<_> = items.chunks(size).enumerate()
+            .map(|(i, chunk)| s.spawn(move || f(i * size, chunk)))
+            .collect();
+        handles.into_iter().map(|h| h.join().expect("a parsing thread panicked")).collect()
+    })
+}
+
+/// Cuts the csv 'data' into at most 'chunks' parts that each hold whole records
+pub fn split_records(data: &[u8], delimiter: u8, quote: Option<u8>, chunks: usize) -> Vec<&[u8]> {
+    if chunks <= 1 || data.is_empty() {
+        return vec![data];
+    }
+    let bounds: Vec<usize> = (0..=chunks).map(|i| i * data.len() / chunks).collect();
+    let parts: Vec<(usize, usize)> = bounds.windows(2).map(|w| (w[0], w[1])).collect();
+    let quote = quote.filter(|q| data.contains(q));
+    let cuts: Vec<Option<usize>> = match quote {
+        None => parts.iter()
+            .map(|(start, end)| data[*start..*end].iter().position(|b| *b == b'\n').map(|i| start + i + 1))
+            .collect(),
+        Some(quote) => {
+            let counts: Vec<usize> = map_chunks(&parts, 1, |_, parts| parts.iter()
+                .map(|(start, end)| data[*start..*end].iter().filter(|b| **b == quote).count())
+                .collect::<Vec<usize>>())
+                .into_iter().flatten().collect();
+            let mut inside = false;
+            let mut starts_inside = vec![];
+            for count in counts {
+                starts_inside.push(inside);
+                inside ^= count % 2 == 1;
+            }
+            let scans: Vec<Option<Option<usize>>> = map_chunks(&parts, 1, |first, parts| parts.iter().enumerate()
+                .map(|(i, part)| scan_part(data, *part, starts_inside[first + i], delimiter, quote))
+                .collect::<Vec<_>>())
+                .into_iter().flatten().collect();
+            if scans.iter().any(|scan| scan.is_none()) {
+                return vec![data];
+            }
+            scans.into_iter().map(|scan| scan.flatten()).collect()
+        }
+    };
+    let mut pieces = vec![];
+    let mut start = 0;
+    // The first part always starts a record
+    for cut in cuts.into_iter().skip(1).flatten() {
+        if cut > start && cut < data.len() {
+            pieces.push(&data[start..cut]);
+            start = cut;
+        }
+    }
+    pieces.push(&data[start..]);
+    pieces
+}
+
+/// Checks the quotes of the part of 'data' between 'bounds' and finds the first newline in it that
+/// isn't quoted. 'inside' tells whether the part starts within a quoted field.
+///
+/// Returns None if a quote neither opens, closes nor doubles a quote, otherwise the position after
+/// the newline, if there is one
+fn scan_part(data: &[u8], bounds: (usize, usize), mut inside: bool, delimiter: u8, quote: u8) -> Option<Option<usize>> {
+    let separates = |b: Option<&u8>| matches!(b, None | Some(b'\n') | Some(b'\r')) || b == Some(&delimiter) || b == Some(&quote);
+    let mut cut = None;
+    for i in bounds.0..bounds.1 {
+        match data[i] {
+            b if b == quote => {
+                let placed = match inside {
+                    false => i == 0 || separates(data.get(i - 1)),
+                    true => separates(data.get(i + 1)),
+                };
+                if !placed {
+                    return None;
+                }
+                inside = !inside;
+            }
+            b'\n' if !inside && cut.is_none() => cut = Some(i + 1),
+            _ => {}
+        }
+    }
+    Some(cut)
+}
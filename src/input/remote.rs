@@ -0,0 +1,252 @@
+//! Inputs fetched from a remote location instead of read from a local file.
+//!
+//! An input path can be a 'http://' url or a 's3://bucket/key' object. The content is streamed
+//! into a local cache, so a later run only downloads it again if the server reports a change.
+//! A failed fetch fails the run, so a job fetching the latest model never runs on a stale one.
+//! Setting ['CACHE_FALLBACK_VAR'] to 'true' opts into using the copy cached by an earlier run
+//! instead, with a warning.
+//!
+//! Only plain http is supported: the http client has no TLS, so 'https://' urls, redirects to
+//! them and https s3 endpoints are rejected with a ['TlsUnsupportedError'], see ['crate::http'].
+//! Objects in s3 are read from the plain http, path style endpoint in the ['S3_ENDPOINT_VAR']
+//! environment variable, e.g. a MinIO server or an artifact store gateway. The requests are not
+//! signed, so only buckets that allow anonymous reads can be read; private buckets must be
+//! exposed through a gateway that signs the requests itself. A bucket refusing the read is an
+//! ['UnsignedRequestError'].
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use crate::checksum;
+use crate::errors::http::{ResponseError, TlsUnsupportedError, UnsignedRequestError};
+use crate::http;
+use crate::http::Url;
+
+/// Environment variable with the directory fetched inputs are cached in, a directory in the
+/// temporary directory of the system if it isn't set
+pub const CACHE_DIR_VAR: &str = "THOR_CACHE_DIR";
+
+/// Environment variable with the http endpoint 's3://' urls are read from
+pub const S3_ENDPOINT_VAR: &str = "S3_ENDPOINT";
+
+/// Environment variable which, set to 'true', uses the cached copy of an input that can't be
+/// fetched instead of failing
+pub const CACHE_FALLBACK_VAR: &str = "THOR_CACHE_FALLBACK";
+
+/// Redirects followed before a fetch is given up
+const MAX_REDIRECTS: usize = 5;
+
+/// Whether 'path' is a url of a remote input rather than a local path
+pub fn is_remote(path: &str) -> bool {
+    ["http://", "https://", "s3://"].iter().any(|scheme| path.starts_with(scheme))
+}
+
+/// The local path of the input at 'path', fetching it into the cache first if it is remote.
+/// Local paths are returned unchanged.
+///
+/// # Errors
+///
+/// Will return an error if a remote input can't be fetched, see ['fetch']
+pub fn local_path(path: &str) -> Result<String, Box<dyn Error>> {
+    match is_remote(path) {
+        true => Ok(fetch(path)?.to_string_lossy().to_string()),
+        false => Ok(path.to_string()),
+    }
+}
+
+/// Fetches the input at 'url' into the cache and returns the path of the cached copy. The cached
+/// file keeps the file name of the url, so the format and compression can still be told from its
+/// extension.
+///
+/// # Errors
+///
+/// Will return an error if the url isn't supported or the input can't be fetched, unless the
+/// ['CACHE_FALLBACK_VAR'] allows the copy cached by an earlier run
+pub fn fetch(url: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let source = http_url(url)?;
+    let dir = cache_dir();
+    fs::create_dir_all(&dir)?;
+    let cached = cached_path(url);
+    let validator_path = PathBuf::from(format!("{}.validator", cached.display()));
+    let validator = match cached.exists() {
+        true => fs::read_to_string(&validator_path).ok(),
+        false => None,
+    };
+    match download(&source, &cached, validator.as_deref()).map_err(|e| refused_read(url, e)) {
+        Ok(Fetched::Unchanged) => info!("{} is unchanged, using the cached copy", url),
+        Ok(Fetched::Updated(validator)) => {
+            info!("Fetched {} into {}", url, cached.display());
+            match validator {
+                Some(validator) => fs::write(&validator_path, validator)?,
+                None => { let _ = fs::remove_file(&validator_path); }
+            }
+        }
+        Err(e) if cached.exists() && cache_fallback() => warn!("Couldn't fetch {}, using the cached copy: {}", url, e),
+        Err(e) => {
+            if cached.exists() {
+                info!("Set {}=true to use the copy of {} cached by an earlier run", CACHE_FALLBACK_VAR, url);
+            }
+            return Err(e);
+        }
+    }
+    Ok(cached)
+}
+
+/// The path the input at 'url' is cached at, whether or not it was fetched
+pub fn cached_path(url: &str) -> PathBuf {
+    cache_dir().join(format!("{:016x}-{}", checksum::xxh64(url.as_bytes(), 0), file_name(url)))
+}
+
+/// The http url an input url is fetched from
+///
+/// # Errors
+///
+/// Will return an error for a 's3://' url if ['S3_ENDPOINT_VAR'] isn't set, or a
+/// ['TlsUnsupportedError'] if the url or the endpoint uses https
+fn http_url(url: &str) -> Result<String, Box<dyn Error>> {
+    if url.starts_with("https://") {
+        return Err(TlsUnsupportedError { url: url.to_string() }.into());
+    }
+    match url.strip_prefix("s3://") {
+        None => Ok(url.to_string()),
+        Some(object) => {
+            let endpoint = env::var(S3_ENDPOINT_VAR)
+                .map_err(|_| format!("Reading '{}' needs the http endpoint of the object store in '{}'", url, S3_ENDPOINT_VAR))?;
+            if endpoint.starts_with("https://") {
+                return Err(TlsUnsupportedError { url: endpoint }.into());
+            }
+            Ok(format!("{}/{}", endpoint.trim_end_matches('/'), object))
+        }
+    }
+}
+
+/// Turns an object store refusing the read of an 's3://' url into an ['UnsignedRequestError'],
+/// other errors are returned unchanged
+fn refused_read(url: &str, error: Box<dyn Error>) -> Box<dyn Error> {
+    match error.downcast_ref::<ResponseError>() {
+        Some(response) if url.starts_with("s3://") && matches!(response.status, 401 | 403) =>
+            UnsignedRequestError { url: url.to_string(), status: response.status }.into(),
+        _ => error,
+    }
+}
+
+/// Whether ['CACHE_FALLBACK_VAR'] allows using a stale cached copy
+fn cache_fallback() -> bool {
+    env::var(CACHE_FALLBACK_VAR).map(|v| v == "true" || v == "1").unwrap_or(false)
+}
+
+fn cache_dir() -> PathBuf {
+    match env::var(CACHE_DIR_VAR) {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => env::temp_dir().join("thor_reforged").join("inputs"),
+    }
+}
+
+/// The last segment of the path of 'url', with characters that aren't safe in a file name replaced
+fn file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name: String = path.rsplit('/').next().unwrap_or("")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    match name.trim_matches('.').is_empty() {
+        true => "input".to_string(),
+        false => name,
+    }
+}
+
+/// Outcome of a download
+enum Fetched {
+    /// The server reported that the cached copy is still current
+    Unchanged,
+    /// New content, with the validator to send on the next fetch if the server gave one
+    Updated(Option<String>),
+}
+
+/// Streams the content at 'url' into 'target', following redirects. A 'validator' from an earlier
+/// fetch is sent as a conditional header, so an unchanged input isn't downloaded again.
+fn download(url: &str, target: &Path, validator: Option<&str>) -> Result<Fetched, Box<dyn Error>> {
+    let part = PathBuf::from(format!("{}.part", target.display()));
+    let mut url = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        if url.starts_with("https://") {
+            return Err(TlsUnsupportedError { url }.into());
+        }
+        let headers: Vec<(&str, &str)> = validator.and_then(|v| v.split_once(": ")).into_iter().collect();
+        let mut out = BufWriter::new(File::create(&part)?);
+        let result = http::download(&url, &headers, &mut out).and_then(|r| { out.flush()?; Ok(r) });
+        drop(out);
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                let _ = fs::remove_file(&part);
+                return Err(e);
+            }
+        };
+        match response.status {
+            200..=299 => {
+                fs::rename(&part, target)?;
+                let validator = match (response.header("ETag"), response.header("Last-Modified")) {
+                    (Some(etag), _) => Some(format!("If-None-Match: {}", etag)),
+                    (None, Some(modified)) => Some(format!("If-Modified-Since: {}", modified)),
+                    (None, None) => None,
+                };
+                return Ok(Fetched::Updated(validator));
+            }
+            304 => {
+                fs::remove_file(&part)?;
+                return Ok(Fetched::Unchanged);
+            }
+            301 | 302 | 303 | 307 | 308 => {
+                fs::remove_file(&part)?;
+                let location = response.header("Location").ok_or("A redirect is missing its location")?;
+                url = match location.starts_with('/') {
+                    true => {
+                        let current = Url::parse(&url)?;
+                        format!("http://{}:{}{}", current.host, current.port, location)
+                    }
+                    false => location.to_string(),
+                };
+            }
+            status => {
+                let body = String::from_utf8_lossy(&fs::read(&part)?).to_string();
+                fs::remove_file(&part)?;
+                return Err(ResponseError { status, body }.into());
+            }
+        }
+    }
+    Err(format!("Gave up fetching '{}' after {} redirects", url, MAX_REDIRECTS).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_needing_tls_are_refused() {
+        let error = http_url("https://example.com/links.csv").unwrap_err();
+        assert!(error.downcast_ref::<TlsUnsupportedError>().is_some(), "{}", error);
+        assert_eq!(http_url("http://example.com/links.csv").unwrap(), "http://example.com/links.csv");
+    }
+
+    #[test]
+    fn refused_s3_reads_say_the_requests_are_unsigned() {
+        let refused = || -> Box<dyn Error> { ResponseError { status: 403, body: "AccessDenied".to_string() }.into() };
+        let error = refused_read("s3://bucket/links.csv", refused());
+        assert!(error.downcast_ref::<UnsignedRequestError>().is_some(), "{}", error);
+        let error = refused_read("http://host/links.csv", refused());
+        assert!(error.downcast_ref::<ResponseError>().is_some(), "{}", error);
+    }
+
+    #[test]
+    fn cached_files_keep_the_name_of_the_url() {
+        assert_eq!(file_name("http://host/models/links.csv.gz?version=2"), "links.csv.gz");
+        assert_eq!(file_name("s3://bucket/a b.csv"), "a_b.csv");
+        assert_eq!(file_name("http://host/"), "input");
+        assert_eq!(file_name("http://host/.."), "input");
+    }
+}
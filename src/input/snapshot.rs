@@ -0,0 +1,311 @@
+//! Binary snapshots of a graph and its criticality data.
+//!
+//! Parsing a text input with millions of edges takes far longer than the analysis setup, so a
+//! read input can be saved once with ['write_snapshot'] and loaded with ['SnapshotInput'] on
+//! later runs. A snapshot starts with ['SNAPSHOT_MAGIC'] and the format version, all numbers
+//! are little endian and strings are prefixed by their length in bytes.
+//!
+//! Roll up rules, such as the gates of a fault tree, are not part of the snapshot.
+
+use std::error::Error;
+use std::io::{Read, Write};
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::SnapshotError;
+use crate::input::{Input, open_input};
+use crate::network::{AttrValue, EdgeValueMap, Graph};
+use crate::output::create_output;
+
+/// First bytes of every snapshot file
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"THORSNAP";
+/// Version of the layout written by ['write_snapshot']
+pub const SNAPSHOT_VERSION: u16 = 1;
+/// File extension used for snapshots
+pub const SNAPSHOT_EXTENSION: &str = ".thor";
+
+const NUMBER_TAG: u8 = 0;
+const BOOL_TAG: u8 = 1;
+const TEXT_TAG: u8 = 2;
+
+struct SnapshotWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    fn u8(&mut self, v: u8) -> std::io::Result<()> { self.out.write_all(&[v]) }
+    fn u16(&mut self, v: u16) -> std::io::Result<()> { self.out.write_all(&v.to_le_bytes()) }
+    fn u32(&mut self, v: u32) -> std::io::Result<()> { self.out.write_all(&v.to_le_bytes()) }
+    fn u64(&mut self, v: u64) -> std::io::Result<()> { self.out.write_all(&v.to_le_bytes()) }
+    fn f32(&mut self, v: f32) -> std::io::Result<()> { self.out.write_all(&v.to_le_bytes()) }
+    fn f64(&mut self, v: f64) -> std::io::Result<()> { self.out.write_all(&v.to_le_bytes()) }
+
+    fn len(&mut self, len: usize) -> std::io::Result<()> {
+        self.u64(len as u64)
+    }
+
+    fn str(&mut self, v: &str) -> std::io::Result<()> {
+        self.len(v.len())?;
+        self.out.write_all(v.as_bytes())
+    }
+
+    fn edge_values(&mut self, values: &EdgeValueMap<f32>) -> std::io::Result<()> {
+        self.len(values.len())?;
+        let mut values: Vec<(&(u32, u32), &f32)> = values.iter().collect();
+        values.sort_unstable_by_key(|(key, _)| **key);
+        for ((from, to), value) in values {
+            self.u32(*from)?;
+            self.u32(*to)?;
+            self.f32(*value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the 'graph' and its 'data' to a snapshot at 'path'. Nodes, edges and attribute maps
+/// are written in ascending id order, so the same input always gives the same file.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written
+pub fn write_snapshot(path: &str, graph: &Graph, data: &CriticalityData) -> Result<(), Box<dyn Error>> {
+    let mut w = SnapshotWriter { out: create_output(path)? };
+    w.out.write_all(SNAPSHOT_MAGIC)?;
+    w.u16(SNAPSHOT_VERSION)?;
+
+    let mut ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+    ids.sort_unstable();
+    w.len(ids.len())?;
+    for node in ids.iter().filter_map(|id| graph.get_node(id)) {
+        w.u32(node.id)?;
+        w.str(&node.name)?;
+        w.len(node.attributes.len())?;
+        for (name, value) in node.attributes.iter() {
+            w.str(name)?;
+            match value {
+                AttrValue::Number(n) => { w.u8(NUMBER_TAG)?; w.f64(*n)?; }
+                AttrValue::Bool(b) => { w.u8(BOOL_TAG)?; w.u8(*b as u8)?; }
+                AttrValue::Text(t) => { w.u8(TEXT_TAG)?; w.str(t)?; }
+            }
+        }
+    }
+
+    let mut edges: Vec<(u32, u32)> = graph.get_edges().iter().map(|e| (e.from, e.to)).collect();
+    edges.sort_unstable();
+    w.len(edges.len())?;
+    for (from, to) in edges {
+        w.u32(from)?;
+        w.u32(to)?;
+    }
+
+    let mut static_nodes: Vec<u32> = graph.static_nodes.iter().copied().collect();
+    static_nodes.sort_unstable();
+    w.len(static_nodes.len())?;
+    for id in static_nodes {
+        w.u32(id)?;
+    }
+
+    let mut names: Vec<&String> = data.edge_attributes.keys().collect();
+    names.sort_unstable();
+    w.len(names.len())?;
+    for name in names {
+        w.str(name)?;
+        w.edge_values(&data.edge_attributes[name])?;
+    }
+    let mut off_chances: Vec<(&u32, &f32)> = data.off_chances.iter().collect();
+    off_chances.sort_unstable_by_key(|(id, _)| **id);
+    w.len(off_chances.len())?;
+    for (id, chance) in off_chances {
+        w.u32(*id)?;
+        w.f32(*chance)?;
+    }
+    w.out.flush()?;
+    Ok(())
+}
+
+struct SnapshotReader<R: Read> {
+    input: R,
+    path: String,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    fn error(&self, reason: &str) -> SnapshotError {
+        SnapshotError { path: self.path.clone(), reason: reason.to_string() }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        let mut buf = [0; N];
+        self.input.read_exact(&mut buf).map_err(|_| self.error("the file ends unexpectedly"))?;
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> { Ok(self.bytes::<1>()?[0]) }
+    fn u16(&mut self) -> Result<u16, SnapshotError> { Ok(u16::from_le_bytes(self.bytes()?)) }
+    fn u32(&mut self) -> Result<u32, SnapshotError> { Ok(u32::from_le_bytes(self.bytes()?)) }
+    fn u64(&mut self) -> Result<u64, SnapshotError> { Ok(u64::from_le_bytes(self.bytes()?)) }
+    fn f32(&mut self) -> Result<f32, SnapshotError> { Ok(f32::from_le_bytes(self.bytes()?)) }
+    fn f64(&mut self) -> Result<f64, SnapshotError> { Ok(f64::from_le_bytes(self.bytes()?)) }
+
+    fn len(&mut self) -> Result<usize, SnapshotError> {
+        let len = self.u64()?;
+        usize::try_from(len).map_err(|_| self.error("a length doesn't fit in memory"))
+    }
+
+    fn str(&mut self) -> Result<String, SnapshotError> {
+        let len = self.len()?;
+        let mut buf = vec![];
+        (&mut self.input).take(len as u64).read_to_end(&mut buf)
+            .map_err(|_| self.error("the file can't be read"))?;
+        if buf.len() != len {
+            return Err(self.error("the file ends unexpectedly"));
+        }
+        String::from_utf8(buf).map_err(|_| self.error("a string is not valid utf-8"))
+    }
+
+    fn edge_values(&mut self) -> Result<EdgeValueMap<f32>, SnapshotError> {
+        let mut values = EdgeValueMap::new();
+        for _ in 0..self.len()? {
+            let key = (self.u32()?, self.u32()?);
+            values.insert(key, self.f32()?);
+        }
+        Ok(values)
+    }
+}
+
+/// Configurations which hold information necessary to load a snapshot
+#[derive(Debug, Clone)]
+pub struct SnapshotConfigs {
+    /// The path to the snapshot, usually ending in ['SNAPSHOT_EXTENSION']
+    pub in_path: String,
+}
+
+/// Structure used to load a graph and its criticality data from a snapshot
+pub struct SnapshotInput {}
+
+impl Input for SnapshotInput {
+    type Configs = SnapshotConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: SnapshotConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let mut r = SnapshotReader { input: open_input(&configs.in_path)?, path: configs.in_path };
+        if &r.bytes::<8>()? != SNAPSHOT_MAGIC {
+            return Err(r.error("the file is not a snapshot").into());
+        }
+        let version = r.u16()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(r.error(&format!("version {} is not supported, expected {}", version, SNAPSHOT_VERSION)).into());
+        }
+
+        let mut graph = Graph::new();
+        for _ in 0..r.len()? {
+            let id = r.u32()?;
+            graph.add_node(r.str()?, id);
+            for _ in 0..r.len()? {
+                let name = r.str()?;
+                let value = match r.u8()? {
+                    NUMBER_TAG => AttrValue::Number(r.f64()?),
+                    BOOL_TAG => AttrValue::Bool(r.u8()? != 0),
+                    TEXT_TAG => AttrValue::Text(r.str()?),
+                    tag => return Err(r.error(&format!("unknown attribute type {}", tag)).into()),
+                };
+                graph.set_node_attr(&id, &name, value);
+            }
+        }
+        for _ in 0..r.len()? {
+            let (from, to) = (r.u32()?, r.u32()?);
+            graph.add_edge(from, to);
+        }
+        for _ in 0..r.len()? {
+            graph.static_nodes.insert(r.u32()?);
+        }
+
+        let mut data = CriticalityData::default();
+        for _ in 0..r.len()? {
+            let name = r.str()?;
+            data.add_edge_attribute(&name, r.edge_values()?);
+        }
+        for _ in 0..r.len()? {
+            let id = r.u32()?;
+            data.off_chances.insert(id, r.f32()?);
+        }
+        if r.input.read(&mut [0])? != 0 {
+            return Err(r.error("there is data after the end of the snapshot").into());
+        }
+        Ok((graph, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::network::{ALPHA_ATTR, NodeValueMap};
+
+    fn path(name: &str) -> String {
+        std::env::temp_dir().join(format!("thor_{}_{}{}", name, std::process::id(), SNAPSHOT_EXTENSION)).to_string_lossy().to_string()
+    }
+
+    /// Loads the snapshot 'bytes' from a temporary file
+    fn load(name: &str, bytes: &[u8]) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let path = path(name);
+        fs::write(&path, bytes).unwrap();
+        let read = SnapshotInput {}.read(SnapshotConfigs { in_path: path.clone() });
+        fs::remove_file(&path).unwrap();
+        read
+    }
+
+    /// a -> b -> c with attributes of every type, alpha values and off chances, as saved
+    fn saved() -> (Graph, Vec<u8>) {
+        let mut graph = Graph::new();
+        for (name, id) in [("a", 1), ("b", 2), ("c", 3)] {
+            graph.add_node(name.to_string(), id);
+        }
+        graph.add_edge(1, 2);
+        graph.add_edge(2, 3);
+        graph.static_nodes.insert(1);
+        graph.set_node_attr(&2, "cost", AttrValue::Number(2.5));
+        graph.set_node_attr(&2, "redundant", AttrValue::Bool(true));
+        graph.set_node_attr(&3, "site", AttrValue::Text("north".to_string()));
+        let mut data = CriticalityData::default();
+        data.add_edge_attribute(ALPHA_ATTR, [((1, 2), 0.5), ((2, 3), 1.0)].into_iter().collect());
+        data.off_chances = NodeValueMap::from([(2, 0.1), (3, 0.9)]);
+        let path = path("saved");
+        write_snapshot(&path, &graph, &data).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        (graph, bytes)
+    }
+
+    #[test]
+    fn loaded_snapshots_hold_what_was_saved() {
+        let (graph, bytes) = saved();
+        assert!(bytes.starts_with(SNAPSHOT_MAGIC));
+        assert_eq!(bytes, saved().1, "the same graph should give the same file");
+        let (loaded, data) = load("round_trip", &bytes).unwrap();
+        assert_eq!(loaded.get_node_ids(), graph.get_node_ids());
+        assert_eq!(loaded.get_edges(), graph.get_edges());
+        assert_eq!(loaded.static_nodes, graph.static_nodes);
+        for id in [1, 2, 3] {
+            assert_eq!(loaded.get_node(&id).unwrap().attributes, graph.get_node(&id).unwrap().attributes);
+        }
+        assert_eq!(data.edge_attributes[ALPHA_ATTR][&(1, 2)], 0.5);
+        assert_eq!(data.off_chances, NodeValueMap::from([(2, 0.1), (3, 0.9)]));
+    }
+
+    #[test]
+    fn truncated_and_malformed_snapshots_are_errors() {
+        let (_, bytes) = saved();
+        for end in 0..bytes.len() {
+            assert!(load("truncated", &bytes[..end]).is_err(), "cut at {}", end);
+        }
+        let reason = |bytes: &[u8]| load("malformed", bytes).err().unwrap().downcast::<SnapshotError>().unwrap().reason;
+        assert_eq!(reason(&[&bytes[..], b"x"].concat()), "there is data after the end of the snapshot");
+        assert_eq!(reason(b"THORSNAX\x01\x00"), "the file is not a snapshot");
+        assert_eq!(reason(b"THORSNAP\x09\x00"), "version 9 is not supported, expected 1");
+        // One node whose name claims to be 4 GiB long
+        let mut huge = b"THORSNAP\x01\x00".to_vec();
+        huge.extend_from_slice(&1u64.to_le_bytes());
+        huge.extend_from_slice(&7u32.to_le_bytes());
+        huge.extend_from_slice(&(u32::MAX as u64).to_le_bytes());
+        huge.extend_from_slice(b"name");
+        assert_eq!(reason(&huge), "the file ends unexpectedly");
+    }
+}
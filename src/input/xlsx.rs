@@ -0,0 +1,360 @@
+//! Reading criticality inputs from Excel (.xlsx) workbooks.
+//!
+//! A workbook is a zip archive of xml parts. The sheet names are resolved through the workbook
+//! part and its relationships, cell text is resolved through the shared strings table.
+//!
+//! The archive and its parts are read with the crate's own ['crate::zip'], ['crate::inflate'] and
+//! ['crate::xml'] readers instead of calamine, which isn't a dependency of the crate. They handle
+//! the stored and deflated parts of ordinary workbooks. Legacy .xls, .xlsb and .ods files,
+//! encrypted workbooks and zip64 archives are not read, and formulas are read as their cached
+//! values. A cell or row reference outside of the sheet, such as 'A0', is a
+//! ['CellReferenceError'].
+
+use std::error::Error;
+use crate::analyses::criticality::CriticalityData;
+use crate::errors::input::{CellReferenceError, SheetNotFoundError};
+use crate::input::{apply_node_attributes, ColumnMapping, ColumnRef, create_keyed_edge_value_map, create_node_value_map, Input, LinkPolicies, NodeAttributeTable, read_mapped_links, RowStringMatrix, StringRow};
+use crate::network::{ALPHA_ATTR, Graph};
+use crate::xml;
+use crate::xml::XmlElement;
+use crate::zip::ZipArchive;
+
+const WORKBOOK_PART: &str = "xl/workbook.xml";
+const WORKBOOK_RELS_PART: &str = "xl/_rels/workbook.xml.rels";
+const SHARED_STRINGS_PART: &str = "xl/sharedStrings.xml";
+/// Columns and rows of the largest worksheet Excel can hold
+const MAX_COLUMNS: usize = 16_384;
+const MAX_ROWS: usize = 1_048_576;
+
+/// An opened workbook whose sheets can be read as string matrices
+pub struct Workbook {
+    archive: ZipArchive,
+    /// (sheet name, archive path of the sheet part)
+    sheets: Vec<(String, String)>,
+    shared_strings: Vec<String>,
+}
+
+impl Workbook {
+    /// Opens the workbook at 'path' and reads its sheet list and shared strings
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not a valid xlsx workbook
+    pub fn open(path: &str) -> Result<Workbook, Box<dyn Error>> {
+        let archive = ZipArchive::open(path)?;
+        let workbook = xml::parse(&archive.read_string(WORKBOOK_PART)?
+            .ok_or_else(|| format!("{} is missing {}", path, WORKBOOK_PART))?)?;
+        let rels = match archive.read_string(WORKBOOK_RELS_PART)? {
+            None => vec![],
+            Some(text) => xml::parse(&text)?.children_named("Relationship")
+                .filter_map(|r| Some((r.attr("Id")?.to_string(), r.attr("Target")?.to_string())))
+                .collect(),
+        };
+
+        let mut sheets = vec![];
+        if let Some(list) = workbook.child("sheets") {
+            for (i, sheet) in list.children_named("sheet").enumerate() {
+                let name = sheet.attr("name").unwrap_or("").to_string();
+                let target = sheet.attr("r:id")
+                    .and_then(|id| rels.iter().find(|(r_id, _)| r_id == id))
+                    .map(|(_, target)| target.to_string())
+                    .unwrap_or(format!("worksheets/sheet{}.xml", i + 1));
+                let part = match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                };
+                sheets.push((name, part));
+            }
+        }
+
+        let shared_strings = match archive.read_string(SHARED_STRINGS_PART)? {
+            None => vec![],
+            // Rich text entries are split into several runs, their text is concatenated
+            Some(text) => xml::parse(&text)?.children_named("si").map(|si| si.text()).collect(),
+        };
+        Ok(Workbook { archive, sheets, shared_strings })
+    }
+
+    pub fn sheet_names(&self) -> Vec<&str> {
+        self.sheets.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Reads the sheet called 'name' into a matrix. Empty cells become empty strings so every
+    /// cell keeps the column index it has in the sheet.
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['SheetNotFoundError'] if there is no sheet with the given name, or a
+    /// ['CellReferenceError'] if a cell or row reference is outside of the sheet
+    pub fn read_sheet(&self, name: &str) -> Result<RowStringMatrix, Box<dyn Error>> {
+        let part = self.sheets.iter()
+            .find(|(sheet, _)| sheet == name)
+            .map(|(_, part)| part)
+            .ok_or_else(|| SheetNotFoundError {
+                sheet: name.to_string(),
+                available: self.sheet_names().iter().map(|s| s.to_string()).collect(),
+            })?;
+        let text = self.archive.read_string(part)?
+            .ok_or_else(|| format!("The workbook is missing the part {}", part))?;
+        let worksheet = xml::parse(&text)?;
+
+        let mut rows: RowStringMatrix = vec![];
+        let data = match worksheet.child("sheetData") {
+            None => return Ok(rows),
+            Some(d) => d,
+        };
+        let invalid = |reference: &str| CellReferenceError { sheet: name.to_string(), reference: reference.to_string() };
+        for row in data.children_named("row") {
+            let mut cells: Vec<String> = vec![];
+            for cell in row.children_named("c") {
+                let col = match cell.attr("r") {
+                    Some(reference) => column_index(reference).ok_or_else(|| invalid(reference))?,
+                    None => cells.len(),
+                };
+                while cells.len() < col {
+                    cells.push("".to_string());
+                }
+                cells.push(self.cell_value(cell));
+            }
+            // Rows are numbered from 1
+            let row_index = match row.attr("r") {
+                Some(r) => Some(r.parse::<usize>().ok()
+                    .and_then(|r| r.checked_sub(1))
+                    .filter(|r| *r < MAX_ROWS)
+                    .ok_or_else(|| invalid(r))?),
+                None => None,
+            };
+            if let Some(row_index) = row_index {
+                while rows.len() < row_index {
+                    rows.push(vec![]);
+                }
+            }
+            rows.push(cells);
+        }
+        Ok(rows)
+    }
+
+    fn cell_value(&self, cell: &XmlElement) -> String {
+        let value = cell.child("v").map(|v| v.text()).unwrap_or_default();
+        match cell.attr("t") {
+            Some("s") => value.trim().parse::<usize>().ok()
+                .and_then(|i| self.shared_strings.get(i))
+                .cloned()
+                .unwrap_or_default(),
+            Some("inlineStr") => cell.child("is").map(|is| is.text()).unwrap_or_default(),
+            Some("b") => if value.trim() == "1" { "true".to_string() } else { "false".to_string() },
+            Some(_) => value,
+            // Numbers: whole numbers are written without a fraction so they can be used as ids
+            None => match value.trim().parse::<f64>() {
+                Ok(n) if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", n as i64),
+                _ => value,
+            },
+        }
+    }
+}
+
+/// Converts the column letters of a cell reference such as 'AB12' to a zero based index, None if
+/// the reference has no letters, a row below 1 or a column beyond the sheet
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: String = reference.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    let row = reference[letters.len()..].parse::<usize>().ok()?;
+    if letters.is_empty() || row == 0 {
+        return None;
+    }
+    let mut index: usize = 0;
+    for c in letters.to_ascii_uppercase().chars() {
+        index = index.checked_mul(26)?.checked_add(c as usize - 'A' as usize + 1)?;
+    }
+    index.checked_sub(1).filter(|i| *i < MAX_COLUMNS)
+}
+
+/// Sheet holding named values for every edge, identified by the from and to node ids
+#[derive(Debug, Clone)]
+pub struct EdgeValueSheet {
+    pub sheet: String,
+    pub from_id: ColumnRef,
+    pub to_id: ColumnRef,
+    /// (attribute name, column) of every attribute in the sheet
+    pub values: Vec<(String, ColumnRef)>,
+}
+
+/// Sheet holding a value for every node, identified by the node id
+#[derive(Debug, Clone)]
+pub struct NodeValueSheet {
+    pub sheet: String,
+    pub id: ColumnRef,
+    pub value: ColumnRef,
+}
+
+/// Configurations which hold information necessary to read values for the criticality analysis
+/// from an Excel workbook
+#[derive(Debug, Clone)]
+pub struct XlsxCritConfigs {
+    /// The path to the workbook
+    pub in_path: String,
+    /// Whether the first row of every sheet is a header
+    pub has_headers: bool,
+    /// Sheet containing one edge per row
+    pub links_sheet: String,
+    /// Columns of the links sheet. If none is given the header names are used, see
+    /// ['ColumnMapping::from_headers']
+    pub links_mapping: Option<ColumnMapping>,
+    /// Sheets holding edge attributes, such as the alpha weights
+    pub edge_attributes: Vec<EdgeValueSheet>,
+    /// Where to read the off chance of each node from, if anywhere
+    pub probabilities: Option<NodeValueSheet>,
+    /// Sheet with typed attributes of the nodes, the source of the table is the sheet name
+    pub node_attributes: Option<NodeAttributeTable>,
+}
+
+impl Default for XlsxCritConfigs {
+    fn default() -> Self {
+        XlsxCritConfigs {
+            in_path: "./links.xlsx".to_string(),
+            has_headers: true,
+            links_sheet: "links".to_string(),
+            links_mapping: Some(ColumnMapping::default()),
+            edge_attributes: vec![EdgeValueSheet {
+                sheet: "alpha".to_string(),
+                from_id: 0.into(),
+                to_id: 1.into(),
+                values: vec![(ALPHA_ATTR.to_string(), 2.into())],
+            }],
+            probabilities: Some(NodeValueSheet { sheet: "probabilities".to_string(), id: 0.into(), value: 1.into() }),
+            node_attributes: None,
+        }
+    }
+}
+
+/// Structure used to read all the values necessary for a criticality analysis from a workbook
+pub struct XlsxCritInput {}
+
+impl XlsxCritInput {
+    fn read_rows(workbook: &Workbook, sheet: &str, has_headers: bool) -> Result<(Option<StringRow>, RowStringMatrix), Box<dyn Error>> {
+        let mut rows = workbook.read_sheet(sheet)?.into_iter();
+        let headers = match has_headers {
+            true => Some(rows.next().unwrap_or_default()),
+            false => None,
+        };
+        // Rows without any value are formatting left overs, not edges
+        let rows = rows.filter(|row| row.iter().any(|cell| !cell.trim().is_empty())).collect();
+        Ok((headers, rows))
+    }
+}
+
+impl Input for XlsxCritInput {
+    type Configs = XlsxCritConfigs;
+    type AnalysisData = CriticalityData;
+
+    fn read(&self, configs: XlsxCritConfigs) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let workbook = Workbook::open(&configs.in_path)?;
+        let (headers, links) = XlsxCritInput::read_rows(&workbook, &configs.links_sheet, configs.has_headers)?;
+        let mapping = match (&configs.links_mapping, &headers) {
+            (Some(mapping), _) => mapping.clone(),
+            (None, Some(headers)) => ColumnMapping::from_headers(headers),
+            (None, None) => ColumnMapping::default(),
+        };
+        let (mut graph, mut data) = read_mapped_links(&headers, &links, &mapping, &LinkPolicies::default())?;
+        if let Some(table) = &configs.node_attributes {
+            let (headers, rows) = XlsxCritInput::read_rows(&workbook, &table.source, configs.has_headers)?;
+            apply_node_attributes(&mut graph, &headers, &rows, table)?;
+        }
+
+        for attributes in configs.edge_attributes.iter() {
+            let (headers, rows) = XlsxCritInput::read_rows(&workbook, &attributes.sheet, configs.has_headers)?;
+            let from_id = attributes.from_id.resolve(&headers)?;
+            let to_id = attributes.to_id.resolve(&headers)?;
+            for (name, column) in attributes.values.iter() {
+                data.add_edge_attribute(name, create_keyed_edge_value_map(&rows, from_id, to_id, column.resolve(&headers)?, &graph.keys())?);
+            }
+        }
+        if let Some(probabilities) = &configs.probabilities {
+            let (headers, rows) = XlsxCritInput::read_rows(&workbook, &probabilities.sheet, configs.has_headers)?;
+            data.off_chances.append(&mut create_node_value_map(&rows,
+                probabilities.id.resolve(&headers)?, probabilities.value.resolve(&headers)?, &graph.keys())?);
+        }
+        Ok((graph, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::network::NodeValueMap;
+    use crate::zip::tests::archive;
+
+    /// A cell holding a number, at 'reference'
+    fn cell(reference: &str, value: &str) -> String {
+        format!("<c r=\"{}\"><v>{}</v></c>", reference, value)
+    }
+
+    /// Writes a workbook with the sheets (name, rows of cells) to a temporary file
+    fn workbook(name: &str, sheets: &[(&str, Vec<String>)]) -> String {
+        let mut parts = vec![];
+        let mut list = String::new();
+        let mut rels = String::new();
+        for (i, (sheet, rows)) in sheets.iter().enumerate() {
+            list.push_str(&format!("<sheet name=\"{}\" sheetId=\"{}\" r:id=\"rId{}\"/>", sheet, i + 1, i + 1));
+            rels.push_str(&format!("<Relationship Id=\"rId{}\" Target=\"worksheets/s{}.xml\"/>", i + 1, i + 1));
+            parts.push((format!("xl/worksheets/s{}.xml", i + 1), format!("<worksheet><sheetData>{}</sheetData></worksheet>", rows.concat())));
+        }
+        parts.push((WORKBOOK_PART.to_string(), format!("<workbook xmlns:r=\"urn:r\"><sheets>{}</sheets></workbook>", list)));
+        parts.push((WORKBOOK_RELS_PART.to_string(), format!("<Relationships>{}</Relationships>", rels)));
+        let entries: Vec<(&str, u16, &[u8], u32)> = parts.iter()
+            .map(|(part, text)| (part.as_str(), 0, text.as_bytes(), text.len() as u32))
+            .collect();
+        let path = std::env::temp_dir().join(format!("thor_{}_{}.xlsx", name, std::process::id()));
+        std::fs::write(&path, archive(&entries)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn read(path: &str) -> Result<(Graph, CriticalityData), Box<dyn Error>> {
+        let configs = XlsxCritConfigs { in_path: path.to_string(), edge_attributes: vec![], ..Default::default() };
+        let read = XlsxCritInput {}.read(configs);
+        std::fs::remove_file(path).unwrap();
+        read
+    }
+
+    fn links() -> Vec<String> {
+        vec![
+            "<row r=\"1\"><c r=\"A1\" t=\"inlineStr\"><is><t>from</t></is></c></row>".to_string(),
+            format!("<row r=\"2\">{}{}{}{}</row>", cell("A2", "0"), cell("B2", "0"), cell("C2", "1"), cell("D2", "1")),
+        ]
+    }
+
+    #[test]
+    fn links_and_off_chances_are_read_from_their_sheets() {
+        let probabilities = vec![
+            "<row r=\"1\"/>".to_string(),
+            // Row 3 after an empty row 2, the blank row is skipped
+            format!("<row r=\"3\">{}{}</row>", cell("A3", "1"), cell("B3", "0.9")),
+        ];
+        let path = workbook("read", &[("links", links()), ("probabilities", probabilities)]);
+        let (graph, data) = read(&path).unwrap();
+        assert_eq!(graph.get_node_ids(), HashSet::from([0, 1]));
+        assert!(graph.get_edge(0, 1).is_some());
+        assert_eq!(data.off_chances, NodeValueMap::from([(1, 0.9)]));
+    }
+
+    #[test]
+    fn references_outside_of_the_sheet_are_errors() {
+        for (name, row) in [("cell", format!("<row r=\"3\">{}</row>", cell("A0", "1"))), ("row", "<row r=\"0\"/>".to_string())] {
+            let path = workbook(name, &[("links", links()), ("probabilities", vec![row])]);
+            let error = read(&path).err().unwrap().to_string();
+            let reference = if name == "cell" { "A0" } else { "0" };
+            assert_eq!(error, format!("The sheet 'probabilities' has a cell or row with the invalid reference '{}'", reference));
+        }
+    }
+
+    #[test]
+    fn column_indexes_of_cell_references() {
+        assert_eq!(column_index("A1"), Some(0));
+        assert_eq!(column_index("ab12"), Some(27));
+        assert_eq!(column_index("XFD1048576"), Some(16_383));
+        // No row, no column, a row below 1, beyond the sheet or overflowing
+        for reference in ["A", "12", "A0", "XFE1", "ZZZZZZZZZZZZZZZZ1"] {
+            assert_eq!(column_index(reference), None, "{}", reference);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+//! Minimal JSON value type with a parser and a writer.
+//!
+//! Used wherever the program needs to talk JSON (database APIs, result files, configs) without
+//! pulling in a full serialization framework. Objects keep their keys in insertion order so
+//! written files stay stable and readable.
+
+use std::fmt;
+use std::fmt::{Display, Formatter, Write};
+use crate::errors::json::JsonParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Creates an empty object that can be filled using ['JsonValue::insert']
+    pub fn object() -> JsonValue {
+        JsonValue::Object(vec![])
+    }
+
+    /// Inserts a key into an object, replacing the previous value with the same key.
+    /// Does nothing if the value is not an object.
+    pub fn insert<K: Into<String>, V: Into<JsonValue>>(&mut self, key: K, value: V) {
+        if let JsonValue::Object(entries) = self {
+            let key = key.into();
+            let value = value.into();
+            match entries.iter_mut().find(|(k, _)| *k == key) {
+                None => entries.push((key, value)),
+                Some(entry) => entry.1 = value,
+            }
+        }
+    }
+
+    /// Builder style version of ['JsonValue::insert']
+    pub fn with<K: Into<String>, V: Into<JsonValue>>(mut self, key: K, value: V) -> JsonValue {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Vec<(String, JsonValue)>> {
+        match self {
+            JsonValue::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    /// Converts a scalar value into the string used when treating it as a table cell
+    pub fn to_cell_string(&self) -> String {
+        match self {
+            JsonValue::String(s) => s.to_string(),
+            JsonValue::Null => "".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Writes the value with new lines and two space indentation
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, Some(0)).unwrap();
+        out
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self { JsonValue::Bool(b) }
+}
+impl From<f64> for JsonValue {
+    fn from(n: f64) -> Self { JsonValue::Number(n) }
+}
+impl From<f32> for JsonValue {
+    fn from(n: f32) -> Self { JsonValue::Number(n as f64) }
+}
+impl From<u64> for JsonValue {
+    fn from(n: u64) -> Self { JsonValue::Number(n as f64) }
+}
+impl From<u32> for JsonValue {
+    fn from(n: u32) -> Self { JsonValue::Number(n as f64) }
+}
+impl From<usize> for JsonValue {
+    fn from(n: usize) -> Self { JsonValue::Number(n as f64) }
+}
+impl From<i64> for JsonValue {
+    fn from(n: i64) -> Self { JsonValue::Number(n as f64) }
+}
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self { JsonValue::String(s.to_string()) }
+}
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self { JsonValue::String(s) }
+}
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(v: Vec<T>) -> Self { JsonValue::Array(v.into_iter().map(|x| x.into()).collect()) }
+}
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(o: Option<T>) -> Self {
+        match o {
+            None => JsonValue::Null,
+            Some(x) => x.into(),
+        }
+    }
+}
+
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write_value(&mut out, self, None)?;
+        f.write_str(&out)
+    }
+}
+
+fn write_indent(out: &mut String, indent: Option<usize>) {
+    if let Some(level) = indent {
+        out.push('\n');
+        for _ in 0..level {
+            out.push_str("  ");
+        }
+    }
+}
+
+fn write_value(out: &mut String, value: &JsonValue, indent: Option<usize>) -> fmt::Result {
+    let inner = indent.map(|i| i + 1);
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Bool(b) => write!(out, "{}", b)?,
+        JsonValue::Number(n) => {
+            if !n.is_finite() {
+                out.push_str("null");
+            } else if n.fract() == 0.0 && n.abs() < 1e15 {
+                write!(out, "{}", *n as i64)?;
+            } else {
+                write!(out, "{}", n)?;
+            }
+        }
+        JsonValue::String(s) => write_string(out, s)?,
+        JsonValue::Array(values) => {
+            out.push('[');
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 { out.push(','); }
+                write_indent(out, inner);
+                write_value(out, v, inner)?;
+            }
+            if !values.is_empty() { write_indent(out, indent); }
+            out.push(']');
+        }
+        JsonValue::Object(entries) => {
+            out.push('{');
+            for (i, (k, v)) in entries.iter().enumerate() {
+                if i > 0 { out.push(','); }
+                write_indent(out, inner);
+                write_string(out, k)?;
+                out.push(':');
+                if indent.is_some() { out.push(' '); }
+                write_value(out, v, inner)?;
+            }
+            if !entries.is_empty() { write_indent(out, indent); }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(out: &mut String, s: &str) -> fmt::Result {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}
+
+/// Parses a JSON document
+///
+/// # Errors
+///
+/// Returns a ['JsonParseError'] with the byte position of the first invalid character
+pub fn parse(text: &str) -> Result<JsonValue, JsonParseError> {
+    let mut parser = Parser { chars: text.as_bytes(), pos: 0 };
+    parser.skip_whitespace();
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.chars.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    chars: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &str) -> JsonParseError {
+        JsonParseError { pos: self.pos, reason: reason.to_string() }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_whitespace() { break; }
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), JsonParseError> {
+        if self.chars[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", literal)))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, JsonParseError> {
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'n') => self.expect("null").map(|_| JsonValue::Null),
+            Some(b't') => self.expect("true").map(|_| JsonValue::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| JsonValue::Bool(false)),
+            Some(b'"') => self.parse_string().map(JsonValue::String),
+            Some(b'[') => self.parse_array(),
+            Some(b'{') => self.parse_object(),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, JsonParseError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.' | b'e' | b'E') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let text = std::str::from_utf8(&self.chars[start..self.pos]).unwrap();
+        text.parse::<f64>()
+            .map(JsonValue::Number)
+            .map_err(|_| JsonParseError { pos: start, reason: format!("invalid number '{}'", text) })
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonParseError> {
+        let hex = self.chars.get(self.pos..self.pos + 4)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u32::from_str_radix(h, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(hex)
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonParseError> {
+        self.pos += 1;
+        let mut bytes: Vec<u8> = vec![];
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    let decoded = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.parse_hex4()?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                self.expect("\\u")?;
+                                let low = self.parse_hex4()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    let mut buf = [0u8; 4];
+                    bytes.extend_from_slice(decoded.encode_utf8(&mut buf).as_bytes());
+                }
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid utf-8 in string"))
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, JsonParseError> {
+        self.pos += 1;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(values));
+        }
+        loop {
+            self.skip_whitespace();
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => { self.pos += 1; break; }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+        Ok(JsonValue::Array(values))
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, JsonParseError> {
+        self.pos += 1;
+        let mut object = JsonValue::object();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(object);
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            object.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => { self.pos += 1; break; }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+        Ok(object)
+    }
+}
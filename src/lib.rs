@@ -0,0 +1,52 @@
+pub mod input;
+pub mod output;
+pub mod export;
+pub mod network;
+pub mod editor;
+pub mod errors;
+pub mod roll_up;
+pub mod analyses;
+pub mod util;
+pub mod validation;
+pub mod cli;
+pub mod config;
+pub mod settings;
+pub mod pipeline;
+pub mod registry;
+pub mod orchestrator;
+pub mod sweep;
+pub mod metrics;
+pub mod logging;
+pub mod exit;
+pub mod manifest;
+pub mod hooks;
+pub mod generator;
+pub mod anonymize;
+pub mod json;
+pub mod http;
+pub mod xml;
+pub mod zip;
+pub mod inflate;
+pub mod gzip;
+pub mod zstd;
+pub mod mmap;
+pub mod checksum;
+pub mod pattern;
+pub mod render;
+#[cfg(feature = "serde")]
+pub mod serialization;
+
+#[cfg(feature = "serde")]
+pub mod server;
+#[cfg(feature = "serde")]
+pub mod websocket;
+#[cfg(feature = "serde")]
+pub mod daemon;
+#[cfg(feature = "serde")]
+pub mod audit;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
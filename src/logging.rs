@@ -0,0 +1,125 @@
+//! Log output of the binary, as text lines or as json lines.
+//!
+//! Besides the free text messages the program logs structured events under ['EVENT_TARGET']:
+//! 'run_started', 'run_finished', 'phase_completed', 'worker_finished' and 'error'. In the json
+//! format every line is an object with 'timestamp', 'level' and 'target', events add their name
+//! under 'event' and their fields, other messages their text under 'message'. Errors carry a
+//! stable 'code', see ['error_code'].
+
+use std::error::Error;
+use std::io::Write;
+use log::{log, Level};
+use crate::errors::analysis::{BaselineDeviationError, CriticalBandError, LimitExceededError, ThorError, TooManyNodesError, UnsupportedRuleError};
+use crate::errors::config::ConfigError;
+use crate::errors::filter::FilterError;
+use crate::errors::input::{ColumnNotFoundError, InputError, ModelError, SnapshotError, UnknownFormatError};
+use crate::errors::json::JsonParseError;
+use crate::errors::network::{EndNodeError, NoEndConnectionError, StartNodeError, UnknownNodeError, UnknownNodesError};
+use crate::errors::validation::ValidationError;
+use crate::json;
+use crate::json::JsonValue;
+
+/// Target of the structured events
+pub const EVENT_TARGET: &str = "thor::event";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One json object per line
+    Json,
+}
+
+impl LogFormat {
+    /// The format given by '--log-format text|json'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format is unknown
+    pub fn from_args(args: &[String]) -> Result<LogFormat, String> {
+        let position = args.iter().position(|a| a == "--log-format");
+        match position.and_then(|i| args.get(i + 1)).map(|f| f.as_str()) {
+            None | Some("text") => Ok(LogFormat::Text),
+            Some("json") => Ok(LogFormat::Json),
+            Some(other) => Err(format!("Unknown log format '{}', expected text or json", other)),
+        }
+    }
+}
+
+/// Installs the logger, filtered by the 'RUST_LOG' variable. Without the variable json logs show
+/// the info level so the events are written, text logs only errors.
+pub fn init(format: LogFormat) {
+    let default_filter = match format {
+        LogFormat::Text => "error",
+        LogFormat::Json => "info",
+    };
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter));
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let mut line = JsonValue::object()
+                .with("timestamp", buf.timestamp_millis().to_string())
+                .with("level", record.level().as_str())
+                .with("target", record.target());
+            let message = record.args().to_string();
+            // Events are logged as their name followed by their fields as a json object
+            let event = match record.target() == EVENT_TARGET {
+                true => message.split_once(' ').and_then(|(name, fields)| Some((name, json::parse(fields).ok()?))),
+                false => None,
+            };
+            match event {
+                Some((name, JsonValue::Object(fields))) => {
+                    line.insert("event", name);
+                    for (key, value) in fields {
+                        line.insert(key, value);
+                    }
+                }
+                _ => line.insert("message", message),
+            }
+            writeln!(buf, "{}", line)
+        });
+    }
+    builder.init();
+}
+
+/// Logs the event 'name' with the 'fields', which must be a json object
+pub fn event(level: Level, name: &str, fields: JsonValue) {
+    log!(target: EVENT_TARGET, level, "{} {}", name, fields);
+}
+
+/// Logs an 'error' event with the code and the message of the error
+pub fn error_event(e: &(dyn Error + 'static)) {
+    event(Level::Error, "error", JsonValue::object().with("code", error_code(e)).with("message", e.to_string()));
+}
+
+/// Stable code of the kind of the error, 'unknown' for kinds without a code
+pub fn error_code(e: &(dyn Error + 'static)) -> &'static str {
+    if let Some(ThorError::Failed(inner)) = e.downcast_ref::<ThorError>() {
+        return error_code(inner.as_ref());
+    }
+    if let Some(InputError { source }) = e.downcast_ref::<InputError>() {
+        return error_code(source.as_ref());
+    }
+    match e {
+        e if e.is::<ThorError>() => "cancelled",
+        e if e.is::<LimitExceededError>() => "limit_exceeded",
+        e if e.is::<BaselineDeviationError>() => "baseline_deviation",
+        e if e.is::<CriticalBandError>() => "critical_nodes",
+        e if e.is::<TooManyNodesError>() => "too_many_nodes",
+        e if e.is::<UnsupportedRuleError>() => "unsupported_rule",
+        e if e.is::<ConfigError>() => "invalid_config",
+        e if e.is::<JsonParseError>() => "invalid_json",
+        e if e.is::<ColumnNotFoundError>() => "missing_column",
+        e if e.is::<ModelError>() => "invalid_model",
+        e if e.is::<SnapshotError>() => "invalid_snapshot",
+        e if e.is::<UnknownFormatError>() => "unknown_format",
+        e if e.is::<FilterError>() => "invalid_filter",
+        e if e.is::<StartNodeError>() || e.is::<EndNodeError>() => "missing_start_or_end",
+        e if e.is::<NoEndConnectionError>() => "no_end_connection",
+        e if e.is::<UnknownNodesError>() => "unknown_nodes",
+        e if e.is::<UnknownNodeError>() => "unknown_node",
+        e if e.is::<ValidationError>() => "invalid_input",
+        e if e.is::<std::io::Error>() => "io",
+        e if e.is::<csv::Error>() => "invalid_csv",
+        _ => "unknown",
+    }
+}
@@ -1,70 +1,35 @@
-mod input;
-mod network;
-mod errors;
-mod roll_up;
-mod analyses;
-mod util;
+use std::env;
+use std::process::ExitCode;
+use thor_reforged::exit::{ErrorFormat, report_error};
+use thor_reforged::logging::{error_event, init, LogFormat};
+use thor_reforged::settings::Settings;
 
-use std::collections::HashSet;
-use std::error::Error;
-use crate::analyses::{Analysis};
-use crate::analyses::criticality::{Criticality};
-use crate::network::Graph;
-use crate::roll_up::OrRule;
-use std::time::{Instant};
-use rand::rngs::StdRng;
-use rand::SeedableRng;
-use crate::analyses::criticality::loop_condition::MaxLoopCondition;
-use crate::analyses::criticality::vis_gen::visibility_states_gen::RandomGen;
-use crate::input::{Input, STDCritConfigs, STDCritInput};
-
-fn init(){
-    env_logger::init();
-}
-
-fn main() -> Result<(), Box<dyn Error>>{
-    init();
-    //let args: Vec<String> = env::args().collect();
-    //dbg!(args);
-
-    let crit_config = STDCritConfigs { in_path: "./links.csv".to_string()};
-    let crit_input = STDCritInput {};
-    let (mut graph, _crit_data) = crit_input.read(crit_config)?;
-
-    let l_map = graph.links_map();
-    let start_id = Graph::get_start_id(&l_map).unwrap();
-    let end_id = Graph::get_end_id(&l_map).unwrap();
-    graph.static_nodes.insert(start_id);
-    graph.static_nodes.insert(end_id);
-    let dynamic_ids: HashSet<u32> = graph.get_node_ids().into_iter()
-        .filter(|id| !graph.static_nodes.contains(id))
-        .collect();
-
-    let crit = Criticality {
-        threads: num_cpus::get() as u8,
-        graph,
-        dynamic_ids: dynamic_ids.clone(),
-        vis_gen: Box::new(
-            RandomGen {
-                rng: StdRng::from_entropy(),
-                ids: dynamic_ids,
-                off_chances: Default::default(),
-            }
-        ),
-        loop_condition: Box::new(
-            MaxLoopCondition {
-                max: 9,
-                index: 0 }
-        ),
-        roll_up_rule: Box::new(
-            OrRule {}
-        ),
-        l_map,
-        start_id,
-        end_id,
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    // Flags given by 'THOR_*' environment variables, below those of the command line
+    let vars = env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    let settings = Settings::new(&args).with_env(vars);
+    let args = settings.args();
+    let formats = LogFormat::from_args(&args).and_then(|log| Ok((log, ErrorFormat::from_args(&args)?)));
+    let (log_format, error_format) = match formats {
+        Ok(formats) => formats,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            return ExitCode::FAILURE;
+        }
     };
-    let start = Instant::now();
-    crit.analyze();
-    println!("Time elapsed: {:?}", start.elapsed());
-    Ok(())
+    init(log_format);
+    settings.log();
+    // The first argument selects the command: analyze (the default), validate, convert, report,
+    // serve or generate, see the cli module
+    match thor_reforged::cli::run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // Text logs leave the error to the report below
+            if log_format == LogFormat::Json {
+                error_event(e.as_ref());
+            }
+            report_error(e.as_ref(), error_format, thor_reforged::cli::command(&args).unwrap_or("analyze"))
+        }
+    }
 }
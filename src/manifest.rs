@@ -0,0 +1,172 @@
+//! Run manifests, recording what produced a set of results.
+//!
+//! With '--manifest <path>', or next to the '--results' file of an analysis as
+//! '<results>.manifest.json', a successful run writes a json object with:
+//!
+//! * 'tool': the name and version of the crate and the git commit it was built from
+//! * 'command' and 'args': the command and every flag in effect, after the configuration, its
+//!   profile and the 'THOR_*' variables were applied, see ['crate::settings']
+//! * 'config': the path, profile and hash of the configuration, if one was used
+//! * 'seed': the '--seed' the random states of the run were drawn from. An analysis without one
+//!   is given a random seed below ['MAX_EXACT_SEED'], which is recorded, so the run draws the
+//!   same states again with the same seed and '--threads'. Its states can also be replayed from its '--record' file, which
+//!   is listed under 'outputs'.
+//! * 'inputs' and 'outputs': the path, size and xxh64 hash of every file read or written, see
+//!   ['INPUT_FLAGS'] and ['OUTPUT_FLAGS']. Remote inputs are hashed in their cached copy.
+//! * 'timing': the start and end of the run in UTC and its length in seconds
+//! * 'host': the name, operating system, architecture and cpu count of the host
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::checksum;
+use crate::cli::{arg_value, command};
+use crate::config::DEFAULT_CONFIG;
+use crate::input::{remote, STDIN_PATH};
+use crate::json::JsonValue;
+use crate::mmap::MappedFile;
+use crate::output::{STDOUT_PATH, write_output};
+
+/// Git commit the binary was built from, empty outside of a git checkout
+pub const GIT_HASH: &str = env!("THOR_GIT_HASH");
+
+/// Largest seed a json number holds exactly, larger seeds are recorded as strings
+pub const MAX_EXACT_SEED: u64 = (1 << 53) - 1;
+
+/// Flags naming the files a run reads
+pub const INPUT_FLAGS: [&str; 10] = ["--input", "--alpha", "--config", "--node-attributes", "--scenarios", "--dependencies", "--ccf-groups", "--event-tree", "--replay", "--baseline"];
+
+/// Flags naming the files a run writes
+pub const OUTPUT_FLAGS: [&str; 13] = ["--results", "--output", "--mapping", "--alert-file", "--svg", "--png", "--html", "--cytoscape", "--node-link", "--record", "--sample-log", "--partial-results", "--convergence"];
+
+/// A run whose manifest is written once it succeeds
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    path: String,
+    started: SystemTime,
+    start: Instant,
+}
+
+impl Manifest {
+    /// Starts the manifest of a run with the 'args', None if no manifest is asked for
+    pub fn start(args: &[String]) -> Option<Manifest> {
+        let path = match (arg_value(args, "--manifest"), arg_value(args, "--results")) {
+            (Some(path), _) => path.to_string(),
+            // Only the runs writing the results, not the commands reading them again
+            (None, Some(results)) if results != STDOUT_PATH && matches!(command(args), None | Some("analyze") | Some("load")) => {
+                format!("{}.manifest.json", results)
+            }
+            _ => return None,
+        };
+        Some(Manifest { path, started: SystemTime::now(), start: Instant::now() })
+    }
+
+    /// The manifest of the run with the 'args', now that it finished
+    pub fn to_json(&self, args: &[String]) -> JsonValue {
+        let config = arg_value(args, "--config").map(|c| c.as_str())
+            .or(Some(DEFAULT_CONFIG).filter(|c| Path::new(c).exists()))
+            .map(|path| JsonValue::object()
+                .with("path", path)
+                .with("profile", arg_value(args, "--profile").cloned())
+                .with("xxh64", hash_file(path).map(|(_, hash)| hash)));
+        JsonValue::object()
+            .with("tool", tool())
+            .with("command", command(args).unwrap_or("analyze"))
+            .with("args", args.iter().skip(1).cloned().collect::<Vec<String>>())
+            .with("config", config)
+            .with("seed", seed(args))
+            .with("inputs", files(args, &INPUT_FLAGS))
+            .with("outputs", files(args, &OUTPUT_FLAGS))
+            .with("timing", JsonValue::object()
+                .with("started", utc_timestamp(self.started))
+                .with("finished", utc_timestamp(SystemTime::now()))
+                .with("seconds", self.start.elapsed().as_secs_f64()))
+            .with("host", JsonValue::object()
+                .with("name", host_name())
+                .with("os", env::consts::OS)
+                .with("arch", env::consts::ARCH)
+                .with("cpus", num_cpus::get()))
+    }
+
+    /// Writes the manifest of the run with the 'args'
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the manifest can't be written
+    pub fn write(&self, args: &[String]) -> io::Result<()> {
+        write_output(&self.path, self.to_json(args).to_pretty_string() + "\n")
+    }
+}
+
+/// The '--seed' of the 'args' as a number, or as a string of its digits if it is above
+/// ['MAX_EXACT_SEED'], null without one
+pub(crate) fn seed(args: &[String]) -> JsonValue {
+    match arg_value(args, "--seed").and_then(|s| s.parse::<u64>().ok()) {
+        Some(seed) if seed <= MAX_EXACT_SEED => JsonValue::from(seed),
+        Some(seed) => JsonValue::from(seed.to_string()),
+        None => JsonValue::Null,
+    }
+}
+
+/// Name and version of the crate and the git commit it was built from
+pub(crate) fn tool() -> JsonValue {
+    JsonValue::object()
+        .with("name", env!("CARGO_PKG_NAME"))
+        .with("version", env!("CARGO_PKG_VERSION"))
+        .with("git_hash", Some(GIT_HASH).filter(|h| !h.is_empty()))
+}
+
+/// Path, size and hash of the file of every flag in 'flags' given in the 'args'
+pub(crate) fn files(args: &[String], flags: &[&str]) -> Vec<JsonValue> {
+    flags.iter()
+        .filter_map(|flag| Some((*flag, arg_value(args, flag)?)))
+        .map(|(flag, path)| {
+            let hashed = hash_file(path);
+            JsonValue::object()
+                .with("flag", flag)
+                .with("path", path.as_str())
+                .with("bytes", hashed.as_ref().map(|(len, _)| *len))
+                .with("xxh64", hashed.map(|(_, hash)| hash))
+        })
+        .collect()
+}
+
+/// Size and xxh64 hash of the file at 'path', None if it can't be read
+fn hash_file(path: &str) -> Option<(usize, String)> {
+    let local = match remote::is_remote(path) {
+        true => remote::cached_path(path).to_string_lossy().to_string(),
+        false => path.to_string(),
+    };
+    if local == STDIN_PATH {
+        return None;
+    }
+    let data = MappedFile::open(&local).ok()?;
+    Some((data.len(), format!("{:016x}", checksum::xxh64(&data, 0))))
+}
+
+/// Name of the host, None if it can't be found
+fn host_name() -> Option<String> {
+    env::var("HOSTNAME").ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// The 'time' in UTC as '2024-01-31T12:00:00Z'
+pub(crate) fn utc_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    // Civil date of the days since 1970-01-01, counted in eras of 400 years from 0000-03-01
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rest / 3600, rest / 60 % 60, rest % 60)
+}
@@ -0,0 +1,109 @@
+//! Counters of a running service, exposed in the Prometheus text format.
+//!
+//! The server shares one ['Metrics'] with every analysis it runs through the
+//! ['crate::analyses::AnalysisContext'], the sampling engine counts its workers and the states it
+//! drew while it runs, in batches of a thousand or so states per worker, and the server counts
+//! the requests and the runs. The rate of 'thor_states_sampled_total' is the live throughput.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Content type of ['Metrics::render']
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    states_sampled: AtomicU64,
+    active_workers: AtomicU64,
+    queued_requests: AtomicU64,
+    runs_completed: AtomicU64,
+    runs_failed: AtomicU64,
+    /// Duration and states per second of the last completed run, stored as f64 bits
+    last_run_seconds: AtomicU64,
+    last_run_states_per_second: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            started: Instant::now(),
+            states_sampled: AtomicU64::new(0),
+            active_workers: AtomicU64::new(0),
+            queued_requests: AtomicU64::new(0),
+            runs_completed: AtomicU64::new(0),
+            runs_failed: AtomicU64::new(0),
+            last_run_seconds: AtomicU64::new(0f64.to_bits()),
+            last_run_states_per_second: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    pub fn worker_started(&self) {
+        self.active_workers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn worker_finished(&self) {
+        self.active_workers.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Counts 'states' more states drawn by a worker
+    pub fn states_sampled(&self, states: u64) {
+        self.states_sampled.fetch_add(states, Ordering::Relaxed);
+    }
+
+    pub fn request_received(&self) {
+        self.queued_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn request_answered(&self) {
+        self.queued_requests.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Records a completed run that evaluated 'states' states
+    pub fn run_completed(&self, duration: Duration, states: u64) {
+        let seconds = duration.as_secs_f64();
+        let rate = if seconds > 0.0 { states as f64 / seconds } else { 0.0 };
+        self.last_run_seconds.store(seconds.to_bits(), Ordering::Relaxed);
+        self.last_run_states_per_second.store(rate.to_bits(), Ordering::Relaxed);
+        self.runs_completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn run_failed(&self) {
+        self.runs_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let float = |value: &AtomicU64| f64::from_bits(value.load(Ordering::Relaxed));
+        let metrics: [(&str, &str, &str, f64); 8] = [
+            ("thor_states_sampled_total", "counter", "Visibility states drawn by the sampling workers",
+             self.states_sampled.load(Ordering::Relaxed) as f64),
+            ("thor_active_workers", "gauge", "Sampling workers currently running",
+             self.active_workers.load(Ordering::Relaxed) as f64),
+            ("thor_queue_depth", "gauge", "Requests received and not answered yet",
+             self.queued_requests.load(Ordering::Relaxed) as f64),
+            ("thor_runs_completed_total", "counter", "Analysis runs that completed",
+             self.runs_completed.load(Ordering::Relaxed) as f64),
+            ("thor_runs_failed_total", "counter", "Analysis runs that failed",
+             self.runs_failed.load(Ordering::Relaxed) as f64),
+            ("thor_last_run_duration_seconds", "gauge", "Duration of the last completed run",
+             float(&self.last_run_seconds)),
+            ("thor_last_run_states_per_second", "gauge", "States evaluated per second by the last completed run",
+             float(&self.last_run_states_per_second)),
+            ("thor_uptime_seconds", "gauge", "Time since the service started",
+             self.started.elapsed().as_secs_f64()),
+        ];
+        let mut text = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(text, "# HELP {} {}\n# TYPE {} {}\n{} {}", name, help, name, kind, name, value);
+        }
+        text
+    }
+}
@@ -0,0 +1,91 @@
+//! Read-only memory maps of files, so large inputs are parsed in place instead of being copied
+//! into memory first.
+//!
+//! Platforms without 'mmap' read the whole file instead. The file must not be truncated by
+//! another process while it is mapped.
+
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+
+/// The content of a file, mapped into memory or read
+pub struct MappedFile {
+    #[cfg(unix)]
+    ptr: *mut libc::c_void,
+    #[cfg(unix)]
+    len: usize,
+    #[cfg(not(unix))]
+    data: Vec<u8>,
+}
+
+// The mapping is read only and owned by the struct
+unsafe impl Send for MappedFile {}
+unsafe impl Sync for MappedFile {}
+
+impl MappedFile {
+    /// Maps the file at 'path' into memory
+    ///
+    /// # Errors
+    ///
+    /// Will return an io error if the file can't be opened or mapped
+    #[cfg(unix)]
+    pub fn open(path: &str) -> io::Result<MappedFile> {
+        use std::os::unix::io::AsRawFd;
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "the file is too large to be mapped"))?;
+        // Empty files can't be mapped
+        if len == 0 {
+            return Ok(MappedFile { ptr: std::ptr::null_mut(), len });
+        }
+        // SAFETY: a fresh private read only mapping of an open file, released in drop. The file
+        // descriptor may be closed once the mapping exists.
+        let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(MappedFile { ptr, len })
+    }
+
+    /// Reads the file at 'path' into memory
+    ///
+    /// # Errors
+    ///
+    /// Will return an io error if the file can't be read
+    #[cfg(not(unix))]
+    pub fn open(path: &str) -> io::Result<MappedFile> {
+        use std::io::Read;
+        let mut data = vec![];
+        File::open(path)?.read_to_end(&mut data)?;
+        Ok(MappedFile { data })
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    #[cfg(unix)]
+    fn deref(&self) -> &[u8] {
+        match self.len {
+            0 => &[],
+            // SAFETY: the mapping covers 'len' readable bytes for the lifetime of self
+            len => unsafe { std::slice::from_raw_parts(self.ptr as *const u8, len) },
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(unix)]
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: the pointer and length are those returned by mmap, and no slice of the
+            // mapping outlives self
+            unsafe { libc::munmap(self.ptr, self.len) };
+        }
+    }
+}
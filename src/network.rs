@@ -1,12 +1,73 @@
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::ops::Index;
+use std::sync::Arc;
 use crate::errors::network::{EndNodeError, NoEndConnectionError, StartNodeError};
 use crate::roll_up::RollUp;
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// Typed value of a node attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue {
+    Number(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl AttrValue {
+    /// Reads a cell as a number or a bool if possible, and as text otherwise
+    pub fn parse(cell: &str) -> AttrValue {
+        let cell = cell.trim();
+        if let Ok(n) = cell.parse::<f64>() {
+            return AttrValue::Number(n);
+        }
+        match cell.to_ascii_lowercase().as_str() {
+            "true" => AttrValue::Bool(true),
+            "false" => AttrValue::Bool(false),
+            _ => AttrValue::Text(cell.to_string()),
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AttrValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AttrValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttrValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AttrValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AttrValue::Number(n) => write!(f, "{}", n),
+            AttrValue::Bool(b) => write!(f, "{}", b),
+            AttrValue::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Named attributes of a node, such as 'capacity', 'cost' or 'category'
+pub type NodeAttributes = BTreeMap<String, AttrValue>;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Node {
     pub name: String,
     pub id: u32,
+    pub attributes: NodeAttributes,
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
@@ -19,35 +80,396 @@ pub type NodeValueMap<D> = BTreeMap<u32, D>;
 
 pub type EdgeValueMap<D> = BTreeMap<(u32, u32), D>;
 
+/// Named numeric attributes of the edges, such as 'alpha', 'weight' or 'capacity'
+pub type EdgeAttributeMaps = HashMap<String, EdgeValueMap<f32>>;
+
+/// Name of the edge attribute holding the alpha weight of every edge
+pub const ALPHA_ATTR: &str = "alpha";
+
+/// Name of the node attribute holding the string identifier of a node read with one, such as a
+/// UUID. The node gets an interned numeric id, see ['Graph::id_of'].
+pub const KEY_ATTR: &str = "key";
+
 #[derive(Debug, Clone)]
 pub struct Graph {
     nodes: HashMap<u32, Node>,
     edges: HashSet<Edge>,
-    pub static_nodes: HashSet<u32>
+    pub static_nodes: HashSet<u32>,
+    /// Id of every node with a ['KEY_ATTR'], by key
+    keys: HashMap<String, u32>
 }
 
+/// (children, parents) of every node. The children of a node are the nodes of the edges leading to
+/// it, their values are rolled up into it. Use ['Links'] rather than the tuple fields.
 pub type LinkMap = HashMap<u32, (Vec<u32>, Vec<u32>)>;
 
+/// Named access to the children and parents in a ['LinkMap']
+pub trait Links {
+    /// The nodes rolled up into 'id', empty if the node is unknown
+    fn children_of(&self, id: &u32) -> &[u32];
+    /// The nodes 'id' is rolled up into, empty if the node is unknown
+    fn parents_of(&self, id: &u32) -> &[u32];
+}
+
+impl Links for LinkMap {
+    fn children_of(&self, id: &u32) -> &[u32] {
+        self.get(id).map(|(children, _)| children.as_slice()).unwrap_or(&[])
+    }
+
+    fn parents_of(&self, id: &u32) -> &[u32] {
+        self.get(id).map(|(_, parents)| parents.as_slice()).unwrap_or(&[])
+    }
+}
+
+/// Compact index of a set of nodes, every node id has an index below the number of nodes. Ids that
+/// are about as dense as the nodes are looked up in a table, other ids in a hash map.
+#[derive(Debug, Clone, Default)]
+pub struct NodeIndex {
+    /// Id of the node at every index
+    ids: Vec<u32>,
+    lookup: IndexLookup,
+}
+
+#[derive(Debug, Clone)]
+enum IndexLookup {
+    /// The index of every id below the length of the table, ['NO_INDEX'] for unknown ids
+    Table(Vec<u32>),
+    Hashed(HashMap<u32, u32>),
+}
+
+impl Default for IndexLookup {
+    fn default() -> Self {
+        IndexLookup::Table(vec![])
+    }
+}
+
+/// Table entry of the ids that aren't indexed
+const NO_INDEX: u32 = u32::MAX;
+
+impl NodeIndex {
+    /// Indexes the 'ids' in their order, repeated ids keep their first index
+    pub fn new(ids: &[u32]) -> NodeIndex {
+        let mut unique = Vec::with_capacity(ids.len());
+        let max_id = ids.iter().max().map(|id| *id as usize + 1).unwrap_or(0);
+        let lookup = match max_id <= 4 * ids.len() + 1024 {
+            true => {
+                let mut table = vec![NO_INDEX; max_id];
+                for id in ids {
+                    if table[*id as usize] == NO_INDEX {
+                        table[*id as usize] = unique.len() as u32;
+                        unique.push(*id);
+                    }
+                }
+                IndexLookup::Table(table)
+            }
+            false => {
+                let mut map = HashMap::with_capacity(ids.len());
+                for id in ids {
+                    map.entry(*id).or_insert_with(|| {
+                        unique.push(*id);
+                        unique.len() as u32 - 1
+                    });
+                }
+                IndexLookup::Hashed(map)
+            }
+        };
+        NodeIndex { ids: unique, lookup }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn index_of(&self, id: &u32) -> Option<usize> {
+        match &self.lookup {
+            IndexLookup::Table(table) => table.get(*id as usize).filter(|i| **i != NO_INDEX).map(|i| *i as usize),
+            IndexLookup::Hashed(map) => map.get(id).map(|i| *i as usize),
+        }
+    }
+
+    /// The indexed ids, in the order of their indices
+    pub fn ids(&self) -> &[u32] {
+        &self.ids
+    }
+}
+
+/// Values of nodes looked up by their id
+pub trait NodeValues<D> {
+    fn value(&self, id: &u32) -> Option<&D>;
+}
+
+impl<D> NodeValues<D> for NodeValueMap<D> {
+    fn value(&self, id: &u32) -> Option<&D> {
+        self.get(id)
+    }
+}
+
+/// Values of the nodes of a ['NodeIndex'] in a vector, so reading and writing the value of a node
+/// doesn't walk or grow a tree as in a ['NodeValueMap']
+#[derive(Debug, Clone)]
+pub struct DenseValues<D> {
+    index: Arc<NodeIndex>,
+    values: Vec<Option<D>>,
+}
+
+impl<D: Copy> DenseValues<D> {
+    /// No value for any node of the 'index'
+    pub fn new(index: Arc<NodeIndex>) -> DenseValues<D> {
+        DenseValues { values: vec![None; index.len()], index }
+    }
+
+    pub fn get(&self, id: &u32) -> Option<&D> {
+        self.values.get(self.index.index_of(id)?)?.as_ref()
+    }
+
+    /// Sets the value of the node at 'index' and returns its previous value
+    pub fn set_at(&mut self, index: usize, value: D) -> Option<D> {
+        self.values[index].replace(value)
+    }
+
+    /// Removes the value of every node, keeping the vector for the next values
+    pub fn clear(&mut self) {
+        self.values.fill(None);
+    }
+
+    /// Replaces the values by those of the indexed nodes in 'values', other nodes are ignored
+    pub fn assign(&mut self, values: &NodeValueMap<D>) {
+        self.clear();
+        for (id, value) in values.iter() {
+            if let Some(index) = self.index.index_of(id) {
+                self.values[index] = Some(*value);
+            }
+        }
+    }
+}
+
+impl<D: Copy> NodeValues<D> for DenseValues<D> {
+    fn value(&self, id: &u32) -> Option<&D> {
+        self.get(id)
+    }
+}
+
+/// The 'values' without those of the nodes 'left_out'
+pub struct LeftOut<'a, D> {
+    pub values: &'a dyn NodeValues<D>,
+    pub left_out: &'a [u32],
+}
+
+impl<D> NodeValues<D> for LeftOut<'_, D> {
+    fn value(&self, id: &u32) -> Option<&D> {
+        match self.left_out.contains(id) {
+            true => None,
+            false => self.values.value(id),
+        }
+    }
+}
+
+/// The links of a ['LinkMap'] in compressed sparse row form, built once for rolling up many
+/// states. Every node has a dense index, and the children and parents of all nodes are contiguous
+/// slices of two lists. The nodes of the roll up path take the first indices in the order of the
+/// path, so a roll up walks the indices without looking up a node.
+#[derive(Debug, Clone, Default)]
+pub struct CsrLinks {
+    index: Arc<NodeIndex>,
+    /// Number of nodes on the roll up path
+    path_len: usize,
+    /// The children of the node at index i are 'children[child_starts[i]..child_starts[i + 1]]'
+    child_starts: Vec<usize>,
+    children: Vec<u32>,
+    parent_starts: Vec<usize>,
+    parents: Vec<u32>,
+}
+
+impl CsrLinks {
+    /// Packs the links of 'l_map' with the nodes of the roll up 'path' first, in its order, and
+    /// every other node after them by id
+    pub fn new(l_map: &LinkMap, path: &[u32]) -> CsrLinks {
+        let on_path: HashSet<&u32> = path.iter().collect();
+        let mut others: Vec<u32> = l_map.keys().filter(|id| !on_path.contains(id)).copied().collect();
+        others.sort_unstable();
+        let index = NodeIndex::new(&[path, &others].concat());
+        let (mut child_starts, mut children) = (vec![0], vec![]);
+        let (mut parent_starts, mut parents) = (vec![0], vec![]);
+        for id in index.ids() {
+            children.extend_from_slice(l_map.children_of(id));
+            child_starts.push(children.len());
+            parents.extend_from_slice(l_map.parents_of(id));
+            parent_starts.push(parents.len());
+        }
+        CsrLinks { index: Arc::new(index), path_len: path.len(), child_starts, children, parent_starts, parents }
+    }
+
+    /// The roll up path the links were packed for
+    pub fn path(&self) -> &[u32] {
+        &self.index.ids()[..self.path_len]
+    }
+
+    /// The index of the nodes, shared by the ['DenseValues'] of a roll up
+    pub fn index(&self) -> &Arc<NodeIndex> {
+        &self.index
+    }
+
+    pub fn index_of(&self, id: &u32) -> Option<usize> {
+        self.index.index_of(id)
+    }
+
+    /// Position of the node 'id' on the roll up path, which is also its index
+    pub fn path_position(&self, id: &u32) -> Option<usize> {
+        self.index_of(id).filter(|i| *i < self.path_len)
+    }
+
+    pub fn id_at(&self, index: usize) -> u32 {
+        self.index.ids()[index]
+    }
+
+    /// The children of the node at 'index'
+    pub fn children_at(&self, index: usize) -> &[u32] {
+        &self.children[self.child_starts[index]..self.child_starts[index + 1]]
+    }
+
+    /// The parents of the node at 'index'
+    pub fn parents_at(&self, index: usize) -> &[u32] {
+        &self.parents[self.parent_starts[index]..self.parent_starts[index + 1]]
+    }
+}
+
+impl Links for CsrLinks {
+    fn children_of(&self, id: &u32) -> &[u32] {
+        self.index_of(id).map(|i| self.children_at(i)).unwrap_or(&[])
+    }
+
+    fn parents_of(&self, id: &u32) -> &[u32] {
+        self.index_of(id).map(|i| self.parents_at(i)).unwrap_or(&[])
+    }
+}
+
+/// A roll up path indexed for ['Graph::roll_up_delta']. A node on the path only sees the children
+/// before it, the children after it aren't rolled up yet when it is. The positions on the path are
+/// the indices of the nodes in the ['CsrLinks'] it is built from.
+#[derive(Debug, Clone)]
+pub struct RollUpPath {
+    /// Positions of the parents after the node at every position
+    later_parents: Vec<Vec<usize>>,
+    /// Children of the node at every position that aren't before it, None if there are none
+    later_children: Vec<Option<Vec<u32>>>,
+}
+
+impl RollUpPath {
+    pub fn new(links: &CsrLinks) -> RollUpPath {
+        let later_parents = (0..links.path().len())
+            .map(|i| links.parents_at(i).iter().filter_map(|p| links.path_position(p)).filter(|p| *p > i).collect())
+            .collect();
+        let later_children = (0..links.path().len())
+            .map(|i| {
+                let late: Vec<u32> = links.children_at(i).iter()
+                    .filter(|c| links.path_position(c).map(|p| p >= i).unwrap_or(true))
+                    .copied()
+                    .collect();
+                Some(late).filter(|late| !late.is_empty())
+            })
+            .collect();
+        RollUpPath { later_parents, later_children }
+    }
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Graph::new()
+    }
+}
+
 #[allow(dead_code)]
 impl Graph {
     pub fn new() -> Graph {
         Graph {
             nodes: HashMap::new(),
             edges: HashSet::new(),
-            static_nodes: HashSet::new()
+            static_nodes: HashSet::new(),
+            keys: HashMap::new()
         }
     }
 
     pub fn add_node(&mut self, name: String, id: u32) -> Option<Node> {
-        self.nodes.insert(id, Node { name, id })
+        let replaced = self.nodes.insert(id, Node { name, id, attributes: NodeAttributes::new() });
+        self.unindex_key(replaced.as_ref());
+        replaced
     }
 
     pub fn get_node(&self, id: &u32) -> Option<&Node> {
-        return self.nodes.get(id)
+        self.nodes.get(id)
     }
 
     pub fn remove_node(&mut self, id: &u32) -> Option<Node> {
-        return self.nodes.remove(id);
+        let removed = self.nodes.remove(id);
+        self.unindex_key(removed.as_ref());
+        removed
+    }
+
+    /// Sets an attribute of a node and returns the previous value. Returns None without doing
+    /// anything if there is no node with the id.
+    pub fn set_node_attr(&mut self, id: &u32, name: &str, value: AttrValue) -> Option<AttrValue> {
+        let node = self.nodes.get_mut(id)?;
+        if name == KEY_ATTR {
+            if let Some(previous) = node.attributes.get(KEY_ATTR).and_then(|k| k.as_str()) {
+                if self.keys.get(previous) == Some(id) {
+                    self.keys.remove(previous);
+                }
+            }
+            if let Some(key) = value.as_str() {
+                self.keys.insert(key.to_string(), *id);
+            }
+        }
+        node.attributes.insert(name.to_string(), value)
+    }
+
+    /// Removes an attribute of a node and returns its value
+    pub fn remove_node_attr(&mut self, id: &u32, name: &str) -> Option<AttrValue> {
+        let removed = self.nodes.get_mut(id)?.attributes.remove(name);
+        if name == KEY_ATTR {
+            if let Some(key) = removed.as_ref().and_then(|k| k.as_str()) {
+                if self.keys.get(key) == Some(id) {
+                    self.keys.remove(key);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Drops the key of a node that was replaced or removed from the key index
+    fn unindex_key(&mut self, node: Option<&Node>) {
+        if let Some(node) = node {
+            if let Some(key) = node.attributes.get(KEY_ATTR).and_then(|k| k.as_str()) {
+                if self.keys.get(key) == Some(&node.id) {
+                    self.keys.remove(key);
+                }
+            }
+        }
+    }
+
+    pub fn get_node_attr(&self, id: &u32, name: &str) -> Option<&AttrValue> {
+        self.nodes.get(id)?.attributes.get(name)
+    }
+
+    /// Numeric value of a node attribute, None if the node doesn't have it or it isn't a number
+    pub fn get_node_attr_f64(&self, id: &u32, name: &str) -> Option<f64> {
+        self.get_node_attr(id, name).and_then(|v| v.as_f64())
+    }
+
+    /// Id of the node identified by 'key': the node whose ['KEY_ATTR'] is 'key', or else the node
+    /// whose numeric id 'key' is
+    pub fn id_of(&self, key: &str) -> Option<u32> {
+        let key = key.trim();
+        self.keys.get(key).copied()
+            .or_else(|| key.parse::<u32>().ok().filter(|id| self.nodes.contains_key(id)))
+    }
+
+    /// Interned id of every node with a ['KEY_ATTR'], by key
+    pub fn keys(&self) -> HashMap<String, u32> {
+        self.keys.clone()
     }
 
     pub fn get_node_ids(&self) -> HashSet<u32> {
@@ -62,48 +484,139 @@ impl Graph {
         self.edges.insert(Edge { from, to })
     }
 
+    pub fn get_edges(&self) -> &HashSet<Edge> {
+        &self.edges
+    }
+
     pub fn get_edge(&self, from: u32, to: u32) -> Option<&Edge> {
-        return self.edges.get( &Edge { from, to})
+        self.edges.get( &Edge { from, to})
     }
 
     pub fn remove_edge(&mut self, from: u32, to: u32) -> bool {
-        return self.edges.remove( &Edge { from, to})
+        self.edges.remove( &Edge { from, to})
+    }
+
+    /// Turns every edge around, the children of every node become its parents
+    pub fn reverse_edges(&mut self) {
+        self.edges = self.edges.iter().map(|edge| Edge { from: edge.to, to: edge.from }).collect();
     }
 
+    /// Orients the edges of an undirected graph away from 'start'. Every edge leads from the node
+    /// in the earlier breadth first layer around 'start' to the one in the later layer, edges
+    /// within a layer lead from the smaller id. The graph is then acyclic and every node connected
+    /// to 'start' is reached along the edges. Returns the edges that were turned around, as they
+    /// were before.
+    pub fn orient_from(&mut self, start: u32) -> Vec<(u32, u32)> {
+        let mut neighbours: HashMap<u32, Vec<u32>> = HashMap::new();
+        for edge in self.edges.iter() {
+            neighbours.entry(edge.from).or_default().push(edge.to);
+            neighbours.entry(edge.to).or_default().push(edge.from);
+        }
+        let mut layers: HashMap<u32, usize> = HashMap::from([(start, 0)]);
+        let mut agenda: VecDeque<u32> = VecDeque::from([start]);
+        while let Some(current) = agenda.pop_front() {
+            let layer = layers[&current] + 1;
+            for neighbour in neighbours.get(&current).into_iter().flatten() {
+                if !layers.contains_key(neighbour) {
+                    layers.insert(*neighbour, layer);
+                    agenda.push_back(*neighbour);
+                }
+            }
+        }
+        // Nodes that aren't connected to 'start' come after every layer
+        let rank = |id: &u32| (layers.get(id).copied().unwrap_or(usize::MAX), *id);
+        let mut reversed = vec![];
+        self.edges = self.edges.iter()
+            .map(|edge| match rank(&edge.from) > rank(&edge.to) {
+                true => {
+                    reversed.push((edge.from, edge.to));
+                    Edge { from: edge.to, to: edge.from }
+                }
+                false => edge.clone(),
+            })
+            .collect();
+        reversed
+    }
+
+    /// The children and parents of every node, each sorted by id so the roll up path and
+    /// everything else walking the links visit the nodes in the same order on every run
     pub fn links_map(&self) -> LinkMap {
          let mut map: LinkMap = HashMap::new();
         for edge in self.edges.iter() {
-            if !map.contains_key(&edge.from) {
-                map.insert(edge.from, (vec![], vec![]));
-            }
-            if !map.contains_key(&edge.to) {
-                map.insert(edge.to, (vec![], vec![]));
-            }
-            map.get_mut(&edge.from).unwrap().1.push(edge.to);
-            map.get_mut(&edge.to).unwrap().0.push(edge.from);
+            // The edges lead from the child to the parent
+            map.entry(edge.from).or_insert((vec![], vec![])).1.push(edge.to);
+            map.entry(edge.to).or_insert((vec![], vec![])).0.push(edge.from);
+        }
+        for (children, parents) in map.values_mut() {
+            children.sort_unstable();
+            parents.sort_unstable();
         }
         map
     }
 
+    /// Rolls up the nodes of the path the 'links' were packed for, in its order
     pub fn roll_up_state(&self,
-                         graph_path: &Vec<u32>,
-                         l_map: &LinkMap,
-                         roll_up_rule: &Box<dyn RollUp>,
-                         visibilities: &NodeValueMap<u8>)
-        -> NodeValueMap<f32>
+                         links: &CsrLinks,
+                         roll_up_rule: &dyn RollUp,
+                         visibilities: &dyn NodeValues<u8>)
+        -> DenseValues<f32>
     {
-        let mut new_state = NodeValueMap::new();
-        for node in graph_path {
-            let children = &l_map.get(node).unwrap().0;
-            new_state.insert(*node, roll_up_rule.get_value(node, children, visibilities, &new_state));
-        }
+        let mut new_state = DenseValues::new(links.index().clone());
+        self.roll_up_state_into(links, roll_up_rule, visibilities, &mut new_state);
         new_state
     }
 
+    /// Same as ['Graph::roll_up_state'] but rolls up into the 'values' of a previous roll up with
+    /// the same 'links', so a sampling thread reuses one vector for every state
+    pub fn roll_up_state_into(&self,
+                              links: &CsrLinks,
+                              roll_up_rule: &dyn RollUp,
+                              visibilities: &dyn NodeValues<u8>,
+                              values: &mut DenseValues<f32>)
+    {
+        values.clear();
+        for (i, node) in links.path().iter().enumerate() {
+            let value = roll_up_rule.get_value(node, links.children_at(i), visibilities, values);
+            values.set_at(i, value);
+        }
+    }
+
+    /// Updates the 'values' of a roll up along the 'path' after the visibility of the node
+    /// 'changed' changed to the one in 'visibilities'. Only the node and, while their values
+    /// change, its parents after it on the path are rolled up again, so the values are the same as
+    /// those of ['Graph::roll_up_state'] with the new visibilities. Changes of several nodes are
+    /// applied by calling this for every changed node.
+    pub fn roll_up_delta(&self,
+                         path: &RollUpPath,
+                         links: &CsrLinks,
+                         roll_up_rule: &dyn RollUp,
+                         visibilities: &dyn NodeValues<u8>,
+                         values: &mut DenseValues<f32>,
+                         changed: u32)
+    {
+        let Some(start) = links.path_position(&changed) else { return };
+        let mut dirty: BTreeSet<usize> = BTreeSet::from([start]);
+        while let Some(position) = dirty.pop_first() {
+            let id = links.id_at(position);
+            let children = links.children_at(position);
+            let value = match &path.later_children[position] {
+                None => roll_up_rule.get_value(&id, children, visibilities, values),
+                // The children after the node are left out, as they aren't rolled up yet
+                Some(late) => roll_up_rule.get_value(&id, children, visibilities, &LeftOut { values, left_out: late }),
+            };
+            if values.set_at(position, value) != Some(value) {
+                dirty.extend(path.later_parents[position].iter());
+            }
+        }
+    }
+
     pub fn deep_clone(&self) -> Self {
         let mut clone = Graph::new();
         for node in &self.nodes {
             clone.add_node(node.1.name.to_string(), node.1.id);
+            for (name, value) in node.1.attributes.iter() {
+                clone.set_node_attr(&node.1.id, name, value.clone());
+            }
         }
         for edge in &self.edges {
             clone.add_edge(edge.from, edge.to);
@@ -111,11 +624,105 @@ impl Graph {
         clone
     }
 
+    /// The weakly connected components of the graph, nodes linked by edges of either direction.
+    /// Every component is sorted and the components are sorted by their smallest id.
+    pub fn components(&self) -> Vec<Vec<u32>> {
+        let l_map = self.links_map();
+        let mut ids: Vec<u32> = self.get_node_ids().into_iter().collect();
+        ids.sort_unstable();
+        let mut seen: HashSet<u32> = HashSet::new();
+        let mut components = vec![];
+        for id in ids {
+            if !seen.insert(id) {
+                continue;
+            }
+            let mut component = vec![id];
+            let mut agenda: VecDeque<u32> = VecDeque::from([id]);
+            while let Some(current) = agenda.pop_front() {
+                for next in l_map.children_of(&current).iter().chain(l_map.parents_of(&current)) {
+                    if seen.insert(*next) {
+                        component.push(*next);
+                        agenda.push_back(*next);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// The nodes on a directed path from 'start' to 'end', those reached from 'start' along the
+    /// edges that also reach 'end'. Other nodes can't change the value of 'end', apart from
+    /// children that are never rolled up and so count as operable.
+    pub fn nodes_on_paths(map: &LinkMap, start: u32, end: u32) -> HashSet<u32> {
+        let reached: HashSet<u32> = Graph::get_bfs_path(map, start).into_iter().collect();
+        let mut on_paths: HashSet<u32> = HashSet::new();
+        if !reached.contains(&end) {
+            return on_paths;
+        }
+        let mut agenda: VecDeque<u32> = VecDeque::from([end]);
+        on_paths.insert(end);
+        while let Some(current) = agenda.pop_front() {
+            for child in map.children_of(&current) {
+                if reached.contains(child) && on_paths.insert(*child) {
+                    agenda.push_back(*child);
+                }
+            }
+        }
+        on_paths
+    }
+
+    /// Collapses the nodes 'ids' into one supernode 'new_id' named 'name'. Edges to and from the
+    /// nodes lead to and from the supernode, edges between them are dropped. The supernode has
+    /// the attributes of every node, of attributes set on several nodes the one of the smallest
+    /// id is kept, and it is static only if every node was. Ids that aren't part of the graph
+    /// are ignored, 'new_id' may be one of the 'ids'.
+    pub fn contract(&mut self, ids: &HashSet<u32>, new_id: u32, name: String) {
+        let mut merged: Vec<Node> = ids.iter().filter_map(|id| self.nodes.remove(id)).collect();
+        for node in merged.iter() {
+            self.unindex_key(Some(node));
+        }
+        merged.sort_by_key(|node| node.id);
+        let mut attributes = NodeAttributes::new();
+        for node in merged.iter().rev() {
+            attributes.extend(node.attributes.clone());
+        }
+        let all_static = merged.iter().all(|node| self.static_nodes.contains(&node.id));
+        for node in merged.iter() {
+            self.static_nodes.remove(&node.id);
+        }
+        let rewire = |id: u32| if ids.contains(&id) { new_id } else { id };
+        self.edges = self.edges.iter()
+            .map(|edge| Edge { from: rewire(edge.from), to: rewire(edge.to) })
+            .filter(|edge| edge.from != new_id || edge.to != new_id)
+            .collect();
+        let key = attributes.get(KEY_ATTR).and_then(|k| k.as_str()).map(|k| k.to_string());
+        let replaced = self.nodes.insert(new_id, Node { name, id: new_id, attributes });
+        self.unindex_key(replaced.as_ref());
+        if let Some(key) = key {
+            self.keys.insert(key, new_id);
+        }
+        if all_static && !merged.is_empty() {
+            self.static_nodes.insert(new_id);
+        }
+    }
+
+    /// The graph of the nodes 'ids' and the edges between them
+    pub fn subgraph(&self, ids: &HashSet<u32>) -> Graph {
+        Graph {
+            nodes: self.nodes.iter().filter(|(id, _)| ids.contains(id)).map(|(id, node)| (*id, node.clone())).collect(),
+            edges: self.edges.iter().filter(|e| ids.contains(&e.from) && ids.contains(&e.to)).cloned().collect(),
+            static_nodes: self.static_nodes.intersection(ids).copied().collect(),
+            keys: self.keys.iter().filter(|(_, id)| ids.contains(id)).map(|(key, id)| (key.clone(), *id)).collect(),
+        }
+    }
+
     pub fn get_start_id(map: &LinkMap) -> Result<u32, StartNodeError>{
         let mut starts: Vec<u32> = vec![];
-        for node in map {
-            if node.1.0.is_empty() {
-                starts.push(*node.0);
+        for id in map.keys() {
+            if map.children_of(id).is_empty() {
+                starts.push(*id);
             }
         }
         if starts.len() == 1 {
@@ -126,9 +733,9 @@ impl Graph {
 
     pub fn get_end_id(map: &LinkMap) -> Result<u32, EndNodeError>{
         let mut ends: Vec<u32> = vec![];
-        for node in map {
-            if node.1.1.is_empty() {
-                ends.push(*node.0);
+        for id in map.keys() {
+            if map.parents_of(id).is_empty() {
+                ends.push(*id);
             }
         }
         if ends.len() == 1 {
@@ -137,21 +744,45 @@ impl Graph {
         Err(EndNodeError { ends })
     }
 
+    /// The roll up path from 'start_id': the nodes reached from it along the edges, every node
+    /// after all of its reached children and otherwise in breadth first order. Nodes on a cycle,
+    /// which the validation rejects, never have all their children before them and follow the
+    /// others in breadth first order.
     pub fn get_bfs_path(map: &LinkMap, start_id: u32) -> Vec<u32>{
-        let mut path: Vec<u32> = vec![];
+        let mut reached: Vec<u32> = vec![];
         let mut visited: HashSet<u32> = HashSet::from([start_id]);
         let mut agenda: VecDeque<u32> = VecDeque::from([start_id]);
         while !agenda.is_empty() {
             let current = agenda.pop_front().unwrap();
-            path.push(current);
-            if !map.contains_key(&current){ continue; }
-            for parent in &map.index(&current).1 {
+            reached.push(current);
+            for parent in map.parents_of(&current) {
                 if !visited.contains(parent) {
                     agenda.push_back(*parent);
                     visited.insert(*parent);
                 }
             }
         }
+        // Kahn's algorithm over the reached nodes, a node is freed once its reached children are
+        let mut waiting: HashMap<u32, usize> = reached.iter()
+            .map(|id| (*id, map.children_of(id).iter().filter(|child| visited.contains(child)).count()))
+            .collect();
+        let mut path: Vec<u32> = Vec::with_capacity(reached.len());
+        let mut agenda: VecDeque<u32> = reached.iter().filter(|id| waiting[id] == 0).copied().collect();
+        while let Some(current) = agenda.pop_front() {
+            path.push(current);
+            for parent in map.parents_of(&current) {
+                if let Some(children) = waiting.get_mut(parent) {
+                    *children -= 1;
+                    if *children == 0 {
+                        agenda.push_back(*parent);
+                    }
+                }
+            }
+        }
+        if path.len() < reached.len() {
+            let placed: HashSet<u32> = path.iter().copied().collect();
+            path.extend(reached.iter().filter(|id| !placed.contains(id)));
+        }
         path
     }
 
@@ -166,4 +797,69 @@ impl Graph {
         }
         Err(NoEndConnectionError { start_id, end_id })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_follow_the_nodes() {
+        let mut graph = Graph::new();
+        graph.add_node("a".to_string(), 1);
+        graph.add_node("b".to_string(), 2);
+        graph.set_node_attr(&1, KEY_ATTR, AttrValue::Text("uuid-a".to_string()));
+        graph.set_node_attr(&2, KEY_ATTR, AttrValue::Text("uuid-b".to_string()));
+        assert_eq!(graph.id_of("uuid-a"), Some(1));
+        assert_eq!(graph.id_of("2"), Some(2));
+
+        graph.set_node_attr(&1, KEY_ATTR, AttrValue::Text("uuid-c".to_string()));
+        assert_eq!(graph.id_of("uuid-a"), None);
+        assert_eq!(graph.id_of("uuid-c"), Some(1));
+
+        graph.remove_node(&2);
+        assert_eq!(graph.id_of("uuid-b"), None);
+
+        graph.add_node("d".to_string(), 3);
+        graph.contract(&HashSet::from([1, 3]), 4, "ad".to_string());
+        assert_eq!(graph.keys(), HashMap::from([("uuid-c".to_string(), 4)]));
+        assert_eq!(graph.subgraph(&HashSet::from([4])).id_of("uuid-c"), Some(4));
+    }
+
+    #[test]
+    fn roll_up_path_puts_every_node_after_its_children() {
+        // start -> a -> end and start -> b -> c -> end, breadth first the end comes before c
+        let mut graph = Graph::new();
+        for (from, to) in [(0, 1), (0, 2), (1, 4), (2, 3), (3, 4)] {
+            graph.add_edge(from, to);
+        }
+        let l_map = graph.links_map();
+        assert_eq!(Graph::get_bfs_path(&l_map, 0), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn csr_links_hold_the_links_of_every_node_with_the_path_first() {
+        // The path from 0 reaches 1 to 4, 5 -> 6 is off the path
+        let mut graph = Graph::new();
+        for (from, to) in [(0, 1), (0, 2), (1, 4), (2, 3), (3, 4), (5, 6)] {
+            graph.add_edge(from, to);
+        }
+        let l_map = graph.links_map();
+        let path = Graph::get_bfs_path(&l_map, 0);
+        let links = CsrLinks::new(&l_map, &path);
+        assert_eq!(links.path(), path.as_slice());
+        for (position, id) in path.iter().enumerate() {
+            assert_eq!(links.path_position(id), Some(position));
+            assert_eq!(links.id_at(position), *id);
+        }
+        for id in 0..7 {
+            assert_eq!(links.children_of(&id), l_map.children_of(&id));
+            assert_eq!(links.parents_of(&id), l_map.parents_of(&id));
+        }
+        // Nodes off the path come after it by id
+        assert_eq!((links.index_of(&5), links.index_of(&6)), (Some(5), Some(6)));
+        assert_eq!(links.path_position(&5), None);
+        assert_eq!(links.index_of(&7), None);
+        assert!(links.children_of(&7).is_empty());
+    }
+}
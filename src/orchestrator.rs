@@ -0,0 +1,85 @@
+//! Running several analyses concurrently over one loaded graph.
+//!
+//! Every analysis runs as a pipeline ['Stage'] on its own thread and its own copy of the
+//! ['PipelineState']. The copies share the graph and its link map, so the input is read and
+//! linked once however many analyses run. The outcomes are collected in the order the analyses
+//! were requested, so the merged report doesn't depend on which analysis finishes first.
+
+use std::thread;
+use std::time::{Duration, Instant};
+use log::info;
+use crate::analyses::AnalysisContext;
+use crate::analyses::criticality::CriticalityResults;
+use crate::errors::analysis::ThorError;
+use crate::json::JsonValue;
+use crate::pipeline::{PipelineState, Stage};
+use crate::registry::AnalysisRegistry;
+
+/// Analyses run side by side
+#[derive(Default)]
+pub struct Orchestrator {
+    pub stages: Vec<Box<dyn Stage>>,
+}
+
+/// What one analysis produced
+#[derive(Debug, Clone)]
+pub struct StageOutcome {
+    pub name: String,
+    /// Text reported by the analysis, empty for analyses that only compute criticality results
+    pub report: String,
+    pub results: Option<CriticalityResults>,
+    pub elapsed: Duration,
+}
+
+impl Orchestrator {
+    /// Builds the analyses 'names' of the 'registry', every analysis with the same 'options'
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason if a name is unknown or the options are invalid for an analysis
+    pub fn from_names(registry: &AnalysisRegistry, names: &[&str], options: &JsonValue) -> Result<Orchestrator, String> {
+        let stages = names.iter()
+            .map(|name| registry.build(name, options))
+            .collect::<Result<Vec<Box<dyn Stage>>, String>>()?;
+        Ok(Orchestrator { stages })
+    }
+
+    /// Runs every analysis on a copy of the 'state' sharing its graph
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first analysis, in the requested order, that failed. The other
+    /// analyses still run to the end.
+    pub fn run(&self, state: &PipelineState, ctx: &AnalysisContext) -> Result<Vec<StageOutcome>, ThorError> {
+        info!("Running {} analyses concurrently on {} dynamic nodes", self.stages.len(), state.dynamic_ids.len());
+        thread::scope(|scope| {
+            let handles: Vec<_> = self.stages.iter().map(|stage| {
+                let mut state = state.clone();
+                state.results = None;
+                state.report.clear();
+                scope.spawn(move || -> Result<StageOutcome, Option<String>> {
+                    let start = Instant::now();
+                    // Errors aren't Send, only their message leaves the thread
+                    stage.run(&mut state, ctx).map_err(|e| match e {
+                        ThorError::Cancelled => None,
+                        ThorError::Failed(e) => Some(format!("the {} analysis failed: {}", stage.name(), e)),
+                    })?;
+                    Ok(StageOutcome {
+                        name: stage.name().to_string(),
+                        report: state.report,
+                        results: state.results,
+                        elapsed: start.elapsed(),
+                    })
+                })
+            }).collect();
+            handles.into_iter()
+                .map(|handle| match handle.join() {
+                    Ok(Ok(outcome)) => Ok(outcome),
+                    Ok(Err(None)) => Err(ThorError::Cancelled),
+                    Ok(Err(Some(reason))) => Err(ThorError::Failed(reason.into())),
+                    Err(_) => Err(ThorError::Failed("an analysis thread panicked".into())),
+                })
+                .collect()
+        })
+    }
+}
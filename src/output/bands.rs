@@ -0,0 +1,166 @@
+//! Classifying the nodes of the results into bands of criticality, such as critical, high,
+//! medium and low, and raising an alert when a node reaches the highest band, so changes to an
+//! architecture can be gated in CI.
+//!
+//! The bands are given by '--bands' as a comma separated list of 'name=cutoff' from the highest
+//! to the lowest band, ['DEFAULT_BANDS'] if not given. A node is in the first band whose cutoff
+//! its criticality reaches. The last band may be a name without a cutoff, holding every node below
+//! the other bands, otherwise those nodes are in no band.
+//!
+//! The first band is the critical one. '--alert-file <path>' writes the nodes in it as json, with
+//! the 'band', its 'cutoff', every band and the 'id', 'name' and 'criticality' of every 'node',
+//! and only if there are any. '--fail-on-critical' fails the run with a ['CriticalBandError'] once
+//! the results are written.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use crate::analyses::criticality::CriticalityResults;
+use crate::analyses::criticality::ranking::rank;
+use crate::errors::analysis::CriticalBandError;
+use crate::json::JsonValue;
+use crate::network::Graph;
+use crate::output::{Output, write_output};
+
+/// Bands used when only an alert is asked for
+pub const DEFAULT_BANDS: &str = "critical=0.5,high=0.2,medium=0.05,low";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Band {
+    pub name: String,
+    /// Lowest criticality of the band, None for the band of every node below the others
+    pub cutoff: Option<f64>,
+}
+
+/// Bands from the highest to the lowest, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriticalityBands {
+    pub bands: Vec<Band>,
+}
+
+impl CriticalityBands {
+    /// Parses a list of 'name=cutoff', see the module documentation
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a band has no name or an invalid cutoff, the cutoffs don't decrease or
+    /// a band without a cutoff isn't the last
+    pub fn parse(spec: &str) -> Result<CriticalityBands, String> {
+        let mut bands: Vec<Band> = vec![];
+        for part in spec.split(',').map(|p| p.trim()) {
+            if bands.last().map(|b| b.cutoff.is_none()).unwrap_or(false) {
+                return Err(format!("Only the last band can be given without a cutoff, in '{}'", spec));
+            }
+            let (name, cutoff) = match part.split_once('=') {
+                Some((name, cutoff)) => {
+                    let cutoff = cutoff.trim().parse::<f64>()
+                        .map_err(|_| format!("The cutoff of the band '{}' must be a number, got '{}'", name.trim(), cutoff.trim()))?;
+                    (name.trim(), Some(cutoff))
+                }
+                None => (part, None),
+            };
+            if name.is_empty() {
+                return Err(format!("Every band needs a name, in '{}'", spec));
+            }
+            if let (Some(cutoff), Some(Band { cutoff: Some(above), .. })) = (cutoff, bands.last()) {
+                if cutoff >= *above {
+                    return Err(format!("The bands must be given from the highest to the lowest cutoff, in '{}'", spec));
+                }
+            }
+            bands.push(Band { name: name.to_string(), cutoff });
+        }
+        if bands[0].cutoff.is_none() {
+            return Err(format!("The critical band needs a cutoff, in '{}'", spec));
+        }
+        Ok(CriticalityBands { bands })
+    }
+
+    /// The band of a node of the 'criticality', None if it is below every band
+    pub fn band(&self, criticality: f64) -> Option<&Band> {
+        self.bands.iter().find(|band| band.cutoff.map(|cutoff| criticality >= cutoff).unwrap_or(true))
+    }
+
+    /// The first band, alerted on
+    pub fn critical(&self) -> &Band {
+        &self.bands[0]
+    }
+
+    /// (id, criticality) of the nodes of the 'results' in the critical band, most critical first
+    pub fn alerts(&self, results: &CriticalityResults) -> Vec<(u32, f64)> {
+        let cutoff = self.critical().cutoff.unwrap_or(f64::NEG_INFINITY);
+        rank(results).into_iter()
+            .filter(|(_, node)| node.criticality >= cutoff)
+            .map(|(id, node)| (id, node.criticality))
+            .collect()
+    }
+
+    /// Number of nodes of the 'results' in every band
+    pub fn counts(&self, results: &CriticalityResults) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = self.bands.iter().map(|band| (band.name.as_str(), 0)).collect();
+        for node in results.nodes.values() {
+            if let Some(i) = self.band(node.criticality).and_then(|band| self.bands.iter().position(|b| b == band)) {
+                counts[i].1 += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// Collects the nodes in the critical band of every results written, writing them to the alert
+/// file if given. Clones share the nodes collected, so the one kept by the caller tells whether
+/// to fail once the run is over, see ['AlertOutput::check'].
+#[derive(Debug, Clone)]
+pub struct AlertOutput {
+    pub bands: CriticalityBands,
+    pub path: Option<String>,
+    /// (id, name, criticality) of the nodes alerted on so far
+    alerts: Arc<Mutex<Vec<(u32, String, f64)>>>,
+}
+
+impl AlertOutput {
+    pub fn new(bands: CriticalityBands, path: Option<String>) -> AlertOutput {
+        AlertOutput { bands, path, alerts: Arc::new(Mutex::new(vec![])) }
+    }
+
+    /// Fails with a ['CriticalBandError'] if nodes of the results written are in the critical band
+    ///
+    /// # Errors
+    ///
+    /// Returns the ['CriticalBandError'] of the nodes in the critical band
+    pub fn check(&self) -> Result<(), CriticalBandError> {
+        let alerts = self.alerts.lock().unwrap();
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        let critical = self.bands.critical();
+        Err(CriticalBandError {
+            band: critical.name.clone(),
+            cutoff: critical.cutoff.unwrap_or(f64::NEG_INFINITY),
+            nodes: alerts.iter().map(|(id, _, criticality)| (*id, *criticality)).collect(),
+        })
+    }
+}
+
+impl Output for AlertOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        let mut alerts = self.alerts.lock().unwrap();
+        for (id, criticality) in self.bands.alerts(results) {
+            let name = graph.get_node(&id).map(|n| n.name.clone()).unwrap_or_default();
+            alerts.push((id, name, criticality));
+        }
+        let (Some(path), false) = (self.path.as_ref(), alerts.is_empty()) else { return Ok(()) };
+        let critical = self.bands.critical();
+        let bands: Vec<JsonValue> = self.bands.bands.iter()
+            .map(|band| JsonValue::object().with("name", band.name.as_str()).with("cutoff", band.cutoff))
+            .collect();
+        let nodes: Vec<JsonValue> = alerts.iter()
+            .map(|(id, name, criticality)| JsonValue::object().with("id", *id).with("name", name.as_str()).with("criticality", *criticality))
+            .collect();
+        let alert = JsonValue::object()
+            .with("band", critical.name.as_str())
+            .with("cutoff", critical.cutoff)
+            .with("bands", bands)
+            .with("nodes", nodes);
+        write_output(path, alert.to_pretty_string() + "\n")?;
+        Ok(())
+    }
+}
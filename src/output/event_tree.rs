@@ -0,0 +1,28 @@
+//! Printing the risk numbers of an event tree fed by the results of an analysis.
+
+use std::error::Error;
+use crate::analyses::criticality::CriticalityResults;
+use crate::analyses::event_tree::EventTree;
+use crate::network::Graph;
+use crate::output::Output;
+
+/// Prints the expected consequence, the probability of every sequence and the contribution of
+/// every node to the standard output
+pub struct EventTreeOutput {
+    pub tree: EventTree,
+}
+
+impl Output for EventTreeOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        let consequences = self.tree.evaluate(results);
+        println!("Expected consequence: {}", consequences.expected_consequence);
+        for (name, probability) in consequences.sequences.iter() {
+            println!("Sequence {}: probability {}", name, probability);
+        }
+        for (id, contribution) in consequences.contributions.iter() {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            println!("{} ({}): consequence contribution {}", name, id, contribution);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,473 @@
+//! Filtering the results handed to the outputs, so only the nodes of interest are written.
+//!
+//! A filter is an expression of conditions on the nodes combined with 'and', 'or', 'not' and
+//! parentheses, such as 'name~^DB_ and crit>0.1' or '(tag=db or group=storage) and not
+//! on_count<10'. 'and' binds tighter than 'or'. A condition is a field, an operator and a value:
+//!
+//! * 'name', 'tag', 'group': text fields, compared with '=' and '!=' or matched by the regular
+//!   expression after '~' and '!~', see ['crate::pattern']. The tags of a node are the comma or
+//!   semicolon separated values of its ['TAGS_ATTR'] attribute, its groups the ones of its
+//!   ['GROUP_ATTR'] attribute and the groups holding it that were read with
+//!   ['crate::input::read_node_groups']. '=' and '~' hold if any value of the node matches, '!='
+//!   and '!~' if none does.
+//! * 'id' and the metrics 'criticality' (or 'crit'), 'mean_end_on', 'mean_end_off', 'on_count' and
+//!   'off_count': numbers, compared with '=', '!=', '<', '<=', '>' and '>='
+//! * any other name: the node attribute of that name, compared as a number if the value is one and
+//!   the operator isn't '~' or '!~', and as text otherwise. Conditions on a number are false for
+//!   nodes without the attribute.
+//!
+//! Values with spaces or parentheses are quoted with '"' or '\''. Only the nodes of the results
+//! are filtered, the summary of the run and the graph are written as they are.
+
+use std::error::Error;
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult};
+use crate::analyses::criticality::samples::NodeGroup;
+use crate::errors::filter::FilterError;
+use crate::network::{AttrValue, Graph};
+use crate::output::Output;
+use crate::pattern::Pattern;
+
+/// Node attribute holding the tags of a node
+pub const TAGS_ATTR: &str = "tags";
+/// Node attribute holding the groups of a node
+pub const GROUP_ATTR: &str = "group";
+
+/// A parsed filter, see the module documentation
+#[derive(Debug, Clone)]
+pub struct ResultFilter {
+    expression: Expression,
+    /// Groups known besides the ['GROUP_ATTR'] of the nodes
+    groups: Vec<NodeGroup>,
+}
+
+#[derive(Debug, Clone)]
+enum Expression {
+    Condition(Condition),
+    Not(Box<Expression>),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Text { field: Field, comparison: TextComparison, negated: bool },
+    Number { field: Field, operator: Operator, value: f64 },
+}
+
+#[derive(Debug, Clone)]
+enum Field {
+    Name,
+    Tag,
+    Group,
+    Id,
+    Criticality,
+    MeanEndOn,
+    MeanEndOff,
+    OnCount,
+    OffCount,
+    Attribute(String),
+}
+
+#[derive(Debug, Clone)]
+enum TextComparison {
+    Equals(String),
+    Matches(Pattern),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Match,
+    NotMatch,
+}
+
+impl Operator {
+    /// Longest first, so '<=' isn't read as '<'
+    const SYMBOLS: [(&'static str, Operator); 8] = [
+        ("!=", Operator::NotEqual), ("<=", Operator::LessOrEqual), (">=", Operator::GreaterOrEqual),
+        ("!~", Operator::NotMatch), ("=", Operator::Equal), ("<", Operator::Less), (">", Operator::Greater),
+        ("~", Operator::Match),
+    ];
+
+    fn compare(&self, a: f64, b: f64) -> bool {
+        match self {
+            Operator::Equal => a == b,
+            Operator::NotEqual => a != b,
+            Operator::Less => a < b,
+            Operator::LessOrEqual => a <= b,
+            Operator::Greater => a > b,
+            Operator::GreaterOrEqual => a >= b,
+            Operator::Match | Operator::NotMatch => false,
+        }
+    }
+}
+
+impl Field {
+    fn from_name(name: &str) -> Field {
+        match name {
+            "name" => Field::Name,
+            "tag" | "tags" => Field::Tag,
+            "group" => Field::Group,
+            "id" => Field::Id,
+            "crit" | "criticality" => Field::Criticality,
+            "mean_end_on" => Field::MeanEndOn,
+            "mean_end_off" => Field::MeanEndOff,
+            "on_count" => Field::OnCount,
+            "off_count" => Field::OffCount,
+            other => Field::Attribute(other.to_string()),
+        }
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, Field::Name | Field::Tag | Field::Group)
+    }
+}
+
+impl ResultFilter {
+    /// Parses the 'expression', see the module documentation
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['FilterError'] if the expression or one of its patterns is invalid
+    pub fn parse(expression: &str) -> Result<ResultFilter, FilterError> {
+        let mut parser = Parser { source: expression, chars: expression.chars().collect(), pos: 0 };
+        let parsed = parser.or()?;
+        parser.skip_spaces();
+        match parser.peek() {
+            None => Ok(ResultFilter { expression: parsed, groups: vec![] }),
+            Some(')') => Err(parser.error("unmatched ')'")),
+            Some(_) => Err(parser.error("expected 'and', 'or' or the end of the filter")),
+        }
+    }
+
+    /// Adds the 'groups' to the ones the nodes name themselves
+    pub fn with_groups(mut self, groups: Vec<NodeGroup>) -> ResultFilter {
+        self.groups.extend(groups);
+        self
+    }
+
+    /// Whether the node 'id' of the 'graph' with the result 'node' passes the filter
+    pub fn matches(&self, graph: &Graph, id: u32, node: &NodeCritResult) -> bool {
+        self.evaluate(&self.expression, graph, id, node)
+    }
+
+    /// The 'results' with only the nodes passing the filter
+    pub fn apply(&self, graph: &Graph, results: &CriticalityResults) -> CriticalityResults {
+        let mut filtered = results.clone();
+        filtered.nodes.retain(|id, node| self.matches(graph, *id, node));
+        filtered
+    }
+
+    fn evaluate(&self, expression: &Expression, graph: &Graph, id: u32, node: &NodeCritResult) -> bool {
+        match expression {
+            Expression::Condition(condition) => self.holds(condition, graph, id, node),
+            Expression::Not(inner) => !self.evaluate(inner, graph, id, node),
+            Expression::And(terms) => terms.iter().all(|t| self.evaluate(t, graph, id, node)),
+            Expression::Or(terms) => terms.iter().any(|t| self.evaluate(t, graph, id, node)),
+        }
+    }
+
+    fn holds(&self, condition: &Condition, graph: &Graph, id: u32, node: &NodeCritResult) -> bool {
+        let graph_node = graph.get_node(&id);
+        let attribute = |name: &str| graph_node.and_then(|n| n.attributes.get(name));
+        match condition {
+            Condition::Text { field, comparison, negated } => {
+                let values: Vec<String> = match field {
+                    Field::Name => vec![graph_node.map(|n| n.name.clone()).unwrap_or_default()],
+                    Field::Tag => attribute(TAGS_ATTR).map(split_values).unwrap_or_default(),
+                    Field::Group => attribute(GROUP_ATTR).map(split_values).unwrap_or_default().into_iter()
+                        .chain(self.groups.iter().filter(|g| g.members.contains(&id)).map(|g| g.name.clone()))
+                        .collect(),
+                    Field::Attribute(name) => attribute(name).map(|v| v.to_string()).into_iter().collect(),
+                    _ => vec![],
+                };
+                let found = values.iter().any(|value| match comparison {
+                    TextComparison::Equals(text) => value == text,
+                    TextComparison::Matches(pattern) => pattern.is_match(value),
+                });
+                found != *negated
+            }
+            Condition::Number { field, operator, value } => {
+                let number = match field {
+                    Field::Id => Some(id as f64),
+                    Field::Criticality => Some(node.criticality),
+                    Field::MeanEndOn => Some(node.mean_end_on),
+                    Field::MeanEndOff => Some(node.mean_end_off),
+                    Field::OnCount => Some(node.on_count as f64),
+                    Field::OffCount => Some(node.off_count as f64),
+                    Field::Attribute(name) => match attribute(name) {
+                        Some(AttrValue::Number(n)) => Some(*n),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                number.map(|n| operator.compare(n, *value)).unwrap_or(false)
+            }
+        }
+    }
+}
+
+/// The comma or semicolon separated values of an attribute
+fn split_values(value: &AttrValue) -> Vec<String> {
+    value.to_string().split([',', ';'])
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> FilterError {
+        FilterError { expression: self.source.to_string(), pos: self.pos, reason: reason.to_string() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips the keyword 'word' if it comes next, followed by a space, a parenthesis or the end
+    fn keyword(&mut self, word: &str) -> bool {
+        self.skip_spaces();
+        let end = self.pos + word.len();
+        let matches = end <= self.chars.len()
+            && self.chars[self.pos..end].iter().collect::<String>().eq_ignore_ascii_case(word)
+            && self.chars.get(end).map(|c| c.is_whitespace() || *c == '(').unwrap_or(true);
+        if matches {
+            self.pos = end;
+        }
+        matches
+    }
+
+    fn or(&mut self) -> Result<Expression, FilterError> {
+        let mut terms = vec![self.and()?];
+        while self.keyword("or") {
+            terms.push(self.and()?);
+        }
+        Ok(match terms.len() {
+            1 => terms.pop().unwrap(),
+            _ => Expression::Or(terms),
+        })
+    }
+
+    fn and(&mut self) -> Result<Expression, FilterError> {
+        let mut terms = vec![self.unary()?];
+        while self.keyword("and") {
+            terms.push(self.unary()?);
+        }
+        Ok(match terms.len() {
+            1 => terms.pop().unwrap(),
+            _ => Expression::And(terms),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expression, FilterError> {
+        if self.keyword("not") {
+            return Ok(Expression::Not(Box::new(self.unary()?)));
+        }
+        self.skip_spaces();
+        match self.peek() {
+            None => Err(self.error("expected a condition")),
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.or()?;
+                self.skip_spaces();
+                if self.peek() != Some(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(_) => Ok(Expression::Condition(self.condition()?)),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, FilterError> {
+        let start = self.pos;
+        while self.peek().map(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-').unwrap_or(false) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("expected the field of a condition"));
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        self.skip_spaces();
+        let rest: String = self.chars[self.pos..].iter().collect();
+        let operator = match Operator::SYMBOLS.iter().find(|(symbol, _)| rest.starts_with(symbol)) {
+            Some((symbol, operator)) => {
+                self.pos += symbol.len();
+                *operator
+            }
+            None => return Err(self.error(&format!("expected an operator after '{}'", name))),
+        };
+        self.skip_spaces();
+        let value_pos = self.pos;
+        let value = self.value()?;
+
+        let field = Field::from_name(&name);
+        let number = value.parse::<f64>().ok();
+        match operator {
+            Operator::Match | Operator::NotMatch => {
+                if !field.is_text() && !matches!(field, Field::Attribute(_)) {
+                    return Err(FilterError { pos: start, ..self.error(&format!("'{}' is a number, it can't be matched by a pattern", name)) });
+                }
+                let pattern = Pattern::new(&value)
+                    .map_err(|e| FilterError { pos: value_pos + e.pos, ..self.error(&e.reason) })?;
+                Ok(Condition::Text { field, comparison: TextComparison::Matches(pattern), negated: operator == Operator::NotMatch })
+            }
+            Operator::Equal | Operator::NotEqual if field.is_text() || (matches!(field, Field::Attribute(_)) && number.is_none()) => {
+                Ok(Condition::Text { field, comparison: TextComparison::Equals(value), negated: operator == Operator::NotEqual })
+            }
+            _ if field.is_text() => {
+                Err(FilterError { pos: start, ..self.error(&format!("'{}' is text, it can only be compared with '=', '!=', '~' and '!~'", name)) })
+            }
+            _ => match number {
+                Some(value) => Ok(Condition::Number { field, operator, value }),
+                None => Err(FilterError { pos: value_pos, ..self.error(&format!("'{}' is compared with a number, got '{}'", name, value)) }),
+            },
+        }
+    }
+
+    /// A quoted value, or the characters up to the next space or the ')' closing a group
+    fn value(&mut self) -> Result<String, FilterError> {
+        let mut value = String::new();
+        if let Some(quote @ ('"' | '\'')) = self.peek() {
+            self.pos += 1;
+            loop {
+                match self.peek() {
+                    None => return Err(self.error("unclosed quote")),
+                    Some('\\') if self.chars.get(self.pos + 1) == Some(&quote) => {
+                        value.push(quote);
+                        self.pos += 2;
+                    }
+                    Some(c) if c == quote => {
+                        self.pos += 1;
+                        return Ok(value);
+                    }
+                    Some(c) => {
+                        value.push(c);
+                        self.pos += 1;
+                    }
+                }
+            }
+        }
+        // Parentheses opened within the value, such as those of a pattern, are part of it
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                c if c.is_whitespace() => break,
+                ')' if depth == 0 => break,
+                ')' => depth -= 1,
+                '(' => depth += 1,
+                _ => {}
+            }
+            value.push(c);
+            self.pos += 1;
+        }
+        if value.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+        Ok(value)
+    }
+}
+
+/// Writes only the nodes of the results passing the 'filter' to the 'output'
+pub struct FilteredOutput {
+    pub filter: ResultFilter,
+    pub output: Box<dyn Output>,
+}
+
+impl Output for FilteredOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        self.output.write(graph, &self.filter.apply(graph, results))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyses::criticality::histogram::Histogram;
+    use crate::network::NodeValueMap;
+
+    fn node(criticality: f64, on_count: u64) -> NodeCritResult {
+        NodeCritResult { on_count, off_count: 10 - on_count, mean_end_on: 1.0, mean_end_off: 1.0 - criticality, criticality }
+    }
+
+    /// DB_main, DB_replica and web with their tags, groups and costs, and results for each
+    fn results() -> (Graph, CriticalityResults) {
+        let mut graph = Graph::new();
+        for (name, id) in [("DB_main", 1), ("DB_replica", 2), ("web", 3)] {
+            graph.add_node(name.to_string(), id);
+        }
+        graph.set_node_attr(&1, TAGS_ATTR, AttrValue::Text("db, primary".to_string()));
+        graph.set_node_attr(&2, TAGS_ATTR, AttrValue::Text("db;replica".to_string()));
+        graph.set_node_attr(&3, GROUP_ATTR, AttrValue::Text("edge".to_string()));
+        graph.set_node_attr(&1, "cost", AttrValue::Number(10.0));
+        graph.set_node_attr(&3, "cost", AttrValue::Number(2.0));
+        graph.set_node_attr(&2, "site", AttrValue::Text("north hall".to_string()));
+        let nodes = NodeValueMap::from([(1, node(0.5, 8)), (2, node(0.05, 2)), (3, node(0.2, 5))]);
+        (graph, CriticalityResults { row_count: 10, end_op_mean: 0.9, end_op_histogram: Histogram::default(), nodes })
+    }
+
+    fn kept(filter: &str) -> Vec<u32> {
+        let (graph, results) = results();
+        let filter = ResultFilter::parse(filter).unwrap()
+            .with_groups(vec![NodeGroup { name: "storage".to_string(), members: vec![1, 2] }]);
+        filter.apply(&graph, &results).nodes.keys().copied().collect()
+    }
+
+    #[test]
+    fn conditions_select_nodes_by_their_fields_metrics_and_attributes() {
+        assert_eq!(kept("name~^DB_ and crit>0.1"), vec![1]);
+        assert_eq!(kept("tag=db"), vec![1, 2]);
+        assert_eq!(kept("tag!=primary"), vec![2, 3]);
+        assert_eq!(kept("group=storage"), vec![1, 2]);
+        assert_eq!(kept("group=edge"), vec![3]);
+        assert_eq!(kept("on_count>=5 and id!=1"), vec![3]);
+        assert_eq!(kept("cost<5"), vec![3]);
+        assert_eq!(kept("site=\"north hall\""), vec![2]);
+        assert_eq!(kept("name~(main|web)"), vec![1, 3]);
+        // Conditions on a number are false for nodes without the attribute, also when negated
+        assert_eq!(kept("cost!=10"), vec![3]);
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(kept("tag=replica or name=web and crit>0.3"), vec![2]);
+        assert_eq!(kept("(tag=replica or name=web) and crit>0.1"), vec![3]);
+        assert_eq!(kept("not (tag=db) or crit>=0.5"), vec![1, 3]);
+        assert_eq!(kept("NOT tag=db AND mean_end_off<1"), vec![3]);
+    }
+
+    #[test]
+    fn malformed_filters_point_at_the_error() {
+        let error = |filter: &str| {
+            let error = ResultFilter::parse(filter).unwrap_err();
+            (error.pos, error.reason)
+        };
+        assert_eq!(error("crit>0.1)"), (8, "unmatched ')'".to_string()));
+        assert_eq!(error("(crit>0.1"), (9, "expected ')'".to_string()));
+        assert_eq!(error("crit 0.1"), (5, "expected an operator after 'crit'".to_string()));
+        assert_eq!(error("crit>high"), (5, "'crit' is compared with a number, got 'high'".to_string()));
+        assert_eq!(error("name<b"), (0, "'name' is text, it can only be compared with '=', '!=', '~' and '!~'".to_string()));
+        assert_eq!(error("crit~1"), (0, "'crit' is a number, it can't be matched by a pattern".to_string()));
+        assert_eq!(error("name=\"DB"), (8, "unclosed quote".to_string()));
+        assert_eq!(error("crit>0.1 and"), (12, "expected a condition".to_string()));
+        assert_eq!(error("crit>0.1 crit<1").1, "expected 'and', 'or' or the end of the filter");
+    }
+}
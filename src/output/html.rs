@@ -0,0 +1,118 @@
+//! Writing the results of an analysis as a single HTML page.
+
+use std::error::Error;
+use std::fmt::Write as _;
+use crate::analyses::criticality::CriticalityResults;
+use crate::analyses::exact::tornado::TornadoResults;
+use crate::network::{EdgeValueMap, Graph};
+use crate::output::{Output, write_output};
+use crate::render::Heatmap;
+use crate::render::svg::render_svg;
+use crate::xml::escape;
+
+const STYLE: &str = "<style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:4px 8px;text-align:right}td:first-child,th:first-child{text-align:left}</style>\n";
+
+/// Width of the label column and of the bars of a tornado chart, and the height of a bar
+const TORNADO_LABEL_WIDTH: f64 = 200.0;
+const TORNADO_WIDTH: f64 = 600.0;
+const TORNADO_ROW: f64 = 24.0;
+
+/// Writes an HTML report to 'path': a summary, the distribution of the end operability if the
+/// states were sampled, the criticality heatmap of the graph and a table of the nodes from most
+/// to least critical
+pub struct HtmlOutput {
+    pub path: String,
+    pub alpha: Option<EdgeValueMap<f32>>,
+}
+
+impl Output for HtmlOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        write_output(&self.path, to_html(graph, results, self.alpha.as_ref()))?;
+        Ok(())
+    }
+}
+
+/// The HTML report of the 'results', see ['HtmlOutput']
+pub fn to_html(graph: &Graph, results: &CriticalityResults, alpha: Option<&EdgeValueMap<f32>>) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Criticality report</title>\n");
+    out.push_str(STYLE);
+    out.push_str("</head>\n<body>\n<h1>Criticality report</h1>\n");
+    // Exact analyses roll up no states
+    let states = match results.row_count {
+        0 => String::new(),
+        states => format!(", {} unique states", states),
+    };
+    writeln!(out, "<p>{} nodes, {} edges{}, mean end operability {}</p>",
+             graph.get_node_ids().len(), graph.get_edges().len(), states, results.end_op_mean).unwrap();
+    let histogram = &results.end_op_histogram;
+    if histogram.total() > 0 {
+        out.push_str("<h2>End operability</h2>\n<table>\n<tr><th>Value</th><th>States</th><th>Cumulative share</th></tr>\n");
+        if histogram.below > 0 {
+            writeln!(out, "<tr><td>&lt; 0</td><td>{}</td><td>{:.4}</td></tr>", histogram.below, histogram.below as f64 / histogram.total() as f64).unwrap();
+        }
+        let width = 1.0 / histogram.counts.len() as f64;
+        for (count, (edge, share)) in histogram.counts.iter().zip(histogram.cdf()) {
+            writeln!(out, "<tr><td>{:.2} to {:.2}</td><td>{}</td><td>{:.4}</td></tr>", edge - width, edge, count, share).unwrap();
+        }
+        if histogram.above > 0 {
+            writeln!(out, "<tr><td>&gt; 1</td><td>{}</td><td>1.0000</td></tr>", histogram.above).unwrap();
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str(&render_svg(&Heatmap::criticality(graph, results, alpha)));
+
+    let mut nodes: Vec<_> = results.nodes.iter().collect();
+    nodes.sort_by(|a, b| b.1.criticality.total_cmp(&a.1.criticality).then(a.0.cmp(b.0)));
+    out.push_str("<table>\n<tr><th>Node</th><th>Id</th><th>Criticality</th><th>Mean end on</th><th>Mean end off</th><th>On</th><th>Off</th></tr>\n");
+    for (id, node) in nodes {
+        let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+        writeln!(out, "<tr><td>{}</td><td>{}</td><td>{:.6}</td><td>{:.6}</td><td>{:.6}</td><td>{}</td><td>{}</td></tr>",
+                 escape(name), id, node.criticality, node.mean_end_on, node.mean_end_off, node.on_count, node.off_count).unwrap();
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+/// HTML page of the tornado chart of the 'results', the widest range on top, and a table of its
+/// data. The bars span the end node reliability from the pessimistic to the optimistic off chance
+/// of their node, around a line at the reliability with every node at its off chance.
+pub fn tornado_to_html(graph: &Graph, results: &TornadoResults) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Tornado chart</title>\n");
+    out.push_str(STYLE);
+    out.push_str("</head>\n<body>\n<h1>Tornado chart</h1>\n");
+    writeln!(out, "<p>End node reliability {} with every node at its off chance, {} nodes varied</p>", results.base, results.bars.len()).unwrap();
+
+    let values = results.bars.iter().flat_map(|b| [b.pessimistic, b.optimistic]).chain(std::iter::once(results.base));
+    let (min, max) = values.fold((f64::MAX, f64::MIN), |(min, max), v| (min.min(v), max.max(v)));
+    let span = if max > min { max - min } else { 1.0 };
+    let x = |value: f64| TORNADO_LABEL_WIDTH + (value - min) / span * TORNADO_WIDTH;
+    let height = TORNADO_ROW * (results.bars.len() as f64 + 1.0);
+    writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-size=\"12\">",
+             TORNADO_LABEL_WIDTH + TORNADO_WIDTH + 20.0, height).unwrap();
+    for (row, bar) in results.bars.iter().enumerate() {
+        let y = TORNADO_ROW * row as f64;
+        let name = graph.get_node(&bar.id).map(|n| n.name.as_str()).unwrap_or("");
+        writeln!(out, "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\">{} ({})</text>", TORNADO_LABEL_WIDTH - 8.0, y + TORNADO_ROW * 0.65, escape(name), bar.id).unwrap();
+        // The part below the base reliability is drawn red, the part above green
+        for (from, to, colour) in [(bar.pessimistic.min(results.base), results.base, "#d9534f"), (results.base, bar.optimistic.max(results.base), "#5cb85c")] {
+            if to > from {
+                writeln!(out, "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
+                         x(from), y + 2.0, x(to) - x(from), TORNADO_ROW - 4.0, colour).unwrap();
+            }
+        }
+    }
+    writeln!(out, "<line x1=\"{0:.2}\" y1=\"0\" x2=\"{0:.2}\" y2=\"{1:.2}\" stroke=\"#333\"/>", x(results.base), height - TORNADO_ROW).unwrap();
+    for value in [min, results.base, max] {
+        writeln!(out, "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\">{:.4}</text>", x(value), height - 6.0, value).unwrap();
+    }
+    out.push_str("</svg>\n");
+
+    out.push_str("<table>\n<tr><th>Node</th><th>Id</th><th>Pessimistic off chance</th><th>Optimistic off chance</th><th>Pessimistic reliability</th><th>Optimistic reliability</th><th>Swing</th></tr>\n");
+    for bar in results.bars.iter() {
+        let name = graph.get_node(&bar.id).map(|n| n.name.as_str()).unwrap_or("");
+        writeln!(out, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.6}</td><td>{:.6}</td><td>{:.6}</td></tr>",
+                 escape(name), bar.id, bar.pessimistic_off_chance, bar.optimistic_off_chance, bar.pessimistic, bar.optimistic, bar.swing()).unwrap();
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,187 @@
+//! Storing the results of a criticality analysis as json, so reports can be written again
+//! without rerunning the analysis.
+//!
+//! The file describes itself, independently of the structures of the crate:
+//!
+//! * 'format' and 'format_version': ['RESULTS_FORMAT'] and '<major>.<minor>', see
+//!   ['FORMAT_MAJOR'] and ['FORMAT_MINOR']
+//! * 'metrics': the definition of every metric of the node records, see ['NODE_METRICS']
+//! * 'run': the tool that wrote the file, the command, args and seed of the run and when the
+//!   file was 'created'
+//! * 'summary': the 'analysis', the 'row_count' of unique states, the 'end_op_mean' and the
+//!   'end_op_histogram' if the states were sampled
+//! * 'nodes': a record for every dynamic node with its 'id', its 'name' and its metrics, in the
+//!   order of the ids
+//! * 'graph': the analysed graph, see ['crate::serialization']
+//!
+//! Readers skip fields they don't know and set missing metrics of a node to zero, so files of a
+//! later minor version, which may only add fields, are read as well. Files of a later major
+//! version are refused with a ['ResultsVersionError']. Files without a 'format', written before
+//! the format was versioned, are read as version 1.
+
+use std::error::Error;
+use std::time::SystemTime;
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult};
+use crate::analyses::criticality::histogram::Histogram;
+use crate::cli::command;
+use crate::errors::json::{JsonFieldError, ResultsVersionError};
+use crate::input::read_input_string;
+use crate::json;
+use crate::json::JsonValue;
+use crate::manifest::{seed, tool, utc_timestamp};
+use crate::network::{Graph, NodeValueMap};
+use crate::output::{Output, write_output};
+use crate::serialization::json::{from_json, to_json};
+
+/// Name of the format, written under 'format'
+pub const RESULTS_FORMAT: &str = "thor-results";
+/// Major version of the format, raised when fields are removed or change their meaning
+pub const FORMAT_MAJOR: u64 = 2;
+/// Minor version of the format, raised when fields are added
+pub const FORMAT_MINOR: u64 = 0;
+
+/// Every metric of a node record with its definition
+pub const NODE_METRICS: [(&str, &str); 5] = [
+    ("on_count", "Number of unique states where the node was visible"),
+    ("off_count", "Number of unique states where the node was not visible"),
+    ("mean_end_on", "Mean operability of the end node while the node is visible"),
+    ("mean_end_off", "Mean operability of the end node while the node is not visible"),
+    ("criticality", "Difference between the mean end operability while on and while off"),
+];
+
+/// The analysed graph together with its results, as written by ['JsonOutput']
+#[derive(Debug, Clone)]
+pub struct StoredResults {
+    pub graph: Graph,
+    pub results: CriticalityResults,
+    /// Metadata of the run that wrote the results, None for files of version 1
+    pub run: Option<JsonValue>,
+}
+
+impl StoredResults {
+    /// Reads results written by a ['JsonOutput'], from the standard input if 'path' is
+    /// ['crate::input::STDIN_PATH']
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or doesn't hold stored results
+    pub fn read(path: &str) -> Result<StoredResults, Box<dyn Error>> {
+        StoredResults::from_document(json::parse(&read_input_string(path)?)?)
+    }
+
+    /// Reads a results document of any version, see the module documentation
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ResultsVersionError'] if the document is of another format or a later major
+    /// version, or a ['JsonFieldError'] if a field is missing or invalid
+    pub fn from_document(document: JsonValue) -> Result<StoredResults, Box<dyn Error>> {
+        let format = match document.get("format") {
+            None => return Ok(from_json(document)?),
+            Some(format) => format.to_cell_string(),
+        };
+        let version = document.get("format_version").map(|v| v.to_cell_string()).unwrap_or_default();
+        let major = version.split('.').next().and_then(|m| m.parse::<u64>().ok());
+        if format != RESULTS_FORMAT || major.map(|m| m > FORMAT_MAJOR).unwrap_or(true) {
+            return Err(ResultsVersionError { found: format!("'{}' version '{}'", format, version), supported: FORMAT_MAJOR }.into());
+        }
+
+        let field_error = |field: &str, expected: &str| JsonFieldError { field: field.to_string(), expected: expected.to_string() };
+        let graph: Graph = from_json(document.get("graph").cloned().ok_or_else(|| field_error("graph", "a graph"))?)?;
+        let summary = document.get("summary").ok_or_else(|| field_error("summary", "an object"))?;
+        let end_op_histogram: Histogram = match summary.get("end_op_histogram") {
+            Some(histogram) if !histogram.is_null() => from_json(histogram.clone())?,
+            _ => Histogram::default(),
+        };
+        let mut nodes = NodeValueMap::new();
+        let records = document.get("nodes").and_then(|n| n.as_array()).ok_or_else(|| field_error("nodes", "an array"))?;
+        for (i, record) in records.iter().enumerate() {
+            let id = record.get("id").and_then(|id| id.as_u64()).ok_or_else(|| field_error(&format!("nodes[{}].id", i), "a node id"))?;
+            let metric = |name: &str| record.get(name).and_then(|v| v.as_f64()).unwrap_or(0.0);
+            nodes.insert(id as u32, NodeCritResult {
+                on_count: metric("on_count") as u64,
+                off_count: metric("off_count") as u64,
+                mean_end_on: metric("mean_end_on"),
+                mean_end_off: metric("mean_end_off"),
+                criticality: metric("criticality"),
+            });
+        }
+        let results = CriticalityResults {
+            row_count: summary.get("row_count").and_then(|r| r.as_u64()).ok_or_else(|| field_error("summary.row_count", "a number"))?,
+            end_op_mean: summary.get("end_op_mean").and_then(|m| m.as_f64()).ok_or_else(|| field_error("summary.end_op_mean", "a number"))?,
+            end_op_histogram,
+            nodes,
+        };
+        Ok(StoredResults { graph, results, run: document.get("run").cloned() })
+    }
+
+    /// The document of the results, see the module documentation
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph or the histogram can't be converted to json
+    pub fn to_document(&self) -> Result<JsonValue, Box<dyn Error>> {
+        let mut metrics = JsonValue::object();
+        for (name, definition) in NODE_METRICS {
+            metrics.insert(name, definition);
+        }
+        let mut ids: Vec<&u32> = self.results.nodes.keys().collect();
+        ids.sort_unstable();
+        let nodes: Vec<JsonValue> = ids.into_iter()
+            .map(|id| {
+                let node = &self.results.nodes[id];
+                JsonValue::object()
+                    .with("id", *id)
+                    .with("name", self.graph.get_node(id).map(|n| n.name.clone()))
+                    .with("on_count", node.on_count)
+                    .with("off_count", node.off_count)
+                    .with("mean_end_on", node.mean_end_on)
+                    .with("mean_end_off", node.mean_end_off)
+                    .with("criticality", node.criticality)
+            })
+            .collect();
+        let histogram = match self.results.end_op_histogram.counts.is_empty() {
+            true => JsonValue::Null,
+            false => to_json(&self.results.end_op_histogram)?,
+        };
+        Ok(JsonValue::object()
+            .with("format", RESULTS_FORMAT)
+            .with("format_version", format!("{}.{}", FORMAT_MAJOR, FORMAT_MINOR))
+            .with("metrics", metrics)
+            .with("run", self.run.clone())
+            .with("summary", JsonValue::object()
+                .with("analysis", "criticality")
+                .with("row_count", self.results.row_count)
+                .with("end_op_mean", self.results.end_op_mean)
+                .with("end_op_histogram", histogram))
+            .with("nodes", nodes)
+            .with("graph", to_json(&self.graph)?))
+    }
+}
+
+/// Writes the graph and the results to a json file at 'path', or to the standard output if the
+/// path is ['crate::output::STDOUT_PATH']
+pub struct JsonOutput {
+    pub path: String,
+    /// Metadata of the run stored with the results, see ['run_metadata']
+    pub run: JsonValue,
+}
+
+impl Output for JsonOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        let mut run = self.run.clone();
+        run.insert("tool", tool());
+        run.insert("created", utc_timestamp(SystemTime::now()));
+        let stored = StoredResults { graph: graph.clone(), results: results.clone(), run: Some(run) };
+        write_output(&self.path, stored.to_document()?.to_pretty_string() + "\n")?;
+        Ok(())
+    }
+}
+
+/// The command, args and seed of the run with the 'args', stored with its results
+pub fn run_metadata(args: &[String]) -> JsonValue {
+    JsonValue::object()
+        .with("command", command(args).unwrap_or("analyze"))
+        .with("args", args.iter().skip(1).cloned().collect::<Vec<String>>())
+        .with("seed", seed(args))
+}
@@ -0,0 +1,139 @@
+//! Module containing the structures used to write the results of analyses.
+//!
+//! Every destination implements the ['Output'] trait and is handed the analysed graph together
+//! with the results once an analysis is complete.
+
+use std::error::Error;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use crate::analyses::criticality::{CriticalityResults, NodeCritResult};
+use crate::analyses::criticality::ranking::{CriticalitySummary, SUMMARY_PERCENTILES, rank};
+use crate::output::bands::CriticalityBands;
+use crate::network::Graph;
+
+pub mod bands;
+pub mod event_tree;
+pub mod filter;
+pub mod html;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod neo4j;
+pub mod networkx;
+pub mod render;
+
+/// Path standing for the standard output wherever results or graphs are written to a path
+pub const STDOUT_PATH: &str = "-";
+
+/// Opens the file at 'path' for buffered writing, or the standard output if the path is
+/// ['STDOUT_PATH']
+///
+/// # Errors
+///
+/// Returns an io error if the file can't be created
+pub fn create_output(path: &str) -> io::Result<Box<dyn Write>> {
+    match path == STDOUT_PATH {
+        true => Ok(Box::new(BufWriter::new(io::stdout().lock()))),
+        false => Ok(Box::new(BufWriter::new(File::create(path)?))),
+    }
+}
+
+/// Writes the whole 'content' to the file at 'path', or to the standard output, see
+/// ['create_output']
+///
+/// # Errors
+///
+/// Returns an io error if the content can't be written
+pub fn write_output<C: AsRef<[u8]>>(path: &str, content: C) -> io::Result<()> {
+    let mut out = create_output(path)?;
+    out.write_all(content.as_ref())?;
+    out.flush()
+}
+
+/// A trait which provides a method for writing the results of a criticality analysis
+pub trait Output: Send {
+    /// Writes the 'results' obtained by analysing the 'graph'
+    ///
+    /// # Errors
+    ///
+    /// May return a ['Error'] if the results could not be written to the destination
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>>;
+}
+
+/// First line printed by ['StdOutput']. Exact analyses roll up no states, so their results have
+/// no state count to report.
+fn summary_line(results: &CriticalityResults) -> String {
+    match results.row_count {
+        0 => format!("Mean end operability: {}", results.end_op_mean),
+        states => format!("Unique states: {}, mean end operability: {}", states, results.end_op_mean),
+    }
+}
+
+/// Prints the results to the standard output, one node per line, after a summary of the spread
+/// of the end operability and of the criticality. With 'top' only that many of the most critical nodes are printed, from the
+/// most to the least critical, otherwise every node in the order of their ids. With 'bands' the
+/// number of nodes in every band is printed and every node is labelled with its band.
+#[derive(Debug, Clone, Default)]
+pub struct StdOutput {
+    pub top: Option<usize>,
+    pub bands: Option<CriticalityBands>,
+}
+
+impl Output for StdOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        println!("{}", summary_line(results));
+        let histogram = &results.end_op_histogram;
+        if let (Some(min), Some(max)) = (histogram.min, histogram.max) {
+            let percentiles: Vec<String> = SUMMARY_PERCENTILES.iter()
+                .filter_map(|p| histogram.percentile(*p).map(|v| format!("p{}={:.4}", p, v)))
+                .collect();
+            println!("End operability: min {}, max {}, {}", min, max, percentiles.join(", "));
+        }
+        if let Some(summary) = CriticalitySummary::new(results) {
+            let percentiles: Vec<String> = summary.percentiles.iter().map(|(p, v)| format!("p{}={}", p, v)).collect();
+            println!("Criticality of {} nodes: min {}, mean {}, max {}, {}", summary.count, summary.min, summary.mean, summary.max, percentiles.join(", "));
+        }
+        if let Some(bands) = self.bands.as_ref() {
+            let counts: Vec<String> = bands.counts(results).iter().map(|(band, count)| format!("{} {}", band, count)).collect();
+            println!("Bands: {}", counts.join(", "));
+        }
+        let nodes: Vec<(u32, &NodeCritResult)> = match self.top {
+            Some(n) => rank(results).into_iter().take(n).collect(),
+            None => results.nodes.iter().map(|(id, node)| (*id, node)).collect(),
+        };
+        for (id, node) in nodes.iter() {
+            let name = graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+            let attributes: Vec<String> = graph.get_node(id).iter()
+                .flat_map(|n| n.attributes.iter())
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            let band = match self.bands.as_ref().and_then(|bands| bands.band(node.criticality)) {
+                Some(band) => format!(" ({})", band.name),
+                None => String::new(),
+            };
+            match attributes.is_empty() {
+                true => println!("{} ({}): criticality {}{}", name, id, node.criticality, band),
+                false => println!("{} ({}): criticality {}{} [{}]", name, id, node.criticality, band, attributes.join(", ")),
+            }
+        }
+        if nodes.len() < results.nodes.len() {
+            println!("... {} less critical nodes not shown", results.nodes.len() - nodes.len());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyses::criticality::histogram::Histogram;
+    use crate::network::NodeValueMap;
+
+    #[test]
+    fn exact_results_are_summarized_without_a_state_count() {
+        let mut results = CriticalityResults { row_count: 0, end_op_mean: 0.75, end_op_histogram: Histogram::default(), nodes: NodeValueMap::new() };
+        assert_eq!(summary_line(&results), "Mean end operability: 0.75");
+        results.row_count = 4;
+        assert_eq!(summary_line(&results), "Unique states: 4, mean end operability: 0.75");
+    }
+}
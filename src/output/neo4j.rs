@@ -0,0 +1,55 @@
+//! Writing criticality results back to the Neo4j database the graph was read from.
+//!
+//! Like the input the results are sent through the plain http Cypher endpoint, bolt and https
+//! aren't supported, see ['crate::input::neo4j'].
+
+use std::error::Error;
+use crate::analyses::criticality::CriticalityResults;
+use crate::input::neo4j::Neo4jConnection;
+use crate::json::JsonValue;
+use crate::network::Graph;
+use crate::output::Output;
+
+/// Stores the criticality of every dynamic node as properties on the matching database node
+pub struct Neo4jOutput {
+    pub connection: Neo4jConnection,
+    /// Label of the nodes the results are written to
+    pub node_label: String,
+    /// Integer property used as the node id. The internal Neo4j id is used if none is given
+    pub id_property: Option<String>,
+    /// Property the criticality score is written to
+    pub score_property: String,
+}
+
+impl Neo4jOutput {
+    /// Creates an output using the same default label and id as ['Neo4jCritConfigs']
+    pub fn new(connection: Neo4jConnection) -> Neo4jOutput {
+        Neo4jOutput {
+            connection,
+            node_label: "Node".to_string(),
+            id_property: None,
+            score_property: "criticality".to_string(),
+        }
+    }
+}
+
+impl Output for Neo4jOutput {
+    fn write(&self, _graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        let rows: Vec<JsonValue> = results.nodes.iter()
+            .map(|(id, node)| JsonValue::object()
+                .with("id", *id)
+                .with("criticality", node.criticality)
+                .with("end_on", node.mean_end_on)
+                .with("end_off", node.mean_end_off))
+            .collect();
+        let statement = format!(
+            "UNWIND $rows AS row MATCH (n:`{label}`) WHERE {id} = row.id \
+            SET n.`{score}` = row.criticality, n.`{score}_end_on` = row.end_on, n.`{score}_end_off` = row.end_off",
+            label = self.node_label,
+            id = Neo4jConnection::id_expression("n", &self.id_property),
+            score = self.score_property,
+        );
+        self.connection.run(&statement, JsonValue::object().with("rows", rows))?;
+        Ok(())
+    }
+}
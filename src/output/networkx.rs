@@ -0,0 +1,23 @@
+//! Writing criticality results as node attributes of the node-link json of NetworkX.
+
+use std::error::Error;
+use crate::analyses::criticality::{CriticalityData, CriticalityResults};
+use crate::export::networkx::to_node_link;
+use crate::network::Graph;
+use crate::output::{Output, write_output};
+
+/// Writes the analysed graph with the results of every dynamic node to a node-link json file at
+/// 'path', see ['crate::export::networkx']. The end node operability is written to the graph
+/// attributes.
+pub struct NodeLinkOutput {
+    pub path: String,
+    /// Off chances and edge attributes written along with the graph
+    pub data: CriticalityData,
+}
+
+impl Output for NodeLinkOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        write_output(&self.path, to_node_link(graph, Some(&self.data), Some(results)).to_pretty_string() + "\n")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+//! Writing drawings of the analysed graph with its nodes colored by their criticality.
+
+use std::error::Error;
+use crate::analyses::criticality::CriticalityResults;
+use crate::export::cytoscape::to_cytoscape;
+use crate::network::{EdgeValueMap, Graph};
+use crate::output::{Output, write_output};
+use crate::render::Heatmap;
+use crate::render::png::render_png;
+use crate::render::svg::render_svg;
+
+/// Writes an SVG heatmap of the criticality to 'path'. Edges are drawn thicker the larger their
+/// 'alpha' weight, if given.
+pub struct SvgOutput {
+    pub path: String,
+    pub alpha: Option<EdgeValueMap<f32>>,
+}
+
+impl Output for SvgOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        write_output(&self.path, render_svg(&Heatmap::criticality(graph, results, self.alpha.as_ref())))?;
+        Ok(())
+    }
+}
+
+/// Writes a PNG heatmap of the criticality to 'path', see ['SvgOutput']
+pub struct PngOutput {
+    pub path: String,
+    pub alpha: Option<EdgeValueMap<f32>>,
+}
+
+impl Output for PngOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        write_output(&self.path, render_png(&Heatmap::criticality(graph, results, self.alpha.as_ref())))?;
+        Ok(())
+    }
+}
+
+/// Writes the elements json of Cytoscape.js with the criticality of every node to 'path', for
+/// the web viewer, see ['crate::export::cytoscape']
+pub struct CytoscapeOutput {
+    pub path: String,
+    pub alpha: Option<EdgeValueMap<f32>>,
+}
+
+impl Output for CytoscapeOutput {
+    fn write(&self, graph: &Graph, results: &CriticalityResults) -> Result<(), Box<dyn Error>> {
+        let heatmap = Heatmap::criticality(graph, results, self.alpha.as_ref());
+        write_output(&self.path, to_cytoscape(&heatmap, Some(results)).to_pretty_string() + "
+")?;
+        Ok(())
+    }
+}
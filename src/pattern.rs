@@ -0,0 +1,286 @@
+//! Minimal backtracking regular expressions, for matching node names.
+//!
+//! Handles literals, '.', the classes '[...]' and '[^...]' with ranges, the classes '\d', '\w' and
+//! '\s' and their negations '\D', '\W' and '\S', the anchors '^' and '$', groups '(...)' and
+//! '(?:...)', alternatives '|' and the greedy repetitions '*', '+', '?', '{n}', '{n,}' and
+//! '{n,m}'. A leading '(?i)' ignores the case. Patterns match anywhere in the text unless anchored.
+//! Backreferences, lookarounds and lazy repetitions are not supported.
+
+use crate::errors::filter::PatternError;
+
+/// A parsed regular expression, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    root: Node,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class { ranges: Vec<(char, char)>, negated: bool },
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize> },
+}
+
+const DIGITS: [(char, char); 1] = [('0', '9')];
+const WORD: [(char, char); 4] = [('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+const SPACE: [(char, char); 3] = [(' ', ' '), ('\t', '\r'), ('\u{a0}', '\u{a0}')];
+
+impl Pattern {
+    /// Parses the 'pattern'
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['PatternError'] if the pattern is invalid or uses unsupported syntax
+    pub fn new(pattern: &str) -> Result<Pattern, PatternError> {
+        let ignore_case = pattern.starts_with("(?i)");
+        let mut parser = Parser { source: pattern, chars: pattern.chars().collect(), pos: 0 };
+        if ignore_case {
+            parser.pos = 4;
+        }
+        let root = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+        Ok(Pattern { source: pattern.to_string(), root, ignore_case })
+    }
+
+    /// The pattern as it was written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the pattern matches anywhere in the 'text'
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        (0..=text.len()).any(|start| self.matches(&self.root, &text, start, &mut |_| true))
+    }
+
+    /// Whether 'node' matches the 'text' from 'pos' on and 'next' accepts the position after it
+    fn matches(&self, node: &Node, text: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        match node {
+            Node::Empty => next(pos),
+            Node::Char(c) => pos < text.len() && self.same(text[pos], *c) && next(pos + 1),
+            Node::Any => pos < text.len() && next(pos + 1),
+            Node::Class { ranges, negated } => {
+                pos < text.len() && self.in_class(text[pos], ranges) != *negated && next(pos + 1)
+            }
+            Node::Start => pos == 0 && next(pos),
+            Node::End => pos == text.len() && next(pos),
+            Node::Concat(nodes) => self.concat(nodes, text, pos, next),
+            Node::Alternation(options) => options.iter().any(|option| self.matches(option, text, pos, next)),
+            Node::Repeat { node, min, max } => self.repeat(node, *min, *max, 0, text, pos, next),
+        }
+    }
+
+    fn concat(&self, nodes: &[Node], text: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        match nodes.split_first() {
+            None => next(pos),
+            Some((first, rest)) => self.matches(first, text, pos, &mut |p| self.concat(rest, text, p, next)),
+        }
+    }
+
+    /// Matches as many more repetitions as possible first, then backtracks
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(&self, node: &Node, min: usize, max: Option<usize>, count: usize, text: &[char], pos: usize,
+              next: &mut dyn FnMut(usize) -> bool) -> bool {
+        if max.map(|m| count >= m).unwrap_or(false) {
+            return next(pos);
+        }
+        // A repetition matching nothing only counts towards the minimum, or it would never end
+        let more = self.matches(node, text, pos, &mut |p| {
+            (p != pos || count < min) && self.repeat(node, min, max, count + 1, text, p, next)
+        });
+        more || (count >= min && next(pos))
+    }
+
+    fn same(&self, a: char, b: char) -> bool {
+        a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn in_class(&self, c: char, ranges: &[(char, char)]) -> bool {
+        let within = |c: char| ranges.iter().any(|(from, to)| *from <= c && c <= *to);
+        within(c) || (self.ignore_case && (c.to_lowercase().any(within) || c.to_uppercase().any(within)))
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> PatternError {
+        PatternError { pattern: self.source.to_string(), pos: self.pos, reason: reason.to_string() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn alternation(&mut self) -> Result<Node, PatternError> {
+        let mut options = vec![self.concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            options.push(self.concat()?);
+        }
+        Ok(match options.len() {
+            1 => options.pop().unwrap(),
+            _ => Node::Alternation(options),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, PatternError> {
+        let mut nodes = vec![];
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.repetition(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Node, PatternError> {
+        let c = self.peek().unwrap();
+        self.pos += 1;
+        match c {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '(' => {
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                } else if self.peek() == Some('?') {
+                    return Err(self.error("only '(?:' groups and a leading '(?i)' are supported"));
+                }
+                let node = self.alternation()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                self.pos += 1;
+                Ok(node)
+            }
+            '[' => self.class(),
+            '\\' => self.escape(),
+            '*' | '+' | '?' => {
+                self.pos -= 1;
+                Err(self.error("nothing to repeat"))
+            }
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, PatternError> {
+        let c = self.peek().ok_or_else(|| self.error("trailing '\\'"))?;
+        self.pos += 1;
+        Ok(match c {
+            'd' | 'D' => Node::Class { ranges: DIGITS.to_vec(), negated: c == 'D' },
+            'w' | 'W' => Node::Class { ranges: WORD.to_vec(), negated: c == 'W' },
+            's' | 'S' => Node::Class { ranges: SPACE.to_vec(), negated: c == 'S' },
+            'n' => Node::Char('\n'),
+            't' => Node::Char('\t'),
+            c if c.is_alphanumeric() => {
+                self.pos -= 1;
+                return Err(self.error(&format!("unknown escape '\\{}'", c)));
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    /// A class after its '['
+    fn class(&mut self) -> Result<Node, PatternError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = vec![];
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("unclosed class"))?;
+            self.pos += 1;
+            let from = match c {
+                // A ']' right after the '[' is a member
+                ']' if !first => break,
+                '\\' => {
+                    let escaped = self.peek().ok_or_else(|| self.error("unclosed class"))?;
+                    self.pos += 1;
+                    match escaped {
+                        'd' => { ranges.extend(DIGITS); first = false; continue; }
+                        'w' => { ranges.extend(WORD); first = false; continue; }
+                        's' => { ranges.extend(SPACE); first = false; continue; }
+                        'n' => '\n',
+                        't' => '\t',
+                        c if c.is_alphanumeric() => {
+                            self.pos -= 1;
+                            return Err(self.error(&format!("unknown escape '\\{}' in a class", c)));
+                        }
+                        c => c,
+                    }
+                }
+                c => c,
+            };
+            first = false;
+            let to = match (self.peek(), self.chars.get(self.pos + 1)) {
+                (Some('-'), Some(to)) if *to != ']' => {
+                    self.pos += 2;
+                    *to
+                }
+                _ => from,
+            };
+            if to < from {
+                return Err(self.error(&format!("the range '{}-{}' is reversed", from, to)));
+            }
+            ranges.push((from, to));
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    /// The 'atom' with the repetition following it, if any
+    fn repetition(&mut self, atom: Node) -> Result<Node, PatternError> {
+        let (min, max) = match self.peek() {
+            Some('*') => { self.pos += 1; (0, None) }
+            Some('+') => { self.pos += 1; (1, None) }
+            Some('?') => { self.pos += 1; (0, Some(1)) }
+            Some('{') => match self.bounds() {
+                Some(bounds) => bounds,
+                // Not a repetition, the '{' is read as a character next
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if matches!(self.peek(), Some('*' | '+' | '?')) {
+            return Err(self.error("lazy and repeated repetitions are not supported"));
+        }
+        if max.map(|max| max < min).unwrap_or(false) {
+            return Err(self.error("the repetition has a maximum below its minimum"));
+        }
+        Ok(Node::Repeat { node: Box::new(atom), min, max })
+    }
+
+    /// Reads '{n}', '{n,}' or '{n,m}', leaving the position untouched if there is none
+    fn bounds(&mut self) -> Option<(usize, Option<usize>)> {
+        let end = self.chars[self.pos..].iter().position(|c| *c == '}')? + self.pos;
+        let inside: String = self.chars[self.pos + 1..end].iter().collect();
+        let bounds = match inside.split_once(',') {
+            None => inside.parse::<usize>().ok().map(|n| (n, Some(n))),
+            Some((min, "")) => min.parse::<usize>().ok().map(|n| (n, None)),
+            Some((min, max)) => min.parse::<usize>().ok().zip(max.parse::<usize>().ok()).map(|(min, max)| (min, Some(max))),
+        }?;
+        self.pos = end + 1;
+        Some(bounds)
+    }
+}
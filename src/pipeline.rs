@@ -0,0 +1,350 @@
+//! Chaining analyses over one loaded graph.
+//!
+//! A pipeline runs its stages in order on a shared ['PipelineState']. Analysis stages store their
+//! results in the state, transforming stages change the graph or the dynamic nodes for the stages
+//! that follow, so for example the nodes that a sampled analysis found uncritical can be frozen
+//! before an exact analysis of the remaining nodes. Pipelines are declared in the 'pipeline'
+//! section of the ['crate::config::Config'] as a list of stages:
+//!
+//! * '{"stage": "criticality", "samples": n}' samples the criticality of the dynamic nodes
+//! * '{"stage": "exact"}' or '{"stage": "birnbaum"}' computes the criticality, the Birnbaum
+//!   importance, exactly
+//! * '{"stage": "removal"}' prints the end node reliability without each dynamic node
+//! * '{"stage": "freeze", "below": c}' makes every dynamic node whose last criticality is below
+//!   'c' static
+//! * '{"stage": "harden", "budget": b, "hardening": "static" or factor, "samples": n}' hardens the
+//!   nodes chosen by the greedy hardening optimizer
+//!
+//! The stage names are looked up in an ['AnalysisRegistry'], which can be extended with stages
+//! of other crates.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+use log::{info, Level};
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::CriticalityResults;
+use crate::analyses::criticality::builder::{CriticalityBuilder, DEFAULT_SAMPLES};
+use crate::analyses::criticality::vis_gen::visibility_states_gen::DEFAULT_OFF_CHANCE;
+use crate::analyses::exact::ExactCriticality;
+use crate::analyses::hardening::{costs_from_attributes, GreedyHardening, Hardening};
+use crate::analyses::removal::NodeRemoval;
+use crate::config::Config;
+use crate::errors::analysis::ThorError;
+use crate::errors::config::ConfigError;
+use crate::json::JsonValue;
+use crate::logging::event;
+use crate::registry::AnalysisRegistry;
+use crate::network::{Graph, LinkMap, NodeValueMap};
+use crate::roll_up::RollUp;
+
+/// Everything a stage reads and changes. The graph and its link map are shared, stages that
+/// change the graph copy it first.
+pub struct PipelineState {
+    pub graph: Arc<Graph>,
+    pub l_map: Arc<LinkMap>,
+    pub dynamic_ids: HashSet<u32>,
+    /// Chance of every dynamic node to not be visible
+    pub off_chances: NodeValueMap<f32>,
+    pub roll_up_rule: Box<dyn RollUp>,
+    pub start_id: u32,
+    pub end_id: u32,
+    /// Results of the last stage that computed the criticality of the nodes
+    pub results: Option<CriticalityResults>,
+    /// Text reported by the stages that don't compute criticality results
+    pub report: String,
+}
+
+impl Clone for PipelineState {
+    fn clone(&self) -> Self {
+        PipelineState {
+            graph: self.graph.clone(),
+            l_map: self.l_map.clone(),
+            dynamic_ids: self.dynamic_ids.clone(),
+            off_chances: self.off_chances.clone(),
+            roll_up_rule: dyn_clone::clone_box(&*self.roll_up_rule),
+            start_id: self.start_id,
+            end_id: self.end_id,
+            results: self.results.clone(),
+            report: self.report.clone(),
+        }
+    }
+}
+
+impl PipelineState {
+    pub fn new(graph: Graph, dynamic_ids: HashSet<u32>, off_chances: NodeValueMap<f32>, roll_up_rule: Box<dyn RollUp>, start_id: u32, end_id: u32) -> PipelineState {
+        PipelineState {
+            l_map: Arc::new(graph.links_map()),
+            graph: Arc::new(graph),
+            dynamic_ids,
+            off_chances,
+            roll_up_rule,
+            start_id,
+            end_id,
+            results: None,
+            report: String::new(),
+        }
+    }
+}
+
+/// A single step of a ['Pipeline']
+pub trait Stage: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Runs the stage on the 'state'
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stage fails or needs results that no earlier stage computed
+    fn run(&self, state: &mut PipelineState, ctx: &AnalysisContext) -> Result<(), ThorError>;
+}
+
+/// Stages run one after another
+#[derive(Default)]
+pub struct Pipeline {
+    pub stages: Vec<Box<dyn Stage>>,
+}
+
+impl Pipeline {
+    /// Builds the pipeline declared in the 'pipeline' section of the configuration, see the
+    /// module documentation
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ConfigError'] if the section is missing or a stage is unknown or invalid
+    pub fn from_config(config: &Config) -> Result<Pipeline, ConfigError> {
+        Pipeline::from_config_with(config, &AnalysisRegistry::default())
+    }
+
+    /// Same as ['Pipeline::from_config'] with the stages of the 'registry'
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ConfigError'] if the section is missing or a stage is unknown or invalid
+    pub fn from_config_with(config: &Config, registry: &AnalysisRegistry) -> Result<Pipeline, ConfigError> {
+        let stages = config.get("pipeline")
+            .and_then(|p| p.as_array())
+            .ok_or_else(|| config.error("pipeline", "must be a list of stages"))?;
+        let stages = stages.iter().enumerate()
+            .map(|(i, stage)| registry.build_declared(stage).map_err(|reason| config.error(&format!("pipeline[{}]", i), &reason)))
+            .collect::<Result<Vec<Box<dyn Stage>>, ConfigError>>()?;
+        Ok(Pipeline { stages })
+    }
+
+    /// Runs every stage on the 'state'
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first stage that fails
+    pub fn run(&self, state: &mut PipelineState, ctx: &AnalysisContext) -> Result<(), ThorError> {
+        for stage in self.stages.iter() {
+            ctx.check_cancelled()?;
+            info!("Running the {} stage on {} dynamic nodes", stage.name(), state.dynamic_ids.len());
+            let start = Instant::now();
+            stage.run(state, ctx)?;
+            event(Level::Info, "phase_completed", JsonValue::object()
+                .with("phase", stage.name())
+                .with("seconds", start.elapsed().as_secs_f64()));
+            print!("{}", std::mem::take(&mut state.report));
+        }
+        Ok(())
+    }
+}
+
+/// Builds a stage from its json declaration with the stages of the default
+/// ['AnalysisRegistry'], returning the reason if it is invalid
+pub fn stage_from_json(value: &JsonValue) -> Result<Box<dyn Stage>, String> {
+    AnalysisRegistry::default().build_declared(value)
+}
+
+/// The number 'key' of a stage declaration, None if it isn't given
+pub fn number_option(value: &JsonValue, key: &str) -> Result<Option<f64>, String> {
+    value.get(key).map(|v| v.as_f64().ok_or(format!("'{}' must be a number", key))).transpose()
+}
+
+fn samples_option(value: &JsonValue) -> Result<u64, String> {
+    Ok(number_option(value, "samples")?.map(|s| s as u64).unwrap_or(DEFAULT_SAMPLES))
+}
+
+/// Samples the criticality of the dynamic nodes
+pub struct CriticalityStage {
+    pub samples: u64,
+}
+
+impl CriticalityStage {
+    pub fn from_json(value: &JsonValue) -> Result<Box<dyn Stage>, String> {
+        Ok(Box::new(CriticalityStage { samples: samples_option(value)? }))
+    }
+}
+
+impl Stage for CriticalityStage {
+    fn name(&self) -> &str {
+        "criticality"
+    }
+
+    fn run(&self, state: &mut PipelineState, ctx: &AnalysisContext) -> Result<(), ThorError> {
+        let mut criticality = CriticalityBuilder::new((*state.graph).clone())
+            .dynamic_ids(state.dynamic_ids.clone())
+            .off_chances(state.off_chances.clone())
+            .roll_up_rule(dyn_clone::clone_box(&*state.roll_up_rule))
+            .start_id(state.start_id)
+            .end_id(state.end_id)
+            .samples(self.samples)
+            .build()?;
+        criticality.outputs.clear();
+        state.results = Some(criticality.run(ctx)?);
+        Ok(())
+    }
+}
+
+/// Computes the criticality of the dynamic nodes exactly
+pub struct ExactStage {}
+
+impl ExactStage {
+    pub fn from_json(_value: &JsonValue) -> Result<Box<dyn Stage>, String> {
+        Ok(Box::new(ExactStage {}))
+    }
+}
+
+impl Stage for ExactStage {
+    fn name(&self) -> &str {
+        "exact"
+    }
+
+    fn run(&self, state: &mut PipelineState, ctx: &AnalysisContext) -> Result<(), ThorError> {
+        let exact = ExactCriticality {
+            graph: (*state.graph).clone(),
+            dynamic_ids: state.dynamic_ids.clone(),
+            off_chances: state.off_chances.clone(),
+            roll_up_rule: dyn_clone::clone_box(&*state.roll_up_rule),
+            l_map: (*state.l_map).clone(),
+            start_id: state.start_id,
+            end_id: state.end_id,
+            outputs: vec![],
+        };
+        state.results = Some(exact.run(ctx)?);
+        Ok(())
+    }
+}
+
+/// Prints the end node reliability without each dynamic node
+pub struct RemovalStage {}
+
+impl RemovalStage {
+    pub fn from_json(_value: &JsonValue) -> Result<Box<dyn Stage>, String> {
+        Ok(Box::new(RemovalStage {}))
+    }
+}
+
+impl Stage for RemovalStage {
+    fn name(&self) -> &str {
+        "removal"
+    }
+
+    fn run(&self, state: &mut PipelineState, ctx: &AnalysisContext) -> Result<(), ThorError> {
+        let removal = NodeRemoval {
+            graph: (*state.graph).clone(),
+            dynamic_ids: state.dynamic_ids.clone(),
+            off_chances: state.off_chances.clone(),
+            roll_up_rule: dyn_clone::clone_box(&*state.roll_up_rule),
+            l_map: (*state.l_map).clone(),
+            start_id: state.start_id,
+            end_id: state.end_id,
+        };
+        state.report += &removal.run(ctx)?.report(&state.graph);
+        Ok(())
+    }
+}
+
+/// Makes every dynamic node whose criticality in the last results is below 'below' static
+pub struct FreezeStage {
+    pub below: f64,
+}
+
+impl FreezeStage {
+    pub fn from_json(value: &JsonValue) -> Result<Box<dyn Stage>, String> {
+        Ok(Box::new(FreezeStage { below: number_option(value, "below")?.ok_or("the freeze stage needs 'below'")? }))
+    }
+}
+
+impl Stage for FreezeStage {
+    fn name(&self) -> &str {
+        "freeze"
+    }
+
+    fn run(&self, state: &mut PipelineState, _ctx: &AnalysisContext) -> Result<(), ThorError> {
+        let results = state.results.as_ref()
+            .ok_or_else(|| ThorError::Failed("the freeze stage needs the results of an earlier analysis stage".into()))?;
+        let frozen: Vec<u32> = results.nodes.iter()
+            .filter(|(id, node)| node.criticality < self.below && state.dynamic_ids.contains(id))
+            .map(|(id, _)| *id)
+            .collect();
+        info!("Freezing {} nodes with a criticality below {}", frozen.len(), self.below);
+        let graph = Arc::make_mut(&mut state.graph);
+        for id in frozen {
+            state.dynamic_ids.remove(&id);
+            graph.static_nodes.insert(id);
+        }
+        Ok(())
+    }
+}
+
+/// Hardens the nodes chosen by the ['GreedyHardening'] optimizer within the budget
+pub struct HardenStage {
+    pub budget: f64,
+    pub hardening: Hardening,
+    pub samples: u64,
+}
+
+impl HardenStage {
+    pub fn from_json(value: &JsonValue) -> Result<Box<dyn Stage>, String> {
+        let hardening = match value.get("hardening") {
+            None => Hardening::Static,
+            Some(h) if h.as_str() == Some("static") => Hardening::Static,
+            Some(h) => Hardening::ReduceOffChance(h.as_f64().ok_or("'hardening' must be \"static\" or a number")? as f32),
+        };
+        Ok(Box::new(HardenStage {
+            budget: number_option(value, "budget")?.ok_or("the harden stage needs a 'budget'")?,
+            hardening,
+            samples: samples_option(value)?,
+        }))
+    }
+}
+
+impl Stage for HardenStage {
+    fn name(&self) -> &str {
+        "harden"
+    }
+
+    fn run(&self, state: &mut PipelineState, ctx: &AnalysisContext) -> Result<(), ThorError> {
+        let greedy = GreedyHardening {
+            graph: (*state.graph).clone(),
+            dynamic_ids: state.dynamic_ids.clone(),
+            off_chances: state.off_chances.clone(),
+            roll_up_rule: dyn_clone::clone_box(&*state.roll_up_rule),
+            start_id: state.start_id,
+            end_id: state.end_id,
+            costs: costs_from_attributes(&state.graph),
+            budget: self.budget,
+            hardening: self.hardening,
+            samples: self.samples,
+            seed: None,
+            threads: num_cpus::get() as u8,
+        };
+        let plan = greedy.run(ctx)?;
+        state.report += &plan.report(&state.graph);
+        for step in plan.steps {
+            match self.hardening {
+                Hardening::Static => {
+                    state.dynamic_ids.remove(&step.id);
+                    Arc::make_mut(&mut state.graph).static_nodes.insert(step.id);
+                }
+                Hardening::ReduceOffChance(factor) => {
+                    let off_chance = *state.off_chances.get(&step.id).unwrap_or(&DEFAULT_OFF_CHANCE);
+                    state.off_chances.insert(step.id, off_chance * factor);
+                }
+            }
+        }
+        Ok(())
+    }
+}
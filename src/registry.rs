@@ -0,0 +1,78 @@
+//! Selecting analyses by name at runtime.
+//!
+//! The registry maps names to factories building a pipeline ['Stage'] from its json options, so
+//! the configuration, the command line and the server can run any registered analysis without
+//! knowing its type.
+
+use std::collections::BTreeMap;
+use crate::json::JsonValue;
+use crate::pipeline::{CriticalityStage, ExactStage, FreezeStage, HardenStage, RemovalStage, Stage};
+
+/// Builds a stage from its json options, returning the reason if they are invalid
+pub type StageFactory = fn(&JsonValue) -> Result<Box<dyn Stage>, String>;
+
+/// Names of the stages and their factories. The default registry holds every stage of this
+/// crate, see ['crate::pipeline'].
+#[derive(Clone)]
+pub struct AnalysisRegistry {
+    factories: BTreeMap<String, StageFactory>,
+}
+
+impl Default for AnalysisRegistry {
+    fn default() -> Self {
+        let mut registry = AnalysisRegistry::new();
+        registry.register("criticality", CriticalityStage::from_json);
+        registry.register("exact", ExactStage::from_json);
+        // The criticality computed by the exact analysis is the Birnbaum importance
+        registry.register("birnbaum", ExactStage::from_json);
+        registry.register("removal", RemovalStage::from_json);
+        registry.register("freeze", FreezeStage::from_json);
+        registry.register("harden", HardenStage::from_json);
+        registry
+    }
+}
+
+impl AnalysisRegistry {
+    /// A registry without any stage
+    pub fn new() -> AnalysisRegistry {
+        AnalysisRegistry { factories: BTreeMap::new() }
+    }
+
+    /// Registers the 'factory' under 'name', replacing the factory registered before
+    pub fn register(&mut self, name: &str, factory: StageFactory) {
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Registered names in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        self.factories.keys().map(|n| n.as_str()).collect()
+    }
+
+    /// Builds the stage 'name' from its 'options'
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason if the name is unknown or the options are invalid
+    pub fn build(&self, name: &str, options: &JsonValue) -> Result<Box<dyn Stage>, String> {
+        match self.factories.get(name) {
+            Some(factory) => factory(options),
+            None => Err(format!("unknown stage '{}', known stages are {}", name, self.names().join(", "))),
+        }
+    }
+
+    /// Builds a stage from a declaration naming it in its 'stage' field, see ['crate::pipeline']
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason if the declaration has no name or the stage can't be built
+    pub fn build_declared(&self, declaration: &JsonValue) -> Result<Box<dyn Stage>, String> {
+        match declaration.get("stage").and_then(|s| s.as_str()) {
+            Some(name) => self.build(name, declaration),
+            None => Err("every stage needs a 'stage' name".to_string()),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Drawing a graph with its nodes colored by a score, such as their criticality.
+//!
+//! Graphs are laid out in layers from the start nodes on the left to the end nodes on the right,
+//! in the style of Sugiyama: every node is placed one layer right of its furthest child, and the
+//! nodes within a layer are ordered by the mean position of their neighbours to reduce edge
+//! crossings. Edges spanning several layers are drawn as straight lines.
+
+use std::collections::{HashMap, VecDeque};
+use crate::analyses::criticality::CriticalityResults;
+use crate::network::{EdgeValueMap, Graph, Links, NodeValueMap};
+
+pub mod png;
+pub mod svg;
+
+/// Horizontal distance between two layers
+pub const LAYER_SPACING: f64 = 140.0;
+/// Vertical distance between two nodes of a layer
+pub const NODE_SPACING: f64 = 60.0;
+/// Radius of a drawn node
+pub const NODE_RADIUS: f64 = 16.0;
+/// Space around the drawing
+pub const MARGIN: f64 = 40.0;
+/// Number of up and down sweeps ordering the layers
+const ORDERING_SWEEPS: usize = 8;
+
+/// Position of the center of every node
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub positions: NodeValueMap<(f64, f64)>,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Layout {
+    /// Lays out the 'graph' in layers, see the module documentation
+    pub fn layered(graph: &Graph) -> Layout {
+        let layers = order_layers(graph, assign_layers(graph));
+        let tallest = layers.iter().map(|l| l.len()).max().unwrap_or(0);
+        let height = 2.0 * MARGIN + (tallest.max(1) - 1) as f64 * NODE_SPACING;
+        let width = 2.0 * MARGIN + (layers.len().max(1) - 1) as f64 * LAYER_SPACING;
+        let mut positions = NodeValueMap::new();
+        for (x, layer) in layers.iter().enumerate() {
+            // Every layer is centered vertically
+            let offset = (tallest - layer.len()) as f64 * NODE_SPACING / 2.0;
+            for (y, id) in layer.iter().enumerate() {
+                positions.insert(*id, (MARGIN + x as f64 * LAYER_SPACING, MARGIN + offset + y as f64 * NODE_SPACING));
+            }
+        }
+        Layout { positions, width, height }
+    }
+}
+
+/// Layer of every node, one more than the largest layer of its children. Nodes on a cycle are
+/// placed one layer after the last acyclic layer.
+fn assign_layers(graph: &Graph) -> Vec<Vec<u32>> {
+    let l_map = graph.links_map();
+    let mut remaining: HashMap<u32, usize> = graph.get_node_ids().into_iter()
+        .map(|id| (id, l_map.children_of(&id).len()))
+        .collect();
+    let mut layer_of: HashMap<u32, usize> = HashMap::new();
+    let mut agenda: VecDeque<u32> = remaining.iter().filter(|(_, n)| **n == 0).map(|(id, _)| *id).collect();
+    while let Some(id) = agenda.pop_front() {
+        remaining.remove(&id);
+        let layer = layer_of.get(&id).copied().unwrap_or(0);
+        layer_of.entry(id).or_insert(layer);
+        for parent in l_map.parents_of(&id) {
+            let parent_layer = layer_of.entry(*parent).or_insert(0);
+            *parent_layer = (*parent_layer).max(layer + 1);
+            if let Some(n) = remaining.get_mut(parent) {
+                *n -= 1;
+                if *n == 0 {
+                    agenda.push_back(*parent);
+                }
+            }
+        }
+    }
+    let cyclic_layer = layer_of.values().max().map(|l| l + 1).unwrap_or(0);
+    for id in remaining.keys() {
+        layer_of.insert(*id, cyclic_layer);
+    }
+
+    let mut layers: Vec<Vec<u32>> = vec![vec![]; layer_of.values().max().map(|l| l + 1).unwrap_or(0)];
+    for (id, layer) in layer_of {
+        layers[layer].push(id);
+    }
+    for layer in layers.iter_mut() {
+        layer.sort_unstable();
+    }
+    layers.retain(|l| !l.is_empty());
+    layers
+}
+
+/// Reorders every layer by the mean index of the node's neighbours in the previous layer,
+/// sweeping alternately from the left and from the right
+fn order_layers(graph: &Graph, mut layers: Vec<Vec<u32>>) -> Vec<Vec<u32>> {
+    let l_map = graph.links_map();
+    for sweep in 0..ORDERING_SWEEPS {
+        let forward = sweep % 2 == 0;
+        let order: Vec<usize> = match forward {
+            true => (1..layers.len()).collect(),
+            false => (0..layers.len().saturating_sub(1)).rev().collect(),
+        };
+        for i in order {
+            let fixed = match forward { true => i - 1, false => i + 1 };
+            let index: HashMap<u32, usize> = layers[fixed].iter().enumerate().map(|(i, id)| (*id, i)).collect();
+            let mut keyed: Vec<(f64, usize, u32)> = layers[i].iter().enumerate().map(|(pos, id)| {
+                let neighbours: Vec<usize> = l_map.get(id).iter()
+                    .flat_map(|(children, parents)| children.iter().chain(parents.iter()))
+                    .filter_map(|n| index.get(n).copied())
+                    .collect();
+                let barycenter = match neighbours.is_empty() {
+                    true => pos as f64,
+                    false => neighbours.iter().sum::<usize>() as f64 / neighbours.len() as f64,
+                };
+                (barycenter, pos, *id)
+            }).collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            layers[i] = keyed.into_iter().map(|(_, _, id)| id).collect();
+        }
+    }
+    layers
+}
+
+/// RGB color of a node, from pale yellow for a score of zero to dark red for the highest score
+pub fn heat_color(score: f64, max: f64) -> (u8, u8, u8) {
+    const STOPS: [(u8, u8, u8); 3] = [(255, 255, 204), (253, 141, 60), (189, 0, 38)];
+    let t = match max > 0.0 {
+        true => (score / max).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+    let (low, high, t) = match t < 0.5 {
+        true => (STOPS[0], STOPS[1], t * 2.0),
+        false => (STOPS[1], STOPS[2], (t - 0.5) * 2.0),
+    };
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    (mix(low.0, high.0), mix(low.1, high.1), mix(low.2, high.2))
+}
+
+/// Color of nodes without a score, such as the static nodes
+pub const NO_SCORE_COLOR: (u8, u8, u8) = (200, 200, 200);
+
+/// What is drawn on top of the layout: the score of every node and the weight of every edge
+pub struct Heatmap<'a> {
+    pub graph: &'a Graph,
+    pub layout: Layout,
+    pub scores: NodeValueMap<f64>,
+    /// Edge weights, usually the alpha weights, that set the thickness of the edges
+    pub weights: Option<&'a EdgeValueMap<f32>>,
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(graph: &'a Graph, scores: NodeValueMap<f64>, weights: Option<&'a EdgeValueMap<f32>>) -> Heatmap<'a> {
+        Heatmap { graph, layout: Layout::layered(graph), scores, weights }
+    }
+
+    /// Heatmap of the criticality of every node in the 'results'
+    pub fn criticality(graph: &'a Graph, results: &CriticalityResults, weights: Option<&'a EdgeValueMap<f32>>) -> Heatmap<'a> {
+        let scores = results.nodes.iter().map(|(id, node)| (*id, node.criticality)).collect();
+        Heatmap::new(graph, scores, weights)
+    }
+
+    /// Fill color of the node 'id'
+    pub fn node_color(&self, id: &u32) -> (u8, u8, u8) {
+        let max = self.scores.values().fold(0.0, |m: f64, s| m.max(*s));
+        match self.scores.get(id) {
+            Some(score) => heat_color(*score, max),
+            None => NO_SCORE_COLOR,
+        }
+    }
+
+    /// Line width of the edge, between 1 and 6 in proportion to its weight
+    pub fn edge_width(&self, from: u32, to: u32) -> f64 {
+        let weights = match self.weights {
+            None => return 1.5,
+            Some(w) => w,
+        };
+        let max = weights.values().fold(0.0, |m: f32, w| m.max(w.abs()));
+        match (weights.get(&(from, to)), max > 0.0) {
+            (Some(weight), true) => 1.0 + 5.0 * (weight.abs() / max) as f64,
+            _ => 1.0,
+        }
+    }
+
+    /// Edges sorted by their nodes, so drawings are always the same
+    pub fn edges(&self) -> Vec<(u32, u32)> {
+        let mut edges: Vec<(u32, u32)> = self.graph.get_edges().iter().map(|e| (e.from, e.to)).collect();
+        edges.sort_unstable();
+        edges
+    }
+}
@@ -0,0 +1,131 @@
+//! Drawing a ['Heatmap'] as a PNG image.
+//!
+//! The image is rasterized without text, so nodes are only told apart by their position and
+//! color; use the SVG drawing when the names are needed. The image data is stored uncompressed.
+
+use crate::checksum::{adler32, crc32};
+use crate::render::{Heatmap, NODE_RADIUS};
+
+const BACKGROUND: (u8, u8, u8) = (255, 255, 255);
+const EDGE_COLOR: (u8, u8, u8) = (110, 110, 110);
+const BORDER_COLOR: (u8, u8, u8) = (51, 51, 51);
+/// Largest block of a stored deflate stream
+const MAX_STORED_BLOCK: usize = 65_535;
+
+/// RGB image drawn pixel by pixel
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Canvas {
+        let pixels = [BACKGROUND.0, BACKGROUND.1, BACKGROUND.2].repeat(width * height);
+        Canvas { width, height, pixels }
+    }
+
+    fn set(&mut self, x: i64, y: i64, (r, g, b): (u8, u8, u8)) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let i = 3 * (y as usize * self.width + x as usize);
+        self.pixels[i..i + 3].copy_from_slice(&[r, g, b]);
+    }
+
+    /// Colors every pixel whose center is within 'width / 2' of the segment
+    fn line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64), width: f64, color: (u8, u8, u8)) {
+        let half = width / 2.0;
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length = dx * dx + dy * dy;
+        for y in (y1.min(y2) - half).floor() as i64..=(y1.max(y2) + half).ceil() as i64 {
+            for x in (x1.min(x2) - half).floor() as i64..=(x1.max(x2) + half).ceil() as i64 {
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+                let t = match length > 0.0 {
+                    true => (((px - x1) * dx + (py - y1) * dy) / length).clamp(0.0, 1.0),
+                    false => 0.0,
+                };
+                let (cx, cy) = (x1 + t * dx - px, y1 + t * dy - py);
+                if cx * cx + cy * cy <= half * half {
+                    self.set(x, y, color);
+                }
+            }
+        }
+    }
+
+    fn circle(&mut self, (cx, cy): (f64, f64), radius: f64, fill: (u8, u8, u8), border: (u8, u8, u8)) {
+        for y in (cy - radius).floor() as i64..=(cy + radius).ceil() as i64 {
+            for x in (cx - radius).floor() as i64..=(cx + radius).ceil() as i64 {
+                let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance <= radius - 1.5 {
+                    self.set(x, y, fill);
+                } else if distance <= radius {
+                    self.set(x, y, border);
+                }
+            }
+        }
+    }
+}
+
+/// The PNG image of the heatmap
+pub fn render_png(heatmap: &Heatmap) -> Vec<u8> {
+    let layout = &heatmap.layout;
+    let mut canvas = Canvas::new(layout.width.ceil() as usize, layout.height.ceil() as usize);
+    for (from, to) in heatmap.edges() {
+        if let (Some(a), Some(b)) = (layout.positions.get(&from), layout.positions.get(&to)) {
+            canvas.line(*a, *b, heatmap.edge_width(from, to), EDGE_COLOR);
+        }
+    }
+    for (id, position) in layout.positions.iter() {
+        canvas.circle(*position, NODE_RADIUS, heatmap.node_color(id), BORDER_COLOR);
+    }
+    encode_png(&canvas)
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Zlib stream holding 'data' in stored deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = match data.is_empty() {
+        true => vec![&[]],
+        false => data.chunks(MAX_STORED_BLOCK).collect(),
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        out.push((i + 1 == blocks.len()) as u8);
+        let length = block.len() as u16;
+        out.extend_from_slice(&length.to_le_bytes());
+        out.extend_from_slice(&(!length).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn encode_png(canvas: &Canvas) -> Vec<u8> {
+    let mut out = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    let mut header = vec![];
+    header.extend_from_slice(&(canvas.width as u32).to_be_bytes());
+    header.extend_from_slice(&(canvas.height as u32).to_be_bytes());
+    // 8 bit RGB, default compression and filtering, no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    chunk(&mut out, b"IHDR", &header);
+
+    // Every row starts with filter type 0 (none)
+    let mut raw = Vec::with_capacity(canvas.height * (3 * canvas.width + 1));
+    for row in canvas.pixels.chunks(3 * canvas.width) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
@@ -0,0 +1,65 @@
+//! Drawing a ['Heatmap'] as SVG.
+
+use std::fmt::Write as _;
+use crate::render::{heat_color, Heatmap, NODE_RADIUS};
+use crate::xml::escape;
+
+/// Height of the color legend below the drawing
+const LEGEND_HEIGHT: f64 = 40.0;
+
+/// Css notation of a color, as in '#bd0026'
+pub(crate) fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// The SVG document of the heatmap. Nodes show their name, their id and score are shown as a
+/// tooltip. Edges end in an arrow on the border of their target.
+pub fn render_svg(heatmap: &Heatmap) -> String {
+    let layout = &heatmap.layout;
+    let height = layout.height + LEGEND_HEIGHT;
+    let mut out = String::new();
+    writeln!(out, r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"##,
+             w = layout.width, h = height).unwrap();
+    out.push_str(r##"<defs><marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" orient="auto-start-reverse"><path d="M 0 0 L 10 5 L 0 10 z" fill="#555"/></marker></defs>"##);
+    out.push('\n');
+    out.push_str("<g class=\"edges\" stroke=\"#555\" stroke-opacity=\"0.7\">\n");
+    for (from, to) in heatmap.edges() {
+        let (Some(a), Some(b)) = (layout.positions.get(&from), layout.positions.get(&to)) else { continue };
+        // Edges are drawn from the border of one circle to the border of the other
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= 2.0 * NODE_RADIUS {
+            continue;
+        }
+        let (ux, uy) = (dx / length * NODE_RADIUS, dy / length * NODE_RADIUS);
+        writeln!(out, r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke-width="{:.2}" marker-end="url(#arrow)"><title>{} → {}</title></line>"##,
+                 a.0 + ux, a.1 + uy, b.0 - ux, b.1 - uy, heatmap.edge_width(from, to), from, to).unwrap();
+    }
+    out.push_str("</g>\n<g class=\"nodes\" stroke=\"#333\">\n");
+    for (id, (x, y)) in layout.positions.iter() {
+        let name = heatmap.graph.get_node(id).map(|n| n.name.as_str()).unwrap_or("");
+        let tooltip = match heatmap.scores.get(id) {
+            Some(score) => format!("{} ({}): {}", name, id, score),
+            None => format!("{} ({})", name, id),
+        };
+        writeln!(out, r##"<g><title>{}</title><circle cx="{:.1}" cy="{:.1}" r="{}" fill="{}"/><text x="{:.1}" y="{:.1}" text-anchor="middle" stroke="none">{}</text></g>"##,
+                 escape(&tooltip), x, y, NODE_RADIUS, hex(heatmap.node_color(id)), x, y + NODE_RADIUS + 12.0, escape(name)).unwrap();
+    }
+    out.push_str("</g>\n");
+    write_legend(&mut out, heatmap, layout.height);
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Gradient from the lowest to the highest score below the drawing
+fn write_legend(out: &mut String, heatmap: &Heatmap, top: f64) {
+    let max = heatmap.scores.values().fold(0.0, |m: f64, s| m.max(*s));
+    out.push_str("<defs><linearGradient id=\"heat\">");
+    for stop in 0..=4 {
+        let t = stop as f64 / 4.0;
+        write!(out, r##"<stop offset="{}" stop-color="{}"/>"##, t, hex(heat_color(t, 1.0))).unwrap();
+    }
+    out.push_str("</linearGradient></defs>\n");
+    writeln!(out, r##"<rect x="10" y="{:.1}" width="120" height="10" fill="url(#heat)" stroke="#333"/><text x="10" y="{:.1}">0</text><text x="130" y="{:.1}" text-anchor="end">{:.4}</text>"##,
+             top + 4.0, top + 28.0, top + 28.0, max).unwrap();
+}
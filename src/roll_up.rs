@@ -1,34 +1,55 @@
+use std::collections::HashMap;
 use dyn_clone::DynClone;
 use crate::analyses::VISIBLE_VAL;
-use crate::network::{NodeValueMap};
+use crate::json::JsonValue;
+use crate::network::{EdgeValueMap, NodeValues};
 
 const MAX_OPERABILITY: f32 = 1.0;
 const MIN_OPERABILITY: f32 = 0.0;
 
+/// Boolean function of the children computed by a rule when every value is either the maximum
+/// or the minimum operability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanGate {
+    Or,
+    And,
+    /// True if at least the given number of children are
+    AtLeast(usize),
+}
+
 pub trait RollUp : DynClone + Send {
-    fn get_value(&self, t_id: &u32, children: &Vec<u32>, visibilities: &NodeValueMap<u8>, values: &NodeValueMap<f32>) -> f32 {
+    /// The boolean gate the rule computes for the node 't_id', if any. Exact analyses can only be
+    /// computed for rules that have one.
+    fn boolean_gate(&self, _t_id: &u32) -> Option<BooleanGate> {
+        None
+    }
+
+    fn get_value(&self, t_id: &u32, children: &[u32], visibilities: &dyn NodeValues<u8>, values: &dyn NodeValues<f32>) -> f32 {
         if children.is_empty() {
             return MAX_OPERABILITY;
         }
-        let t_visible = visibilities.get(t_id);
-        return match t_visible {
+        let t_visible = visibilities.value(t_id);
+        match t_visible {
             None => { self.compute_val(t_id, children, values) }
             Some(x) => {
                 if *x == VISIBLE_VAL { self.compute_val(t_id, children, values) } else { MIN_OPERABILITY }
             }
         }
     }
-    fn compute_val(&self, t_id: &u32, children: &Vec<u32>, values: &NodeValueMap<f32>) -> f32;
+    fn compute_val(&self, t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32;
 }
 
 #[derive(Clone)]
 pub struct OrRule {}
 
 impl RollUp for OrRule {
-    fn compute_val(&self, _t_id: &u32, children: &Vec<u32>, values: &NodeValueMap<f32>) -> f32 {
+    fn boolean_gate(&self, _t_id: &u32) -> Option<BooleanGate> {
+        Some(BooleanGate::Or)
+    }
+    fn compute_val(&self, _t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
         let mut max = MIN_OPERABILITY;
         for child in children {
-            match values.get(child) {
+            match values.value(child) {
                 None => {
                     return MAX_OPERABILITY
                 }
@@ -39,6 +60,237 @@ impl RollUp for OrRule {
                 }
             }
         }
-        return max
+        max
+    }
+}
+/// Operable only if every child is, the value is the minimum over the children
+#[derive(Clone)]
+pub struct AndRule {}
+
+impl RollUp for AndRule {
+    fn boolean_gate(&self, _t_id: &u32) -> Option<BooleanGate> {
+        Some(BooleanGate::And)
+    }
+
+    fn compute_val(&self, _t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        // Children that aren't rolled up yet count as operable, like in the ['OrRule']
+        children.iter()
+            .filter_map(|child| values.value(child))
+            .fold(MAX_OPERABILITY, |min, val| min.min(*val))
+    }
+}
+
+/// Operable if at least 'min' children are, the value is the 'min'-th largest child value
+#[derive(Clone)]
+pub struct AtLeastRule {
+    pub min: usize,
+}
+
+impl RollUp for AtLeastRule {
+    fn boolean_gate(&self, _t_id: &u32) -> Option<BooleanGate> {
+        Some(BooleanGate::AtLeast(self.min))
+    }
+
+    fn compute_val(&self, _t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        if self.min == 0 {
+            return MAX_OPERABILITY;
+        }
+        let mut child_values: Vec<f32> = children.iter()
+            .map(|child| *values.value(child).unwrap_or(&MAX_OPERABILITY))
+            .collect();
+        child_values.sort_by(|a, b| b.total_cmp(a));
+        *child_values.get(self.min - 1).unwrap_or(&MIN_OPERABILITY)
+    }
+}
+
+/// Uses a different rule for some nodes, every other node uses the 'default' rule
+pub struct NodeRules {
+    pub rules: HashMap<u32, Box<dyn RollUp>>,
+    pub default: Box<dyn RollUp>,
+}
+
+impl Clone for NodeRules {
+    fn clone(&self) -> Self {
+        NodeRules {
+            rules: self.rules.iter().map(|(id, rule)| (*id, dyn_clone::clone_box(&**rule))).collect(),
+            default: dyn_clone::clone_box(&*self.default),
+        }
+    }
+}
+
+impl NodeRules {
+    fn rule(&self, t_id: &u32) -> &dyn RollUp {
+        self.rules.get(t_id).unwrap_or(&self.default).as_ref()
+    }
+}
+
+impl RollUp for NodeRules {
+    fn boolean_gate(&self, t_id: &u32) -> Option<BooleanGate> {
+        self.rule(t_id).boolean_gate(t_id)
+    }
+
+    fn compute_val(&self, t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        self.rule(t_id).compute_val(t_id, children, values)
+    }
+}
+
+/// Fully operable once the sum of the child values reaches the 'threshold', scaled linearly below
+/// it. Models capacity like redundancy, e.g. 3 of 5 pumps giving enough flow is a threshold of 3.
+#[derive(Clone)]
+pub struct ThresholdRule {
+    pub threshold: f32,
+}
+
+impl RollUp for ThresholdRule {
+    fn compute_val(&self, _t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        if self.threshold <= 0.0 {
+            return MAX_OPERABILITY;
+        }
+        // Children that aren't rolled up yet count as operable, like in the ['OrRule']
+        let sum: f32 = children.iter().map(|child| *values.value(child).unwrap_or(&MAX_OPERABILITY)).sum();
+        (sum / self.threshold).min(MAX_OPERABILITY)
+    }
+}
+
+/// Every child independently covers the node with the chance 'alpha' of the edge from the child
+/// times the value of the child, the value is the chance that at least one child covers the node:
+/// 1 - (1 - a1 * v1) * (1 - a2 * v2) * ... Edges without an alpha weight always cover the node,
+/// with every weight one the rule computes the ['OrRule'] on boolean values.
+#[derive(Clone)]
+pub struct NoisyOrRule {
+    /// Alpha weight of the edges (child, node)
+    pub alpha: EdgeValueMap<f32>,
+}
+
+impl RollUp for NoisyOrRule {
+    fn compute_val(&self, t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        let uncovered: f32 = children.iter()
+            .map(|child| {
+                let alpha = self.alpha.get(&(*child, *t_id)).unwrap_or(&MAX_OPERABILITY).clamp(MIN_OPERABILITY, MAX_OPERABILITY);
+                // Children that aren't rolled up yet count as operable, like in the ['OrRule']
+                1.0 - alpha * values.value(child).unwrap_or(&MAX_OPERABILITY)
+            })
+            .product();
+        MAX_OPERABILITY - uncovered
+    }
+}
+
+/// Weighted sum of the values of several rules, e.g. 0.7 times the ['AndRule'] plus 0.3 times
+/// the ['OrRule']. The weights should add up to one to keep the value an operability.
+pub struct WeightedRule {
+    pub rules: Vec<(f32, Box<dyn RollUp>)>,
+}
+
+impl Clone for WeightedRule {
+    fn clone(&self) -> Self {
+        WeightedRule { rules: self.rules.iter().map(|(weight, rule)| (*weight, dyn_clone::clone_box(&**rule))).collect() }
+    }
+}
+
+impl RollUp for WeightedRule {
+    fn compute_val(&self, t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        self.rules.iter()
+            .map(|(weight, rule)| weight * rule.compute_val(t_id, children, values))
+            .sum()
+    }
+}
+
+/// The smallest value of several rules
+pub struct MinRule {
+    pub rules: Vec<Box<dyn RollUp>>,
+}
+
+impl Clone for MinRule {
+    fn clone(&self) -> Self {
+        MinRule { rules: self.rules.iter().map(|rule| dyn_clone::clone_box(&**rule)).collect() }
     }
-}
\ No newline at end of file
+}
+
+impl RollUp for MinRule {
+    fn compute_val(&self, t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        self.rules.iter()
+            .map(|rule| rule.compute_val(t_id, children, values))
+            .fold(MAX_OPERABILITY, f32::min)
+    }
+}
+
+/// The largest value of several rules
+pub struct MaxRule {
+    pub rules: Vec<Box<dyn RollUp>>,
+}
+
+impl Clone for MaxRule {
+    fn clone(&self) -> Self {
+        MaxRule { rules: self.rules.iter().map(|rule| dyn_clone::clone_box(&**rule)).collect() }
+    }
+}
+
+impl RollUp for MaxRule {
+    fn compute_val(&self, t_id: &u32, children: &[u32], values: &dyn NodeValues<f32>) -> f32 {
+        self.rules.iter()
+            .map(|rule| rule.compute_val(t_id, children, values))
+            .fold(MIN_OPERABILITY, f32::max)
+    }
+}
+
+/// Builds a rule from its json declaration. A rule is either the name "or", "and" or "noisy_or",
+/// which covers with the 'alpha' weights of the edges, or an object with a single key:
+///
+/// ```json
+/// { "at_least": 2 }
+/// { "threshold": 2.5 }
+/// { "weighted": [[0.7, "and"], [0.3, "or"]] }
+/// { "min": ["and", { "at_least": 2 }] }
+/// { "max": ["and", "or"] }
+/// ```
+///
+/// # Errors
+///
+/// Returns the reason if the declaration is not a known rule
+pub fn rule_from_json(declaration: &JsonValue, alpha: &EdgeValueMap<f32>) -> Result<Box<dyn RollUp>, String> {
+    if let Some(name) = declaration.as_str() {
+        return match name {
+            "or" => Ok(Box::new(OrRule {})),
+            "and" => Ok(Box::new(AndRule {})),
+            "noisy_or" => Ok(Box::new(NoisyOrRule { alpha: alpha.clone() })),
+            _ => Err(format!("unknown roll up rule '{}'", name)),
+        };
+    }
+    let (name, value) = match declaration.as_object().map(|entries| entries.as_slice()) {
+        Some([(name, value)]) => (name.as_str(), value),
+        _ => return Err("a roll up rule must be a name or an object with a single key".to_string()),
+    };
+    let rules = |value: &JsonValue| -> Result<Vec<Box<dyn RollUp>>, String> {
+        value.as_array()
+            .ok_or(format!("'{}' must be a list of rules", name))?
+            .iter()
+            .map(|rule| rule_from_json(rule, alpha))
+            .collect()
+    };
+    match name {
+        "at_least" => {
+            let min = value.as_u64().ok_or("'at_least' must be a whole number".to_string())?;
+            Ok(Box::new(AtLeastRule { min: min as usize }))
+        }
+        "threshold" => {
+            let threshold = value.as_f64().ok_or("'threshold' must be a number".to_string())?;
+            Ok(Box::new(ThresholdRule { threshold: threshold as f32 }))
+        }
+        "weighted" => {
+            let terms = value.as_array().ok_or("'weighted' must be a list of [weight, rule] pairs".to_string())?;
+            let rules = terms.iter()
+                .map(|term| match term.as_array().map(|pair| pair.as_slice()) {
+                    Some([weight, rule]) => {
+                        let weight = weight.as_f64().ok_or("the weight of a rule must be a number".to_string())?;
+                        Ok((weight as f32, rule_from_json(rule, alpha)?))
+                    }
+                    _ => Err("'weighted' must be a list of [weight, rule] pairs".to_string()),
+                })
+                .collect::<Result<_, String>>()?;
+            Ok(Box::new(WeightedRule { rules }))
+        }
+        "min" => Ok(Box::new(MinRule { rules: rules(value)? })),
+        "max" => Ok(Box::new(MaxRule { rules: rules(value)? })),
+        _ => Err(format!("unknown roll up rule '{}'", name)),
+    }
+}
@@ -0,0 +1,571 @@
+//! A serde data format backed by ['JsonValue'], so every serializable type can be written as and
+//! read from json without a separate json library.
+//!
+//! Map keys are written as strings. Numeric keys are parsed back from their string and tuple
+//! keys, such as the (from, to) keys of edge value maps, are joined with a comma.
+
+use std::fmt::Display;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{self, Impossible, Serialize};
+use serde::Deserializer;
+use crate::errors::json::JsonSerdeError;
+use crate::json;
+use crate::json::JsonValue;
+
+impl ser::Error for JsonSerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JsonSerdeError { reason: msg.to_string() }
+    }
+}
+
+impl de::Error for JsonSerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JsonSerdeError { reason: msg.to_string() }
+    }
+}
+
+/// Converts 'value' to a json value
+///
+/// # Errors
+///
+/// Returns a ['JsonSerdeError'] if the value can't be represented in json
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<JsonValue, JsonSerdeError> {
+    value.serialize(ValueSerializer)
+}
+
+/// Converts 'value' to a compact json string
+///
+/// # Errors
+///
+/// Returns a ['JsonSerdeError'] if the value can't be represented in json
+pub fn to_json_string<T: Serialize + ?Sized>(value: &T) -> Result<String, JsonSerdeError> {
+    Ok(to_json(value)?.to_string())
+}
+
+/// Reads a 'T' from a json value
+///
+/// # Errors
+///
+/// Returns a ['JsonSerdeError'] if the json doesn't describe a 'T'
+pub fn from_json<T: DeserializeOwned>(value: JsonValue) -> Result<T, JsonSerdeError> {
+    T::deserialize(value)
+}
+
+/// Parses a json string and reads a 'T' from it
+///
+/// # Errors
+///
+/// Returns a ['JsonSerdeError'] if the text is not json or doesn't describe a 'T'
+pub fn from_json_str<T: DeserializeOwned>(text: &str) -> Result<T, JsonSerdeError> {
+    let value = json::parse(text).map_err(|e| JsonSerdeError { reason: e.to_string() })?;
+    from_json(value)
+}
+
+fn number<T: Into<f64>>(n: T) -> JsonValue {
+    JsonValue::Number(n.into())
+}
+
+/// Serializes values into ['JsonValue']s. Enums use the external tagging of serde: unit variants
+/// are strings, other variants are objects with the variant name as their only key.
+pub struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::Bool(v)) }
+    fn serialize_i8(self, v: i8) -> Result<JsonValue, JsonSerdeError> { Ok(number(v)) }
+    fn serialize_i16(self, v: i16) -> Result<JsonValue, JsonSerdeError> { Ok(number(v)) }
+    fn serialize_i32(self, v: i32) -> Result<JsonValue, JsonSerdeError> { Ok(number(v)) }
+    fn serialize_i64(self, v: i64) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::Number(v as f64)) }
+    fn serialize_u8(self, v: u8) -> Result<JsonValue, JsonSerdeError> { Ok(number(v)) }
+    fn serialize_u16(self, v: u16) -> Result<JsonValue, JsonSerdeError> { Ok(number(v)) }
+    fn serialize_u32(self, v: u32) -> Result<JsonValue, JsonSerdeError> { Ok(number(v)) }
+    fn serialize_u64(self, v: u64) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::Number(v as f64)) }
+    // Widened through the shortest decimal form, so 0.1 is written as 0.1 and not 0.10000000149011612
+    fn serialize_f32(self, v: f32) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Number(v.to_string().parse::<f64>().unwrap_or(v as f64)))
+    }
+    fn serialize_f64(self, v: f64) -> Result<JsonValue, JsonSerdeError> { Ok(number(v)) }
+    fn serialize_char(self, v: char) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::String(v.to_string())) }
+    fn serialize_str(self, v: &str) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::String(v.to_string())) }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Array(v.iter().map(|b| number(*b)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::Null) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<JsonValue, JsonSerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::Null) }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<JsonValue, JsonSerdeError> { Ok(JsonValue::Null) }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<JsonValue, JsonSerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Object(vec![(variant.to_string(), value.serialize(self)?)]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, JsonSerdeError> {
+        Ok(SeqSerializer { items: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, JsonSerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer, JsonSerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<VariantSerializer<SeqSerializer>, JsonSerdeError> {
+        Ok(VariantSerializer { variant, inner: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer, JsonSerdeError> {
+        Ok(MapSerializer { entries: vec![], key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer, JsonSerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<VariantSerializer<MapSerializer>, JsonSerdeError> {
+        Ok(VariantSerializer { variant, inner: self.serialize_map(Some(len))? })
+    }
+}
+
+pub struct SeqSerializer {
+    items: Vec<JsonValue>,
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonSerdeError> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Array(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonSerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonSerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonSerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonSerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct MapSerializer {
+    entries: Vec<(String, JsonValue)>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), JsonSerdeError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonSerdeError> {
+        let key = self.key.take().ok_or_else(|| JsonSerdeError { reason: "a map value was written before its key".to_string() })?;
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Object(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), JsonSerdeError> {
+        self.entries.push((key.to_string(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Object(self.entries))
+    }
+}
+
+/// Wraps the value of a tuple or struct variant in an object keyed by the variant name
+pub struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonSerdeError> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Object(vec![(self.variant.to_string(), ser::SerializeSeq::end(self.inner)?)]))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = JsonValue;
+    type Error = JsonSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), JsonSerdeError> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonSerdeError> {
+        Ok(JsonValue::Object(vec![(self.variant.to_string(), ser::SerializeStruct::end(self.inner)?)]))
+    }
+}
+
+/// Writes map keys as strings
+struct KeySerializer;
+
+fn key_error() -> JsonSerdeError {
+    JsonSerdeError { reason: "map keys must be strings, numbers or tuples of them".to_string() }
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = JsonSerdeError;
+    type SerializeSeq = Impossible<String, JsonSerdeError>;
+    type SerializeTuple = TupleKeySerializer;
+    type SerializeTupleStruct = Impossible<String, JsonSerdeError>;
+    type SerializeTupleVariant = Impossible<String, JsonSerdeError>;
+    type SerializeMap = Impossible<String, JsonSerdeError>;
+    type SerializeStruct = Impossible<String, JsonSerdeError>;
+    type SerializeStructVariant = Impossible<String, JsonSerdeError>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_i8(self, v: i8) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_i16(self, v: i16) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_i32(self, v: i32) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_i64(self, v: i64) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_u8(self, v: u8) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_u16(self, v: u16) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_u32(self, v: u32) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_u64(self, v: u64) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_f32(self, v: f32) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_f64(self, v: f64) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_char(self, v: char) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_str(self, v: &str) -> Result<String, JsonSerdeError> { Ok(v.to_string()) }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, JsonSerdeError> { Err(key_error()) }
+    fn serialize_none(self) -> Result<String, JsonSerdeError> { Err(key_error()) }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, JsonSerdeError> { value.serialize(self) }
+    fn serialize_unit(self) -> Result<String, JsonSerdeError> { Err(key_error()) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, JsonSerdeError> { Err(key_error()) }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<String, JsonSerdeError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<String, JsonSerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<String, JsonSerdeError> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, JsonSerdeError> { Err(key_error()) }
+
+    fn serialize_tuple(self, _len: usize) -> Result<TupleKeySerializer, JsonSerdeError> {
+        Ok(TupleKeySerializer { parts: vec![] })
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, JsonSerdeError> { Err(key_error()) }
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, JsonSerdeError> { Err(key_error()) }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, JsonSerdeError> { Err(key_error()) }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, JsonSerdeError> { Err(key_error()) }
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, JsonSerdeError> { Err(key_error()) }
+}
+
+struct TupleKeySerializer {
+    parts: Vec<String>,
+}
+
+impl ser::SerializeTuple for TupleKeySerializer {
+    type Ok = String;
+    type Error = JsonSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonSerdeError> {
+        self.parts.push(value.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<String, JsonSerdeError> {
+        Ok(self.parts.join(","))
+    }
+}
+
+fn type_error(expected: &str, value: &JsonValue) -> JsonSerdeError {
+    JsonSerdeError { reason: format!("expected {}, got {}", expected, value) }
+}
+
+impl<'de> IntoDeserializer<'de, JsonSerdeError> for JsonValue {
+    type Deserializer = JsonValue;
+
+    fn into_deserializer(self) -> JsonValue {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for JsonValue {
+    type Error = JsonSerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        match self {
+            JsonValue::Null => visitor.visit_unit(),
+            JsonValue::Bool(b) => visitor.visit_bool(b),
+            // Whole numbers are handed over as integers so they can be read into integer types
+            JsonValue::Number(n) if n.fract() == 0.0 && n >= 0.0 && n < u64::MAX as f64 => visitor.visit_u64(n as u64),
+            JsonValue::Number(n) if n.fract() == 0.0 && n < 0.0 && n >= i64::MIN as f64 => visitor.visit_i64(n as i64),
+            JsonValue::Number(n) => visitor.visit_f64(n),
+            JsonValue::String(s) => visitor.visit_string(s),
+            JsonValue::Array(items) => visitor.visit_seq(SeqReader { items: items.into_iter() }),
+            JsonValue::Object(entries) => visitor.visit_map(MapReader { entries: entries.into_iter(), value: None }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        match self {
+            JsonValue::Null => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    // Json has no infinite numbers, they are written as null, see ['JsonValue']
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        match self {
+            JsonValue::Null => visitor.visit_f32(f32::INFINITY),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        match self {
+            JsonValue::Null => visitor.visit_f64(f64::INFINITY),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, JsonSerdeError> {
+        match self {
+            JsonValue::String(variant) => visitor.visit_enum(EnumReader { variant, value: None }),
+            JsonValue::Object(mut entries) if entries.len() == 1 => {
+                let (variant, value) = entries.pop().unwrap();
+                visitor.visit_enum(EnumReader { variant, value: Some(value) })
+            }
+            other => Err(type_error("an enum variant", &other)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct SeqReader {
+    items: std::vec::IntoIter<JsonValue>,
+}
+
+impl<'de> SeqAccess<'de> for SeqReader {
+    type Error = JsonSerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, JsonSerdeError> {
+        match self.items.next() {
+            Some(item) => seed.deserialize(item).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct MapReader {
+    entries: std::vec::IntoIter<(String, JsonValue)>,
+    value: Option<JsonValue>,
+}
+
+impl<'de> MapAccess<'de> for MapReader {
+    type Error = JsonSerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, JsonSerdeError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer { key }).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, JsonSerdeError> {
+        let value = self.value.take().ok_or_else(|| JsonSerdeError { reason: "a map value was read before its key".to_string() })?;
+        seed.deserialize(value)
+    }
+}
+
+/// Reads map keys, which are always strings in json, as strings, numbers or tuples
+struct KeyDeserializer {
+    key: String,
+}
+
+impl KeyDeserializer {
+    fn parse<T: std::str::FromStr>(&self) -> Result<T, JsonSerdeError> {
+        self.key.trim().parse::<T>().map_err(|_| JsonSerdeError { reason: format!("the key '{}' is not a number", self.key) })
+    }
+}
+
+impl<'de> Deserializer<'de> for KeyDeserializer {
+    type Error = JsonSerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        visitor.visit_string(self.key)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_bool(self.parse()?) }
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_i8(self.parse()?) }
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_i16(self.parse()?) }
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_i32(self.parse()?) }
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_i64(self.parse()?) }
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_u8(self.parse()?) }
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_u16(self.parse()?) }
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_u32(self.parse()?) }
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_u64(self.parse()?) }
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_f32(self.parse()?) }
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonSerdeError> { visitor.visit_f64(self.parse()?) }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        let parts: Vec<JsonValue> = self.key.split(',').map(|part| JsonValue::String(part.to_string())).collect();
+        visitor.visit_seq(KeySeqReader { parts: parts.into_iter() })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, JsonSerdeError> {
+        visitor.visit_enum(EnumReader { variant: self.key, value: None })
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf option unit unit_struct seq tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// The parts of a tuple key, every part is read like a key of its own
+struct KeySeqReader {
+    parts: std::vec::IntoIter<JsonValue>,
+}
+
+impl<'de> SeqAccess<'de> for KeySeqReader {
+    type Error = JsonSerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, JsonSerdeError> {
+        match self.parts.next() {
+            Some(JsonValue::String(key)) => seed.deserialize(KeyDeserializer { key }).map(Some),
+            Some(other) => Err(type_error("a key", &other)),
+            None => Ok(None),
+        }
+    }
+}
+
+struct EnumReader {
+    variant: String,
+    value: Option<JsonValue>,
+}
+
+impl<'de> EnumAccess<'de> for EnumReader {
+    type Error = JsonSerdeError;
+    type Variant = VariantReader;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, VariantReader), JsonSerdeError> {
+        let variant = seed.deserialize(JsonValue::String(self.variant))?;
+        Ok((variant, VariantReader { value: self.value }))
+    }
+}
+
+struct VariantReader {
+    value: Option<JsonValue>,
+}
+
+impl<'de> VariantAccess<'de> for VariantReader {
+    type Error = JsonSerdeError;
+
+    fn unit_variant(self) -> Result<(), JsonSerdeError> {
+        match self.value {
+            None | Some(JsonValue::Null) => Ok(()),
+            Some(other) => Err(type_error("a unit variant", &other)),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, JsonSerdeError> {
+        seed.deserialize(self.value.unwrap_or(JsonValue::Null))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, JsonSerdeError> {
+        self.value.unwrap_or(JsonValue::Null).deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, JsonSerdeError> {
+        self.value.unwrap_or(JsonValue::Null).deserialize_any(visitor)
+    }
+}
@@ -0,0 +1,275 @@
+//! Serde support for the graph and the results of the analyses, enabled by the 'serde' feature.
+//!
+//! The implementations are written by hand so no derive macros are needed. Graphs are written as
+//! sorted lists of nodes, edges and static node ids, so the same graph always serializes the same
+//! way. See ['json'] for reading and writing the values as json.
+
+use std::fmt;
+use std::fmt::Formatter;
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::analyses::criticality::{CriticalityData, CriticalityResults, NodeCritResult};
+use crate::analyses::criticality::histogram::Histogram;
+use crate::analyses::criticality::mission_time::MissionTimeResults;
+use crate::analyses::criticality::pairwise::PairwiseResults;
+use crate::analyses::event_tree::{ConsequenceResults, EventTree, Outcome, Sequence};
+use crate::analyses::scenario::{Scenario, ScenarioResult};
+use crate::analyses::shortest_path::{PathDegradation, ShortestPathResults};
+use crate::network::{AttrValue, Edge, Graph, Node};
+use crate::output::json::StoredResults;
+
+pub mod json;
+
+/// Implements Serialize and Deserialize for a struct with public fields. Structs are written as
+/// maps of their named fields, formats without field names may read them as sequences. Fields
+/// listed after 'optional' are written, but set to their default if they are missing, so values
+/// written before the field was added can still be read. Fields listed after 'skip' are neither
+/// written nor read, they are set to their default.
+macro_rules! serde_struct {
+    ($ty:ident { $($field:ident),+ $(,)? } $(optional { $($optional:ident),+ $(,)? })? $(skip { $($skipped:ident),+ $(,)? })?) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut state = serializer.serialize_struct(stringify!($ty), [$(stringify!($field)),+ $($(, stringify!($optional))+)?].len())?;
+                $(state.serialize_field(stringify!($field), &self.$field)?;)+
+                $($(state.serialize_field(stringify!($optional), &self.$optional)?;)+)?
+                state.end()
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct StructVisitor;
+
+                impl<'de> Visitor<'de> for StructVisitor {
+                    type Value = $ty;
+
+                    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                        write!(f, "a {}", stringify!($ty))
+                    }
+
+                    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<$ty, A::Error> {
+                        $(let mut $field = None;)+
+                        $($(let mut $optional = None;)+)?
+                        while let Some(key) = map.next_key::<String>()? {
+                            match key.as_str() {
+                                $(stringify!($field) => $field = Some(map.next_value()?),)+
+                                $($(stringify!($optional) => $optional = Some(map.next_value()?),)+)?
+                                // Unknown fields are skipped so newer files can still be read
+                                _ => { map.next_value::<IgnoredAny>()?; }
+                            }
+                        }
+                        Ok($ty {
+                            $($field: $field.ok_or_else(|| de::Error::missing_field(stringify!($field)))?,)+
+                            $($($optional: $optional.unwrap_or_default(),)+)?
+                            $($($skipped: Default::default(),)+)?
+                        })
+                    }
+
+                    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<$ty, A::Error> {
+                        Ok($ty {
+                            $($field: seq.next_element()?.ok_or_else(|| de::Error::missing_field(stringify!($field)))?,)+
+                            $($($optional: seq.next_element()?.unwrap_or_default(),)+)?
+                            $($($skipped: Default::default(),)+)?
+                        })
+                    }
+                }
+
+                deserializer.deserialize_struct(stringify!($ty), &[$(stringify!($field)),+ $($(, stringify!($optional))+)?], StructVisitor)
+            }
+        }
+    };
+}
+
+serde_struct!(Node { name, id, attributes });
+serde_struct!(Edge { from, to });
+serde_struct!(CriticalityData { edge_attributes, off_chances } skip { warnings });
+serde_struct!(CriticalityResults { row_count, end_op_mean, nodes } optional { end_op_histogram });
+serde_struct!(Histogram { counts, bounds, below, above, min, max });
+serde_struct!(NodeCritResult { on_count, off_count, mean_end_on, mean_end_off, criticality });
+serde_struct!(PairwiseResults { pairs, results });
+serde_struct!(MissionTimeResults { mission_times, results });
+serde_struct!(ShortestPathResults { base_length, nodes });
+serde_struct!(PathDegradation { length_without, increase });
+serde_struct!(Scenario { name, states });
+serde_struct!(ScenarioResult { name, end_operability });
+serde_struct!(Sequence { name, outcome, probability, consequence });
+serde_struct!(EventTree { sequences });
+serde_struct!(ConsequenceResults { expected_consequence, sequences, contributions });
+serde_struct!(StoredResults { graph, results } skip { run });
+
+/// A graph together with its criticality data, as written by
+/// ['crate::export::json::JsonExport']
+#[derive(Debug, Clone)]
+pub struct GraphDocument {
+    pub graph: Graph,
+    pub data: CriticalityData,
+}
+
+serde_struct!(GraphDocument { graph, data });
+
+/// Serialized form of a ['Graph'], every list is sorted
+struct GraphRepr {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    static_nodes: Vec<u32>,
+}
+
+serde_struct!(GraphRepr { nodes, edges, static_nodes });
+
+impl From<&Graph> for GraphRepr {
+    fn from(graph: &Graph) -> Self {
+        let mut ids: Vec<u32> = graph.get_node_ids().into_iter().collect();
+        ids.sort_unstable();
+        let nodes = ids.iter().filter_map(|id| graph.get_node(id)).cloned().collect();
+        let mut edges: Vec<Edge> = graph.get_edges().iter().cloned().collect();
+        edges.sort_unstable_by_key(|e| (e.from, e.to));
+        let mut static_nodes: Vec<u32> = graph.static_nodes.iter().copied().collect();
+        static_nodes.sort_unstable();
+        GraphRepr { nodes, edges, static_nodes }
+    }
+}
+
+impl From<GraphRepr> for Graph {
+    fn from(repr: GraphRepr) -> Self {
+        let mut graph = Graph::new();
+        for node in repr.nodes {
+            graph.add_node(node.name, node.id);
+            for (name, value) in node.attributes {
+                graph.set_node_attr(&node.id, &name, value);
+            }
+        }
+        for edge in repr.edges {
+            graph.add_edge(edge.from, edge.to);
+        }
+        graph.static_nodes.extend(repr.static_nodes);
+        graph
+    }
+}
+
+impl Serialize for Graph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Graph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(GraphRepr::deserialize(deserializer)?.into())
+    }
+}
+
+/// Attribute values are written as plain numbers, bools and strings
+impl Serialize for AttrValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            AttrValue::Number(n) => serializer.serialize_f64(*n),
+            AttrValue::Bool(b) => serializer.serialize_bool(*b),
+            AttrValue::Text(s) => serializer.serialize_str(s),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AttrValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AttrVisitor;
+
+        impl<'de> Visitor<'de> for AttrVisitor {
+            type Value = AttrValue;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(f, "a number, a bool or a string")
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<AttrValue, E> {
+                Ok(AttrValue::Bool(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<AttrValue, E> {
+                Ok(AttrValue::Number(v as f64))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<AttrValue, E> {
+                Ok(AttrValue::Number(v as f64))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<AttrValue, E> {
+                Ok(AttrValue::Number(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<AttrValue, E> {
+                Ok(AttrValue::Text(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(AttrVisitor)
+    }
+}
+
+const OUTCOMES: &[&str] = &["Success", "Failure"];
+
+impl Serialize for Outcome {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Outcome::Success => serializer.serialize_unit_variant("Outcome", 0, OUTCOMES[0]),
+            Outcome::Failure => serializer.serialize_unit_variant("Outcome", 1, OUTCOMES[1]),
+        }
+    }
+}
+
+/// Variant identifier of an ['Outcome'], given as its name or its index
+struct OutcomeVariant(Outcome);
+
+impl<'de> Deserialize<'de> for OutcomeVariant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VariantVisitor;
+
+        impl<'de> Visitor<'de> for VariantVisitor {
+            type Value = OutcomeVariant;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(f, "one of {}", OUTCOMES.join(", "))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<OutcomeVariant, E> {
+                match v {
+                    0 => Ok(OutcomeVariant(Outcome::Success)),
+                    1 => Ok(OutcomeVariant(Outcome::Failure)),
+                    _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<OutcomeVariant, E> {
+                match v {
+                    "Success" => Ok(OutcomeVariant(Outcome::Success)),
+                    "Failure" => Ok(OutcomeVariant(Outcome::Failure)),
+                    _ => Err(de::Error::unknown_variant(v, OUTCOMES)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(VariantVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Outcome {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OutcomeVisitor;
+
+        impl<'de> Visitor<'de> for OutcomeVisitor {
+            type Value = Outcome;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(f, "one of {}", OUTCOMES.join(", "))
+            }
+
+            fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<Outcome, A::Error> {
+                let (OutcomeVariant(outcome), variant) = data.variant()?;
+                de::VariantAccess::unit_variant(variant)?;
+                Ok(outcome)
+            }
+        }
+
+        deserializer.deserialize_enum("Outcome", OUTCOMES, OutcomeVisitor)
+    }
+}
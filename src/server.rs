@@ -0,0 +1,544 @@
+//! Minimal blocking HTTP/1.1 server exposing the analyses to other programs.
+//!
+//! Connections are handled by a fixed number of worker threads, see ['serve_connections'], and
+//! closed after a single response. A request whose head or body is over ['MAX_HEAD_SIZE'] or
+//! ['MAX_BODY_SIZE'] is answered with 413, and a client that stops sending or reading for
+//! ['IO_TIMEOUT'] is dropped. Graphs are posted as links tables with a header, see
+//! ['read_headered_links'], and results are returned as json. The routes are:
+//!
+//! * 'GET /health' answers with '{"status": "ok"}'
+//! * 'POST /validate' checks the posted graph with ['validate_model']
+//! * 'POST /analyze' runs a criticality analysis on the posted graph, the number of sampled
+//!   states can be given with the 'samples' query parameter and the nodes answered can be
+//!   filtered with the 'filter' query parameter, see ['crate::output::filter']
+//! * 'GET /metrics' answers with the ['Metrics'] of the server in the Prometheus text format
+//! * 'GET /analyze/stream' upgrades the connection to a WebSocket, see ['stream_analysis']
+//!
+//! With an audit log every request posting work is recorded before it is answered, see
+//! ['crate::audit'].
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+use log::{error, info, warn};
+use crate::analyses::{Analysis, AnalysisContext, CancellationToken};
+use crate::analyses::criticality::Criticality;
+use crate::analyses::criticality::builder::{CriticalityBuilder, DEFAULT_SAMPLES};
+use crate::analyses::criticality::partial::{DEFAULT_PARTIAL_EVERY, PartialResults};
+use crate::audit::{AuditLog, content_id, query_config, requester};
+use crate::errors::http::RequestTooLargeError;
+use crate::input::read_headered_links;
+use crate::json::JsonValue;
+use crate::metrics::{Metrics, PROMETHEUS_CONTENT_TYPE};
+use crate::network::Graph;
+use crate::output::filter::ResultFilter;
+use crate::serialization::json::to_json;
+use crate::validation::validate_model;
+use crate::websocket;
+use crate::websocket::{MAX_MESSAGE_SIZE, Message, NORMAL_CLOSURE};
+
+/// Address the server listens on when none is given
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+/// Nodes in the streamed partial results if the request doesn't ask for a number
+pub const DEFAULT_STREAM_TOP: usize = 10;
+/// Connections answered at once if no number is given
+pub const DEFAULT_WORKERS: usize = 16;
+/// Bytes of the request line and headers of a request
+pub const MAX_HEAD_SIZE: usize = 64 * 1024;
+/// Bytes of the body of a request, as many as a WebSocket message
+pub const MAX_BODY_SIZE: usize = MAX_MESSAGE_SIZE as usize;
+/// Time a client may take to send a part of its request or to read a part of the reply
+pub const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings of the server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// 'host:port' to listen on
+    pub address: String,
+    /// Number of states sampled by an analysis that doesn't ask for a number
+    pub samples: u64,
+    /// Log every request posting work is recorded to, if any
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Connections answered at once, more wait until a worker is free
+    pub workers: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig { address: DEFAULT_ADDRESS.to_string(), samples: DEFAULT_SAMPLES, audit_log: None, workers: DEFAULT_WORKERS }
+    }
+}
+
+/// A request read from a connection
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Address of the client, None if it isn't known
+    pub peer: Option<String>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn query_value(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Response written back to a connection
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn json(status: u16, value: &JsonValue) -> Reply {
+        Reply { status, content_type: "application/json".to_string(), body: value.to_string().into_bytes() }
+    }
+
+    pub fn text(status: u16, content_type: &str, text: String) -> Reply {
+        Reply { status, content_type: content_type.to_string(), body: text.into_bytes() }
+    }
+
+    /// A json object with the 'message' under the key 'error'
+    pub fn error(status: u16, message: &str) -> Reply {
+        Reply::json(status, &JsonValue::object().with("error", message))
+    }
+
+    /// Answer to a request that couldn't be read, 413 if it is too large and 400 otherwise
+    pub fn unreadable(e: &(dyn Error + 'static)) -> Reply {
+        match e.downcast_ref::<RequestTooLargeError>() {
+            Some(_) => Reply::error(413, &e.to_string()),
+            None => Reply::error(400, &e.to_string()),
+        }
+    }
+}
+
+/// Listens on the configured address and answers requests until the process is stopped
+///
+/// # Errors
+///
+/// Returns an error if the address can't be bound
+pub fn serve(config: ServerConfig) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(&config.address)?;
+    info!("Listening on {}", config.address);
+    if let Some(audit) = config.audit_log.as_ref() {
+        info!("Recording the requests in {}", audit.path().display());
+    }
+    let metrics = Arc::new(Metrics::new());
+    let workers = config.workers;
+    serve_connections(&listener, workers, move |stream| handle_connection(stream, &config, &metrics));
+    Ok(())
+}
+
+/// Answers the connections of the 'listener' with 'handle' on 'workers' threads, with the
+/// ['IO_TIMEOUT'] set. While every worker is busy the next connection waits in the backlog of
+/// the listener, so a flood of connections doesn't start a thread for each of them.
+pub(crate) fn serve_connections<F>(listener: &TcpListener, workers: usize, handle: F)
+    where F: Fn(TcpStream) -> Result<(), Box<dyn Error>> + Send + Sync + 'static
+{
+    let (tx, rx) = mpsc::sync_channel::<TcpStream>(0);
+    let rx = Arc::new(Mutex::new(rx));
+    let handle = Arc::new(handle);
+    for _ in 0..workers.max(1) {
+        let (rx, handle) = (rx.clone(), handle.clone());
+        thread::spawn(move || loop {
+            // The lock is only held while waiting for the next connection
+            let stream = match rx.lock().unwrap().recv() {
+                Ok(stream) => stream,
+                Err(_) => return,
+            };
+            let answered = stream.set_read_timeout(Some(IO_TIMEOUT))
+                .and_then(|_| stream.set_write_timeout(Some(IO_TIMEOUT)))
+                .map_err(|e| e.into())
+                .and_then(|_| handle(stream));
+            if let Err(e) = answered {
+                error!("Failed to answer a request: {}", e);
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => if tx.send(stream).is_err() { break },
+            Err(e) => error!("Failed to accept a connection: {}", e),
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, config: &ServerConfig, metrics: &Arc<Metrics>) -> Result<(), Box<dyn Error>> {
+    metrics.request_received();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request = read_request(&mut reader).map(|mut request| {
+        request.peer = stream.peer_addr().ok().map(|a| a.to_string());
+        request
+    });
+    let reply = match request {
+        Ok(request) if request.path == "/analyze/stream" && websocket::is_upgrade(&request) => {
+            info!("{} {} (websocket)", request.method, request.path);
+            let streamed = stream_analysis(&mut reader, &mut stream, &request, config, metrics);
+            metrics.request_answered();
+            return streamed;
+        }
+        Ok(request) => {
+            info!("{} {}", request.method, request.path);
+            let reply = route(&request, config, metrics);
+            match config.audit_log.as_ref().filter(|_| request.method == "POST") {
+                Some(audit) => audit_reply(audit, &request, reply),
+                None => reply,
+            }
+        }
+        Err(e) => Reply::unreadable(e.as_ref()),
+    };
+    let written = write_reply(&mut stream, &reply);
+    metrics.request_answered();
+    written
+}
+
+/// Answers a request, see the module documentation for the routes
+pub fn route(request: &Request, config: &ServerConfig, metrics: &Arc<Metrics>) -> Reply {
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/health") => Ok(Reply::json(200, &JsonValue::object().with("status", "ok"))),
+        ("GET", "/metrics") => Ok(Reply::text(200, PROMETHEUS_CONTENT_TYPE, metrics.render())),
+        ("POST", "/validate") => validate(request),
+        ("POST", "/analyze") => analyze(request, config, metrics),
+        ("GET", "/analyze/stream") => Ok(Reply::error(426, "Upgrade to a WebSocket required")),
+        (_, "/health") | (_, "/metrics") | (_, "/validate") | (_, "/analyze") | (_, "/analyze/stream") => Ok(Reply::error(405, "Method not allowed")),
+        _ => Ok(Reply::error(404, "Not found")),
+    };
+    result.unwrap_or_else(|e| Reply::error(422, &e.to_string()))
+}
+
+/// Records the 'reply' to the 'request' in the audit log, or refuses the request if it can't be
+/// recorded
+fn audit_reply(audit: &AuditLog, request: &Request, reply: Reply) -> Reply {
+    let event = request.path.trim_matches('/').replace('/', "_");
+    let recorded = audit.record(&event, requester(request)
+        .with("inputs", vec![JsonValue::object()
+            .with("bytes", request.body.len())
+            .with("xxh64", content_id(&request.body))])
+        .with("config", query_config(request))
+        .with("status", reply.status as u64)
+        .with("result_id", Some(content_id(&reply.body)).filter(|_| reply.status == 200)));
+    match recorded {
+        Ok(()) => reply,
+        Err(e) => {
+            error!("Failed to record a request in the audit log: {}", e);
+            Reply::error(500, "The request could not be recorded in the audit log")
+        }
+    }
+}
+
+fn validate(request: &Request) -> Result<Reply, Box<dyn Error>> {
+    let (graph, data) = read_headered_links(request.body.as_slice())?;
+    let l_map = graph.links_map();
+    let pairs = vec![(Graph::get_start_id(&l_map)?, Graph::get_end_id(&l_map)?)];
+    let report = validate_model(&graph, &data, &pairs);
+    Ok(Reply::json(200, &JsonValue::object()
+        .with("valid", report.is_valid())
+        .with("errors", report.errors)
+        .with("warnings", report.warnings)))
+}
+
+fn analyze(request: &Request, config: &ServerConfig, metrics: &Arc<Metrics>) -> Result<Reply, Box<dyn Error>> {
+    let samples = match request.query_value("samples") {
+        Some(samples) => samples.parse::<u64>()?,
+        None => config.samples,
+    };
+    let filter = request.query_value("filter").map(ResultFilter::parse).transpose()?;
+    let (graph, data) = read_headered_links(request.body.as_slice())?;
+    let mut criticality = CriticalityBuilder::new(graph)
+        .off_chances(data.off_chances)
+        .samples(samples)
+        .build()?;
+    // The results are only sent back in the reply
+    criticality.outputs.clear();
+    let ctx = AnalysisContext { metrics: Some(metrics.clone()), ..Default::default() };
+    let start = Instant::now();
+    let results = criticality.run(&ctx).inspect_err(|_| metrics.run_failed())?;
+    metrics.run_completed(start.elapsed(), results.row_count);
+    let results = match filter {
+        Some(filter) => filter.apply(&criticality.graph, &results),
+        None => results,
+    };
+    Ok(Reply::json(200, &to_json(&results)?))
+}
+
+/// Runs a criticality analysis over a WebSocket, streaming its progress as it runs instead of
+/// answering once it is done.
+///
+/// After the handshake the client sends the graph as a links table with a header in one text
+/// message. The server then sends json text messages with a 'type' and its 'data':
+///
+/// * 'progress': the estimates of the sampling threads, see
+///   ['crate::analyses::criticality::throughput']
+/// * 'partial': the results so far after every 'every' states of a thread, with only the 'top'
+///   most critical nodes (['DEFAULT_STREAM_TOP'] if not given), see
+///   ['crate::analyses::criticality::partial']
+/// * 'result': the final results, as answered by 'POST /analyze' and filtered the same way
+/// * 'error': the 'message' of the error the analysis failed with
+///
+/// and closes the connection. The 'samples', 'every', 'top' and 'filter' query parameters of the
+/// upgrade request set the sampled states, the states between partial results, the nodes in them
+/// and the nodes of the final results. The
+/// analysis is cancelled if the client goes away before it is done.
+///
+/// # Errors
+///
+/// Returns an error if the handshake or the connection fails or the client sends no graph
+pub fn stream_analysis<R: Read, W: Write>(reader: &mut R, stream: &mut W, request: &Request, config: &ServerConfig,
+                                          metrics: &Arc<Metrics>) -> Result<(), Box<dyn Error>> {
+    websocket::handshake(stream, request)?;
+    let body = match websocket::read_message(reader, stream)? {
+        Message::Text(text) => text.into_bytes(),
+        Message::Binary(data) => data,
+        Message::Close => return Ok(()),
+    };
+    let send = |stream: &mut W, kind: &str, data: JsonValue| {
+        websocket::write_text(stream, &JsonValue::object().with("type", kind).with("data", data).to_string())
+    };
+    let PreparedStream { criticality, every, top, filter } = match prepare_stream(request, config, &body) {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            send(stream, "error", JsonValue::object().with("message", e.to_string()))?;
+            return websocket::write_close(stream, NORMAL_CLOSURE);
+        }
+    };
+
+    // The analysis moves to its own thread, the filter needs the graph afterwards
+    let filter = filter.map(|filter| (filter, criticality.graph.clone()));
+    let (tx, rx) = mpsc::channel();
+    let cancellation = CancellationToken::new();
+    let ctx = AnalysisContext {
+        metrics: Some(metrics.clone()),
+        partial: Some(PartialResults { every, path: None, curve_path: None, listener: Some(tx) }),
+        ..AnalysisContext::new(cancellation.clone())
+    };
+    let run_metrics = metrics.clone();
+    let run = thread::spawn(move || {
+        let start = Instant::now();
+        let results = criticality.run(&ctx).inspect_err(|_| run_metrics.run_failed());
+        if let Ok(results) = results.as_ref() {
+            run_metrics.run_completed(start.elapsed(), results.row_count);
+        }
+        results.map_err(|e| e.to_string())
+    });
+
+    // The channel closes once the analysis is done and its context is dropped
+    for update in rx {
+        let kind = update.get("type").and_then(|t| t.as_str()).unwrap_or("progress").to_string();
+        let mut data = update.get("data").cloned().unwrap_or(JsonValue::Null);
+        if let Some(nodes) = data.get("nodes").and_then(|n| n.as_array()) {
+            let nodes: Vec<JsonValue> = nodes.iter().take(top).cloned().collect();
+            data.insert("nodes", nodes);
+        }
+        if let Err(e) = send(stream, &kind, data) {
+            warn!("The client of a streamed analysis went away, cancelling it: {}", e);
+            cancellation.cancel();
+            let _ = run.join();
+            return Ok(());
+        }
+    }
+    let (kind, data) = match run.join().map_err(|_| "The streamed analysis panicked")? {
+        Ok(results) => match filter.as_ref() {
+            Some((filter, graph)) => ("result", to_json(&filter.apply(graph, &results))?),
+            None => ("result", to_json(&results)?),
+        },
+        Err(message) => ("error", JsonValue::object().with("message", message)),
+    };
+    // The result id is the hash of the message, as the client receives it
+    let message = JsonValue::object().with("type", kind).with("data", data).to_string();
+    if let Some(audit) = config.audit_log.as_ref() {
+        let recorded = audit.record("analyze_stream", requester(request)
+            .with("inputs", vec![JsonValue::object()
+                .with("bytes", body.len())
+                .with("xxh64", content_id(&body))])
+            .with("config", query_config(request))
+            .with("status", kind)
+            .with("result_id", Some(content_id(message.as_bytes())).filter(|_| kind == "result")));
+        if let Err(e) = recorded {
+            error!("Failed to record a streamed analysis in the audit log: {}", e);
+            send(stream, "error", JsonValue::object().with("message", "The analysis could not be recorded in the audit log"))?;
+            return websocket::write_close(stream, NORMAL_CLOSURE);
+        }
+    }
+    websocket::write_text(stream, &message)?;
+    websocket::write_close(stream, NORMAL_CLOSURE)
+}
+
+/// The analysis of a stream and the options of its messages, see ['stream_analysis']
+struct PreparedStream {
+    criticality: Criticality,
+    /// States between partial results
+    every: u64,
+    /// Nodes in the partial results
+    top: usize,
+    /// Filter of the final results
+    filter: Option<ResultFilter>,
+}
+
+/// The analysis of the graph in the first message of a stream, see ['stream_analysis']
+fn prepare_stream(request: &Request, config: &ServerConfig, body: &[u8]) -> Result<PreparedStream, Box<dyn Error>> {
+    let samples = match request.query_value("samples") {
+        Some(samples) => samples.parse::<u64>()?,
+        None => config.samples,
+    };
+    let every = match request.query_value("every") {
+        Some(every) => every.parse::<u64>()?,
+        None => DEFAULT_PARTIAL_EVERY,
+    };
+    let top = match request.query_value("top") {
+        Some(top) => top.parse::<usize>()?,
+        None => DEFAULT_STREAM_TOP,
+    };
+    let filter = request.query_value("filter").map(ResultFilter::parse).transpose()?;
+    let (graph, data) = read_headered_links(body)?;
+    let mut criticality = CriticalityBuilder::new(graph)
+        .off_chances(data.off_chances)
+        .samples(samples)
+        .build()?;
+    // The results are only sent over the stream
+    criticality.outputs.clear();
+    Ok(PreparedStream { criticality, every, top, filter })
+}
+
+/// Reads the request line, the headers and a body of the given 'Content-Length'
+///
+/// # Errors
+///
+/// Returns a ['RequestTooLargeError'] if the request line and headers are over
+/// ['MAX_HEAD_SIZE'] or the body is over ['MAX_BODY_SIZE'], or another error if the connection
+/// fails or the request is malformed
+pub fn read_request<R: BufRead>(mut reader: R) -> Result<Request, Box<dyn Error>> {
+    // Bytes of the head left to read
+    let mut head_left = MAX_HEAD_SIZE;
+    let mut request_line = String::new();
+    read_head_line(&mut reader, &mut request_line, &mut head_left)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Err(format!("Malformed http request line: {}", request_line.trim()).into()),
+    };
+    let (path, query) = match target.split_once('?') {
+        None => (target, vec![]),
+        Some((path, query)) => (path.to_string(), parse_query(query)),
+    };
+
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        if read_head_line(&mut reader, &mut line, &mut head_left)? == 0 { break; }
+        let line = line.trim_end();
+        if line.is_empty() { break; }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let mut request = Request { method, path, query, headers, body: vec![], peer: None };
+    if let Some(length) = request.header("Content-Length").and_then(|l| l.parse::<usize>().ok()) {
+        if length > MAX_BODY_SIZE {
+            return Err(RequestTooLargeError { part: "body".to_string(), limit: MAX_BODY_SIZE }.into());
+        }
+        let mut body = vec![0u8; length];
+        reader.read_exact(&mut body)?;
+        request.body = body;
+    }
+    Ok(request)
+}
+
+/// Reads a line of the head of a request, at most the 'left' bytes of the head
+fn read_head_line<R: BufRead>(reader: &mut R, line: &mut String, left: &mut usize) -> Result<usize, Box<dyn Error>> {
+    let read = reader.take(*left as u64).read_line(line)?;
+    if read == *left && !line.ends_with('\n') {
+        return Err(RequestTooLargeError { part: "head".to_string(), limit: MAX_HEAD_SIZE }.into());
+    }
+    *left -= read;
+    Ok(read)
+}
+
+/// Splits 'key=value&key=value', '+' and percent escapes are decoded
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query.split('&')
+        .filter(|p| !p.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            None => (decode_component(pair), String::new()),
+            Some((k, v)) => (decode_component(k), decode_component(v)),
+        })
+        .collect()
+}
+
+fn decode_component(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'+', _) => { out.push(b' '); i += 1; }
+            (b'%', Some(byte)) => { out.push(byte); i += 3; }
+            (b, _) => { out.push(b); i += 1; }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+pub(crate) fn write_reply<W: Write>(stream: &mut W, reply: &Reply) -> Result<(), Box<dyn Error>> {
+    let head = format!("HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                       reply.status, reason_phrase(reply.status), reply.content_type, reply.body.len());
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&reply.body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        426 => "Upgrade Required",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_over_the_limits_are_refused_before_reading_them() {
+        let request = read_request(&b"POST /analyze?samples=10 HTTP/1.1\r\nContent-Length: 4\r\n\r\nbody"[..]).unwrap();
+        assert_eq!((request.path.as_str(), request.query_value("samples"), request.body.as_slice()), ("/analyze", Some("10"), &b"body"[..]));
+
+        let huge_body = format!("POST /analyze HTTP/1.1\r\nContent-Length: {}\r\n\r\n", usize::MAX);
+        let error = read_request(huge_body.as_bytes()).unwrap_err();
+        assert_eq!(Reply::unreadable(error.as_ref()).status, 413);
+
+        let endless_header = format!("GET /health HTTP/1.1\r\nX-Filler: {}", "a".repeat(MAX_HEAD_SIZE));
+        let error = read_request(endless_header.as_bytes()).unwrap_err();
+        assert_eq!(Reply::unreadable(error.as_ref()).status, 413);
+
+        let error = read_request(&b"nonsense\r\n\r\n"[..]).unwrap_err();
+        assert_eq!(Reply::unreadable(error.as_ref()).status, 400);
+    }
+}
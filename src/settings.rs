@@ -0,0 +1,158 @@
+//! Resolution of the settings of a run from its layers.
+//!
+//! Every setting is a command line flag, and can be given in several places. From the lowest to
+//! the highest precedence:
+//!
+//! 1. the defaults of the commands, used when a flag is given nowhere
+//! 2. the 'flags' of the configuration file and of its selected profile, see ['Config::flags']
+//! 3. environment variables named ['ENV_PREFIX'] followed by the flag in capitals, with '_' for
+//!    '-', such as 'THOR_THREADS=4' for '--threads 4' or 'THOR_INPUT=/data/links.csv'. The value
+//!    'true' gives a flag without a value, 'false' or an empty value leaves it out.
+//! 4. the command line
+//!
+//! The layers are joined into one argument list with the highest layer first, every flag is read
+//! from the first place it appears.
+
+use log::debug;
+use crate::config::Config;
+use crate::errors::config::ConfigError;
+use crate::input::remote;
+
+/// Prefix of the environment variables giving flags
+pub const ENV_PREFIX: &str = "THOR_";
+
+/// Variables with the prefix that are read where they are needed rather than as flags
+const RESERVED_VARS: [&str; 2] = [remote::CACHE_DIR_VAR, remote::CACHE_FALLBACK_VAR];
+
+/// Layer a setting was given in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    CommandLine,
+    Env,
+    Config,
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The arguments of every layer, from the highest to the lowest precedence
+    layers: Vec<(Source, Vec<String>)>,
+}
+
+impl Settings {
+    /// The settings given on the command line, 'args[0]' is the name of the binary
+    pub fn new(args: &[String]) -> Settings {
+        Settings { layers: vec![(Source::CommandLine, args.to_vec())] }
+    }
+
+    /// Adds the flags given by the environment variables 'vars' below the current layers
+    pub fn with_env<I: IntoIterator<Item = (String, String)>>(mut self, vars: I) -> Settings {
+        let mut vars: Vec<(String, String)> = vars.into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX) && !RESERVED_VARS.contains(&name.as_str()))
+            .collect();
+        vars.sort();
+        let mut args = vec![];
+        for (name, value) in vars {
+            let flag = format!("--{}", name[ENV_PREFIX.len()..].to_ascii_lowercase().replace('_', "-"));
+            match value.as_str() {
+                "" | "false" => {}
+                "true" => args.push(flag),
+                _ => args.extend([flag, value]),
+            }
+        }
+        self.layers.push((Source::Env, args));
+        self
+    }
+
+    /// Adds the flags of the configuration below the current layers
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ConfigError'] if the flags of the configuration are invalid
+    pub fn with_config(mut self, config: Option<&Config>) -> Result<Settings, ConfigError> {
+        let args = match config {
+            Some(config) => config.flags()?,
+            None => vec![],
+        };
+        self.layers.push((Source::Config, args));
+        Ok(self)
+    }
+
+    /// The arguments of all layers, to be read like the command line
+    pub fn args(&self) -> Vec<String> {
+        self.layers.iter().flat_map(|(_, args)| args.iter().cloned()).collect()
+    }
+
+    /// Logs the flags taken from the environment and the configuration
+    pub fn log(&self) {
+        for (source, args) in self.layers.iter().filter(|(source, _)| *source != Source::CommandLine) {
+            for flag in args.iter().filter(|a| a.starts_with("--")) {
+                if self.source(flag) == Some(*source) {
+                    debug!("Setting {} from {:?}", flag, source);
+                }
+            }
+        }
+    }
+
+    /// The layer the flag 'name' is taken from, None if it is given nowhere and has its default
+    pub fn source(&self, name: &str) -> Option<Source> {
+        self.layers.iter()
+            .find(|(_, args)| args.iter().any(|a| a == name))
+            .map(|(source, _)| *source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{arg_value, has_flag};
+    use crate::json;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    fn settings(args: &[&str], env: &[(&str, &str)], flags: &str) -> Settings {
+        let config = Config { path: "thor.json".to_string(), values: json::parse(flags).unwrap(), profile: None };
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        Settings::new(&args).with_env(vars(env)).with_config(Some(&config)).unwrap()
+    }
+
+    #[test]
+    fn every_flag_is_taken_from_its_highest_layer() {
+        let settings = settings(
+            &["thor", "analyze", "links.csv", "--samples", "50"],
+            &[("THOR_SAMPLES", "100"), ("THOR_THREADS", "4"), ("OTHER_SEED", "1")],
+            r#"{ "flags": { "samples": 1000, "threads": 2, "seed": 7 } }"#,
+        );
+        let args = settings.args();
+        assert_eq!(arg_value(&args, "--samples").unwrap(), "50");
+        assert_eq!(arg_value(&args, "--threads").unwrap(), "4");
+        assert_eq!(arg_value(&args, "--seed").unwrap(), "7");
+        assert_eq!(settings.source("--samples"), Some(Source::CommandLine));
+        assert_eq!(settings.source("--threads"), Some(Source::Env));
+        assert_eq!(settings.source("--seed"), Some(Source::Config));
+        assert_eq!(settings.source("--headers"), None);
+        // The positional arguments of the command line stay in place
+        assert_eq!(&args[..3], ["thor", "analyze", "links.csv"]);
+    }
+
+    #[test]
+    fn variables_name_flags_and_switch_them_with_booleans() {
+        let env = [("THOR_PER_COMPONENT", "true"), ("THOR_HEADERS", "false"), ("THOR_OUT", ""),
+                   ("THOR_PAGE_SIZE", "20"), (remote::CACHE_DIR_VAR, "/tmp/cache")];
+        let args = settings(&["thor", "analyze"], &env, "{}").args();
+        assert!(has_flag(&args, "--per-component"));
+        assert!(!has_flag(&args, "--headers"));
+        assert!(!has_flag(&args, "--out"));
+        assert_eq!(arg_value(&args, "--page-size").unwrap(), "20");
+        // Reserved variables are read where they are needed
+        assert!(!has_flag(&args, "--cache-dir"));
+    }
+
+    #[test]
+    fn invalid_configuration_flags_are_errors() {
+        let config = Config { path: "thor.json".to_string(), values: json::parse(r#"{ "flags": { "samples": [1] } }"#).unwrap(), profile: None };
+        let error = Settings::new(&[]).with_config(Some(&config)).unwrap_err();
+        assert_eq!(error.path, "thor.json");
+    }
+}
@@ -0,0 +1,221 @@
+//! Running the criticality analysis over a grid of parameter values.
+//!
+//! A sweep is declared in the 'sweep' section of the ['crate::config::Config']:
+//!
+//! ```json
+//! {
+//!   "sweep": {
+//!     "samples": 10000,
+//!     "parameters": [
+//!       { "node": 12, "from": 0.01, "to": 0.2, "steps": 10 },
+//!       { "node": 7, "values": [0.1, 0.5] }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! Every parameter is the off chance of a dynamic node, given as 'steps' evenly spaced values
+//! from 'from' to 'to', both included, or as a list of 'values'. The analysis samples
+//! 'samples' states, ['DEFAULT_SAMPLES'] if not given, at every point of the grid, the cartesian
+//! product of the values. The points run in parallel on the worker threads, each sampling on a
+//! single thread, and the results are collected in the order of the points so the table doesn't
+//! depend on which point finishes first.
+//!
+//! ['SweepResults::to_csv'] writes the results in long format, one row per point and value, the
+//! shape plotting tools group and facet by.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use log::info;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::CriticalityResults;
+use crate::analyses::criticality::builder::{CriticalityBuilder, DEFAULT_SAMPLES};
+use crate::config::Config;
+use crate::errors::analysis::ThorError;
+use crate::errors::config::ConfigError;
+use crate::json::JsonValue;
+use crate::pipeline::PipelineState;
+
+/// Section of the configuration declaring the sweep
+pub const SWEEP_KEY: &str = "sweep";
+
+/// Off chances a dynamic node takes in the sweep
+#[derive(Debug, Clone)]
+pub struct SweepParameter {
+    pub node: u32,
+    pub values: Vec<f32>,
+}
+
+/// Results of a point, or the message of its error, None if it was cancelled. Errors aren't
+/// Send, only their message leaves the worker thread.
+type PointOutcome = Result<CriticalityResults, Option<String>>;
+
+/// A grid of parameter values, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    pub parameters: Vec<SweepParameter>,
+    /// Number of states sampled at every point
+    pub samples: u64,
+}
+
+/// The results at every point of the grid
+#[derive(Debug, Clone)]
+pub struct SweepResults {
+    pub parameters: Vec<SweepParameter>,
+    /// Value of every parameter at the point with the results there, in the order of the points
+    pub points: Vec<(Vec<f32>, CriticalityResults)>,
+}
+
+impl Sweep {
+    /// Reads the sweep declared in the 'sweep' section of the configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ConfigError'] if the section is missing or a parameter is invalid
+    pub fn from_config(config: &Config) -> Result<Sweep, ConfigError> {
+        let section = config.get(SWEEP_KEY)
+            .ok_or_else(|| config.error(SWEEP_KEY, "is missing, it declares the parameters of the sweep"))?;
+        let samples = match section.get("samples") {
+            Some(samples) => samples.as_f64().filter(|s| *s >= 1.0)
+                .ok_or_else(|| config.error(SWEEP_KEY, "must give a positive number of 'samples'"))? as u64,
+            None => DEFAULT_SAMPLES,
+        };
+        let parameters = section.get("parameters").and_then(|p| p.as_array())
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| config.error(SWEEP_KEY, "must give a list of 'parameters'"))?;
+        let parameters = parameters.iter().enumerate()
+            .map(|(i, parameter)| read_parameter(parameter)
+                .map_err(|reason| config.error(&format!("{}.parameters[{}]", SWEEP_KEY, i), &reason)))
+            .collect::<Result<Vec<SweepParameter>, ConfigError>>()?;
+        Ok(Sweep { parameters, samples })
+    }
+
+    /// Value of every parameter at every point of the grid, the last parameter changing fastest
+    pub fn points(&self) -> Vec<Vec<f32>> {
+        self.parameters.iter().fold(vec![vec![]], |points, parameter| {
+            points.iter()
+                .flat_map(|point| parameter.values.iter().map(move |value| {
+                    let mut point = point.clone();
+                    point.push(*value);
+                    point
+                }))
+                .collect()
+        })
+    }
+
+    /// Analyses the 'state' at every point of the grid on 'workers' threads
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter isn't a dynamic node of the state, or the analysis of the
+    /// first point, in their order, that failed
+    pub fn run(&self, state: &PipelineState, workers: usize, ctx: &AnalysisContext) -> Result<SweepResults, ThorError> {
+        if let Some(parameter) = self.parameters.iter().find(|p| !state.dynamic_ids.contains(&p.node)) {
+            return Err(ThorError::Failed(format!("The swept node {} is not a dynamic node", parameter.node).into()));
+        }
+        let points = self.points();
+        info!("Sweeping {} points of {} parameters on {} threads", points.len(), self.parameters.len(), workers);
+        let next = AtomicUsize::new(0);
+        let outcomes: Vec<Mutex<Option<PointOutcome>>> = points.iter().map(|_| Mutex::new(None)).collect();
+        let (next, slots, grid) = (&next, &outcomes, &points);
+        thread::scope(|scope| {
+            for _ in 0..workers.clamp(1, points.len().max(1)) {
+                // Every worker has its own copy of the state, sharing the graph
+                let state = state.clone();
+                scope.spawn(move || {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(point) = grid.get(index) else { break };
+                        let outcome = self.run_point(&state, point, ctx).map_err(|e| match e {
+                            ThorError::Cancelled => None,
+                            ThorError::Failed(e) => Some(format!("the point {:?} failed: {}", point, e)),
+                        });
+                        *slots[index].lock().unwrap() = Some(outcome);
+                    }
+                });
+            }
+        });
+        let results = outcomes.into_iter()
+            .map(|outcome| match outcome.into_inner().unwrap() {
+                Some(Ok(results)) => Ok(results),
+                Some(Err(Some(reason))) => Err(ThorError::Failed(reason.into())),
+                Some(Err(None)) => Err(ThorError::Cancelled),
+                None => Err(ThorError::Failed("a sweep thread panicked".into())),
+            })
+            .collect::<Result<Vec<CriticalityResults>, ThorError>>()?;
+        Ok(SweepResults { parameters: self.parameters.clone(), points: points.into_iter().zip(results).collect() })
+    }
+
+    /// Samples the criticality with the off chances of the 'point' on a single thread
+    fn run_point(&self, state: &PipelineState, point: &[f32], ctx: &AnalysisContext) -> Result<CriticalityResults, ThorError> {
+        let mut off_chances = state.off_chances.clone();
+        for (parameter, value) in self.parameters.iter().zip(point.iter()) {
+            off_chances.insert(parameter.node, *value);
+        }
+        let mut criticality = CriticalityBuilder::new((*state.graph).clone())
+            .dynamic_ids(state.dynamic_ids.clone())
+            .off_chances(off_chances)
+            .roll_up_rule(dyn_clone::clone_box(&*state.roll_up_rule))
+            .start_id(state.start_id)
+            .end_id(state.end_id)
+            .samples(self.samples)
+            .threads(1)
+            .build()?;
+        criticality.outputs.clear();
+        criticality.run(ctx)
+    }
+}
+
+impl SweepResults {
+    /// The results as a csv table in long format: the point, the value of every parameter as a
+    /// column 'off_chance_<node>', the metric and the node it is of, empty for the end node
+    /// operability, and its value
+    pub fn to_csv(&self) -> String {
+        let columns: Vec<String> = self.parameters.iter().map(|p| format!("off_chance_{}", p.node)).collect();
+        let mut csv = format!("point,{},metric,node,value\n", columns.join(","));
+        for (index, (point, results)) in self.points.iter().enumerate() {
+            let values: Vec<String> = point.iter().map(|v| v.to_string()).collect();
+            let prefix = format!("{},{}", index, values.join(","));
+            csv.push_str(&format!("{},end_op_mean,,{}\n", prefix, results.end_op_mean));
+            let mut nodes: Vec<_> = results.nodes.iter().collect();
+            nodes.sort_by_key(|(id, _)| **id);
+            for (id, node) in nodes {
+                csv.push_str(&format!("{},criticality,{},{}\n", prefix, id, node.criticality));
+            }
+        }
+        csv
+    }
+}
+
+/// Reads a parameter declaration, returning the reason if it is invalid
+fn read_parameter(value: &JsonValue) -> Result<SweepParameter, String> {
+    let node = value.get("node").and_then(|n| n.as_f64())
+        .ok_or("must give the id of the 'node' whose off chance is swept")? as u32;
+    let values: Vec<f32> = match (value.get("values"), value.get("from"), value.get("to")) {
+        (Some(values), None, None) => values.as_array()
+            .and_then(|values| values.iter().map(|v| v.as_f64().map(|v| v as f32)).collect::<Option<Vec<f32>>>())
+            .ok_or("must give the 'values' as a list of numbers")?,
+        (None, Some(from), Some(to)) => {
+            let (from, to) = from.as_f64().zip(to.as_f64()).ok_or("must give 'from' and 'to' as numbers")?;
+            let steps = match value.get("steps") {
+                Some(steps) => steps.as_f64().filter(|s| *s >= 1.0).ok_or("must give a positive number of 'steps'")? as usize,
+                None => 2,
+            };
+            (0..steps)
+                .map(|i| match steps {
+                    1 => from,
+                    _ => from + (to - from) * i as f64 / (steps - 1) as f64,
+                } as f32)
+                .collect()
+        }
+        _ => return Err("must give either 'values' or 'from' and 'to'".to_string()),
+    };
+    if values.is_empty() {
+        return Err("must give at least one value".to_string());
+    }
+    if let Some(value) = values.iter().find(|v| !(0.0..=1.0).contains(*v)) {
+        return Err(format!("has the off chance {}, which is not between 0 and 1", value));
+    }
+    Ok(SweepParameter { node, values })
+}
@@ -0,0 +1,144 @@
+//! Checks of a graph and its data that can be made without running an analysis.
+//!
+//! Errors make every analysis of the graph fail or give meaningless results, warnings point at
+//! parts of the input that are most likely mistakes but are ignored by the analyses.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::analyses::criticality::CriticalityData;
+use crate::analyses::criticality::vis_gen::visibility_states_gen::Lifetime;
+use crate::errors::network::UnknownNodesError;
+use crate::network::{Graph, LinkMap, Links};
+
+/// Problems found in a graph and its data
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Prints every error and warning to the standard output
+    pub fn print(&self) {
+        for error in self.errors.iter() {
+            println!("error: {}", error);
+        }
+        for warning in self.warnings.iter() {
+            println!("warning: {}", warning);
+        }
+        println!("{} errors, {} warnings", self.errors.len(), self.warnings.len());
+    }
+}
+
+/// Checks the 'graph' and its 'data' for use with the (source, sink) 'pairs':
+///
+/// * every pair has to be part of the graph and the sink has to be reachable from the source
+/// * the graph has to be acyclic, as the roll up visits every node once
+/// * off chances have to lie between zero and one, edge attributes have to be finite
+/// * lifetime attributes have to describe a known distribution
+///
+/// Off chances and edge attributes of unknown nodes and edges, nodes that are not reachable from
+/// any source, components of the graph without any pair and the warnings of reading the input
+/// are reported as warnings.
+pub fn validate_model(graph: &Graph, data: &CriticalityData, pairs: &[(u32, u32)]) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let node_ids = graph.get_node_ids();
+    if node_ids.is_empty() {
+        report.errors.push("The graph has no nodes".to_string());
+        return report;
+    }
+    let l_map = graph.links_map();
+
+    for (source, sink) in pairs.iter() {
+        let mut unknown: Vec<u32> = [*source, *sink].into_iter().filter(|id| !node_ids.contains(id)).collect();
+        unknown.dedup();
+        match unknown.is_empty() {
+            false => report.errors.push(UnknownNodesError { ids: unknown }.to_string()),
+            true => if let Err(e) = Graph::validate_end_connection(&l_map, *source, *sink) {
+                report.errors.push(e.to_string());
+            }
+        }
+    }
+
+    if let Some(cycle_nodes) = nodes_on_cycles(&l_map) {
+        report.errors.push(format!("The graph has a cycle through the nodes {:?}", cycle_nodes));
+    }
+
+    let mut reached: HashSet<u32> = HashSet::new();
+    for (source, _) in pairs.iter().filter(|(source, _)| node_ids.contains(source)) {
+        reached.extend(Graph::get_bfs_path(&l_map, *source));
+    }
+    let mut unreached: Vec<u32> = node_ids.iter().filter(|id| !reached.contains(id)).copied().collect();
+    if !pairs.is_empty() && !unreached.is_empty() {
+        unreached.sort_unstable();
+        report.warnings.push(format!("The nodes {:?} can't be reached from any start node", unreached));
+    }
+
+    let pair_nodes: HashSet<u32> = pairs.iter().flat_map(|(source, sink)| [*source, *sink]).collect();
+    let detached: Vec<u32> = graph.components().into_iter()
+        .filter(|component| !component.iter().any(|id| pair_nodes.contains(id)))
+        .flatten()
+        .collect();
+    if !pairs.is_empty() && !detached.is_empty() {
+        report.warnings.push(format!("The nodes {:?} are not connected to any start or end node", detached));
+    }
+
+    for (id, off_chance) in data.off_chances.iter() {
+        if !(0.0..=1.0).contains(off_chance) {
+            report.errors.push(format!("The off chance of node {} is {}, it should lie between 0 and 1", id, off_chance));
+        }
+        if !node_ids.contains(id) {
+            report.warnings.push(format!("An off chance is given for node {} which is not part of the graph", id));
+        }
+    }
+
+    let mut attribute_names: Vec<&String> = data.edge_attributes.keys().collect();
+    attribute_names.sort();
+    for name in attribute_names {
+        for ((from, to), value) in data.edge_attributes[name].iter() {
+            if !value.is_finite() {
+                report.errors.push(format!("The {} of the edge ({}, {}) is {}", name, from, to, value));
+            }
+            if graph.get_edge(*from, *to).is_none() {
+                report.warnings.push(format!("A {} is given for the edge ({}, {}) which is not part of the graph", name, from, to));
+            }
+        }
+    }
+
+    if let Err(e) = Lifetime::from_attributes(graph) {
+        report.errors.push(e.to_string());
+    }
+    report.warnings.extend(data.warnings.iter().cloned());
+    report
+}
+
+/// The nodes that lie on a cycle or behind one, in ascending order, or None if the graph is
+/// acyclic. Uses Kahn's algorithm: nodes that are never freed of their children are cyclic.
+pub(crate) fn nodes_on_cycles(l_map: &LinkMap) -> Option<Vec<u32>> {
+    let mut remaining: HashMap<u32, usize> = l_map.iter()
+        .map(|(id, (children, _))| (*id, children.len()))
+        .collect();
+    let mut agenda: VecDeque<u32> = remaining.iter().filter(|(_, n)| **n == 0).map(|(id, _)| *id).collect();
+    while let Some(id) = agenda.pop_front() {
+        remaining.remove(&id);
+        for parent in l_map.parents_of(&id) {
+            if let Some(n) = remaining.get_mut(parent) {
+                *n -= 1;
+                if *n == 0 {
+                    agenda.push_back(*parent);
+                }
+            }
+        }
+    }
+    match remaining.is_empty() {
+        true => None,
+        false => {
+            let mut ids: Vec<u32> = remaining.into_keys().collect();
+            ids.sort_unstable();
+            Some(ids)
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! WebAssembly API for running small analyses in a browser, enabled by the 'wasm' feature.
+//!
+//! Built with 'cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm
+//! --crate-type cdylib' and loaded through 'js/thor.js'. Browsers have no threads or clock for
+//! the sampling, so it runs on the calling thread without a wall time limit or progress reports,
+//! see ['crate::analyses::criticality'].
+//!
+//! A request is a json object with:
+//!
+//! * 'document': the graph and its data as written by ['crate::export::json::JsonExport']
+//! * 'samples': the number of sampled states, ['DEFAULT_SAMPLES'] if missing
+//! * 'roll_up': the roll up rule, see ['rule_from_json'], the ['crate::roll_up::OrRule'] if
+//!   missing
+//!
+//! The reply holds the 'results' of the criticality analysis as stored by 'analyze --results', or
+//! the 'error' as reported by ['error_json'].
+//!
+//! The module exports 'thor_alloc' and 'thor_free' for the page to pass the request in the
+//! memory of the module, and 'thor_analyze' which returns the reply preceded by its length as a
+//! little endian u32. The module imports 'env.thor_random_fill(ptr, len)', which fills the
+//! memory with random bytes for the seeds of the sampling.
+//!
+//! CI builds the module and loads it through 'js/thor.js' with 'js/smoke.mjs'.
+
+use std::error::Error;
+use crate::analyses::{Analysis, AnalysisContext};
+use crate::analyses::criticality::builder::{CriticalityBuilder, DEFAULT_SAMPLES};
+use crate::exit::error_json;
+use crate::json;
+use crate::json::JsonValue;
+use crate::network::EdgeValueMap;
+use crate::roll_up::rule_from_json;
+use crate::serialization::GraphDocument;
+use crate::serialization::json::{from_json, to_json};
+
+/// Runs the criticality analysis of a 'request', see the module documentation
+///
+/// # Errors
+///
+/// Returns an error if the request isn't valid json, holds no valid document or rule, or the
+/// analysis fails
+pub fn analyze_request(request: &str) -> Result<JsonValue, Box<dyn Error>> {
+    let request = json::parse(request)?;
+    let document = request.get("document").ok_or("The request has no 'document'")?;
+    let GraphDocument { graph, data } = from_json(document.clone())?;
+    let samples = match request.get("samples") {
+        Some(samples) => samples.as_u64().ok_or("The 'samples' of the request must be a positive integer")?,
+        None => DEFAULT_SAMPLES,
+    };
+    let mut builder = CriticalityBuilder::new(graph)
+        .off_chances(data.off_chances.clone())
+        .samples(samples)
+        .threads(1);
+    if let Some(declaration) = request.get("roll_up") {
+        let no_alpha = EdgeValueMap::new();
+        builder = builder.roll_up_rule(rule_from_json(declaration, data.alpha().unwrap_or(&no_alpha))?);
+    }
+    let mut criticality = builder.build()?;
+    // The results are only sent back in the reply
+    criticality.outputs.clear();
+    let results = criticality.run(&AnalysisContext::default())?;
+    Ok(JsonValue::object().with("results", to_json(&results)?))
+}
+
+/// The reply to a 'request', with either its results or its error
+pub fn reply(request: &str) -> JsonValue {
+    match analyze_request(request) {
+        Ok(reply) => reply,
+        Err(e) => JsonValue::object().with("error", error_json(e.as_ref(), "analyze")),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod exports {
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        fn thor_random_fill(ptr: *mut u8, len: usize);
+    }
+
+    fn random_fill(buffer: &mut [u8]) -> Result<(), getrandom::Error> {
+        // SAFETY: the page only writes the 'len' bytes of the buffer
+        unsafe { thor_random_fill(buffer.as_mut_ptr(), buffer.len()) };
+        Ok(())
+    }
+
+    getrandom::register_custom_getrandom!(random_fill);
+
+    /// Reserves 'len' bytes for the page to write a request to
+    #[no_mangle]
+    pub extern "C" fn thor_alloc(len: usize) -> *mut u8 {
+        Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+    }
+
+    /// Releases 'len' bytes reserved by ['thor_alloc'] or returned by ['thor_analyze']
+    ///
+    /// # Safety
+    ///
+    /// 'ptr' and 'len' must be those of a buffer of this module that isn't used afterwards
+    #[no_mangle]
+    pub unsafe extern "C" fn thor_free(ptr: *mut u8, len: usize) {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+
+    /// Answers the utf-8 request of 'len' bytes at 'ptr', see the module documentation. The reply
+    /// is released with ['thor_free'] and its length plus four bytes.
+    ///
+    /// # Safety
+    ///
+    /// 'ptr' must point to 'len' readable bytes
+    #[no_mangle]
+    pub unsafe extern "C" fn thor_analyze(ptr: *const u8, len: usize) -> *mut u8 {
+        let request = String::from_utf8_lossy(std::slice::from_raw_parts(ptr, len));
+        let reply = super::reply(&request).to_string();
+        let mut buffer = Vec::with_capacity(reply.len() + 4);
+        buffer.extend_from_slice(&(reply.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(reply.as_bytes());
+        Box::into_raw(buffer.into_boxed_slice()) as *mut u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// start -> a -> end and start -> b -> end, as written by the json export
+    const DOCUMENT: &str = r#"{"graph": {"nodes": [{"name": "start", "id": 0, "attributes": {}},
+        {"name": "a", "id": 1, "attributes": {}}, {"name": "b", "id": 2, "attributes": {}},
+        {"name": "end", "id": 3, "attributes": {}}], "edges": [{"from": 0, "to": 1}, {"from": 0, "to": 2},
+        {"from": 1, "to": 3}, {"from": 2, "to": 3}], "static_nodes": []},
+        "data": {"edge_attributes": {}, "off_chances": {}}}"#;
+
+    #[test]
+    fn requests_are_answered_with_results_or_an_error() {
+        let results = reply(&format!(r#"{{"document": {}, "samples": 100}}"#, DOCUMENT));
+        let nodes = results.get("results").and_then(|r| r.get("nodes")).unwrap_or_else(|| panic!("{}", results));
+        for id in ["1", "2"] {
+            assert_eq!(nodes.get(id).and_then(|n| n.get("criticality")).and_then(|c| c.as_f64()), Some(0.5));
+        }
+        for request in [r#"{"samples": 100}"#, "not json", &format!(r#"{{"document": {}, "samples": -1}}"#, DOCUMENT)] {
+            assert!(reply(request).get("error").is_some(), "{}", request);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Minimal WebSocket framing (RFC 6455) for the streaming routes of ['crate::server'].
+//!
+//! Only what the server needs is supported: answering the handshake of an upgraded http request,
+//! reading the masked messages of a client and writing unmasked text and close frames.
+//! Fragmented messages are joined and pings are answered while a message is read. Extensions
+//! and subprotocols are never negotiated.
+
+use std::error::Error;
+use std::io::{Read, Write};
+use crate::checksum::sha1;
+use crate::http::base64;
+use crate::server::Request;
+
+/// Appended to the key of the client before hashing it into the accept key of the handshake
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Largest message read from a client, larger ones close the connection
+pub const MAX_MESSAGE_SIZE: u64 = 64 * 1024 * 1024;
+/// Close code of a connection closed after its work is done
+pub const NORMAL_CLOSURE: u16 = 1000;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// A whole message sent by the client
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    /// The client closed the connection
+    Close,
+}
+
+/// Whether the 'request' asks to upgrade the connection to a WebSocket
+pub fn is_upgrade(request: &Request) -> bool {
+    request.method == "GET"
+        && request.header("Upgrade").map(|u| u.eq_ignore_ascii_case("websocket")).unwrap_or(false)
+        && request.header("Sec-WebSocket-Key").is_some()
+}
+
+/// The 'Sec-WebSocket-Accept' answering the 'Sec-WebSocket-Key' of a client
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes()))
+}
+
+/// Answers the handshake of an upgrade 'request', frames can be exchanged afterwards
+///
+/// # Errors
+///
+/// Returns an error if the request has no key or the answer can't be written
+pub fn handshake<W: Write>(stream: &mut W, request: &Request) -> Result<(), Box<dyn Error>> {
+    let key = request.header("Sec-WebSocket-Key").ok_or("The WebSocket handshake has no Sec-WebSocket-Key")?;
+    let head = format!("HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                       accept_key(key));
+    stream.write_all(head.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn write_frame<W: Write>(stream: &mut W, opcode: u8, payload: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut head = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => head.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            head.push(126);
+            head.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            head.push(127);
+            head.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    stream.write_all(&head)?;
+    stream.write_all(payload)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Sends a text message in a single frame
+///
+/// # Errors
+///
+/// Returns an error if the connection fails
+pub fn write_text<W: Write>(stream: &mut W, text: &str) -> Result<(), Box<dyn Error>> {
+    write_frame(stream, OP_TEXT, text.as_bytes())
+}
+
+/// Sends a close frame with the 'code', no frames may be sent afterwards
+///
+/// # Errors
+///
+/// Returns an error if the connection fails
+pub fn write_close<W: Write>(stream: &mut W, code: u16) -> Result<(), Box<dyn Error>> {
+    write_frame(stream, OP_CLOSE, &code.to_be_bytes())
+}
+
+/// Reads the next whole message of the client, answering its pings on 'stream'
+///
+/// # Errors
+///
+/// Returns an error if the connection fails, a frame isn't masked, a text message isn't utf-8 or
+/// a message is larger than ['MAX_MESSAGE_SIZE']
+pub fn read_message<R: Read, W: Write>(reader: &mut R, stream: &mut W) -> Result<Message, Box<dyn Error>> {
+    let mut message: Option<(u8, Vec<u8>)> = None;
+    loop {
+        let (fin, opcode, payload) = read_frame(reader)?;
+        match opcode {
+            OP_CLOSE => return Ok(Message::Close),
+            OP_PING => { write_frame(stream, OP_PONG, &payload)?; continue; }
+            OP_PONG => continue,
+            OP_CONTINUATION => match message.as_mut() {
+                Some((_, data)) => data.extend_from_slice(&payload),
+                None => return Err("A WebSocket continuation frame has no message to continue".into()),
+            },
+            OP_TEXT | OP_BINARY if message.is_none() => message = Some((opcode, payload)),
+            OP_TEXT | OP_BINARY => return Err("A WebSocket message started before the previous one ended".into()),
+            _ => return Err(format!("Unknown WebSocket opcode {:#x}", opcode).into()),
+        }
+        if message.as_ref().map(|(_, data)| data.len() as u64 > MAX_MESSAGE_SIZE).unwrap_or(false) {
+            return Err(format!("A WebSocket message is larger than {} bytes", MAX_MESSAGE_SIZE).into());
+        }
+        if fin {
+            return match message.take() {
+                Some((OP_TEXT, data)) => Ok(Message::Text(String::from_utf8(data)?)),
+                Some((_, data)) => Ok(Message::Binary(data)),
+                None => Err("A WebSocket frame ended no message".into()),
+            };
+        }
+    }
+}
+
+/// Reads a frame of the client and unmasks its payload
+fn read_frame<R: Read>(reader: &mut R) -> Result<(bool, u8, Vec<u8>), Box<dyn Error>> {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head)?;
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0F;
+    if head[1] & 0x80 == 0 {
+        return Err("A WebSocket frame of the client isn't masked".into());
+    }
+    let length = match head[1] & 0x7F {
+        126 => {
+            let mut bytes = [0u8; 2];
+            reader.read_exact(&mut bytes)?;
+            u16::from_be_bytes(bytes) as u64
+        }
+        127 => {
+            let mut bytes = [0u8; 8];
+            reader.read_exact(&mut bytes)?;
+            u64::from_be_bytes(bytes)
+        }
+        length => length as u64,
+    };
+    if length > MAX_MESSAGE_SIZE {
+        return Err(format!("A WebSocket message is larger than {} bytes", MAX_MESSAGE_SIZE).into());
+    }
+    let mut mask = [0u8; 4];
+    reader.read_exact(&mut mask)?;
+    let mut payload = vec![0u8; length as usize];
+    reader.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((fin, opcode, payload))
+}
@@ -0,0 +1,344 @@
+//! Minimal non-validating XML parser producing an element tree.
+//!
+//! Handles elements, attributes, text, CDATA and the predefined / numeric entities. Comments,
+//! processing instructions and doctype declarations are skipped.
+
+use crate::errors::xml::XmlParseError;
+
+/// Deepest nesting of elements accepted, the parser recurses once per level
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlElement {
+    /// Full name of the element including any namespace prefix
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    /// Name of the element without its namespace prefix
+    pub fn local_name(&self) -> &str {
+        local(&self.name)
+    }
+
+    /// Value of the attribute called 'name', ignoring namespace prefixes
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|(k, _)| k == name || local(k) == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All child elements
+    pub fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|c| match c {
+            XmlNode::Element(e) => Some(e),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    /// Child elements with the local name 'name'
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> {
+        self.elements().filter(move |e| e.local_name() == name)
+    }
+
+    /// First child element with the local name 'name'
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.elements().find(|e| e.local_name() == name)
+    }
+
+    /// Concatenated text of the element and all its descendants
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for child in &self.children {
+            match child {
+                XmlNode::Text(t) => out.push_str(t),
+                XmlNode::Element(e) => out.push_str(&e.text()),
+            }
+        }
+        out
+    }
+}
+
+fn local(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Parses a document and returns its root element
+///
+/// # Errors
+///
+/// Returns a ['XmlParseError'] if the document is not well-formed
+pub fn parse(text: &str) -> Result<XmlElement, XmlParseError> {
+    let mut parser = Parser { text: text.trim_start_matches('\u{feff}'), pos: 0 };
+    parser.skip_misc()?;
+    if parser.rest().is_empty() {
+        return Err(parser.error("no root element"));
+    }
+    if !parser.rest().starts_with('<') {
+        return Err(parser.error("text before the root element"));
+    }
+    let root = parser.parse_element(0)?;
+    parser.skip_misc()?;
+    if !parser.rest().trim().is_empty() {
+        return Err(parser.error("content after the root element"));
+    }
+    Ok(root)
+}
+
+/// Replaces the predefined and numeric character references in 'text'
+pub fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let end = match rest.find(';') {
+            None => break,
+            Some(e) => e,
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse::<u32>().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escapes the characters that can't appear as is in text or attribute values
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn error(&self, reason: &str) -> XmlParseError {
+        XmlParseError { pos: self.pos, reason: reason.to_string() }
+    }
+
+    fn skip_to(&mut self, end: &str) -> Result<(), XmlParseError> {
+        match self.rest().find(end) {
+            None => Err(self.error(&format!("missing '{}'", end))),
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips whitespace, comments, processing instructions and doctype declarations
+    fn skip_misc(&mut self) -> Result<(), XmlParseError> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_to("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_to("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.skip_to(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String, XmlParseError> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '=')).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += len;
+        Ok(rest[..len].to_string())
+    }
+
+    fn parse_element(&mut self, depth: usize) -> Result<XmlElement, XmlParseError> {
+        if depth == MAX_DEPTH {
+            return Err(self.error("elements nested too deeply"));
+        }
+        self.pos += 1;
+        let name = self.parse_name()?;
+        let mut element = XmlElement { name, attributes: vec![], children: vec![] };
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = self.parse_name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("expected '=' after attribute name"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = self.rest().chars().next().ok_or_else(|| self.error("unexpected end of document"))?;
+            if quote != '"' && quote != '\'' {
+                return Err(self.error("expected a quoted attribute value"));
+            }
+            self.pos += 1;
+            let end = self.rest().find(quote).ok_or_else(|| self.error("unterminated attribute value"))?;
+            let value = unescape(&self.rest()[..end]);
+            self.pos += end + 1;
+            element.attributes.push((key, value));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error(&format!("element '{}' is not closed", element.name)));
+            }
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let closing = self.parse_name()?;
+                if closing != element.name {
+                    return Err(self.error(&format!("expected '</{}>' but found '</{}>'", element.name, closing)));
+                }
+                self.skip_whitespace();
+                self.skip_to(">")?;
+                return Ok(element);
+            }
+            if rest.starts_with("<!--") || rest.starts_with("<?") {
+                self.skip_misc()?;
+                continue;
+            }
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").ok_or_else(|| self.error("unterminated CDATA section"))?;
+                element.children.push(XmlNode::Text(cdata[..end].to_string()));
+                self.pos += 9 + end + 3;
+                continue;
+            }
+            if rest.starts_with('<') {
+                let child = self.parse_element(depth + 1)?;
+                element.children.push(XmlNode::Element(child));
+                continue;
+            }
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = unescape(&rest[..end]);
+            self.pos += end;
+            if !text.trim().is_empty() {
+                element.children.push(XmlNode::Text(text));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = "<?xml version=\"1.0\"?>\n<!-- links -->\n<x:sheet xmlns:x=\"urn:s\">\
+        <x:row r='1'><x:c t=\"s\"><x:v>0</x:v></x:c><x:c><x:v>1 &amp; 2</x:v></x:c></x:row>\
+        <x:row r=\"2\"/><![CDATA[<raw>]]></x:sheet>";
+
+    #[test]
+    fn documents_parse_into_their_tree() {
+        let root = parse(SHEET).unwrap();
+        assert_eq!((root.name.as_str(), root.local_name()), ("x:sheet", "sheet"));
+        let rows: Vec<&XmlElement> = root.children_named("row").collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].attr("r"), Some("1"));
+        let cells: Vec<&XmlElement> = rows[0].children_named("c").collect();
+        assert_eq!(cells[0].attr("t"), Some("s"));
+        assert_eq!(cells[1].child("v").unwrap().text(), "1 & 2");
+        assert!(rows[1].children.is_empty());
+        assert_eq!(root.text(), "01 & 2<raw>");
+    }
+
+    #[test]
+    fn escaped_text_reads_back_the_same() {
+        let text = "a < b & \"c\" > 'd' \u{e9}";
+        let root = parse(&format!("<v k=\"{}\">{}</v>", escape(text), escape(text))).unwrap();
+        assert_eq!((root.attr("k"), root.text()), (Some(text), text.to_string()));
+        assert_eq!(unescape("&#65;&#x42;&bogus; & &#xFFFFFFFF;"), "AB&bogus; & &#xFFFFFFFF;");
+    }
+
+    #[test]
+    fn truncated_documents_are_errors() {
+        for (end, _) in SHEET.char_indices().skip(1) {
+            assert!(parse(&SHEET[..end]).is_err(), "cut at {}", end);
+        }
+    }
+
+    #[test]
+    fn malformed_documents_are_errors() {
+        for (document, reason) in [
+            ("", "no root element"),
+            ("text<a/>", "text before the root element"),
+            ("<a></b>", "expected '</a>' but found '</b>'"),
+            ("<a/><b/>", "content after the root element"),
+            ("<a k=v/>", "expected a quoted attribute value"),
+            ("<a k/>", "expected '=' after attribute name"),
+            ("<a k='v/>", "unterminated attribute value"),
+            ("<a><![CDATA[x</a>", "unterminated CDATA section"),
+            ("<a><!-- x</a>", "missing '-->'"),
+        ] {
+            assert_eq!(parse(document).unwrap_err().reason, reason, "{}", document);
+        }
+    }
+
+    #[test]
+    fn deep_nesting_is_refused_instead_of_overflowing_the_stack() {
+        let nested = |depth: usize| format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        let error = parse(&nested(100_000)).unwrap_err();
+        assert_eq!(error.reason, "elements nested too deeply");
+    }
+}
@@ -0,0 +1,224 @@
+//! Reader for zip archives, as used by office documents.
+//!
+//! Supports stored and deflated entries. Zip64 archives, encryption and multi-disk archives are
+//! not supported.
+
+use std::error::Error;
+use std::fs;
+use crate::errors::compression::ZipError;
+use crate::inflate;
+
+const END_OF_CENTRAL_DIR_SIG: u32 = 0x06054b50;
+const CENTRAL_DIR_SIG: u32 = 0x02014b50;
+const LOCAL_HEADER_SIG: u32 = 0x04034b50;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+
+#[derive(Debug, Clone)]
+struct ZipEntry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    size: usize,
+    header_offset: usize,
+}
+
+/// An archive which has been read into memory
+pub struct ZipArchive {
+    data: Vec<u8>,
+    entries: Vec<ZipEntry>,
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16, ZipError> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| ZipError { reason: "unexpected end of archive".to_string() })
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, ZipError> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| ZipError { reason: "unexpected end of archive".to_string() })
+}
+
+impl ZipArchive {
+    /// Reads the archive at 'path'
+    ///
+    /// # Errors
+    ///
+    /// Returns an io error if the file can't be read, or a ['ZipError'] if it isn't a valid archive
+    pub fn open(path: &str) -> Result<ZipArchive, Box<dyn Error>> {
+        Ok(ZipArchive::from_bytes(fs::read(path)?)?)
+    }
+
+    /// Parses the central directory of an archive held in memory
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ZipError'] if the data isn't a valid archive
+    pub fn from_bytes(data: Vec<u8>) -> Result<ZipArchive, ZipError> {
+        // The end of central directory record is at least 22 bytes and may be followed by a comment
+        let min_start = data.len().saturating_sub(22 + u16::MAX as usize);
+        let end = (min_start..data.len().saturating_sub(21)).rev()
+            .find(|i| read_u32(&data, *i).ok() == Some(END_OF_CENTRAL_DIR_SIG))
+            .ok_or_else(|| ZipError { reason: "no end of central directory record".to_string() })?;
+        let entry_count = read_u16(&data, end + 10)? as usize;
+        let mut pos = read_u32(&data, end + 16)? as usize;
+
+        let mut entries = vec![];
+        for _ in 0..entry_count {
+            if read_u32(&data, pos)? != CENTRAL_DIR_SIG {
+                return Err(ZipError { reason: "invalid central directory entry".to_string() });
+            }
+            let method = read_u16(&data, pos + 10)?;
+            let compressed_size = read_u32(&data, pos + 20)? as usize;
+            let size = read_u32(&data, pos + 24)? as usize;
+            let name_len = read_u16(&data, pos + 28)? as usize;
+            let extra_len = read_u16(&data, pos + 30)? as usize;
+            let comment_len = read_u16(&data, pos + 32)? as usize;
+            let header_offset = read_u32(&data, pos + 42)? as usize;
+            let name = data.get(pos + 46..pos + 46 + name_len)
+                .map(|n| String::from_utf8_lossy(n).to_string())
+                .ok_or_else(|| ZipError { reason: "unexpected end of archive".to_string() })?;
+            entries.push(ZipEntry { name, method, compressed_size, size, header_offset });
+            pos += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(ZipArchive { data, entries })
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    /// Returns the decompressed content of the entry called 'name', or None if there is no such
+    /// entry
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ZipError'] if the entry is corrupt or uses an unsupported compression method
+    pub fn read(&self, name: &str) -> Result<Option<Vec<u8>>, ZipError> {
+        let entry = match self.entries.iter().find(|e| e.name == name) {
+            None => return Ok(None),
+            Some(e) => e,
+        };
+        let pos = entry.header_offset;
+        if read_u32(&self.data, pos)? != LOCAL_HEADER_SIG {
+            return Err(ZipError { reason: format!("invalid local header for {}", name) });
+        }
+        let name_len = read_u16(&self.data, pos + 26)? as usize;
+        let extra_len = read_u16(&self.data, pos + 28)? as usize;
+        let start = pos + 30 + name_len + extra_len;
+        let raw = self.data.get(start..start + entry.compressed_size)
+            .ok_or_else(|| ZipError { reason: format!("truncated entry {}", name) })?;
+        // The declared size bounds the content, so a corrupt entry can't inflate without limit
+        let content = match entry.method {
+            METHOD_STORED => raw.to_vec(),
+            METHOD_DEFLATE => inflate::inflate_limited(raw, entry.size)
+                .map_err(|e| ZipError { reason: format!("entry {}: {}", name, e) })?,
+            method => return Err(ZipError { reason: format!("unsupported compression method {} for {}", method, name) }),
+        };
+        if content.len() != entry.size {
+            return Err(ZipError { reason: format!("entry {} is not its declared size", name) });
+        }
+        Ok(Some(content))
+    }
+
+    /// Same as ['ZipArchive::read'] but converts the content to a string
+    ///
+    /// # Errors
+    ///
+    /// Returns a ['ZipError'] if the entry can't be read
+    pub fn read_string(&self, name: &str) -> Result<Option<String>, ZipError> {
+        Ok(self.read(name)?.map(|bytes| String::from_utf8_lossy(&bytes).to_string()))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// "hello hello hello" compressed with the fixed deflate codes
+    const HELLO: [u8; 10] = [0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00];
+
+    /// Writes an archive of (name, method, raw data, declared size) entries
+    pub(crate) fn archive(entries: &[(&str, u16, &[u8], u32)]) -> Vec<u8> {
+        let mut data = vec![];
+        let mut central = vec![];
+        for (name, method, raw, size) in entries {
+            let offset = data.len() as u32;
+            data.extend_from_slice(&LOCAL_HEADER_SIG.to_le_bytes());
+            data.extend_from_slice(&[0; 22]);
+            data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            data.extend_from_slice(&[0; 2]);
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(raw);
+
+            central.extend_from_slice(&CENTRAL_DIR_SIG.to_le_bytes());
+            central.extend_from_slice(&[0; 6]);
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            central.extend_from_slice(&(raw.len() as u32).to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let central_offset = data.len() as u32;
+        data.extend_from_slice(&central);
+        data.extend_from_slice(&END_OF_CENTRAL_DIR_SIG.to_le_bytes());
+        data.extend_from_slice(&[0; 6]);
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        data.extend_from_slice(&central_offset.to_le_bytes());
+        data.extend_from_slice(&[0; 2]);
+        data
+    }
+
+    #[test]
+    fn stored_and_deflated_entries_are_read() {
+        let zip = ZipArchive::from_bytes(archive(&[
+            ("a.txt", METHOD_STORED, b"stored", 6),
+            ("xl/b.xml", METHOD_DEFLATE, &HELLO, 17),
+        ])).unwrap();
+        assert_eq!(zip.names(), vec!["a.txt", "xl/b.xml"]);
+        assert_eq!(zip.read_string("a.txt").unwrap().unwrap(), "stored");
+        assert_eq!(zip.read_string("xl/b.xml").unwrap().unwrap(), "hello hello hello");
+        assert!(zip.read("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn truncated_archives_are_errors() {
+        let data = archive(&[("a.txt", METHOD_STORED, b"stored", 6)]);
+        for end in 0..data.len() {
+            let read = ZipArchive::from_bytes(data[..end].to_vec()).and_then(|zip| zip.read("a.txt"));
+            assert!(read.is_err(), "cut at {}", end);
+        }
+    }
+
+    #[test]
+    fn entries_must_hold_their_declared_size() {
+        let zip = ZipArchive::from_bytes(archive(&[
+            ("bomb", METHOD_DEFLATE, &HELLO, 5),
+            ("short", METHOD_STORED, b"stored", 100),
+            ("bzip", 12, b"stored", 6),
+        ])).unwrap();
+        assert!(zip.read("bomb").unwrap_err().reason.contains("content larger than declared"));
+        assert_eq!(zip.read("short").unwrap_err().reason, "entry short is not its declared size");
+        assert_eq!(zip.read("bzip").unwrap_err().reason, "unsupported compression method 12 for bzip");
+
+        // A compressed size past the end of the archive
+        let mut data = archive(&[("a.txt", METHOD_STORED, b"stored", 6)]);
+        let central = data.len() - 22 - 46 - 5;
+        data[central + 20..central + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        let zip = ZipArchive::from_bytes(data).unwrap();
+        assert_eq!(zip.read("a.txt").unwrap_err().reason, "truncated entry a.txt");
+    }
+
+    #[test]
+    fn data_without_an_end_record_is_not_an_archive() {
+        let error = ZipArchive::from_bytes(b"from,to\n0,1\n".to_vec()).err().unwrap();
+        assert_eq!(error.reason, "no end of central directory record");
+    }
+}
@@ -0,0 +1,724 @@
+//! Decoder for Zstandard files (RFC 8878).
+//!
+//! Supports every frame written without a dictionary, including skippable frames and frames
+//! with a content checksum. Only decompression is supported.
+
+use crate::checksum::xxh64;
+use crate::errors::compression::ZstdError;
+
+/// First four bytes of every zstd frame
+pub const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+const FRAME_MAGIC: u32 = 0xFD2F_B528;
+/// Skippable frames use any magic number with these upper 28 bits
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+
+const BLOCK_RAW: u8 = 0;
+const BLOCK_RLE: u8 = 1;
+const BLOCK_COMPRESSED: u8 = 2;
+
+const LITERALS_RAW: u8 = 0;
+const LITERALS_RLE: u8 = 1;
+const LITERALS_COMPRESSED: u8 = 2;
+
+const MODE_PREDEFINED: u8 = 0;
+const MODE_RLE: u8 = 1;
+const MODE_COMPRESSED: u8 = 2;
+
+/// Largest content of a block, whatever the size of the window
+const MAX_BLOCK_SIZE: usize = 128 * 1024;
+const MAX_HUFFMAN_BITS: u32 = 11;
+const MAX_WEIGHT_ACCURACY: u32 = 6;
+const MAX_LITERALS_ACCURACY: u32 = 9;
+const MAX_MATCH_ACCURACY: u32 = 9;
+const MAX_OFFSET_ACCURACY: u32 = 8;
+
+const LITERALS_DEFAULT: [i16; 36] = [4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2,
+    1, 1, 1, 1, 1, -1, -1, -1, -1];
+const MATCH_DEFAULT: [i16; 53] = [1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1];
+const OFFSET_DEFAULT: [i16; 29] = [1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1,
+    -1, -1, -1];
+
+/// (baseline, extra bits) of every literals length code
+const LITERALS_CODES: [(u32, u32); 36] = [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0),
+    (9, 0), (10, 0), (11, 0), (12, 0), (13, 0), (14, 0), (15, 0), (16, 1), (18, 1), (20, 1), (22, 1), (24, 2),
+    (28, 2), (32, 3), (40, 3), (48, 4), (64, 6), (128, 7), (256, 8), (512, 9), (1024, 10), (2048, 11), (4096, 12),
+    (8192, 13), (16384, 14), (32768, 15), (65536, 16)];
+/// (baseline, extra bits) of every match length code
+const MATCH_CODES: [(u32, u32); 53] = [(3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0), (11, 0),
+    (12, 0), (13, 0), (14, 0), (15, 0), (16, 0), (17, 0), (18, 0), (19, 0), (20, 0), (21, 0), (22, 0), (23, 0),
+    (24, 0), (25, 0), (26, 0), (27, 0), (28, 0), (29, 0), (30, 0), (31, 0), (32, 0), (33, 0), (34, 0), (35, 1),
+    (37, 1), (39, 1), (41, 1), (43, 2), (47, 2), (51, 3), (59, 3), (67, 4), (83, 4), (99, 5), (131, 7), (259, 8),
+    (515, 9), (1027, 10), (2051, 11), (4099, 12), (8195, 13), (16387, 14), (32771, 15), (65539, 16)];
+
+fn error(reason: &str) -> ZstdError {
+    ZstdError { reason: reason.to_string() }
+}
+
+/// Decompresses every frame of a zstd file
+///
+/// # Errors
+///
+/// Returns a ['ZstdError'] if a frame is truncated, malformed, fails its checksum or needs a
+/// dictionary
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, ZstdError> {
+    let mut out = vec![];
+    let mut pos = 0;
+    while pos < data.len() {
+        let magic = read_le(data, pos, 4)? as u32;
+        pos += 4;
+        if magic & 0xFFFF_FFF0 == SKIPPABLE_MAGIC {
+            pos += 4 + read_le(data, pos, 4)? as usize;
+            if pos > data.len() {
+                return Err(error("truncated skippable frame"));
+            }
+        } else if magic == FRAME_MAGIC {
+            pos = frame(data, pos, &mut out)?;
+        } else {
+            return Err(error("unknown frame magic number"));
+        }
+    }
+    Ok(out)
+}
+
+/// Little endian number of 'len' bytes at 'pos'
+fn read_le(data: &[u8], pos: usize, len: usize) -> Result<u64, ZstdError> {
+    let bytes = data.get(pos..pos + len).ok_or_else(|| error("unexpected end of stream"))?;
+    Ok(bytes.iter().rev().fold(0, |value, b| (value << 8) | *b as u64))
+}
+
+/// Decompresses the frame whose header starts at 'pos' into 'out' and returns the position after
+/// the frame
+fn frame(data: &[u8], mut pos: usize, out: &mut Vec<u8>) -> Result<usize, ZstdError> {
+    let descriptor = read_le(data, pos, 1)? as u8;
+    pos += 1;
+    let single_segment = descriptor & 0x20 != 0;
+    let has_checksum = descriptor & 0x04 != 0;
+    if descriptor & 0x08 != 0 {
+        return Err(error("reserved frame header bit is set"));
+    }
+    if !single_segment {
+        // Window descriptor, the whole output stays in memory
+        pos += 1;
+    }
+    let dictionary_len = [0, 1, 2, 4][(descriptor & 0x03) as usize];
+    if read_le(data, pos, dictionary_len)? != 0 {
+        return Err(error("frames using a dictionary are not supported"));
+    }
+    pos += dictionary_len;
+    let content_size_len = match descriptor >> 6 {
+        0 if single_segment => 1,
+        0 => 0,
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    // The two byte size is stored minus 256
+    let content_size = match content_size_len {
+        0 => None,
+        2 => Some(read_le(data, pos, 2)? + 256),
+        len => Some(read_le(data, pos, len)?),
+    };
+    pos += content_size_len;
+
+    let start = out.len();
+    let mut state = FrameState::new();
+    loop {
+        let header = read_le(data, pos, 3)? as u32;
+        pos += 3;
+        let last = header & 1 == 1;
+        let size = (header >> 3) as usize;
+        if size > MAX_BLOCK_SIZE {
+            return Err(error("block larger than the maximum block size"));
+        }
+        match ((header >> 1) & 3) as u8 {
+            BLOCK_RAW => {
+                out.extend_from_slice(data.get(pos..pos + size).ok_or_else(|| error("truncated raw block"))?);
+                pos += size;
+            }
+            BLOCK_RLE => {
+                let byte = read_le(data, pos, 1)? as u8;
+                out.resize(out.len() + size, byte);
+                pos += 1;
+            }
+            BLOCK_COMPRESSED => {
+                let block = data.get(pos..pos + size).ok_or_else(|| error("truncated compressed block"))?;
+                state.block(block, out, start)?;
+                pos += size;
+            }
+            _ => return Err(error("reserved block type")),
+        }
+        if last {
+            break;
+        }
+    }
+    // Checked after decoding, so a huge declared size is never allocated up front
+    if content_size.is_some_and(|size| size != (out.len() - start) as u64) {
+        return Err(error("content size mismatch"));
+    }
+    if has_checksum {
+        let checksum = read_le(data, pos, 4)? as u32;
+        if xxh64(&out[start..], 0) as u32 != checksum {
+            return Err(error("checksum mismatch"));
+        }
+        pos += 4;
+    }
+    Ok(pos)
+}
+
+/// Reads a bitstream from its first byte on, least significant bit first. Bits past the end
+/// read as zero, ['ForwardBits::position'] tells whether the end was passed.
+struct ForwardBits<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl<'a> ForwardBits<'a> {
+    fn bits(&mut self, count: u32) -> u32 {
+        let value = bits_at(self.data, self.bit, count);
+        self.bit += count as usize;
+        value as u32
+    }
+
+    /// Bytes used so far, counting the partially read byte
+    fn position(&self) -> usize {
+        self.bit.div_ceil(8)
+    }
+}
+
+/// Reads a bitstream backwards from the padding marker in its last byte. Reading past the start
+/// gives zeros, and the stream is consumed exactly once the position reaches zero.
+struct BackwardBits<'a> {
+    data: &'a [u8],
+    bit: i64,
+}
+
+impl<'a> BackwardBits<'a> {
+    fn new(data: &'a [u8]) -> Result<BackwardBits<'a>, ZstdError> {
+        match data.last() {
+            Some(last) if *last != 0 => {
+                let padding = last.leading_zeros() as i64 + 1;
+                Ok(BackwardBits { data, bit: data.len() as i64 * 8 - padding })
+            }
+            _ => Err(error("bitstream without an end marker")),
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> u64 {
+        self.bit -= count as i64;
+        match self.bit >= 0 {
+            true => bits_at(self.data, self.bit as usize, count),
+            false => {
+                let available = count as i64 + self.bit;
+                match available > 0 {
+                    true => bits_at(self.data, 0, available as u32) << (-self.bit),
+                    false => 0,
+                }
+            }
+        }
+    }
+}
+
+/// The 'count' bits of 'data' starting at bit 'bit', at most 56
+fn bits_at(data: &[u8], bit: usize, count: u32) -> u64 {
+    if count == 0 {
+        return 0;
+    }
+    let start = bit / 8;
+    let mut word = 0u64;
+    for (i, byte) in data.iter().skip(start).take(8).enumerate() {
+        word |= (*byte as u64) << (8 * i);
+    }
+    (word >> (bit % 8)) & ((1u64 << count) - 1)
+}
+
+/// Finite state entropy decoding table
+#[derive(Clone)]
+struct FseTable {
+    accuracy: u32,
+    symbols: Vec<u8>,
+    bits: Vec<u8>,
+    baselines: Vec<u16>,
+}
+
+impl FseTable {
+    /// Table of a single symbol, used for every state
+    fn rle(symbol: u8) -> FseTable {
+        FseTable { accuracy: 0, symbols: vec![symbol], bits: vec![0], baselines: vec![0] }
+    }
+
+    /// Reads the normalised counts at the start of 'data' and returns the table with the number
+    /// of bytes they used
+    fn read(data: &[u8], max_accuracy: u32) -> Result<(FseTable, usize), ZstdError> {
+        let mut reader = ForwardBits { data, bit: 0 };
+        let accuracy = reader.bits(4) + 5;
+        if accuracy > max_accuracy {
+            return Err(error("table accuracy too large"));
+        }
+        let mut remaining: i32 = 1 << accuracy;
+        let mut counts: Vec<i16> = vec![];
+        while remaining > 0 && counts.len() < 256 {
+            let bits = 32 - ((remaining + 1) as u32).leading_zeros();
+            let mut value = reader.bits(bits);
+            let low_mask = (1u32 << (bits - 1)) - 1;
+            let threshold = (1u32 << bits) - 1 - (remaining as u32 + 1);
+            if value & low_mask < threshold {
+                reader.bit -= 1;
+                value &= low_mask;
+            } else if value > low_mask {
+                value -= threshold;
+            }
+            let count = value as i16 - 1;
+            remaining -= count.unsigned_abs() as i32;
+            counts.push(count);
+            if count == 0 {
+                loop {
+                    let repeat = reader.bits(2);
+                    counts.resize(counts.len() + repeat as usize, 0);
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+            }
+        }
+        if remaining != 0 || counts.len() > 256 || reader.position() > data.len() {
+            return Err(error("invalid table description"));
+        }
+        Ok((FseTable::new(&counts, accuracy)?, reader.position()))
+    }
+
+    fn new(counts: &[i16], accuracy: u32) -> Result<FseTable, ZstdError> {
+        let size = 1usize << accuracy;
+        let mut symbols = vec![0u8; size];
+        let mut next: Vec<u32> = vec![0; counts.len()];
+        // Symbols with a "less than one" count take a cell each from the end of the table
+        let mut high = size;
+        for (symbol, count) in counts.iter().enumerate() {
+            if *count == -1 {
+                high -= 1;
+                symbols[high] = symbol as u8;
+                next[symbol] = 1;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut pos = 0;
+        for (symbol, count) in counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+            next[symbol] = *count as u32;
+            for _ in 0..*count {
+                symbols[pos] = symbol as u8;
+                pos = (pos + step) & (size - 1);
+                while pos >= high {
+                    pos = (pos + step) & (size - 1);
+                }
+            }
+        }
+        if pos != 0 {
+            return Err(error("invalid table counts"));
+        }
+        let mut bits = vec![0u8; size];
+        let mut baselines = vec![0u16; size];
+        for i in 0..size {
+            let state = &mut next[symbols[i] as usize];
+            bits[i] = (accuracy - (31 - state.leading_zeros())) as u8;
+            baselines[i] = ((*state << bits[i]) as usize - size) as u16;
+            *state += 1;
+        }
+        Ok(FseTable { accuracy, symbols, bits, baselines })
+    }
+
+    fn init(&self, reader: &mut BackwardBits) -> usize {
+        reader.bits(self.accuracy) as usize
+    }
+
+    fn update(&self, state: usize, reader: &mut BackwardBits) -> usize {
+        self.baselines[state] as usize + reader.bits(self.bits[state] as u32) as usize
+    }
+}
+
+/// Huffman decoding table of the literals
+#[derive(Clone)]
+struct HuffmanTable {
+    max_bits: u32,
+    symbols: Vec<u8>,
+    bits: Vec<u8>,
+}
+
+impl HuffmanTable {
+    /// Reads the tree description at the start of 'data' and returns the table with the number of
+    /// bytes it used
+    fn read(data: &[u8]) -> Result<(HuffmanTable, usize), ZstdError> {
+        let header = *data.first().ok_or_else(|| error("missing huffman tree"))? as usize;
+        let (mut weights, used) = match header < 128 {
+            true => {
+                let compressed = data.get(1..1 + header).ok_or_else(|| error("truncated huffman tree"))?;
+                (fse_weights(compressed)?, 1 + header)
+            }
+            false => {
+                let count = header - 127;
+                let packed = data.get(1..1 + count.div_ceil(2)).ok_or_else(|| error("truncated huffman tree"))?;
+                let weights = (0..count).map(|i| match i % 2 {
+                    0 => packed[i / 2] >> 4,
+                    _ => packed[i / 2] & 0x0f,
+                }).collect();
+                (weights, 1 + count.div_ceil(2))
+            }
+        };
+
+        // The weight of the last symbol completes the sum of the others to a power of two
+        if weights.iter().any(|w| *w as u32 > MAX_HUFFMAN_BITS) {
+            return Err(error("invalid huffman weights"));
+        }
+        let sum: u32 = weights.iter().filter(|w| **w > 0).map(|w| 1u32 << (w - 1)).sum();
+        if sum == 0 || weights.len() > 255 {
+            return Err(error("invalid huffman weights"));
+        }
+        let max_bits = 32 - sum.leading_zeros();
+        let left = (1u32 << max_bits) - sum;
+        if !left.is_power_of_two() || max_bits > MAX_HUFFMAN_BITS {
+            return Err(error("invalid huffman weights"));
+        }
+        weights.push(left.trailing_zeros() as u8 + 1);
+
+        let code_bits: Vec<u32> = weights.iter()
+            .map(|w| if *w > 0 { max_bits + 1 - *w as u32 } else { 0 })
+            .collect();
+        let mut rank_counts = vec![0usize; max_bits as usize + 1];
+        for b in code_bits.iter().filter(|b| **b > 0) {
+            rank_counts[*b as usize] += 1;
+        }
+        // Longer codes take the lower positions of the table
+        let mut starts = vec![0usize; max_bits as usize + 1];
+        for b in (1..max_bits as usize).rev() {
+            starts[b] = starts[b + 1] + rank_counts[b + 1] * (1 << (max_bits as usize - b - 1));
+        }
+        let size = 1usize << max_bits;
+        let mut symbols = vec![0u8; size];
+        let mut bits = vec![0u8; size];
+        for (symbol, b) in code_bits.iter().enumerate().filter(|(_, b)| **b > 0) {
+            let len = 1usize << (max_bits - b);
+            let start = starts[*b as usize];
+            symbols[start..start + len].fill(symbol as u8);
+            bits[start..start + len].fill(*b as u8);
+            starts[*b as usize] += len;
+        }
+        Ok((HuffmanTable { max_bits, symbols, bits }, used))
+    }
+
+    /// Decodes 'count' literals from a single stream
+    fn stream(&self, data: &[u8], count: usize, out: &mut Vec<u8>) -> Result<(), ZstdError> {
+        let mut reader = BackwardBits::new(data)?;
+        let mask = (1usize << self.max_bits) - 1;
+        let mut state = reader.bits(self.max_bits) as usize;
+        for _ in 0..count {
+            out.push(self.symbols[state]);
+            let b = self.bits[state] as u32;
+            state = ((state << b) | reader.bits(b) as usize) & mask;
+        }
+        match reader.bit == -(self.max_bits as i64) {
+            true => Ok(()),
+            false => Err(error("literals stream size mismatch")),
+        }
+    }
+}
+
+/// Huffman weights compressed with a finite state entropy table, decoded by two interleaved states
+fn fse_weights(data: &[u8]) -> Result<Vec<u8>, ZstdError> {
+    let (table, used) = FseTable::read(data, MAX_WEIGHT_ACCURACY)?;
+    let mut reader = BackwardBits::new(&data[used..])?;
+    let mut states = [table.init(&mut reader), table.init(&mut reader)];
+    let mut weights = vec![];
+    for turn in 0.. {
+        if weights.len() > 255 {
+            return Err(error("too many huffman weights"));
+        }
+        let current = turn % 2;
+        weights.push(table.symbols[states[current]]);
+        states[current] = table.update(states[current], &mut reader);
+        if reader.bit < 0 {
+            weights.push(table.symbols[states[1 - current]]);
+            break;
+        }
+    }
+    Ok(weights)
+}
+
+/// Tables and offsets carried from one block of a frame to the next
+struct FrameState {
+    huffman: Option<HuffmanTable>,
+    literals_table: Option<FseTable>,
+    offsets_table: Option<FseTable>,
+    match_table: Option<FseTable>,
+    repeat_offsets: [usize; 3],
+}
+
+impl FrameState {
+    fn new() -> FrameState {
+        FrameState { huffman: None, literals_table: None, offsets_table: None, match_table: None, repeat_offsets: [1, 4, 8] }
+    }
+
+    /// Decompresses a compressed block into 'out', whose frame starts at 'frame_start'
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>, frame_start: usize) -> Result<(), ZstdError> {
+        let limit = out.len() + MAX_BLOCK_SIZE;
+        let (literals, used) = self.literals(block)?;
+        let rest = &block[used..];
+
+        let first = *rest.first().ok_or_else(|| error("missing sequences section"))? as usize;
+        let (count, mut pos) = match first {
+            0..=127 => (first, 1),
+            128..=254 => (((first - 128) << 8) + read_le(rest, 1, 1)? as usize, 2),
+            _ => (read_le(rest, 1, 2)? as usize + 0x7F00, 3),
+        };
+        if count == 0 {
+            out.extend_from_slice(&literals);
+            return Ok(());
+        }
+        let modes = read_le(rest, pos, 1)? as u8;
+        pos += 1;
+        pos += table(&mut self.literals_table, modes >> 6, &rest[pos..], &LITERALS_DEFAULT, 6, MAX_LITERALS_ACCURACY)?;
+        pos += table(&mut self.offsets_table, (modes >> 4) & 3, &rest[pos..], &OFFSET_DEFAULT, 5, MAX_OFFSET_ACCURACY)?;
+        pos += table(&mut self.match_table, (modes >> 2) & 3, &rest[pos..], &MATCH_DEFAULT, 6, MAX_MATCH_ACCURACY)?;
+        let (Some(ll_table), Some(of_table), Some(ml_table)) = (&self.literals_table, &self.offsets_table, &self.match_table) else {
+            return Err(error("repeated table without a previous one"));
+        };
+
+        let mut reader = BackwardBits::new(&rest[pos..])?;
+        let mut ll_state = ll_table.init(&mut reader);
+        let mut of_state = of_table.init(&mut reader);
+        let mut ml_state = ml_table.init(&mut reader);
+        let mut literal_pos = 0;
+        for i in 0..count {
+            let of_code = of_table.symbols[of_state] as u32;
+            let ml_code = ml_table.symbols[ml_state] as usize;
+            let ll_code = ll_table.symbols[ll_state] as usize;
+            if of_code > 31 || ml_code >= MATCH_CODES.len() || ll_code >= LITERALS_CODES.len() {
+                return Err(error("invalid sequence code"));
+            }
+            let offset_value = (1usize << of_code) + reader.bits(of_code) as usize;
+            let (ml_base, ml_bits) = MATCH_CODES[ml_code];
+            let match_len = ml_base as usize + reader.bits(ml_bits) as usize;
+            let (ll_base, ll_bits) = LITERALS_CODES[ll_code];
+            let literals_len = ll_base as usize + reader.bits(ll_bits) as usize;
+            if i + 1 < count {
+                ll_state = ll_table.update(ll_state, &mut reader);
+                ml_state = ml_table.update(ml_state, &mut reader);
+                of_state = of_table.update(of_state, &mut reader);
+            }
+
+            if out.len() + literals_len + match_len > limit {
+                return Err(error("block larger than the maximum block size"));
+            }
+            let offset = resolve_offset(&mut self.repeat_offsets, offset_value, literals_len);
+            let copied = literals.get(literal_pos..literal_pos + literals_len).ok_or_else(|| error("not enough literals"))?;
+            out.extend_from_slice(copied);
+            literal_pos += literals_len;
+            if offset == 0 || offset > out.len() - frame_start {
+                return Err(error("match offset too far back"));
+            }
+            let start = out.len() - offset;
+            for k in 0..match_len {
+                out.push(out[start + k]);
+            }
+        }
+        if reader.bit != 0 {
+            return Err(error("sequences stream size mismatch"));
+        }
+        out.extend_from_slice(&literals[literal_pos..]);
+        Ok(())
+    }
+
+    /// Decodes the literals section at the start of a block and returns the literals with the
+    /// size of the section
+    fn literals(&mut self, block: &[u8]) -> Result<(Vec<u8>, usize), ZstdError> {
+        let first = *block.first().ok_or_else(|| error("missing literals section"))?;
+        let kind = first & 3;
+        let size_format = (first >> 2) & 3;
+        if kind == LITERALS_RAW || kind == LITERALS_RLE {
+            let (size, header) = match size_format {
+                0 | 2 => ((first >> 3) as usize, 1),
+                1 => ((read_le(block, 0, 2)? >> 4) as usize, 2),
+                _ => ((read_le(block, 0, 3)? >> 4) as usize, 3),
+            };
+            if size > MAX_BLOCK_SIZE {
+                return Err(error("block larger than the maximum block size"));
+            }
+            return match kind {
+                LITERALS_RAW => {
+                    let literals = block.get(header..header + size).ok_or_else(|| error("truncated literals"))?;
+                    Ok((literals.to_vec(), header + size))
+                }
+                _ => Ok((vec![read_le(block, header, 1)? as u8; size], header + 1)),
+            };
+        }
+
+        let (header, size_bits, streams) = match size_format {
+            0 => (3, 10, 1),
+            1 => (3, 10, 4),
+            2 => (4, 14, 4),
+            _ => (5, 18, 4),
+        };
+        let value = read_le(block, 0, header)?;
+        let mask = (1u64 << size_bits) - 1;
+        let regenerated = ((value >> 4) & mask) as usize;
+        if regenerated > MAX_BLOCK_SIZE {
+            return Err(error("block larger than the maximum block size"));
+        }
+        let compressed = ((value >> (4 + size_bits)) & mask) as usize;
+        let mut data = block.get(header..header + compressed).ok_or_else(|| error("truncated literals"))?;
+        if kind == LITERALS_COMPRESSED {
+            let (table, used) = HuffmanTable::read(data)?;
+            self.huffman = Some(table);
+            data = &data[used..];
+        }
+        let table = self.huffman.as_ref().ok_or_else(|| error("repeated huffman tree without a previous one"))?;
+
+        let mut literals = Vec::with_capacity(regenerated);
+        match streams {
+            1 => table.stream(data, regenerated, &mut literals)?,
+            _ => {
+                let sizes = [read_le(data, 0, 2)? as usize, read_le(data, 2, 2)? as usize, read_le(data, 4, 2)? as usize];
+                let share = regenerated.div_ceil(4);
+                let mut pos = 6;
+                for (i, size) in sizes.iter().enumerate() {
+                    let stream = data.get(pos..pos + size).ok_or_else(|| error("truncated literals stream"))?;
+                    table.stream(stream, share.min(regenerated.saturating_sub(i * share)), &mut literals)?;
+                    pos += size;
+                }
+                let last = data.get(pos..).ok_or_else(|| error("truncated literals stream"))?;
+                table.stream(last, regenerated - literals.len(), &mut literals)?;
+            }
+        }
+        Ok((literals, header + compressed))
+    }
+}
+
+/// Resolves the offset of a sequence and updates the repeated offsets
+fn resolve_offset(repeats: &mut [usize; 3], offset_value: usize, literals_len: usize) -> usize {
+    if offset_value > 3 {
+        let offset = offset_value - 3;
+        *repeats = [offset, repeats[0], repeats[1]];
+        return offset;
+    }
+    let index = offset_value - 1 + (literals_len == 0) as usize;
+    if index == 0 {
+        return repeats[0];
+    }
+    let offset = match index {
+        1 | 2 => repeats[index],
+        _ => repeats[0].saturating_sub(1),
+    };
+    if index > 1 {
+        repeats[2] = repeats[1];
+    }
+    repeats[1] = repeats[0];
+    repeats[0] = offset;
+    offset
+}
+
+/// Sets the 'table' of a sequence code from its compression 'mode' and returns the number of
+/// bytes its description used
+fn table(table: &mut Option<FseTable>, mode: u8, data: &[u8], defaults: &[i16], default_accuracy: u32, max_accuracy: u32) -> Result<usize, ZstdError> {
+    match mode {
+        MODE_PREDEFINED => {
+            *table = Some(FseTable::new(defaults, default_accuracy)?);
+            Ok(0)
+        }
+        MODE_RLE => {
+            *table = Some(FseTable::rle(*data.first().ok_or_else(|| error("missing rle symbol"))?));
+            Ok(1)
+        }
+        MODE_COMPRESSED => {
+            let (read, used) = FseTable::read(data, max_accuracy)?;
+            *table = Some(read);
+            Ok(used)
+        }
+        // Repeat the table of the previous block
+        _ => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "hello" in a raw block, with a checksum
+    const HELLO: [u8; 18] = [0x28, 0xb5, 0x2f, 0xfd, 0x24, 0x05, 0x29, 0x00, 0x00, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+        0xa3, 0x6d, 0x9f, 0x88];
+    /// A thousand zero bytes, with a checksum
+    const ZEROS: [u8; 22] = [0x28, 0xb5, 0x2f, 0xfd, 0x64, 0xe8, 0x02, 0x45, 0x00, 0x00, 0x08, 0x00, 0x01, 0x00,
+        0xe4, 0x2b, 0x20, 0x04, 0x5a, 0x07, 0x44, 0x79];
+    /// ['links'] in a compressed block of huffman coded literals and sequences, with a checksum
+    const LINKS: [u8; 70] = [0x28, 0xb5, 0x2f, 0xfd, 0x24, 0x65, 0xcd, 0x01, 0x00, 0x52, 0x46, 0x0d, 0x09, 0xa0,
+        0xed, 0xf0, 0xdb, 0x96, 0xeb, 0x87, 0xad, 0x50, 0x0d, 0x81, 0xbc, 0x39, 0xe0, 0xc5, 0xe1, 0xee, 0x0d, 0x76,
+        0x6d, 0xa8, 0x5b, 0x03, 0x5d, 0x1a, 0xe6, 0xce, 0x20, 0x57, 0x86, 0xef, 0x43, 0xdc, 0x18, 0xb2, 0x09, 0x58,
+        0x84, 0xeb, 0x81, 0xd5, 0xa0, 0x5a, 0x40, 0x25, 0x98, 0x0e, 0x48, 0x05, 0xee, 0x10, 0x01, 0x00, 0xed, 0x88,
+        0x89, 0x2c];
+
+    /// "0,1\n1,2\n" up to "19,20\n"
+    fn links() -> Vec<u8> {
+        (0..20).flat_map(|i| format!("{},{}\n", i, i + 1).into_bytes()).collect()
+    }
+
+    /// A single segment frame holding one block with the 3 byte 'header', followed by 'content'
+    fn frame_with_block(header: u32, content: &[u8]) -> Vec<u8> {
+        let mut data = ZSTD_MAGIC.to_vec();
+        data.extend_from_slice(&[0x20, 0x00]);
+        data.extend_from_slice(&header.to_le_bytes()[..3]);
+        data.extend_from_slice(content);
+        data
+    }
+
+    #[test]
+    fn frames_decode_to_their_content() {
+        assert_eq!(decompress(&HELLO).unwrap(), b"hello");
+        assert_eq!(decompress(&ZEROS).unwrap(), vec![0; 1000]);
+        assert_eq!(decompress(&LINKS).unwrap(), links());
+
+        // Frames are concatenated, skippable frames are left out
+        let mut data = LINKS.to_vec();
+        data.extend_from_slice(&[0x5a, 0x2a, 0x4d, 0x18, 0x03, 0x00, 0x00, 0x00, 1, 2, 3]);
+        data.extend_from_slice(&HELLO);
+        let mut expected = links();
+        expected.extend_from_slice(b"hello");
+        assert_eq!(decompress(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn truncated_frames_are_errors() {
+        for data in [&HELLO[..], &ZEROS[..], &LINKS[..]] {
+            for end in 1..data.len() {
+                assert!(decompress(&data[..end]).is_err(), "{:?} cut at {}", data, end);
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_frames_never_decode_to_other_content() {
+        for i in 0..LINKS.len() {
+            for flip in [0x01, 0x10, 0xff] {
+                let mut data = LINKS;
+                data[i] ^= flip;
+                if let Ok(content) = decompress(&data) {
+                    assert_eq!(content, links(), "byte {} flipped with {:#x}", i, flip);
+                }
+            }
+        }
+        assert_eq!(decompress(b"PK\x03\x04").unwrap_err().reason, "unknown frame magic number");
+    }
+
+    #[test]
+    fn huge_declared_sizes_are_refused() {
+        // The last RLE block of one byte repeated one past the largest block
+        let rle = frame_with_block(1 | (1 << 1) | ((MAX_BLOCK_SIZE as u32 + 1) << 3), &[0]);
+        assert_eq!(decompress(&rle).unwrap_err().reason, "block larger than the maximum block size");
+        // "hello" claiming a content size of 2^40 bytes
+        let mut claimed = ZSTD_MAGIC.to_vec();
+        claimed.extend_from_slice(&[0xe0, 0, 0, 0, 0, 0, 1, 0, 0]);
+        claimed.extend_from_slice(&(1u32 | (5 << 3)).to_le_bytes()[..3]);
+        claimed.extend_from_slice(b"hello");
+        assert_eq!(decompress(&claimed).unwrap_err().reason, "content size mismatch");
+        // A skippable frame longer than the file
+        let skippable = [0x50, 0x2a, 0x4d, 0x18, 0xff, 0xff, 0xff, 0xff, 0];
+        assert_eq!(decompress(&skippable).unwrap_err().reason, "truncated skippable frame");
+    }
+}